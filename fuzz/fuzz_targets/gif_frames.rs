@@ -0,0 +1,22 @@
+#![no_main]
+
+use std::fs::File;
+
+use emulsion_fuzz::{image_loader::load_gif, write_temp_file};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+	let path = write_temp_file(data, "gif");
+	let file = match File::open(&path) {
+		Ok(file) => file,
+		Err(_) => return,
+	};
+	if let Ok(frames) = load_gif(file, 0) {
+		// Decode every frame, stopping at the first error like the loader does
+		for frame in frames {
+			if frame.is_err() {
+				break;
+			}
+		}
+	}
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use emulsion_fuzz::{farbfeld, xbm, xpm};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+	let _ = gelatin::image::guess_format(data);
+	let _ = farbfeld::is_farbfeld(data);
+	let _ = xbm::is_xbm(data);
+	let _ = xpm::is_xpm(data);
+});
@@ -0,0 +1,18 @@
+#![no_main]
+
+use emulsion_fuzz::{image_loader::complex_load_image, write_temp_file};
+use libfuzzer_sys::fuzz_target;
+
+/// The format is detected from the file extension if the content doesn't identify it
+const EXTENSIONS: &[&str] =
+	&["png", "jpg", "gif", "webp", "tiff", "bmp", "ico", "xbm", "xpm", "ff"];
+
+// The first byte selects the file extension, the rest is the content of the file
+fuzz_target!(|data: &[u8]| {
+	if let Some((&selector, content)) = data.split_first() {
+		let extension = EXTENSIONS[selector as usize % EXTENSIONS.len()];
+		let path = write_temp_file(content, extension);
+		// Errors are expected, only panics are bugs
+		let _ = complex_load_image(&path, true, 0, |_frame| Ok(()));
+	}
+});
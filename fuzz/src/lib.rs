@@ -0,0 +1,55 @@
+//! Compiles the image loader of Emulsion into a library that the fuzz targets can call.
+
+use std::fs;
+use std::path::PathBuf;
+
+// The loader refers to the decoders of the other formats through `super`
+#[allow(dead_code)]
+#[path = "../../src/image_cache/archive.rs"]
+pub mod archive;
+#[allow(dead_code)]
+#[path = "../../src/image_cache/color_management.rs"]
+pub mod color_management;
+#[allow(dead_code)]
+#[path = "../../src/image_cache/dds.rs"]
+pub mod dds;
+#[allow(dead_code)]
+#[path = "../../src/image_cache/farbfeld.rs"]
+pub mod farbfeld;
+#[allow(dead_code)]
+#[path = "../../src/image_cache/icns.rs"]
+pub mod icns;
+#[allow(dead_code)]
+#[path = "../../src/image_cache/image_loader.rs"]
+pub mod image_loader;
+#[cfg(feature = "jpeg2000")]
+#[allow(dead_code)]
+#[path = "../../src/image_cache/jpeg2000.rs"]
+pub mod jpeg2000;
+#[allow(dead_code)]
+#[path = "../../src/image_cache/perceptual_hash.rs"]
+pub mod perceptual_hash;
+#[allow(dead_code)]
+#[path = "../../src/image_cache/postscript.rs"]
+pub mod postscript;
+#[allow(dead_code)]
+#[path = "../../src/image_cache/raw_thumbnail.rs"]
+pub mod raw_thumbnail;
+#[allow(dead_code)]
+#[path = "../../src/image_cache/scratch_file.rs"]
+pub mod scratch_file;
+#[allow(dead_code)]
+#[path = "../../src/image_cache/xbm.rs"]
+pub mod xbm;
+#[allow(dead_code)]
+#[path = "../../src/image_cache/xpm.rs"]
+pub mod xpm;
+
+/// Writes the data to a file in the temp folder and returns its path. The file name is the same
+/// for the whole process, because libFuzzer runs the inputs of a process one after the other.
+pub fn write_temp_file(data: &[u8], extension: &str) -> PathBuf {
+	let name = format!("emulsion-fuzz-{}.{}", std::process::id(), extension);
+	let path = std::env::temp_dir().join(name);
+	fs::write(&path, data).unwrap();
+	path
+}
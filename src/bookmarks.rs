@@ -0,0 +1,53 @@
+//! The folders that were bookmarked with `add_bookmark`. They are kept in the config file as
+//! `[[bookmarks]]` tables, so that they come along when the config file is shared.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use toml_edit::{value, ArrayOfTables, DocumentMut, Item, Table};
+
+use crate::configuration::{Bookmark, Configuration};
+
+pub struct Bookmarks {
+	config_path: PathBuf,
+	entries: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+	pub fn new(config: &Configuration, config_path: PathBuf) -> Bookmarks {
+		Bookmarks { config_path, entries: config.bookmarks.clone().unwrap_or_default() }
+	}
+
+	pub fn entries(&self) -> &[Bookmark] {
+		&self.entries
+	}
+
+	/// Adds the bookmark and writes the bookmarks into the config file. A bookmark of the same
+	/// folder is replaced.
+	pub fn add(&mut self, name: String, path: PathBuf) -> Result<(), String> {
+		self.entries.retain(|bookmark| bookmark.path != path);
+		self.entries.push(Bookmark { name, path });
+		save_bookmarks(&self.config_path, &self.entries)
+	}
+}
+
+/// Replaces the `[[bookmarks]]` tables of the config file. The rest of the file, including the
+/// comments, is left as it is.
+fn save_bookmarks(file_path: &Path, bookmarks: &[Bookmark]) -> Result<(), String> {
+	let cfg_str = fs::read_to_string(file_path).unwrap_or_default();
+	let mut doc = cfg_str.parse::<DocumentMut>().map_err(|e| format!("{}", e))?;
+	let mut tables = ArrayOfTables::new();
+	for bookmark in bookmarks {
+		let mut table = Table::new();
+		table.insert("name", value(bookmark.name.as_str()));
+		table.insert("path", value(bookmark.path.to_string_lossy().as_ref()));
+		tables.push(table);
+	}
+	doc.insert("bookmarks", Item::ArrayOfTables(tables));
+
+	if let Some(parent) = file_path.parent() {
+		fs::create_dir_all(parent).map_err(|e| format!("{}", e))?;
+	}
+	fs::write(file_path, doc.to_string())
+		.map_err(|_| format!("Could not write config to {:?}", file_path))
+}
@@ -0,0 +1,90 @@
+//! The statistics of the color channels of an image, see `channel_stats`.
+
+use gelatin::image::RgbaImage;
+use rayon::prelude::*;
+
+pub const CHANNEL_NAMES: [&str; 4] = ["R", "G", "B", "A"];
+
+/// The statistics of one channel, in the 0 to 255 range of the stored values
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ChannelStats {
+	pub min: u8,
+	pub max: u8,
+	pub mean: f64,
+	pub std_dev: f64,
+}
+
+#[derive(Copy, Clone)]
+struct Sums {
+	min: u8,
+	max: u8,
+	sum: u64,
+	sum_of_squares: u64,
+}
+
+impl Sums {
+	const EMPTY: Sums = Sums { min: u8::MAX, max: 0, sum: 0, sum_of_squares: 0 };
+
+	fn add(mut self, value: u8) -> Sums {
+		self.min = self.min.min(value);
+		self.max = self.max.max(value);
+		self.sum += value as u64;
+		self.sum_of_squares += value as u64 * value as u64;
+		self
+	}
+
+	fn merge(self, other: Sums) -> Sums {
+		Sums {
+			min: self.min.min(other.min),
+			max: self.max.max(other.max),
+			sum: self.sum + other.sum,
+			sum_of_squares: self.sum_of_squares + other.sum_of_squares,
+		}
+	}
+}
+
+/// The minimum, maximum, mean and standard deviation of the R, G, B and A channels. The rows
+/// are summed up in parallel. Returns `None` for an empty image.
+pub fn channel_stats(image: &RgbaImage) -> Option<[ChannelStats; 4]> {
+	let (width, height) = image.dimensions();
+	if width == 0 || height == 0 {
+		return None;
+	}
+	let sums = image
+		.as_raw()
+		.par_chunks(width as usize * 4)
+		.map(|row| {
+			row.chunks_exact(4).fold([Sums::EMPTY; 4], |mut sums, pixel| {
+				for (sums, value) in sums.iter_mut().zip(pixel) {
+					*sums = sums.add(*value);
+				}
+				sums
+			})
+		})
+		.reduce(|| [Sums::EMPTY; 4], |a, b| [0, 1, 2, 3].map(|i| a[i].merge(b[i])));
+	let count = width as f64 * height as f64;
+	Some(sums.map(|sums| {
+		let mean = sums.sum as f64 / count;
+		let variance = sums.sum_of_squares as f64 / count - mean * mean;
+		ChannelStats { min: sums.min, max: sums.max, mean, std_dev: variance.max(0.0).sqrt() }
+	}))
+}
+
+/// The statistics as the lines of a table with a row for every channel. The values are given
+/// both in the 0 to 1 range and in the stored 0 to 255 range.
+pub fn table_lines(stats: &[ChannelStats; 4]) -> Vec<String> {
+	let mut lines =
+		vec![format!("{:<3}{:>16}{:>16}{:>16}{:>16}", "", "Min", "Max", "Mean", "Std. dev.")];
+	for (name, stats) in CHANNEL_NAMES.iter().zip(stats) {
+		let cell = |value: f64| format!("{:.3} ({:.1})", value / 255.0, value);
+		lines.push(format!(
+			"{:<3}{:>16}{:>16}{:>16}{:>16}",
+			name,
+			cell(stats.min as f64),
+			cell(stats.max as f64),
+			cell(stats.mean),
+			cell(stats.std_dev)
+		));
+	}
+	lines
+}
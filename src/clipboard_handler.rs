@@ -4,13 +4,32 @@ use std::sync::{
 	Arc, Condvar, Mutex,
 };
 
-use gelatin::image::imageops::{
-	flip_horizontal_in_place, flip_vertical_in_place, rotate180_in_place, rotate270, rotate90,
-};
+use gelatin::image::RgbaImage;
 
-use crate::image_cache::image_loader::{
-	complex_load_image, ImageLoaderError, LoadResult, Orientation,
-};
+use crate::image_cache::image_loader::{complex_load_image, ImageLoaderError, LoadResult};
+
+/// Reads the image that's currently on the clipboard
+///
+/// The image formats are negotiated by arboard. On X11 and Wayland it only exchanges `image/png`,
+/// both when copying and when pasting, so images offered exclusively as `image/bmp` or
+/// `image/jpeg` by other applications can't be pasted.
+pub fn get_clipboard_image() -> Result<RgbaImage, String> {
+	let mut clipboard = arboard::Clipboard::new()
+		.map_err(|e| format!("The clipboard could not be created, error was: {}", e))?;
+	let image = clipboard
+		.get_image()
+		.map_err(|e| format!("Could not get an image from the clipboard, error was: {}", e))?;
+	RgbaImage::from_raw(image.width as u32, image.height as u32, image.bytes.into_owned())
+		.ok_or_else(|| "The clipboard image has an invalid size".to_string())
+}
+
+pub fn set_clipboard_text(text: &str) -> Result<(), String> {
+	let mut clipboard = arboard::Clipboard::new()
+		.map_err(|e| format!("The clipboard could not be created, error was: {}", e))?;
+	clipboard
+		.set_text(text)
+		.map_err(|e| format!("Could not set the clipboard text, error was: {}", e))
+}
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 enum ClipboardState {
@@ -109,36 +128,7 @@ impl ClipboardHandler {
 			let result = complex_load_image(&request_path, false, 0, |frame| {
 				if let LoadResult::Frame { mut image, orientation, .. } = frame {
 					if let Ok(clipboard) = &mut clipboard {
-						// Note: the imageops functions use clockwise rotation whereas the
-						// `Orientation` type describes counter-clockwise rotation.
-						image = match orientation {
-							Orientation::Deg0 => image,
-							Orientation::Deg0HorFlip => {
-								flip_horizontal_in_place(&mut image);
-								image
-							}
-							Orientation::Deg90 => rotate270(&image),
-							Orientation::Deg90VerFlip => {
-								let mut result = rotate270(&image);
-								flip_vertical_in_place(&mut result);
-								result
-							}
-							Orientation::Deg180 => {
-								rotate180_in_place(&mut image);
-								image
-							}
-							Orientation::Deg180HorFlip => {
-								// This is identical to just a vertical flip with no rotation.
-								flip_vertical_in_place(&mut image);
-								image
-							}
-							Orientation::Deg270 => rotate90(&image),
-							Orientation::Deg270VerFlip => {
-								let mut result = rotate90(&image);
-								flip_vertical_in_place(&mut result);
-								result
-							}
-						};
+						image = orientation.apply(image);
 						let (w, h) = image.dimensions();
 						let cb_image = arboard::ImageData {
 							width: w as usize,
@@ -1,10 +1,60 @@
+use crate::configuration::{parse_color, FitMode, SortOrder};
+use crate::headless::PipeFormat;
 use crate::Version;
-use clap::{parser::ValueSource, value_parser, Arg, Command};
+use clap::{
+	builder::{PossibleValuesParser, TypedValueParser},
+	parser::ValueSource,
+	value_parser, Arg, Command,
+};
+use log::LevelFilter;
 use std::path::Path;
 
+#[derive(Clone)]
 pub struct Args {
 	pub file_path: Option<String>,
 	pub displayed_folders: Option<u32>,
+	pub no_fullscreen: bool,
+	pub presentation: bool,
+	pub no_zoom_memory: bool,
+	pub no_transitions: bool,
+	pub quiet: bool,
+	pub verbose: bool,
+	pub log_file: Option<String>,
+	pub log_level: Option<LevelFilter>,
+	pub preload_all: bool,
+	pub filter_stars: Option<u8>,
+	pub filter_tag: Option<String>,
+	pub watch_dir: Option<String>,
+	/// The M3U file whose images are browsed, see `--playlist`
+	pub playlist: Option<String>,
+	/// The JSON file of `save_session` whose images and view are restored, see `--session`
+	pub session: Option<String>,
+	pub remote_port: Option<u16>,
+	pub fps: Option<u32>,
+	pub slideshow_interval_ms: Option<f32>,
+	pub background_color: Option<[u8; 3]>,
+	pub fit: Option<FitMode>,
+	pub aspect_ratio: Option<(u32, u32)>,
+	pub sort: Option<SortOrder>,
+	pub seed: Option<u64>,
+	pub max_image_size: Option<u32>,
+	pub icc_convert_to_display: bool,
+	pub color_profile: Option<String>,
+	pub zoom: Option<u32>,
+	pub pan_x: Option<f32>,
+	pub pan_y: Option<f32>,
+	pub export_frame: Option<u32>,
+	pub find_duplicates: bool,
+	/// The folder to load images from and the number of loads, see `--stress-test`
+	pub stress_test: Option<(String, u32)>,
+	pub dump_exif: bool,
+	pub exif_with_stats: bool,
+	pub convert: bool,
+	pub auto_orient: bool,
+	pub pipe_out: bool,
+	pub pipe_format: PipeFormat,
+	pub output_path: Option<String>,
+	pub xdg_open: bool,
 }
 
 /// Parses the command-line arguments and returns the file path
@@ -45,7 +95,402 @@ pub fn parse_args(config_path: &Path, cache_path: &Path) -> Args {
 				.num_args(0)
 				.conflicts_with("FOLDER_COUNT"),
 		)
+		.arg(
+			Arg::new("no-fullscreen")
+				.long("no-fullscreen")
+				.help("Start in windowed mode, even if the previous session ended in fullscreen")
+				.num_args(0),
+		)
+		.arg(
+			Arg::new("presentation")
+				.long("presentation")
+				.help(
+					"Start in presentation mode, which shows only the image on a black background",
+				)
+				.num_args(0),
+		)
+		.arg(
+			Arg::new("no-zoom-memory")
+				.long("no-zoom-memory")
+				.help("Don't restore the zoom and pan of an image when returning to it")
+				.num_args(0),
+		)
+		.arg(
+			Arg::new("no-transitions")
+				.long("no-transitions")
+				.help("Switch between images instantly instead of crossfading")
+				.num_args(0),
+		)
+		.arg(
+			Arg::new("quiet")
+				.long("quiet")
+				.help("Don't print errors of the image loader")
+				.num_args(0)
+				.conflicts_with("verbose"),
+		)
+		.arg(
+			Arg::new("verbose")
+				.long("verbose")
+				.help("Print every image that is loaded along with its format and frame count")
+				.num_args(0),
+		)
+		.arg(
+			Arg::new("LOG_FILE")
+				.long("log-file")
+				.help(
+					"Write the errors and the log to this file instead of the standard error \
+					output. It's renamed to <LOG_FILE>.1 when it exceeds 10 MB.",
+				)
+				.num_args(1),
+		)
+		.arg(
+			Arg::new("LOG_LEVEL")
+				.long("log-level")
+				.help(
+					"The least severe log records to write. `debug` also prints every loaded image",
+				)
+				.num_args(1)
+				.value_parser(
+					PossibleValuesParser::new(["debug", "info", "warn", "error"])
+						.map(|s| s.parse::<LevelFilter>().unwrap()),
+				),
+		)
+		.arg(
+			Arg::new("preload-all")
+				.long("preload-all")
+				.help(
+					"Load every image of the folder into memory at startup, so that navigation \
+					is instant. Images larger than --max-image-size are skipped",
+				)
+				.num_args(0),
+		)
+		.arg(
+			Arg::new("STARS")
+				.long("filter-stars")
+				.help(
+					"Only show the images that are rated with at least this many stars in their \
+					XMP sidecar",
+				)
+				.num_args(1)
+				.value_parser(value_parser!(u8).range(1..=9)),
+		)
+		.arg(
+			Arg::new("TAG")
+				.long("filter-tag")
+				.help("Only show the images that are tagged with this tag")
+				.num_args(1),
+		)
+		.arg(
+			Arg::new("WATCH_DIR")
+				.long("watch-dir")
+				.help(
+					"Watch the folder and show every new image that appears in it, for example \
+					from a tethered camera",
+				)
+				.num_args(1),
+		)
+		.arg(
+			Arg::new("PLAYLIST")
+				.long("playlist")
+				.help(
+					"Browse the images listed in this M3U file, one path per line, instead of a \
+					folder. Relative paths are relative to the folder of the file",
+				)
+				.num_args(1)
+				.conflicts_with("PATH"),
+		)
+		.arg(
+			Arg::new("SESSION")
+				.long("session")
+				.help(
+					"Browse the images of this session file, which is saved with `save_session`, \
+					starting at the image and with the zoom and pan of the session",
+				)
+				.num_args(1)
+				.conflicts_with_all(["PATH", "PLAYLIST"]),
+		)
+		.arg(
+			Arg::new("REMOTE_PORT")
+				.long("remote-port")
+				.help(
+					"Accept JSON commands like {\"action\": \"next\"} from WebSocket \
+					connections to this port on localhost",
+				)
+				.num_args(1)
+				.value_parser(value_parser!(u16)),
+		)
+		.arg(
+			Arg::new("FPS")
+				.long("fps")
+				.help("Redraw the window at most this many times per second. 0 means no limit")
+				.num_args(1)
+				.value_parser(value_parser!(u32)),
+		)
+		.arg(
+			Arg::new("SLIDESHOW_INTERVAL")
+				.long("slideshow-interval")
+				.help(
+					"The number of milliseconds each image is shown for during a presentation. \
+					Fractions are allowed, intervals below 100 are raised to 100",
+				)
+				.num_args(1)
+				.value_parser(value_parser!(f32)),
+		)
+		.arg(
+			Arg::new("COLOR")
+				.long("background-color")
+				.help(
+					"The color behind the image, written as #rrggbb, rrggbb or rgb(r, g, b). \
+					Overrides the color of the theme",
+				)
+				.num_args(1)
+				.value_parser(parse_color),
+		)
+		.arg(
+			Arg::new("FIT")
+				.long("fit")
+				.help("How to scale the image when it's first displayed")
+				.num_args(1)
+				.value_parser(
+					PossibleValuesParser::new(["window", "width", "height", "1:1", "none"])
+						.map(|s| s.parse::<FitMode>().unwrap()),
+				),
+		)
+		.arg(
+			Arg::new("ASPECT_RATIO")
+				.long("aspect-ratio")
+				.value_name("W:H")
+				.help(
+					"Fit the image into the largest frame of this aspect ratio, like 16:9, and \
+					cover the rest of the window with black bars",
+				)
+				.num_args(1)
+				.value_parser(parse_aspect_ratio),
+		)
+		.arg(
+			Arg::new("SORT")
+				.long("sort")
+				.help("The order of the images in the folder. The default is `name`")
+				.num_args(1)
+				.value_parser(
+					PossibleValuesParser::new(["name", "date", "size", "random"])
+						.map(|s| s.parse::<SortOrder>().unwrap()),
+				),
+		)
+		.arg(
+			Arg::new("random")
+				.long("random")
+				.help(
+					"Show the images of the folder in a shuffled order, starting with a random \
+					one. Every image is shown once before the order repeats. Same as \
+					`--sort random`",
+				)
+				.num_args(0)
+				.conflicts_with("SORT"),
+		)
+		.arg(
+			Arg::new("SEED")
+				.long("seed")
+				.help(
+					"The seed of the shuffled order of `--random` or `--sort random`, for a \
+					reproducible order",
+				)
+				.num_args(1)
+				.value_parser(value_parser!(u64)),
+		)
+		.arg(
+			Arg::new("PERCENT")
+				.long("zoom")
+				.help("Display the image at this zoom level in percent (from 1 to 3200)")
+				.num_args(1)
+				.value_parser(value_parser!(u32).range(1..=3200))
+				.conflicts_with("FIT"),
+		)
+		.arg(
+			Arg::new("PAN_X")
+				.long("pan-x")
+				.help("The horizontal image coordinate to display at the center of the window")
+				.num_args(1)
+				.allow_negative_numbers(true)
+				.value_parser(value_parser!(f32))
+				.requires("PERCENT"),
+		)
+		.arg(
+			Arg::new("PAN_Y")
+				.long("pan-y")
+				.help("The vertical image coordinate to display at the center of the window")
+				.num_args(1)
+				.allow_negative_numbers(true)
+				.value_parser(value_parser!(f32))
+				.requires("PERCENT"),
+		)
+		.arg(
+			Arg::new("MEGAPIXELS")
+				.long("max-image-size")
+				.help("Refuse to load images that have more megapixels than this")
+				.num_args(1)
+				.value_parser(value_parser!(u32)),
+		)
+		.arg(
+			Arg::new("icc-convert-to-display")
+				.long("icc-convert-to-display")
+				.help(
+					"Convert the colors of the images from their embedded ICC profile (or sRGB) \
+					to the profile of the display",
+				)
+				.num_args(0),
+		)
+		.arg(
+			Arg::new("COLOR_PROFILE")
+				.long("color-profile")
+				.help(
+					"The ICC profile of the display for --icc-convert-to-display, instead of the \
+					one the system tells",
+				)
+				.num_args(1)
+				.requires("icc-convert-to-display"),
+		)
+		.arg(
+			Arg::new("EXPORT_FRAME")
+				.long("export-frame")
+				.help(
+					"Save the frame with the given index (starting from 0) of the animation at \
+					PATH to OUTPUT, then exit without opening a window",
+				)
+				.num_args(1)
+				.value_parser(value_parser!(u32))
+				.requires("PATH"),
+		)
+		.arg(
+			Arg::new("find-duplicates")
+				.long("find-duplicates")
+				.help(
+					"List the pairs of images in the folder at PATH that look nearly the same, \
+					closest first, then exit without opening a window",
+				)
+				.num_args(0)
+				.requires("PATH")
+				.conflicts_with("EXPORT_FRAME"),
+		)
+		.arg(
+			Arg::new("dump-exif")
+				.long("dump-exif")
+				.help(
+					"Write the EXIF metadata of the image at PATH as JSON to the output file (or \
+					to the standard output), then exit without opening a window",
+				)
+				.num_args(0)
+				.requires("PATH")
+				.conflicts_with_all(["EXPORT_FRAME", "find-duplicates"]),
+		)
+		.arg(
+			Arg::new("OUTPUT_FORMAT")
+				.long("output-format")
+				.help(
+					"The format of --dump-exif. `json` nests the EXIF data under `exif` and adds \
+					the size and mean luminance of the image under `stats`",
+				)
+				.num_args(1)
+				.value_parser(PossibleValuesParser::new(["json"]))
+				.requires("dump-exif"),
+		)
+		.arg(
+			Arg::new("convert")
+				.long("convert")
+				.help(
+					"Convert the image at PATH to the format given by the file extension of \
+					OUTPUT, then exit without opening a window",
+				)
+				.num_args(0)
+				.requires("PATH")
+				.conflicts_with_all(["EXPORT_FRAME", "dump-exif", "find-duplicates"]),
+		)
+		.arg(
+			Arg::new("pipe-out")
+				.long("pipe-out")
+				.help(
+					"Decode the image at PATH and write its pixels to the standard output in the \
+					format of --pipe-format, then exit without opening a window",
+				)
+				.num_args(0)
+				.requires("PATH")
+				.conflicts_with_all(["EXPORT_FRAME", "dump-exif", "find-duplicates", "convert"]),
+		)
+		.arg(
+			Arg::new("PIPE_FORMAT")
+				.long("pipe-format")
+				.help(
+					"The format of --pipe-out. `raw` writes the width and the height as ASCII \
+					lines followed by the RGBA bytes, `ppm` and `pgm` write binary PPM (RGB) and \
+					PGM (grayscale) images. The default is `raw`",
+				)
+				.num_args(1)
+				.value_parser(
+					PossibleValuesParser::new(["raw", "ppm", "pgm"])
+						.map(|s| s.parse::<PipeFormat>().unwrap()),
+				)
+				.requires("pipe-out"),
+		)
+		.arg(
+			Arg::new("STRESS_TEST")
+				.long("stress-test")
+				.help(
+					"Load N randomly chosen images from the folder DIR, report how the memory \
+					usage changed, then exit without opening a window. Use --seed for a \
+					reproducible order",
+				)
+				.num_args(2)
+				.value_names(["DIR", "N"])
+				.conflicts_with_all([
+					"EXPORT_FRAME",
+					"dump-exif",
+					"find-duplicates",
+					"convert",
+					"pipe-out",
+				]),
+		)
+		.arg(
+			Arg::new("auto-orient")
+				.long("auto-orient")
+				.help("Rotate the converted image according to its EXIF orientation")
+				.num_args(0)
+				.requires("convert"),
+		)
+		.arg(
+			Arg::new("OUTPUT_FILE")
+				.long("output")
+				.short('o')
+				.help("The file path to write to (same as OUTPUT)")
+				.num_args(1)
+				.conflicts_with("OUTPUT"),
+		)
+		.arg(
+			Arg::new("xdg-open")
+				.long("xdg-open")
+				.help(
+					"Make emulsion the default application of all the supported image types \
+					with xdg-mime, then exit without opening a window",
+				)
+				.num_args(0)
+				.conflicts_with_all([
+					"PATH",
+					"EXPORT_FRAME",
+					"dump-exif",
+					"find-duplicates",
+					"convert",
+					"pipe-out",
+					"STRESS_TEST",
+				]),
+		)
 		.arg(Arg::new("PATH").help("The file path of the image").index(1))
+		.arg(
+			Arg::new("OUTPUT")
+				.help(
+					"The file path to write to (see --export-frame, --find-duplicates, \
+					--dump-exif and --convert)",
+				)
+				.index(2),
+		)
 		.get_matches();
 
 	let file_path = matches.get_one::<String>("PATH").cloned();
@@ -54,10 +499,130 @@ pub fn parse_args(config_path: &Path, cache_path: &Path) -> Args {
 	let displayed_folders = if is_absolute {
 		// Subtract one because we later want to add one to this value, and we don't want
 		// an overflow
-		Some(std::u32::MAX - 1)
+		Some(u32::MAX - 1)
 	} else {
 		matches.get_one::<u32>("FOLDER_COUNT").copied()
 	};
 
-	Args { file_path, displayed_folders }
+	let no_fullscreen = matches.value_source("no-fullscreen") == Some(ValueSource::CommandLine);
+
+	let presentation = matches.value_source("presentation") == Some(ValueSource::CommandLine);
+
+	let no_zoom_memory = matches.value_source("no-zoom-memory") == Some(ValueSource::CommandLine);
+
+	let no_transitions = matches.value_source("no-transitions") == Some(ValueSource::CommandLine);
+
+	let quiet = matches.value_source("quiet") == Some(ValueSource::CommandLine);
+	let verbose = matches.value_source("verbose") == Some(ValueSource::CommandLine);
+	let log_file = matches.get_one::<String>("LOG_FILE").cloned();
+	let log_level = matches.get_one::<LevelFilter>("LOG_LEVEL").copied();
+
+	let preload_all = matches.value_source("preload-all") == Some(ValueSource::CommandLine);
+	let filter_stars = matches.get_one::<u8>("STARS").copied();
+	let filter_tag = matches.get_one::<String>("TAG").cloned();
+	let watch_dir = matches.get_one::<String>("WATCH_DIR").cloned();
+	let playlist = matches.get_one::<String>("PLAYLIST").cloned();
+	let session = matches.get_one::<String>("SESSION").cloned();
+	let remote_port = matches.get_one::<u16>("REMOTE_PORT").copied();
+	let fps = matches.get_one::<u32>("FPS").copied();
+	let slideshow_interval_ms = matches.get_one::<f32>("SLIDESHOW_INTERVAL").copied();
+	let background_color = matches.get_one::<[u8; 3]>("COLOR").copied();
+
+	let fit = matches.get_one::<FitMode>("FIT").copied();
+	let aspect_ratio = matches.get_one::<(u32, u32)>("ASPECT_RATIO").copied();
+	let sort = if matches.value_source("random") == Some(ValueSource::CommandLine) {
+		Some(SortOrder::Random)
+	} else {
+		matches.get_one::<SortOrder>("SORT").copied()
+	};
+	let seed = matches.get_one::<u64>("SEED").copied();
+
+	let max_image_size = matches.get_one::<u32>("MEGAPIXELS").copied();
+	let icc_convert_to_display =
+		matches.value_source("icc-convert-to-display") == Some(ValueSource::CommandLine);
+	let color_profile = matches.get_one::<String>("COLOR_PROFILE").cloned();
+
+	let zoom = matches.get_one::<u32>("PERCENT").copied();
+	let pan_x = matches.get_one::<f32>("PAN_X").copied();
+	let pan_y = matches.get_one::<f32>("PAN_Y").copied();
+
+	let export_frame = matches.get_one::<u32>("EXPORT_FRAME").copied();
+	let find_duplicates = matches.value_source("find-duplicates") == Some(ValueSource::CommandLine);
+	let stress_test = matches.get_many::<String>("STRESS_TEST").map(|mut values| {
+		let folder = values.next().cloned().unwrap_or_default();
+		let count = values.next().map(|count| count.parse::<u32>());
+		match count {
+			Some(Ok(count)) if count > 0 => (folder, count),
+			_ => clap::Error::raw(
+				clap::error::ErrorKind::InvalidValue,
+				"The number of loads of --stress-test must be a positive integer\n",
+			)
+			.exit(),
+		}
+	});
+	let dump_exif = matches.value_source("dump-exif") == Some(ValueSource::CommandLine);
+	let exif_with_stats = matches.get_one::<String>("OUTPUT_FORMAT").is_some();
+	let convert = matches.value_source("convert") == Some(ValueSource::CommandLine);
+	let auto_orient = matches.value_source("auto-orient") == Some(ValueSource::CommandLine);
+	let pipe_out = matches.value_source("pipe-out") == Some(ValueSource::CommandLine);
+	let pipe_format = matches.get_one::<PipeFormat>("PIPE_FORMAT").copied().unwrap_or_default();
+	let output_path = matches
+		.get_one::<String>("OUTPUT_FILE")
+		.or_else(|| matches.get_one::<String>("OUTPUT"))
+		.cloned();
+	let xdg_open = matches.value_source("xdg-open") == Some(ValueSource::CommandLine);
+
+	Args {
+		file_path,
+		displayed_folders,
+		no_fullscreen,
+		presentation,
+		no_zoom_memory,
+		no_transitions,
+		quiet,
+		verbose,
+		log_file,
+		log_level,
+		preload_all,
+		filter_stars,
+		filter_tag,
+		watch_dir,
+		playlist,
+		session,
+		remote_port,
+		fps,
+		slideshow_interval_ms,
+		background_color,
+		fit,
+		aspect_ratio,
+		sort,
+		seed,
+		max_image_size,
+		icc_convert_to_display,
+		color_profile,
+		zoom,
+		pan_x,
+		pan_y,
+		export_frame,
+		find_duplicates,
+		stress_test,
+		dump_exif,
+		exif_with_stats,
+		convert,
+		auto_orient,
+		pipe_out,
+		pipe_format,
+		output_path,
+		xdg_open,
+	}
+}
+
+/// Parses an aspect ratio written as `W:H`, where both are positive integers
+fn parse_aspect_ratio(ratio: &str) -> Result<(u32, u32), String> {
+	let invalid = || format!("Invalid aspect ratio {:?}, expected W:H like 16:9", ratio);
+	let (w, h) = ratio.split_once(':').ok_or_else(invalid)?;
+	match (w.trim().parse::<u32>(), h.trim().parse::<u32>()) {
+		(Ok(w), Ok(h)) if w > 0 && h > 0 => Ok((w, h)),
+		_ => Err(invalid()),
+	}
 }
@@ -0,0 +1,90 @@
+//! Finds the name of the color space of an image from its embedded ICC profile or its EXIF
+//! data. Only the headers are read, no colors are converted.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use gelatin::image::{ImageDecoder, ImageReader};
+
+/// The name used for the images that don't say which color space they are in
+pub const ASSUMED_SRGB: &str = "sRGB (assumed)";
+
+/// The size of the ICC profile header, which the tag table follows
+const ICC_HEADER_LEN: usize = 128;
+const ICC_TAG_ENTRY_LEN: usize = 12;
+/// The EXIF `ColorSpace` value of sRGB
+const EXIF_SRGB: u16 = 1;
+
+/// The description of the embedded ICC profile, or the color space given in the EXIF data,
+/// or `ASSUMED_SRGB`
+pub fn color_profile_name(path: &Path) -> String {
+	read_icc_profile(path)
+		.and_then(|profile| icc_description(&profile))
+		.or_else(|| exif_color_space(path))
+		.unwrap_or_else(|| ASSUMED_SRGB.to_owned())
+}
+
+fn read_icc_profile(path: &Path) -> Option<Vec<u8>> {
+	let reader = ImageReader::open(path).ok()?.with_guessed_format().ok()?;
+	reader.into_decoder().ok()?.icc_profile().ok()?
+}
+
+/// Reads the `desc` tag of an ICC profile. Version 2 profiles store it as ASCII text and
+/// version 4 profiles as UTF-16 in one or more languages, of which the first is used.
+pub fn icc_description(profile: &[u8]) -> Option<String> {
+	let tag_count = read_u32(profile, ICC_HEADER_LEN)? as usize;
+	let tag = (0..tag_count).find_map(|i| {
+		let entry = ICC_HEADER_LEN + 4 + i * ICC_TAG_ENTRY_LEN;
+		if profile.get(entry..entry + 4)? != b"desc" {
+			return None;
+		}
+		let offset = read_u32(profile, entry + 4)? as usize;
+		let size = read_u32(profile, entry + 8)? as usize;
+		profile.get(offset..offset.checked_add(size)?)
+	})?;
+	let description = match tag.get(0..4)? {
+		b"desc" => {
+			let len = read_u32(tag, 8)? as usize;
+			let ascii = tag.get(12..12usize.checked_add(len)?)?;
+			String::from_utf8_lossy(ascii).into_owned()
+		}
+		b"mluc" => {
+			// The first record after the record count and the record size
+			let len = read_u32(tag, 20)? as usize;
+			let offset = read_u32(tag, 24)? as usize;
+			let utf16: Vec<u16> = tag
+				.get(offset..offset.checked_add(len)?)?
+				.chunks_exact(2)
+				.map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+				.collect();
+			String::from_utf16_lossy(&utf16)
+		}
+		_ => return None,
+	};
+	let description = description.trim_end_matches('\0').trim();
+	(!description.is_empty()).then(|| description.to_owned())
+}
+
+/// The EXIF `ColorSpace` is either sRGB or "uncalibrated". The interoperability index tells
+/// Adobe RGB apart from the other uncalibrated ones.
+fn exif_color_space(path: &Path) -> Option<String> {
+	let file = File::open(path).ok()?;
+	let exif = exif::Reader::new().read_from_container(&mut BufReader::new(file)).ok()?;
+	let color_space = exif.get_field(exif::Tag::ColorSpace, exif::In::PRIMARY)?;
+	if color_space.value.get_uint(0) == Some(EXIF_SRGB as u32) {
+		return Some("sRGB".to_owned());
+	}
+	let interop = exif.get_field(exif::Tag::InteroperabilityIndex, exif::In::PRIMARY)?;
+	match &interop.value {
+		exif::Value::Ascii(values) if values.first().is_some_and(|v| v == b"R03") => {
+			Some("Adobe RGB (1998)".to_owned())
+		}
+		_ => None,
+	}
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+	let bytes = bytes.get(at..at.checked_add(4)?)?;
+	Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
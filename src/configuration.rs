@@ -1,8 +1,8 @@
 //! There are two files that store properties for Emulsion, the *cache* and the *config*.
 //!
-//! The most important distinction between these is that Emulsion never writes to the *config*
-//! but it does write to the *cache* to save portions of the state of the program (e.g. window size
-//! and position).
+//! The most important distinction between these is that Emulsion only writes to the *config* when
+//! the user changes a setting in the settings panel, but it does write to the *cache* to save
+//! portions of the state of the program (e.g. window size and position).
 //!
 //! Furthermore it's generally true that the user will only edit the *config* to specify their
 //! preferences.
@@ -10,7 +10,8 @@
 use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
@@ -30,6 +31,36 @@ impl Theme {
 	}
 }
 
+/// Parses a color written as "#rrggbb"
+pub fn parse_hex_color(color: &str) -> Result<[u8; 3], String> {
+	let hex = color.trim().trim_start_matches('#');
+	let invalid = || format!("Invalid color {:?}, expected the format #rrggbb", color);
+	if hex.len() != 6 || !hex.is_ascii() {
+		return Err(invalid());
+	}
+	let mut rgb = [0; 3];
+	for (i, channel) in rgb.iter_mut().enumerate() {
+		*channel = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+	}
+	Ok(rgb)
+}
+
+/// Parses a color written as "#rrggbb", "rrggbb" or "rgb(r, g, b)"
+pub fn parse_color(color: &str) -> Result<[u8; 3], String> {
+	let invalid = || format!("Invalid color {:?}, expected #rrggbb or rgb(r, g, b)", color);
+	let trimmed = color.trim();
+	let rgb_args = trimmed.strip_prefix("rgb(").and_then(|rest| rest.strip_suffix(')'));
+	let args = match rgb_args {
+		Some(args) => args,
+		None => return parse_hex_color(trimmed).map_err(|_| invalid()),
+	};
+	let channels: Vec<_> = args.split(',').map(|c| c.trim().parse::<u8>()).collect();
+	match channels.as_slice() {
+		[Ok(r), Ok(g), Ok(b)] => Ok([*r, *g, *b]),
+		_ => Err(invalid()),
+	}
+}
+
 #[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Antialias {
@@ -39,15 +70,150 @@ pub enum Antialias {
 	Never,
 }
 
+/// How the image is scaled when it's first displayed
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FitMode {
+	/// Scale the image so that it fills the window while staying entirely visible
+	Window,
+	/// Scale the image so that its width equals the width of the window
+	Width,
+	/// Scale the image so that its height equals the height of the window
+	Height,
+	/// Display each image pixel as one display pixel
+	#[serde(rename = "1:1")]
+	Original,
+	/// Use the scaling from the previous session
+	None,
+}
+impl FitMode {
+	/// The name of the mode as written in the config file
+	pub fn as_str(self) -> &'static str {
+		match self {
+			FitMode::Window => "window",
+			FitMode::Width => "width",
+			FitMode::Height => "height",
+			FitMode::Original => "1:1",
+			FitMode::None => "none",
+		}
+	}
+}
+impl FromStr for FitMode {
+	type Err = String;
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"window" => Ok(FitMode::Window),
+			"width" => Ok(FitMode::Width),
+			"height" => Ok(FitMode::Height),
+			"1:1" => Ok(FitMode::Original),
+			"none" => Ok(FitMode::None),
+			other => Err(format!("Unknown fit mode {:?}", other)),
+		}
+	}
+}
+
+/// The order in which the images of a folder are navigated
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+	/// Alphabetical order of the file names, with the numbers ordered by their value
+	#[default]
+	Name,
+	/// The most recently modified image first
+	Date,
+	/// The smallest file first
+	Size,
+	/// A shuffled order that only depends on the seed
+	Random,
+}
+impl FromStr for SortOrder {
+	type Err = String;
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"name" => Ok(SortOrder::Name),
+			"date" => Ok(SortOrder::Date),
+			"size" => Ok(SortOrder::Size),
+			"random" => Ok(SortOrder::Random),
+			other => Err(format!("Unknown sort order {:?}", other)),
+		}
+	}
+}
+
+/// How the images are resampled when they are shown smaller than their size
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScaleQuality {
+	/// Linear filtering of the mipmaps on the GPU
+	#[default]
+	Linear,
+	/// A Lanczos filter on the CPU for images that fit into the window at less than half of
+	/// their size. Sharper, but it takes longer to show large images.
+	Lanczos3,
+}
+
+/// How the texels are interpolated when the image is shown larger than its size
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InterpolationMode {
+	/// The color of the closest texel, which keeps the edges of the pixels sharp
+	Nearest = 0,
+	#[default]
+	Bilinear = 1,
+	/// Catmull-Rom interpolation of the 4×4 closest texels. Sharper than bilinear, but it
+	/// takes four times as many texture reads.
+	Bicubic = 2,
+}
+
 #[derive(Debug, Default, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub struct CacheImageSection {
 	pub fit_stretches: bool,
 	pub antialiasing: Antialias,
+	/// The command that was run last with `run_command`
+	pub last_command: Option<String>,
 }
 
-#[derive(Debug, Default, Eq, PartialEq, Clone, Deserialize)]
+#[derive(Debug, Default, PartialEq, Clone, Deserialize)]
 pub struct ConfigImageSection {
 	pub antialiasing: Option<String>,
+	/// Restore the zoom and pan of recently viewed images when returning to them
+	pub remember_zoom: Option<bool>,
+	/// Blend the edges of the image into the background when they don't fall on whole pixels
+	pub smooth_edges: Option<bool>,
+	pub fit: Option<FitMode>,
+	/// Images with more megapixels than this are not loaded
+	pub max_image_size: Option<u32>,
+	/// The number of threads that decode images
+	pub thread_count: Option<u32>,
+	/// The amount of memory used for caching decoded images, in megabytes
+	pub cache_size_mb: Option<u32>,
+	/// The number of images after and before the current one that are loaded in the
+	/// background, written as `[ahead, behind]`
+	pub prefetch_window: Option<(usize, usize)>,
+	/// Images that are wider or taller than this many pixels are downscaled before they are
+	/// uploaded to the GPU. The limit of the GPU applies either way.
+	pub max_texture_size: Option<u32>,
+	/// The duration of the crossfade when switching to another image. Zero disables it.
+	pub transition_ms: Option<u32>,
+	/// The order of the images within their folder
+	pub sort: Option<SortOrder>,
+	pub scale_quality: Option<ScaleQuality>,
+	/// How the image is interpolated when it's zoomed in and antialiased
+	pub interpolation_mode: Option<InterpolationMode>,
+	/// The zoom above which the automatic antialiasing shows images that are narrower than
+	/// 256 pixels with the nearest interpolation. 2 means 200%.
+	pub pixel_art_zoom: Option<f32>,
+	/// The exponent applied to the displayed colors. 1 leaves them unchanged, values above 1
+	/// darken the midtones and values below 1 brighten them.
+	pub gamma_correction: Option<f32>,
+	/// Show the images that appear in the watched folder (see `--watch-dir`) as soon as they
+	/// are found, if they were modified after the shown one
+	pub auto_advance_on_new_load: Option<bool>,
+	/// Store the SHA-256 hash of a file the first time it's shown by `show_integrity`, so that
+	/// the later hashes of the file are compared with it
+	pub store_file_hashes: Option<bool>,
+	/// The frame rate of the numbered images of a sequence, for the timecode of
+	/// `toggle_timecode`
+	pub sequence_fps: Option<f32>,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
@@ -58,15 +224,28 @@ pub struct CacheWindowSection {
 	pub win_x: i32,
 	pub win_y: i32,
 	pub maximized: bool,
+	/// Whether the window was in fullscreen mode the last time it was toggled
+	#[serde(default)]
+	pub fullscreen: bool,
 }
 impl Default for CacheWindowSection {
 	fn default() -> Self {
-		Self { dark: false, win_w: 580, win_h: 558, win_x: 64, win_y: 64, maximized: false }
+		Self {
+			dark: false,
+			win_w: 580,
+			win_h: 558,
+			win_x: 64,
+			win_y: 64,
+			maximized: false,
+			fullscreen: false,
+		}
 	}
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Default, Clone, Deserialize)]
 pub struct ConfigWindowSection {
+	/// Overrides the background color of the theme. For example "#1a1a2e"
+	pub background_color: Option<String>,
 	pub start_fullscreen: Option<bool>,
 	pub start_maximized: Option<bool>,
 	pub show_bottom_bar: Option<bool>,
@@ -76,6 +255,155 @@ pub struct ConfigWindowSection {
 	pub win_h: Option<u32>,
 	pub win_x: Option<i32>,
 	pub win_y: Option<i32>,
+	/// The maximal number of redraws per second. Zero means that there's no limit
+	pub fps: Option<u32>,
+}
+
+#[derive(Debug, Default, Eq, PartialEq, Clone, Deserialize)]
+pub struct ConfigFileOperationsSection {
+	/// The number of file operations (like deleting an image) that can be undone
+	pub undo_steps: Option<usize>,
+	/// The command that `open_terminal` runs with the shell. `${folder}` is replaced by the
+	/// folder of the shown image.
+	pub terminal_command: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Deserialize)]
+pub struct ConfigSlideshowSection {
+	/// The number of seconds each image is shown for during a presentation
+	pub interval_secs: Option<f32>,
+	/// Slowly zoom into and pan across the images during a presentation
+	pub ken_burns: Option<bool>,
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Deserialize)]
+pub struct ConfigSearchSection {
+	/// How many levels of subfolders the file name search (`search_files`) looks into
+	pub max_depth: Option<usize>,
+}
+
+/// The lines that are drawn over the image when the grid is shown, see `grid_settings`. The
+/// default is a white 3×3 grid for the rule of thirds.
+#[derive(Debug, Default, PartialEq, Clone, Deserialize)]
+pub struct ConfigGridSection {
+	pub visible: Option<bool>,
+	pub columns: Option<u8>,
+	pub rows: Option<u8>,
+	/// A hex color like `#ffffff`
+	pub color: Option<String>,
+	/// From 0 (invisible) to 1 (opaque)
+	pub opacity: Option<f32>,
+	/// The width of the lines in logical pixels
+	pub thickness: Option<f32>,
+}
+
+impl ConfigGridSection {
+	pub const DEFAULT_COLOR: &'static str = "#ffffff";
+
+	pub fn columns(&self) -> u8 {
+		self.columns.unwrap_or(3).max(1)
+	}
+
+	pub fn rows(&self) -> u8 {
+		self.rows.unwrap_or(3).max(1)
+	}
+
+	/// The color of the lines with the opacity as alpha
+	pub fn line_color(&self) -> [f32; 4] {
+		let color = self.color.as_deref().unwrap_or(Self::DEFAULT_COLOR);
+		let [r, g, b] = parse_hex_color(color).unwrap_or([255, 255, 255]);
+		let opacity = self.opacity.unwrap_or(0.6).clamp(0.0, 1.0);
+		[r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, opacity]
+	}
+
+	pub fn thickness(&self) -> f32 {
+		self.thickness.unwrap_or(1.0).max(0.5)
+	}
+}
+
+/// A folder that was bookmarked with `add_bookmark`, stored as a `[[bookmarks]]` table
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+pub struct Bookmark {
+	pub name: String,
+	pub path: PathBuf,
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Deserialize)]
+pub struct ConfigSidebarSection {
+	/// The folder at the top of the directory sidebar (`toggle_sidebar`). The default is the
+	/// home folder.
+	pub root: Option<String>,
+}
+
+/// The paper size of the pages of the PDFs that are made from a folder
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaperSize {
+	A3,
+	#[default]
+	A4,
+	A5,
+	Letter,
+	Legal,
+}
+impl PaperSize {
+	/// The width and the height of the portrait page in millimeters
+	pub fn size_mm(self) -> (f32, f32) {
+		match self {
+			PaperSize::A3 => (297.0, 420.0),
+			PaperSize::A4 => (210.0, 297.0),
+			PaperSize::A5 => (148.0, 210.0),
+			PaperSize::Letter => (215.9, 279.4),
+			PaperSize::Legal => (215.9, 355.6),
+		}
+	}
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Deserialize)]
+pub struct ConfigPdfSection {
+	pub paper: Option<PaperSize>,
+	/// The space between the image and the edges of the page
+	pub margin_mm: Option<f32>,
+}
+
+/// The overlay image of `export_watermarked`
+#[derive(Debug, Default, PartialEq, Clone, Deserialize)]
+pub struct ConfigWatermarkSection {
+	/// A PNG with transparency, drawn at its size over the exported image
+	pub path: Option<PathBuf>,
+	/// From 0 (invisible) to 1 (as opaque as the watermark itself)
+	pub alpha: Option<f32>,
+	pub corner: Option<WatermarkCorner>,
+	/// The distance from the edges of the image in pixels
+	pub margin_px: Option<u32>,
+	/// The distance from the edges in percent of the width and the height of the image.
+	/// Replaces `margin_px` when it's set.
+	pub margin_percent: Option<f32>,
+}
+
+/// Where the watermark is placed on the image
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatermarkCorner {
+	TopLeft,
+	TopRight,
+	BottomLeft,
+	#[default]
+	BottomRight,
+	Center,
+}
+
+/// The image hosting service that `upload_image` sends the images to
+#[derive(Debug, Default, Eq, PartialEq, Clone, Deserialize)]
+pub struct ConfigUploadSection {
+	/// The address that the image is posted to
+	pub url: Option<String>,
+	/// Sent as the `Authorization` header, like `Bearer abc` or `Client-ID abc`
+	pub token: Option<String>,
+	/// The form field that the file is sent in. The file is the whole body if it's not set.
+	pub form_field: Option<String>,
+	/// Where the address of the uploaded image is in the JSON reply, like `data.link`
+	pub url_path: Option<String>,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Deserialize)]
@@ -115,6 +443,16 @@ struct IncompleteCache {
 	pub window: Option<CacheWindowSection>,
 	pub updates: Option<CacheUpdateSection>,
 	pub image: Option<CacheImageSection>,
+	pub layouts: Option<BTreeMap<String, WindowLayout>>,
+}
+
+/// The panels that are shown, saved under a name by `save_layout`
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct WindowLayout {
+	pub directory_sidebar: bool,
+	pub bookmarks_sidebar: bool,
+	pub bottom_bar: bool,
+	pub histogram: bool,
 }
 
 #[derive(Debug, Default, Eq, PartialEq, Clone, Serialize)]
@@ -122,6 +460,9 @@ pub struct Cache {
 	pub window: CacheWindowSection,
 	pub updates: CacheUpdateSection,
 	pub image: CacheImageSection,
+	/// The saved layouts by their names
+	#[serde(skip_serializing_if = "BTreeMap::is_empty")]
+	pub layouts: BTreeMap<String, WindowLayout>,
 }
 impl From<IncompleteCache> for Cache {
 	fn from(cache: IncompleteCache) -> Self {
@@ -129,6 +470,7 @@ impl From<IncompleteCache> for Cache {
 			window: cache.window.unwrap_or_default(),
 			updates: cache.updates.unwrap_or_default(),
 			image: cache.image.unwrap_or_default(),
+			layouts: cache.layouts.unwrap_or_default(),
 		}
 	}
 }
@@ -223,7 +565,31 @@ pub struct Configuration {
 	pub title: Option<TitleSection>,
 	pub image: Option<ConfigImageSection>,
 	pub window: Option<ConfigWindowSection>,
+	pub file_operations: Option<ConfigFileOperationsSection>,
+	pub slideshow: Option<ConfigSlideshowSection>,
+	pub pdf: Option<ConfigPdfSection>,
+	pub search: Option<ConfigSearchSection>,
+	pub sidebar: Option<ConfigSidebarSection>,
+	pub grid: Option<ConfigGridSection>,
+	pub upload: Option<ConfigUploadSection>,
+	pub watermark: Option<ConfigWatermarkSection>,
+	pub bookmarks: Option<Vec<Bookmark>>,
+}
+
+/// The settings that the settings panel and the grid panel write into the config file. Only
+/// the ones that were changed in a panel are written, so that the values that were given on
+/// the command line don't end up in the file.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PanelSetting {
+	BackgroundColor,
+	ThreadCount,
+	CacheSize,
+	Fit,
+	SlideshowInterval,
+	/// The whole `[grid]` section
+	Grid,
 }
+
 impl Configuration {
 	pub fn load<P: AsRef<Path>>(file_path: P) -> Result<Configuration, String> {
 		let file_path = file_path.as_ref();
@@ -233,4 +599,99 @@ impl Configuration {
 		//println!("Read config from file:\n{:#?}", result);
 		Ok(result)
 	}
+
+	/// The names of the settings that differ in `reloaded` but are only read when Emulsion
+	/// starts, so changing them in the file has no effect until the next start
+	pub fn settings_needing_restart(&self, reloaded: &Configuration) -> Vec<&'static str> {
+		let image = |c: &Configuration| c.image.clone().unwrap_or_default();
+		let window = |c: &Configuration| c.window.clone().unwrap_or_default();
+		let (old_image, new_image) = (image(self), image(reloaded));
+		let (old_window, new_window) = (window(self), window(reloaded));
+		let mut changed = Vec::new();
+		if old_image.sort != new_image.sort {
+			changed.push("image.sort");
+		}
+		if old_image.max_image_size != new_image.max_image_size {
+			changed.push("image.max_image_size");
+		}
+		if old_window.show_bottom_bar != new_window.show_bottom_bar {
+			changed.push("window.show_bottom_bar");
+		}
+		if self.sidebar != reloaded.sidebar {
+			changed.push("sidebar.root");
+		}
+		if self.updates != reloaded.updates {
+			changed.push("updates.check_updates");
+		}
+		changed
+	}
+
+	/// Writes the given settings into the config file. The rest of the file, including the
+	/// comments, is left as it is.
+	pub fn save_panel_settings<P: AsRef<Path>>(
+		&self,
+		file_path: P,
+		settings: &[PanelSetting],
+	) -> Result<(), String> {
+		use toml_edit::{value, DocumentMut, Item, Table};
+
+		let file_path = file_path.as_ref();
+		let cfg_str = fs::read_to_string(file_path).unwrap_or_default();
+		let mut doc = cfg_str.parse::<DocumentMut>().map_err(|e| format!("{}", e))?;
+		let mut set = |section: &str, key: &str, new_value: Option<Item>| {
+			let table = doc.entry(section).or_insert(Item::Table(Table::new()));
+			if let Some(table) = table.as_table_like_mut() {
+				match new_value {
+					Some(new_value) => {
+						table.insert(key, new_value);
+					}
+					None => {
+						table.remove(key);
+					}
+				}
+			}
+		};
+		let window = self.window.as_ref();
+		let image = self.image.as_ref();
+		let slideshow = self.slideshow.as_ref();
+		for setting in settings {
+			match setting {
+				PanelSetting::BackgroundColor => {
+					let background_color = window.and_then(|w| w.background_color.clone());
+					set("window", "background_color", background_color.map(value));
+				}
+				PanelSetting::ThreadCount => {
+					let thread_count = image.and_then(|i| i.thread_count);
+					set("image", "thread_count", thread_count.map(|t| value(t as i64)));
+				}
+				PanelSetting::CacheSize => {
+					let cache_size = image.and_then(|i| i.cache_size_mb);
+					set("image", "cache_size_mb", cache_size.map(|c| value(c as i64)));
+				}
+				PanelSetting::Fit => {
+					let fit = image.and_then(|i| i.fit).map(|f| f.as_str());
+					set("image", "fit", fit.map(value));
+				}
+				PanelSetting::SlideshowInterval => {
+					let interval = slideshow.and_then(|s| s.interval_secs);
+					set("slideshow", "interval_secs", interval.map(|i| value(i as f64)));
+				}
+				PanelSetting::Grid => {
+					let grid = self.grid.clone().unwrap_or_default();
+					set("grid", "visible", grid.visible.map(value));
+					set("grid", "columns", grid.columns.map(|c| value(c as i64)));
+					set("grid", "rows", grid.rows.map(|r| value(r as i64)));
+					set("grid", "color", grid.color.map(value));
+					set("grid", "opacity", grid.opacity.map(|o| value(o as f64)));
+					set("grid", "thickness", grid.thickness.map(|t| value(t as f64)));
+				}
+			}
+		}
+
+		if let Some(parent) = file_path.parent() {
+			fs::create_dir_all(parent).map_err(|e| format!("{}", e))?;
+		}
+		fs::write(file_path, doc.to_string())
+			.map_err(|_| format!("Could not write config to {:?}", file_path))
+	}
 }
@@ -0,0 +1,99 @@
+//! Stores the regions of interest of images, which are set with `set_crop`. Whenever such an
+//! image is opened, the view is zoomed and panned to show only its region.
+//!
+//! The regions are kept in `crops.toml` in the configuration folder, as `[[crops]]` tables
+//! with the absolute path of the image and the rectangle in the pixels of the oriented image.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// A rectangle in the pixels of the oriented image
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Crop {
+	pub x: u32,
+	pub y: u32,
+	pub w: u32,
+	pub h: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CropEntry {
+	path: PathBuf,
+	x: u32,
+	y: u32,
+	w: u32,
+	h: u32,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct CropsFile {
+	#[serde(default)]
+	crops: Vec<CropEntry>,
+}
+
+pub struct CropStore {
+	file_path: PathBuf,
+	crops: BTreeMap<PathBuf, Crop>,
+}
+
+impl CropStore {
+	/// Reads the regions from the file at `file_path`. A missing file means that no region is
+	/// set yet.
+	pub fn load(file_path: PathBuf) -> io::Result<CropStore> {
+		let contents = match fs::read_to_string(&file_path) {
+			Ok(contents) => contents,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+			Err(e) => return Err(e),
+		};
+		let file: CropsFile = toml::from_str(&contents).map_err(io::Error::other)?;
+		let crops = file
+			.crops
+			.into_iter()
+			.filter(|entry| entry.w > 0 && entry.h > 0)
+			.map(|CropEntry { path, x, y, w, h }| (path, Crop { x, y, w, h }))
+			.collect();
+		Ok(CropStore { file_path, crops })
+	}
+
+	pub fn get(&self, image_path: &Path) -> Option<Crop> {
+		let key = image_path.canonicalize().ok()?;
+		self.crops.get(&key).copied()
+	}
+
+	/// Replaces the region of the image and saves every region to the file
+	pub fn set(&mut self, image_path: &Path, crop: Crop) -> io::Result<()> {
+		self.crops.insert(image_path.canonicalize()?, crop);
+		self.save()
+	}
+
+	/// Forgets the region of the image. Returns false if it had none.
+	pub fn remove(&mut self, image_path: &Path) -> io::Result<bool> {
+		if self.crops.remove(&image_path.canonicalize()?).is_none() {
+			return Ok(false);
+		}
+		self.save().map(|_| true)
+	}
+
+	fn save(&self) -> io::Result<()> {
+		let crops = self
+			.crops
+			.iter()
+			.map(|(path, crop)| CropEntry {
+				path: path.clone(),
+				x: crop.x,
+				y: crop.y,
+				w: crop.w,
+				h: crop.h,
+			})
+			.collect();
+		let contents = toml::to_string(&CropsFile { crops }).map_err(io::Error::other)?;
+		if let Some(parent) = self.file_path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		fs::write(&self.file_path, contents)
+	}
+}
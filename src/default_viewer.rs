@@ -0,0 +1,129 @@
+//! Makes emulsion the default image viewer of the desktop with `xdg-mime`, see `--xdg-open`.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use directories_next::BaseDirs;
+
+use crate::image_cache::image_loader::is_file_supported;
+
+const DESKTOP_FILE_NAME: &str = "emulsion.desktop";
+/// Installed for the current user when no other desktop file of emulsion is found
+static DESKTOP_FILE: &str = include_str!("../distribution/linux/emulsion.desktop");
+
+/// The MIME types of the file extensions that can be shown, as far as they have one
+const MIME_TYPES: &[(&str, &str)] = &[
+	("jpg", "image/jpeg"),
+	("jpeg", "image/jpeg"),
+	("png", "image/png"),
+	("apng", "image/apng"),
+	("gif", "image/gif"),
+	("webp", "image/webp"),
+	("tif", "image/tiff"),
+	("tiff", "image/tiff"),
+	("tga", "image/x-tga"),
+	("bmp", "image/bmp"),
+	("ico", "image/vnd.microsoft.icon"),
+	("hdr", "image/vnd.radiance"),
+	("pbm", "image/x-portable-bitmap"),
+	("pam", "image/x-portable-arbitrarymap"),
+	("ppm", "image/x-portable-pixmap"),
+	("pgm", "image/x-portable-graymap"),
+	("xbm", "image/x-xbitmap"),
+	("xpm", "image/x-xpixmap"),
+	("ff", "image/x-farbfeld"),
+	("dds", "image/x-dds"),
+	("icns", "image/x-icns"),
+	("cbz", "application/vnd.comicbook+zip"),
+	("ora", "image/openraster"),
+	("cr2", "image/x-canon-cr2"),
+	("nef", "image/x-nikon-nef"),
+	("arw", "image/x-sony-arw"),
+	("eps", "image/x-eps"),
+	("ps", "application/postscript"),
+	("avif", "image/avif"),
+	("jp2", "image/jp2"),
+	("j2k", "image/x-jp2-codestream"),
+	("j2c", "image/x-jp2-codestream"),
+];
+
+/// The MIME types of the extensions that `is_file_supported` accepts in this build, without
+/// duplicates
+pub fn supported_mime_types() -> Vec<&'static str> {
+	let mut mime_types = Vec::new();
+	for (extension, mime_type) in MIME_TYPES {
+		let supported = is_file_supported(Path::new(&format!("image.{}", extension)));
+		if supported && !mime_types.contains(mime_type) {
+			mime_types.push(*mime_type);
+		}
+	}
+	mime_types
+}
+
+/// Installs the desktop file if it's missing, then sets it as the default application of
+/// every supported MIME type. Stops at the first MIME type that can't be set.
+pub fn register_default_viewer() -> Result<(), String> {
+	let installed = install_desktop_file().map_err(|e| manual_steps(&e))?;
+	if let Some(path) = installed {
+		println!("Installed {}", path.display());
+	}
+	for mime_type in supported_mime_types() {
+		let output = Command::new("xdg-mime")
+			.args(["default", DESKTOP_FILE_NAME, mime_type])
+			.output()
+			.map_err(|e| match e.kind() {
+				io::ErrorKind::NotFound => manual_steps("xdg-mime was not found"),
+				_ => manual_steps(&format!("Could not start xdg-mime: {}", e)),
+			})?;
+		if !output.status.success() {
+			let stderr = String::from_utf8_lossy(&output.stderr);
+			let error = format!("xdg-mime failed for {}: {}", mime_type, stderr.trim());
+			return Err(manual_steps(&error));
+		}
+		println!("Emulsion opens {}", mime_type);
+	}
+	Ok(())
+}
+
+/// Writes the bundled desktop file into the applications folder of the current user, unless
+/// one of the data folders has it already. Returns the path if it was written.
+fn install_desktop_file() -> Result<Option<PathBuf>, String> {
+	let user_data = BaseDirs::new()
+		.map(|dirs| dirs.data_dir().to_owned())
+		.ok_or("The data folder of the user is unknown")?;
+	let system_data = std::env::var("XDG_DATA_DIRS")
+		.ok()
+		.filter(|dirs| !dirs.is_empty())
+		.unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+	let data_dirs =
+		std::iter::once(user_data.clone()).chain(system_data.split(':').map(PathBuf::from));
+	for dir in data_dirs {
+		if dir.join("applications").join(DESKTOP_FILE_NAME).is_file() {
+			return Ok(None);
+		}
+	}
+	let applications = user_data.join("applications");
+	let path = applications.join(DESKTOP_FILE_NAME);
+	fs::create_dir_all(&applications)
+		.and_then(|()| fs::write(&path, DESKTOP_FILE))
+		.map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+	Ok(Some(path))
+}
+
+/// The error followed by what has to be done to set the default viewer by hand
+fn manual_steps(error: &str) -> String {
+	format!(
+		"{}\n\n\
+		To make emulsion the default image viewer by hand:\n\
+		1. Copy distribution/linux/{} to ~/.local/share/applications, unless it's there\n\
+		2. Install xdg-utils with the package manager of the system, or make emulsion the \
+		default in the file associations of the desktop settings\n\
+		3. Run `xdg-mime default {} <type>` for each of:\n   {}",
+		error,
+		DESKTOP_FILE_NAME,
+		DESKTOP_FILE_NAME,
+		supported_mime_types().join(" ")
+	)
+}
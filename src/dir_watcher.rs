@@ -0,0 +1,104 @@
+//! Watches a folder for new images, for example the ones that a tethered camera saves.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::image_cache::image_loader::is_file_supported;
+
+/// How often the folder is listed to find new files
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Lists the folder on a background thread and reports the images that appear in it.
+///
+/// A new file is only reported once its size stayed the same between two listings, so that
+/// images that are still being written aren't loaded half finished.
+pub struct DirWatcher {
+	receiver: Receiver<PathBuf>,
+	run: Arc<AtomicBool>,
+}
+
+impl DirWatcher {
+	pub fn new(dir: PathBuf) -> DirWatcher {
+		let (sender, receiver) = channel();
+		let run = Arc::new(AtomicBool::new(true));
+		let thread_run = run.clone();
+		thread::Builder::new()
+			.name("directory watcher".into())
+			.spawn(move || watch(&dir, &sender, &thread_run))
+			.unwrap();
+		DirWatcher { receiver, run }
+	}
+
+	/// Returns the most recent of the new images since the last call, if there are any
+	pub fn try_recv_latest(&self) -> Option<PathBuf> {
+		let mut latest = None;
+		loop {
+			match self.receiver.try_recv() {
+				Ok(path) => latest = Some(path),
+				Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => return latest,
+			}
+		}
+	}
+}
+
+impl Drop for DirWatcher {
+	fn drop(&mut self) {
+		self.run.store(false, Ordering::Relaxed);
+	}
+}
+
+/// Returns the files in the folder along with their sizes
+fn list_files(dir: &Path) -> HashMap<PathBuf, u64> {
+	let entries = match fs::read_dir(dir) {
+		Ok(entries) => entries,
+		Err(e) => {
+			eprintln!("Could not list the watched folder {:?}: {}", dir, e);
+			return HashMap::new();
+		}
+	};
+	entries
+		.flatten()
+		.filter_map(|entry| {
+			let metadata = entry.metadata().ok()?;
+			if metadata.is_file() {
+				Some((entry.path(), metadata.len()))
+			} else {
+				None
+			}
+		})
+		.collect()
+}
+
+fn watch(dir: &Path, sender: &Sender<PathBuf>, run: &AtomicBool) {
+	let mut known: HashSet<PathBuf> = list_files(dir).into_keys().collect();
+	// The new files that might still be written, with the size they had at the last listing
+	let mut growing: HashMap<PathBuf, u64> = HashMap::new();
+	while run.load(Ordering::Relaxed) {
+		thread::sleep(POLL_INTERVAL);
+		let files = list_files(dir);
+		known.retain(|path| files.contains_key(path));
+		growing.retain(|path, _| files.contains_key(path));
+		let mut new_files: Vec<_> =
+			files.into_iter().filter(|(path, _)| !known.contains(path)).collect();
+		// Report the files in the order of their names when several arrive at once
+		new_files.sort();
+		for (path, size) in new_files {
+			let finished = size > 0 && growing.get(&path) == Some(&size);
+			if !finished {
+				growing.insert(path, size);
+				continue;
+			}
+			growing.remove(&path);
+			if is_file_supported(&path) && sender.send(path.clone()).is_err() {
+				return;
+			}
+			known.insert(path);
+		}
+	}
+}
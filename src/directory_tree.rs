@@ -0,0 +1,149 @@
+//! The folders below a root folder as a tree whose nodes can be expanded and collapsed, for
+//! the directory sidebar.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A folder that's shown in the tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeNode {
+	pub path: PathBuf,
+	pub name: String,
+	/// The number of ancestors of the folder below the root, zero for the root itself
+	pub depth: usize,
+	/// The number of supported images directly in the folder
+	pub image_count: usize,
+	pub has_subfolders: bool,
+	pub expanded: bool,
+}
+
+/// What is known about a folder after reading it
+#[derive(Debug, Clone)]
+struct FolderEntry {
+	path: PathBuf,
+	name: String,
+	image_count: usize,
+	has_subfolders: bool,
+}
+
+/// The folders below `root` that are shown, in the order of a depth-first walk, going into the
+/// expanded folders only. The folders are read when they are first shown and remembered until
+/// `refresh` is called.
+pub struct DirectoryTree {
+	root: FolderEntry,
+	is_image: fn(&Path) -> bool,
+	expanded: HashSet<PathBuf>,
+	subfolders: HashMap<PathBuf, Vec<FolderEntry>>,
+	nodes: Vec<TreeNode>,
+}
+
+impl DirectoryTree {
+	/// The root is expanded from the start. `is_image` decides which files are counted.
+	pub fn new(root: PathBuf, is_image: fn(&Path) -> bool) -> DirectoryTree {
+		let root = read_folder_entry(root, is_image);
+		let mut tree = DirectoryTree {
+			expanded: HashSet::from([root.path.clone()]),
+			root,
+			is_image,
+			subfolders: HashMap::new(),
+			nodes: Vec::new(),
+		};
+		tree.update_nodes();
+		tree
+	}
+
+	pub fn nodes(&self) -> &[TreeNode] {
+		&self.nodes
+	}
+
+	/// Expands the node at `index` if it's collapsed and collapses it otherwise. The expanded
+	/// folders inside a collapsed one stay expanded, for when it's expanded again.
+	pub fn toggle(&mut self, index: usize) {
+		let path = match self.nodes.get(index) {
+			Some(node) if node.has_subfolders => node.path.clone(),
+			_ => return,
+		};
+		if !self.expanded.remove(&path) {
+			self.expanded.insert(path);
+		}
+		self.update_nodes();
+	}
+
+	/// Reads the shown folders again, to pick up the changes on the disk since they were read
+	pub fn refresh(&mut self) {
+		self.root = read_folder_entry(self.root.path.clone(), self.is_image);
+		self.subfolders.clear();
+		self.update_nodes();
+	}
+
+	fn update_nodes(&mut self) {
+		let mut nodes = Vec::new();
+		let root = self.root.clone();
+		self.push_nodes(&root, 0, &mut nodes);
+		self.nodes = nodes;
+	}
+
+	fn push_nodes(&mut self, entry: &FolderEntry, depth: usize, nodes: &mut Vec<TreeNode>) {
+		let expanded = entry.has_subfolders && self.expanded.contains(&entry.path);
+		nodes.push(TreeNode {
+			path: entry.path.clone(),
+			name: entry.name.clone(),
+			depth,
+			image_count: entry.image_count,
+			has_subfolders: entry.has_subfolders,
+			expanded,
+		});
+		if !expanded {
+			return;
+		}
+		let is_image = self.is_image;
+		let subfolders = self
+			.subfolders
+			.entry(entry.path.clone())
+			.or_insert_with(|| read_subfolders(&entry.path, is_image))
+			.clone();
+		for subfolder in &subfolders {
+			self.push_nodes(subfolder, depth + 1, nodes);
+		}
+	}
+}
+
+/// The folders directly in `path`, sorted by name. Hidden folders are left out.
+fn read_subfolders(path: &Path, is_image: fn(&Path) -> bool) -> Vec<FolderEntry> {
+	let entries = match fs::read_dir(path) {
+		Ok(entries) => entries,
+		Err(_) => return Vec::new(),
+	};
+	let mut subfolders: Vec<FolderEntry> = entries
+		.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+		.filter(|path| path.is_dir() && !is_hidden(path))
+		.map(|path| read_folder_entry(path, is_image))
+		.collect();
+	subfolders.sort_by_key(|entry| entry.name.to_lowercase());
+	subfolders
+}
+
+fn read_folder_entry(path: PathBuf, is_image: fn(&Path) -> bool) -> FolderEntry {
+	let name = match path.file_name() {
+		Some(name) => name.to_string_lossy().into_owned(),
+		None => path.to_string_lossy().into_owned(),
+	};
+	let mut image_count = 0;
+	let mut has_subfolders = false;
+	if let Ok(entries) = fs::read_dir(&path) {
+		for entry in entries.filter_map(|entry| entry.ok()) {
+			let entry_path = entry.path();
+			if entry_path.is_dir() {
+				has_subfolders |= !is_hidden(&entry_path);
+			} else if is_image(&entry_path) {
+				image_count += 1;
+			}
+		}
+	}
+	FolderEntry { path, name, image_count, has_subfolders }
+}
+
+fn is_hidden(path: &Path) -> bool {
+	path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
@@ -0,0 +1,191 @@
+//! Downloads the images that are opened by their address, like
+//! `emulsion https://example.com/image.jpg`. The image is written to a temporary folder of the
+//! session, which only the current user can access, and then opened like any other file. An address that was downloaded before is
+//! opened from that file again, without downloading it a second time.
+//!
+//! Downloading needs the `networking` feature.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "networking")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+#[cfg(feature = "networking")]
+use std::{fs, io};
+
+use lazy_static::lazy_static;
+
+/// The number of redirects that are followed before the download gives up
+#[cfg(feature = "networking")]
+const MAX_REDIRECTS: u32 = 5;
+
+/// Downloads that are larger than this are stopped
+#[cfg(feature = "networking")]
+const MAX_DOWNLOAD_SIZE: u64 = 256 * 1024 * 1024;
+
+/// The file extensions for the content types that the address may not tell
+#[cfg(feature = "networking")]
+const CONTENT_TYPE_EXTENSIONS: &[(&str, &str)] = &[
+	("image/jpeg", "jpg"),
+	("image/png", "png"),
+	("image/gif", "gif"),
+	("image/webp", "webp"),
+	("image/bmp", "bmp"),
+	("image/tiff", "tif"),
+	("image/avif", "avif"),
+	("image/svg+xml", "svg"),
+	("image/x-icon", "ico"),
+];
+
+/// Numbers the downloaded files
+#[cfg(feature = "networking")]
+static NEXT_INDEX: AtomicUsize = AtomicUsize::new(0);
+
+/// Numbers the folders that the session tried to create
+#[cfg(feature = "networking")]
+static NEXT_DIR_ID: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static! {
+	/// The files that the addresses were downloaded to in this session
+	static ref DOWNLOADED: Mutex<HashMap<String, PathBuf>> = Mutex::new(HashMap::new());
+	/// The folder of the downloads, once the first download created it
+	static ref DOWNLOAD_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+pub enum DownloadProgress {
+	/// The number of bytes received so far and the size of the file if the server told it
+	Bytes(u64, Option<u64>),
+	Finished(Result<PathBuf, String>),
+}
+
+/// Whether the path is actually the address of an image on the web
+pub fn is_url(path: &Path) -> bool {
+	let path = match path.to_str() {
+		Some(path) => path.to_ascii_lowercase(),
+		None => return false,
+	};
+	path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// The file that the address was downloaded to earlier in this session
+pub fn downloaded_file(url: &str) -> Option<PathBuf> {
+	let downloaded = DOWNLOADED.lock().unwrap();
+	downloaded.get(url).filter(|path| path.is_file()).cloned()
+}
+
+/// The folder that the downloads of this session are written to. It's created by the first
+/// download, and a folder that exists already is never reused, because on a shared temporary
+/// folder another user could have put it there.
+#[cfg(feature = "networking")]
+fn download_dir() -> io::Result<PathBuf> {
+	let mut download_dir = DOWNLOAD_DIR.lock().unwrap();
+	if let Some(dir) = download_dir.as_ref() {
+		return Ok(dir.clone());
+	}
+	loop {
+		let dir_id = NEXT_DIR_ID.fetch_add(1, Ordering::Relaxed);
+		let folder_name = format!("emulsion-downloads-{}-{}", std::process::id(), dir_id);
+		let dir = std::env::temp_dir().join(folder_name);
+		let mut builder = fs::DirBuilder::new();
+		#[cfg(unix)]
+		std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+		match builder.create(&dir) {
+			Ok(()) => {
+				*download_dir = Some(dir.clone());
+				return Ok(dir);
+			}
+			Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+			Err(e) => return Err(e),
+		}
+	}
+}
+
+/// Deletes the downloaded files, when the program exits
+pub fn remove_downloads() {
+	if let Some(dir) = DOWNLOAD_DIR.lock().unwrap().take() {
+		let _ = std::fs::remove_dir_all(dir);
+	}
+}
+
+/// Downloads the image at `url` into the folder of the session, and returns the path of the
+/// file. `progress` is called whenever more of the file was received. Files larger than
+/// `MAX_DOWNLOAD_SIZE` aren't downloaded.
+#[cfg(feature = "networking")]
+pub fn download(url: &str, progress: &dyn Fn(u64, Option<u64>)) -> Result<PathBuf, String> {
+	use std::io::{Read, Write};
+
+	if let Some(path) = downloaded_file(url) {
+		return Ok(path);
+	}
+	let agent = ureq::AgentBuilder::new().redirects(MAX_REDIRECTS).build();
+	let response = agent
+		.get(url)
+		.set("User-Agent", "emulsion")
+		.call()
+		.map_err(|e| format!("Could not download {}: {}", url, e))?;
+	let total = response.header("Content-Length").and_then(|len| len.parse::<u64>().ok());
+	let too_large = || format!("{} is larger than {} MB", url, MAX_DOWNLOAD_SIZE / (1024 * 1024));
+	if total.is_some_and(|total| total > MAX_DOWNLOAD_SIZE) {
+		return Err(too_large());
+	}
+	let extension = CONTENT_TYPE_EXTENSIONS
+		.iter()
+		.find(|(content_type, _)| *content_type == response.content_type())
+		.map(|(_, extension)| *extension);
+
+	let dir = download_dir().map_err(|e| format!("Could not create the download folder: {}", e))?;
+	let path = dir.join(file_name(url, extension, NEXT_INDEX.fetch_add(1, Ordering::Relaxed)));
+	let write_error = |e: io::Error| format!("Could not write {:?}: {}", path, e);
+	let mut file =
+		fs::OpenOptions::new().write(true).create_new(true).open(&path).map_err(write_error)?;
+	// One byte more than the limit is read, to tell a file of exactly the limit from a larger one
+	let mut reader = response.into_reader().take(MAX_DOWNLOAD_SIZE + 1);
+	let mut buffer = vec![0; 1 << 16];
+	let mut received = 0;
+	loop {
+		let read = match reader.read(&mut buffer) {
+			Ok(0) => break,
+			Ok(read) => read,
+			Err(e) => {
+				drop(file);
+				let _ = fs::remove_file(&path);
+				return Err(format!("The download of {} stopped: {}", url, e));
+			}
+		};
+		if received + read as u64 > MAX_DOWNLOAD_SIZE {
+			drop(file);
+			let _ = fs::remove_file(&path);
+			return Err(too_large());
+		}
+		file.write_all(&buffer[..read]).map_err(write_error)?;
+		received += read as u64;
+		progress(received, total);
+	}
+	DOWNLOADED.lock().unwrap().insert(url.to_owned(), path.clone());
+	Ok(path)
+}
+
+#[cfg(not(feature = "networking"))]
+pub fn download(url: &str, _progress: &dyn Fn(u64, Option<u64>)) -> Result<PathBuf, String> {
+	Err(format!("Opening {} needs Emulsion to be built with the `networking` feature", url))
+}
+
+/// The last segment of the address, numbered so that the images of different addresses with
+/// the same name don't overwrite each other. The extension of the content type is added if the
+/// name has none.
+#[cfg(feature = "networking")]
+fn file_name(url: &str, extension: Option<&str>, index: usize) -> String {
+	let path = url.split(['?', '#']).next().unwrap_or_default();
+	let name = path.rsplit('/').next().unwrap_or_default();
+	let name: String = name
+		.chars()
+		.map(|c| if c.is_ascii_alphanumeric() || "._-".contains(c) { c } else { '_' })
+		.collect();
+	let name = if name.trim_matches('.').is_empty() { "image".to_owned() } else { name };
+	match extension {
+		Some(extension) if Path::new(&name).extension().is_none() => {
+			format!("{}-{}.{}", index, name, extension)
+		}
+		_ => format!("{}-{}", index, name),
+	}
+}
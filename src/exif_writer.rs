@@ -0,0 +1,322 @@
+//! Edits a few of the descriptive EXIF tags of JPEG files.
+//!
+//! The other fields and the thumbnail are kept, but the EXIF data is encoded again as a whole,
+//! so offsets within vendor specific fields like the maker note may no longer be valid.
+
+use std::fs;
+use std::io::{BufReader, Cursor};
+use std::path::{Path, PathBuf};
+
+use exif::{experimental::Writer, Context, Field, In, Rational, Tag, Value};
+
+/// The tag that Windows stores the keywords in, encoded as UTF-16
+const XP_KEYWORDS: Tag = Tag(Context::Tiff, 0x9c9e);
+
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+const MARKER_SOI: u8 = 0xd8;
+const MARKER_APP0: u8 = 0xe0;
+const MARKER_APP1: u8 = 0xe1;
+const MARKER_SOS: u8 = 0xda;
+const MARKER_EOI: u8 = 0xd9;
+/// The length of a segment is stored in two bytes that count themselves too
+const MAX_SEGMENT_DATA_LEN: usize = u16::MAX as usize - 2;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum EditableTag {
+	Description,
+	Artist,
+	Copyright,
+	Keywords,
+	/// The latitude and longitude in decimal degrees, separated by a comma
+	Gps,
+}
+
+impl EditableTag {
+	pub const ALL: [EditableTag; 5] = [
+		EditableTag::Description,
+		EditableTag::Artist,
+		EditableTag::Copyright,
+		EditableTag::Keywords,
+		EditableTag::Gps,
+	];
+
+	pub fn label(self) -> &'static str {
+		match self {
+			EditableTag::Description => "Description",
+			EditableTag::Artist => "Artist",
+			EditableTag::Copyright => "Copyright",
+			EditableTag::Keywords => "Keywords",
+			EditableTag::Gps => "GPS",
+		}
+	}
+}
+
+/// Returns true if the EXIF data of the file can be edited
+pub fn is_editable(path: &Path) -> bool {
+	let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+	matches!(extension.as_deref(), Some("jpg" | "jpeg" | "jpe" | "jfif"))
+}
+
+/// The backup of the original file, `photo.jpg.bak` for `photo.jpg`
+pub fn backup_path(path: &Path) -> PathBuf {
+	let mut name = path.as_os_str().to_owned();
+	name.push(".bak");
+	PathBuf::from(name)
+}
+
+fn read_exif(path: &Path) -> Result<Option<exif::Exif>, String> {
+	let file = fs::File::open(path).map_err(|e| format!("Could not open {:?}: {}", path, e))?;
+	match exif::Reader::new().read_from_container(&mut BufReader::new(file)) {
+		Ok(exif) => Ok(Some(exif)),
+		Err(exif::Error::NotFound(_)) => Ok(None),
+		Err(e) => Err(format!("Could not read the EXIF data: {}", e)),
+	}
+}
+
+/// Returns the current values of the editable tags in the order of `EditableTag::ALL`. Tags
+/// that aren't set are empty.
+pub fn read_editable(path: &Path) -> Result<Vec<String>, String> {
+	let exif = read_exif(path)?;
+	let field = |tag| exif.as_ref().and_then(|exif| exif.get_field(tag, In::PRIMARY));
+	let ascii = |tag| match field(tag).map(|f| &f.value) {
+		Some(Value::Ascii(strings)) => strings
+			.first()
+			.map(|s| String::from_utf8_lossy(s).trim().to_owned())
+			.unwrap_or_default(),
+		_ => String::new(),
+	};
+	let keywords = match field(XP_KEYWORDS).map(|f| &f.value) {
+		Some(Value::Byte(bytes)) => decode_utf16(bytes),
+		_ => String::new(),
+	};
+	let coordinate = |tag, ref_tag, negative: &[u8]| {
+		let degrees = match field(tag).map(|f| &f.value) {
+			Some(Value::Rational(parts)) if parts.len() == 3 => {
+				parts[0].to_f64() + parts[1].to_f64() / 60.0 + parts[2].to_f64() / 3600.0
+			}
+			_ => return None,
+		};
+		let is_negative = match field(ref_tag).map(|f| &f.value) {
+			Some(Value::Ascii(strings)) => strings.first().is_some_and(|s| s == negative),
+			_ => false,
+		};
+		Some(if is_negative { -degrees } else { degrees })
+	};
+	let latitude = coordinate(Tag::GPSLatitude, Tag::GPSLatitudeRef, b"S");
+	let longitude = coordinate(Tag::GPSLongitude, Tag::GPSLongitudeRef, b"W");
+	let gps = match (latitude, longitude) {
+		(Some(latitude), Some(longitude)) => format!("{:.6}, {:.6}", latitude, longitude),
+		_ => String::new(),
+	};
+	Ok(EditableTag::ALL
+		.iter()
+		.map(|tag| match tag {
+			EditableTag::Description => ascii(Tag::ImageDescription),
+			EditableTag::Artist => ascii(Tag::Artist),
+			EditableTag::Copyright => ascii(Tag::Copyright),
+			EditableTag::Keywords => keywords.clone(),
+			EditableTag::Gps => gps.clone(),
+		})
+		.collect())
+}
+
+/// Parses coordinates in decimal degrees like `40.7128, -74.0060`. Returns `None` if `text`
+/// is empty.
+pub fn parse_gps(text: &str) -> Result<Option<(f64, f64)>, String> {
+	let text = text.trim();
+	if text.is_empty() {
+		return Ok(None);
+	}
+	let invalid = || format!("Invalid GPS coordinates {:?}, expected e.g. 40.7128, -74.0060", text);
+	let (latitude, longitude) = text.split_once(',').ok_or_else(invalid)?;
+	let latitude: f64 = latitude.trim().parse().map_err(|_| invalid())?;
+	let longitude: f64 = longitude.trim().parse().map_err(|_| invalid())?;
+	if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+		return Err(invalid());
+	}
+	Ok(Some((latitude, longitude)))
+}
+
+/// Replaces the editable tags of the JPEG with `values`, which are in the order of
+/// `EditableTag::ALL`. Empty values remove the tag. The original file is copied to its
+/// backup path first, unless a backup already exists from an earlier edit.
+pub fn write_editable(path: &Path, values: &[String]) -> Result<(), String> {
+	let gps = parse_gps(&values[EditableTag::ALL.len() - 1])?;
+	let jpeg = fs::read(path).map_err(|e| format!("Could not read {:?}: {}", path, e))?;
+	let exif = read_exif(path)?;
+
+	let mut new_fields = Vec::new();
+	for (tag, value) in EditableTag::ALL.iter().zip(values) {
+		let value = value.trim();
+		if value.is_empty() {
+			continue;
+		}
+		let ascii = |tag| Field {
+			tag,
+			ifd_num: In::PRIMARY,
+			value: Value::Ascii(vec![value.as_bytes().to_vec()]),
+		};
+		match tag {
+			EditableTag::Description => new_fields.push(ascii(Tag::ImageDescription)),
+			EditableTag::Artist => new_fields.push(ascii(Tag::Artist)),
+			EditableTag::Copyright => new_fields.push(ascii(Tag::Copyright)),
+			EditableTag::Keywords => new_fields.push(Field {
+				tag: XP_KEYWORDS,
+				ifd_num: In::PRIMARY,
+				value: Value::Byte(encode_utf16(value)),
+			}),
+			EditableTag::Gps => (),
+		}
+	}
+	if let Some((latitude, longitude)) = gps {
+		let gps_field = |tag, value| Field { tag, ifd_num: In::PRIMARY, value };
+		let ascii = |s: &str| Value::Ascii(vec![s.as_bytes().to_vec()]);
+		let latitude_ref = if latitude < 0.0 { "S" } else { "N" };
+		let longitude_ref = if longitude < 0.0 { "W" } else { "E" };
+		new_fields.push(gps_field(Tag::GPSVersionID, Value::Byte(vec![2, 3, 0, 0])));
+		new_fields.push(gps_field(Tag::GPSLatitudeRef, ascii(latitude_ref)));
+		new_fields.push(gps_field(Tag::GPSLatitude, degrees_to_rationals(latitude)));
+		new_fields.push(gps_field(Tag::GPSLongitudeRef, ascii(longitude_ref)));
+		new_fields.push(gps_field(Tag::GPSLongitude, degrees_to_rationals(longitude)));
+	}
+
+	let replaced = |field: &Field| {
+		let edited = [
+			Tag::ImageDescription,
+			Tag::Artist,
+			Tag::Copyright,
+			XP_KEYWORDS,
+			Tag::GPSLatitudeRef,
+			Tag::GPSLatitude,
+			Tag::GPSLongitudeRef,
+			Tag::GPSLongitude,
+		];
+		// The version is kept along with the other GPS fields if the position is removed
+		let version_replaced = gps.is_some() && field.tag == Tag::GPSVersionID;
+		field.ifd_num == In::PRIMARY && (edited.contains(&field.tag) || version_replaced)
+	};
+	let mut writer = Writer::new();
+	let mut primary_field_count = new_fields.len();
+	let mut little_endian = false;
+	if let Some(exif) = &exif {
+		little_endian = exif.little_endian();
+		for field in exif.fields().filter(|field| !replaced(field)) {
+			primary_field_count += (field.ifd_num == In::PRIMARY) as usize;
+			writer.push_field(field);
+		}
+		if let Some(thumbnail) = thumbnail_data(exif) {
+			writer.set_jpeg(thumbnail, In::THUMBNAIL);
+		}
+	}
+	for field in &new_fields {
+		writer.push_field(field);
+	}
+	// The EXIF data can't be written without any field in the primary IFD, but then there's
+	// nothing to keep either
+	let app1_data = if primary_field_count == 0 {
+		None
+	} else {
+		let mut tiff = Cursor::new(Vec::new());
+		writer.write(&mut tiff, little_endian).map_err(|e| format!("Could not encode: {}", e))?;
+		let mut data = EXIF_HEADER.to_vec();
+		data.extend_from_slice(&tiff.into_inner());
+		Some(data)
+	};
+	let new_jpeg = replace_exif_segment(&jpeg, app1_data.as_deref())?;
+
+	let backup = backup_path(path);
+	if !backup.exists() {
+		fs::copy(path, &backup).map_err(|e| format!("Could not back up the file: {}", e))?;
+	}
+	fs::write(path, new_jpeg).map_err(|e| format!("Could not write {:?}: {}", path, e))
+}
+
+/// The embedded JPEG thumbnail, which the writer needs to be given separately
+fn thumbnail_data(exif: &exif::Exif) -> Option<&[u8]> {
+	let offset = exif.get_field(Tag::JPEGInterchangeFormat, In::THUMBNAIL)?.value.get_uint(0)?;
+	let len = exif.get_field(Tag::JPEGInterchangeFormatLength, In::THUMBNAIL)?.value.get_uint(0)?;
+	exif.buf().get(offset as usize..offset as usize + len as usize)
+}
+
+/// Returns the JPEG with its EXIF segment replaced by one with `app1_data`, or removed if it's
+/// `None`. The new segment comes after the JFIF segment if there is one.
+fn replace_exif_segment(jpeg: &[u8], app1_data: Option<&[u8]>) -> Result<Vec<u8>, String> {
+	let invalid = || String::from("The file is not a valid JPEG");
+	if jpeg.len() < 2 || jpeg[0] != 0xff || jpeg[1] != MARKER_SOI {
+		return Err(invalid());
+	}
+	let mut new_segment = Vec::new();
+	if let Some(data) = app1_data {
+		if data.len() > MAX_SEGMENT_DATA_LEN {
+			return Err("The EXIF data is too large for a JPEG".into());
+		}
+		new_segment.extend_from_slice(&[0xff, MARKER_APP1]);
+		new_segment.extend_from_slice(&((data.len() + 2) as u16).to_be_bytes());
+		new_segment.extend_from_slice(data);
+	}
+	let mut output = Vec::with_capacity(jpeg.len() + new_segment.len());
+	output.extend_from_slice(&jpeg[..2]);
+	let mut inserted = false;
+	let mut pos = 2;
+	loop {
+		if jpeg.get(pos) != Some(&0xff) {
+			return Err(invalid());
+		}
+		// Markers may be preceded by any number of fill bytes
+		let mut marker_pos = pos + 1;
+		while jpeg.get(marker_pos) == Some(&0xff) {
+			marker_pos += 1;
+		}
+		let marker = *jpeg.get(marker_pos).ok_or_else(invalid)?;
+		if !inserted && marker != MARKER_APP0 {
+			output.extend_from_slice(&new_segment);
+			inserted = true;
+		}
+		if marker == MARKER_SOS || marker == MARKER_EOI {
+			// The compressed image data follows, which is copied as it is
+			output.extend_from_slice(&jpeg[pos..]);
+			return Ok(output);
+		}
+		let standalone = marker == 0x01 || (0xd0..=0xd7).contains(&marker);
+		let end = if standalone {
+			marker_pos + 1
+		} else {
+			let len = jpeg.get(marker_pos + 1..marker_pos + 3).ok_or_else(invalid)?;
+			marker_pos + 1 + u16::from_be_bytes([len[0], len[1]]) as usize
+		};
+		let segment = jpeg.get(pos..end).ok_or_else(invalid)?;
+		let data = segment.get(marker_pos + 3 - pos..);
+		let is_exif = marker == MARKER_APP1 && data.is_some_and(|d| d.starts_with(EXIF_HEADER));
+		if !is_exif {
+			output.extend_from_slice(segment);
+		}
+		pos = end;
+	}
+}
+
+fn degrees_to_rationals(degrees: f64) -> Value {
+	let degrees = degrees.abs();
+	let whole_degrees = degrees.floor();
+	let minutes = (degrees - whole_degrees) * 60.0;
+	let whole_minutes = minutes.floor();
+	let seconds = (minutes - whole_minutes) * 60.0;
+	Value::Rational(vec![
+		Rational { num: whole_degrees as u32, denom: 1 },
+		Rational { num: whole_minutes as u32, denom: 1 },
+		Rational { num: (seconds * 10000.0).round() as u32, denom: 10000 },
+	])
+}
+
+/// Decodes the null terminated UTF-16LE text of the Windows tags
+fn decode_utf16(bytes: &[u8]) -> String {
+	let units: Vec<u16> = bytes
+		.chunks_exact(2)
+		.map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+		.take_while(|&unit| unit != 0)
+		.collect();
+	String::from_utf16_lossy(&units)
+}
+
+fn encode_utf16(text: &str) -> Vec<u8> {
+	text.encode_utf16().chain(std::iter::once(0)).flat_map(|unit| unit.to_le_bytes()).collect()
+}
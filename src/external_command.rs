@@ -0,0 +1,86 @@
+//! Runs a shell command on the shown image with `run_command`, like
+//! `convert $input -negate $output`. `$input` (or `$file`) is replaced by the path of the
+//! image and `$output` by a new file in the temporary folder, both quoted for the shell.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::temp_file;
+use crate::terminal::{quote, shell_command};
+
+const INPUT_PLACEHOLDERS: &[&str] = &["$input", "$file"];
+const OUTPUT_PLACEHOLDER: &str = "$output";
+
+/// The command with the placeholders replaced. The template is read once from the start, so
+/// a path that has a placeholder in its name is left as it is.
+pub fn expand(template: &str, input: &Path, output: &Path) -> String {
+	let input = quote(&input.to_string_lossy());
+	let output = quote(&output.to_string_lossy());
+	let mut command = String::with_capacity(template.len());
+	let mut rest = template;
+	while let Some(start) = rest.find('$') {
+		command.push_str(&rest[..start]);
+		rest = &rest[start..];
+		let replacement = if rest.starts_with(OUTPUT_PLACEHOLDER) {
+			Some((OUTPUT_PLACEHOLDER, &output))
+		} else {
+			INPUT_PLACEHOLDERS
+				.iter()
+				.find(|placeholder| rest.starts_with(**placeholder))
+				.map(|placeholder| (*placeholder, &input))
+		};
+		match replacement {
+			Some((placeholder, path)) => {
+				command.push_str(path);
+				rest = &rest[placeholder.len()..];
+			}
+			None => {
+				command.push('$');
+				rest = &rest[1..];
+			}
+		}
+	}
+	command.push_str(rest);
+	command
+}
+
+/// Runs the command with the shell of the system in the folder of the image, and waits for it
+/// to exit. Returns the file that should be shown afterwards: the output file if the command
+/// has `$output`, otherwise the image, which the command is expected to change in place. The
+/// error has what the command wrote to its standard error.
+pub fn run(template: &str, input: &Path) -> Result<PathBuf, String> {
+	let extension = input.extension().unwrap_or_default().to_string_lossy();
+	let output = temp_file::create("emulsion-output", &extension)
+		.map_err(|e| format!("Could not create the output file: {}", e))?;
+	let result = run_with_output(template, input, &output);
+	if !matches!(&result, Ok(path) if path == &output) {
+		let _ = fs::remove_file(&output);
+	}
+	result
+}
+
+fn run_with_output(template: &str, input: &Path, output: &Path) -> Result<PathBuf, String> {
+	let command = expand(template, input, output);
+	let mut shell = shell_command(&command);
+	if let Some(folder) = input.parent().filter(|folder| folder.is_dir()) {
+		shell.current_dir(folder);
+	}
+	let result = shell.output().map_err(|e| format!("Could not run {:?}: {}", command, e))?;
+	if !result.status.success() {
+		let stderr = String::from_utf8_lossy(&result.stderr);
+		let stderr = stderr.trim();
+		return Err(if stderr.is_empty() {
+			format!("The command failed with {}", result.status)
+		} else {
+			format!("The command failed: {}", stderr)
+		});
+	}
+	if !template.contains(OUTPUT_PLACEHOLDER) {
+		return Ok(input.to_owned());
+	}
+	// The file is created empty beforehand, so that no one else can put one there
+	if fs::metadata(output).map_or(true, |metadata| metadata.len() == 0) {
+		return Err("The command didn't write $output".into());
+	}
+	Ok(output.to_owned())
+}
@@ -0,0 +1,136 @@
+//! Asks the user for an image, or for where to save a file, with the file chooser of the
+//! operating system.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// The extensions offered in the file chooser. Files of the other supported formats can still
+/// be opened once their folder is shown.
+const IMAGE_EXTENSIONS: &[&str] = &[
+	"jpg", "jpeg", "png", "apng", "gif", "webp", "tif", "tiff", "tga", "bmp", "ico", "hdr", "pbm",
+	"pam", "ppm", "pgm", "xbm", "xpm", "ff", "dds", "icns", "eps", "ps", "cbz", "ora", "cr2",
+	"nef", "arw",
+];
+/// The sessions that were saved with `save_session`
+const SESSION_EXTENSION: &str = "json";
+
+/// Shows the file chooser, starting in `start_dir` if it's given, and waits until it's closed.
+/// Returns `None` if the user cancelled it.
+pub fn pick_image(start_dir: Option<&Path>) -> Result<Option<PathBuf>, String> {
+	picked_path(run_platform_dialog(start_dir)?)
+}
+
+/// Asks where to save a file, suggesting `file_name` in `start_dir`. Returns `None` if the user
+/// cancelled it.
+pub fn pick_save_path(start_dir: &Path, file_name: &str) -> Result<Option<PathBuf>, String> {
+	picked_path(run_platform_save_dialog(start_dir, file_name)?)
+}
+
+fn picked_path(output: Output) -> Result<Option<PathBuf>, String> {
+	let picked = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+	if picked.is_empty() {
+		// The choosers exit with an error when they are cancelled, with nothing printed
+		return Ok(None);
+	}
+	if !output.status.success() {
+		return Err(format!(
+			"The file chooser failed: {}",
+			String::from_utf8_lossy(&output.stderr).trim()
+		));
+	}
+	Ok(Some(PathBuf::from(picked)))
+}
+
+fn run(command: &mut Command) -> Result<Output, String> {
+	command.output().map_err(|e| format!("Could not open the file chooser: {}", e))
+}
+
+#[cfg(target_os = "windows")]
+fn run_platform_dialog(start_dir: Option<&Path>) -> Result<Output, String> {
+	const SCRIPT: &str = "Add-Type -AssemblyName System.Windows.Forms; \
+		$dialog = New-Object System.Windows.Forms.OpenFileDialog; \
+		$dialog.Filter = $env:EMULSION_FILTER; \
+		$dialog.InitialDirectory = $env:EMULSION_START_DIR; \
+		if ($dialog.ShowDialog() -eq 'OK') { Write-Output $dialog.FileName }";
+	let patterns: Vec<String> = IMAGE_EXTENSIONS.iter().map(|ext| format!("*.{}", ext)).collect();
+	let filter =
+		format!("Images|{}|Sessions|*.{}|All files|*.*", patterns.join(";"), SESSION_EXTENSION);
+	run(Command::new("powershell")
+		.args(["-NoProfile", "-NonInteractive", "-STA", "-Command", SCRIPT])
+		.env("EMULSION_FILTER", filter)
+		.env("EMULSION_START_DIR", start_dir.unwrap_or(Path::new(""))))
+}
+
+#[cfg(target_os = "windows")]
+fn run_platform_save_dialog(start_dir: &Path, file_name: &str) -> Result<Output, String> {
+	const SCRIPT: &str = "Add-Type -AssemblyName System.Windows.Forms; \
+		$dialog = New-Object System.Windows.Forms.SaveFileDialog; \
+		$dialog.InitialDirectory = $env:EMULSION_START_DIR; \
+		$dialog.FileName = $env:EMULSION_FILE_NAME; \
+		if ($dialog.ShowDialog() -eq 'OK') { Write-Output $dialog.FileName }";
+	run(Command::new("powershell")
+		.args(["-NoProfile", "-NonInteractive", "-STA", "-Command", SCRIPT])
+		.env("EMULSION_START_DIR", start_dir)
+		.env("EMULSION_FILE_NAME", file_name))
+}
+
+#[cfg(target_os = "macos")]
+fn run_platform_dialog(start_dir: Option<&Path>) -> Result<Output, String> {
+	let types: Vec<String> = IMAGE_EXTENSIONS
+		.iter()
+		.chain([&SESSION_EXTENSION])
+		.map(|ext| format!("\"{}\"", ext))
+		.collect();
+	let mut script = format!("POSIX path of (choose file of type {{{}}}", types.join(", "));
+	if let Some(dir) = start_dir {
+		let dir = dir.to_string_lossy().replace('\\', "\\\\").replace('"', "\\\"");
+		script.push_str(&format!(" default location POSIX file \"{}\"", dir));
+	}
+	script.push(')');
+	run(Command::new("osascript").args(["-e", &script]))
+}
+
+#[cfg(target_os = "macos")]
+fn run_platform_save_dialog(start_dir: &Path, file_name: &str) -> Result<Output, String> {
+	let escape = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"");
+	let script = format!(
+		"POSIX path of (choose file name default name \"{}\" default location POSIX file \"{}\")",
+		escape(file_name),
+		escape(&start_dir.to_string_lossy())
+	);
+	run(Command::new("osascript").args(["-e", &script]))
+}
+
+/// Uses zenity, which comes with GNOME, or kdialog on KDE if zenity isn't installed
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn run_platform_dialog(start_dir: Option<&Path>) -> Result<Output, String> {
+	let patterns: Vec<String> = IMAGE_EXTENSIONS.iter().map(|ext| format!("*.{}", ext)).collect();
+	let mut zenity = Command::new("zenity");
+	zenity.args(["--file-selection", "--title=Open an image"]);
+	zenity.arg(format!("--file-filter=Images | {}", patterns.join(" ")));
+	zenity.arg(format!("--file-filter=Sessions | *.{}", SESSION_EXTENSION));
+	zenity.arg("--file-filter=All files | *");
+	if let Some(dir) = start_dir {
+		// A trailing slash makes zenity open the folder instead of selecting it
+		zenity.arg(format!("--filename={}/", dir.to_string_lossy()));
+	}
+	match zenity.output() {
+		Ok(output) => Ok(output),
+		Err(_) => run(Command::new("kdialog")
+			.arg("--getopenfilename")
+			.arg(start_dir.unwrap_or(Path::new(".")))
+			.arg(format!("Images ({})\nSessions (*.{})", patterns.join(" "), SESSION_EXTENSION))),
+	}
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn run_platform_save_dialog(start_dir: &Path, file_name: &str) -> Result<Output, String> {
+	let suggested = start_dir.join(file_name);
+	let mut zenity = Command::new("zenity");
+	zenity.args(["--file-selection", "--save", "--confirm-overwrite", "--title=Save as"]);
+	zenity.arg(format!("--filename={}", suggested.to_string_lossy()));
+	match zenity.output() {
+		Ok(output) => Ok(output),
+		Err(_) => run(Command::new("kdialog").arg("--getsavefilename").arg(suggested)),
+	}
+}
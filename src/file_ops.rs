@@ -0,0 +1,147 @@
+//! Keeps track of the operations that were made on image files, so that they can be undone.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The number of file operations that can be undone, if not specified in the configuration
+pub const DEFAULT_UNDO_STEPS: usize = 10;
+
+// Not every kind of operation is available in the viewer yet
+#[allow(dead_code)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum OpKind {
+	/// The file at `original_path` was moved to the trash
+	Delete,
+	/// The file at `original_path` was moved to `new_path`
+	Move,
+	/// The file at `original_path` was renamed to `new_path`
+	Rename,
+	/// The file at `original_path` was copied to `new_path`
+	Copy,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileOperation {
+	pub kind: OpKind,
+	pub original_path: PathBuf,
+	pub new_path: Option<PathBuf>,
+}
+
+impl FileOperation {
+	pub fn delete(path: PathBuf) -> FileOperation {
+		FileOperation { kind: OpKind::Delete, original_path: path, new_path: None }
+	}
+
+	pub fn with_new_path(kind: OpKind, original_path: PathBuf, new_path: PathBuf) -> FileOperation {
+		FileOperation { kind, original_path, new_path: Some(new_path) }
+	}
+
+	/// Reverts the effect of this operation on the file system.
+	fn revert(&self) -> Result<(), String> {
+		if self.kind == OpKind::Delete {
+			return restore_from_trash(&self.original_path);
+		}
+		let new_path = self
+			.new_path
+			.as_ref()
+			.ok_or_else(|| format!("The {:?} operation has no target path", self.kind))?;
+		let result = match self.kind {
+			OpKind::Move | OpKind::Rename => {
+				if self.original_path.exists() {
+					return Err(format!("{:?} already exists", self.original_path));
+				}
+				fs::rename(new_path, &self.original_path)
+			}
+			OpKind::Copy => fs::remove_file(new_path),
+			OpKind::Delete => unreachable!(),
+		};
+		result.map_err(|e| format!("Could not undo the {:?} of {:?}: {}", self.kind, new_path, e))
+	}
+}
+
+#[cfg(any(
+	target_os = "windows",
+	all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+))]
+fn restore_from_trash(path: &Path) -> Result<(), String> {
+	let items =
+		trash::os_limited::list().map_err(|e| format!("Could not list the trash: {}", e))?;
+	// The same path may have been deleted multiple times, restore the latest one
+	let item = items
+		.into_iter()
+		.filter(|item| item.original_path() == path)
+		.max_by_key(|item| item.time_deleted)
+		.ok_or_else(|| format!("{:?} is not in the trash anymore", path))?;
+	trash::os_limited::restore_all([item])
+		.map_err(|e| format!("Could not restore {:?} from the trash: {}", path, e))
+}
+
+#[cfg(not(any(
+	target_os = "windows",
+	all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+)))]
+fn restore_from_trash(path: &Path) -> Result<(), String> {
+	let trash = match trash_folder() {
+		Some(folder) => format!("{:?}", folder),
+		None => "the trash".to_owned(),
+	};
+	Err(format!(
+		"Restoring from the trash is not supported on this platform, {:?} can be restored from {}",
+		path, trash
+	))
+}
+
+/// The folder of the trash on the platforms where the trash can't be listed
+#[cfg(not(any(
+	target_os = "windows",
+	all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+)))]
+fn trash_folder() -> Option<PathBuf> {
+	if cfg!(target_os = "macos") {
+		directories_next::UserDirs::new().map(|dirs| dirs.home_dir().join(".Trash"))
+	} else {
+		None
+	}
+}
+
+/// A stack of the latest file operations. It only lives as long as the process does.
+pub struct FileOpHistory {
+	operations: VecDeque<FileOperation>,
+	capacity: usize,
+}
+
+impl FileOpHistory {
+	pub fn new(capacity: usize) -> FileOpHistory {
+		FileOpHistory { operations: VecDeque::with_capacity(capacity), capacity }
+	}
+
+	/// Records an operation that was successfully executed. The oldest operation is dropped if
+	/// the history is full.
+	pub fn push(&mut self, operation: FileOperation) {
+		if self.capacity == 0 {
+			return;
+		}
+		if self.operations.len() >= self.capacity {
+			self.operations.pop_front();
+		}
+		self.operations.push_back(operation);
+	}
+
+	/// Reverts the latest operation and returns it. Returns `None` if there's nothing to undo.
+	///
+	/// The operation is removed from the history even if reverting it failed, so that a
+	/// single broken operation doesn't block undoing the ones before it.
+	pub fn undo(&mut self) -> Option<Result<FileOperation, String>> {
+		let operation = self.operations.pop_back()?;
+		Some(operation.revert().map(|()| operation))
+	}
+
+	/// Same as `undo`, but reverts the latest deletion and keeps the operations made after it.
+	/// Returns `None` if no file was deleted.
+	pub fn undo_delete(&mut self) -> Option<Result<FileOperation, String>> {
+		let index = self.operations.iter().rposition(|op| op.kind == OpKind::Delete)?;
+		let operation = self.operations.remove(index)?;
+		Some(operation.revert().map(|()| operation))
+	}
+}
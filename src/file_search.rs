@@ -0,0 +1,147 @@
+//! Finds the images whose names match a pattern in a folder and its subfolders, and arranges
+//! them into a grid of thumbnails.
+
+use std::path::{Path, PathBuf};
+
+use gelatin::image::{self, imageops, Rgba, RgbaImage};
+use walkdir::WalkDir;
+
+/// How many levels of subfolders are searched, if it's not set in the configuration
+pub const DEFAULT_SEARCH_DEPTH: usize = 4;
+/// More results would make the grid too large for the GPU, so the rest is left out
+pub const MAX_RESULTS: usize = 200;
+
+/// The size of a thumbnail along its larger side
+const CELL_SIZE: u32 = 160;
+const CELL_GAP: u32 = 8;
+const MAX_COLUMNS: usize = 8;
+const BACKGROUND: Rgba<u8> = Rgba([32, 32, 32, 255]);
+const SELECTION_COLOR: Rgba<u8> = Rgba([80, 160, 255, 255]);
+const SELECTION_WIDTH: u32 = 4;
+
+/// Matches a file name against a glob pattern, ignoring the case of ASCII letters. `*`
+/// matches any number of characters and `?` matches a single character. A pattern without
+/// wildcards matches the names that contain it.
+pub fn glob_matches(pattern: &str, name: &str) -> bool {
+	let pattern: Vec<char> = pattern.to_ascii_lowercase().chars().collect();
+	let name: Vec<char> = name.to_ascii_lowercase().chars().collect();
+	if pattern.is_empty() {
+		return true;
+	}
+	if !pattern.iter().any(|&c| c == '*' || c == '?') {
+		return name.windows(pattern.len()).any(|window| window == &pattern[..]);
+	}
+	// Backtracks to the last star when the rest of the name doesn't match
+	let (mut p, mut n) = (0, 0);
+	let mut last_star = None;
+	while n < name.len() {
+		match pattern.get(p) {
+			Some('*') => {
+				last_star = Some((p, n));
+				p += 1;
+			}
+			Some(&c) if c == '?' || c == name[n] => {
+				p += 1;
+				n += 1;
+			}
+			_ => match last_star {
+				Some((star_p, star_n)) => {
+					p = star_p + 1;
+					n = star_n + 1;
+					last_star = Some((star_p, star_n + 1));
+				}
+				None => return false,
+			},
+		}
+	}
+	pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Returns the files below `root`, at most `max_depth` folders deep, whose names match the
+/// pattern and that `is_image` accepts. The paths are sorted and at most `MAX_RESULTS` long.
+pub fn find_files(
+	root: &Path,
+	pattern: &str,
+	max_depth: usize,
+	is_image: impl Fn(&Path) -> bool,
+) -> Vec<PathBuf> {
+	let mut found: Vec<PathBuf> = WalkDir::new(root)
+		.max_depth(max_depth + 1)
+		.into_iter()
+		.filter_map(|entry| entry.ok())
+		.filter(|entry| entry.file_type().is_file())
+		.filter(|entry| glob_matches(pattern, &entry.file_name().to_string_lossy()))
+		.map(|entry| entry.into_path())
+		.filter(|path| is_image(path))
+		.take(MAX_RESULTS)
+		.collect();
+	found.sort();
+	found
+}
+
+/// A grid of thumbnails, one for each path, in rows from left to right
+pub struct ThumbnailGrid {
+	pub paths: Vec<PathBuf>,
+	pub columns: usize,
+	sheet: RgbaImage,
+}
+
+impl ThumbnailGrid {
+	/// Decodes and shrinks every image. The cells of the images that can't be decoded stay
+	/// empty.
+	pub fn new(paths: Vec<PathBuf>) -> ThumbnailGrid {
+		let columns = ((paths.len() as f32).sqrt().ceil() as usize).clamp(1, MAX_COLUMNS);
+		let rows = paths.len().div_ceil(columns).max(1);
+		let stride = CELL_SIZE + CELL_GAP;
+		let width = columns as u32 * stride + CELL_GAP;
+		let height = rows as u32 * stride + CELL_GAP;
+		let mut sheet = RgbaImage::from_pixel(width, height, BACKGROUND);
+		for (i, path) in paths.iter().enumerate() {
+			let thumbnail = match image::open(path) {
+				Ok(image) => image.thumbnail(CELL_SIZE, CELL_SIZE).into_rgba8(),
+				Err(_) => continue,
+			};
+			let (cell_x, cell_y) = Self::cell_origin(i, columns);
+			// Centers the thumbnail in its cell
+			let x = cell_x + (CELL_SIZE - thumbnail.width()) / 2;
+			let y = cell_y + (CELL_SIZE - thumbnail.height()) / 2;
+			imageops::overlay(&mut sheet, &thumbnail, x as i64, y as i64);
+		}
+		ThumbnailGrid { paths, columns, sheet }
+	}
+
+	fn cell_origin(index: usize, columns: usize) -> (u32, u32) {
+		let stride = CELL_SIZE + CELL_GAP;
+		let col = (index % columns) as u32;
+		let row = (index / columns) as u32;
+		(CELL_GAP + col * stride, CELL_GAP + row * stride)
+	}
+
+	/// The grid with a frame around the thumbnail at `selected`
+	pub fn render(&self, selected: usize) -> RgbaImage {
+		let mut image = self.sheet.clone();
+		let (x, y) = Self::cell_origin(selected, self.columns);
+		let outer = CELL_SIZE + 2 * SELECTION_WIDTH;
+		let (left, top) = (x.saturating_sub(SELECTION_WIDTH), y.saturating_sub(SELECTION_WIDTH));
+		for dy in 0..outer {
+			for dx in 0..outer {
+				let on_edge = dx < SELECTION_WIDTH
+					|| dy < SELECTION_WIDTH
+					|| dx >= outer - SELECTION_WIDTH
+					|| dy >= outer - SELECTION_WIDTH;
+				let (px, py) = (left + dx, top + dy);
+				if on_edge && px < image.width() && py < image.height() {
+					image.put_pixel(px, py, SELECTION_COLOR);
+				}
+			}
+		}
+		image
+	}
+
+	/// The index of the thumbnail that's `(dx, dy)` cells away from `selected`, staying within
+	/// the grid
+	pub fn moved_selection(&self, selected: usize, dx: isize, dy: isize) -> usize {
+		let target = selected as isize + dx + dy * self.columns as isize;
+		target.clamp(0, self.paths.len().saturating_sub(1) as isize) as usize
+	}
+}
@@ -0,0 +1,57 @@
+//! Reads where a photo was taken from the GPS fields of its EXIF data.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// How far the map is zoomed in on the location, 14 shows the streets of a town
+const MAP_ZOOM: u32 = 14;
+
+/// The latitude and the longitude of the image in decimal degrees, or `None` if the EXIF data
+/// doesn't have both
+pub fn read_coordinates(path: &Path) -> Option<(f64, f64)> {
+	let file = File::open(path).ok()?;
+	let exif = exif::Reader::new().read_from_container(&mut BufReader::new(file)).ok()?;
+	let coordinate = |value_tag, ref_tag| {
+		let value = exif.get_field(value_tag, exif::In::PRIMARY)?;
+		let reference = exif.get_field(ref_tag, exif::In::PRIMARY)?;
+		let reference = match &reference.value {
+			exif::Value::Ascii(values) => *values.first()?.first()? as char,
+			_ => return None,
+		};
+		match &value.value {
+			exif::Value::Rational(dms) if dms.len() >= 3 => {
+				let (degrees, minutes, seconds) =
+					(dms[0].to_f64(), dms[1].to_f64(), dms[2].to_f64());
+				if !(degrees.is_finite() && minutes.is_finite() && seconds.is_finite()) {
+					return None;
+				}
+				// Fractional degrees and minutes are moved into the seconds
+				let seconds = seconds + minutes.fract() * 60.0 + degrees.fract() * 3600.0;
+				Some(dms_to_decimal(degrees as u32, minutes as u32, seconds, reference))
+			}
+			_ => None,
+		}
+	};
+	let latitude = coordinate(exif::Tag::GPSLatitude, exif::Tag::GPSLatitudeRef)?;
+	let longitude = coordinate(exif::Tag::GPSLongitude, exif::Tag::GPSLongitudeRef)?;
+	Some((latitude, longitude))
+}
+
+/// Converts degrees, minutes and seconds to decimal degrees. The south and west references
+/// make the result negative.
+pub fn dms_to_decimal(degrees: u32, minutes: u32, seconds: f64, reference: char) -> f64 {
+	let decimal = degrees as f64 + minutes as f64 / 60.0 + seconds / 3600.0;
+	match reference.to_ascii_uppercase() {
+		'S' | 'W' => -decimal,
+		_ => decimal,
+	}
+}
+
+/// The OpenStreetMap page with a marker at the location
+pub fn map_url(latitude: f64, longitude: f64) -> String {
+	format!(
+		"https://www.openstreetmap.org/?mlat={:.6}&mlon={:.6}&zoom={}",
+		latitude, longitude, MAP_ZOOM
+	)
+}
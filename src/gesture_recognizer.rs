@@ -0,0 +1,115 @@
+//! Turns the touches of a touchscreen into panning, zooming and swiping.
+
+use std::collections::BTreeMap;
+
+use gelatin::{cgmath::InnerSpace, misc::LogicalVector, winit::event::TouchPhase};
+
+/// A single finger has to move at least this far sideways to count as a swipe
+const SWIPE_MIN_DISTANCE: f32 = 50.0;
+/// A swipe that moves more than this vertically is not a swipe
+const SWIPE_MAX_VERTICAL: f32 = 20.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gesture {
+	/// Two fingers moved. `pan` is how far their midpoint moved, `zoom` is the ratio of the new
+	/// distance between them to the old one and `anchor` is their new midpoint.
+	PanZoom { pan: LogicalVector, zoom: f32, anchor: LogicalVector },
+	/// A single finger moved to the left and was lifted
+	SwipeLeft,
+	/// A single finger moved to the right and was lifted
+	SwipeRight,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TouchPoint {
+	start: LogicalVector,
+	pos: LogicalVector,
+}
+
+/// Tracks the fingers that touch the screen
+#[derive(Default)]
+pub struct GestureRecognizer {
+	touches: BTreeMap<u64, TouchPoint>,
+	/// More than one finger touched the screen since the last time that none did. Lifting the
+	/// last one of them isn't a swipe then.
+	multi_touch: bool,
+}
+
+impl GestureRecognizer {
+	pub fn new() -> GestureRecognizer {
+		Default::default()
+	}
+
+	/// Processes a touch event and returns the gesture that it completes, if any
+	pub fn touch(&mut self, id: u64, phase: TouchPhase, pos: LogicalVector) -> Option<Gesture> {
+		match phase {
+			TouchPhase::Started => {
+				self.touches.insert(id, TouchPoint { start: pos, pos });
+				if self.touches.len() > 1 {
+					self.multi_touch = true;
+				}
+				None
+			}
+			TouchPhase::Moved => {
+				let before = self.two_touches();
+				if let Some(touch) = self.touches.get_mut(&id) {
+					touch.pos = pos;
+				}
+				match (before, self.two_touches()) {
+					(Some(before), Some(after)) => Some(pan_zoom(before, after)),
+					_ => None,
+				}
+			}
+			TouchPhase::Ended => {
+				let touch = self.touches.remove(&id)?;
+				let multi_touch = self.multi_touch;
+				if self.touches.is_empty() {
+					self.multi_touch = false;
+				}
+				if multi_touch {
+					return None;
+				}
+				let moved = pos - touch.start;
+				if moved.vec.x.abs() > SWIPE_MIN_DISTANCE && moved.vec.y.abs() < SWIPE_MAX_VERTICAL
+				{
+					Some(if moved.vec.x < 0.0 { Gesture::SwipeLeft } else { Gesture::SwipeRight })
+				} else {
+					None
+				}
+			}
+			TouchPhase::Cancelled => {
+				self.touches.remove(&id);
+				if self.touches.is_empty() {
+					self.multi_touch = false;
+				}
+				None
+			}
+		}
+	}
+
+	/// The positions of the fingers if exactly two touch the screen
+	fn two_touches(&self) -> Option<(LogicalVector, LogicalVector)> {
+		if self.touches.len() != 2 {
+			return None;
+		}
+		let mut positions = self.touches.values().map(|touch| touch.pos);
+		Some((positions.next()?, positions.next()?))
+	}
+}
+
+fn pan_zoom(
+	before: (LogicalVector, LogicalVector),
+	after: (LogicalVector, LogicalVector),
+) -> Gesture {
+	let midpoint = |(a, b): (LogicalVector, LogicalVector)| (a + b) * 0.5;
+	let distance = |(a, b): (LogicalVector, LogicalVector)| (b - a).vec.magnitude();
+	let (before_distance, after_distance) = (distance(before), distance(after));
+	// Fingers on top of each other can't tell how much to zoom
+	let zoom = if before_distance > 1.0 && after_distance > 1.0 {
+		after_distance / before_distance
+	} else {
+		1.0
+	};
+	let anchor = midpoint(after);
+	Gesture::PanZoom { pan: anchor - midpoint(before), zoom, anchor }
+}
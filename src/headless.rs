@@ -0,0 +1,574 @@
+//! Command line modes that process a file and exit without opening a window.
+
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use color_quant::NeuQuant;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use rayon::prelude::*;
+use serde_json::{Map, Value as JsonValue};
+
+use gelatin::image::{DynamicImage, ImageError, ImageFormat, RgbaImage};
+
+use crate::cmd_line::Args;
+use crate::default_viewer;
+use crate::image_cache::{
+	farbfeld,
+	image_loader::{
+		complex_load_image, is_file_supported, ImageLoader, ImageLoaderError, LoadRequest,
+		LoadResult, NON_EXISTENT_REQUEST_ID, PRIORITY_REQUEST_ID,
+	},
+	perceptual_hash::{PerceptualHash, DUPLICATE_DISTANCE},
+};
+
+/// The memory usage of `--stress-test` is sampled this many times, once after each batch
+const STRESS_TEST_BATCHES: u32 = 10;
+/// The memory may grow by this factor after the first batch before it counts as a leak, to
+/// leave room for the allocator keeping freed pages around
+const STRESS_TEST_GROWTH_LIMIT: f64 = 1.1;
+const STRESS_TEST_POLL_INTERVAL: Duration = Duration::from_millis(1);
+/// A batch is given up on when none of its results arrived for this long, and its requests
+/// that haven't ended count as unanswered
+const STRESS_TEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The pixel formats that `--pipe-out` writes to the standard output
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum PipeFormat {
+	/// The width and the height as ASCII lines, then the RGBA bytes row by row
+	#[default]
+	Raw,
+	/// Binary PPM, without the alpha channel
+	Ppm,
+	/// Binary PGM with the luma of the image
+	Pgm,
+}
+
+impl FromStr for PipeFormat {
+	type Err = String;
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"raw" => Ok(PipeFormat::Raw),
+			"ppm" => Ok(PipeFormat::Ppm),
+			"pgm" => Ok(PipeFormat::Pgm),
+			other => Err(format!("Unknown pipe format {:?}", other)),
+		}
+	}
+}
+
+/// Runs the headless mode selected by the arguments.
+///
+/// Returns the exit code if a headless mode was selected, and `None` if the
+/// viewer should start normally.
+pub fn run(args: &Args) -> Option<i32> {
+	let result = if let Some(frame_index) = args.export_frame {
+		// The input path is required by the argument parser when exporting a frame
+		let input = args.file_path.as_ref()?;
+		match args.output_path.as_ref() {
+			Some(output) => export_frame(Path::new(input), frame_index, Path::new(output)),
+			None => Err("--export-frame requires an output path".into()),
+		}
+	} else if args.convert {
+		let input = args.file_path.as_ref()?;
+		match args.output_path.as_ref() {
+			Some(output) => convert(Path::new(input), Path::new(output), args.auto_orient),
+			None => Err("--convert requires an output path".into()),
+		}
+	} else if args.pipe_out {
+		let input = args.file_path.as_ref()?;
+		pipe_out(Path::new(input), args.pipe_format)
+	} else if args.find_duplicates {
+		let input = args.file_path.as_ref()?;
+		find_duplicates(Path::new(input), args.output_path.as_ref().map(Path::new))
+	} else if let Some((folder, count)) = args.stress_test.as_ref() {
+		stress_test(Path::new(folder), *count, args.seed)
+	} else if args.xdg_open {
+		default_viewer::register_default_viewer()
+	} else if args.dump_exif {
+		let input = args.file_path.as_ref()?;
+		let output = args.output_path.as_ref().map(Path::new);
+		if args.exif_with_stats {
+			dump_exif_with_stats(Path::new(input), output)
+		} else {
+			dump_exif(Path::new(input), output)
+		}
+	} else {
+		return None;
+	};
+	match result {
+		Ok(()) => Some(0),
+		Err(e) => {
+			eprintln!("{}", e);
+			Some(1)
+		}
+	}
+}
+
+/// Decodes the animation until the frame at `frame_index` and saves that frame to `output`.
+/// The format of the output is determined by its file extension.
+pub fn export_frame(input: &Path, frame_index: u32, output: &Path) -> Result<(), String> {
+	let mut frame_count = 0;
+	let mut frame = None;
+	let result = complex_load_image(input, true, 0, |load_result| {
+		if let LoadResult::Frame { image, .. } = load_result {
+			if frame_count == frame_index {
+				frame = Some(image);
+				// Stop decoding the rest of the animation
+				return Err(ImageLoaderError { description: "".into() });
+			}
+			frame_count += 1;
+		}
+		Ok(())
+	});
+	let frame = match (frame, result) {
+		(Some(frame), _) => frame,
+		(None, Err(e)) => return Err(format!("Could not load {:?}: {}", input, e)),
+		(None, Ok(())) => {
+			return Err(format!(
+				"Can't export frame {} because {:?} only has {} frame(s)",
+				frame_index, input, frame_count
+			))
+		}
+	};
+	save_image(frame, output)
+}
+
+/// Loads the image at `input` and saves it to `output`. The format of the output is determined by
+/// its file extension. Only the first frame of an animation is converted.
+///
+/// If `auto_orient` is set, the pixels are transformed according to the EXIF orientation of the
+/// input, because most output formats can't store it.
+pub fn convert(input: &Path, output: &Path, auto_orient: bool) -> Result<(), String> {
+	if !is_file_supported(input) {
+		return Err(format!("The format of {:?} is not supported", input));
+	}
+	ImageFormat::from_path(output)
+		.map_err(|_| format!("Can't determine the output format from {:?}", output))?;
+
+	let mut converted = None;
+	complex_load_image(input, false, 0, |load_result| {
+		if let LoadResult::Frame { image, orientation, .. } = load_result {
+			converted = Some(if auto_orient { orientation.apply(image) } else { image });
+		}
+		Ok(())
+	})
+	.map_err(|e| format!("Could not load {:?}: {}", input, e))?;
+	match converted {
+		Some(image) => save_image(image, output),
+		None => Err(format!("{:?} doesn't contain an image", input)),
+	}
+}
+
+/// Decodes the image at `input` and writes its pixels to the standard output in `format`. Only
+/// the first frame of an animation is written.
+pub fn pipe_out(input: &Path, format: PipeFormat) -> Result<(), String> {
+	if !is_file_supported(input) {
+		return Err(format!("The format of {:?} is not supported", input));
+	}
+	let mut decoded = None;
+	complex_load_image(input, false, 0, |load_result| {
+		if let LoadResult::Frame { image, .. } = load_result {
+			decoded = Some(image);
+			// Stop decoding the rest of the animation
+			return Err(ImageLoaderError { description: "".into() });
+		}
+		Ok(())
+	})
+	.or_else(|e| if decoded.is_some() { Ok(()) } else { Err(e) })
+	.map_err(|e| format!("Could not load {:?}: {}", input, e))?;
+	let image = decoded.ok_or_else(|| format!("{:?} doesn't contain an image", input))?;
+
+	let mut stdout = BufWriter::new(io::stdout().lock());
+	write_pixels(&image, format, &mut stdout)
+		.and_then(|()| stdout.flush())
+		.map_err(|e| format!("Could not write the pixels: {}", e))
+}
+
+/// Writes the header of `format` followed by the pixels of the image
+pub fn write_pixels<W: Write>(
+	image: &RgbaImage,
+	format: PipeFormat,
+	out: &mut W,
+) -> io::Result<()> {
+	let (width, height) = image.dimensions();
+	match format {
+		PipeFormat::Raw => {
+			write!(out, "{}\n{}\n", width, height)?;
+			out.write_all(image.as_raw())
+		}
+		PipeFormat::Ppm => {
+			write!(out, "P6\n{} {}\n255\n", width, height)?;
+			let rgb: Vec<u8> = image.pixels().flat_map(|p| [p[0], p[1], p[2]]).collect();
+			out.write_all(&rgb)
+		}
+		PipeFormat::Pgm => {
+			write!(out, "P5\n{} {}\n255\n", width, height)?;
+			let luma = DynamicImage::ImageRgba8(image.clone()).into_luma8();
+			out.write_all(luma.as_raw())
+		}
+	}
+}
+
+/// Saves the image using the format that belongs to the file extension of `output`. The alpha
+/// channel is dropped if the format can't store it.
+fn save_image(image: RgbaImage, output: &Path) -> Result<(), String> {
+	match ImageFormat::from_path(output) {
+		Ok(ImageFormat::Gif) => return save_gif(&image, output),
+		Ok(ImageFormat::Farbfeld) => {
+			return std::fs::write(output, farbfeld::encode_farbfeld(&image))
+				.map_err(|e| format!("Could not save the image to {:?}: {}", output, e))
+		}
+		_ => (),
+	}
+	let image = DynamicImage::ImageRgba8(image);
+	let result = match image.save(output) {
+		Err(ImageError::Unsupported(_)) => image.to_rgb8().save(output),
+		result => result,
+	};
+	result.map_err(|e| format!("Could not save the image to {:?}: {}", output, e))
+}
+
+/// Reduces the colors of the image to at most `palette_size` using the NeuQuant algorithm.
+///
+/// Returns the palette index of every pixel in row-major order, and the palette.
+pub fn quantize_rgba_to_palette(img: &RgbaImage, palette_size: u8) -> (Vec<u8>, Vec<[u8; 3]>) {
+	const SAMPLE_FACTOR: i32 = 10;
+	let quantizer = NeuQuant::new(SAMPLE_FACTOR, palette_size as usize, img.as_raw());
+	let palette = quantizer.color_map_rgb().chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect();
+	let indices = img.pixels().map(|p| quantizer.index_of(&p.0) as u8).collect();
+	(indices, palette)
+}
+
+/// Saves the image as a GIF with a palette of 255 colors. Pixels that are more than half
+/// transparent use an additional, transparent palette entry.
+fn save_gif(image: &RgbaImage, output: &Path) -> Result<(), String> {
+	const ALPHA_THRESHOLD: u8 = 128;
+	let (w, h) = image.dimensions();
+	let too_large = || format!("The image is too large to be saved as a GIF ({}x{})", w, h);
+	let w = u16::try_from(w).map_err(|_| too_large())?;
+	let h = u16::try_from(h).map_err(|_| too_large())?;
+
+	let (mut indices, mut palette) = quantize_rgba_to_palette(image, u8::MAX);
+	let mut transparent_index = None;
+	if image.pixels().any(|p| p[3] < ALPHA_THRESHOLD) {
+		let index = palette.len() as u8;
+		palette.push([0, 0, 0]);
+		for (pixel_index, pixel) in indices.iter_mut().zip(image.pixels()) {
+			if pixel[3] < ALPHA_THRESHOLD {
+				*pixel_index = index;
+			}
+		}
+		transparent_index = Some(index);
+	}
+
+	let save_error =
+		|e: &dyn std::fmt::Display| format!("Could not save the image to {:?}: {}", output, e);
+	let file = File::create(output).map_err(|e| save_error(&e))?;
+	let flat_palette = palette.concat();
+	let mut encoder =
+		gif::Encoder::new(BufWriter::new(file), w, h, &flat_palette).map_err(|e| save_error(&e))?;
+	let frame = gif::Frame::from_indexed_pixels(w, h, indices, transparent_index);
+	encoder.write_frame(&frame).map_err(|e| save_error(&e))
+}
+
+/// Reads the EXIF fields of `input` and writes them as a JSON object to `output`, or to the
+/// standard output if no output is given.
+///
+/// Fields of the primary image are keyed by their tag name. Fields of the thumbnail are placed in
+/// a nested object under the "Thumbnail" key.
+pub fn dump_exif(input: &Path, output: Option<&Path>) -> Result<(), String> {
+	let exif = read_exif(input)?;
+	write_json(&JsonValue::Object(exif_to_json(&exif)), output)
+}
+
+/// Writes a line with the distance and the two paths for every pair of images in the folder
+/// whose perceptual hashes differ in at most `DUPLICATE_DISTANCE` bits, closest first. If
+/// `input` is a file, its folder is searched.
+pub fn find_duplicates(input: &Path, output: Option<&Path>) -> Result<(), String> {
+	let folder = if input.is_dir() { input } else { input.parent().unwrap_or(Path::new(".")) };
+	let paths = supported_files_in(folder)?;
+
+	let mut loader = background_loader();
+	loader.enable_perceptual_hashes();
+	for (req_id, path) in paths.iter().enumerate() {
+		loader.send_load_request(LoadRequest {
+			req_id: req_id as u64,
+			path: path.clone(),
+			batch_id: None,
+		});
+	}
+	// Only the hashes are needed, the frames are dropped as soon as they arrive
+	loader.by_ref().for_each(drop);
+	let hashes: Vec<(&PathBuf, u64)> =
+		paths.iter().filter_map(|path| Some((path, loader.perceptual_hash(path)?))).collect();
+
+	let mut pairs = Vec::new();
+	for (i, &(first, first_hash)) in hashes.iter().enumerate() {
+		for &(second, second_hash) in &hashes[i + 1..] {
+			let distance = PerceptualHash::distance(first_hash, second_hash);
+			if distance <= DUPLICATE_DISTANCE {
+				pairs.push((distance, first, second));
+			}
+		}
+	}
+	pairs.sort_by_key(|&(distance, ..)| distance);
+
+	let mut report = String::new();
+	for (distance, first, second) in pairs {
+		report.push_str(&format!("{}\t{}\t{}\n", distance, first.display(), second.display()));
+	}
+	match output {
+		Some(output) => {
+			fs::write(output, report).map_err(|e| format!("Could not write {:?}: {}", output, e))
+		}
+		None => io::stdout().write_all(report.as_bytes()).map_err(|e| e.to_string()),
+	}
+}
+
+/// Loads `count` images picked at random from `folder`, in batches, and measures the resident
+/// memory of the process after each batch. Fails if the memory grew after every batch, which
+/// hints at a leak, or if the loader sent a result for a request that was never made.
+pub fn stress_test(folder: &Path, count: u32, seed: Option<u64>) -> Result<(), String> {
+	let paths = supported_files_in(folder)?;
+	if paths.is_empty() {
+		return Err(format!("There are no supported images in {:?}", folder));
+	}
+	let mut rng = match seed {
+		Some(seed) => StdRng::seed_from_u64(seed),
+		None => StdRng::from_entropy(),
+	};
+	let mut loader = background_loader();
+	let batch_size = (count / STRESS_TEST_BATCHES).max(1);
+	let mut finished = HashSet::new();
+	let mut phantom_results = 0;
+	let mut failed = 0;
+	let mut samples = vec![resident_memory()];
+	let mut submitted = 0;
+	while submitted < count {
+		let batch_len = batch_size.min(count - submitted);
+		let batch_paths = (0..batch_len).map(|_| paths.choose(&mut rng).unwrap().clone());
+		let batch_id = loader.send_batch_request(batch_paths.collect());
+		submitted += batch_len;
+		let mut finished_in_batch = 0;
+		let mut last_result = Instant::now();
+		while finished_in_batch < batch_len && last_result.elapsed() < STRESS_TEST_TIMEOUT {
+			let results = loader.drain_batch_results(batch_id);
+			if results.is_empty() {
+				std::thread::sleep(STRESS_TEST_POLL_INTERVAL);
+			} else {
+				last_result = Instant::now();
+			}
+			for result in results {
+				let ended = match result {
+					LoadResult::Failed { .. } => {
+						failed += 1;
+						true
+					}
+					LoadResult::Done { .. } | LoadResult::Aborted { .. } => true,
+					_ => false,
+				};
+				if ended && finished.insert(result.req_id()) {
+					finished_in_batch += 1;
+				}
+			}
+		}
+		// Only the batches were requested, so anything else is a phantom
+		while loader.try_recv_prefetched().is_ok() {
+			phantom_results += 1;
+		}
+		samples.push(resident_memory());
+	}
+
+	println!("Loaded {} images from {} files, {} failed", count, paths.len(), failed);
+	let unanswered = count as usize - finished.len();
+	if unanswered > 0 {
+		println!("{} requests were never answered", unanswered);
+	}
+	let leaking = match samples.iter().copied().collect::<Option<Vec<u64>>>() {
+		Some(samples) => {
+			let megabytes: Vec<String> =
+				samples.iter().map(|&bytes| format!("{:.1}", bytes as f64 / 1e6)).collect();
+			println!("Resident memory after each batch (MB): {}", megabytes.join(" "));
+			// The first batch fills the caches and the thread stacks, so it's not counted
+			let after_warmup = &samples[1.min(samples.len() - 1)..];
+			let grew_every_time = after_warmup.len() > 2
+				&& after_warmup.windows(2).all(|pair| pair[1] > pair[0])
+				&& *after_warmup.last().unwrap() as f64
+					> after_warmup[0] as f64 * STRESS_TEST_GROWTH_LIMIT;
+			if grew_every_time {
+				println!("The memory usage grew after every batch");
+			} else {
+				println!("The memory usage stabilized");
+			}
+			grew_every_time
+		}
+		None => {
+			println!("The memory usage can't be measured on this platform");
+			false
+		}
+	};
+	if phantom_results > 0 {
+		println!("Received {} results for requests that were never made", phantom_results);
+	}
+	if leaking || phantom_results > 0 || unanswered > 0 {
+		Err("The stress test failed".into())
+	} else {
+		Ok(())
+	}
+}
+
+/// The resident set size of this process in bytes, if the platform tells it
+#[cfg(target_os = "linux")]
+fn resident_memory() -> Option<u64> {
+	let status = fs::read_to_string("/proc/self/status").ok()?;
+	let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+	let kilobytes = line["VmRSS:".len()..].trim().trim_end_matches("kB").trim();
+	kilobytes.parse::<u64>().ok().map(|kilobytes| kilobytes * 1024)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn resident_memory() -> Option<u64> {
+	None
+}
+
+/// The supported images directly in `folder`, sorted by path
+fn supported_files_in(folder: &Path) -> Result<Vec<PathBuf>, String> {
+	let entries =
+		fs::read_dir(folder).map_err(|e| format!("Could not read {:?}: {}", folder, e))?;
+	let mut paths: Vec<PathBuf> = entries
+		.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+		.filter(|path| path.is_file() && is_file_supported(path))
+		.collect();
+	paths.sort();
+	Ok(paths)
+}
+
+/// A loader for processing many files, with a thread for each core
+fn background_loader() -> ImageLoader {
+	let threads = std::thread::available_parallelism().map_or(1, |n| n.get() as u32);
+	let loader = ImageLoader::new(threads, threads as usize * 2);
+	// None of the files is more urgent than the others
+	PRIORITY_REQUEST_ID.store(NON_EXISTENT_REQUEST_ID, Ordering::SeqCst);
+	loader
+}
+
+/// Like `dump_exif`, but writes `{ "exif": ..., "stats": ... }` where the stats are computed
+/// from the pixels of the first frame. Images without EXIF data get `"exif": null`.
+pub fn dump_exif_with_stats(input: &Path, output: Option<&Path>) -> Result<(), String> {
+	if !is_file_supported(input) {
+		return Err(format!("The format of {:?} is not supported", input));
+	}
+	let exif = match read_exif(input) {
+		Ok(exif) => JsonValue::Object(exif_to_json(&exif)),
+		Err(_) => JsonValue::Null,
+	};
+	let mut first_frame = None;
+	complex_load_image(input, false, 0, |load_result| {
+		if let LoadResult::Frame { image, .. } = load_result {
+			first_frame.get_or_insert(image);
+		}
+		Ok(())
+	})
+	.map_err(|e| format!("Could not load {:?}: {}", input, e))?;
+	let image = first_frame.ok_or_else(|| format!("{:?} doesn't contain an image", input))?;
+
+	let mut json = Map::new();
+	json.insert("exif".into(), exif);
+	json.insert("stats".into(), image_stats(&image));
+	write_json(&JsonValue::Object(json), output)
+}
+
+/// Returns the size, the mean of each channel and the mean Rec. 709 luminance of the image.
+/// The means are between 0 and 1. The channels are summed in parallel.
+pub fn image_stats(image: &RgbaImage) -> JsonValue {
+	const PIXELS_PER_CHUNK: usize = 64 * 1024;
+	let sums = image
+		.as_raw()
+		.par_chunks(PIXELS_PER_CHUNK * 4)
+		.map(|chunk| {
+			let mut sums = [0u64; 4];
+			for pixel in chunk.chunks_exact(4) {
+				for (sum, &value) in sums.iter_mut().zip(pixel) {
+					*sum += value as u64;
+				}
+			}
+			sums
+		})
+		.reduce(|| [0; 4], |a, b| [a[0] + b[0], a[1] + b[1], a[2] + b[2], a[3] + b[3]]);
+
+	let pixel_count = (image.width() as u64 * image.height() as u64).max(1);
+	let means = sums.map(|sum| sum as f64 / pixel_count as f64 / 255.0);
+	let mean_luminance = 0.2126 * means[0] + 0.7152 * means[1] + 0.0722 * means[2];
+	serde_json::json!({
+		"mean_luminance": mean_luminance,
+		"channel_means": means,
+		"width": image.width(),
+		"height": image.height(),
+		"channels": 4,
+	})
+}
+
+fn read_exif(input: &Path) -> Result<exif::Exif, String> {
+	let file = File::open(input).map_err(|e| format!("Could not open {:?}: {}", input, e))?;
+	exif::Reader::new()
+		.read_from_container(&mut BufReader::new(file))
+		.map_err(|e| format!("Could not read the EXIF data of {:?}: {}", input, e))
+}
+
+/// Stores the primary fields at the top level and the fields of the thumbnail under `Thumbnail`
+fn exif_to_json(exif: &exif::Exif) -> Map<String, JsonValue> {
+	let mut primary = Map::new();
+	let mut thumbnail = Map::new();
+	for field in exif.fields() {
+		let fields = if field.ifd_num == exif::In::PRIMARY { &mut primary } else { &mut thumbnail };
+		fields.insert(field.tag.to_string(), exif_value_to_json(&field.value));
+	}
+	if !thumbnail.is_empty() {
+		primary.insert("Thumbnail".into(), JsonValue::Object(thumbnail));
+	}
+	primary
+}
+
+fn write_json(json: &JsonValue, output: Option<&Path>) -> Result<(), String> {
+	let json = serde_json::to_string_pretty(json).map_err(|e| e.to_string())?;
+	let write_result = match output {
+		Some(output) => std::fs::write(output, json + "\n"),
+		None => writeln!(io::stdout(), "{}", json),
+	};
+	write_result.map_err(|e| format!("Could not write the EXIF data: {}", e))
+}
+
+/// Converts the value of an EXIF field to JSON. Fields holding a single number are stored as a
+/// plain number, others as an array.
+fn exif_value_to_json(value: &exif::Value) -> JsonValue {
+	fn numbers<T: Copy + Into<JsonValue>>(values: &[T]) -> JsonValue {
+		match values {
+			[single] => (*single).into(),
+			values => values.iter().map(|v| (*v).into()).collect(),
+		}
+	}
+	let ascii = |bytes: &Vec<u8>| JsonValue::String(String::from_utf8_lossy(bytes).into_owned());
+	match value {
+		exif::Value::Byte(v) => numbers(v),
+		exif::Value::Ascii(strings) => match &strings[..] {
+			[single] => ascii(single),
+			strings => strings.iter().map(ascii).collect(),
+		},
+		exif::Value::Short(v) => numbers(v),
+		exif::Value::Long(v) => numbers(v),
+		exif::Value::Rational(v) => numbers(&v.iter().map(|r| r.to_f64()).collect::<Vec<_>>()),
+		exif::Value::SByte(v) => numbers(v),
+		exif::Value::Undefined(bytes, _) => bytes.iter().map(|&b| JsonValue::from(b)).collect(),
+		exif::Value::SShort(v) => numbers(v),
+		exif::Value::SLong(v) => numbers(v),
+		exif::Value::SRational(v) => numbers(&v.iter().map(|r| r.to_f64()).collect::<Vec<_>>()),
+		exif::Value::Float(v) => numbers(v),
+		exif::Value::Double(v) => numbers(v),
+		exif::Value::Unknown(..) => JsonValue::Null,
+	}
+}
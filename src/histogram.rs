@@ -0,0 +1,65 @@
+//! The histograms of the color channels and of the luminance of an image, see `histogram`.
+
+use gelatin::image::RgbaImage;
+use rayon::prelude::*;
+
+pub const BIN_COUNT: usize = 256;
+/// The names of the histograms, in the order of `Histogram::bins`
+pub const HISTOGRAM_NAMES: [&str; 4] = ["R", "G", "B", "L"];
+
+/// The number of pixels with each of the 256 stored values, for the R, G and B channels and
+/// for the luminance
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+	pub bins: [[u32; BIN_COUNT]; 4],
+}
+
+impl Histogram {
+	const EMPTY: Histogram = Histogram { bins: [[0; BIN_COUNT]; 4] };
+
+	/// The largest count of any bin of any of the histograms
+	pub fn max_count(&self) -> u32 {
+		self.bins.iter().flatten().copied().max().unwrap_or(0)
+	}
+
+	fn merge(mut self, other: Histogram) -> Histogram {
+		for (bins, other_bins) in self.bins.iter_mut().zip(&other.bins) {
+			for (count, other_count) in bins.iter_mut().zip(other_bins) {
+				*count += other_count;
+			}
+		}
+		self
+	}
+}
+
+/// The Rec. 709 luminance of the stored values, without linearizing them first. The weights
+/// add up to 256.
+pub fn luminance(r: u8, g: u8, b: u8) -> u8 {
+	((54 * r as u32 + 183 * g as u32 + 19 * b as u32) >> 8) as u8
+}
+
+/// Counts the values of every pixel, the rows are counted in parallel. The alpha channel is
+/// ignored.
+pub fn histogram(image: &RgbaImage) -> Histogram {
+	let width = image.width() as usize;
+	if width == 0 {
+		return Histogram::EMPTY;
+	}
+	image
+		.as_raw()
+		.par_chunks(width * 4)
+		.fold(
+			|| Histogram::EMPTY,
+			|mut histogram, row| {
+				for pixel in row.chunks_exact(4) {
+					let (r, g, b) = (pixel[0], pixel[1], pixel[2]);
+					histogram.bins[0][r as usize] += 1;
+					histogram.bins[1][g as usize] += 1;
+					histogram.bins[2][b as usize] += 1;
+					histogram.bins[3][luminance(r, g, b) as usize] += 1;
+				}
+				histogram
+			},
+		)
+		.reduce(|| Histogram::EMPTY, Histogram::merge)
+}
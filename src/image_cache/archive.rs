@@ -0,0 +1,266 @@
+//! Reading of comic book archives and OpenRaster files.
+//!
+//! A CBZ file is a ZIP archive of images where the pages are ordered by their names. An
+//! OpenRaster file is a ZIP archive of the layers of a drawing, with the flattened drawing in
+//! `mergedimage.png`. Only the parts of the ZIP format that these archives use are supported:
+//! entries that are stored or compressed with deflate, without encryption and without the
+//! ZIP64 extensions.
+
+use std::convert::TryInto;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use flate2::read::DeflateDecoder;
+
+use super::image_loader::{is_file_supported, ImageLoaderError, Result};
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x04034b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIR_SIGNATURE: u32 = 0x06054b50;
+const END_OF_CENTRAL_DIR_LEN: usize = 22;
+/// The end of central directory record may be followed by a comment of at most this length
+const MAX_COMMENT_LEN: usize = u16::MAX as usize;
+const LOCAL_HEADER_LEN: usize = 30;
+const CENTRAL_HEADER_LEN: usize = 46;
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATE: u16 = 8;
+
+/// The first entry of an OpenRaster file is the stored `mimetype` file with this content
+const OPENRASTER_MIMETYPE: &[u8] = b"mimetypeimage/openraster";
+const OPENRASTER_MERGED_IMAGE: &str = "mergedimage.png";
+
+/// Distinguishes the folders of the books that were opened by this process
+static NEXT_BOOK_ID: AtomicU32 = AtomicU32::new(0);
+
+/// Returns true if the file at `path` should be opened as a comic book
+pub fn is_comic_book(path: &Path) -> bool {
+	let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+	extension.as_deref() == Some("cbz")
+}
+
+/// Returns true if the file at `path` should be opened as an OpenRaster drawing
+pub fn is_openraster(path: &Path) -> bool {
+	let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+	extension.as_deref() == Some("ora")
+}
+
+/// Returns true if the file starts like an OpenRaster file, whatever its file ending is
+pub fn is_openraster_data(file_start: &[u8]) -> bool {
+	let name_start = LOCAL_HEADER_LEN;
+	read_u32(file_start, 0).ok() == Some(LOCAL_HEADER_SIGNATURE)
+		&& file_start.get(name_start..name_start + OPENRASTER_MIMETYPE.len())
+			== Some(OPENRASTER_MIMETYPE)
+}
+
+fn archive_error(description: &str) -> ImageLoaderError {
+	ImageLoaderError { description: format!("Invalid archive: {}", description).into() }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+	let bytes = data.get(offset..offset + 2).ok_or_else(|| archive_error("unexpected end"))?;
+	Ok(u16::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+	let bytes = data.get(offset..offset + 4).ok_or_else(|| archive_error("unexpected end"))?;
+	Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+struct ZipEntry {
+	name: String,
+	method: u16,
+	compressed_size: usize,
+	uncompressed_size: usize,
+	local_header_offset: usize,
+}
+
+/// The contents of a ZIP archive, read into memory as a whole
+pub struct ZipArchive {
+	data: Vec<u8>,
+	entries: Vec<ZipEntry>,
+}
+
+impl ZipArchive {
+	pub fn open(path: &Path) -> Result<ZipArchive> {
+		ZipArchive::from_data(fs::read(path)?)
+	}
+
+	pub fn from_data(data: Vec<u8>) -> Result<ZipArchive> {
+		let earliest_end = data.len().saturating_sub(END_OF_CENTRAL_DIR_LEN + MAX_COMMENT_LEN);
+		let latest_end = data
+			.len()
+			.checked_sub(END_OF_CENTRAL_DIR_LEN)
+			.ok_or_else(|| archive_error("the file is too short"))?;
+		let end = (earliest_end..=latest_end)
+			.rev()
+			.find(|&offset| read_u32(&data, offset).ok() == Some(END_OF_CENTRAL_DIR_SIGNATURE))
+			.ok_or_else(|| archive_error("the end of the central directory is missing"))?;
+		let entry_count = read_u16(&data, end + 10)? as usize;
+		let mut offset = read_u32(&data, end + 16)? as usize;
+
+		let mut entries = Vec::with_capacity(entry_count);
+		for _ in 0..entry_count {
+			if read_u32(&data, offset)? != CENTRAL_HEADER_SIGNATURE {
+				return Err(archive_error("invalid central directory entry"));
+			}
+			let name_len = read_u16(&data, offset + 28)? as usize;
+			let extra_len = read_u16(&data, offset + 30)? as usize;
+			let comment_len = read_u16(&data, offset + 32)? as usize;
+			let name_start = offset + CENTRAL_HEADER_LEN;
+			let name = data
+				.get(name_start..name_start + name_len)
+				.ok_or_else(|| archive_error("unexpected end"))?;
+			entries.push(ZipEntry {
+				name: String::from_utf8_lossy(name).into_owned(),
+				method: read_u16(&data, offset + 10)?,
+				compressed_size: read_u32(&data, offset + 20)? as usize,
+				uncompressed_size: read_u32(&data, offset + 24)? as usize,
+				local_header_offset: read_u32(&data, offset + 42)? as usize,
+			});
+			offset = name_start + name_len + extra_len + comment_len;
+		}
+		Ok(ZipArchive { data, entries })
+	}
+
+	/// Returns the indices of the entries that are supported images, ordered by their names
+	pub fn image_entries(&self) -> Vec<usize> {
+		let mut indices: Vec<_> = (0..self.entries.len())
+			.filter(|&i| {
+				let name = &self.entries[i].name;
+				// Only the extensions are recognized, because the entries aren't files on the
+				// disk that could be read to detect their format
+				let path = Path::new(name);
+				!name.ends_with('/') && !is_comic_book(path) && is_file_supported(path)
+			})
+			.collect();
+		indices.sort_by(|&a, &b| {
+			lexical_sort::natural_lexical_cmp(&self.entries[a].name, &self.entries[b].name)
+		});
+		indices
+	}
+
+	/// The index of the entry with the given path within the archive
+	pub fn find(&self, name: &str) -> Option<usize> {
+		self.entries.iter().position(|entry| entry.name == name)
+	}
+
+	/// The name of the file within the archive without the folders it's in
+	pub fn file_name(&self, index: usize) -> &str {
+		let name = &self.entries[index].name;
+		name.rsplit(['/', '\\']).next().unwrap_or(name)
+	}
+
+	/// Decompresses the entry at `index`
+	pub fn read(&self, index: usize) -> Result<Vec<u8>> {
+		let entry = &self.entries[index];
+		let header = entry.local_header_offset;
+		if read_u32(&self.data, header)? != LOCAL_HEADER_SIGNATURE {
+			return Err(archive_error("invalid local file header"));
+		}
+		let name_len = read_u16(&self.data, header + 26)? as usize;
+		let extra_len = read_u16(&self.data, header + 28)? as usize;
+		let start = header + LOCAL_HEADER_LEN + name_len + extra_len;
+		let compressed = start
+			.checked_add(entry.compressed_size)
+			.and_then(|end| self.data.get(start..end))
+			.ok_or_else(|| archive_error("unexpected end"))?;
+		match entry.method {
+			METHOD_STORED => Ok(compressed.to_vec()),
+			METHOD_DEFLATE => {
+				// The size in the header is only used as a limit, a wrong size can't cause a
+				// large allocation
+				let mut contents = Vec::new();
+				DeflateDecoder::new(compressed)
+					.take(entry.uncompressed_size as u64)
+					.read_to_end(&mut contents)?;
+				Ok(contents)
+			}
+			method => Err(archive_error(&format!("unsupported compression method {}", method))),
+		}
+	}
+}
+
+/// Reads the encoded image of the first page of the comic book
+pub fn read_cover(path: &Path) -> Result<Vec<u8>> {
+	let archive = ZipArchive::open(path)?;
+	let first = *archive
+		.image_entries()
+		.first()
+		.ok_or_else(|| archive_error("the archive doesn't contain any images"))?;
+	archive.read(first)
+}
+
+/// Reads the encoded PNG of the flattened drawing. The layers themselves are ignored.
+pub fn read_merged_image(path: &Path) -> Result<Vec<u8>> {
+	let archive = ZipArchive::open(path)?;
+	let index = archive.find(OPENRASTER_MERGED_IMAGE).ok_or_else(|| {
+		archive_error(&format!("the OpenRaster file has no {}", OPENRASTER_MERGED_IMAGE))
+	})?;
+	archive.read(index)
+}
+
+/// The pages of a comic book, extracted into a temporary folder so that they can be browsed
+/// like the images of a folder. The folder is removed when this is dropped.
+pub struct ComicBook {
+	pub archive_path: PathBuf,
+	pub pages_dir: PathBuf,
+}
+
+impl ComicBook {
+	/// Extracts the images of the archive into a new folder within `temp_root`. The pages are
+	/// prefixed with their page number so that they are listed in the order of the book.
+	/// This reads the whole archive, so it's better done on another thread.
+	pub fn extract(archive_path: &Path, temp_root: &Path) -> Result<ComicBook> {
+		let archive = ZipArchive::open(archive_path)?;
+		let pages = archive.image_entries();
+		if pages.is_empty() {
+			return Err(archive_error("the archive doesn't contain any images"));
+		}
+		let pages_dir = create_book_dir(temp_root)?;
+		let book = ComicBook { archive_path: archive_path.to_owned(), pages_dir };
+		for (page, &index) in pages.iter().enumerate() {
+			let name = format!("{:04} {}", page + 1, archive.file_name(index));
+			fs::write(book.pages_dir.join(name), archive.read(index)?)?;
+		}
+		Ok(book)
+	}
+
+	pub fn first_page(&self) -> Option<PathBuf> {
+		let mut pages: Vec<_> = fs::read_dir(&self.pages_dir).ok()?.flatten().collect();
+		pages.sort_by_key(|entry| entry.file_name());
+		pages.first().map(|entry| entry.path())
+	}
+
+	/// Returns true if `path` is one of the extracted pages
+	pub fn contains(&self, path: &Path) -> bool {
+		path.parent() == Some(self.pages_dir.as_path())
+	}
+}
+
+/// Creates a folder for the pages that no other process created before, and that only the
+/// current user can access. A folder that exists already is never reused, because on a
+/// shared temporary folder another user could have put it there.
+fn create_book_dir(temp_root: &Path) -> io::Result<PathBuf> {
+	loop {
+		let book_id = NEXT_BOOK_ID.fetch_add(1, Ordering::Relaxed);
+		let folder_name = format!("emulsion-book-{}-{}", std::process::id(), book_id);
+		let pages_dir = temp_root.join(folder_name);
+		let mut builder = fs::DirBuilder::new();
+		#[cfg(unix)]
+		std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+		match builder.create(&pages_dir) {
+			Ok(()) => return Ok(pages_dir),
+			Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+			Err(e) => return Err(e),
+		}
+	}
+}
+
+impl Drop for ComicBook {
+	fn drop(&mut self) {
+		let _ = fs::remove_dir_all(&self.pages_dir);
+	}
+}
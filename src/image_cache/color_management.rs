@@ -0,0 +1,206 @@
+//! Converts the colors of the decoded images from their embedded ICC profile to the profile of
+//! the display, see `--icc-convert-to-display`. The images without a profile are taken to be
+//! sRGB.
+//!
+//! The profile of the display is read from a file, or asked from the operating system. On
+//! macOS that's the profile of the main display. On Linux the primaries and the gamma of the
+//! first connected monitor are read from its EDID, a display profile set in the desktop
+//! settings isn't seen.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use gelatin::image::{ImageDecoder, ImageReader, RgbaImage};
+use moxcms::{
+	Chromaticity, ColorPrimaries, ColorProfile, Layout, Transform8BitExecutor, TransformOptions,
+	XyY,
+};
+
+/// The transforms are only kept for this many different profiles of the images
+const MAX_CACHED_TRANSFORMS: usize = 16;
+const EDID_HEADER: &[u8] = &[0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00];
+const EDID_GAMMA: usize = 23;
+/// The low bits of the chromaticity coordinates, followed by their high bits
+const EDID_CHROMATICITY: usize = 25;
+
+/// The display profile with the transforms to it, by the embedded profile they start from.
+/// Only set when the colors are converted.
+static DISPLAY_TRANSFORMS: OnceLock<DisplayTransforms> = OnceLock::new();
+
+struct DisplayTransforms {
+	display: ColorProfile,
+	/// The empty profile stands for the images without one
+	transforms: Mutex<HashMap<Vec<u8>, Arc<Transform8BitExecutor>>>,
+}
+
+/// Converts the colors of every image that's loaded from now on to `display`. Only the first
+/// call has an effect.
+pub fn convert_to_display(display: ColorProfile) {
+	let transforms = DisplayTransforms { display, transforms: Mutex::new(HashMap::new()) };
+	let _ = DISPLAY_TRANSFORMS.set(transforms);
+}
+
+/// Reads the profile from the file at `path`, or asks the operating system for the profile of
+/// the display if it's `None`
+pub fn display_profile(path: Option<&Path>) -> Result<ColorProfile, String> {
+	match path {
+		Some(path) => {
+			let data = fs::read(path).map_err(|e| format!("Could not read {:?}: {}", path, e))?;
+			ColorProfile::new_from_slice(&data)
+				.map_err(|e| format!("{:?} is not a valid ICC profile: {}", path, e))
+		}
+		None => system_display_profile(),
+	}
+}
+
+/// The transform from the embedded profile of the image at `path` to the display, or `None`
+/// if the colors aren't converted or the profile can't be used
+pub fn transform_for(path: &Path) -> Option<Arc<Transform8BitExecutor>> {
+	let display = DISPLAY_TRANSFORMS.get()?;
+	let embedded = embedded_profile(path).unwrap_or_default();
+	let mut transforms = display.transforms.lock().unwrap();
+	if let Some(transform) = transforms.get(&embedded) {
+		return Some(transform.clone());
+	}
+	let source = if embedded.is_empty() {
+		ColorProfile::new_srgb()
+	} else {
+		ColorProfile::new_from_slice(&embedded).ok()?
+	};
+	let options = TransformOptions::default();
+	let transform =
+		source.create_transform_8bit(Layout::Rgba, &display.display, Layout::Rgba, options);
+	let transform = match transform {
+		Ok(transform) => transform,
+		Err(e) => {
+			eprintln!("Could not convert the colors of {:?} to the display: {}", path, e);
+			return None;
+		}
+	};
+	if transforms.len() >= MAX_CACHED_TRANSFORMS {
+		transforms.clear();
+	}
+	transforms.insert(embedded, transform.clone());
+	Some(transform)
+}
+
+/// Replaces the colors of the image with the transformed ones, one row at a time
+pub fn apply(transform: &Transform8BitExecutor, image: &mut RgbaImage) {
+	let row_len = image.width() as usize * 4;
+	if row_len == 0 {
+		return;
+	}
+	let mut source = vec![0; row_len];
+	for row in image.chunks_exact_mut(row_len) {
+		source.copy_from_slice(row);
+		if transform.transform(&source, row).is_err() {
+			return;
+		}
+	}
+}
+
+fn embedded_profile(path: &Path) -> Option<Vec<u8>> {
+	let reader = ImageReader::new(BufReader::new(File::open(path).ok()?));
+	let reader = reader.with_guessed_format().ok()?;
+	reader.into_decoder().ok()?.icc_profile().ok()?
+}
+
+/// A profile with the primaries, the white point and the gamma that the EDID of a monitor
+/// gives. Returns `None` if it's not an EDID or the monitor doesn't say its gamma.
+pub fn edid_profile(edid: &[u8]) -> Option<ColorProfile> {
+	if !edid.starts_with(EDID_HEADER) || edid.len() < EDID_CHROMATICITY + 10 {
+		return None;
+	}
+	let gamma = match edid[EDID_GAMMA] {
+		0xFF => return None,
+		value => (value as f32 + 100.0) / 100.0,
+	};
+	let low = &edid[EDID_CHROMATICITY..EDID_CHROMATICITY + 2];
+	let high = &edid[EDID_CHROMATICITY + 2..EDID_CHROMATICITY + 10];
+	// The coordinates have ten bits, the two lowest ones are packed into the first two bytes
+	let coordinate = |index: usize| {
+		let low_bits = (low[index / 4] >> (6 - 2 * (index % 4))) & 0b11;
+		((high[index] as u16) << 2 | low_bits as u16) as f32 / 1024.0
+	};
+	let chromaticity = |index: usize| Chromaticity::new(coordinate(index), coordinate(index + 1));
+	let primaries =
+		ColorPrimaries { red: chromaticity(0), green: chromaticity(2), blue: chromaticity(4) };
+	let white = chromaticity(6);
+	if white.y <= 0.0 {
+		return None;
+	}
+	let mut profile = ColorProfile::new_srgb();
+	profile.update_rgb_colorimetry(XyY::new(white.x as f64, white.y as f64, 1.0), primaries);
+	let curve = moxcms::curve_from_gamma(gamma);
+	profile.red_trc = Some(curve.clone());
+	profile.green_trc = Some(curve.clone());
+	profile.blue_trc = Some(curve);
+	profile.description = None;
+	Some(profile)
+}
+
+#[cfg(target_os = "macos")]
+fn system_display_profile() -> Result<ColorProfile, String> {
+	use std::ffi::c_void;
+
+	#[link(name = "CoreGraphics", kind = "framework")]
+	extern "C" {
+		fn CGMainDisplayID() -> u32;
+		fn CGDisplayCopyColorSpace(display: u32) -> *const c_void;
+		fn CGColorSpaceCopyICCData(space: *const c_void) -> *const c_void;
+	}
+	#[link(name = "CoreFoundation", kind = "framework")]
+	extern "C" {
+		fn CFDataGetLength(data: *const c_void) -> isize;
+		fn CFDataGetBytePtr(data: *const c_void) -> *const u8;
+		fn CFRelease(object: *const c_void);
+	}
+
+	// SAFETY: Every object that's copied is released once, after its bytes were copied
+	let data = unsafe {
+		let space = CGDisplayCopyColorSpace(CGMainDisplayID());
+		if space.is_null() {
+			return Err("macOS didn't tell the color space of the display".into());
+		}
+		let icc = CGColorSpaceCopyICCData(space);
+		CFRelease(space);
+		if icc.is_null() {
+			return Err("The color space of the display has no ICC profile".into());
+		}
+		let bytes =
+			std::slice::from_raw_parts(CFDataGetBytePtr(icc), CFDataGetLength(icc) as usize);
+		let data = bytes.to_vec();
+		CFRelease(icc);
+		data
+	};
+	ColorProfile::new_from_slice(&data)
+		.map_err(|e| format!("The profile of the display can't be read: {}", e))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn system_display_profile() -> Result<ColorProfile, String> {
+	let entries = fs::read_dir("/sys/class/drm")
+		.map_err(|e| format!("The monitors could not be listed: {}", e))?;
+	let mut connectors: Vec<_> = entries.filter_map(|entry| entry.ok()).map(|e| e.path()).collect();
+	connectors.sort();
+	for connector in connectors {
+		let connected = fs::read_to_string(connector.join("status"))
+			.is_ok_and(|status| status.trim() == "connected");
+		if !connected {
+			continue;
+		}
+		if let Some(profile) = fs::read(connector.join("edid")).ok().and_then(|e| edid_profile(&e))
+		{
+			return Ok(profile);
+		}
+	}
+	Err("No connected monitor tells its colors, use --color-profile".into())
+}
+
+#[cfg(not(unix))]
+fn system_display_profile() -> Result<ColorProfile, String> {
+	Err("The profile of the display can't be found on this system, use --color-profile".into())
+}
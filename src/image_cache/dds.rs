@@ -0,0 +1,325 @@
+//! Decoder for DirectDraw Surface (DDS) textures.
+//!
+//! A DDS file consists of the magic bytes `DDS `, a 124 byte header, an optional 20 byte
+//! DX10 header, and the surfaces. Only the first surface is decoded, which is the largest mip
+//! level of the first face or array element. Supported are the block compressed formats BC1 to
+//! BC5 (DXT1 to DXT5, ATI1 and ATI2), and uncompressed pixels described by bit masks.
+
+use std::convert::TryInto;
+
+use gelatin::image::{Rgba, RgbaImage};
+
+use super::image_loader::{ImageLoaderError, Result};
+
+const MAGIC: &[u8] = b"DDS ";
+const HEADER_LEN: usize = 128;
+const DX10_HEADER_LEN: usize = 20;
+
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_ALPHA: u32 = 0x2;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDPF_LUMINANCE: u32 = 0x20000;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Compression {
+	Bc1,
+	Bc2,
+	Bc3,
+	Bc4,
+	Bc5,
+}
+
+impl Compression {
+	fn block_len(self) -> usize {
+		match self {
+			Compression::Bc1 | Compression::Bc4 => 8,
+			Compression::Bc2 | Compression::Bc3 | Compression::Bc5 => 16,
+		}
+	}
+}
+
+/// The layout of an uncompressed pixel
+struct PixelMasks {
+	bytes_per_pixel: usize,
+	/// The masks of red, green, blue and alpha. A zero mask means that the channel is missing.
+	masks: [u32; 4],
+	/// The pixel is a single luminance value in the red mask
+	luminance: bool,
+}
+
+enum PixelFormat {
+	Compressed(Compression),
+	Uncompressed(PixelMasks),
+}
+
+fn error(description: String) -> ImageLoaderError {
+	ImageLoaderError { description: description.into() }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+	u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+/// Returns true if the data looks like the beginning of a DDS file
+pub fn is_dds(data: &[u8]) -> bool {
+	data.starts_with(MAGIC)
+}
+
+/// Reads the width and the height from the header of a DDS file
+pub fn read_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+	if !is_dds(data) || data.len() < 20 {
+		return None;
+	}
+	Some((read_u32(data, 16), read_u32(data, 12)))
+}
+
+fn four_cc_name(four_cc: u32) -> String {
+	four_cc.to_le_bytes().iter().map(|&b| b as char).collect()
+}
+
+/// Reads the pixel format from the headers. Returns the format and the offset of the first
+/// surface.
+fn read_pixel_format(data: &[u8]) -> Result<(PixelFormat, usize)> {
+	let flags = read_u32(data, 80);
+	if flags & DDPF_FOURCC != 0 {
+		let four_cc = read_u32(data, 84);
+		let compression = match &four_cc.to_le_bytes() {
+			b"DXT1" => Compression::Bc1,
+			b"DXT2" | b"DXT3" => Compression::Bc2,
+			b"DXT4" | b"DXT5" => Compression::Bc3,
+			b"ATI1" | b"BC4U" => Compression::Bc4,
+			b"ATI2" | b"BC5U" => Compression::Bc5,
+			b"DX10" => return read_dx10_format(data),
+			_ => {
+				return Err(error(format!(
+					"DDS files with the {:?} format are not supported",
+					four_cc_name(four_cc)
+				)))
+			}
+		};
+		return Ok((PixelFormat::Compressed(compression), HEADER_LEN));
+	}
+	let bit_count = read_u32(data, 88);
+	if !matches!(bit_count, 8 | 16 | 24 | 32) {
+		return Err(error(format!(
+			"DDS files with {} bits per pixel are not supported",
+			bit_count
+		)));
+	}
+	let mut masks = [read_u32(data, 92), read_u32(data, 96), read_u32(data, 100), 0];
+	if flags & (DDPF_ALPHAPIXELS | DDPF_ALPHA) != 0 {
+		masks[3] = read_u32(data, 104);
+	}
+	if flags & DDPF_ALPHA != 0 && flags & (DDPF_RGB | DDPF_LUMINANCE) == 0 {
+		// Alpha only textures are shown as white with the alpha applied
+		masks[0] = 0;
+		masks[1] = 0;
+		masks[2] = 0;
+	}
+	let masks = PixelMasks {
+		bytes_per_pixel: bit_count as usize / 8,
+		masks,
+		luminance: flags & DDPF_LUMINANCE != 0,
+	};
+	Ok((PixelFormat::Uncompressed(masks), HEADER_LEN))
+}
+
+fn read_dx10_format(data: &[u8]) -> Result<(PixelFormat, usize)> {
+	if data.len() < HEADER_LEN + DX10_HEADER_LEN {
+		return Err(error("The DX10 header of the DDS file is missing".into()));
+	}
+	let dxgi_format = read_u32(data, HEADER_LEN);
+	let rgba = [0xff, 0xff00, 0xff_0000, 0xff00_0000];
+	let bgra = [0xff_0000, 0xff00, 0xff, 0xff00_0000];
+	let bgrx = [0xff_0000, 0xff00, 0xff, 0];
+	let uncompressed = |masks| {
+		PixelFormat::Uncompressed(PixelMasks { bytes_per_pixel: 4, masks, luminance: false })
+	};
+	let format = match dxgi_format {
+		70..=72 => PixelFormat::Compressed(Compression::Bc1),
+		73..=75 => PixelFormat::Compressed(Compression::Bc2),
+		76..=78 => PixelFormat::Compressed(Compression::Bc3),
+		79 | 80 => PixelFormat::Compressed(Compression::Bc4),
+		82 | 83 => PixelFormat::Compressed(Compression::Bc5),
+		27..=30 => uncompressed(rgba),
+		87 | 90 | 91 => uncompressed(bgra),
+		88 | 92 | 93 => uncompressed(bgrx),
+		_ => {
+			return Err(error(format!(
+				"DDS files with the DXGI format {} are not supported",
+				dxgi_format
+			)))
+		}
+	};
+	Ok((format, HEADER_LEN + DX10_HEADER_LEN))
+}
+
+/// Decodes the first surface of a DDS file, which is the highest resolution mip level
+pub fn decode_dds(data: &[u8]) -> Result<RgbaImage> {
+	if !is_dds(data) || data.len() < HEADER_LEN {
+		return Err(error("Not a valid DDS header".into()));
+	}
+	let (width, height) = read_dimensions(data).unwrap();
+	let (format, offset) = read_pixel_format(data)?;
+	let surface = &data[offset..];
+	let surface_len = match &format {
+		PixelFormat::Compressed(compression) => {
+			let blocks = (width as usize).div_ceil(4).checked_mul((height as usize).div_ceil(4));
+			blocks.and_then(|blocks| blocks.checked_mul(compression.block_len()))
+		}
+		PixelFormat::Uncompressed(pixel) => (width as usize)
+			.checked_mul(height as usize)
+			.and_then(|count| count.checked_mul(pixel.bytes_per_pixel)),
+	}
+	.ok_or_else(|| error("DDS image dimensions are too large".into()))?;
+	if surface.len() < surface_len {
+		return Err(error(format!(
+			"DDS file is too short. Expected {} bytes of pixels but found {}",
+			surface_len,
+			surface.len()
+		)));
+	}
+	let mut image = RgbaImage::new(width, height);
+	match format {
+		PixelFormat::Compressed(compression) => {
+			decode_blocks(&surface[..surface_len], compression, &mut image)
+		}
+		PixelFormat::Uncompressed(pixel) => {
+			decode_pixels(&surface[..surface_len], &pixel, &mut image)
+		}
+	}
+	Ok(image)
+}
+
+fn decode_pixels(data: &[u8], pixel: &PixelMasks, image: &mut RgbaImage) {
+	for (bytes, target) in data.chunks_exact(pixel.bytes_per_pixel).zip(image.pixels_mut()) {
+		let mut value_bytes = [0; 4];
+		value_bytes[..bytes.len()].copy_from_slice(bytes);
+		let value = u32::from_le_bytes(value_bytes);
+		let [r, g, b, a] = pixel.masks.map(|mask| read_channel(value, mask));
+		*target = if pixel.luminance {
+			Rgba([r.unwrap_or(255), r.unwrap_or(255), r.unwrap_or(255), a.unwrap_or(255)])
+		} else {
+			Rgba([r.unwrap_or(255), g.unwrap_or(255), b.unwrap_or(255), a.unwrap_or(255)])
+		};
+	}
+}
+
+/// Extracts the channel with the mask from the pixel and scales it to 8 bits. Returns `None`
+/// if the mask is zero.
+fn read_channel(value: u32, mask: u32) -> Option<u8> {
+	if mask == 0 {
+		return None;
+	}
+	let max = mask >> mask.trailing_zeros();
+	let channel = (value & mask) >> mask.trailing_zeros();
+	Some(((channel as u64 * 255 + max as u64 / 2) / max as u64) as u8)
+}
+
+fn decode_blocks(data: &[u8], compression: Compression, image: &mut RgbaImage) {
+	let (width, height) = image.dimensions();
+	let blocks_per_row = width.div_ceil(4) as usize;
+	for (index, block) in data.chunks_exact(compression.block_len()).enumerate() {
+		let pixels = match compression {
+			Compression::Bc1 => decode_color_block(block, true),
+			Compression::Bc2 => {
+				let mut pixels = decode_color_block(&block[8..], false);
+				for (i, pixel) in pixels.iter_mut().enumerate() {
+					let nibble = (block[i / 2] >> (4 * (i % 2))) & 0xf;
+					pixel[3] = nibble * 17;
+				}
+				pixels
+			}
+			Compression::Bc3 => {
+				let mut pixels = decode_color_block(&block[8..], false);
+				for (pixel, alpha) in pixels.iter_mut().zip(decode_alpha_block(&block[..8])) {
+					pixel[3] = alpha;
+				}
+				pixels
+			}
+			Compression::Bc4 => decode_alpha_block(block).map(|v| Rgba([v, v, v, 255])),
+			Compression::Bc5 => {
+				let red = decode_alpha_block(&block[..8]);
+				let green = decode_alpha_block(&block[8..]);
+				let mut pixels = [Rgba([0, 0, 0, 255]); 16];
+				for (i, pixel) in pixels.iter_mut().enumerate() {
+					pixel[0] = red[i];
+					pixel[1] = green[i];
+				}
+				pixels
+			}
+		};
+		let block_x = (index % blocks_per_row) as u32 * 4;
+		let block_y = (index / blocks_per_row) as u32 * 4;
+		for (i, pixel) in pixels.iter().enumerate() {
+			let x = block_x + i as u32 % 4;
+			let y = block_y + i as u32 / 4;
+			// The blocks at the right and the bottom edge may reach past the image
+			if x < width && y < height {
+				image.put_pixel(x, y, *pixel);
+			}
+		}
+	}
+}
+
+fn rgb565(color: u16) -> [u8; 3] {
+	let r = (color >> 11) & 0x1f;
+	let g = (color >> 5) & 0x3f;
+	let b = color & 0x1f;
+	[(r * 255 / 31) as u8, (g * 255 / 63) as u8, (b * 255 / 31) as u8]
+}
+
+/// Decodes the 8 byte color part of a block. The alpha is 255, or 0 for the transparent index
+/// of BC1 blocks whose first color is not greater than the second one.
+fn decode_color_block(block: &[u8], allow_transparency: bool) -> [Rgba<u8>; 16] {
+	let c0 = u16::from_le_bytes([block[0], block[1]]);
+	let c1 = u16::from_le_bytes([block[2], block[3]]);
+	let (rgb0, rgb1) = (rgb565(c0), rgb565(c1));
+	let mix = |w0: u32, w1: u32| {
+		let mut rgb = [0; 3];
+		for (i, channel) in rgb.iter_mut().enumerate() {
+			*channel = ((rgb0[i] as u32 * w0 + rgb1[i] as u32 * w1) / (w0 + w1)) as u8;
+		}
+		Rgba([rgb[0], rgb[1], rgb[2], 255])
+	};
+	let palette = if c0 > c1 || !allow_transparency {
+		[mix(1, 0), mix(0, 1), mix(2, 1), mix(1, 2)]
+	} else {
+		[mix(1, 0), mix(0, 1), mix(1, 1), Rgba([0, 0, 0, 0])]
+	};
+	let indices = u32::from_le_bytes(block[4..8].try_into().unwrap());
+	let mut pixels = [Rgba([0, 0, 0, 0]); 16];
+	for (i, pixel) in pixels.iter_mut().enumerate() {
+		*pixel = palette[(indices >> (2 * i)) as usize & 0x3];
+	}
+	pixels
+}
+
+/// Decodes an 8 byte block of a single channel, as used for the alpha of BC3 and for BC4 and
+/// BC5
+fn decode_alpha_block(block: &[u8]) -> [u8; 16] {
+	let (a0, a1) = (block[0] as u32, block[1] as u32);
+	let mut palette = [0u8; 8];
+	palette[0] = a0 as u8;
+	palette[1] = a1 as u8;
+	if a0 > a1 {
+		for i in 1..7 {
+			palette[i + 1] = ((a0 * (7 - i as u32) + a1 * i as u32) / 7) as u8;
+		}
+	} else {
+		for i in 1..5 {
+			palette[i + 1] = ((a0 * (5 - i as u32) + a1 * i as u32) / 5) as u8;
+		}
+		palette[6] = 0;
+		palette[7] = 255;
+	}
+	let mut index_bytes = [0; 8];
+	index_bytes[..6].copy_from_slice(&block[2..8]);
+	let indices = u64::from_le_bytes(index_bytes);
+	let mut values = [0; 16];
+	for (i, value) in values.iter_mut().enumerate() {
+		*value = palette[(indices >> (3 * i)) as usize & 0x7];
+	}
+	values
+}
@@ -1,12 +1,55 @@
-use std::ffi::{OsStr, OsString};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
 use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::SystemTime;
 
 use log::debug;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
 
 use super::image_loader::is_file_supported;
-use crate::parallel_action::ParallelAction;
+use crate::{
+	configuration::SortOrder,
+	parallel_action::ParallelAction,
+	playlist::{is_playlist, read_playlist},
+	ratings::read_rating,
+	tags,
+};
+
+/// Images with a lower rating than this are left out of the list of images. Zero means that
+/// unrated images are listed too.
+pub static MIN_RATING: AtomicU8 = AtomicU8::new(0);
+
+/// If this is set, only these images are listed. The paths are the keys of the tag store.
+pub static TAG_FILTER: OnceLock<HashSet<PathBuf>> = OnceLock::new();
+
+/// The order of the files. The files are ordered by name if this isn't set.
+pub static SORT_ORDER: OnceLock<SortOrder> = OnceLock::new();
+
+/// The seed of the shuffle for `SortOrder::Random`. The same files are always shuffled into
+/// the same order with the same seed.
+pub static SORT_SEED: AtomicU64 = AtomicU64::new(0);
+
+/// The number of files in the first listing that a folder scan sends. Every following listing
+/// has twice as many, so that a large folder isn't sorted too many times.
+const FIRST_SCAN_BATCH: usize = 64;
+
+/// Returns true if the file is an image that should be in the list of images
+fn is_listed(path: &Path) -> bool {
+	if !is_file_supported(path) {
+		return false;
+	}
+	let min_rating = MIN_RATING.load(Ordering::Relaxed);
+	if min_rating != 0 && read_rating(path).is_none_or(|rating| rating < min_rating) {
+		return false;
+	}
+	TAG_FILTER.get().is_none_or(|images| tags::key(path).is_ok_and(|key| images.contains(&key)))
+}
 
 #[derive(Debug)]
 pub enum Error {
@@ -31,8 +74,7 @@ impl From<std::io::Error> for Error {
 }
 impl From<Error> for std::io::Error {
 	fn from(value: Error) -> Self {
-		use std::io::ErrorKind;
-		std::io::Error::new(ErrorKind::Other, format!("directory::Error: {}", value))
+		std::io::Error::other(format!("directory::Error: {}", value))
 	}
 }
 
@@ -41,7 +83,7 @@ pub type Result<T> = std::result::Result<T, Error>;
 macro_rules! step_to_next_img {
 	($this:ident, $iter:ident) => {
 		for (i, file) in $iter {
-			if is_file_supported(&file.path) {
+			if is_listed(&file.path) {
 				$this.curr_file_idx = i;
 				$this.set_image_index_from_file_index();
 				return;
@@ -50,13 +92,104 @@ macro_rules! step_to_next_img {
 	};
 }
 
+fn sort_files(files: &mut [PathBuf], order: SortOrder) {
+	// The files are first ordered by name so that the other orders don't depend on the order
+	// that the file system lists the files in
+	files.sort_unstable_by(|a, b| {
+		lexical_sort::natural_lexical_cmp(
+			&a.file_name().unwrap().to_string_lossy(),
+			&b.file_name().unwrap().to_string_lossy(),
+		)
+	});
+	match order {
+		SortOrder::Name => (),
+		SortOrder::Date => files.sort_by_cached_key(|path| {
+			let modified = fs::metadata(path).and_then(|m| m.modified());
+			std::cmp::Reverse(modified.unwrap_or(SystemTime::UNIX_EPOCH))
+		}),
+		SortOrder::Size => {
+			files.sort_by_cached_key(|path| fs::metadata(path).map(|m| m.len()).unwrap_or(0))
+		}
+		SortOrder::Random => {
+			let mut rng = StdRng::seed_from_u64(SORT_SEED.load(Ordering::Relaxed));
+			files.shuffle(&mut rng);
+		}
+	}
+}
+
+/// The files of a folder in the order that they are shown in. Reading the folder doesn't need
+/// a `Directory`, so it can be done on another thread.
+///
+/// A playlist is listed like a folder, with its files in the order of the playlist.
+pub struct DirListing {
+	pub dir: PathBuf,
+	files: Vec<PathBuf>,
+}
+
+impl DirListing {
+	pub fn read(dir: &Path) -> Result<DirListing> {
+		if is_playlist(dir) {
+			let files = read_playlist(dir)?;
+			return Ok(DirListing { dir: dir.to_owned(), files });
+		}
+		let files = fs::read_dir(dir)?.filter_map(file_path).collect();
+		Ok(DirListing::sorted(dir, files))
+	}
+
+	fn sorted(dir: &Path, mut files: Vec<PathBuf>) -> DirListing {
+		sort_files(&mut files, SORT_ORDER.get().copied().unwrap_or_default());
+		DirListing { dir: dir.to_owned(), files }
+	}
+}
+
+/// The path of the entry if it's a file or a link to one
+fn file_path(entry: std::io::Result<fs::DirEntry>) -> Option<PathBuf> {
+	let entry = entry.ok()?;
+	let file_type = entry.file_type().ok()?;
+	(file_type.is_file() || file_type.is_symlink()).then(|| entry.path())
+}
+
+/// Reads the folder and sends the files found so far in listings that grow, see
+/// `FIRST_SCAN_BATCH`. `current` is in every listing but the last one, which is complete and
+/// only has it if it's in the folder. Stops early once the receiver is dropped.
+fn scan_dir(dir: PathBuf, current: PathBuf, sender: Sender<Result<(DirListing, bool)>>) {
+	let entries = match fs::read_dir(&dir) {
+		Ok(entries) => entries,
+		Err(e) => {
+			let _ = sender.send(Err(e.into()));
+			return;
+		}
+	};
+	let mut files = vec![current.clone()];
+	let mut found_current = false;
+	let mut next_batch = FIRST_SCAN_BATCH;
+	for path in entries.filter_map(file_path) {
+		if path == current {
+			found_current = true;
+			continue;
+		}
+		files.push(path);
+		if files.len() >= next_batch {
+			next_batch *= 2;
+			let partial = DirListing::sorted(&dir, files.clone());
+			if sender.send(Ok((partial, false))).is_err() {
+				return;
+			}
+		}
+	}
+	if !found_current {
+		files.remove(0);
+	}
+	let _ = sender.send(Ok((DirListing::sorted(&dir, files), true)));
+}
+
 #[derive(Clone)]
 pub struct DirItem {
 	pub path: PathBuf,
 
 	/// Sometimes also abbreviated as `req_id` is used as a more efficient replacement
 	/// of a PathBuf to identify a file load request.
-	pub request_id: u32,
+	pub request_id: u64,
 }
 
 // enum FilterState {
@@ -80,7 +213,7 @@ pub struct Directory {
 
 	/// A monotonically increasing integer used for identifying
 	/// each load request
-	current_req_id: u32,
+	current_req_id: u64,
 
 	/// current file index
 	/// This must never be exposed to users of this object.
@@ -92,6 +225,9 @@ pub struct Directory {
 
 	//filter_state: Arc<Mutex<FilterState>>,
 	filter_action: ParallelAction<Vec<DirItem>, Vec<usize>>,
+
+	/// Receives the listings of the folder while it's read on another thread, see `scan_dir`
+	scan: Option<Receiver<Result<(DirListing, bool)>>>,
 }
 
 fn get_action() -> impl FnMut(Vec<DirItem>) -> Vec<usize> {
@@ -99,7 +235,7 @@ fn get_action() -> impl FnMut(Vec<DirItem>) -> Vec<usize> {
 		input
 			.into_iter()
 			.enumerate()
-			.filter_map(|(i, item)| if is_file_supported(&item.path) { Some(i) } else { None })
+			.filter_map(|(i, item)| if is_listed(&item.path) { Some(i) } else { None })
 			.collect()
 	}
 }
@@ -115,6 +251,7 @@ impl Directory {
 			curr_image_idx: 0,
 			current_req_id: 0,
 			filter_action: ParallelAction::new(get_action()),
+			scan: None,
 		}
 	}
 
@@ -128,6 +265,20 @@ impl Directory {
 	}
 
 	pub fn change_directory_with_filename(&mut self, path: &Path, filename: &OsStr) -> Result<()> {
+		let file_path = path.join(filename);
+		if self.path != path && !is_playlist(path) && file_path.is_file() {
+			// The file is shown right away, and the rest of the folder is added as it's read
+			path.clone_into(&mut self.path);
+			self.set_files(vec![file_path.clone()]);
+			let (sender, receiver) = mpsc::channel();
+			let dir = path.to_owned();
+			thread::spawn(move || scan_dir(dir, file_path, sender));
+			self.scan = Some(receiver);
+			self.curr_file_idx = 0;
+			self.set_image_index_from_file_index();
+			self.check_filter_ready();
+			return Ok(());
+		}
 		self.change_directory(path)?;
 		// Look up the index of the filename in the directory
 		for (index, desc) in self.files.iter().enumerate() {
@@ -143,8 +294,24 @@ impl Directory {
 		Err(Error::Other(format!("Could not find file {:?} in directory {:?}", filename, path)))
 	}
 
-	pub fn curr_filename(&self) -> Option<OsString> {
-		self.files.get(self.curr_file_idx).and_then(|x| x.path.file_name().map(|x| x.to_owned()))
+	/// Makes the file at `path` the current one if it's in the list. Unlike
+	/// `change_directory_with_filename`, the whole path is compared, because the files of a
+	/// playlist may be in different folders.
+	pub fn select_path(&mut self, path: &Path) -> bool {
+		match self.files.iter().position(|item| item.path == path) {
+			Some(index) => {
+				self.curr_file_idx = index;
+				self.set_image_index_from_file_index();
+				self.check_filter_ready();
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// True if the files come from a playlist instead of a folder
+	pub fn is_playlist(&self) -> bool {
+		is_playlist(&self.path)
 	}
 
 	pub fn curr_descriptor(&self) -> Option<&DirItem> {
@@ -219,72 +386,125 @@ impl Directory {
 		}
 	}
 
+	/// True while the folder is read on another thread and the list of files is still
+	/// growing
+	pub fn is_scanning(&self) -> bool {
+		self.scan.is_some()
+	}
+
+	/// Takes the latest listing that the scan of the folder sent. Returns true if the list of
+	/// files changed.
+	pub fn poll_scan(&mut self) -> bool {
+		let receiver = match &self.scan {
+			Some(receiver) => receiver,
+			None => return false,
+		};
+		let mut latest = None;
+		let mut finished = false;
+		loop {
+			match receiver.try_recv() {
+				Ok(Ok((listing, complete))) => {
+					latest = Some(listing);
+					finished = complete;
+				}
+				Ok(Err(e)) => {
+					eprintln!("Could not read the folder {:?}: {}", self.path, e);
+					finished = true;
+				}
+				Err(mpsc::TryRecvError::Empty) => break,
+				Err(mpsc::TryRecvError::Disconnected) => {
+					finished = true;
+					break;
+				}
+			}
+		}
+		if finished {
+			self.scan = None;
+		}
+		match latest {
+			Some(listing) => {
+				self.replace_files(listing);
+				true
+			}
+			None => false,
+		}
+	}
+
 	pub fn update_directory(&mut self) -> Result<()> {
-		let curr_filename = self.curr_filename();
-		let curr_filename = curr_filename.as_deref();
+		let listing = DirListing::read(&self.path)?;
+		self.apply_listing(listing);
+		Ok(())
+	}
+
+	/// Replaces the files with the ones in `listing`, which has to be a listing of this folder.
+	/// Stays on the current file if it's still there, otherwise continues with the next image.
+	pub fn apply_listing(&mut self, listing: DirListing) {
+		// The complete listing replaces the one that's still being read
+		self.scan = None;
+		self.replace_files(listing);
+	}
+
+	fn replace_files(&mut self, listing: DirListing) {
+		let curr_path = self.curr_descriptor().map(|item| item.path.clone());
 		let curr_index = self.curr_file_idx;
 		debug!(
-			"Directory: `update_directory`. Current filename: {:?}, curr_index: {:?}",
-			curr_filename, curr_index
+			"Directory: `apply_listing`. Current file: {:?}, curr_index: {:?}",
+			curr_path, curr_index
 		);
-		self.collect_directory()?;
-		if curr_filename.is_some() {
+		self.set_files(listing.files);
+		if let Some(curr_path) = curr_path {
 			for (index, desc) in self.files.iter().enumerate() {
-				if desc.path.file_name() == curr_filename {
+				if desc.path == curr_path {
 					debug!("Found file the previously 'current' file in the directory.");
 					self.curr_file_idx = index;
 					self.set_image_index_from_file_index();
 					self.check_filter_ready();
-					return Ok(());
+					return;
 				}
 			}
 		}
 		debug!("Previously 'current' file not found, skipping to next supported.");
 		// if is_file_supported, preserve index of previous file or its following files
 		for (index, desc) in self.files.iter().enumerate().skip(curr_index) {
-			if is_file_supported(&desc.path) {
+			if is_listed(&desc.path) {
 				debug!("Next supported file found. Index {:?}, name {:?}.", index, desc.path);
 				self.curr_file_idx = index;
 				self.set_image_index_from_file_index();
 				self.check_filter_ready();
-				return Ok(());
+				return;
 			}
 		}
 		if self.files.len() <= self.curr_file_idx && !self.files.is_empty() {
 			self.curr_file_idx = 0;
 		}
+	}
 
+	pub fn collect_directory(&mut self) -> Result<()> {
+		self.scan = None;
+		let listing = DirListing::read(&self.path)?;
+		self.set_files(listing.files);
 		Ok(())
 	}
 
-	pub fn collect_directory(&mut self) -> Result<()> {
-		let mut dir_files: Vec<_> = fs::read_dir(&self.path)?
-			.filter_map(|x| match x {
-				Ok(entry) => match entry.file_type() {
-					Ok(file_type) => {
-						if file_type.is_file() || file_type.is_symlink() {
-							self.current_req_id += 1;
-							Some(DirItem { path: entry.path(), request_id: self.current_req_id })
-						} else {
-							None
-						}
-					}
-					Err(_) => None,
-				},
-				Err(_) => None,
+	fn set_files(&mut self, paths: Vec<PathBuf>) {
+		// The files that were listed before keep their request ids, so that their textures
+		// are still found in the cache
+		let known: HashMap<_, _> =
+			self.files.drain(..).map(|item| (item.path, item.request_id)).collect();
+		let dir_files: Vec<_> = paths
+			.into_iter()
+			.map(|path| {
+				let request_id = known.get(&path).copied().unwrap_or_else(|| {
+					self.current_req_id += 1;
+					self.current_req_id
+				});
+				DirItem { path, request_id }
 			})
 			.collect();
 
-		dir_files.sort_unstable_by(|a, b| {
-			lexical_sort::natural_lexical_cmp(
-				&a.path.file_name().unwrap().to_string_lossy(),
-				&b.path.file_name().unwrap().to_string_lossy(),
-			)
-		});
-
 		// Set the current file index to the first image
 		for (i, item) in dir_files.iter().enumerate() {
-			if is_file_supported(&item.path) {
+			if is_listed(&item.path) {
 				self.curr_file_idx = i;
 				break;
 			}
@@ -293,7 +513,6 @@ impl Directory {
 		self.img_i_to_file_i.clear();
 		self.file_i_to_img_i.clear();
 		self.files = dir_files;
-		Ok(())
 	}
 
 	fn finished_filtering(&mut self) {
@@ -311,8 +530,17 @@ impl Directory {
 	}
 
 	fn set_image_index_from_file_index(&mut self) {
-		if let Some(img_idx) = self.file_i_to_img_i.get(self.curr_file_idx) {
-			self.curr_image_idx = img_idx.unwrap() as usize;
+		match self.file_i_to_img_i.get(self.curr_file_idx) {
+			Some(Some(img_idx)) => self.curr_image_idx = *img_idx as usize,
+			Some(None) => {
+				// The current file was left out of the list, continue with the next image
+				let next = self.file_i_to_img_i[self.curr_file_idx..].iter().flatten().next();
+				if let Some(&img_idx) = next {
+					self.curr_image_idx = img_idx as usize;
+					self.curr_file_idx = self.img_i_to_file_i[self.curr_image_idx];
+				}
+			}
+			None => (),
 		}
 	}
 
@@ -0,0 +1,72 @@
+//! Decoder and encoder for farbfeld images.
+//!
+//! A farbfeld file consists of the magic bytes `farbfeld`, the width and the height of the image
+//! as 32 bit big-endian integers, and the pixels in row-major order. Every pixel is stored as four
+//! 16 bit big-endian values of red, green, blue and alpha.
+
+use std::convert::TryInto;
+
+use gelatin::image::{Rgba, RgbaImage};
+
+use super::image_loader::{ImageLoaderError, Result};
+
+const MAGIC: &[u8] = b"farbfeld";
+const HEADER_LEN: usize = 16;
+const BYTES_PER_PIXEL: usize = 8;
+
+/// Returns true if the data looks like the beginning of a farbfeld file
+pub fn is_farbfeld(data: &[u8]) -> bool {
+	data.starts_with(MAGIC)
+}
+
+/// Reads the width and the height from the header of a farbfeld file
+pub fn read_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+	if !is_farbfeld(data) || data.len() < HEADER_LEN {
+		return None;
+	}
+	let width = u32::from_be_bytes(data[8..12].try_into().unwrap());
+	let height = u32::from_be_bytes(data[12..16].try_into().unwrap());
+	Some((width, height))
+}
+
+/// Decodes a farbfeld file. The 16 bit channels are reduced to 8 bits by dropping the lower byte.
+pub fn decode_farbfeld(data: &[u8]) -> Result<RgbaImage> {
+	let (width, height) = read_dimensions(data)
+		.ok_or(ImageLoaderError { description: "Not a valid farbfeld header".into() })?;
+	let pixels_len = (width as usize)
+		.checked_mul(height as usize)
+		.and_then(|count| count.checked_mul(BYTES_PER_PIXEL))
+		.ok_or(ImageLoaderError {
+			description: "Farbfeld image dimensions are too large".into(),
+		})?;
+	let pixels = &data[HEADER_LEN..];
+	if pixels.len() < pixels_len {
+		return Err(ImageLoaderError {
+			description: format!(
+				"Farbfeld file is too short. Expected {} bytes of pixels but found {}",
+				pixels_len,
+				pixels.len()
+			)
+			.into(),
+		});
+	}
+	// The most significant byte of each big-endian channel value is its 8 bit equivalent
+	let bytes = pixels[..pixels_len].iter().step_by(2).copied().collect();
+	Ok(RgbaImage::from_raw(width, height, bytes).unwrap())
+}
+
+/// Encodes the image as a farbfeld file. The 8 bit channels are expanded to 16 bits so that
+/// 255 maps to 65535.
+pub fn encode_farbfeld(image: &RgbaImage) -> Vec<u8> {
+	let (width, height) = image.dimensions();
+	let mut data = Vec::with_capacity(HEADER_LEN + image.as_raw().len() * 2);
+	data.extend_from_slice(MAGIC);
+	data.extend_from_slice(&width.to_be_bytes());
+	data.extend_from_slice(&height.to_be_bytes());
+	for &Rgba(channels) in image.pixels() {
+		for channel in channels {
+			data.extend_from_slice(&[channel, channel]);
+		}
+	}
+	data
+}
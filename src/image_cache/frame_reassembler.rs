@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+use log::debug;
+
+use super::image_loader::LoadResult;
+
+/// Puts the frames of a single load request back into the order of the animation.
+///
+/// The frames are expected to arrive in order from the loader threads, but nothing
+/// guarantees that once the frames of an image are decoded in parallel. A frame that
+/// arrives early is held back until all the frames before it have arrived.
+pub struct FrameReassembler {
+	next_index: u32,
+	buffered: BTreeMap<u32, LoadResult>,
+}
+
+impl FrameReassembler {
+	pub fn new() -> Self {
+		FrameReassembler { next_index: 0, buffered: BTreeMap::new() }
+	}
+
+	/// Accepts a frame and appends every frame that is now in order to `output`.
+	///
+	/// Results other than frames are passed through, except that the end of the request
+	/// first releases the frames that are still held back.
+	pub fn push(&mut self, result: LoadResult, output: &mut Vec<LoadResult>) {
+		let frame_index = match result {
+			LoadResult::Frame { frame_index, .. } => frame_index,
+			LoadResult::Done { .. } | LoadResult::Failed { .. } | LoadResult::Aborted { .. } => {
+				self.flush(output);
+				output.push(result);
+				return;
+			}
+			LoadResult::Start { .. } => {
+				output.push(result);
+				return;
+			}
+		};
+		if frame_index < self.next_index {
+			debug!("Dropping frame {} which was already received", frame_index);
+			return;
+		}
+		if frame_index != self.next_index {
+			debug!("Frame {} arrived before frame {}", frame_index, self.next_index);
+		}
+		self.buffered.insert(frame_index, result);
+		while let Some(frame) = self.buffered.remove(&self.next_index) {
+			output.push(frame);
+			self.next_index += 1;
+		}
+	}
+
+	/// Appends the frames that are held back in their order, skipping the missing ones
+	fn flush(&mut self, output: &mut Vec<LoadResult>) {
+		if let Some((&last, _)) = self.buffered.iter().next_back() {
+			debug!("Missing frames before frame {} at the end of the request", last);
+			self.next_index = last + 1;
+		}
+		output.extend(std::mem::take(&mut self.buffered).into_values());
+	}
+}
@@ -0,0 +1,102 @@
+//! Decoder for macOS icon files (ICNS).
+//!
+//! An ICNS file consists of the magic bytes `icns`, the big endian length of the file, and
+//! the entries. Every entry has a four character type and a big endian length, which includes
+//! these 8 bytes, followed by the payload. The newer types of icons hold a PNG or a JPEG 2000
+//! file. The largest of these is decoded, the older types with the raw pixels are skipped.
+
+use std::convert::TryInto;
+
+use gelatin::image::{self, ImageFormat, RgbaImage};
+
+use super::image_loader::{ImageLoaderError, Result};
+#[cfg(feature = "jpeg2000")]
+use super::jpeg2000;
+
+const MAGIC: &[u8] = b"icns";
+const HEADER_LEN: usize = 8;
+const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// The types that hold a PNG or a JPEG 2000 file, with the width of their icon. The retina
+/// types are twice as large as their name says.
+const COMPRESSED_TYPES: &[(&[u8; 4], u32)] = &[
+	(b"icp4", 16),
+	(b"icp5", 32),
+	(b"icp6", 64),
+	(b"ic07", 128),
+	(b"ic08", 256),
+	(b"ic09", 512),
+	(b"ic10", 1024),
+	(b"ic11", 32),
+	(b"ic12", 64),
+	(b"ic13", 256),
+	(b"ic14", 512),
+];
+
+fn error(description: String) -> ImageLoaderError {
+	ImageLoaderError { description: description.into() }
+}
+
+pub fn is_icns(data: &[u8]) -> bool {
+	data.starts_with(MAGIC)
+}
+
+/// The payloads of the icons that hold a file, the largest first
+fn compressed_icons(data: &[u8]) -> Result<Vec<(u32, &[u8])>> {
+	if !is_icns(data) || data.len() < HEADER_LEN {
+		return Err(error("Not an ICNS file".into()));
+	}
+	let mut icons = Vec::new();
+	let mut offset = HEADER_LEN;
+	while offset + HEADER_LEN <= data.len() {
+		let icon_type = &data[offset..offset + 4];
+		let len = u32::from_be_bytes(data[offset + 4..offset + 8].try_into().unwrap()) as usize;
+		if len < HEADER_LEN || len > data.len() - offset {
+			return Err(error(format!("The ICNS entry at {} has an invalid length", offset)));
+		}
+		let payload = &data[offset + HEADER_LEN..offset + len];
+		if let Some((_, width)) = COMPRESSED_TYPES.iter().find(|(t, _)| t.as_slice() == icon_type) {
+			icons.push((*width, payload));
+		}
+		offset += len;
+	}
+	// The sort is stable, so the icons of the same size stay in the order of the file
+	icons.sort_by(|(a, _), (b, _)| b.cmp(a));
+	Ok(icons)
+}
+
+/// The width and height of the largest icon, judged by its type
+pub fn read_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+	let (width, _) = *compressed_icons(data).ok()?.first()?;
+	Some((width, width))
+}
+
+pub fn decode_icns(data: &[u8]) -> Result<RgbaImage> {
+	let icons = compressed_icons(data)?;
+	let mut last_error = None;
+	for (_, payload) in icons {
+		let decoded = if payload.starts_with(PNG_MAGIC) {
+			image::load_from_memory_with_format(payload, ImageFormat::Png)
+				.map(|image| image.into_rgba8())
+				.map_err(ImageLoaderError::from)
+		} else {
+			decode_jpeg2000_icon(payload)
+		};
+		match decoded {
+			Ok(image) => return Ok(image),
+			// A smaller icon may still be readable
+			Err(e) => last_error = Some(e),
+		}
+	}
+	Err(last_error.unwrap_or_else(|| error("The ICNS file has no PNG or JPEG 2000 icon".into())))
+}
+
+#[cfg(feature = "jpeg2000")]
+fn decode_jpeg2000_icon(payload: &[u8]) -> Result<RgbaImage> {
+	jpeg2000::decode_jpeg2000_data(payload)
+}
+
+#[cfg(not(feature = "jpeg2000"))]
+fn decode_jpeg2000_icon(_payload: &[u8]) -> Result<RgbaImage> {
+	Err(error("The icon is JPEG 2000, which needs the `jpeg2000` feature".into()))
+}
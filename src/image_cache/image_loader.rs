@@ -1,19 +1,30 @@
 use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
-use std::io::{BufReader, Read};
+use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
-use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crossbeam_channel::TryRecvError;
 
 use gelatin::image::{
 	self,
 	codecs::{gif::GifDecoder, png::PngDecoder},
-	AnimationDecoder, ImageFormat,
+	AnimationDecoder, ImageDecoder, ImageFormat,
 };
 use usvg::fontdb;
 
+#[cfg(feature = "jpeg2000")]
+use super::jpeg2000;
+use super::{
+	archive, color_management, dds, farbfeld, icns, perceptual_hash::PerceptualHash, postscript,
+	raw_thumbnail, xbm, xpm,
+};
+
 #[derive(Debug, thiserror::Error)]
 #[error("error happened in the image_loader: {description}")]
 pub struct ImageLoaderError {
@@ -41,12 +52,107 @@ pub type Result<T> = std::result::Result<T, ImageLoaderError>;
 /// To implement this we define a variable that is read by the loader threads and
 /// which will only carry out the request if the focused request id matches their request or
 /// if the focused is set to `NON_EXISTENT_REQUEST_ID`
-pub static PRIORITY_REQUEST_ID: AtomicU32 = AtomicU32::new(0); // The first request usually
-pub const NON_EXISTENT_REQUEST_ID: u32 = std::u32::MAX;
+pub static PRIORITY_REQUEST_ID: AtomicU64 = AtomicU64::new(0); // The first request usually
+pub const NON_EXISTENT_REQUEST_ID: u64 = u64::MAX;
+
+/// Images with more pixels than this are not decoded. Zero means that there's no limit.
+pub static MAX_IMAGE_PIXELS: AtomicU64 = AtomicU64::new(0);
+
+/// Nothing is printed
+pub const LOG_LEVEL_QUIET: u8 = 0;
+/// Errors are printed
+pub const LOG_LEVEL_NORMAL: u8 = 1;
+/// Every load request is printed along with the detected format and the number of frames
+pub const LOG_LEVEL_VERBOSE: u8 = 2;
+
+/// Controls how much the loader threads print to the standard error
+pub static LOG_LEVEL: AtomicU8 = AtomicU8::new(LOG_LEVEL_NORMAL);
+
+fn log_enabled(level: u8) -> bool {
+	LOG_LEVEL.load(Ordering::Relaxed) >= level
+}
 
+#[derive(Debug)]
 pub enum ImgFormat {
 	Image(ImageFormat),
 	Svg,
+	Xbm,
+	Xpm,
+	Farbfeld,
+	Dds,
+	/// A macOS icon, which is shown by its largest icon
+	Icns,
+	/// PostScript or EPS, which is rendered by Ghostscript
+	PostScript,
+	/// A camera RAW file, which is shown by its embedded JPEG preview
+	Raw,
+	/// JP2 or a raw J2K codestream, which is decoded by OpenJPEG
+	#[cfg(feature = "jpeg2000")]
+	Jpeg2000,
+	/// A CBZ archive, which is shown by its first page
+	ComicBook,
+	/// An OpenRaster drawing, which is shown by its flattened image
+	OpenRaster,
+}
+impl ImgFormat {
+	/// The short name of the format for the status bar, like `PNG`
+	pub fn name(&self) -> Cow<'static, str> {
+		match self {
+			ImgFormat::Image(format) => match format.extensions_str().first() {
+				Some(extension) => extension.to_uppercase().into(),
+				None => format!("{:?}", format).to_uppercase().into(),
+			},
+			ImgFormat::Svg => "SVG".into(),
+			ImgFormat::Xbm => "XBM".into(),
+			ImgFormat::Xpm => "XPM".into(),
+			ImgFormat::Farbfeld => "FF".into(),
+			ImgFormat::Dds => "DDS".into(),
+			ImgFormat::Icns => "ICNS".into(),
+			ImgFormat::PostScript => "PS".into(),
+			ImgFormat::Raw => "RAW".into(),
+			#[cfg(feature = "jpeg2000")]
+			ImgFormat::Jpeg2000 => "JP2".into(),
+			ImgFormat::ComicBook => "CBZ".into(),
+			ImgFormat::OpenRaster => "ORA".into(),
+		}
+	}
+
+	/// What decodes the files of this format, for `decoder_info`
+	pub fn decoder(&self) -> &'static str {
+		match self {
+			ImgFormat::Image(_) => "the image crate",
+			ImgFormat::Svg => "resvg",
+			ImgFormat::Xbm => "the built-in XBM decoder",
+			ImgFormat::Xpm => "the built-in XPM decoder",
+			ImgFormat::Farbfeld => "the built-in farbfeld decoder",
+			ImgFormat::Dds => "the built-in DDS decoder",
+			ImgFormat::Icns => "the built-in ICNS decoder",
+			ImgFormat::PostScript => "Ghostscript",
+			ImgFormat::Raw => "the image crate, on the embedded JPEG preview",
+			#[cfg(feature = "jpeg2000")]
+			ImgFormat::Jpeg2000 => "OpenJPEG",
+			ImgFormat::ComicBook => "the image crate, on the first page of the archive",
+			ImgFormat::OpenRaster => "the image crate, on the merged image of the archive",
+		}
+	}
+}
+
+/// How the color values of a decoded image relate to the intensity of the light
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TextureColorSpace {
+	/// The values are gamma encoded with the sRGB transfer function, like in most image files
+	Srgb,
+	/// The values are proportional to the light intensity, like in HDR images
+	Linear,
+}
+
+impl TextureColorSpace {
+	fn of_format(format: ImageFormat) -> TextureColorSpace {
+		match format {
+			ImageFormat::Hdr | ImageFormat::OpenExr => TextureColorSpace::Linear,
+			_ => TextureColorSpace::Srgb,
+		}
+	}
 }
 
 /// These values define the transformation for a pixel array which is to be displayed.
@@ -55,7 +161,7 @@ pub enum ImgFormat {
 /// image. This is represented by the value `Deg0`. All other cases must be interpreted as relative
 /// to this. The rotation part is counter-clockwise. When there's a flip it's always interpreted as
 /// if it happened after the rotation.
-#[derive(Default, Debug, Copy, Clone)]
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Orientation {
 	/// Exif 1
 	#[default]
@@ -83,14 +189,138 @@ pub enum Orientation {
 	Deg90,
 }
 
+impl Orientation {
+	/// Transforms the pixels of the image according to this orientation, so that the result can
+	/// be displayed with the `Deg0` orientation.
+	pub fn apply(self, mut image: image::RgbaImage) -> image::RgbaImage {
+		use image::imageops::{
+			flip_horizontal_in_place, flip_vertical_in_place, rotate180_in_place, rotate270,
+			rotate90,
+		};
+		// Note: the imageops functions use clockwise rotation whereas the
+		// `Orientation` type describes counter-clockwise rotation.
+		match self {
+			Orientation::Deg0 => image,
+			Orientation::Deg0HorFlip => {
+				flip_horizontal_in_place(&mut image);
+				image
+			}
+			Orientation::Deg90 => rotate270(&image),
+			Orientation::Deg90VerFlip => {
+				let mut result = rotate270(&image);
+				flip_vertical_in_place(&mut result);
+				result
+			}
+			Orientation::Deg180 => {
+				rotate180_in_place(&mut image);
+				image
+			}
+			Orientation::Deg180HorFlip => {
+				// This is identical to just a vertical flip with no rotation.
+				flip_vertical_in_place(&mut image);
+				image
+			}
+			Orientation::Deg270 => rotate90(&image),
+			Orientation::Deg270VerFlip => {
+				let mut result = rotate90(&image);
+				flip_vertical_in_place(&mut result);
+				result
+			}
+		}
+	}
+
+	/// The orientation that displays the image as if it was displayed with this orientation
+	/// first, and then turned or flipped by `next`
+	pub fn then(self, next: Orientation) -> Orientation {
+		const ALL: [Orientation; 8] = [
+			Orientation::Deg0,
+			Orientation::Deg0HorFlip,
+			Orientation::Deg180,
+			Orientation::Deg180HorFlip,
+			Orientation::Deg90VerFlip,
+			Orientation::Deg270,
+			Orientation::Deg270VerFlip,
+			Orientation::Deg90,
+		];
+		let (a, b) = (next.matrix(), self.matrix());
+		let mut product = [[0; 2]; 2];
+		for (row, product_row) in product.iter_mut().enumerate() {
+			for (col, value) in product_row.iter_mut().enumerate() {
+				*value = a[row][0] * b[0][col] + a[row][1] * b[1][col];
+			}
+		}
+		// The product of two of the matrices is always one of them
+		ALL.iter().copied().find(|o| o.matrix() == product).unwrap()
+	}
+
+	/// The rows of the matrix that maps the pixel positions to the displayed positions, with
+	/// the x axis pointing right and the y axis pointing down
+	fn matrix(self) -> [[i8; 2]; 2] {
+		match self {
+			Orientation::Deg0 => [[1, 0], [0, 1]],
+			Orientation::Deg0HorFlip => [[-1, 0], [0, 1]],
+			Orientation::Deg180 => [[-1, 0], [0, -1]],
+			Orientation::Deg180HorFlip => [[1, 0], [0, -1]],
+			Orientation::Deg90 => [[0, 1], [-1, 0]],
+			Orientation::Deg90VerFlip => [[0, -1], [-1, 0]],
+			Orientation::Deg270 => [[0, -1], [1, 0]],
+			Orientation::Deg270VerFlip => [[0, 1], [1, 0]],
+		}
+	}
+}
+
 /// Detects the format of an image file. It looks at the first 512 bytes;
 /// if that fails, it uses the file ending.
 pub fn detect_format(path: &Path) -> Result<ImgFormat> {
+	detect_format_of_file(path).map(|(format, _)| format)
+}
+
+/// Like `detect_format`, but also returns the file that was opened to look at its first bytes,
+/// rewound to its start, so that the decoder doesn't have to open it again. There's no file
+/// for the formats that are only recognized by the file ending.
+pub fn detect_format_of_file(path: &Path) -> Result<(ImgFormat, Option<fs::File>)> {
+	if archive::is_comic_book(path) {
+		return Ok((ImgFormat::ComicBook, None));
+	}
+	if archive::is_openraster(path) {
+		return Ok((ImgFormat::OpenRaster, None));
+	}
+	// CR2 and NEF files begin like TIFF files
+	if raw_thumbnail::is_raw(path) {
+		return Ok((ImgFormat::Raw, None));
+	}
 	let mut file = fs::File::open(path)?;
+	let format = detect_format_of_start(path, &mut file)?;
+	file.seek(SeekFrom::Start(0))?;
+	Ok((format, Some(file)))
+}
+
+fn detect_format_of_start(path: &Path, file: &mut fs::File) -> Result<ImgFormat> {
 	let mut file_start_bytes = [0; 512];
 
 	// Try to detect the format from the first 512 bytes
-	if file.read_exact(&mut file_start_bytes).is_ok() {
+	let read_len = file.by_ref().take(512).read(&mut file_start_bytes)?;
+	if archive::is_openraster_data(&file_start_bytes[..read_len]) {
+		return Ok(ImgFormat::OpenRaster);
+	}
+	if farbfeld::is_farbfeld(&file_start_bytes[..read_len]) {
+		return Ok(ImgFormat::Farbfeld);
+	}
+	// The image crate recognizes DDS files but can't decode them
+	if dds::is_dds(&file_start_bytes[..read_len]) {
+		return Ok(ImgFormat::Dds);
+	}
+	if icns::is_icns(&file_start_bytes[..read_len]) {
+		return Ok(ImgFormat::Icns);
+	}
+	if postscript::is_postscript(&file_start_bytes[..read_len]) {
+		return Ok(ImgFormat::PostScript);
+	}
+	#[cfg(feature = "jpeg2000")]
+	if jpeg2000::is_jpeg2000(&file_start_bytes[..read_len]) {
+		return Ok(ImgFormat::Jpeg2000);
+	}
+	if read_len == file_start_bytes.len() {
 		if path.extension() == Some(std::ffi::OsStr::new("svg")) {
 			return Ok(ImgFormat::Svg);
 		}
@@ -98,9 +328,36 @@ pub fn detect_format(path: &Path) -> Result<ImgFormat> {
 			return Ok(ImgFormat::Image(format));
 		}
 	}
+	// The X bitmap formats are small enough to fit in less than 512 bytes
+	let file_start_bytes = &file_start_bytes[..read_len];
+	if xpm::is_xpm(file_start_bytes) {
+		return Ok(ImgFormat::Xpm);
+	}
+	if xbm::is_xbm(file_start_bytes) {
+		return Ok(ImgFormat::Xbm);
+	}
 
 	// If that didn't work, try to detect the format from the file ending
-	Ok(ImgFormat::Image(ImageFormat::from_path(path)?))
+	format_from_extension(path)
+}
+
+/// The format that the file ending of the path stands for
+pub fn format_from_extension(path: &Path) -> Result<ImgFormat> {
+	let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+	match extension.as_deref() {
+		Some("xbm") => Ok(ImgFormat::Xbm),
+		Some("xpm") => Ok(ImgFormat::Xpm),
+		Some("ff") => Ok(ImgFormat::Farbfeld),
+		Some("dds") => Ok(ImgFormat::Dds),
+		Some("icns") => Ok(ImgFormat::Icns),
+		Some("eps") | Some("ps") => Ok(ImgFormat::PostScript),
+		Some("cbz") => Ok(ImgFormat::ComicBook),
+		Some("ora") => Ok(ImgFormat::OpenRaster),
+		Some(extension) if raw_thumbnail::EXTENSIONS.contains(&extension) => Ok(ImgFormat::Raw),
+		#[cfg(feature = "jpeg2000")]
+		Some("jp2") | Some("j2k") | Some("j2c") => Ok(ImgFormat::Jpeg2000),
+		_ => Ok(ImgFormat::Image(ImageFormat::from_path(path)?)),
+	}
 }
 
 pub fn detect_orientation(path: &Path) -> std::result::Result<Orientation, exif::Error> {
@@ -122,7 +379,9 @@ pub fn detect_orientation(path: &Path) -> std::result::Result<Orientation, exif:
 					7 => Ok(Orientation::Deg270VerFlip),
 					8 => Ok(Orientation::Deg90),
 					_ => {
-						eprintln!("Invalid Exif orientation. Using default orientation.");
+						if log_enabled(LOG_LEVEL_NORMAL) {
+							eprintln!("Invalid Exif orientation. Using default orientation.");
+						}
 						Ok(Orientation::Deg0)
 					}
 				}
@@ -137,19 +396,114 @@ pub fn detect_orientation(path: &Path) -> std::result::Result<Orientation, exif:
 	}
 }
 
+/// The bytes of the JPEG thumbnail in the Exif data of a JPEG file, which is pointed to by the
+/// `JPEGInterchangeFormat` tag of IFD1. Returns `None` for every other format.
+///
+/// `file` is the one that `detect_format_of_file` opened. It's rewound to its start afterwards.
+pub fn embedded_thumbnail(format: &ImgFormat, file: &mut fs::File) -> Option<Vec<u8>> {
+	if !matches!(format, ImgFormat::Image(ImageFormat::Jpeg)) {
+		return None;
+	}
+	let thumbnail = read_embedded_thumbnail(file);
+	file.seek(SeekFrom::Start(0)).ok()?;
+	thumbnail
+}
+
+fn read_embedded_thumbnail(file: &mut fs::File) -> Option<Vec<u8>> {
+	let mut reader = BufReader::new(file);
+	let exif = exif::Reader::new().read_from_container(&mut reader).ok()?;
+	let field = |tag| exif.get_field(tag, exif::In::THUMBNAIL)?.value.get_uint(0);
+	let offset = field(exif::Tag::JPEGInterchangeFormat)? as usize;
+	let len = field(exif::Tag::JPEGInterchangeFormatLength)? as usize;
+	// The offset is counted from the start of the TIFF header, which is where the buffer starts
+	let thumbnail = exif.buf().get(offset..offset.checked_add(len)?)?;
+	thumbnail.starts_with(&[0xFF, 0xD8]).then(|| thumbnail.to_vec())
+}
+
+/// Reads the dimensions of the image from the file header, without decoding the image.
+///
+/// Returns `None` for formats where the dimensions can't be determined this way.
+///
+/// `file` is the one that `detect_format_of_file` opened. It's rewound to its start afterwards.
+pub fn detect_dimensions(format: &ImgFormat, file: &mut fs::File) -> Option<(u32, u32)> {
+	let dimensions = read_dimensions(format, file);
+	file.seek(SeekFrom::Start(0)).ok()?;
+	dimensions
+}
+
+fn read_dimensions(format: &ImgFormat, file: &mut fs::File) -> Option<(u32, u32)> {
+	match *format {
+		ImgFormat::Image(format) => {
+			let reader = BufReader::new(file);
+			image::ImageReader::with_format(reader, format).into_dimensions().ok()
+		}
+		ImgFormat::Farbfeld => {
+			let mut header = Vec::new();
+			file.take(16).read_to_end(&mut header).ok()?;
+			farbfeld::read_dimensions(&header)
+		}
+		ImgFormat::Dds => {
+			let mut header = Vec::new();
+			file.take(20).read_to_end(&mut header).ok()?;
+			dds::read_dimensions(&header)
+		}
+		ImgFormat::Icns => {
+			let mut data = Vec::new();
+			file.read_to_end(&mut data).ok()?;
+			icns::read_dimensions(&data)
+		}
+		ImgFormat::Svg
+		| ImgFormat::Xbm
+		| ImgFormat::Xpm
+		| ImgFormat::PostScript
+		| ImgFormat::Raw
+		| ImgFormat::ComicBook
+		| ImgFormat::OpenRaster => None,
+		#[cfg(feature = "jpeg2000")]
+		ImgFormat::Jpeg2000 => None,
+	}
+}
+
+/// Returns an error if the image has more pixels than `MAX_IMAGE_PIXELS` allows
+pub fn check_image_size(dimensions: Option<(u32, u32)>) -> Result<()> {
+	let max_pixels = MAX_IMAGE_PIXELS.load(Ordering::Relaxed);
+	if let Some((w, h)) = dimensions {
+		let pixels = w as u64 * h as u64;
+		if max_pixels > 0 && pixels > max_pixels {
+			return Err(ImageLoaderError {
+				description: format!(
+					"The image is {}x{} ({:.1} megapixels) which exceeds the maximum image size \
+					of {:.1} megapixels",
+					w,
+					h,
+					pixels as f64 / 1_000_000.0,
+					max_pixels as f64 / 1_000_000.0
+				)
+				.into(),
+			});
+		}
+	}
+	Ok(())
+}
+
 pub fn simple_load_image(path: &Path, image_format: ImageFormat) -> Result<image::RgbaImage> {
-	let reader = BufReader::new(fs::File::open(path)?);
+	load_image_from_file(fs::File::open(path)?, image_format)
+}
+
+/// Like `simple_load_image`, for a file that's open at its start
+fn load_image_from_file(file: fs::File, image_format: ImageFormat) -> Result<image::RgbaImage> {
+	let reader = BufReader::new(file);
 	let mut image_reader = image::ImageReader::with_format(reader, image_format);
 	image_reader.no_limits();
 	Ok(image_reader.decode()?.into_rgba8())
 }
 
-/// Returns an iterator over the animation frames of a GIF file
-pub fn load_gif(path: &Path, req_id: u32) -> Result<impl Iterator<Item = Result<LoadResult>>> {
-	let file = fs::File::open(path)?;
+/// Returns an iterator over the animation frames of a GIF file that's open at its start
+pub fn load_gif(file: fs::File, req_id: u64) -> Result<impl Iterator<Item = Result<LoadResult>>> {
 	let reader = BufReader::new(file);
 	let decoder = GifDecoder::new(reader)?;
-	Ok(load_animation(req_id, decoder))
+	let canvas_size = decoder.dimensions();
+	Ok(load_animation(req_id, ImageFormat::Gif, canvas_size, decoder))
 }
 
 /// Parse, render and gather an SVG into a ImageBuffer<Rgba>
@@ -183,18 +537,41 @@ pub fn load_svg(path: &std::path::Path) -> Result<image::RgbaImage> {
 pub fn complex_load_image<F>(
 	path: &Path,
 	allow_animation: bool,
-	req_id: u32,
+	req_id: u64,
+	process_image: F,
+) -> Result<()>
+where
+	F: FnMut(LoadResult) -> Result<()>,
+{
+	let (image_format, file) = detect_format_of_file(path)?;
+	load_image_of_format(path, image_format, file, allow_animation, req_id, process_image)
+}
+
+/// Like `complex_load_image`, for a file whose format was already detected. `file` is the file
+/// that was opened for the detection, at its start, if there is one.
+pub fn load_image_of_format<F>(
+	path: &Path,
+	image_format: ImgFormat,
+	file: Option<fs::File>,
+	allow_animation: bool,
+	req_id: u64,
 	mut process_image: F,
 ) -> Result<()>
 where
 	F: FnMut(LoadResult) -> Result<()>,
 {
-	let image_format = detect_format(path)?;
+	if log_enabled(LOG_LEVEL_VERBOSE) {
+		eprintln!("Request #{}: Detected format {:?}", req_id, image_format);
+	}
 	let orientation = detect_orientation(path).unwrap_or(Orientation::Deg0);
+	let open = || match file {
+		Some(file) => Ok(file),
+		None => fs::File::open(path),
+	};
 
 	match image_format {
 		ImgFormat::Image(ImageFormat::Gif) => {
-			let mut frames = load_gif(path, req_id)?;
+			let mut frames = load_gif(open()?, req_id)?;
 			if allow_animation {
 				for frame in frames {
 					process_image(frame?)?;
@@ -204,11 +581,13 @@ where
 			}
 		}
 		ImgFormat::Image(ImageFormat::Png) => {
-			let file = fs::File::open(path)?;
-			let reader = BufReader::new(file);
-			let decoder = PngDecoder::new(reader)?;
+			let reader = BufReader::new(open()?);
+			// The same as `simple_load_image`, which doesn't limit the size either
+			let decoder = PngDecoder::with_limits(reader, image::Limits::no_limits())?;
 			if decoder.is_apng()? {
-				let mut animation = load_animation(req_id, decoder.apng()?);
+				let canvas_size = decoder.dimensions();
+				let mut animation =
+					load_animation(req_id, ImageFormat::Png, canvas_size, decoder.apng()?);
 				if allow_animation {
 					for frame in animation {
 						process_image(frame?)?;
@@ -217,57 +596,218 @@ where
 					process_image(frame?)?;
 				}
 			} else {
-				let image = simple_load_image(path, ImageFormat::Png)?;
-				process_image(LoadResult::Frame { req_id, image, delay_nano: 0, orientation })?;
+				let image = image::DynamicImage::from_decoder(decoder)?.into_rgba8();
+				process_image(still_frame(
+					req_id,
+					image,
+					orientation,
+					Some(ImageFormat::Png),
+					TextureColorSpace::Srgb,
+				))?;
 			}
 		}
 		ImgFormat::Image(image_format) => {
-			let image = simple_load_image(path, image_format)?;
-			process_image(LoadResult::Frame { req_id, image, delay_nano: 0, orientation })?;
+			let image = load_image_from_file(open()?, image_format)?;
+			process_image(still_frame(
+				req_id,
+				image,
+				orientation,
+				Some(image_format),
+				TextureColorSpace::of_format(image_format),
+			))?;
 		}
 		ImgFormat::Svg => {
 			let image = load_svg(path)?;
-			process_image(LoadResult::Frame { req_id, image, delay_nano: 0, orientation })?;
+			process_image(still_frame(req_id, image, orientation, None, TextureColorSpace::Srgb))?;
+		}
+		ImgFormat::Xbm => {
+			let image = xbm::decode_xbm(&fs::read(path)?)?;
+			process_image(still_frame(req_id, image, orientation, None, TextureColorSpace::Srgb))?;
+		}
+		ImgFormat::PostScript => {
+			let image = postscript::render_postscript(path)?;
+			process_image(still_frame(req_id, image, orientation, None, TextureColorSpace::Srgb))?;
+		}
+		#[cfg(feature = "jpeg2000")]
+		ImgFormat::Jpeg2000 => {
+			let image = jpeg2000::decode_jpeg2000(path)?;
+			process_image(still_frame(req_id, image, orientation, None, TextureColorSpace::Srgb))?;
+		}
+		ImgFormat::Xpm => {
+			let image = xpm::decode_xpm(&fs::read(path)?)?;
+			process_image(still_frame(req_id, image, orientation, None, TextureColorSpace::Srgb))?;
+		}
+		ImgFormat::Farbfeld => {
+			let image = farbfeld::decode_farbfeld(&fs::read(path)?)?;
+			process_image(still_frame(
+				req_id,
+				image,
+				orientation,
+				Some(ImageFormat::Farbfeld),
+				TextureColorSpace::Srgb,
+			))?;
+		}
+		ImgFormat::Dds => {
+			let image = dds::decode_dds(&fs::read(path)?)?;
+			process_image(still_frame(
+				req_id,
+				image,
+				orientation,
+				Some(ImageFormat::Dds),
+				TextureColorSpace::Srgb,
+			))?;
+		}
+		ImgFormat::Icns => {
+			let image = icns::decode_icns(&fs::read(path)?)?;
+			process_image(still_frame(req_id, image, orientation, None, TextureColorSpace::Srgb))?;
+		}
+		ImgFormat::Raw => {
+			let image = raw_thumbnail::decode_preview(fs::read(path)?)?;
+			process_image(still_frame(req_id, image, orientation, None, TextureColorSpace::Srgb))?;
+		}
+		ImgFormat::ComicBook => {
+			let cover = archive::read_cover(path)?;
+			let source_format = image::guess_format(&cover).ok();
+			let image = image::load_from_memory(&cover)?.into_rgba8();
+			process_image(still_frame(
+				req_id,
+				image,
+				orientation,
+				source_format,
+				TextureColorSpace::Srgb,
+			))?;
+		}
+		ImgFormat::OpenRaster => {
+			let merged = archive::read_merged_image(path)?;
+			let image = image::load_from_memory_with_format(&merged, ImageFormat::Png)?;
+			process_image(still_frame(
+				req_id,
+				image.into_rgba8(),
+				orientation,
+				Some(ImageFormat::Png),
+				TextureColorSpace::Srgb,
+			))?;
 		}
 	}
 
 	Ok(())
 }
 
+/// The result of an image that has a single frame
+fn still_frame(
+	req_id: u64,
+	image: image::RgbaImage,
+	orientation: Orientation,
+	source_format: Option<ImageFormat>,
+	color_space: TextureColorSpace,
+) -> LoadResult {
+	LoadResult::Frame {
+		req_id,
+		created_at: SystemTime::now(),
+		batch_id: None,
+		frame_index: 0,
+		image,
+		delay_nano: 0,
+		orientation,
+		source_format,
+		color_space,
+	}
+}
+
+/// Places the frames of an animation that only cover a part of the canvas onto the previous
+/// frame, at their offset. The GIF and APNG decoders of the image crate already hand out
+/// frames of the whole canvas, those are passed through.
+pub struct FrameCompositor {
+	canvas_size: (u32, u32),
+	/// The previous frame. It's allocated once and each frame is drawn into it, so only the
+	/// frames that are handed out are copies.
+	canvas: Option<image::RgbaImage>,
+}
+
+impl FrameCompositor {
+	pub fn new(canvas_size: (u32, u32)) -> FrameCompositor {
+		FrameCompositor { canvas_size, canvas: None }
+	}
+
+	/// Returns the frame as it looks on the canvas
+	pub fn composite(&mut self, frame: image::Frame) -> image::RgbaImage {
+		let (left, top) = (frame.left(), frame.top());
+		let buffer = frame.into_buffer();
+		if (left, top) == (0, 0) && buffer.dimensions() == self.canvas_size {
+			match &mut self.canvas {
+				Some(canvas) => canvas.copy_from_slice(&buffer),
+				None => self.canvas = Some(buffer.clone()),
+			}
+			return buffer;
+		}
+		let (width, height) = self.canvas_size;
+		let canvas = self.canvas.get_or_insert_with(|| image::RgbaImage::new(width, height));
+		image::imageops::overlay(canvas, &buffer, left as i64, top as i64);
+		canvas.clone()
+	}
+}
+
 fn load_animation(
-	req_id: u32,
+	req_id: u64,
+	source_format: ImageFormat,
+	canvas_size: (u32, u32),
 	decoder: impl AnimationDecoder<'static>,
 ) -> impl Iterator<Item = Result<LoadResult>> {
 	let frames = decoder.into_frames();
+	let mut compositor = FrameCompositor::new(canvas_size);
 
-	frames.map(move |frame| {
+	frames.enumerate().map(move |(frame_index, frame)| {
 		Ok(frame.map(|frame| {
-			let (mut numerator_ms, mut denom) = frame.delay().numer_denom_ms();
-			if numerator_ms == 0 {
-				// Some animated gifs specify a 0 inter-frame delay, but
-				// most gif viewers interpret this as a 0.1 second delay.
-				numerator_ms = 100;
-				denom = 1;
+			let (numerator_ms, denom_ms) = frame.delay().numer_denom_ms();
+			let delay_nano = delay_nano(numerator_ms, denom_ms);
+			let image = compositor.composite(frame);
+			LoadResult::Frame {
+				req_id,
+				created_at: SystemTime::now(),
+				batch_id: None,
+				frame_index: frame_index as u32,
+				image,
+				delay_nano,
+				orientation: Orientation::Deg0,
+				source_format: Some(source_format),
+				color_space: TextureColorSpace::Srgb,
 			}
-			let numerator_nano = numerator_ms as u64 * 1_000_000;
-			let delay_nano = numerator_nano / (denom as u64);
-			let image = frame.into_buffer();
-			LoadResult::Frame { req_id, image, delay_nano, orientation: Orientation::Deg0 }
 		})?)
 	})
 }
 
+/// The frame delay used when an animation doesn't specify a valid one
+const DEFAULT_FRAME_DELAY_MS: u64 = 100;
+
+/// Converts a frame delay of `numerator_ms / denom_ms` milliseconds to nanoseconds
+pub fn delay_nano(numerator_ms: u32, denom_ms: u32) -> u64 {
+	if numerator_ms == 0 || denom_ms == 0 {
+		// Some animated gifs specify a 0 inter-frame delay, but
+		// most gif viewers interpret this as a 0.1 second delay.
+		return DEFAULT_FRAME_DELAY_MS * 1_000_000;
+	}
+	// This can't overflow because the numerator is at most `u32::MAX`
+	let numerator_nano = numerator_ms as u64 * 1_000_000;
+	numerator_nano / denom_ms as u64
+}
+
 pub fn is_file_supported(filename: &Path) -> bool {
 	if let Some(ext) = filename.extension() {
 		if let Some(ext) = ext.to_str() {
 			let ext = ext.to_lowercase();
 			match ext.as_str() {
 				"jpg" | "jpeg" | "png" | "apng" | "gif" | "webp" | "tif" | "tiff" | "tga"
-				| "bmp" | "ico" | "hdr" | "pbm" | "pam" | "ppm" | "pgm" => {
+				| "bmp" | "ico" | "hdr" | "pbm" | "pam" | "ppm" | "pgm" | "xbm" | "xpm" | "ff"
+				| "dds" | "icns" | "cbz" | "ora" | "cr2" | "nef" | "arw" => {
 					return true;
 				}
+				"eps" | "ps" => {
+					return postscript::ghostscript_available();
+				}
 				#[cfg(feature = "avif")]
 				"avif" => return true,
+				#[cfg(feature = "jpeg2000")]
+				"jp2" | "j2k" | "j2c" => return true,
 				_ => (),
 			}
 		}
@@ -275,69 +815,331 @@ pub fn is_file_supported(filename: &Path) -> bool {
 	detect_format(filename).is_ok()
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct LoadRequest {
-	pub req_id: u32,
+	pub req_id: u64,
 	pub path: PathBuf,
+	/// The batch that the request was sent in with `ImageLoader::send_batch_request`, if any
+	pub batch_id: Option<u32>,
 }
 
+/// The number of requests that a pool allocates up front
+const PREALLOCATED_REQUESTS: usize = 32;
+/// The most requests that a pool keeps, the others are freed when they are released
+const MAX_POOLED_REQUESTS: usize = 256;
+/// The capacity of the paths of the preallocated requests, which fits most paths
+const PREALLOCATED_PATH_LEN: usize = 256;
+
+/// Keeps the requests that the workers are done with, so that the paths of the following
+/// requests are copied into the buffers of the old ones instead of new allocations. The cache
+/// sends a request for every image it prefetches when navigating quickly.
+#[derive(Clone)]
+pub struct LoadRequestPool {
+	free: Arc<Mutex<Vec<LoadRequest>>>,
+}
+
+impl LoadRequestPool {
+	pub fn new() -> LoadRequestPool {
+		let free = (0..PREALLOCATED_REQUESTS)
+			.map(|_| LoadRequest {
+				path: PathBuf::with_capacity(PREALLOCATED_PATH_LEN),
+				..LoadRequest::default()
+			})
+			.collect();
+		LoadRequestPool { free: Arc::new(Mutex::new(free)) }
+	}
+
+	/// A request with the id 0, an empty path and no batch, which goes back to the pool when
+	/// it's dropped
+	pub fn acquire(&self) -> PooledRequest {
+		let request = self.free.lock().unwrap().pop().unwrap_or_default();
+		PooledRequest { request, pool: self.clone() }
+	}
+
+	/// Wraps a request that was allocated elsewhere, so that it joins the pool when it's dropped
+	pub fn adopt(&self, request: LoadRequest) -> PooledRequest {
+		PooledRequest { request, pool: self.clone() }
+	}
+
+	fn release(&self, mut request: LoadRequest) {
+		let mut free = self.free.lock().unwrap();
+		if free.len() < MAX_POOLED_REQUESTS {
+			request.path.as_mut_os_string().clear();
+			request.req_id = 0;
+			request.batch_id = None;
+			free.push(request);
+		}
+	}
+}
+
+impl Default for LoadRequestPool {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// A request from a `LoadRequestPool`, which is released back into it on drop
+pub struct PooledRequest {
+	request: LoadRequest,
+	pool: LoadRequestPool,
+}
+
+impl std::ops::Deref for PooledRequest {
+	type Target = LoadRequest;
+
+	fn deref(&self) -> &LoadRequest {
+		&self.request
+	}
+}
+
+impl std::ops::DerefMut for PooledRequest {
+	fn deref_mut(&mut self) -> &mut LoadRequest {
+		&mut self.request
+	}
+}
+
+impl Drop for PooledRequest {
+	fn drop(&mut self) {
+		// The empty request that's left in place doesn't allocate
+		self.pool.release(std::mem::take(&mut self.request));
+	}
+}
+
+/// How long the steps of a load took on the worker thread
+#[derive(Debug, Default, Copy, Clone)]
+pub struct LoadTimings {
+	/// Reading the metadata of the file
+	pub file_read: Duration,
+	pub format_detection: Duration,
+	/// Reading the dimensions and the embedded thumbnail from the header of the file
+	pub header_read: Duration,
+	/// Decoding every frame, including the color conversion. The decoders read the pixel data
+	/// of the file while decoding, so that's part of this too.
+	pub decode: Duration,
+}
+
+/// What a worker sends about a request. Every result has the time it was made at, so that the
+/// receiver can tell the ones that waited long in the channel from the fresh ones, and the
+/// batch of its request.
 pub enum LoadResult {
 	Start {
-		req_id: u32,
+		req_id: u64,
+		batch_id: Option<u32>,
+		created_at: SystemTime,
 		metadata: fs::Metadata,
+		/// The width and height of the image if it could be determined before decoding it
+		dimensions: Option<(u32, u32)>,
+		/// The JPEG thumbnail that's embedded in the Exif data of a JPEG, which can be shown
+		/// until the image itself is decoded
+		thumbnail: Option<Vec<u8>>,
+		/// The name of the format that the file was detected as, see `ImgFormat::name`
+		detected_format: Option<Cow<'static, str>>,
 	},
 	Frame {
-		req_id: u32,
+		req_id: u64,
+		batch_id: Option<u32>,
+		created_at: SystemTime,
+		/// The position of the frame within the animation, starting from zero
+		frame_index: u32,
 		image: image::RgbaImage,
 		delay_nano: u64,
 
 		/// How much does the image need to be rotated counter-clockwise to be shown correctly
 		orientation: Orientation,
+		/// The format of the file, or of the cover for comic books. `None` for the formats that
+		/// the image crate doesn't know, like SVG.
+		source_format: Option<ImageFormat>,
+		color_space: TextureColorSpace,
 	},
 	Done {
-		req_id: u32,
+		req_id: u64,
+		batch_id: Option<u32>,
+		created_at: SystemTime,
+		timings: LoadTimings,
 	},
 	Failed {
-		req_id: u32,
+		req_id: u64,
+		batch_id: Option<u32>,
+		created_at: SystemTime,
+		/// Why the image couldn't be loaded, to show to the user
+		error: String,
+	},
+	/// The request was cancelled with `ImageLoader::cancel_request` before it finished
+	Aborted {
+		req_id: u64,
+		batch_id: Option<u32>,
+		created_at: SystemTime,
 	},
 }
 
 impl LoadResult {
-	pub fn req_id(&self) -> u32 {
+	pub fn req_id(&self) -> u64 {
 		match self {
 			LoadResult::Start { req_id, .. } => *req_id,
 			LoadResult::Frame { req_id, .. } => *req_id,
 			LoadResult::Done { req_id, .. } => *req_id,
 			LoadResult::Failed { req_id, .. } => *req_id,
+			LoadResult::Aborted { req_id, .. } => *req_id,
+		}
+	}
+
+	pub fn batch_id(&self) -> Option<u32> {
+		match self {
+			LoadResult::Start { batch_id, .. }
+			| LoadResult::Frame { batch_id, .. }
+			| LoadResult::Done { batch_id, .. }
+			| LoadResult::Failed { batch_id, .. }
+			| LoadResult::Aborted { batch_id, .. } => *batch_id,
+		}
+	}
+
+	/// The wall-clock time when the worker made the result
+	pub fn created_at(&self) -> SystemTime {
+		match self {
+			LoadResult::Start { created_at, .. }
+			| LoadResult::Frame { created_at, .. }
+			| LoadResult::Done { created_at, .. }
+			| LoadResult::Failed { created_at, .. }
+			| LoadResult::Aborted { created_at, .. } => *created_at,
+		}
+	}
+
+	/// Returns true if the result was made more than `max_age` ago. A clock that was set back
+	/// since makes results look fresh rather than stale.
+	pub fn is_stale(&self, max_age: Duration) -> bool {
+		self.created_at().elapsed().is_ok_and(|age| age > max_age)
+	}
+}
+
+type PerceptualHashes = Arc<Mutex<Option<HashMap<PathBuf, u64>>>>;
+
+/// The request ids of the batches are counted from here, so that they don't collide with the
+/// ids that the user of the loader picks for the single requests
+const FIRST_BATCH_REQUEST_ID: u64 = 1 << 63;
+
+/// Receives the results on the worker threads instead of the channel, see
+/// `ImageLoader::set_callback`
+pub type ResultCallback = Arc<dyn Fn(LoadResult) + Send + Sync>;
+
+/// The callback that's shared by the loader and the workers
+#[derive(Default)]
+struct CallbackSlot {
+	/// Whether `callback` is set, so that the workers only lock it when there's a callback
+	is_set: AtomicBool,
+	callback: Mutex<Option<ResultCallback>>,
+}
+
+impl CallbackSlot {
+	fn get(&self) -> Option<ResultCallback> {
+		if !self.is_set.load(Ordering::Acquire) {
+			return None;
+		}
+		self.callback.lock().unwrap().clone()
+	}
+
+	fn set(&self, callback: Option<ResultCallback>) {
+		let is_set = callback.is_some();
+		*self.callback.lock().unwrap() = callback;
+		self.is_set.store(is_set, Ordering::Release);
+	}
+}
+
+/// Where the workers deliver their results: to the callback if one is set, otherwise to the
+/// channel
+#[derive(Clone)]
+struct ResultSink {
+	channel: crossbeam_channel::Sender<LoadResult>,
+	callback: Arc<CallbackSlot>,
+	cancelled: Arc<Mutex<HashSet<u64>>>,
+}
+
+/// The receiving end of the result channel was dropped
+#[derive(Debug)]
+struct Disconnected;
+
+impl ResultSink {
+	fn send(&self, result: LoadResult) -> std::result::Result<(), Disconnected> {
+		let callback = match self.callback.get() {
+			Some(callback) => callback,
+			None => return self.channel.send(result).map_err(|_| Disconnected),
+		};
+		if let LoadResult::Done { req_id, .. }
+		| LoadResult::Failed { req_id, .. }
+		| LoadResult::Aborted { req_id, .. } = &result
+		{
+			// The receiver doesn't see the end of the request to do this
+			self.cancelled.lock().unwrap().remove(req_id);
 		}
+		callback(result);
+		Ok(())
 	}
 }
 
 pub struct ImageLoader {
 	running: Arc<AtomicBool>,
 	join_handles: Option<Vec<thread::JoinHandle<()>>>,
-	image_rx: Receiver<LoadResult>,
-	path_tx: Sender<LoadRequest>,
+	image_rx: crossbeam_channel::Receiver<LoadResult>,
+	path_tx: Sender<PooledRequest>,
+	request_pool: LoadRequestPool,
+	/// The ids of the cancelled requests that haven't ended yet. The workers check it before
+	/// loading a request and after every frame.
+	cancelled: Arc<Mutex<HashSet<u64>>>,
+	/// The number of requests that no worker has started loading yet
+	queued: Arc<AtomicUsize>,
+	/// Called by the workers with every result while it's set, instead of sending it
+	callback: Arc<CallbackSlot>,
+	/// The perceptual hashes of the first frames of the loaded files, or `None` if they aren't
+	/// computed
+	perceptual_hashes: PerceptualHashes,
+	/// The number of submitted requests for which no `Done`, `Failed` or `Aborted` result was
+	/// received yet
+	unfinished_requests: usize,
+	next_batch_id: u32,
+	next_batch_req_id: u64,
+	/// The results that were received while draining a batch but belong to something else.
+	/// They are returned before the ones in the channel.
+	held_back: VecDeque<LoadResult>,
 }
 
 impl ImageLoader {
 	/// # Arguemnts
-	/// * `capacity` - Number of bytes. The last image loaded will be the one at which the allocated memory reaches or exceeds capacity
-	pub fn new(threads: u32) -> ImageLoader {
+	/// * `threads` - Number of worker threads that decode images
+	/// * `capacity` - Maximum number of results waiting to be received. A worker blocks when
+	///   the results are full, so that a long animation can't get ahead of the UI by too much.
+	pub fn new(threads: u32, capacity: usize) -> ImageLoader {
 		let running = Arc::new(AtomicBool::from(true));
 		let (load_request_tx, load_request_rx) = channel();
 		let load_request_rx = Arc::new(Mutex::new(load_request_rx));
 
-		let (loaded_img_tx, loaded_img_rx) = channel();
+		let (loaded_img_tx, loaded_img_rx) = crossbeam_channel::bounded(capacity);
+		let cancelled = Arc::new(Mutex::new(HashSet::new()));
+		let queued = Arc::new(AtomicUsize::new(0));
+		let perceptual_hashes = Arc::new(Mutex::new(None));
+		let callback = Arc::new(CallbackSlot::default());
 
 		let mut join_handles = Vec::new();
 		for _ in 0..threads {
 			let running = running.clone();
 			let request_recv = load_request_rx.clone();
 			let request_send = load_request_tx.clone();
-			let img_sender = loaded_img_tx.clone();
+			let img_sender = ResultSink {
+				channel: loaded_img_tx.clone(),
+				callback: Arc::clone(&callback),
+				cancelled: cancelled.clone(),
+			};
+			let cancelled = cancelled.clone();
+			let queued = queued.clone();
+			let perceptual_hashes = perceptual_hashes.clone();
 			join_handles.push(thread::spawn(move || {
-				Self::thread_loop(running, request_recv, request_send, img_sender);
+				Self::thread_loop(
+					running,
+					request_recv,
+					request_send,
+					img_sender,
+					cancelled,
+					queued,
+					perceptual_hashes,
+				);
 			}));
 		}
 
@@ -347,14 +1149,26 @@ impl ImageLoader {
 
 			image_rx: loaded_img_rx,
 			path_tx: load_request_tx,
+			request_pool: LoadRequestPool::new(),
+			cancelled,
+			queued,
+			callback,
+			perceptual_hashes,
+			unfinished_requests: 0,
+			next_batch_id: 0,
+			next_batch_req_id: FIRST_BATCH_REQUEST_ID,
+			held_back: VecDeque::new(),
 		}
 	}
 
 	fn thread_loop(
 		running: Arc<AtomicBool>,
-		request_recv: Arc<Mutex<Receiver<LoadRequest>>>,
-		request_send: Sender<LoadRequest>,
-		img_sender: Sender<LoadResult>,
+		request_recv: Arc<Mutex<Receiver<PooledRequest>>>,
+		request_send: Sender<PooledRequest>,
+		img_sender: ResultSink,
+		cancelled: Arc<Mutex<HashSet<u64>>>,
+		queued: Arc<AtomicUsize>,
+		perceptual_hashes: PerceptualHashes,
 	) {
 		// The size was an arbitrary choice made with the argument that this should be
 		// enough to fit enough image file info to determine the format.
@@ -367,6 +1181,10 @@ impl ImageLoader {
 				let load_request = request_recv.lock().unwrap();
 				let priority = PRIORITY_REQUEST_ID.load(Ordering::SeqCst);
 				request = load_request.recv().unwrap();
+				if !running.load(Ordering::Acquire) {
+					// The request is the one sent by `drop` to wake the worker up
+					break;
+				}
 				let focus_test_passed =
 					priority == request.req_id || priority == NON_EXISTENT_REQUEST_ID;
 				if !focus_test_passed {
@@ -378,55 +1196,305 @@ impl ImageLoader {
 					continue;
 				}
 			};
-			Self::load_and_send(&img_sender, request);
+			// Requests that were put back are still waiting, so only this counts as picked up
+			queued.fetch_sub(1, Ordering::Relaxed);
+			Self::load_and_send(&img_sender, &request, &cancelled, &perceptual_hashes);
 		}
 	}
 
 	pub fn try_recv_prefetched(&mut self) -> std::result::Result<LoadResult, TryRecvError> {
-		self.image_rx.try_recv()
+		if let Some(result) = self.held_back.pop_front() {
+			return Ok(result);
+		}
+		let result = self.image_rx.try_recv();
+		if let Ok(result) = &result {
+			self.track_finished(result);
+		}
+		result
+	}
+
+	/// Blocks until the next result is received. Returns `None` if the worker threads have stopped.
+	pub fn recv_result(&mut self) -> Option<LoadResult> {
+		if let Some(result) = self.held_back.pop_front() {
+			return Some(result);
+		}
+		let result = self.image_rx.recv().ok()?;
+		self.track_finished(&result);
+		Some(result)
 	}
 
 	pub fn send_load_request(&mut self, request: LoadRequest) {
+		let request = self.request_pool.adopt(request);
+		self.send_pooled_request(request);
+	}
+
+	/// A request to fill in and pass to `send_pooled_request`, which reuses the path buffer
+	/// of a request that was loaded before
+	pub fn acquire_request(&self) -> PooledRequest {
+		self.request_pool.acquire()
+	}
+
+	pub fn send_pooled_request(&mut self, request: PooledRequest) {
+		// Counted before sending, so that a worker can't pick it up before it's counted
+		self.queued.fetch_add(1, Ordering::Relaxed);
 		self.path_tx.send(request).unwrap();
+		self.unfinished_requests += 1;
 	}
 
-	fn load_and_send(img_sender: &Sender<LoadResult>, request: LoadRequest) {
-		fn try_load_and_send(img_sender: &Sender<LoadResult>, request: &LoadRequest) -> Result<()> {
+	/// Sends a request for each of the paths, with request ids picked by the loader. Returns
+	/// the id of the batch, which is in every result of these requests.
+	pub fn send_batch_request(&mut self, paths: Vec<PathBuf>) -> u32 {
+		let batch_id = self.next_batch_id;
+		self.next_batch_id = self.next_batch_id.wrapping_add(1);
+		for path in paths {
+			let req_id = self.next_batch_req_id;
+			self.next_batch_req_id += 1;
+			let mut request = self.acquire_request();
+			request.req_id = req_id;
+			request.path.push(path);
+			request.batch_id = Some(batch_id);
+			self.send_pooled_request(request);
+		}
+		batch_id
+	}
+
+	/// Takes the results of the batch that were received so far, without waiting for the
+	/// rest. The results of other requests are kept for `try_recv_prefetched` and
+	/// `recv_result`.
+	pub fn drain_batch_results(&mut self, batch_id: u32) -> Vec<LoadResult> {
+		while let Ok(result) = self.image_rx.try_recv() {
+			self.track_finished(&result);
+			self.held_back.push_back(result);
+		}
+		let (batch, others): (VecDeque<_>, _) =
+			self.held_back.drain(..).partition(|result| result.batch_id() == Some(batch_id));
+		self.held_back = others;
+		batch.into()
+	}
+
+	/// The number of submitted requests that are waiting for a worker. The ones that are
+	/// being loaded aren't included.
+	pub fn queue_depth(&self) -> usize {
+		self.queued.load(Ordering::Relaxed)
+	}
+
+	/// The number of results that were sent by the workers but not received yet
+	pub fn pending_results(&self) -> usize {
+		self.image_rx.len() + self.held_back.len()
+	}
+
+	/// Makes the workers call `callback` with every result from now on, on their own thread as
+	/// soon as the result is made, instead of sending it through the channel. Several workers
+	/// can call it at the same time, and the results that were sent before stay in the channel.
+	///
+	/// While it's set, the results don't reach `try_recv_prefetched`, `recv_result` or the
+	/// iterator, which ends right away.
+	// The viewer polls the channel, the callback is for the other users of the loader
+	#[allow(dead_code)]
+	pub fn set_callback(&self, callback: ResultCallback) {
+		self.callback.set(Some(callback));
+	}
+
+	/// Sends the results through the channel again
+	#[allow(dead_code)]
+	pub fn clear_callback(&self) {
+		self.callback.set(None);
+	}
+
+	/// Stops loading the request. The worker ends it with `Aborted` instead of `Done` or
+	/// `Failed`, unless it already finished. The frames sent before the worker noticed are
+	/// still received.
+	pub fn cancel_request(&self, req_id: u64) {
+		self.cancelled.lock().unwrap().insert(req_id);
+	}
+
+	/// Starts computing the perceptual hash of the first frame of every file that's loaded
+	/// from now on. The hashes are kept until the loader is dropped.
+	pub fn enable_perceptual_hashes(&self) {
+		self.perceptual_hashes.lock().unwrap().get_or_insert_with(HashMap::new);
+	}
+
+	/// The perceptual hash of the file, if it was loaded since the hashes were enabled
+	pub fn perceptual_hash(&self, path: &Path) -> Option<u64> {
+		self.perceptual_hashes.lock().unwrap().as_ref()?.get(path).copied()
+	}
+
+	fn track_finished(&mut self, result: &LoadResult) {
+		if let LoadResult::Done { req_id, .. }
+		| LoadResult::Failed { req_id, .. }
+		| LoadResult::Aborted { req_id, .. } = result
+		{
+			self.unfinished_requests = self.unfinished_requests.saturating_sub(1);
+			// Cancelling a request that has already ended leaves its id behind
+			self.cancelled.lock().unwrap().remove(req_id);
+		}
+	}
+
+	fn load_and_send(
+		img_sender: &ResultSink,
+		request: &LoadRequest,
+		cancelled: &Mutex<HashSet<u64>>,
+		perceptual_hashes: &Mutex<Option<HashMap<PathBuf, u64>>>,
+	) {
+		let is_cancelled = || cancelled.lock().unwrap().contains(&request.req_id);
+		fn try_load_and_send(
+			img_sender: &ResultSink,
+			request: &LoadRequest,
+			is_cancelled: &dyn Fn() -> bool,
+			perceptual_hashes: &Mutex<Option<HashMap<PathBuf, u64>>>,
+		) -> Result<LoadTimings> {
+			let start = Instant::now();
 			let metadata = fs::metadata(&request.path)?;
-			img_sender.send(LoadResult::Start { req_id: request.req_id, metadata }).unwrap();
-			complex_load_image(&request.path, true, request.req_id, |frame| {
+			let file_read = start.elapsed();
+			let mut image_format = detect_format_of_file(&request.path);
+			let format_detection = start.elapsed() - file_read;
+			// The headers are read from the file that was opened to detect the format
+			let (dimensions, thumbnail) = match &mut image_format {
+				Ok((format, Some(file))) => {
+					(detect_dimensions(format, file), embedded_thumbnail(format, file))
+				}
+				_ => (None, None),
+			};
+			let header_read = start.elapsed() - file_read - format_detection;
+			img_sender
+				.send(LoadResult::Start {
+					req_id: request.req_id,
+					batch_id: request.batch_id,
+					created_at: SystemTime::now(),
+					metadata,
+					dimensions,
+					thumbnail,
+					detected_format: image_format.as_ref().ok().map(|(format, _)| format.name()),
+				})
+				.unwrap();
+			check_image_size(dimensions)?;
+			let (image_format, file) = image_format?;
+			let color_transform = color_management::transform_for(&request.path);
+			let mut frame_count = 0;
+			let load = |mut frame| {
+				frame_count += 1;
+				// Most of the formats that the dimensions couldn't be read from ahead are only
+				// checked here, once they are decoded
+				if let LoadResult::Frame { image, .. } = &frame {
+					check_image_size(Some(image.dimensions()))?;
+				}
+				if let LoadResult::Frame { batch_id, image, color_space, .. } = &mut frame {
+					*batch_id = request.batch_id;
+					// The linear images are left alone, the profiles are about the encoded ones
+					if let (Some(transform), TextureColorSpace::Srgb) =
+						(&color_transform, color_space)
+					{
+						color_management::apply(transform.as_ref(), image);
+					}
+				}
+				if let LoadResult::Frame { frame_index: 0, image, .. } = &frame {
+					if let Some(hashes) = perceptual_hashes.lock().unwrap().as_mut() {
+						hashes.insert(request.path.clone(), PerceptualHash::from_image(image).0);
+					}
+				}
 				img_sender.send(frame).unwrap();
+				if is_cancelled() {
+					return Err(ImageLoaderError {
+						description: "the request was cancelled".into(),
+					});
+				}
 				Ok(())
-			})?;
-			Ok(())
+			};
+			load_image_of_format(&request.path, image_format, file, true, request.req_id, load)?;
+			if log_enabled(LOG_LEVEL_VERBOSE) {
+				eprintln!("Request #{}: Loaded {} frame(s)", request.req_id, frame_count);
+			}
+			let decode = start.elapsed() - file_read - format_detection - header_read;
+			Ok(LoadTimings { file_read, format_detection, header_read, decode })
 		}
 
+		if is_cancelled() {
+			img_sender
+				.send(LoadResult::Aborted {
+					req_id: request.req_id,
+					batch_id: request.batch_id,
+					created_at: SystemTime::now(),
+				})
+				.unwrap();
+			return;
+		}
+		if log_enabled(LOG_LEVEL_VERBOSE) {
+			eprintln!("Request #{}: Loading {:?}", request.req_id, request.path);
+		}
+		let result = try_load_and_send(img_sender, request, &is_cancelled, perceptual_hashes);
 		img_sender
-			.send(match try_load_and_send(img_sender, &request) {
-				Ok(()) => LoadResult::Done { req_id: request.req_id },
+			.send(match result {
+				_ if is_cancelled() => {
+					if log_enabled(LOG_LEVEL_VERBOSE) {
+						eprintln!("Request #{}: Cancelled", request.req_id);
+					}
+					LoadResult::Aborted {
+						req_id: request.req_id,
+						batch_id: request.batch_id,
+						created_at: SystemTime::now(),
+					}
+				}
+				Ok(timings) => LoadResult::Done {
+					req_id: request.req_id,
+					batch_id: request.batch_id,
+					created_at: SystemTime::now(),
+					timings,
+				},
 				Err(error) => {
-					eprintln!(
-						"Request #{}: Error occurred while loading file {:?}\n    {}",
-						request.req_id, request.path, error,
-					);
-					LoadResult::Failed { req_id: request.req_id }
+					if log_enabled(LOG_LEVEL_NORMAL) {
+						eprintln!(
+							"Request #{}: Error occurred while loading file {:?}\n    {}",
+							request.req_id, request.path, error,
+						);
+					}
+					LoadResult::Failed {
+						req_id: request.req_id,
+						batch_id: request.batch_id,
+						created_at: SystemTime::now(),
+						error: error.description.into_owned(),
+					}
 				}
 			})
 			.unwrap();
 	}
 }
 
+/// Blocks on every item until it's received, and ends after the last submitted request has
+/// finished.
+impl Iterator for ImageLoader {
+	type Item = LoadResult;
+
+	fn next(&mut self) -> Option<LoadResult> {
+		if self.unfinished_requests == 0 && self.held_back.is_empty() {
+			return None;
+		}
+		if self.held_back.is_empty() && self.callback.get().is_some() {
+			// The end of the requests is never received
+			return None;
+		}
+		self.recv_result()
+	}
+}
+
 impl Drop for ImageLoader {
 	fn drop(&mut self) {
 		self.running.store(false, Ordering::Release);
 		if let Some(join_handles) = self.join_handles.take() {
 			for _ in join_handles.iter() {
-				self.path_tx.send(LoadRequest { req_id: 0, path: PathBuf::from("") }).unwrap();
+				self.path_tx.send(self.request_pool.acquire()).unwrap();
 			}
 
 			for handle in join_handles.into_iter() {
+				// A worker may be blocked on sending a result, so keep receiving
+				// until it gets to the end of its loop.
+				while !handle.is_finished() {
+					while self.image_rx.try_recv().is_ok() {}
+					thread::sleep(Duration::from_millis(1));
+				}
 				if let Err(err) = handle.join() {
-					eprintln!("Error occurred while joining handle {:?}", err);
+					if log_enabled(LOG_LEVEL_NORMAL) {
+						eprintln!("Error occurred while joining handle {:?}", err);
+					}
 				}
 			}
 		}
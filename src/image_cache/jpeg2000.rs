@@ -0,0 +1,48 @@
+//! Decodes JPEG 2000 files with `opj_decompress` from OpenJPEG, which is run as a separate
+//! program for every file. Only built with the `jpeg2000` feature.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use gelatin::image::{ImageFormat, RgbaImage};
+
+use super::image_loader::{simple_load_image, ImageLoaderError, Result};
+use super::scratch_file::ScratchFile;
+
+const OPJ_DECOMPRESS: &str = "opj_decompress";
+
+/// The signature box that every JP2 file starts with
+const JP2_MAGIC: &[u8] = &[0x00, 0x00, 0x00, 0x0C, 0x6A, 0x50, 0x20, 0x20];
+/// The start of codestream marker of a raw J2K codestream, followed by the size marker
+const J2K_MAGIC: &[u8] = &[0xFF, 0x4F, 0xFF, 0x51];
+
+pub fn is_jpeg2000(data: &[u8]) -> bool {
+	data.starts_with(JP2_MAGIC) || data.starts_with(J2K_MAGIC)
+}
+
+/// Decodes the file to a PNG in the temp folder and loads that
+pub fn decode_jpeg2000(path: &Path) -> Result<RgbaImage> {
+	let decoded = ScratchFile::create("emulsion-jp2", "png")?;
+	let mut command = Command::new(OPJ_DECOMPRESS);
+	command.arg("-i").arg(path).arg("-o").arg(&decoded.0);
+	let output = command.output().map_err(|e| ImageLoaderError {
+		description: format!("JPEG 2000 files can only be shown if OpenJPEG is installed: {}", e)
+			.into(),
+	})?;
+	if !output.status.success() || !decoded.is_written() {
+		let stderr = String::from_utf8_lossy(&output.stderr);
+		return Err(ImageLoaderError {
+			description: format!("OpenJPEG could not decode the file: {}", stderr.trim()).into(),
+		});
+	}
+	simple_load_image(&decoded.0, ImageFormat::Png)
+}
+
+/// Decodes a JPEG 2000 file that's embedded in another file, by writing it to the temp folder
+/// first
+pub fn decode_jpeg2000_data(data: &[u8]) -> Result<RgbaImage> {
+	let embedded = ScratchFile::create("emulsion-jp2", "jp2")?;
+	fs::write(&embedded.0, data)?;
+	decode_jpeg2000(&embedded.0)
+}
@@ -0,0 +1,150 @@
+//! Records how long the recent loads took, for finding out why images load slowly.
+
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// The number of completed loads that are kept
+pub const MAX_ENTRIES: usize = 20;
+
+/// One completed load cycle, from the `Start` of the load to its `Done`
+pub struct LoadLogEntry {
+	/// When the load finished, relative to the creation of the log
+	pub finished_at: Duration,
+	pub path: PathBuf,
+	pub dimensions: Option<(u32, u32)>,
+	/// The time between receiving the `Start` and the `Done` of the load on the main thread
+	pub decode_time: Duration,
+	/// The time spent uploading the frames to the GPU
+	pub upload_time: Duration,
+	pub failed: bool,
+}
+
+impl LoadLogEntry {
+	/// Formats the entry as `[timestamp] path - format - WxH - decode_ms - upload_ms`
+	pub fn description(&self) -> String {
+		let format = self
+			.path
+			.extension()
+			.map(|e| e.to_string_lossy().to_uppercase())
+			.unwrap_or_else(|| "?".into());
+		let dimensions = match self.dimensions {
+			Some((w, h)) => format!("{}x{}", w, h),
+			None => "?x?".into(),
+		};
+		let mut description = format!(
+			"[{:.3}] {} - {} - {} - {} ms - {} ms",
+			self.finished_at.as_secs_f64(),
+			self.path.display(),
+			format,
+			dimensions,
+			self.decode_time.as_millis(),
+			self.upload_time.as_millis(),
+		);
+		if self.failed {
+			description.push_str(" - failed");
+		}
+		description
+	}
+}
+
+struct LoadInProgress {
+	path: PathBuf,
+	started: Option<Instant>,
+	received_end: Option<Instant>,
+	dimensions: Option<(u32, u32)>,
+	upload_time: Duration,
+}
+
+pub struct LoadLog {
+	created: Instant,
+	/// The most recent entry is the last one
+	entries: VecDeque<LoadLogEntry>,
+	in_progress: HashMap<u64, LoadInProgress>,
+	/// Incremented whenever the entries change
+	revision: u64,
+}
+
+impl LoadLog {
+	pub fn new() -> LoadLog {
+		LoadLog {
+			created: Instant::now(),
+			entries: VecDeque::with_capacity(MAX_ENTRIES),
+			in_progress: HashMap::new(),
+			revision: 0,
+		}
+	}
+
+	pub fn entries(&self) -> impl DoubleEndedIterator<Item = &LoadLogEntry> + ExactSizeIterator {
+		self.entries.iter()
+	}
+
+	pub fn revision(&self) -> u64 {
+		self.revision
+	}
+
+	/// Removes the completed loads. The loads that are in progress are still recorded.
+	pub fn clear(&mut self) {
+		self.entries.clear();
+		self.revision += 1;
+	}
+
+	pub fn requested(&mut self, req_id: u64, path: &Path) {
+		self.in_progress.insert(
+			req_id,
+			LoadInProgress {
+				path: path.to_owned(),
+				started: None,
+				received_end: None,
+				dimensions: None,
+				upload_time: Duration::ZERO,
+			},
+		);
+	}
+
+	/// Called when the `Start` of the load arrives on the main thread
+	pub fn received_start(&mut self, req_id: u64, dimensions: Option<(u32, u32)>) {
+		if let Some(load) = self.in_progress.get_mut(&req_id) {
+			load.started = Some(Instant::now());
+			load.dimensions = dimensions;
+		}
+	}
+
+	/// Called when the `Done`, `Failed` or `Aborted` of the load arrives on the main thread
+	pub fn received_end(&mut self, req_id: u64) {
+		if let Some(load) = self.in_progress.get_mut(&req_id) {
+			load.received_end = Some(Instant::now());
+		}
+	}
+
+	pub fn uploaded_frame(&mut self, req_id: u64, dimensions: (u32, u32), duration: Duration) {
+		if let Some(load) = self.in_progress.get_mut(&req_id) {
+			load.dimensions = Some(dimensions);
+			load.upload_time += duration;
+		}
+	}
+
+	/// Adds the entry of the load once every result of it has been processed. Cancelled loads
+	/// are forgotten.
+	pub fn finished(&mut self, req_id: u64, failed: bool, cancelled: bool) {
+		let load = match self.in_progress.remove(&req_id) {
+			Some(load) if !cancelled => load,
+			_ => return,
+		};
+		let now = Instant::now();
+		let started = load.started.unwrap_or(now);
+		let received_end = load.received_end.unwrap_or(now);
+		if self.entries.len() == MAX_ENTRIES {
+			self.entries.pop_front();
+		}
+		self.entries.push_back(LoadLogEntry {
+			finished_at: now - self.created,
+			path: load.path,
+			dimensions: load.dimensions,
+			decode_time: received_end.saturating_duration_since(started),
+			upload_time: load.upload_time,
+			failed,
+		});
+		self.revision += 1;
+	}
+}
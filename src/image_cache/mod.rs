@@ -1,34 +1,160 @@
+//! Loads the images of a folder in the background and keeps them on the GPU.
+//!
+//! # Threads
+//!
+//! [`ImageCache`] runs on the main thread, because it owns the textures and those can only be
+//! created where the GL context lives. The decoding happens on the worker threads of
+//! [`ImageLoader`]. The number of workers comes from `thread_count` in the configuration, and by
+//! default it's the number of CPUs clamped to 2..=4. Every worker waits on the same request
+//! queue. A worker that receives a request other than the one in `PRIORITY_REQUEST_ID` puts it
+//! back into the queue, so the image that the user is waiting for is decoded first.
+//!
+//! # Backpressure
+//!
+//! Nothing is decoded further ahead than the cache and the UI can take:
+//!
+//! - The workers send their results through a bounded channel of
+//!   `ImageCache::LOAD_RESULT_CAPACITY` results. A worker blocks while it's full, so a long
+//!   animation doesn't decode all of its frames into memory before the first one is shown.
+//! - At most `ImageCache::MAX_PENDING_REQUESTS` requests are unfinished at any time. Prefetching
+//!   stops at that limit. A priority request first uploads the results that were received, so
+//!   that it can take the place of a finished one.
+//! - [`ImageCache::prefetch_neighbors`] only requests the images within the prefetch window
+//!   around the current one, and only as many of them as the remaining capacity fits, based on
+//!   the size of the images so far. `refresh_cache` evicts the images that are farthest from
+//!   the current one.
+//!
+//! # Request ids
+//!
+//! The [`Directory`] gives every file a `req_id` when the folder is listed, and listing it
+//! again hands out new ones. A load request is identified by the id of its file, and the same
+//! id is the key of the uploaded textures. The results of a request carry its id. They arrive
+//! interleaved with the results of other requests, and [`PendingRequests`] collects them per
+//! request. Requests that were sent for a folder that isn't shown anymore are cancelled; their
+//! results are still received, but they aren't uploaded.
+//!
+//! # Load results
+//!
+//! - [`LoadResult::Start`] is sent before the decoding starts. It contains the modification
+//!   time of the file, which decides whether the cached textures of the id are still valid, and
+//!   the dimensions if they can be read from the header. The thumbnail that's embedded in a
+//!   JPEG is shown in place of the image until its first frame is uploaded, see
+//!   [`ImageCache::placeholder`]. The name of the detected format is kept for the status bar.
+//! - [`LoadResult::Frame`] is sent for every decoded frame. Still images have a single frame.
+//!   Each one is uploaded as an [`AnimationFrameTexture`]. `frame_index` is the position in the
+//!   animation, and the frames are put back into that order if they arrive out of order.
+//! - [`LoadResult::Done`] ends a request that succeeded. The entry of the id is marked as fully
+//!   loaded, which allows the frame numbers to wrap around.
+//! - [`LoadResult::Failed`] ends a request that failed. It can follow a `Start` and even some
+//!   frames when a file is broken halfway through.
+//! - [`LoadResult::Aborted`] ends a request that was cancelled, which isn't an error. The entry
+//!   of the id is dropped if some of its frames were uploaded.
+//!
+//! Every request ends with exactly one `Done`, `Failed` or `Aborted`.
+//!
+//! GIFs, and the other animated formats, are the case where many frames belong to one request.
+//! Their frames are decoded one after the other, and each one is sent as soon as it's ready.
+//! The first frame can be shown while the rest of the animation is being decoded. Asking for
+//! a frame beyond the ones received so far returns `TextureError::WaitingOnLoader` until `Done`
+//! arrives. A frame delay of zero is played as 100 milliseconds, as other viewers do.
+//!
+//! # Sequence
+//!
+//! The main thread polls the cache on every update, for example with
+//! [`ImageCache::load_at_index`] or [`ImageCache::process_prefetched`]. A request goes through
+//! these steps:
+//!
+//! ```text
+//!   main thread (ImageCache)                  worker thread (ImageLoader)
+//!   ------------------------                  ---------------------------
+//!   send_request_for_file
+//!     |--------------- LoadRequest { req_id, path } -------------->|
+//!     |                                                           read metadata
+//!     |<------------------ Start { req_id, .. } -------------------|
+//!   create or invalidate the cache entry                          decode frame 0
+//!     |<------------------ Frame { frame_index: 0 } ---------------|
+//!   upload frame 0, it can be shown now                           decode frame 1
+//!     |<------------------ Frame { frame_index: 1 } ---------------|
+//!     :                           ...                              :
+//!     |<----------------- Done { req_id } or Failed ---------------|
+//!   mark the entry fully loaded or failed,                        take the next request
+//!   clear PRIORITY_REQUEST_ID
+//! ```
+//!
+//! While no result of the requested image has arrived, the load functions return
+//! `TextureError::WaitingOnLoader` and the caller tries again on the next update.
+
 use std::{
 	borrow::Cow,
-	collections::BTreeMap,
+	cell::{Cell, Ref, RefCell},
+	collections::{BTreeMap, HashSet},
 	ffi::{OsStr, OsString},
 	fs, mem,
 	path::{Path, PathBuf},
 	rc::Rc,
 	sync::atomic::Ordering,
-	time::SystemTime,
+	time::{Duration, Instant, SystemTime},
 };
 
-use log::trace;
+use log::{debug, trace};
 
 use gelatin::{
 	glium::{
 		self,
-		texture::{MipmapsOption, RawImage2d, SrgbTexture2d},
-		CapabilitiesSource,
+		framebuffer::{SimpleFrameBuffer, ToColorAttachment},
+		texture::{MipmapsOption, RawImage2d, SrgbTexture2d, Texture2d},
+		uniforms::MagnifySamplerFilter,
+		CapabilitiesSource, Surface,
 	},
-	image,
+	image::{self, imageops, RgbaImage},
+};
+
+#[cfg(feature = "gpu_decode")]
+use gelatin::glium::{
+	buffer::{Buffer, BufferMode, BufferType},
+	SyncFence,
 };
 
 pub mod image_loader;
 
 use self::{directory::DirItem, image_loader::*};
+use crate::configuration::ScaleQuality;
+use crate::playlist::is_playlist;
 
+mod frame_reassembler;
 mod pending_requests;
 use pending_requests::PendingRequests;
 
 pub mod directory;
-use directory::Directory;
+use directory::{DirListing, Directory};
+
+pub mod load_log;
+use load_log::LoadLog;
+
+pub mod perceptual_hash;
+
+mod vram_budget;
+use vram_budget::VramBudget;
+
+pub mod archive;
+pub mod color_management;
+pub mod dds;
+pub mod farbfeld;
+pub mod icns;
+#[cfg(feature = "jpeg2000")]
+pub mod jpeg2000;
+pub mod postscript;
+pub mod raw_thumbnail;
+mod scratch_file;
+pub mod xbm;
+pub mod xpm;
+
+/// The results that are older than this when they are received are logged, because the user
+/// has likely moved on from the image by then
+const STALE_RESULT_AGE: Duration = Duration::from_secs(30);
+
+/// The number of images after and before the current one that are loaded in the background
+pub const DEFAULT_PREFETCH_WINDOW: (usize, usize) = (3, 1);
 
 #[derive(Debug, thiserror::Error)]
 pub enum TextureError {
@@ -37,6 +163,10 @@ pub enum TextureError {
 
 	#[error("Other texture error: {0}")]
 	Other(Cow<'static, str>),
+
+	/// The loader couldn't load the image, with the reason
+	#[error("{0}")]
+	LoadFailed(String),
 }
 impl From<directory::Error> for TextureError {
 	fn from(value: directory::Error) -> Self {
@@ -53,12 +183,6 @@ impl From<std::io::Error> for TextureError {
 		Self::Other(format!("IO error occured during texture creation: {value}").into())
 	}
 }
-impl TextureError {
-	pub fn from_failed_request(req_id: u32) -> Self {
-		Self::Other(format!("Failed to load #{req_id}").into())
-	}
-}
-
 pub type TextureResult<T> = std::result::Result<T, TextureError>;
 
 // use self::texture_load_errors::*;
@@ -108,29 +232,244 @@ impl<'a> RequestKind<'a> {
 	}
 }
 
+/// The texture of a single tile. Sampling either kind gives linear values, which are converted to
+/// sRGB when they are written to the framebuffer. Images in the linear color space therefore must
+/// not be uploaded as sRGB textures, because that would decode their gamma a second time.
+pub enum TileTexture {
+	Srgb(SrgbTexture2d),
+	Linear(Texture2d),
+}
+impl TileTexture {
+	pub fn dimensions(&self) -> (u32, u32) {
+		match self {
+			TileTexture::Srgb(tex) => tex.dimensions(),
+			TileTexture::Linear(tex) => tex.dimensions(),
+		}
+	}
+
+	fn mipmap_levels(&self) -> u32 {
+		match self {
+			TileTexture::Srgb(tex) => tex.get_mipmap_levels(),
+			TileTexture::Linear(tex) => tex.get_mipmap_levels(),
+		}
+	}
+
+	/// Fills the mipmap `level` by downscaling the level above it
+	fn downscale_into_level(&self, display: &gelatin::Display, level: u32) -> bool {
+		match self {
+			TileTexture::Srgb(tex) => {
+				downscale_mipmap(display, tex.mipmap(level - 1), tex.mipmap(level))
+			}
+			TileTexture::Linear(tex) => {
+				downscale_mipmap(display, tex.mipmap(level - 1), tex.mipmap(level))
+			}
+		}
+	}
+}
+
+/// Resamples `src` into `dst`, which copies it if both have the same size
+fn downscale_mipmap<'t, M: ToColorAttachment<'t>>(
+	display: &gelatin::Display,
+	src: Option<M>,
+	dst: Option<M>,
+) -> bool {
+	let (src, dst) = match (src, dst) {
+		(Some(src), Some(dst)) => (src, dst),
+		_ => return false,
+	};
+	match (SimpleFrameBuffer::new(display, src), SimpleFrameBuffer::new(display, dst)) {
+		(Ok(src), Ok(dst)) => {
+			src.fill(&dst, MagnifySamplerFilter::Linear);
+			true
+		}
+		_ => false,
+	}
+}
+
 pub struct TextureGridItem {
-	pub tex: SrgbTexture2d,
+	tex: RefCell<TileTexture>,
 	pub col: u32,
 	pub row: u32,
+	/// The number of mipmap levels, starting from the base level, that contain the image.
+	/// The others are allocated but only filled once the image is zoomed out far enough to
+	/// need them.
+	ready_levels: Cell<u32>,
+	/// Whether the texture was reallocated with every level it can have already, see
+	/// `grow_mipmaps`
+	full_chain: Cell<bool>,
+}
+impl TextureGridItem {
+	fn new(tex: TileTexture, col: u32, row: u32) -> Self {
+		TextureGridItem {
+			tex: RefCell::new(tex),
+			col,
+			row,
+			ready_levels: Cell::new(1),
+			full_chain: Cell::new(false),
+		}
+	}
+
+	pub fn tex(&self) -> Ref<'_, TileTexture> {
+		self.tex.borrow()
+	}
+
+	/// Makes sure that the mipmap levels down to `level` contain the image. Each missing level
+	/// is generated from the one above it, so only the levels that were ever sampled are
+	/// computed.
+	pub fn prepare_mipmaps(&self, display: &gelatin::Display, level: u32) {
+		if level >= self.tex.borrow().mipmap_levels() && !self.full_chain.get() {
+			self.grow_mipmaps(display);
+		}
+		let tex = self.tex.borrow();
+		let level = level.min(tex.mipmap_levels() - 1);
+		let mut ready_levels = self.ready_levels.get();
+		while ready_levels <= level {
+			if !tex.downscale_into_level(display, ready_levels) {
+				break;
+			}
+			ready_levels += 1;
+		}
+		self.ready_levels.set(ready_levels);
+	}
+
+	/// Replaces the texture with one that has every mipmap level that the tile and the video
+	/// memory allow, and copies the base level into it. The textures only get the levels that
+	/// the zoom at the time of the upload needs, so this happens when the image is zoomed out
+	/// further than that. It's only tried once per tile.
+	fn grow_mipmaps(&self, display: &gelatin::Display) {
+		self.full_chain.set(true);
+		let (w, h) = self.tex.borrow().dimensions();
+		let levels = mipmap_level_count(w, h).min(VramBudget::query(display).max_mipmap_levels());
+		if levels <= self.tex.borrow().mipmap_levels() {
+			return;
+		}
+		let mipmaps = MipmapsOption::EmptyMipmapsMax(levels - 1);
+		let grown = match &*self.tex.borrow() {
+			TileTexture::Srgb(tex) => SrgbTexture2d::empty_with_mipmaps(display, mipmaps, w, h)
+				.ok()
+				.filter(|grown| downscale_mipmap(display, tex.mipmap(0), grown.mipmap(0)))
+				.map(TileTexture::Srgb),
+			TileTexture::Linear(tex) => Texture2d::empty_with_mipmaps(display, mipmaps, w, h)
+				.ok()
+				.filter(|grown| downscale_mipmap(display, tex.mipmap(0), grown.mipmap(0)))
+				.map(TileTexture::Linear),
+		};
+		// The old texture keeps being drawn with the levels it has if this fails
+		if let Some(grown) = grown {
+			*self.tex.borrow_mut() = grown;
+			self.ready_levels.set(1);
+		}
+	}
+}
+
+/// The maximum texture size supported by GPUs is limited. However it may be
+/// the I want to view a 16k*16k image while my GPU only supports 4k*4k
+/// textures. To work around this, we split up large images into a grid of
+/// smaller ones, which are displayed to appear as one continous surface.
+pub struct TiledTexture {
+	pub tiles: Vec<TextureGridItem>,
+	/// Number of physical pixels between two adjacent tiles in one dimension.
+	/// For example the pixel offset from the corner of the image to the corner
+	/// of the tile at the 3rd column and 2nd row is
+	/// (3*tile_size, 2*tile_size)
+	pub tile_size: u32,
+	#[allow(dead_code)]
+	pub rows: u32,
+	#[allow(dead_code)]
+	pub cols: u32,
+}
+impl TiledTexture {
+	/// The size of the tiles for images that don't fit into a single texture
+	pub const TILE_SIZE: u32 = 512;
+
+	/// Uploads the image as a single texture if it's small enough, otherwise
+	/// as a grid of `TILE_SIZE`*`TILE_SIZE` textures.
+	///
+	/// `img_bytes` has to be an rgba8 buffer.
+	pub fn from_rgba(
+		display: &gelatin::Display,
+		w: u32,
+		h: u32,
+		img_bytes: &[u8],
+		color_space: TextureColorSpace,
+	) -> TextureResult<Self> {
+		Self::from_rgba_at_zoom(display, w, h, img_bytes, color_space, 0.0)
+	}
+
+	/// Like `from_rgba`, but only allocates the mipmap levels that are sampled when the image is
+	/// shown at `zoom` times its size, see `mipmap_levels_at_zoom`. The other levels are added
+	/// when it's zoomed out further, see `TextureGridItem::prepare_mipmaps`.
+	pub fn from_rgba_at_zoom(
+		display: &gelatin::Display,
+		w: u32,
+		h: u32,
+		img_bytes: &[u8],
+		color_space: TextureColorSpace,
+		zoom: f32,
+	) -> TextureResult<Self> {
+		// The reasoning behind dividing by 2 and taking the min with 4*1024, is
+		// that if the textures are going to be swaped out from GPU memory it
+		// might be easier to shuffle smaller chunks of memory around. (Because
+		// I believe that if the memory is fragmented, it is easier to find
+		// space for a smaller texture)
+		let max_size = (display.get_capabilities().max_texture_size as u32 / 2).min(4 * 1024);
+		let tile_size = if w <= max_size && h <= max_size { w.max(h) } else { Self::TILE_SIZE };
+
+		// Queried once per image, because reading it may wait for the GPU
+		let vram_levels = VramBudget::query(display).max_mipmap_levels();
+		let max_mipmap_levels = vram_levels.min(mipmap_levels_at_zoom(zoom));
+
+		let cols = ((w - 1) / tile_size) + 1;
+		let rows = ((h - 1) / tile_size) + 1;
+
+		// Every tile is sent to the GPU before waiting for any of them, so that the copies can
+		// overlap if they are asynchronous
+		let mut pending = Vec::with_capacity((cols * rows) as usize);
+		for row in 0..rows {
+			for col in 0..cols {
+				let offset_x = col * tile_size;
+				let offset_y = row * tile_size;
+				let tile_w = (w - offset_x).min(tile_size);
+				let tile_h = (h - offset_y).min(tile_size);
+				let tex = upload_tile(
+					display,
+					w,
+					img_bytes,
+					(offset_x, offset_y),
+					(tile_w, tile_h),
+					color_space,
+					max_mipmap_levels,
+				)?;
+				pending.push((tex, col, row));
+			}
+		}
+		let tiles = pending
+			.into_iter()
+			.map(|(tex, col, row)| TextureGridItem::new(tex.resolve(), col, row))
+			.collect();
+		Ok(TiledTexture { tiles, tile_size, rows, cols })
+	}
+}
+
+/// A smaller copy of an image that was resampled on the CPU
+#[derive(Clone)]
+pub struct DownscaledTexture {
+	pub tex_grid: Rc<TiledTexture>,
+	pub w: u32,
+	pub h: u32,
 }
 
 #[derive(Clone)]
 pub struct AnimationFrameTexture {
-	/// The maximum texture size supported by GPUs is limited. However it may be
-	/// the I want to view a 16k*16k image while my GPU only supports 4k*4k
-	/// textures. To work around this, we split up large images into a grid of
-	/// smaller ones, which are displayed to appear as one continous surface.
-	pub tex_grid: Rc<Vec<TextureGridItem>>,
-	/// Number of physical pixels between two adjacent cells in one dimension.
-	/// For example the pixel offset from the corner of the image to the corner
-	/// of the cell at the 3rd column and 2nd row is
-	/// (3*cell_step_size, 2*cell_step_size)
-	pub cell_step_size: u32,
-	pub grid_rows: u32,
-	pub grid_cols: u32,
+	pub tex_grid: Rc<TiledTexture>,
+	/// Drawn instead of `tex_grid` while the image is shown at its size or smaller, see
+	/// `ScaleQuality::Lanczos3`
+	pub downscaled: Option<DownscaledTexture>,
 
 	pub delay_nano: u64,
 	pub orientation: Orientation,
+	/// The format of the file that the frame was decoded from, if it's known
+	pub source_format: Option<image::ImageFormat>,
 
 	/// The total width of the image. This equals to the sum of the widths of the
 	/// textures from a single row of the grid
@@ -145,50 +484,48 @@ impl AnimationFrameTexture {
 		image: image::RgbaImage,
 		delay_nano: u64,
 		orientation: Orientation,
+		color_space: TextureColorSpace,
 	) -> TextureResult<Self> {
-		let (w, h) = image.dimensions();
-		let img_bytes = image.into_raw();
-		let mut tex_grid = Vec::new();
-
-		// The reasoning behind dividing by 2 and taking the min with 4*1024, is
-		// that if the textures are going to be swaped out from GPU memory it
-		// might be easier to shuffle smaller chunks of memory around. (Because
-		// I believe that if the memory is fragmented, it is easier to find
-		// space for a smaller texture)
-		let max_size = (display.get_capabilities().max_texture_size as u32 / 2).min(4 * 1024);
-
-		// DEBUG
-		// let max_size = 1024;
-
-		let grid_cols = ((w - 1) / max_size) + 1;
-		let grid_rows = ((h - 1) / max_size) + 1;
-
-		for row in 0..grid_rows {
-			for col in 0..grid_cols {
-				let offset_x = col * max_size;
-				let offset_y = row * max_size;
-				let cell_w = (w - offset_x).min(max_size);
-				let cell_h = (h - offset_y).min(max_size);
-				let tex = texture_from_img_rect(
-					display, w, h, &img_bytes, offset_x, offset_y, cell_w, cell_h,
-				)?;
-				let item = TextureGridItem { tex, col, row };
-				tex_grid.push(item);
-			}
-		}
+		Self::from_image_at_zoom(display, image, delay_nano, orientation, color_space, 0.0)
+	}
 
+	/// Like `from_image`, for an image that's shown at `zoom` times its size. The texture gets
+	/// no more mipmap levels than that zoom needs.
+	pub fn from_image_at_zoom(
+		display: &gelatin::Display,
+		image: image::RgbaImage,
+		delay_nano: u64,
+		orientation: Orientation,
+		color_space: TextureColorSpace,
+		zoom: f32,
+	) -> TextureResult<Self> {
+		let (w, h) = image.dimensions();
+		let tex_grid =
+			TiledTexture::from_rgba_at_zoom(display, w, h, image.as_raw(), color_space, zoom)?;
 		Ok(AnimationFrameTexture {
 			tex_grid: Rc::new(tex_grid),
+			downscaled: None,
 			delay_nano,
 			orientation,
+			source_format: None,
 			w,
 			h,
-			cell_step_size: max_size,
-			grid_rows,
-			grid_cols,
 		})
 	}
 
+	/// Uploads `image` as the downscaled copy of this frame
+	fn set_downscaled(
+		&mut self,
+		display: &gelatin::Display,
+		image: RgbaImage,
+		color_space: TextureColorSpace,
+	) -> TextureResult<()> {
+		let (w, h) = image.dimensions();
+		let tex_grid = TiledTexture::from_rgba(display, w, h, image.as_raw(), color_space)?;
+		self.downscaled = Some(DownscaledTexture { tex_grid: Rc::new(tex_grid), w, h });
+		Ok(())
+	}
+
 	pub fn oriented_dimensions(&self) -> (u32, u32) {
 		use Orientation::*;
 		match self.orientation {
@@ -198,29 +535,185 @@ impl AnimationFrameTexture {
 	}
 }
 
+/// A tile whose pixels may still be on their way to the GPU.
+///
+/// With the `gpu_decode` feature the pixels are written to a pixel unpack buffer, and the driver
+/// copies them into the texture without blocking the main thread. A fence signals when the copy
+/// is finished. Without the feature the texture is complete when it's created.
+pub struct PendingTexture {
+	texture: TileTexture,
+	#[cfg(feature = "gpu_decode")]
+	upload: Option<PixelUpload>,
+}
+impl PendingTexture {
+	/// Waits until the pixels are in the texture. This only blocks if the GPU hasn't finished
+	/// copying them yet.
+	pub fn resolve(self) -> TileTexture {
+		#[cfg(feature = "gpu_decode")]
+		{
+			if let Some(upload) = self.upload {
+				if let Some(fence) = upload.fence {
+					fence.wait();
+				}
+			}
+		}
+		self.texture
+	}
+}
+
+/// The buffer that the texture is copied from. It's kept until the copy is finished.
+#[cfg(feature = "gpu_decode")]
+struct PixelUpload {
+	fence: Option<SyncFence>,
+	_buffer: Buffer<[(u8, u8, u8, u8)]>,
+}
+
+#[cfg(not(feature = "gpu_decode"))]
+fn upload_tile(
+	display: &gelatin::Display,
+	img_w: u32,
+	img_bytes: &[u8],
+	offset: (u32, u32),
+	size: (u32, u32),
+	color_space: TextureColorSpace,
+	max_mipmap_levels: u32,
+) -> TextureResult<PendingTexture> {
+	let texture = texture_from_img_rect(
+		display,
+		img_w,
+		img_bytes,
+		offset,
+		size,
+		color_space,
+		max_mipmap_levels,
+	)?;
+	Ok(PendingTexture { texture })
+}
+
+#[cfg(feature = "gpu_decode")]
+fn upload_tile(
+	display: &gelatin::Display,
+	img_w: u32,
+	img_bytes: &[u8],
+	offset: (u32, u32),
+	size: (u32, u32),
+	color_space: TextureColorSpace,
+	max_mipmap_levels: u32,
+) -> TextureResult<PendingTexture> {
+	texture_from_image_pbo(display, img_w, img_bytes, offset, size, color_space, max_mipmap_levels)
+}
+
+/// Uploads the rectangle of the image through a pixel unpack buffer. Falls back to the
+/// synchronous upload if the buffer can't be created.
+///
+/// img_bytes has to be an rgba8 buffer.
+#[cfg(feature = "gpu_decode")]
+fn texture_from_image_pbo(
+	display: &gelatin::Display,
+	img_w: u32,
+	img_bytes: &[u8],
+	(offset_x, offset_y): (u32, u32),
+	(cell_w, cell_h): (u32, u32),
+	color_space: TextureColorSpace,
+	max_mipmap_levels: u32,
+) -> TextureResult<PendingTexture> {
+	let pixel_count = cell_w as usize * cell_h as usize;
+	let buffer = Buffer::<[(u8, u8, u8, u8)]>::empty_array(
+		display,
+		BufferType::PixelUnpackBuffer,
+		pixel_count,
+		BufferMode::Default,
+	);
+	let mut buffer = match buffer {
+		Ok(buffer) => buffer,
+		Err(_) => {
+			let offset = (offset_x, offset_y);
+			let size = (cell_w, cell_h);
+			let texture = texture_from_img_rect(
+				display,
+				img_w,
+				img_bytes,
+				offset,
+				size,
+				color_space,
+				max_mipmap_levels,
+			)?;
+			return Ok(PendingTexture { texture, upload: None });
+		}
+	};
+	{
+		let mut mapping = buffer.map_write();
+		let mut index = 0;
+		for y in offset_y..(offset_y + cell_h) {
+			let start = (y as usize * img_w as usize + offset_x as usize) * 4;
+			let end = start + (cell_w as usize * 4);
+			for pixel in img_bytes[start..end].chunks_exact(4) {
+				mapping.set(index, (pixel[0], pixel[1], pixel[2], pixel[3]));
+				index += 1;
+			}
+		}
+	}
+	let mipmaps = tile_mipmaps(cell_w, cell_h, max_mipmap_levels);
+	let texture = match color_space {
+		TextureColorSpace::Srgb => {
+			let tex = SrgbTexture2d::empty_with_mipmaps(display, mipmaps, cell_w, cell_h)?;
+			tex.main_level().raw_upload_from_pixel_buffer(
+				buffer.as_slice(),
+				0..cell_w,
+				0..cell_h,
+				0..1,
+			);
+			TileTexture::Srgb(tex)
+		}
+		TextureColorSpace::Linear => {
+			let tex = Texture2d::empty_with_mipmaps(display, mipmaps, cell_w, cell_h)?;
+			tex.main_level().raw_upload_from_pixel_buffer(
+				buffer.as_slice(),
+				0..cell_w,
+				0..cell_h,
+				0..1,
+			);
+			TileTexture::Linear(tex)
+		}
+	};
+	// Without sync objects the copy is still ordered before any draw that uses the texture,
+	// only its completion can't be waited for
+	let fence = SyncFence::new(display).ok();
+	Ok(PendingTexture { texture, upload: Some(PixelUpload { fence, _buffer: buffer }) })
+}
+
+/// The smaller mipmap levels are generated when they are first needed, see `prepare_mipmaps`.
+/// At most `max_levels` levels are allocated, see `VramBudget`.
+fn tile_mipmaps(cell_w: u32, cell_h: u32, max_levels: u32) -> MipmapsOption {
+	let levels = mipmap_level_count(cell_w, cell_h).min(max_levels);
+	if levels <= 1 {
+		MipmapsOption::NoMipmap
+	} else {
+		// The count excludes the base level
+		MipmapsOption::EmptyMipmapsMax(levels - 1)
+	}
+}
+
 /// img_bytes has to be an rgba8 buffer.
-#[allow(clippy::too_many_arguments)]
 fn texture_from_img_rect(
 	display: &gelatin::Display,
 	img_w: u32,
-	img_h: u32,
 	img_bytes: &[u8],
-	offset_x: u32,
-	offset_y: u32,
-	cell_w: u32,
-	cell_h: u32,
-) -> TextureResult<SrgbTexture2d> {
-	let raw_image;
-	if img_w == cell_w {
+	(offset_x, offset_y): (u32, u32),
+	(cell_w, cell_h): (u32, u32),
+	color_space: TextureColorSpace,
+	max_mipmap_levels: u32,
+) -> TextureResult<TileTexture> {
+	let raw_image = if img_w == cell_w {
 		assert!(offset_x == 0);
 		let start = (offset_y as usize * img_w as usize) * 4;
 		let end = start + (cell_h as usize * cell_w as usize * 4);
-		raw_image = RawImage2d {
+		RawImage2d {
 			data: Cow::Borrowed(&img_bytes[start..end]),
 			format: glium::texture::ClientFormat::U8U8U8U8,
 			width: cell_w,
 			height: cell_h,
-		};
+		}
 	} else {
 		let cell_size = cell_w as usize * cell_h as usize * 4;
 		let mut cell_pixels = Vec::with_capacity(cell_size);
@@ -231,26 +724,101 @@ fn texture_from_img_rect(
 			let end = start + (cell_w as usize * 4);
 			cell_pixels.extend_from_slice(&img_bytes[start..end]);
 		}
-		raw_image = RawImage2d::from_raw_rgba(cell_pixels, (cell_w, cell_h));
+		RawImage2d::from_raw_rgba(cell_pixels, (cell_w, cell_h))
+	};
+
+	let mipmaps = tile_mipmaps(cell_w, cell_h, max_mipmap_levels);
+	Ok(match color_space {
+		TextureColorSpace::Srgb => {
+			TileTexture::Srgb(SrgbTexture2d::with_mipmaps(display, raw_image, mipmaps)?)
+		}
+		TextureColorSpace::Linear => {
+			TileTexture::Linear(Texture2d::with_mipmaps(display, raw_image, mipmaps)?)
+		}
+	})
+}
+
+/// The zoom at which an image of the given size fits into the window
+fn fit_zoom(display: &gelatin::Display, (w, h): (u32, u32), orientation: Orientation) -> f32 {
+	use Orientation::*;
+	let (win_w, win_h) = match orientation {
+		Deg0 | Deg0HorFlip | Deg180 | Deg180HorFlip => display.get_framebuffer_dimensions(),
+		Deg90 | Deg90VerFlip | Deg270 | Deg270VerFlip => {
+			let (win_w, win_h) = display.get_framebuffer_dimensions();
+			(win_h, win_w)
+		}
+	};
+	(win_w as f32 / w as f32).min(win_h as f32 / h as f32)
+}
+
+/// Resamples the image with a Lanczos filter to the size at which it fits into the window, if
+/// that's less than half of its size. The linear filtering of the mipmaps on the GPU blurs the
+/// images that are shown that much smaller.
+fn lanczos_downscale(
+	display: &gelatin::Display,
+	image: &RgbaImage,
+	orientation: Orientation,
+) -> Option<RgbaImage> {
+	let (w, h) = image.dimensions();
+	let scale = fit_zoom(display, (w, h), orientation);
+	if scale >= 0.5 || scale <= 0.0 {
+		return None;
 	}
+	let target_w = ((w as f32 * scale).round() as u32).max(1);
+	let target_h = ((h as f32 * scale).round() as u32).max(1);
+	Some(imageops::resize(image, target_w, target_h, imageops::FilterType::Lanczos3))
+}
 
-	let x_pow = 31 - img_w.leading_zeros();
-	let y_pow = 31 - img_h.leading_zeros();
+/// Downscales the image so that neither its width nor its height exceeds `GL_MAX_TEXTURE_SIZE`
+/// or `max_size`. Some integrated GPUs can't sample larger textures, and smaller ones are
+/// faster to upload and draw on slow hardware.
+pub fn fit_texture_limit(
+	display: &gelatin::Display,
+	image: RgbaImage,
+	max_size: Option<u32>,
+) -> RgbaImage {
+	let gpu_limit = display.get_capabilities().max_texture_size.max(1) as u32;
+	let limit = max_size.map_or(gpu_limit, |size| size.clamp(1, gpu_limit));
+	let (w, h) = image.dimensions();
+	if w <= limit && h <= limit {
+		return image;
+	}
+	let scale = limit as f32 / w.max(h) as f32;
+	let target_w = ((w as f32 * scale).round() as u32).clamp(1, limit);
+	let target_h = ((h as f32 * scale).round() as u32).clamp(1, limit);
+	imageops::resize(&image, target_w, target_h, imageops::FilterType::Triangle)
+}
 
-	let max_mipmap_levels = x_pow.min(y_pow).min(4);
+/// The number of mipmap levels, including the base level, that are sampled when an image is
+/// shown at `zoom` times its size: only the base level at its size or larger, one more level
+/// at half of it, two more at a quarter and so on. A zoom of zero or less stands for any zoom,
+/// which gets every level.
+fn mipmap_levels_at_zoom(zoom: f32) -> u32 {
+	if zoom.is_nan() || zoom <= 0.0 {
+		return vram_budget::MAX_MIPMAP_LEVELS;
+	}
+	if zoom >= 1.0 {
+		return 1;
+	}
+	1 + (1.0 / zoom).log2().floor() as u32
+}
 
-	let mipmaps = if max_mipmap_levels == 1 {
-		MipmapsOption::NoMipmap
-	} else {
-		MipmapsOption::AutoGeneratedMipmapsMax(max_mipmap_levels)
-		//MipmapsOption::AutoGeneratedMipmaps
-	};
-	Ok(SrgbTexture2d::with_mipmaps(display, raw_image, mipmaps)?)
+/// Returns the number of mipmap levels, including the base level, that a texture of the given size
+/// needs to be displayed without aliasing at any zoom level. The last level is a single texel along
+/// the larger dimension.
+fn mipmap_level_count(w: u32, h: u32) -> u32 {
+	let max_dim = w.max(h);
+	if max_dim < 2 {
+		return 1;
+	}
+	// This is `floor(log2(max_dim))`
+	let max_dim_pow = (u32::BITS - 1).saturating_sub(max_dim.leading_zeros());
+	max_dim_pow + 1
 }
 
 struct CachedTexture {
 	/// Contains the load request id
-	_req_id: u32,
+	_req_id: u64,
 	needs_update: bool,
 	mod_time: Option<SystemTime>,
 
@@ -261,14 +829,25 @@ struct CachedTexture {
 	/// If it's not fully loaded yet a `WaitingOnLoader` error is returned.
 	fully_loaded: bool,
 
-	/// - `false` if loading is still in progress or if succeeded.
-	/// - `true` if this failed to load,
-	failed: bool,
+	/// - `None` if loading is still in progress or if succeeded.
+	/// - Why it failed if this failed to load
+	failed: Option<String>,
 
 	/// If the target file is an image this vector will have a single texture once the
 	/// image uploaded to the GPU. If the target file is an animated image like a gif,
 	/// these the frames
 	frames: Vec<AnimationFrameTexture>,
+
+	/// The bytes of the embedded thumbnail until it's needed as the placeholder
+	thumbnail: Option<Vec<u8>>,
+	placeholder: Option<AnimationFrameTexture>,
+
+	/// The time spent uploading the frames of the last load so far
+	upload_time: Duration,
+	/// Set when the last load is done
+	timings: Option<LoadTimings>,
+	/// The format that the worker detected, which can differ from the file ending
+	detected_format: Option<Cow<'static, str>>,
 }
 
 /// The process of loading an image (or animation frame) consists of the following steps.
@@ -284,6 +863,35 @@ struct CachedTexture {
 /// - ImageCache - Prefetched image is uploaded to a GPU texture from the CPU
 ///     - Pixel data is on the gpu referred to by `ImageCache::texture_cache`
 ///
+/// The numbers about the cache and the loader that the debug overlay shows
+pub struct CacheStats {
+	/// The number of frames that are uploaded to the GPU
+	pub textures: usize,
+	/// The estimated size of the uploaded frames, see `get_image_size_estimate`
+	pub texture_bytes: isize,
+	pub queue_depth: usize,
+	/// The number of load results that the UI didn't receive yet
+	pub pending_results: usize,
+	/// The index of the shown frame, the number of frames that are loaded and whether those
+	/// are all of them, or `None` if the current image isn't loaded
+	pub frame: Option<(usize, usize, bool)>,
+}
+
+/// How long the last load of an image took, see `show_load_timings`
+#[derive(Debug, Copy, Clone)]
+pub struct LoadBreakdown {
+	pub worker: LoadTimings,
+	/// The time spent uploading the frames to the GPU
+	pub texture_upload: Duration,
+}
+
+impl LoadBreakdown {
+	pub fn total(&self) -> Duration {
+		let LoadTimings { file_read, format_detection, header_read, decode } = self.worker;
+		file_read + format_detection + header_read + decode + self.texture_upload
+	}
+}
+
 pub struct ImageCache {
 	dir: Directory,
 
@@ -296,8 +904,23 @@ pub struct ImageCache {
 	curr_est_size: isize,
 
 	pending_requests: PendingRequests,
-	texture_cache: BTreeMap<u32, CachedTexture>,
+	texture_cache: BTreeMap<u64, CachedTexture>,
 	loader: ImageLoader,
+
+	/// The request ids of the images that finished loading, if every image of the directory
+	/// should be loaded. `None` if only the images around the current one are loaded.
+	preloaded: Option<HashSet<u64>>,
+
+	/// The timing of the recent loads
+	load_log: LoadLog,
+
+	scale_quality: ScaleQuality,
+	/// The zoom that the new images are shown at, if it's known, see `set_zoom_hint`
+	zoom_hint: Option<f32>,
+	/// The number of images after and before the current one that are prefetched
+	prefetch_window: (usize, usize),
+	/// See `set_max_texture_size`
+	max_texture_size: Option<u32>,
 }
 
 /// This is a store for the supported images loaded from a folder
@@ -306,6 +929,8 @@ pub struct ImageCache {
 impl ImageCache {
 	// Originally 5
 	const MAX_PENDING_REQUESTS: usize = 1;
+	/// The number of load results that the loader threads may send ahead of the UI
+	const LOAD_RESULT_CAPACITY: usize = 64;
 
 	/// # Arguments
 	/// * `capacity` - Number of bytes. The last image loaded will be the one at which the allocated memory reaches or exceeds capacity
@@ -321,16 +946,114 @@ impl ImageCache {
 
 			pending_requests: PendingRequests::new(),
 			texture_cache: BTreeMap::new(),
-			loader: ImageLoader::new(threads),
+			loader: ImageLoader::new(threads, Self::LOAD_RESULT_CAPACITY),
+			preloaded: None,
+			load_log: LoadLog::new(),
+			scale_quality: ScaleQuality::default(),
+			zoom_hint: None,
+			prefetch_window: DEFAULT_PREFETCH_WINDOW,
+			max_texture_size: None,
+		}
+	}
+
+	pub fn load_log(&self) -> &LoadLog {
+		&self.load_log
+	}
+
+	/// The number of load requests that are waiting for a loader thread
+	pub fn queue_depth(&self) -> usize {
+		self.loader.queue_depth()
+	}
+
+	/// Sets how the images are resampled when they are shown smaller than their size. Only has
+	/// an effect on the images that are uploaded after this.
+	pub fn set_scale_quality(&mut self, quality: ScaleQuality) {
+		self.scale_quality = quality;
+	}
+
+	/// Sets the zoom that the images are shown at, so that their textures only get the mipmap
+	/// levels that are needed at it. `None` means that the images are fit into the window.
+	/// Only has an effect on the images that are uploaded after this.
+	pub fn set_zoom_hint(&mut self, zoom: Option<f32>) {
+		self.zoom_hint = zoom;
+	}
+
+	/// Sets how many images after and before the current one are loaded in the background.
+	/// The ones that don't fit into the capacity of the cache are still left out.
+	pub fn set_prefetch_window(&mut self, ahead: usize, behind: usize) {
+		self.prefetch_window = (ahead, behind);
+	}
+
+	/// Sets the largest width and height of the uploaded images, see `fit_texture_limit`.
+	/// Only has an effect on the images that are uploaded after this.
+	pub fn set_max_texture_size(&mut self, max_size: Option<u32>) {
+		self.max_texture_size = max_size;
+	}
+
+	/// When enabled, every image in the directory is loaded and none of them are evicted from
+	/// the cache regardless of its capacity. Images that are too large to be loaded at all
+	/// fail as usual.
+	pub fn set_preload_all(&mut self, enabled: bool) {
+		self.preloaded = if enabled { Some(HashSet::new()) } else { None };
+	}
+
+	/// Returns the number of images that finished loading and the number of images in the
+	/// directory, or `None` if preloading is disabled or the directory isn't filtered yet.
+	pub fn preload_progress(&mut self) -> Option<(usize, usize)> {
+		let total = self.dir.image_count()?;
+		let preloaded = self.preloaded.as_ref()?;
+		Some((preloaded.len().min(total), total))
+	}
+
+	/// Sends a load request for the next image after the current one that hasn't been
+	/// preloaded yet
+	pub fn preload_next(&mut self) {
+		if self.preloaded.is_none() || self.pending_requests.len() >= Self::MAX_PENDING_REQUESTS {
+			return;
+		}
+		let (count, start) = match (self.dir.image_count(), self.dir.curr_img_index()) {
+			(Some(count), Some(start)) => (count, start),
+			_ => return,
+		};
+		for offset in 1..=count {
+			let DirItem { path, request_id } =
+				match self.dir.image_by_index((start + offset) % count) {
+					Some(item) => item.clone(),
+					None => continue,
+				};
+			let preloaded = self.preloaded.as_mut().unwrap();
+			if preloaded.contains(&request_id) || self.pending_requests.contains(&request_id) {
+				continue;
+			}
+			if self.texture_cache.get(&request_id).is_some_and(|tex| tex.fully_loaded) {
+				preloaded.insert(request_id);
+				continue;
+			}
+			self.send_request_for_file(path, request_id, RequestKind::NonPriority);
+			return;
 		}
 	}
 
-	pub fn current_filename(&self) -> Option<OsString> {
-		self.dir.curr_filename()
+	pub fn stats(&self) -> CacheStats {
+		let frame = self.dir.curr_descriptor().and_then(|desc| {
+			let img = self.texture_cache.get(&desc.request_id)?;
+			Some((self.current_frame_idx, img.frames.len(), img.fully_loaded))
+		});
+		CacheStats {
+			textures: self.texture_cache.values().map(|img| img.frames.len()).sum(),
+			texture_bytes: self
+				.texture_cache
+				.values()
+				.map(|img| get_anim_size_estimate(&img.frames))
+				.sum(),
+			queue_depth: self.loader.queue_depth(),
+			pending_results: self.loader.pending_results(),
+			frame,
+		}
 	}
 
 	pub fn current_file_path(&self) -> Option<PathBuf> {
-		self.current_filename().map(|name| self.dir.path().join(name))
+		self.dir.curr_descriptor().map(|item| item.path.clone())
 	}
 
 	/// Returns `None` when the directory hasn't finished filtering image files.
@@ -343,10 +1066,55 @@ impl ImageCache {
 		self.dir.image_count()
 	}
 
+	/// The paths of the images in the directory, in the order they are navigated. Returns
+	/// `None` when the directory hasn't finished filtering image files.
+	pub fn image_paths(&mut self) -> Option<Vec<PathBuf>> {
+		let count = self.dir.image_count()?;
+		(0..count).map(|i| self.dir.image_by_index(i).map(|item| item.path.clone())).collect()
+	}
+
 	fn curr_dir_item(&self) -> Option<DirItem> {
 		self.dir.curr_descriptor().cloned()
 	}
 
+	/// The embedded thumbnail of the current image while none of its frames is uploaded yet.
+	/// The thumbnail is decoded the first time it's asked for.
+	pub fn placeholder(&mut self, display: &gelatin::Display) -> Option<AnimationFrameTexture> {
+		let DirItem { path, request_id } = self.curr_dir_item()?;
+		let entry = self.texture_cache.get_mut(&request_id)?;
+		if !entry.frames.is_empty() || entry.failed.is_some() {
+			return None;
+		}
+		if let Some(thumbnail) = entry.thumbnail.take() {
+			let image =
+				image::load_from_memory_with_format(&thumbnail, image::ImageFormat::Jpeg).ok()?;
+			let orientation = detect_orientation(&path).unwrap_or(Orientation::Deg0);
+			let color_space = TextureColorSpace::Srgb;
+			let texture = AnimationFrameTexture::from_image(
+				display,
+				image.to_rgba8(),
+				0,
+				orientation,
+				color_space,
+			);
+			entry.placeholder = texture.ok();
+		}
+		entry.placeholder.clone()
+	}
+
+	/// The timings of the last load of the current image, once it's done
+	pub fn load_breakdown(&self) -> Option<LoadBreakdown> {
+		let desc = self.dir.curr_descriptor()?;
+		let entry = self.texture_cache.get(&desc.request_id)?;
+		Some(LoadBreakdown { worker: entry.timings?, texture_upload: entry.upload_time })
+	}
+
+	/// The name of the format that the current image was detected as
+	pub fn detected_format(&self) -> Option<Cow<'static, str>> {
+		let desc = self.dir.curr_descriptor()?;
+		self.texture_cache.get(&desc.request_id)?.detected_format.clone()
+	}
+
 	/// Returns tru if and only if the current image has been fully loaded and it has a single frame.
 	pub fn loaded_still_image(&self) -> bool {
 		if let Some(desc) = self.dir.curr_descriptor() {
@@ -371,15 +1139,42 @@ impl ImageCache {
 	/// returns `Ok(())`
 	pub fn update_directory(&mut self) -> directory::Result<()> {
 		self.dir.update_directory()?;
+		self.textures_need_update();
+		Ok(())
+	}
+
+	/// Replaces the list of files with a listing that was read on another thread. Returns false
+	/// and keeps the list if the listing is of a different folder than the current one.
+	pub fn apply_listing(&mut self, listing: DirListing) -> bool {
+		if listing.dir != self.dir.path() {
+			return false;
+		}
+		self.dir.apply_listing(listing);
+		self.textures_need_update();
+		true
+	}
 
-		// indicate that the an update directory
-		// call was made since those were created and they should all be
-		// checked against the modification time of the file system file.
+	/// The folder was listed again since the textures were created, so they should all be
+	/// checked against the modification time of their file.
+	fn textures_need_update(&mut self) {
 		for texture in self.texture_cache.values_mut() {
 			texture.needs_update = true;
 		}
+	}
 
-		Ok(())
+	/// Adds the files that were found since the last call while the folder is read on another
+	/// thread. Returns true while it's still being read.
+	pub fn update_dir_scan(&mut self) -> bool {
+		if self.dir.poll_scan() {
+			// The neighbors of the current image may have been found
+			self.prefetch_neighbors();
+		}
+		self.dir.is_scanning()
+	}
+
+	/// The folder of the current image
+	pub fn current_dir(&self) -> Option<&Path> {
+		Some(self.dir.path()).filter(|path| !path.as_os_str().is_empty())
 	}
 
 	pub fn load_at_index(
@@ -410,9 +1205,15 @@ impl ImageCache {
 		trace!("Begin `load_specific`");
 		self.receive_prefetched();
 		trace!("Receive prefetched done");
+		let prev_img_index = self.dir.curr_img_index();
 		let target_file_name;
 		let parent;
-		if path.is_dir() {
+		// A file of the open playlist is shown within the playlist instead of its folder
+		let in_playlist = self.dir.is_playlist() && self.dir.select_path(path);
+		if in_playlist {
+			parent = self.dir.path().to_owned();
+			target_file_name = None;
+		} else if path.is_dir() || is_playlist(path) {
 			parent = path.to_owned();
 			target_file_name = None;
 		} else {
@@ -421,12 +1222,15 @@ impl ImageCache {
 			parent = filename_and_parent.1;
 		}
 
-		let prev_img_index = self.dir.curr_img_index();
-		if let Some(target_file_name) = target_file_name {
-			self.change_directory_with_filename(&parent, &target_file_name)?;
-		} else {
-			self.change_directory(&parent)?;
-			self.current_frame_idx = 0;
+		match target_file_name {
+			Some(target_file_name) => {
+				self.change_directory_with_filename(&parent, &target_file_name)?
+			}
+			None if in_playlist => (),
+			None => {
+				self.change_directory(&parent)?;
+				self.current_frame_idx = 0;
+			}
 		}
 		if self.dir.path() != parent {
 			let DirItem { path, request_id } = self.curr_dir_item().ok_or_else(|| {
@@ -458,6 +1262,10 @@ impl ImageCache {
 
 	fn refresh_cache(&mut self) {
 		trace!("Begin `refresh_cache`");
+		if self.preloaded.is_some() {
+			// Every image is kept while preloading
+			return;
+		}
 		if let Some(curr_index) = self.dir.curr_img_index() {
 			let cache = mem::take(&mut self.texture_cache);
 
@@ -538,10 +1346,26 @@ impl ImageCache {
 	}
 
 	fn receive_prefetched(&mut self) {
-		use std::sync::mpsc::TryRecvError;
+		use crossbeam_channel::TryRecvError;
 		loop {
 			match self.loader.try_recv_prefetched() {
 				Ok(load_result) => {
+					if load_result.is_stale(STALE_RESULT_AGE) {
+						debug!(
+							"Request #{}: A result arrived more than {:?} after it was made",
+							load_result.req_id(),
+							STALE_RESULT_AGE
+						);
+					}
+					match &load_result {
+						LoadResult::Start { req_id, dimensions, .. } => {
+							self.load_log.received_start(*req_id, *dimensions)
+						}
+						LoadResult::Done { req_id, .. }
+						| LoadResult::Failed { req_id, .. }
+						| LoadResult::Aborted { req_id, .. } => self.load_log.received_end(*req_id),
+						LoadResult::Frame { .. } => (),
+					}
 					self.pending_requests.add_load_result(load_result);
 				}
 				Err(TryRecvError::Disconnected) => panic!("Channel disconnected unexpectidly."),
@@ -610,8 +1434,8 @@ impl ImageCache {
 
 		// Check if it is inside the texture cache first
 		if let Some(tex) = self.texture_cache.get(&req_id) {
-			if tex.failed {
-				return Err(TextureError::from_failed_request(req_id));
+			if let Some(error) = &tex.failed {
+				return Err(TextureError::LoadFailed(error.clone()));
 			}
 			let modified = fs::metadata(&path).ok().and_then(|m| m.modified().ok());
 			let mut get_from_cache = false;
@@ -627,12 +1451,8 @@ impl ImageCache {
 			if get_from_cache {
 				let count = tex.frames.len() as isize;
 				if tex.fully_loaded || (frame_id >= 0 && frame_id < count) {
-					let wrapped_id;
-					if frame_id < 0 {
-						wrapped_id = count + (frame_id % count);
-					} else {
-						wrapped_id = frame_id % count;
-					}
+					let wrapped_id =
+						if frame_id < 0 { count + (frame_id % count) } else { frame_id % count };
 					if let Some(frame) = tex.frames.get(wrapped_id as usize) {
 						self.current_frame_idx = wrapped_id as usize;
 						return Ok(frame.clone());
@@ -658,7 +1478,10 @@ impl ImageCache {
 	) -> TextureResult<Option<AnimationFrameTexture>> {
 		use std::collections::btree_map::Entry;
 		match load_result {
-			LoadResult::Start { req_id, metadata } => {
+			LoadResult::Start {
+				req_id, metadata, dimensions, thumbnail, detected_format, ..
+			} => {
+				trace!("Started loading #{req_id}, dimensions: {dimensions:?}");
 				let curr_mod_time = metadata.modified().ok();
 				if let Some(cancelled) = self.pending_requests.cancelled(&req_id) {
 					if cancelled {
@@ -674,8 +1497,13 @@ impl ImageCache {
 							needs_update: false,
 							fully_loaded: false,
 							mod_time: curr_mod_time,
-							failed: false,
+							failed: None,
 							frames: Vec::new(),
+							thumbnail,
+							placeholder: None,
+							upload_time: Duration::ZERO,
+							timings: None,
+							detected_format,
 						});
 					}
 					Entry::Occupied(mut entry) => {
@@ -694,12 +1522,25 @@ impl ImageCache {
 							let mut_entry = entry.get_mut();
 							mut_entry.frames.clear();
 							mut_entry.mod_time = curr_mod_time;
+							mut_entry.thumbnail = thumbnail;
+							mut_entry.placeholder = None;
+							mut_entry.upload_time = Duration::ZERO;
+							mut_entry.timings = None;
 						}
+						entry.get_mut().detected_format = detected_format;
 					}
 				}
 				Ok(None)
 			}
-			LoadResult::Frame { req_id, image, delay_nano, orientation } => {
+			LoadResult::Frame {
+				req_id,
+				image,
+				delay_nano,
+				orientation,
+				source_format,
+				color_space,
+				..
+			} => {
 				if let Some(cancelled) = self.pending_requests.cancelled(&req_id) {
 					if cancelled {
 						return Ok(None);
@@ -707,19 +1548,49 @@ impl ImageCache {
 				} else {
 					return Ok(None);
 				}
-				let size_estimate = get_image_size_estimate(image.width(), image.height());
+				let image = fit_texture_limit(display, image, self.max_texture_size);
+				let dimensions = image.dimensions();
+				let size_estimate = get_image_size_estimate(dimensions.0, dimensions.1);
 				if let Some(entry) = self.texture_cache.get_mut(&req_id) {
-					let anim_frame =
-						AnimationFrameTexture::from_image(display, image, delay_nano, orientation)?;
+					let upload_start = Instant::now();
+					let downscaled = match self.scale_quality {
+						ScaleQuality::Linear => None,
+						ScaleQuality::Lanczos3 => lanczos_downscale(display, &image, orientation),
+					};
+					let zoom = match self.zoom_hint {
+						Some(zoom) => zoom,
+						None => fit_zoom(display, dimensions, orientation),
+					};
+					let mut anim_frame = AnimationFrameTexture::from_image_at_zoom(
+						display,
+						image,
+						delay_nano,
+						orientation,
+						color_space,
+						zoom,
+					)?;
+					anim_frame.source_format = source_format;
+					if let Some(downscaled) = downscaled {
+						anim_frame.set_downscaled(display, downscaled, color_space)?;
+					}
+					let upload_time = upload_start.elapsed();
+					self.load_log.uploaded_frame(req_id, dimensions, upload_time);
+					entry.upload_time += upload_time;
 					entry.frames.push(anim_frame.clone());
 					self.remaining_capacity -= size_estimate;
 					return Ok(Some(anim_frame));
 				}
 				Ok(None)
 			}
-			LoadResult::Done { req_id } => {
+			LoadResult::Done { req_id, timings, .. } => {
 				if let Some(tex) = self.texture_cache.get_mut(&req_id) {
 					tex.fully_loaded = true;
+					tex.timings = Some(timings);
+				}
+				let cancelled = self.pending_requests.cancelled(&req_id) != Some(false);
+				self.load_log.finished(req_id, false, cancelled);
+				if let Some(preloaded) = &mut self.preloaded {
+					preloaded.insert(req_id);
 				}
 				let _ = PRIORITY_REQUEST_ID.compare_exchange(
 					req_id,
@@ -730,10 +1601,15 @@ impl ImageCache {
 				self.pending_requests.set_finished(&req_id);
 				Ok(None)
 			}
-			LoadResult::Failed { req_id } => {
+			LoadResult::Failed { req_id, error, .. } => {
 				if let Some(tex) = self.texture_cache.get_mut(&req_id) {
 					tex.fully_loaded = true;
-					tex.failed = true;
+					tex.failed = Some(error.clone());
+				}
+				let cancelled = self.pending_requests.cancelled(&req_id) != Some(false);
+				self.load_log.finished(req_id, true, cancelled);
+				if let Some(preloaded) = &mut self.preloaded {
+					preloaded.insert(req_id);
 				}
 				let _ = PRIORITY_REQUEST_ID.compare_exchange(
 					req_id,
@@ -742,24 +1618,54 @@ impl ImageCache {
 					Ordering::SeqCst,
 				);
 				self.pending_requests.set_finished(&req_id);
-				Err(TextureError::from_failed_request(req_id))
+				Err(TextureError::LoadFailed(error))
+			}
+			LoadResult::Aborted { req_id, .. } => {
+				// A partly loaded entry would look like a finished animation once it's
+				// marked as loaded, so it's loaded again if it's needed later
+				if let Some(tex) = self.texture_cache.get(&req_id) {
+					if !tex.fully_loaded {
+						self.remaining_capacity += get_anim_size_estimate(&tex.frames);
+						self.texture_cache.remove(&req_id);
+					}
+				}
+				self.load_log.finished(req_id, false, true);
+				let _ = PRIORITY_REQUEST_ID.compare_exchange(
+					req_id,
+					NON_EXISTENT_REQUEST_ID,
+					Ordering::SeqCst,
+					Ordering::SeqCst,
+				);
+				self.pending_requests.set_finished(&req_id);
+				Ok(None)
 			}
 		}
 	}
 
+	/// Sends load requests for the images in the prefetch window that aren't in the cache or
+	/// are outdated, closest first and alternating between the following and the preceding
+	/// ones
 	pub fn prefetch_neighbors(&mut self) {
-		if let Some(mut index) = self.dir.curr_img_index() {
-			// Send enough load requests so that the estimated total will just fill the cache
-			let mut estimated_remaining_cap = self.remaining_capacity;
-
-			while estimated_remaining_cap > self.curr_est_size {
-				// Send a load request for the closest file not in the cache or outdated
-				index += 1;
-				if self.prefetch_at_index(index) {
-					estimated_remaining_cap -= self.curr_est_size;
-				} else {
-					break;
-				}
+		let index = match self.dir.curr_img_index() {
+			Some(index) => index,
+			None => return,
+		};
+		let (ahead, behind) = self.prefetch_window;
+		let neighbors = (1..=ahead.max(behind)).flat_map(|distance| {
+			let next = (distance <= ahead).then_some(index + distance);
+			let prev = index.checked_sub(distance).filter(|_| distance <= behind);
+			next.into_iter().chain(prev)
+		});
+		// Send enough load requests so that the estimated total will just fill the cache
+		let mut estimated_remaining_cap = self.remaining_capacity;
+		for neighbor in neighbors {
+			if estimated_remaining_cap <= self.curr_est_size
+				|| self.pending_requests.len() >= Self::MAX_PENDING_REQUESTS
+			{
+				break;
+			}
+			if self.prefetch_at_index(neighbor) {
+				estimated_remaining_cap -= self.curr_est_size;
 			}
 		}
 	}
@@ -782,7 +1688,7 @@ impl ImageCache {
 	fn send_request_for_file(
 		&mut self,
 		file_path: PathBuf,
-		req_id: u32,
+		req_id: u64,
 		kind: RequestKind,
 	) -> bool {
 		if let RequestKind::Priority { display } = kind {
@@ -823,9 +1729,12 @@ impl ImageCache {
 		if self.pending_requests.contains(&req_id) {
 			return false;
 		}
-		let request = LoadRequest { req_id, path: file_path };
-		self.pending_requests.add_request(request.clone());
-		self.loader.send_load_request(request);
+		self.load_log.requested(req_id, &file_path);
+		let mut request = self.loader.acquire_request();
+		request.req_id = req_id;
+		request.path.push(&file_path);
+		self.pending_requests.add_request(LoadRequest { req_id, path: file_path, batch_id: None });
+		self.loader.send_pooled_request(request);
 		true
 	}
 
@@ -835,10 +1744,15 @@ impl ImageCache {
 		}
 		self.texture_cache.clear();
 		self.remaining_capacity = self.total_capacity;
+		if let Some(preloaded) = &mut self.preloaded {
+			preloaded.clear();
+		}
+		self.load_log.clear();
 
 		// Cancel all pending load requests
-		for (_, request) in self.pending_requests.iter_mut() {
+		for (req_id, request) in self.pending_requests.iter_mut() {
 			request.cancel();
+			self.loader.cancel_request(*req_id);
 		}
 
 		self.dir.change_directory(dir_path)?;
@@ -850,6 +1764,9 @@ impl ImageCache {
 		dir_path: &Path,
 		filename: &OsStr,
 	) -> directory::Result<()> {
+		if self.dir.path() != dir_path {
+			self.load_log.clear();
+		}
 		self.dir.change_directory_with_filename(dir_path, filename)
 	}
 
@@ -895,10 +1812,7 @@ fn get_file_name_and_parent(path: &Path) -> std::io::Result<(OsString, PathBuf)>
 	let file_name = match path.file_name() {
 		Some(f) => f.to_owned(),
 		None => {
-			return Err(io::Error::new(
-				io::ErrorKind::Other,
-				format!("Could not get file name from path {:?}", path),
-			))
+			return Err(io::Error::other(format!("Could not get file name from path {:?}", path)))
 		}
 	};
 	let parent = match path.parent() {
@@ -912,10 +1826,10 @@ fn get_file_name_and_parent(path: &Path) -> std::io::Result<(OsString, PathBuf)>
 		None => {
 			let mut path = path.canonicalize()?;
 			if !path.pop() {
-				return Err(io::Error::new(
-					io::ErrorKind::Other,
-					format!("Could not get parent directory of {:?}", path),
-				));
+				return Err(io::Error::other(format!(
+					"Could not get parent directory of {:?}",
+					path
+				)));
 			}
 			path
 		}
@@ -2,6 +2,7 @@ use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+use super::frame_reassembler::FrameReassembler;
 use super::image_loader::*;
 
 /// This struct is used in a map to determine the appropriate file when
@@ -18,6 +19,7 @@ pub struct PendingRequestInfo {
 	// But after multiple attempts and a long and tiring fight with the borrow checker
 	// I gave up.
 	results: Vec<LoadResult>,
+	reassembler: FrameReassembler,
 }
 
 impl PendingRequestInfo {
@@ -29,8 +31,8 @@ impl PendingRequestInfo {
 pub struct PendingRequests {
 	/// Keeps track of all requests that have been sent
 	/// but for which no response has been received
-	by_id: HashMap<u32, PendingRequestInfo>,
-	path_to_id: HashMap<PathBuf, u32>,
+	by_id: HashMap<u64, PendingRequestInfo>,
+	path_to_id: HashMap<PathBuf, u64>,
 }
 
 impl PendingRequests {
@@ -38,7 +40,7 @@ impl PendingRequests {
 		PendingRequests { by_id: HashMap::new(), path_to_id: HashMap::new() }
 	}
 
-	pub fn take_results(&mut self, id: u32) -> Option<Vec<LoadResult>> {
+	pub fn take_results(&mut self, id: u64) -> Option<Vec<LoadResult>> {
 		if let Entry::Occupied(mut entry) = self.by_id.entry(id) {
 			if entry.get().finished {
 				let info = entry.remove_entry().1;
@@ -54,15 +56,15 @@ impl PendingRequests {
 	}
 
 	/// This returns all the ids including the finished item's
-	pub fn get_all_ids(&self) -> Vec<u32> {
+	pub fn get_all_ids(&self) -> Vec<u64> {
 		self.by_id.keys().copied().collect()
 	}
 
-	pub fn cancelled(&self, id: &u32) -> Option<bool> {
+	pub fn cancelled(&self, id: &u64) -> Option<bool> {
 		self.get(id).map(|i| i.cancelled)
 	}
 
-	pub fn contains(&self, id: &u32) -> bool {
+	pub fn contains(&self, id: &u64) -> bool {
 		if let Some(info) = self.by_id.get(id) {
 			!info.finished
 		} else {
@@ -70,7 +72,7 @@ impl PendingRequests {
 		}
 	}
 
-	pub fn set_finished(&mut self, id: &u32) {
+	pub fn set_finished(&mut self, id: &u64) {
 		if let Entry::Occupied(mut entry) = self.by_id.entry(*id) {
 			if entry.get().results.is_empty() {
 				entry.remove_entry();
@@ -84,11 +86,11 @@ impl PendingRequests {
 		self.iter().count()
 	}
 
-	/// Returns true if the result was appended to a pending request.
-	/// Returns false otherwise.
+	/// Appends the result to its pending request. Frames are appended in the order of the
+	/// animation, even if they were received out of order.
 	pub fn add_load_result(&mut self, load_result: LoadResult) {
 		if let Some(info) = self.by_id.get_mut(&load_result.req_id()) {
-			info.results.push(load_result);
+			info.reassembler.push(load_result, &mut info.results);
 		} else {
 			unreachable!()
 		}
@@ -103,19 +105,20 @@ impl PendingRequests {
 				//path: request.path,
 				finished: false,
 				results: Vec::with_capacity(3),
+				reassembler: FrameReassembler::new(),
 			},
 		);
 	}
 
-	pub fn get(&self, id: &u32) -> Option<&PendingRequestInfo> {
+	pub fn get(&self, id: &u64) -> Option<&PendingRequestInfo> {
 		self.by_id.get(id).filter(|i| !i.finished)
 	}
 
-	pub fn iter_mut(&mut self) -> impl Iterator<Item = (&u32, &mut PendingRequestInfo)> {
+	pub fn iter_mut(&mut self) -> impl Iterator<Item = (&u64, &mut PendingRequestInfo)> {
 		self.by_id.iter_mut().filter(|(_, i)| !i.finished)
 	}
 
-	pub fn iter(&self) -> impl Iterator<Item = (&u32, &PendingRequestInfo)> {
+	pub fn iter(&self) -> impl Iterator<Item = (&u64, &PendingRequestInfo)> {
 		self.by_id.iter().filter(|(_, i)| !i.finished)
 	}
 }
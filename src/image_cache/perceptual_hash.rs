@@ -0,0 +1,67 @@
+//! Hashes that stay nearly the same when an image is resized, recompressed or slightly edited,
+//! for finding near-duplicates.
+
+use gelatin::image::{imageops, RgbaImage};
+
+/// The images are shrunk to this size before the DCT
+const SAMPLE_SIZE: usize = 32;
+/// The number of the lowest frequencies that are kept along each axis
+const KEPT_SIZE: usize = 8;
+
+/// Images whose hashes differ in at most this many bits are considered near-duplicates
+pub const DUPLICATE_DISTANCE: u8 = 10;
+
+/// A DCT-based perceptual hash (pHash) of an image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PerceptualHash(pub u64);
+
+impl PerceptualHash {
+	/// Shrinks the image to 32×32 grayscale, takes the 8×8 lowest frequencies of its DCT and
+	/// sets a bit for each of them that is above their mean. The DC coefficient is left out of
+	/// the mean, because it's the brightness of the whole image and would outweigh the others.
+	pub fn from_image(image: &RgbaImage) -> PerceptualHash {
+		let small = imageops::grayscale(&imageops::thumbnail(
+			image,
+			SAMPLE_SIZE as u32,
+			SAMPLE_SIZE as u32,
+		));
+		let pixels: Vec<f64> = small.pixels().map(|p| p.0[0] as f64).collect();
+
+		// The cosines of the DCT-II for the kept frequencies, `cosines[u][x]`
+		let mut cosines = [[0.0; SAMPLE_SIZE]; KEPT_SIZE];
+		for (u, row) in cosines.iter_mut().enumerate() {
+			for (x, cosine) in row.iter_mut().enumerate() {
+				let angle = (2 * x + 1) as f64 * u as f64 * std::f64::consts::PI;
+				*cosine = (angle / (2 * SAMPLE_SIZE) as f64).cos();
+			}
+		}
+		// The DCT is separable, so the rows are transformed first and the columns after
+		let mut rows = [[0.0; KEPT_SIZE]; SAMPLE_SIZE];
+		for (y, row) in rows.iter_mut().enumerate() {
+			let line = &pixels[y * SAMPLE_SIZE..(y + 1) * SAMPLE_SIZE];
+			for (u, coefficient) in row.iter_mut().enumerate() {
+				*coefficient = line.iter().zip(&cosines[u]).map(|(p, c)| p * c).sum();
+			}
+		}
+		let mut coefficients = [0.0; KEPT_SIZE * KEPT_SIZE];
+		for v in 0..KEPT_SIZE {
+			for u in 0..KEPT_SIZE {
+				coefficients[v * KEPT_SIZE + u] =
+					rows.iter().zip(&cosines[v]).map(|(row, c)| row[u] * c).sum();
+			}
+		}
+
+		let mean = coefficients[1..].iter().sum::<f64>() / (coefficients.len() - 1) as f64;
+		let hash = coefficients
+			.iter()
+			.enumerate()
+			.filter(|(_, &coefficient)| coefficient > mean)
+			.fold(0u64, |hash, (i, _)| hash | (1 << i));
+		PerceptualHash(hash)
+	}
+
+	/// The number of bits that differ between the hashes. Zero means the images look the same.
+	pub fn distance(a: u64, b: u64) -> u8 {
+		(a ^ b).count_ones() as u8
+	}
+}
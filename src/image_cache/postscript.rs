@@ -0,0 +1,72 @@
+//! Renders PostScript and EPS files with Ghostscript, which is run as a separate program for
+//! every file.
+
+use std::path::Path;
+use std::process::Command;
+use std::sync::OnceLock;
+
+use gelatin::image::{ImageFormat, RgbaImage};
+
+use super::image_loader::{simple_load_image, ImageLoaderError, Result};
+use super::scratch_file::ScratchFile;
+
+/// Whether Ghostscript can be run, once `ghostscript_available` checked it
+static GHOSTSCRIPT_AVAILABLE: OnceLock<bool> = OnceLock::new();
+
+#[cfg(target_os = "windows")]
+const GHOSTSCRIPT: &str = "gswin64c";
+#[cfg(not(target_os = "windows"))]
+const GHOSTSCRIPT: &str = "gs";
+
+/// The resolution the pages are rendered at
+const RESOLUTION_DPI: u32 = 150;
+
+/// The binary EPS files start with a header for the preview image that comes before the
+/// PostScript code
+const BINARY_EPS_MAGIC: &[u8] = &[0xC5, 0xD0, 0xD3, 0xC6];
+
+pub fn is_postscript(data: &[u8]) -> bool {
+	data.starts_with(b"%!PS") || data.starts_with(BINARY_EPS_MAGIC)
+}
+
+/// Whether Ghostscript can be run. PostScript files are only listed among the images if it
+/// can. It's only run to find out the first time this is called, which is when the first
+/// PostScript file is found, so that starting up doesn't wait for it.
+pub fn ghostscript_available() -> bool {
+	*GHOSTSCRIPT_AVAILABLE.get_or_init(|| {
+		Command::new(GHOSTSCRIPT)
+			.arg("--version")
+			.output()
+			.is_ok_and(|output| output.status.success())
+	})
+}
+
+/// Renders the first page of the file to a PNG in the temp folder and decodes that
+pub fn render_postscript(path: &Path) -> Result<RgbaImage> {
+	if !ghostscript_available() {
+		return Err(ImageLoaderError {
+			description: "PostScript files can only be shown if Ghostscript is installed".into(),
+		});
+	}
+	let rendered = ScratchFile::create("emulsion-eps", "png")?;
+
+	let mut output_arg = std::ffi::OsString::from("-sOutputFile=");
+	output_arg.push(&rendered.0);
+	let output = Command::new(GHOSTSCRIPT)
+		.args(["-dNOPAUSE", "-dBATCH", "-dSAFER", "-dQUIET", "-dEPSCrop"])
+		.args(["-dFirstPage=1", "-dLastPage=1", "-sDEVICE=png16m"])
+		.arg(format!("-r{}", RESOLUTION_DPI))
+		.arg(output_arg)
+		// Ends the options, so that a file name starting with `-` is still read as the file.
+		// `-dBATCH` quits after it.
+		.arg("--")
+		.arg(path)
+		.output()?;
+	if !output.status.success() || !rendered.is_written() {
+		let stderr = String::from_utf8_lossy(&output.stderr);
+		return Err(ImageLoaderError {
+			description: format!("Ghostscript could not render the file: {}", stderr.trim()).into(),
+		});
+	}
+	simple_load_image(&rendered.0, ImageFormat::Png)
+}
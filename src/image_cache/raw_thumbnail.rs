@@ -0,0 +1,78 @@
+//! Shows the camera RAW files (CR2, NEF and ARW) by the JPEG preview that the camera embedded in
+//! them, which is far faster than demosaicing the sensor data and looks the way the camera
+//! rendered it.
+//!
+//! These files are TIFF containers. The previews are found at the offsets that the IFDs give,
+//! and by looking for the start of a JPEG anywhere in the file, because some cameras keep their
+//! largest preview in a sub-IFD or in a maker note. The largest of the previews is decoded.
+
+use std::io::Cursor;
+use std::path::Path;
+
+use gelatin::image::{self, ImageFormat, RgbaImage};
+
+use super::image_loader::{ImageLoaderError, Result};
+
+/// The extensions of the RAW files that are shown by their preview
+pub const EXTENSIONS: &[&str] = &["cr2", "nef", "arw"];
+
+const JPEG_MAGIC: &[u8] = &[0xFF, 0xD8, 0xFF];
+
+pub fn is_raw(path: &Path) -> bool {
+	let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+	extension.is_some_and(|e| EXTENSIONS.contains(&e.as_str()))
+}
+
+/// Decodes the largest JPEG preview of the RAW file
+pub fn decode_preview(data: Vec<u8>) -> Result<RgbaImage> {
+	let not_found = || ImageLoaderError { description: "the RAW file has no JPEG preview".into() };
+	let exif = exif::Reader::new().read_raw(data).map_err(|e| ImageLoaderError {
+		description: format!("the RAW file can't be read: {}", e).into(),
+	})?;
+	let preview = find_preview(&exif).ok_or_else(not_found)?;
+	Ok(image::load_from_memory_with_format(preview, ImageFormat::Jpeg)?.into_rgba8())
+}
+
+/// The bytes of the preview with the most pixels, starting at its start of image marker. The
+/// previews that were found by searching the file reach until the end of the file, which is fine
+/// because the decoder stops at the end of the JPEG.
+fn find_preview(exif: &exif::Exif) -> Option<&[u8]> {
+	let data = exif.buf();
+	let mut candidates = Vec::new();
+	let tag_pairs = [
+		(exif::Tag::JPEGInterchangeFormat, exif::Tag::JPEGInterchangeFormatLength),
+		(exif::Tag::StripOffsets, exif::Tag::StripByteCounts),
+	];
+	for ifd in [exif::In::PRIMARY, exif::In::THUMBNAIL] {
+		for (offset_tag, len_tag) in tag_pairs {
+			let field = |tag| exif.get_field(tag, ifd)?.value.get_uint(0);
+			if let (Some(offset), Some(len)) = (field(offset_tag), field(len_tag)) {
+				let (offset, len) = (offset as usize, len as usize);
+				if let Some(bytes) = data.get(offset..offset.saturating_add(len)) {
+					candidates.push(bytes);
+				}
+			}
+		}
+	}
+	let mut start = 0;
+	while let Some(found) = find(&data[start..], JPEG_MAGIC) {
+		candidates.push(&data[start + found..]);
+		start += found + 1;
+	}
+	candidates
+		.into_iter()
+		.filter(|bytes| bytes.starts_with(JPEG_MAGIC))
+		.filter_map(|bytes| Some((jpeg_pixel_count(bytes)?, bytes)))
+		.max_by_key(|(pixels, _)| *pixels)
+		.map(|(_, bytes)| bytes)
+}
+
+fn jpeg_pixel_count(bytes: &[u8]) -> Option<u64> {
+	let reader = image::ImageReader::with_format(Cursor::new(bytes), ImageFormat::Jpeg);
+	let (w, h) = reader.into_dimensions().ok()?;
+	Some(w as u64 * h as u64)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+	haystack.windows(needle.len()).position(|window| window == needle)
+}
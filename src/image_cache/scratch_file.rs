@@ -0,0 +1,46 @@
+//! Files in the temporary folder that the decoders hand to other programs, and that are deleted
+//! again when the decoding is done.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Makes the names of the files unique within the process
+static NEXT_FILE_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Deletes the file when it's dropped, also when the decoding failed
+pub struct ScratchFile(pub PathBuf);
+
+impl ScratchFile {
+	/// Creates an empty file named `{prefix}-{pid}-{id}.{extension}` in the temporary folder
+	/// that only the current user can access. A file that exists already is never reused,
+	/// because on a shared temporary folder another user could have put it there.
+	pub fn create(prefix: &str, extension: &str) -> io::Result<ScratchFile> {
+		loop {
+			let file_id = NEXT_FILE_ID.fetch_add(1, Ordering::Relaxed);
+			let name = format!("{}-{}-{}.{}", prefix, std::process::id(), file_id, extension);
+			let path = std::env::temp_dir().join(name);
+			let mut options = fs::OpenOptions::new();
+			options.write(true).create_new(true);
+			#[cfg(unix)]
+			std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+			match options.open(&path) {
+				Ok(_) => return Ok(ScratchFile(path)),
+				Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+				Err(e) => return Err(e),
+			}
+		}
+	}
+
+	/// Whether the program wrote something into the file
+	pub fn is_written(&self) -> bool {
+		fs::metadata(&self.0).is_ok_and(|metadata| metadata.len() > 0)
+	}
+}
+
+impl Drop for ScratchFile {
+	fn drop(&mut self) {
+		let _ = fs::remove_file(&self.0);
+	}
+}
@@ -0,0 +1,36 @@
+//! Limits the number of mipmap levels of the textures when the GPU is running out of memory.
+
+/// The most mipmap levels, including the base level, that a texture gets. The smaller levels
+/// would only be sampled when an image is shown at less than 1/128 of its size.
+pub const MAX_MIPMAP_LEVELS: u32 = 8;
+
+const MIB: usize = 1024 * 1024;
+/// Below this much free video memory, the textures get fewer mipmap levels
+const LOW_VRAM: usize = 512 * MIB;
+const LOW_VRAM_MIPMAP_LEVELS: u32 = 4;
+/// Below this much free video memory, the textures only get their base level
+const CRITICAL_VRAM: usize = 64 * MIB;
+
+/// A snapshot of the free video memory, as reported by the `GL_NVX_gpu_memory_info` or the
+/// `GL_ATI_meminfo` extension
+#[derive(Debug, Clone, Copy)]
+pub struct VramBudget {
+	/// `None` if the driver doesn't report it
+	free_bytes: Option<usize>,
+}
+
+impl VramBudget {
+	pub fn query(display: &gelatin::Display) -> VramBudget {
+		VramBudget { free_bytes: display.get_free_video_memory() }
+	}
+
+	/// The number of mipmap levels, including the base level, that a new texture may have.
+	/// Without a report of the free memory it's assumed to be ample.
+	pub fn max_mipmap_levels(&self) -> u32 {
+		match self.free_bytes {
+			Some(free) if free < CRITICAL_VRAM => 1,
+			Some(free) if free < LOW_VRAM => LOW_VRAM_MIPMAP_LEVELS,
+			_ => MAX_MIPMAP_LEVELS,
+		}
+	}
+}
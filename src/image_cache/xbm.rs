@@ -0,0 +1,105 @@
+//! Decoder for X BitMap files.
+//!
+//! An XBM file is a piece of C source code that defines the dimensions of the
+//! bitmap and an array holding the pixels with one bit per pixel. For example:
+//!
+//! ```text
+//! #define icon_width 16
+//! #define icon_height 2
+//! static unsigned char icon_bits[] = {
+//!    0xff, 0x00, 0x00, 0xff };
+//! ```
+//!
+//! Each row starts on a byte boundary and the least significant bit of a byte is the leftmost
+//! pixel. The older X10 variant uses `short` values which are handled the same way but with 16
+//! pixels per array item.
+
+use gelatin::image::{Rgba, RgbaImage};
+
+use super::image_loader::{ImageLoaderError, Result};
+
+const FOREGROUND: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const BACKGROUND: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// Returns true if the data looks like the beginning of an XBM file
+pub fn is_xbm(data: &[u8]) -> bool {
+	let text = String::from_utf8_lossy(data);
+	let text = text.trim_start();
+	text.starts_with("#define") && text.lines().next().is_some_and(|l| l.contains("_width"))
+}
+
+pub fn decode_xbm(data: &[u8]) -> Result<RgbaImage> {
+	let text = std::str::from_utf8(data)
+		.map_err(|_| ImageLoaderError { description: "XBM file is not valid text".into() })?;
+
+	let mut width = None;
+	let mut height = None;
+	for line in text.lines() {
+		let mut parts = line.split_whitespace();
+		if parts.next() != Some("#define") {
+			continue;
+		}
+		let (Some(name), Some(value)) = (parts.next(), parts.next()) else {
+			continue;
+		};
+		if name.ends_with("_width") {
+			width = value.parse::<u32>().ok();
+		} else if name.ends_with("_height") {
+			height = value.parse::<u32>().ok();
+		}
+	}
+	let (Some(width), Some(height)) = (width, height) else {
+		return Err(ImageLoaderError { description: "XBM file has no width or height".into() });
+	};
+
+	let array_start = text
+		.find('{')
+		.ok_or(ImageLoaderError { description: "XBM file has no pixel array".into() })?;
+	let array_end = text[array_start..]
+		.find('}')
+		.map(|end| array_start + end)
+		.ok_or(ImageLoaderError { description: "XBM pixel array is not closed".into() })?;
+	let declaration = &text[..array_start];
+	let bits_per_item = if declaration.contains("short") { 16 } else { 8 };
+
+	let mut items = Vec::new();
+	for item in text[array_start + 1..array_end].split(',') {
+		let item = item.trim();
+		if item.is_empty() {
+			continue;
+		}
+		let value = if let Some(hex) = item.strip_prefix("0x").or(item.strip_prefix("0X")) {
+			u16::from_str_radix(hex, 16)
+		} else {
+			item.parse::<u16>()
+		};
+		let value = value.map_err(|_| ImageLoaderError {
+			description: format!("Invalid value in XBM pixel array: {:?}", item).into(),
+		})?;
+		items.push(value);
+	}
+
+	let items_per_row = (width as usize).div_ceil(bits_per_item);
+	if items.len() < items_per_row * height as usize {
+		return Err(ImageLoaderError {
+			description: format!(
+				"XBM pixel array is too short. Expected {} items but found {}",
+				items_per_row * height as usize,
+				items.len()
+			)
+			.into(),
+		});
+	}
+
+	let mut image = RgbaImage::from_pixel(width, height, BACKGROUND);
+	for y in 0..height {
+		let row = &items[y as usize * items_per_row..(y as usize + 1) * items_per_row];
+		for x in 0..width {
+			let item = row[x as usize / bits_per_item];
+			if item & (1 << (x as usize % bits_per_item)) != 0 {
+				image.put_pixel(x, y, FOREGROUND);
+			}
+		}
+	}
+	Ok(image)
+}
@@ -0,0 +1,201 @@
+//! Decoder for X PixMap (XPM3) files.
+//!
+//! An XPM file is a C source file containing an array of strings. The first string holds
+//! `<width> <height> <color count> <chars per pixel>`, it's followed by the color table, then by
+//! one string per pixel row. For example:
+//!
+//! ```text
+//! /* XPM */
+//! static char *icon[] = {
+//! "2 2 2 1",
+//! ". c None",
+//! "# c #FF0000",
+//! ".#",
+//! "#."
+//! };
+//! ```
+
+use std::collections::HashMap;
+
+use gelatin::image::{Rgba, RgbaImage};
+
+use super::image_loader::{check_image_size, ImageLoaderError, Result};
+
+const XPM_MAGIC: &str = "/* XPM */";
+
+/// Returns true if the data looks like the beginning of an XPM file
+pub fn is_xpm(data: &[u8]) -> bool {
+	String::from_utf8_lossy(data).trim_start().starts_with(XPM_MAGIC)
+}
+
+fn error(description: String) -> ImageLoaderError {
+	ImageLoaderError { description: description.into() }
+}
+
+/// Collects the contents of all string literals, skipping comments
+fn string_literals(text: &str) -> Vec<String> {
+	let mut literals = Vec::new();
+	let mut chars = text.chars().peekable();
+	while let Some(ch) = chars.next() {
+		match ch {
+			'/' if chars.peek() == Some(&'*') => {
+				chars.next();
+				let mut prev = '\0';
+				for ch in chars.by_ref() {
+					if prev == '*' && ch == '/' {
+						break;
+					}
+					prev = ch;
+				}
+			}
+			'"' => {
+				let mut literal = String::new();
+				while let Some(ch) = chars.next() {
+					match ch {
+						'"' => break,
+						'\\' => {
+							if let Some(escaped) = chars.next() {
+								literal.push(escaped);
+							}
+						}
+						ch => literal.push(ch),
+					}
+				}
+				literals.push(literal);
+			}
+			_ => (),
+		}
+	}
+	literals
+}
+
+fn parse_hex_color(hex: &str) -> Option<Rgba<u8>> {
+	if hex.is_empty() || !hex.len().is_multiple_of(3) || hex.len() > 12 {
+		return None;
+	}
+	// The channels are cut by bytes below, which only works for ASCII
+	if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+		return None;
+	}
+	let digits = hex.len() / 3;
+	let mut rgb = [0u8; 3];
+	for (i, channel) in rgb.iter_mut().enumerate() {
+		let value = u16::from_str_radix(&hex[i * digits..(i + 1) * digits], 16).ok()?;
+		// Keep the most significant byte, or expand a single hex digit
+		*channel = match digits {
+			1 => (value * 17) as u8,
+			2 => value as u8,
+			n => (value >> (4 * (n - 2))) as u8,
+		};
+	}
+	Some(Rgba([rgb[0], rgb[1], rgb[2], 255]))
+}
+
+fn parse_color(value: &str) -> Option<Rgba<u8>> {
+	if let Some(hex) = value.strip_prefix('#') {
+		return parse_hex_color(hex);
+	}
+	let name = value.to_lowercase().replace(' ', "");
+	let rgb = match name.as_str() {
+		"none" => return Some(Rgba([0, 0, 0, 0])),
+		"black" => [0, 0, 0],
+		"white" => [255, 255, 255],
+		"red" => [255, 0, 0],
+		"green" => [0, 255, 0],
+		"blue" => [0, 0, 255],
+		"yellow" => [255, 255, 0],
+		"cyan" => [0, 255, 255],
+		"magenta" => [255, 0, 255],
+		"gray" | "grey" => [190, 190, 190],
+		name => {
+			// X11 defines "gray0" to "gray100" (and "grey...") as percentages of white
+			let percent = name.strip_prefix("gray").or(name.strip_prefix("grey"))?;
+			let percent = percent.parse::<u32>().ok().filter(|p| *p <= 100)?;
+			let v = ((percent * 255 + 50) / 100) as u8;
+			[v, v, v]
+		}
+	};
+	Some(Rgba([rgb[0], rgb[1], rgb[2], 255]))
+}
+
+/// Picks the best color from the definitions following the pixel characters.
+/// The definitions are pairs of a key and a value, for example `c #FFFFFF m white`
+fn parse_color_definition(definition: &str) -> Option<Rgba<u8>> {
+	const KEYS: [&str; 5] = ["c", "g", "g4", "m", "s"];
+	let mut values: HashMap<&str, String> = HashMap::new();
+	let mut curr_key = None;
+	for token in definition.split_whitespace() {
+		if KEYS.contains(&token) {
+			curr_key = Some(token);
+			values.insert(token, String::new());
+		} else if let Some(key) = curr_key {
+			let value = values.get_mut(key).unwrap();
+			if !value.is_empty() {
+				value.push(' ');
+			}
+			value.push_str(token);
+		}
+	}
+	// Prefer the color value, then fall back to grayscale and monochrome
+	["c", "g", "g4", "m"].iter().filter_map(|key| values.get(key)).find_map(|v| parse_color(v))
+}
+
+pub fn decode_xpm(data: &[u8]) -> Result<RgbaImage> {
+	let text = std::str::from_utf8(data).map_err(|_| error("XPM file is not valid text".into()))?;
+	let literals = string_literals(text);
+	let header = literals.first().ok_or_else(|| error("XPM file has no header".into()))?;
+
+	let values = header
+		.split_whitespace()
+		.take(4)
+		.map(|v| v.parse::<u32>())
+		.collect::<std::result::Result<Vec<_>, _>>()
+		.map_err(|_| error(format!("Invalid XPM header {:?}", header)))?;
+	let [width, height, color_count, chars_per_pixel] = values[..] else {
+		return Err(error(format!("Invalid XPM header {:?}", header)));
+	};
+	let cpp = chars_per_pixel as usize;
+	if cpp == 0 {
+		return Err(error("XPM file has zero characters per pixel".into()));
+	}
+	let (color_count, height_usize) = (color_count as usize, height as usize);
+	let color_lines = literals.get(1..1 + color_count);
+	let pixel_lines = literals.get(1 + color_count..1 + color_count + height_usize);
+	let (Some(color_lines), Some(pixel_lines)) = (color_lines, pixel_lines) else {
+		return Err(error("XPM file ended unexpectedly".into()));
+	};
+
+	let mut colors: HashMap<Vec<char>, Rgba<u8>> = HashMap::with_capacity(color_lines.len());
+	for line in color_lines {
+		let chars = line.chars().collect::<Vec<_>>();
+		if chars.len() < cpp {
+			return Err(error(format!("Invalid XPM color definition {:?}", line)));
+		}
+		let definition = chars[cpp..].iter().collect::<String>();
+		let color = parse_color_definition(&definition)
+			.ok_or_else(|| error(format!("Unsupported XPM color definition {:?}", line)))?;
+		colors.insert(chars[..cpp].to_vec(), color);
+	}
+
+	// Every pixel line has to hold a whole row, so that the width in the header can't allocate
+	// more than the file describes
+	let line_len = (width as usize)
+		.checked_mul(cpp)
+		.ok_or_else(|| error("XPM image dimensions are too large".into()))?;
+	if let Some(line) = pixel_lines.iter().find(|line| line.chars().count() < line_len) {
+		return Err(error(format!("XPM pixel line {:?} is shorter than {} pixels", line, width)));
+	}
+	check_image_size(Some((width, height)))?;
+
+	let mut image = RgbaImage::new(width, height);
+	for (y, line) in pixel_lines.iter().enumerate() {
+		let chars = line.chars().collect::<Vec<_>>();
+		for (x, pixel) in chars.chunks_exact(cpp).take(width as usize).enumerate() {
+			let color = colors
+				.get(pixel)
+				.ok_or_else(|| error(format!("Undefined XPM pixel {:?}", pixel)))?;
+			image.put_pixel(x as u32, y as u32, *color);
+		}
+	}
+	Ok(image)
+}
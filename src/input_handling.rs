@@ -16,6 +16,75 @@ pub static IMG_FIT_NAME: &str = "img_fit";
 pub static IMG_FIT_BEST_NAME: &str = "img_fit_best";
 pub static IMG_DEL_NAME: &str = "img_del";
 pub static IMG_COPY_NAME: &str = "img_copy";
+pub static COPY_PATH_NAME: &str = "copy_path";
+pub static COPY_METADATA_NAME: &str = "copy_metadata";
+pub static OPEN_EXTERNALLY_NAME: &str = "open_externally";
+pub static PROPERTIES_NAME: &str = "properties";
+pub static ROTATE_CW_NAME: &str = "rotate_cw";
+pub static ROTATE_CCW_NAME: &str = "rotate_ccw";
+pub static FLIP_HORIZONTAL_NAME: &str = "flip_horizontal";
+pub static FLIP_VERTICAL_NAME: &str = "flip_vertical";
+pub static UNDO_FILE_OP_NAME: &str = "undo_file_op";
+pub static UNDO_DELETE_NAME: &str = "undo_delete";
+pub static MOVE_TO_NEW_FOLDER_NAME: &str = "move_to_new_folder";
+pub static RENAME_NAME: &str = "rename";
+pub static SNAP_WINDOW_NAME: &str = "snap_window";
+pub static NEW_WINDOW_NAME: &str = "new_window";
+pub static NEW_EMPTY_WINDOW_NAME: &str = "new_empty_window";
+pub static CLOSE_FILE_NAME: &str = "close_file";
+pub static AUTO_ADVANCE_NAME: &str = "toggle_auto_advance";
+pub static SEARCH_FILES_NAME: &str = "search_files";
+pub static TOGGLE_SIDEBAR_NAME: &str = "toggle_sidebar";
+pub static ADD_BOOKMARK_NAME: &str = "add_bookmark";
+pub static TOGGLE_BOOKMARKS_NAME: &str = "toggle_bookmarks";
+pub static SETTINGS_NAME: &str = "settings";
+pub static GRID_SETTINGS_NAME: &str = "grid_settings";
+pub static RELOAD_CONFIG_NAME: &str = "reload_config";
+pub static HISTORY_BACK_NAME: &str = "history_back";
+pub static HISTORY_FORWARD_NAME: &str = "history_forward";
+pub static NEXT_OTHER_FORMAT_NAME: &str = "next_other_format";
+pub static RUN_COMMAND_NAME: &str = "run_command";
+pub static UPLOAD_IMAGE_NAME: &str = "upload_image";
+pub static PRESENTATION_MODE_NAME: &str = "presentation_mode";
+pub static SPLIT_VIEW_NAME: &str = "split_view";
+pub static PASTE_NAME: &str = "paste";
+pub static EXPORT_VIEW_NAME: &str = "export_view";
+pub static SET_WALLPAPER_NAME: &str = "set_wallpaper";
+pub static TOGGLE_STAR_NAME: &str = "toggle_star";
+pub static RATING_MODE_NAME: &str = "rating_mode";
+pub static EDIT_TAGS_NAME: &str = "edit_tags";
+pub static TOGGLE_LOAD_LOG_NAME: &str = "toggle_load_log";
+pub static SHOW_LOAD_TIMINGS_NAME: &str = "show_load_timings";
+pub static CHANNEL_STATS_NAME: &str = "channel_stats";
+pub static EDIT_EXIF_NAME: &str = "edit_exif";
+pub static COLOR_SAMPLER_NAME: &str = "color_sampler";
+pub static COPY_COLOR_NAME: &str = "copy_color";
+pub static MEASURE_NAME: &str = "measure";
+pub static SET_CROP_NAME: &str = "set_crop";
+pub static CLEAR_CROP_NAME: &str = "clear_crop";
+pub static COPY_COLOR_PROFILE_NAME: &str = "copy_color_profile";
+pub static MAKE_PDF_NAME: &str = "make_pdf";
+pub static MAKE_TIFF_NAME: &str = "make_tiff";
+pub static RELOAD_FOLDER_NAME: &str = "reload_folder";
+pub static EXPORT_SVG_NAME: &str = "export_svg";
+pub static EXPORT_PLAYLIST_NAME: &str = "export_playlist";
+pub static SAVE_SESSION_NAME: &str = "save_session";
+pub static OPEN_FILE_NAME: &str = "open_file";
+pub static OPEN_TERMINAL_NAME: &str = "open_terminal";
+pub static SHOW_ON_MAP_NAME: &str = "show_on_map";
+pub static TOGGLE_SHORTCUTS_NAME: &str = "toggle_shortcuts";
+pub static TOGGLE_DEBUG_NAME: &str = "toggle_debug";
+pub static SHOW_INTEGRITY_NAME: &str = "show_integrity";
+pub static GAMMA_UP_NAME: &str = "gamma_up";
+pub static GAMMA_DOWN_NAME: &str = "gamma_down";
+pub static GAMMA_PANEL_NAME: &str = "gamma_panel";
+pub static PRINT_IMAGE_NAME: &str = "print_image";
+pub static HISTOGRAM_NAME: &str = "histogram";
+pub static EXPORT_WATERMARKED_NAME: &str = "export_watermarked";
+pub static DECODER_INFO_NAME: &str = "decoder_info";
+pub static SAVE_LAYOUT_NAME: &str = "save_layout";
+pub static LOAD_LAYOUT_NAME: &str = "load_layout";
+pub static TOGGLE_TIMECODE_NAME: &str = "toggle_timecode";
 pub static PAN_NAME: &str = "pan";
 pub static PAN_VERT_NAME: &str = "pan_vert"; // Vertical panning
 pub static PAN_HOR_NAME: &str = "pan_hor"; // Horizontal panning
@@ -31,10 +100,107 @@ pub static PAN_RIGHT_NAME: &str = "pan_right";
 pub static PAN_UP_NAME: &str = "pan_up";
 pub static PAN_DOWN_NAME: &str = "pan_down";
 
+/// The description of every action, in the order that the shortcuts overlay lists them
+pub static SHORTCUTS: &[(&str, &str)] = &[
+	(IMG_NEXT_NAME, "Next image"),
+	(IMG_PREV_NAME, "Previous image"),
+	(HISTORY_BACK_NAME, "Back to the previously viewed image"),
+	(HISTORY_FORWARD_NAME, "Forward to the next viewed image"),
+	(NEXT_OTHER_FORMAT_NAME, "Next image of a different format"),
+	(IMG_ORIG_NAME, "Show the image at its size"),
+	(IMG_FIT_NAME, "Fit the image to the window"),
+	(IMG_FIT_BEST_NAME, "Fit the image, but don't enlarge it"),
+	(ZOOM_IN_NAME, "Zoom in"),
+	(ZOOM_OUT_NAME, "Zoom out"),
+	(PAN_NAME, "Pan with the mouse while held"),
+	(PAN_VERT_NAME, "Pan vertically with the mouse while held"),
+	(PAN_HOR_NAME, "Pan horizontally with the mouse while held"),
+	(PAN_LEFT_NAME, "Pan left"),
+	(PAN_RIGHT_NAME, "Pan right"),
+	(PAN_UP_NAME, "Pan up"),
+	(PAN_DOWN_NAME, "Pan down"),
+	(TOGGLE_FULLSCREEN_NAME, "Toggle fullscreen"),
+	(ESCAPE_NAME, "Leave fullscreen, or quit"),
+	(SNAP_WINDOW_NAME, "Resize the window to the image"),
+	(PLAY_ANIM_NAME, "Play or pause the animation"),
+	(PLAY_PRESENT_NAME, "Start or stop the slideshow"),
+	(PLAY_PRESENT_RND_NAME, "Start or stop the slideshow in random order"),
+	(TOGGLE_ANTIALIAS_NAME, "Toggle antialiasing"),
+	(SET_AUTOMATIC_ANTIALIAS_NAME, "Antialias automatically"),
+	(GAMMA_UP_NAME, "Increase the gamma"),
+	(GAMMA_DOWN_NAME, "Decrease the gamma"),
+	(GAMMA_PANEL_NAME, "Set the gamma with a slider"),
+	(PRINT_IMAGE_NAME, "Print the image"),
+	(HISTOGRAM_NAME, "Show the histogram of the colors"),
+	(EXPORT_WATERMARKED_NAME, "Save a copy of the image with the watermark"),
+	(DECODER_INFO_NAME, "Show how the image was detected and decoded"),
+	(SAVE_LAYOUT_NAME, "Save the open panels as a named layout"),
+	(LOAD_LAYOUT_NAME, "Restore a saved layout"),
+	(TOGGLE_TIMECODE_NAME, "Show or hide the timecode of the numbered image"),
+	(ROTATE_CW_NAME, "Rotate clockwise"),
+	(ROTATE_CCW_NAME, "Rotate counter-clockwise"),
+	(FLIP_HORIZONTAL_NAME, "Flip horizontally"),
+	(FLIP_VERTICAL_NAME, "Flip vertically"),
+	(IMG_COPY_NAME, "Copy the image"),
+	(COPY_PATH_NAME, "Copy the path of the image"),
+	(COPY_METADATA_NAME, "Copy the file metadata as JSON"),
+	(PASTE_NAME, "Paste an image"),
+	(IMG_DEL_NAME, "Delete the image"),
+	(RENAME_NAME, "Rename the image"),
+	(MOVE_TO_NEW_FOLDER_NAME, "Move the image into a new folder"),
+	(UNDO_FILE_OP_NAME, "Undo the last file operation"),
+	(UNDO_DELETE_NAME, "Restore the last deleted file from the trash"),
+	(TOGGLE_STAR_NAME, "Star or unstar the image"),
+	(RATING_MODE_NAME, "Rate with the number keys"),
+	(EDIT_TAGS_NAME, "Edit the tags"),
+	(EDIT_EXIF_NAME, "Edit the EXIF data"),
+	(COLOR_SAMPLER_NAME, "Sample the colors under the cursor"),
+	(COPY_COLOR_NAME, "Copy the color under the cursor"),
+	(COPY_COLOR_PROFILE_NAME, "Show and copy the color profile"),
+	(MEASURE_NAME, "Measure distances"),
+	(SET_CROP_NAME, "Set the region to show whenever the image is opened"),
+	(CLEAR_CROP_NAME, "Clear the region to show"),
+	(SHOW_ON_MAP_NAME, "Show where the photo was taken"),
+	(OPEN_FILE_NAME, "Open an image"),
+	(OPEN_EXTERNALLY_NAME, "Open the image in the default editor"),
+	(PROPERTIES_NAME, "Show the properties of the file"),
+	(NEW_WINDOW_NAME, "Open the image in a new window"),
+	(NEW_EMPTY_WINDOW_NAME, "Open an empty window"),
+	(CLOSE_FILE_NAME, "Close the image"),
+	(SEARCH_FILES_NAME, "Search the files by name"),
+	(TOGGLE_SIDEBAR_NAME, "Show or hide the folder tree"),
+	(ADD_BOOKMARK_NAME, "Bookmark the folder"),
+	(TOGGLE_BOOKMARKS_NAME, "Show or hide the bookmarks"),
+	(RELOAD_FOLDER_NAME, "Read the folder again"),
+	(AUTO_ADVANCE_NAME, "Toggle showing new images of the watched folder"),
+	(OPEN_TERMINAL_NAME, "Open a terminal in the folder"),
+	(RUN_COMMAND_NAME, "Run a command on the image"),
+	(UPLOAD_IMAGE_NAME, "Upload the image and copy its address"),
+	(PRESENTATION_MODE_NAME, "Show only the image, Escape to leave"),
+	(SPLIT_VIEW_NAME, "Compare the image with and without the turns and gamma"),
+	(EXPORT_VIEW_NAME, "Save the view as a PNG"),
+	(EXPORT_SVG_NAME, "Save the view as an SVG"),
+	(MAKE_PDF_NAME, "Make a PDF of the folder"),
+	(MAKE_TIFF_NAME, "Save the folder as a multi-page TIFF"),
+	(EXPORT_PLAYLIST_NAME, "Save the images as a playlist"),
+	(SAVE_SESSION_NAME, "Save the images and the view as a session"),
+	(SET_WALLPAPER_NAME, "Set as the desktop wallpaper"),
+	(TOGGLE_LOAD_LOG_NAME, "Show or hide the load log"),
+	(SHOW_LOAD_TIMINGS_NAME, "Show how long the image took to load"),
+	(CHANNEL_STATS_NAME, "Show the statistics of the color channels"),
+	(TOGGLE_DEBUG_NAME, "Show or hide the renderer stats"),
+	(SHOW_INTEGRITY_NAME, "Show or hide the file hashes"),
+	(SETTINGS_NAME, "Settings"),
+	(GRID_SETTINGS_NAME, "Grid overlay settings"),
+	(RELOAD_CONFIG_NAME, "Reload the config file"),
+	(TOGGLE_SHORTCUTS_NAME, "Show or hide the keyboard shortcuts"),
+];
+
 lazy_static! {
 	pub static ref DEFAULT_BINDINGS: HashMap<&'static str, Vec<&'static str>> = {
 		let mut m = HashMap::new();
-		m.insert(TOGGLE_FULLSCREEN_NAME, vec!["F11", "Return"]);
+		// Not `F`, which has always fit the image to the window and still does
+		m.insert(TOGGLE_FULLSCREEN_NAME, vec!["F11", "Return", "Alt+Return"]);
 		m.insert(ESCAPE_NAME, vec!["Escape", "Q"]);
 		m.insert(IMG_NEXT_NAME, vec!["D", "Right", "PageDown"]);
 		m.insert(IMG_PREV_NAME, vec!["A", "Left", "PageUp"]);
@@ -42,7 +208,78 @@ lazy_static! {
 		m.insert(IMG_FIT_NAME, vec!["F"]);
 		m.insert(IMG_FIT_BEST_NAME, vec!["E"]);
 		m.insert(IMG_DEL_NAME, vec!["Delete"]);
-		m.insert(IMG_COPY_NAME, vec!["CmdCtrl+C"]);
+		m.insert(IMG_COPY_NAME, vec!["CmdCtrl+C", "CmdCtrl+Shift+C"]);
+		m.insert(COPY_PATH_NAME, vec!["CmdCtrl+Alt+X"]);
+		m.insert(COPY_METADATA_NAME, vec!["CmdCtrl+Alt+J"]);
+		m.insert(UNDO_FILE_OP_NAME, vec!["CmdCtrl+Z"]);
+		m.insert(UNDO_DELETE_NAME, vec!["CmdCtrl+Shift+Z"]);
+		m.insert(RENAME_NAME, vec!["CmdCtrl+R", "F2"]);
+		m.insert(MOVE_TO_NEW_FOLDER_NAME, vec!["CmdCtrl+Alt+N"]);
+		m.insert(SNAP_WINDOW_NAME, vec!["CmdCtrl+W"]);
+		m.insert(NEW_WINDOW_NAME, vec!["CmdCtrl+N"]);
+		m.insert(NEW_EMPTY_WINDOW_NAME, vec!["CmdCtrl+Shift+N"]);
+		m.insert(CLOSE_FILE_NAME, vec!["CmdCtrl+Shift+W"]);
+		m.insert(AUTO_ADVANCE_NAME, vec!["CmdCtrl+Shift+A"]);
+		m.insert(SEARCH_FILES_NAME, vec!["CmdCtrl+Shift+F"]);
+		m.insert(TOGGLE_SIDEBAR_NAME, vec!["CmdCtrl+Shift+L"]);
+		m.insert(ADD_BOOKMARK_NAME, vec!["CmdCtrl+Shift+B"]);
+		m.insert(TOGGLE_BOOKMARKS_NAME, vec!["CmdCtrl+Alt+B"]);
+		m.insert(SETTINGS_NAME, vec!["CmdCtrl+,"]);
+		m.insert(GRID_SETTINGS_NAME, vec!["CmdCtrl+Shift+G"]);
+		m.insert(RELOAD_CONFIG_NAME, vec!["CmdCtrl+Alt+R"]);
+		m.insert(HISTORY_BACK_NAME, vec!["Alt+Left"]);
+		m.insert(HISTORY_FORWARD_NAME, vec!["Alt+Right"]);
+		m.insert(NEXT_OTHER_FORMAT_NAME, vec!["CmdCtrl+Shift+J"]);
+		m.insert(RUN_COMMAND_NAME, vec!["CmdCtrl+K"]);
+		m.insert(UPLOAD_IMAGE_NAME, vec!["CmdCtrl+Shift+U"]);
+		m.insert(PRESENTATION_MODE_NAME, vec!["CmdCtrl+Alt+P"]);
+		m.insert(SPLIT_VIEW_NAME, vec!["CmdCtrl+Shift+V"]);
+		m.insert(PASTE_NAME, vec!["CmdCtrl+V"]);
+		m.insert(EXPORT_VIEW_NAME, vec!["CmdCtrl+E"]);
+		m.insert(SET_WALLPAPER_NAME, vec!["CmdCtrl+B"]);
+		// `S` toggles the antialiasing
+		m.insert(TOGGLE_STAR_NAME, vec!["CmdCtrl+S"]);
+		m.insert(RATING_MODE_NAME, vec!["CmdCtrl+0"]);
+		m.insert(EDIT_TAGS_NAME, vec!["CmdCtrl+T"]);
+		m.insert(TOGGLE_LOAD_LOG_NAME, vec!["CmdCtrl+L"]);
+		m.insert(SHOW_LOAD_TIMINGS_NAME, vec!["CmdCtrl+Alt+L"]);
+		m.insert(CHANNEL_STATS_NAME, vec!["CmdCtrl+Alt+M"]);
+		m.insert(EDIT_EXIF_NAME, vec!["CmdCtrl+Shift+E"]);
+		m.insert(COLOR_SAMPLER_NAME, vec!["Alt+C"]);
+		m.insert(COPY_COLOR_NAME, vec!["CmdCtrl+Alt+C"]);
+		m.insert(MEASURE_NAME, vec!["CmdCtrl+Shift+M"]);
+		// `CmdCtrl+Alt+B` shows the bookmarks
+		m.insert(SET_CROP_NAME, vec!["CmdCtrl+Alt+K"]);
+		m.insert(CLEAR_CROP_NAME, vec!["CmdCtrl+Alt+Shift+K"]);
+		m.insert(COPY_COLOR_PROFILE_NAME, vec!["CmdCtrl+Alt+I"]);
+		m.insert(MAKE_PDF_NAME, vec!["CmdCtrl+Shift+P"]);
+		m.insert(MAKE_TIFF_NAME, vec!["CmdCtrl+Alt+F"]);
+		m.insert(RELOAD_FOLDER_NAME, vec!["CmdCtrl+Shift+R"]);
+		m.insert(EXPORT_SVG_NAME, vec!["CmdCtrl+Alt+S"]);
+		m.insert(EXPORT_PLAYLIST_NAME, vec!["CmdCtrl+Alt+E"]);
+		m.insert(SAVE_SESSION_NAME, vec!["CmdCtrl+Shift+S"]);
+		m.insert(OPEN_FILE_NAME, vec!["CmdCtrl+Shift+O"]);
+		m.insert(OPEN_EXTERNALLY_NAME, vec!["CmdCtrl+Alt+O"]);
+		m.insert(PROPERTIES_NAME, vec!["CmdCtrl+I"]);
+		m.insert(OPEN_TERMINAL_NAME, vec!["CmdCtrl+Alt+T"]);
+		m.insert(SHOW_ON_MAP_NAME, vec!["CmdCtrl+Alt+G"]);
+		m.insert(TOGGLE_SHORTCUTS_NAME, vec!["CmdCtrl+Shift+H", "?"]);
+		m.insert(TOGGLE_DEBUG_NAME, vec!["CmdCtrl+Alt+D"]);
+		m.insert(SHOW_INTEGRITY_NAME, vec!["CmdCtrl+Shift+I"]);
+		m.insert(GAMMA_UP_NAME, vec!["CmdCtrl+Up"]);
+		m.insert(GAMMA_DOWN_NAME, vec!["CmdCtrl+Down"]);
+		m.insert(GAMMA_PANEL_NAME, vec!["CmdCtrl+Alt+A"]);
+		m.insert(PRINT_IMAGE_NAME, vec!["CmdCtrl+P"]);
+		m.insert(HISTOGRAM_NAME, vec!["CmdCtrl+Alt+H"]);
+		m.insert(EXPORT_WATERMARKED_NAME, vec!["CmdCtrl+Shift+X"]);
+		m.insert(DECODER_INFO_NAME, vec!["CmdCtrl+Alt+Shift+I"]);
+		m.insert(SAVE_LAYOUT_NAME, vec!["CmdCtrl+Alt+W"]);
+		m.insert(LOAD_LAYOUT_NAME, vec!["CmdCtrl+Alt+Shift+W"]);
+		m.insert(TOGGLE_TIMECODE_NAME, vec!["CmdCtrl+Shift+T"]);
+		m.insert(ROTATE_CW_NAME, vec!["R"]);
+		m.insert(ROTATE_CCW_NAME, vec!["Alt+R"]);
+		m.insert(FLIP_HORIZONTAL_NAME, vec!["H"]);
+		m.insert(FLIP_VERTICAL_NAME, vec!["V"]);
 		m.insert(PAN_NAME, vec!["Space"]);
 		m.insert(PLAY_ANIM_NAME, vec!["Alt+A", "Alt+V"]);
 		m.insert(PLAY_PRESENT_NAME, vec!["P"]);
@@ -128,11 +365,13 @@ pub fn keys_triggered<S: AsRef<str>>(
 		let mut has_alt = false;
 		let mut has_ctrl = false;
 		let mut has_logo = false;
+		let mut has_shift = false;
 		for mod_str in parts.iter().take(parts.len() - 1) {
 			match mod_str.as_ref() {
 				"alt" => has_alt = true,
 				"ctrl" => has_ctrl = true,
 				"logo" => has_logo = true,
+				"shift" => has_shift = true,
 				"cmdctrl" => {
 					if cfg!(target_os = "macos") {
 						has_logo = true;
@@ -143,9 +382,17 @@ pub fn keys_triggered<S: AsRef<str>>(
 				_ => (),
 			}
 		}
+		// Shift is ignored for keys without modifiers so that for example "D" also triggers
+		// while shift is held. In key combinations, shift must match like any other modifier.
+		let shift_matches = if has_alt || has_ctrl || has_logo || has_shift {
+			has_shift == modifiers.shift_key()
+		} else {
+			true
+		};
 		if has_alt == modifiers.alt_key()
 			&& has_ctrl == modifiers.control_key()
 			&& has_logo == modifiers.super_key()
+			&& shift_matches
 		{
 			return true;
 		}
@@ -153,6 +400,17 @@ pub fn keys_triggered<S: AsRef<str>>(
 	false
 }
 
+/// The keys that trigger the action, from the configuration if it's bound there
+pub fn action_keys(config: &Configuration, action_name: &str) -> Vec<String> {
+	match config.bindings.as_ref().and_then(|b| b.get(action_name)) {
+		Some(keys) => keys.clone(),
+		None => match DEFAULT_BINDINGS.get(action_name) {
+			Some(keys) => keys.iter().map(|&key| key.to_owned()).collect(),
+			None => Vec::new(),
+		},
+	}
+}
+
 pub fn action_triggered(
 	config: &Rc<RefCell<Configuration>>,
 	action_name: &str,
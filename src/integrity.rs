@@ -0,0 +1,202 @@
+//! Computes the SHA-256 hashes of files and compares them with the hashes that were stored
+//! for them before, to notice the files that were changed or corrupted since.
+//!
+//! The stored hashes are kept in a file in the configuration folder. Every line holds a hash
+//! in hexadecimal, followed by a tab and the absolute path of the file.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// The first 32 bits of the fractional parts of the cube roots of the first 64 primes
+const ROUND_CONSTANTS: [u32; 64] = [
+	0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+	0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+	0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+	0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+	0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+	0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+	0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+	0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// The first 32 bits of the fractional parts of the square roots of the first 8 primes
+const INITIAL_STATE: [u32; 8] = [
+	0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const BLOCK_LEN: usize = 64;
+
+/// Computes a SHA-256 hash of the data that's passed to `update` in any number of pieces
+pub struct Sha256 {
+	state: [u32; 8],
+	block: [u8; BLOCK_LEN],
+	/// The number of bytes in `block` that don't fill a whole block yet
+	block_len: usize,
+	/// The number of bytes hashed so far
+	total_len: u64,
+}
+
+impl Default for Sha256 {
+	fn default() -> Self {
+		Sha256 { state: INITIAL_STATE, block: [0; BLOCK_LEN], block_len: 0, total_len: 0 }
+	}
+}
+
+impl Sha256 {
+	pub fn new() -> Sha256 {
+		Sha256::default()
+	}
+
+	pub fn update(&mut self, mut data: &[u8]) {
+		self.total_len += data.len() as u64;
+		while !data.is_empty() {
+			let take = (BLOCK_LEN - self.block_len).min(data.len());
+			self.block[self.block_len..self.block_len + take].copy_from_slice(&data[..take]);
+			self.block_len += take;
+			data = &data[take..];
+			if self.block_len == BLOCK_LEN {
+				let block = self.block;
+				self.compress(&block);
+				self.block_len = 0;
+			}
+		}
+	}
+
+	pub fn finish(mut self) -> [u8; 32] {
+		let bit_len = self.total_len.wrapping_mul(8);
+		// A single one bit, then zeros up to the last 8 bytes of a block, which hold the length
+		let mut padding = vec![0x80];
+		let padded_len = (self.block_len + 1 + 8).div_ceil(BLOCK_LEN) * BLOCK_LEN;
+		padding.resize(padded_len - self.block_len - 8, 0);
+		padding.extend_from_slice(&bit_len.to_be_bytes());
+		let total_len = self.total_len;
+		self.update(&padding);
+		self.total_len = total_len;
+
+		let mut hash = [0; 32];
+		for (bytes, word) in hash.chunks_exact_mut(4).zip(self.state) {
+			bytes.copy_from_slice(&word.to_be_bytes());
+		}
+		hash
+	}
+
+	fn compress(&mut self, block: &[u8; BLOCK_LEN]) {
+		let mut schedule = [0u32; 64];
+		for (word, bytes) in schedule.iter_mut().zip(block.chunks_exact(4)) {
+			*word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+		}
+		for i in 16..64 {
+			let s0 = schedule[i - 15].rotate_right(7)
+				^ schedule[i - 15].rotate_right(18)
+				^ (schedule[i - 15] >> 3);
+			let s1 = schedule[i - 2].rotate_right(17)
+				^ schedule[i - 2].rotate_right(19)
+				^ (schedule[i - 2] >> 10);
+			schedule[i] =
+				schedule[i - 16].wrapping_add(s0).wrapping_add(schedule[i - 7]).wrapping_add(s1);
+		}
+
+		let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+		for (constant, word) in ROUND_CONSTANTS.iter().zip(schedule) {
+			let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+			let choice = (e & f) ^ (!e & g);
+			let temp1 =
+				h.wrapping_add(s1).wrapping_add(choice).wrapping_add(*constant).wrapping_add(word);
+			let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+			let majority = (a & b) ^ (a & c) ^ (b & c);
+			let temp2 = s0.wrapping_add(majority);
+			h = g;
+			g = f;
+			f = e;
+			e = d.wrapping_add(temp1);
+			d = c;
+			c = b;
+			b = a;
+			a = temp1.wrapping_add(temp2);
+		}
+		for (state, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+			*state = state.wrapping_add(value);
+		}
+	}
+}
+
+pub fn to_hex(hash: &[u8]) -> String {
+	hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// The SHA-256 hash of the file in lowercase hexadecimal. The file is read in pieces, so
+/// large files don't have to fit into memory.
+pub fn hash_file(path: &Path) -> io::Result<String> {
+	let mut file = fs::File::open(path)?;
+	let mut hasher = Sha256::new();
+	let mut buffer = vec![0; 1 << 16];
+	loop {
+		let read = file.read(&mut buffer)?;
+		if read == 0 {
+			break;
+		}
+		hasher.update(&buffer[..read]);
+	}
+	Ok(to_hex(&hasher.finish()))
+}
+
+/// How the hash of a file compares with the stored one
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Integrity {
+	/// There's no stored hash for the file
+	Unknown,
+	Ok,
+	Mismatch,
+}
+
+pub struct HashStore {
+	file_path: PathBuf,
+	hashes: BTreeMap<PathBuf, String>,
+}
+
+impl HashStore {
+	/// Reads the hashes from the file at `file_path`. A missing file means that no hash is
+	/// stored yet.
+	pub fn load(file_path: PathBuf) -> io::Result<HashStore> {
+		let contents = match fs::read_to_string(&file_path) {
+			Ok(contents) => contents,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+			Err(e) => return Err(e),
+		};
+		let hashes = contents
+			.lines()
+			.filter_map(|line| line.split_once('\t'))
+			.map(|(hash, path)| (PathBuf::from(path), hash.trim().to_ascii_lowercase()))
+			.collect();
+		Ok(HashStore { file_path, hashes })
+	}
+
+	pub fn check(&self, path: &Path, hash: &str) -> Integrity {
+		let stored = path.canonicalize().ok().and_then(|key| self.hashes.get(&key));
+		match stored {
+			None => Integrity::Unknown,
+			Some(stored) if stored.eq_ignore_ascii_case(hash) => Integrity::Ok,
+			Some(_) => Integrity::Mismatch,
+		}
+	}
+
+	/// Stores the hash of the file, replacing the one that was stored before, and saves every
+	/// hash to the file
+	pub fn store(&mut self, path: &Path, hash: String) -> io::Result<()> {
+		let key = path.canonicalize()?;
+		if key.to_string_lossy().contains('\n') {
+			return Err(io::Error::other("the path contains a line break"));
+		}
+		self.hashes.insert(key, hash);
+		let mut contents = String::new();
+		for (path, hash) in &self.hashes {
+			contents.push_str(hash);
+			contents.push('\t');
+			contents.push_str(&path.to_string_lossy());
+			contents.push('\n');
+		}
+		fs::write(&self.file_path, contents)
+	}
+}
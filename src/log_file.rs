@@ -0,0 +1,109 @@
+//! Writes the error messages and the log records to a file instead of the standard error
+//! output, see `--log-file`.
+
+use std::ffi::OsString;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// When the file would grow larger than this, it's renamed to `<name>.1` and a new one is
+/// started
+pub const MAX_LOG_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+static LOGGER: OnceLock<Mutex<Logger>> = OnceLock::new();
+
+/// Appends timestamped lines to a file and rotates it when it gets too large
+pub struct Logger {
+	path: PathBuf,
+	file: File,
+	size: u64,
+	max_size: u64,
+}
+
+impl Logger {
+	pub fn open(path: &Path, max_size: u64) -> io::Result<Logger> {
+		let file = OpenOptions::new().create(true).append(true).open(path)?;
+		let size = file.metadata()?.len();
+		Ok(Logger { path: path.to_owned(), file, size, max_size })
+	}
+
+	/// Writes `message` after the current time in ISO 8601 and `label`
+	pub fn write_line(&mut self, label: &str, message: &str) -> io::Result<()> {
+		let timestamp = humantime::format_rfc3339_millis(SystemTime::now());
+		let line = format!("{} {:<6} {}\n", timestamp, label, message);
+		if self.size > 0 && self.size + line.len() as u64 > self.max_size {
+			self.rotate()?;
+		}
+		self.file.write_all(line.as_bytes())?;
+		self.size += line.len() as u64;
+		Ok(())
+	}
+
+	/// Replaces the previous rotated file with the current one and starts an empty file
+	fn rotate(&mut self) -> io::Result<()> {
+		fs::rename(&self.path, rotated_path(&self.path))?;
+		self.file = OpenOptions::new().create(true).write(true).truncate(true).open(&self.path)?;
+		self.size = 0;
+		Ok(())
+	}
+}
+
+/// The path that the full log file is renamed to
+pub fn rotated_path(path: &Path) -> PathBuf {
+	let mut rotated = OsString::from(path.as_os_str());
+	rotated.push(".1");
+	PathBuf::from(rotated)
+}
+
+/// Sends the log records that pass `level` and everything printed with `eprintln!` to the
+/// file at `path`
+pub fn install(path: &Path, level: LevelFilter) -> io::Result<()> {
+	let logger = Logger::open(path, MAX_LOG_FILE_SIZE)?;
+	if LOGGER.set(Mutex::new(logger)).is_err() {
+		return Err(io::Error::other("the log file is already set"));
+	}
+	log::set_boxed_logger(Box::new(FileLog { level }))
+		.map_err(|e| io::Error::other(e.to_string()))?;
+	log::set_max_level(level);
+	Ok(())
+}
+
+/// What `eprintln!` expands to in this crate. The messages go to the log file if there is one.
+pub fn print_error_line(args: fmt::Arguments) {
+	match LOGGER.get() {
+		Some(logger) => {
+			let mut logger = logger.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+			if let Err(e) = logger.write_line("STDERR", &args.to_string()) {
+				std::eprintln!("{}\n(Could not write to the log file: {})", args, e);
+			}
+		}
+		None => std::eprintln!("{}", args),
+	}
+}
+
+struct FileLog {
+	level: LevelFilter,
+}
+
+impl Log for FileLog {
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		metadata.level() <= self.level
+	}
+
+	fn log(&self, record: &Record) {
+		if !self.enabled(record.metadata()) {
+			return;
+		}
+		if let Some(logger) = LOGGER.get() {
+			let mut logger = logger.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+			let _ = logger.write_line(record.level().as_str(), &record.args().to_string());
+		}
+	}
+
+	fn flush(&self) {}
+}
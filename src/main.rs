@@ -2,16 +2,16 @@
 
 use std::cell::{Cell, RefCell};
 use std::f32;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
-use directories_next::ProjectDirs;
+use directories_next::{ProjectDirs, UserDirs};
 use lazy_static::lazy_static;
 
-use log::{debug, trace};
+use log::{debug, trace, LevelFilter};
 
 use gelatin::winit::{
 	dpi::{PhysicalPosition, PhysicalSize},
@@ -21,6 +21,7 @@ use gelatin::winit::{
 use gelatin::{
 	application::*,
 	button::*,
+	frame_rate_limiter::FrameRateLimiter,
 	image,
 	label::*,
 	line_layout_container::*,
@@ -30,24 +31,80 @@ use gelatin::{
 	NextUpdate, Widget,
 };
 
+use crate::bookmarks::Bookmarks;
 use crate::configuration::Theme;
-use crate::configuration::{Cache, ConfigWindowSection, Configuration};
+use crate::configuration::{parse_color, Cache, ConfigWindowSection, Configuration};
+use crate::crops::CropStore;
+use crate::dir_watcher::DirWatcher;
+use crate::image_cache::image_loader::{LOG_LEVEL, LOG_LEVEL_QUIET, LOG_LEVEL_VERBOSE};
+use crate::integrity::HashStore;
+use crate::remote_control::RemoteControl;
+use crate::tags::TagStore;
 use crate::version::Version;
 use crate::widgets::{
-	bottom_bar::BottomBar, copy_notification::CopyNotifications, help_screen::*, picture_widget::*,
+	bookmarks_sidebar::BookmarksSidebar, bottom_bar::BottomBar,
+	channel_stats_overlay::ChannelStatsOverlay, color_sampler::ColorSampler,
+	context_menu::ContextMenu, copy_notification::CopyNotifications, debug_overlay::DebugOverlay,
+	directory_sidebar::DirectorySidebar, empty_hint::EmptyHint, error_overlay::ErrorOverlay,
+	exif_editor::ExifEditor, gamma_panel::GammaPanel, grid_panel::GridPanel, help_screen::*,
+	histogram_panel::HistogramPanel, layout_menu::LayoutMenu, load_log_overlay::LoadLogOverlay,
+	picture_widget::*, preload_indicator::PreloadIndicator, print_panel::PrintPanel,
+	settings_panel::SettingsPanel, shortcuts_overlay::ShortcutsOverlay, text_overlay::TextOverlay,
+	timecode_overlay::TimecodeOverlay,
 };
 
+/// Prints to the log file instead of the standard error output if `--log-file` is given
+macro_rules! eprintln {
+	($($arg:tt)*) => {
+		$crate::log_file::print_error_line(format_args!($($arg)*))
+	};
+}
+
+mod bookmarks;
+mod channel_stats;
 mod clipboard_handler;
 mod cmd_line;
+mod color_profile;
 mod configuration;
+mod crops;
+mod default_viewer;
+mod dir_watcher;
+mod directory_tree;
+mod download;
+mod exif_writer;
+mod external_command;
+mod file_dialog;
+mod file_ops;
+mod file_search;
+mod geolocation;
+mod gesture_recognizer;
 mod handle_panic;
+mod headless;
+mod histogram;
 mod image_cache;
 mod input_handling;
+mod integrity;
+mod log_file;
+mod navigation_history;
 mod parallel_action;
+mod pdf_writer;
 mod playback_manager;
+mod playlist;
+mod printing;
+mod ratings;
+mod remote_control;
 mod shaders;
+mod svg_export;
+mod tags;
+mod temp_file;
+mod terminal;
+mod tiff_writer;
+mod timecode;
+mod upload;
 mod utils;
 mod version;
+mod wallpaper;
+mod watermark;
 mod widgets;
 
 lazy_static! {
@@ -74,13 +131,22 @@ pub enum EmulsionEvent {
 // ========================================================
 fn main() {
 	std::panic::set_hook(Box::new(handle_panic::handle_panic));
-	env_logger::init();
-	trace!("Starting up. Panic hook set, logger initialized.");
 
 	// Load configuration and cache files
 	let (config_path, cache_path) = get_config_and_cache_paths();
 
 	let args = cmd_line::parse_args(&config_path, &cache_path);
+	init_logger(&args);
+	trace!("Starting up. Panic hook set, logger initialized.");
+	if args.quiet {
+		LOG_LEVEL.store(LOG_LEVEL_QUIET, Ordering::Relaxed);
+	} else if args.verbose || args.log_level == Some(LevelFilter::Debug) {
+		LOG_LEVEL.store(LOG_LEVEL_VERBOSE, Ordering::Relaxed);
+	}
+
+	if let Some(exit_code) = headless::run(&args) {
+		std::process::exit(exit_code);
+	}
 
 	let cache = Cache::load(&cache_path);
 	let config = Configuration::load(&config_path);
@@ -90,11 +156,42 @@ fn main() {
 
 	let first_launch = cache.is_err();
 	let cache = Arc::new(Mutex::new(cache.unwrap_or_default()));
-	let config = Rc::new(RefCell::new(config.unwrap_or_default()));
-
-	if args.displayed_folders.is_some() {
-		config.borrow_mut().title.get_or_insert_with(Default::default).displayed_folders =
-			args.displayed_folders;
+	let mut config = config.unwrap_or_default();
+	apply_arg_overrides(&mut config, &args);
+	let config = Rc::new(RefCell::new(config));
+	if let Some(stars) = args.filter_stars {
+		image_cache::directory::MIN_RATING.store(stars, Ordering::Relaxed);
+	}
+	let sort = args.sort.or_else(|| config.borrow().image.as_ref().and_then(|s| s.sort));
+	if let Some(sort) = sort {
+		let _ = image_cache::directory::SORT_ORDER.set(sort);
+	}
+	let seed = args.seed.unwrap_or_else(rand::random);
+	image_cache::directory::SORT_SEED.store(seed, Ordering::Relaxed);
+	let tag_store = TagStore::load(config_path.with_file_name("tags.txt"));
+	if let Err(e) = &tag_store {
+		eprintln!("Could not read the tags: {}", e);
+	}
+	if let Some(tag) = &args.filter_tag {
+		let images = tag_store.as_ref().map(|s| s.images_with_tag(tag)).unwrap_or_default();
+		let _ = image_cache::directory::TAG_FILTER.set(images);
+	}
+	if let Some(megapixels) = config.borrow().image.as_ref().and_then(|s| s.max_image_size) {
+		let max_pixels = megapixels as u64 * 1_000_000;
+		image_cache::image_loader::MAX_IMAGE_PIXELS.store(max_pixels, Ordering::Relaxed);
+	}
+	if args.icc_convert_to_display {
+		let profile_path = args.color_profile.as_deref().map(Path::new);
+		match image_cache::color_management::display_profile(profile_path) {
+			Ok(profile) => image_cache::color_management::convert_to_display(profile),
+			Err(e) => eprintln!("The colors are not converted: {}", e),
+		}
+	}
+	if args.no_zoom_memory {
+		config.borrow_mut().image.get_or_insert_with(Default::default).remember_zoom = Some(false);
+	}
+	if args.no_transitions {
+		config.borrow_mut().image.get_or_insert_with(Default::default).transition_ms = Some(0);
 	}
 
 	let mut application = Application::<()>::new();
@@ -148,9 +245,17 @@ fn main() {
 			.unwrap();
 		let window = Window::new(&mut application, window_desc);
 
-		if let Some(ConfigWindowSection { start_fullscreen: Some(true), .. }) = window_cfg {
+		let start_fullscreen = match window_cfg {
+			_ if args.no_fullscreen => false,
+			Some(ConfigWindowSection { start_fullscreen: Some(fullscreen), .. }) => *fullscreen,
+			_ => window_cache.fullscreen,
+		};
+		if start_fullscreen {
 			window.set_fullscreen(true);
 		}
+		window_cache.fullscreen = start_fullscreen;
+		let fps = args.fps.or_else(|| window_cfg.as_ref().and_then(|w| w.fps));
+		window.set_target_fps(fps.unwrap_or(FrameRateLimiter::DEFAULT_FPS));
 		window
 	};
 	add_window_movement_listener(&window, cache.clone());
@@ -168,30 +273,212 @@ fn main() {
 
 	let copy_notifications_widget = Rc::new(Label::new());
 	let copy_notifications = CopyNotifications::new(&copy_notifications_widget);
+	let error_overlay_widget = Rc::new(Label::new());
+	let error_overlay = ErrorOverlay::new(&error_overlay_widget);
+	let text_overlay_widget = Rc::new(Label::new());
+	let text_overlay = TextOverlay::new(&text_overlay_widget);
+	let load_log_widget = Rc::new(Label::new());
+	let load_log_overlay = LoadLogOverlay::new(&load_log_widget);
+	let exif_editor_widget = Rc::new(Label::new());
+	let exif_editor = ExifEditor::new(&exif_editor_widget);
+	let color_sampler_widget = Rc::new(Label::new());
+	let color_sampler = ColorSampler::new(&color_sampler_widget);
 
 	let bottom_bar = Rc::new(BottomBar::new(&config.borrow()));
+	let mut filters = Vec::new();
+	if let Some(stars) = args.filter_stars {
+		filters.push(format!("{} stars+", stars));
+	}
+	if let Some(tag) = &args.filter_tag {
+		filters.push(format!("tag \"{}\"", tag.trim()));
+	}
+	if !filters.is_empty() {
+		bottom_bar.set_filter_text(Some(format!("[Filtered: {}]", filters.join(", "))));
+	}
+	bottom_bar.set_visible_if_should_show(!window.fullscreen());
+	let settings_panel = SettingsPanel::new(config.clone(), config_path.clone());
 	let picture_widget = make_picture_widget(
 		&window,
 		bottom_bar.clone(),
 		left_to_pan_hint.clone(),
 		copy_notifications,
+		error_overlay,
+		text_overlay,
+		load_log_overlay,
+		exif_editor,
+		color_sampler,
+		settings_panel.clone(),
 		config.clone(),
 		cache.clone(),
 	);
 
-	if let Some(file_path) = args.file_path {
+	if let Some(percent) = args.zoom {
+		picture_widget.set_zoom_percent(percent);
+	}
+	if let Some((w, h)) = args.aspect_ratio {
+		picture_widget.set_aspect_ratio(w, h);
+	}
+	if args.pan_x.is_some() || args.pan_y.is_some() {
+		picture_widget.set_initial_pan(args.pan_x, args.pan_y);
+	}
+	if args.presentation {
+		picture_widget.start_presentation();
+	}
+	if let Ok(tag_store) = tag_store {
+		picture_widget.set_tag_store(tag_store);
+	}
+	match CropStore::load(config_path.with_file_name("crops.toml")) {
+		Ok(crop_store) => picture_widget.set_crop_store(crop_store),
+		Err(e) => eprintln!("Could not read the stored regions of the images: {}", e),
+	}
+	match HashStore::load(config_path.with_file_name("hashes.db")) {
+		Ok(hash_store) => picture_widget.set_hash_store(hash_store),
+		Err(e) => eprintln!("Could not read the stored file hashes: {}", e),
+	}
+	let preload_indicator_widget = Rc::new(Label::new());
+	if args.preload_all {
+		picture_widget.enable_preloading(PreloadIndicator::new(&preload_indicator_widget));
+	}
+	let empty_hint_widget = Rc::new(Label::new());
+	let reload_args = args.clone();
+	let is_empty = args.file_path.is_none()
+		&& args.watch_dir.is_none()
+		&& args.playlist.is_none()
+		&& args.session.is_none();
+	if let Some(dir) = args.watch_dir {
+		if args.file_path.is_none() {
+			picture_widget.jump_to_path(dir.clone());
+		}
+		picture_widget.watch_directory(DirWatcher::new(PathBuf::from(dir)));
+	}
+	if let Some(port) = args.remote_port {
+		match RemoteControl::start(port) {
+			Ok(remote_control) => picture_widget.set_remote_control(remote_control),
+			Err(e) => eprintln!("Could not start the remote control on port {}: {}", port, e),
+		}
+	}
+	let empty_hint = EmptyHint::new(&empty_hint_widget);
+	empty_hint.set_visible(is_empty);
+	picture_widget.set_empty_hint(empty_hint);
+	let shortcuts_widget = Rc::new(Label::new());
+	picture_widget.set_shortcuts_overlay(ShortcutsOverlay::new(&shortcuts_widget));
+	let histogram_widget = Rc::new(Label::new());
+	picture_widget.set_histogram_panel(HistogramPanel::new(&histogram_widget));
+	let timecode_widget = Rc::new(Label::new());
+	picture_widget.set_timecode_overlay(TimecodeOverlay::new(&timecode_widget));
+	let channel_stats_widget = Rc::new(Label::new());
+	picture_widget.set_channel_stats_overlay(ChannelStatsOverlay::new(&channel_stats_widget));
+	let debug_widget = Rc::new(Label::new());
+	picture_widget.set_debug_overlay(DebugOverlay::new(&debug_widget));
+	if let Some(file_path) = args.file_path.or(args.playlist).or(args.session) {
 		picture_widget.jump_to_path(file_path);
 	}
 
+	let directory_sidebar = DirectorySidebar::new(sidebar_root(&config.borrow()));
+	{
+		let picture_widget = Rc::downgrade(&picture_widget);
+		directory_sidebar.set_on_navigate(move |folder| {
+			if let Some(picture_widget) = picture_widget.upgrade() {
+				picture_widget.jump_to_path(folder);
+			}
+		});
+	}
+	picture_widget.set_directory_sidebar(directory_sidebar.clone());
+
+	let bookmarks = Bookmarks::new(&config.borrow(), config_path.clone());
+	let bookmarks_sidebar = BookmarksSidebar::new(bookmarks);
+	{
+		let picture_widget = Rc::downgrade(&picture_widget);
+		bookmarks_sidebar.set_on_navigate(move |folder| {
+			if let Some(picture_widget) = picture_widget.upgrade() {
+				picture_widget.jump_to_path(folder);
+			}
+		});
+	}
+	picture_widget.set_bookmarks_sidebar(bookmarks_sidebar.clone());
+
+	let context_menu = ContextMenu::new();
+	{
+		let picture_widget = Rc::downgrade(&picture_widget);
+		context_menu.set_on_select(move |item| {
+			if let Some(picture_widget) = picture_widget.upgrade() {
+				picture_widget.run_menu_item(item);
+			}
+		});
+	}
+	picture_widget.set_context_menu(context_menu.clone());
+
+	let layout_menu = LayoutMenu::new();
+	{
+		let picture_widget = Rc::downgrade(&picture_widget);
+		layout_menu.set_on_select(move |name| {
+			if let Some(picture_widget) = picture_widget.upgrade() {
+				picture_widget.load_layout(&name);
+			}
+		});
+	}
+	picture_widget.set_layout_menu(layout_menu.clone());
+	let grid_panel = GridPanel::new(config.clone(), config_path.clone());
+	{
+		let picture_widget = picture_widget.clone();
+		grid_panel.set_on_change(move || picture_widget.apply_settings());
+	}
+	picture_widget.set_grid_panel(grid_panel.clone());
+	let gamma_panel = GammaPanel::new();
+	{
+		let picture_widget = Rc::downgrade(&picture_widget);
+		gamma_panel.set_on_change(move |gamma| {
+			if let Some(picture_widget) = picture_widget.upgrade() {
+				picture_widget.set_gamma(gamma);
+			}
+		});
+	}
+	picture_widget.set_gamma_panel(gamma_panel.clone());
+	let print_panel = PrintPanel::new();
+	{
+		let picture_widget = Rc::downgrade(&picture_widget);
+		print_panel.set_on_print(move |layout| {
+			if let Some(picture_widget) = picture_widget.upgrade() {
+				picture_widget.print(layout);
+			}
+		});
+	}
+	picture_widget.set_print_panel(print_panel.clone());
+
 	let picture_area_container = make_picture_area_container();
 	picture_area_container.add_child(picture_widget.clone());
 	picture_area_container.add_child(copy_notifications_widget);
+	picture_area_container.add_child(error_overlay_widget);
+	picture_area_container.add_child(text_overlay_widget);
+	picture_area_container.add_child(load_log_widget);
+	picture_area_container.add_child(shortcuts_widget);
+	picture_area_container.add_child(histogram_widget);
+	picture_area_container.add_child(timecode_widget);
+	picture_area_container.add_child(channel_stats_widget);
+	picture_area_container.add_child(debug_widget);
+	picture_area_container.add_child(exif_editor_widget);
+	picture_area_container.add_child(color_sampler_widget);
+	if args.preload_all {
+		picture_area_container.add_child(preload_indicator_widget);
+	}
+	picture_area_container.add_child(empty_hint_widget);
 	picture_area_container.add_child(left_to_pan_hint);
 	picture_area_container.add_child(help_screen.clone());
 	picture_area_container.add_child(update_notification.clone());
+	picture_area_container.add_child(context_menu.widget.clone());
+	picture_area_container.add_child(layout_menu.widget.clone());
+	picture_area_container.add_child(settings_panel.widget.clone());
+	picture_area_container.add_child(grid_panel.widget.clone());
+	picture_area_container.add_child(gamma_panel.widget.clone());
+	picture_area_container.add_child(print_panel.widget.clone());
+
+	let main_row = make_main_row();
+	main_row.add_child(directory_sidebar.widget.clone());
+	main_row.add_child(bookmarks_sidebar.widget.clone());
+	main_row.add_child(picture_area_container);
 
 	let root_container = make_root_container();
-	root_container.add_child(picture_area_container);
+	root_container.add_child(main_row);
 	root_container.add_child(bottom_bar.widget.clone());
 
 	let update_available = Arc::new(AtomicBool::new(false));
@@ -212,6 +499,7 @@ fn main() {
 		let theme = theme.clone();
 		let update_available = update_available.clone();
 		let bottom_bar = bottom_bar.clone();
+		let config = config.clone();
 
 		Rc::new(move || {
 			match theme.get() {
@@ -228,10 +516,50 @@ fn main() {
 					update_label.set_icon(Some(update_label_image.clone()));
 				}
 			}
+			let bg_color = config.borrow().window.as_ref().and_then(|w| w.background_color.clone());
+			if let Some(bg_color) = bg_color {
+				match parse_color(&bg_color) {
+					Ok([r, g, b]) => window.set_bg_color([
+						r as f32 / 255.0,
+						g as f32 / 255.0,
+						b as f32 / 255.0,
+						1.0,
+					]),
+					Err(e) => eprintln!("Invalid background color in the config: {}", e),
+				}
+			}
 			bottom_bar.set_theme(theme.get(), update_available.load(Ordering::SeqCst));
 		})
 	};
 	set_theme();
+	{
+		let picture_widget = picture_widget.clone();
+		let set_theme = set_theme.clone();
+		settings_panel.set_on_change(move || {
+			picture_widget.apply_settings();
+			set_theme();
+		});
+	}
+	{
+		let config = config.clone();
+		let config_path = config_path.clone();
+		let theme = theme.clone();
+		let set_theme = set_theme.clone();
+		let window = window.clone();
+		picture_widget.set_on_reload_config(move || {
+			let mut reloaded = Configuration::load(&config_path)?;
+			apply_arg_overrides(&mut reloaded, &reload_args);
+			let needing_restart = config.borrow().settings_needing_restart(&reloaded);
+			if let Some(new_theme) = reloaded.window.as_ref().and_then(|w| w.theme) {
+				theme.set(new_theme);
+			}
+			let fps = reload_args.fps.or_else(|| reloaded.window.as_ref().and_then(|w| w.fps));
+			window.set_target_fps(fps.unwrap_or(FrameRateLimiter::DEFAULT_FPS));
+			*config.borrow_mut() = reloaded;
+			set_theme();
+			Ok(needing_restart)
+		});
+	}
 	{
 		let cache = cache.clone();
 		let set_theme = set_theme.clone();
@@ -327,6 +655,7 @@ fn main() {
 
 	application.set_at_exit(Some(move || {
 		cache.lock().unwrap().save(cache_path).unwrap();
+		download::remove_downloads();
 		if let Some(h) = update_checker_join_handle {
 			h.join().unwrap();
 		}
@@ -335,6 +664,23 @@ fn main() {
 }
 // ========================================================
 
+/// Writes the log to the file of `--log-file`, or to the standard error output filtered by
+/// `RUST_LOG` as before. `--log-level` overrides `RUST_LOG`.
+fn init_logger(args: &cmd_line::Args) {
+	if let Some(path) = &args.log_file {
+		let level = args.log_level.unwrap_or(LevelFilter::Info);
+		match log_file::install(Path::new(path), level) {
+			Ok(()) => return,
+			Err(e) => eprintln!("Could not open the log file {:?}: {}", path, e),
+		}
+	}
+	let mut builder = env_logger::Builder::from_default_env();
+	if let Some(level) = args.log_level {
+		builder.filter_level(level);
+	}
+	builder.init();
+}
+
 fn make_icon() -> Icon {
 	let img = image::load_from_memory(include_bytes!("../resource/emulsion48.png")).unwrap();
 	let rgba = img.into_rgba8();
@@ -344,6 +690,8 @@ fn make_icon() -> Icon {
 
 fn add_window_movement_listener(window: &Window, cache: Arc<Mutex<Cache>>) {
 	window.add_global_event_handler(move |window, event| match event {
+		// The geometry from before fullscreen is restored and remembered instead
+		WindowEvent::Resized(_) | WindowEvent::Moved(_) if window.fullscreen() => (),
 		WindowEvent::Resized(new_size) => {
 			let mut cache = cache.lock().unwrap();
 			cache.window.win_w = new_size.width;
@@ -368,6 +716,46 @@ fn make_root_container() -> Rc<VerticalLayoutContainer> {
 	container
 }
 
+/// The directory sidebar and the picture next to each other
+fn make_main_row() -> Rc<HorizontalLayoutContainer> {
+	let container = Rc::new(HorizontalLayoutContainer::new());
+	container.set_margin_all(0.0);
+	container.set_height(Length::Stretch { min: 0.0, max: f32::INFINITY });
+	container.set_width(Length::Stretch { min: 0.0, max: f32::INFINITY });
+	container
+}
+
+/// Replaces the settings of the configuration that were also given on the command line
+fn apply_arg_overrides(config: &mut Configuration, args: &cmd_line::Args) {
+	if args.displayed_folders.is_some() {
+		config.title.get_or_insert_with(Default::default).displayed_folders =
+			args.displayed_folders;
+	}
+	if args.fit.is_some() {
+		config.image.get_or_insert_with(Default::default).fit = args.fit;
+	}
+	if let Some(ms) = args.slideshow_interval_ms {
+		config.slideshow.get_or_insert_with(Default::default).interval_secs = Some(ms / 1000.0);
+	}
+	if let Some([r, g, b]) = args.background_color {
+		config.window.get_or_insert_with(Default::default).background_color =
+			Some(format!("#{:02x}{:02x}{:02x}", r, g, b));
+	}
+	if args.max_image_size.is_some() {
+		config.image.get_or_insert_with(Default::default).max_image_size = args.max_image_size;
+	}
+}
+
+fn sidebar_root(config: &Configuration) -> PathBuf {
+	if let Some(root) = config.sidebar.as_ref().and_then(|s| s.root.as_ref()) {
+		return PathBuf::from(root);
+	}
+	match UserDirs::new() {
+		Some(user_dirs) => user_dirs.home_dir().to_owned(),
+		None => PathBuf::from("/"),
+	}
+}
+
 fn make_picture_area_container() -> Rc<VerticalLayoutContainer> {
 	let picture_area_container = Rc::new(VerticalLayoutContainer::new());
 	picture_area_container.set_margin_all(0.0);
@@ -408,11 +796,18 @@ fn make_update_notification(update_label: Rc<Label>) -> Rc<HorizontalLayoutConta
 	container
 }
 
+#[allow(clippy::too_many_arguments)]
 fn make_picture_widget(
 	window: &Rc<Window>,
 	bottom_bar: Rc<BottomBar>,
 	left_to_pan_hint: Rc<HelpScreen>,
 	copy_notifications: CopyNotifications,
+	error_overlay: ErrorOverlay,
+	text_overlay: TextOverlay,
+	load_log_overlay: LoadLogOverlay,
+	exif_editor: ExifEditor,
+	color_sampler: ColorSampler,
+	settings_panel: Rc<SettingsPanel>,
 	config: Rc<RefCell<Configuration>>,
 	cache: Arc<Mutex<Cache>>,
 ) -> Rc<PictureWidget> {
@@ -422,6 +817,12 @@ fn make_picture_widget(
 		bottom_bar,
 		left_to_pan_hint,
 		copy_notifications,
+		error_overlay,
+		text_overlay,
+		load_log_overlay,
+		exif_editor,
+		color_sampler,
+		settings_panel,
 		config,
 		cache,
 	));
@@ -0,0 +1,66 @@
+//! The images that were shown one after the other, which `history_back` and `history_forward`
+//! go through like the history of a web browser.
+
+use std::path::{Path, PathBuf};
+
+/// The number of images that are remembered. The oldest one is dropped when a new one would go
+/// past it.
+pub const MAX_ENTRIES: usize = 50;
+
+#[derive(Debug, Default)]
+pub struct NavigationHistory {
+	stack: Vec<PathBuf>,
+	/// The index of the image that's shown, within `stack`
+	cursor: usize,
+}
+
+impl NavigationHistory {
+	pub fn new() -> NavigationHistory {
+		NavigationHistory::default()
+	}
+
+	/// Records that the image was navigated to. The images that could be gone forward to are
+	/// forgotten, like when a link is followed in a browser. Showing the image that's already
+	/// the current entry, as going back or forward does, leaves the history as it is.
+	pub fn push(&mut self, path: PathBuf) {
+		if self.current() == Some(path.as_path()) {
+			return;
+		}
+		self.stack.truncate(self.cursor + 1);
+		self.stack.push(path);
+		if self.stack.len() > MAX_ENTRIES {
+			self.stack.remove(0);
+		}
+		self.cursor = self.stack.len() - 1;
+	}
+
+	pub fn current(&self) -> Option<&Path> {
+		self.stack.get(self.cursor).map(PathBuf::as_path)
+	}
+
+	/// Moves to the image that was shown before the current one, and returns it
+	pub fn back(&mut self) -> Option<&Path> {
+		if self.cursor == 0 || self.stack.is_empty() {
+			return None;
+		}
+		self.cursor -= 1;
+		self.current()
+	}
+
+	/// Moves to the image that was shown after the current one before going back, and returns
+	/// it
+	pub fn forward(&mut self) -> Option<&Path> {
+		if self.cursor + 1 >= self.stack.len() {
+			return None;
+		}
+		self.cursor += 1;
+		self.current()
+	}
+
+	/// Replaces the path of an image that was renamed or moved
+	pub fn rename(&mut self, from: &Path, to: &Path) {
+		for path in self.stack.iter_mut().filter(|path| *path == from) {
+			*path = to.to_owned();
+		}
+	}
+}
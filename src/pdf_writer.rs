@@ -0,0 +1,422 @@
+//! Writes the images of a folder into a PDF with one image per page, and single images into
+//! the PDFs that are printed by `print_image`.
+//!
+//! JPEG files are embedded as they are, with the `DCTDecode` filter. Every other image is
+//! decoded and embedded as deflate compressed RGB pixels, with a grayscale soft mask if it has
+//! transparent pixels.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::{write::ZlibEncoder, Compression};
+use gelatin::image::{imageops, ImageFormat, RgbaImage};
+
+use crate::configuration::PaperSize;
+use crate::image_cache::image_loader::{
+	complex_load_image, detect_format, detect_orientation, ImgFormat, LoadResult, Orientation,
+};
+
+const POINTS_PER_MM: f32 = 72.0 / 25.4;
+const POINTS_PER_INCH: f32 = 72.0;
+
+#[derive(Debug, Copy, Clone)]
+pub struct PageLayout {
+	pub paper: PaperSize,
+	pub margin_mm: f32,
+}
+
+/// How the image of `print_image` is put on the page
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PrintLayout {
+	/// The page is as large as the image at `dpi` if this is `None`
+	pub paper: Option<PaperSize>,
+	pub landscape: bool,
+	pub dpi: u32,
+	/// Prints every pixel at `dpi` instead of fitting the image to the page
+	pub actual_size: bool,
+	pub margin_mm: f32,
+}
+
+impl PrintLayout {
+	/// The width and height of the page, and of the image on it, in points
+	pub fn page_and_image_size(&self, (img_w, img_h): (u32, u32)) -> ((f32, f32), (f32, f32)) {
+		let dpi = self.dpi.max(1) as f32;
+		let actual = (img_w as f32 / dpi * POINTS_PER_INCH, img_h as f32 / dpi * POINTS_PER_INCH);
+		let paper = match self.paper {
+			Some(paper) => paper,
+			None => return (actual, actual),
+		};
+		let (paper_w, paper_h) = paper.size_mm();
+		let (mut page_w, mut page_h) = (paper_w * POINTS_PER_MM, paper_h * POINTS_PER_MM);
+		if self.landscape {
+			std::mem::swap(&mut page_w, &mut page_h);
+		}
+		if self.actual_size {
+			return ((page_w, page_h), actual);
+		}
+		let margin = self.margin_mm.max(0.0) * POINTS_PER_MM;
+		((page_w, page_h), fit_size((img_w, img_h), (page_w, page_h), margin))
+	}
+}
+
+/// How many images made it into the PDF
+#[derive(Debug, Copy, Clone)]
+pub struct PdfSummary {
+	pub pages: usize,
+	/// The images that could not be read
+	pub skipped: usize,
+}
+
+/// The pixels of an image in the form that's embedded in the PDF
+enum EmbeddedImage {
+	Jpeg { data: Vec<u8>, width: u32, height: u32, components: u8 },
+	Pixels(RgbaImage),
+}
+
+impl EmbeddedImage {
+	fn dimensions(&self) -> (u32, u32) {
+		match self {
+			EmbeddedImage::Jpeg { width, height, .. } => (*width, *height),
+			EmbeddedImage::Pixels(image) => image.dimensions(),
+		}
+	}
+}
+
+/// Writes the objects of the file and records where each of them starts
+struct PdfFile {
+	out: BufWriter<File>,
+	position: usize,
+	/// The byte offset of every object. The object numbers start from 1.
+	offsets: Vec<usize>,
+}
+
+impl PdfFile {
+	fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+		self.out.write_all(bytes)?;
+		self.position += bytes.len();
+		Ok(())
+	}
+
+	/// Reserves the number of an object that's written later
+	fn reserve(&mut self) -> usize {
+		self.offsets.push(0);
+		self.offsets.len()
+	}
+
+	fn write_object(
+		&mut self,
+		id: usize,
+		dictionary: &str,
+		stream: Option<&[u8]>,
+	) -> io::Result<()> {
+		self.offsets[id - 1] = self.position;
+		self.write(format!("{} 0 obj\n{}\n", id, dictionary).as_bytes())?;
+		if let Some(stream) = stream {
+			self.write(b"stream\n")?;
+			self.write(stream)?;
+			self.write(b"\nendstream\n")?;
+		}
+		self.write(b"endobj\n")
+	}
+
+	fn finish(mut self, catalog_id: usize) -> io::Result<()> {
+		let xref_position = self.position;
+		let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", self.offsets.len() + 1);
+		for offset in &self.offsets {
+			xref.push_str(&format!("{:010} 00000 n \n", offset));
+		}
+		xref.push_str(&format!(
+			"trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+			self.offsets.len() + 1,
+			catalog_id,
+			xref_position
+		));
+		self.write(xref.as_bytes())?;
+		self.out.flush()
+	}
+}
+
+/// Writes a PDF to `target` with one page for each of the images, in the given order. The images
+/// are scaled to fit the page within the margins, and the page is turned to landscape for
+/// images that are wider than they are tall. `progress` is called after each image with the
+/// number of images that were processed.
+pub fn write_pdf(
+	images: &[PathBuf],
+	target: &Path,
+	layout: PageLayout,
+	mut progress: impl FnMut(usize),
+) -> Result<PdfSummary, String> {
+	let result = write_pages(images, target, layout, &mut progress);
+	if !matches!(result, Ok(PdfSummary { pages, .. }) if pages > 0) {
+		let _ = fs::remove_file(target);
+	}
+	match result {
+		Ok(summary) if summary.pages == 0 => Err("None of the images could be read".into()),
+		Ok(summary) => Ok(summary),
+		Err(e) => Err(format!("Could not write {}: {}", target.display(), e)),
+	}
+}
+
+fn write_pages(
+	images: &[PathBuf],
+	target: &Path,
+	layout: PageLayout,
+	progress: &mut impl FnMut(usize),
+) -> io::Result<PdfSummary> {
+	let (mut pdf, catalog_id, pages_id) = start_document(target)?;
+	let (paper_w, paper_h) = layout.paper.size_mm();
+	let margin = layout.margin_mm.max(0.0) * POINTS_PER_MM;
+	let mut page_ids = Vec::new();
+	let mut skipped = 0;
+	for (index, path) in images.iter().enumerate() {
+		let image = match read_image(path) {
+			Ok(image) => image,
+			Err(e) => {
+				eprintln!("Could not add {:?} to the PDF: {}", path, e);
+				skipped += 1;
+				progress(index + 1);
+				continue;
+			}
+		};
+		let (img_w, img_h) = image.dimensions();
+		let (mut page_w, mut page_h) = (paper_w * POINTS_PER_MM, paper_h * POINTS_PER_MM);
+		if img_w > img_h {
+			std::mem::swap(&mut page_w, &mut page_h);
+		}
+		let draw_size = fit_size((img_w, img_h), (page_w, page_h), margin);
+		page_ids.push(write_page(&mut pdf, pages_id, image, (page_w, page_h), draw_size)?);
+		progress(index + 1);
+	}
+	finish_document(pdf, catalog_id, pages_id, &page_ids)?;
+	Ok(PdfSummary { pages: page_ids.len(), skipped })
+}
+
+/// Writes a PDF with the image at `path` on a single page, for printing it
+pub fn write_print_pdf(path: &Path, target: &Path, layout: PrintLayout) -> Result<(), String> {
+	let image = read_image(path)?;
+	let (page_size, draw_size) = layout.page_and_image_size(image.dimensions());
+	let image = match image {
+		// There's no use in more pixels than the printer prints at the DPI
+		EmbeddedImage::Pixels(pixels) => {
+			let dpi = layout.dpi.max(1) as f32;
+			let max_w = (draw_size.0 / POINTS_PER_INCH * dpi).ceil() as u32;
+			let max_h = (draw_size.1 / POINTS_PER_INCH * dpi).ceil() as u32;
+			if pixels.width() > max_w.max(1) && pixels.height() > max_h.max(1) {
+				let filter = imageops::FilterType::Lanczos3;
+				EmbeddedImage::Pixels(imageops::resize(&pixels, max_w, max_h, filter))
+			} else {
+				EmbeddedImage::Pixels(pixels)
+			}
+		}
+		jpeg => jpeg,
+	};
+	let write = || -> io::Result<()> {
+		let (mut pdf, catalog_id, pages_id) = start_document(target)?;
+		let page_id = write_page(&mut pdf, pages_id, image, page_size, draw_size)?;
+		finish_document(pdf, catalog_id, pages_id, &[page_id])
+	};
+	write().map_err(|e| format!("Could not write {}: {}", target.display(), e))
+}
+
+/// The size of the image scaled to fit the page within the margins, in points
+fn fit_size((img_w, img_h): (u32, u32), (page_w, page_h): (f32, f32), margin: f32) -> (f32, f32) {
+	let available_w = (page_w - 2.0 * margin).max(1.0);
+	let available_h = (page_h - 2.0 * margin).max(1.0);
+	let scale = (available_w / img_w as f32).min(available_h / img_h as f32);
+	(img_w as f32 * scale, img_h as f32 * scale)
+}
+
+/// Creates the file and reserves the numbers of the catalog and of the page tree
+fn start_document(target: &Path) -> io::Result<(PdfFile, usize, usize)> {
+	let mut pdf =
+		PdfFile { out: BufWriter::new(File::create(target)?), position: 0, offsets: Vec::new() };
+	// The second line has bytes above 127 so that the file is recognized as binary
+	pdf.write(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n")?;
+	let catalog_id = pdf.reserve();
+	let pages_id = pdf.reserve();
+	Ok((pdf, catalog_id, pages_id))
+}
+
+/// Writes a page with the image centered on it and returns the object number of the page
+fn write_page(
+	pdf: &mut PdfFile,
+	pages_id: usize,
+	image: EmbeddedImage,
+	(page_w, page_h): (f32, f32),
+	(draw_w, draw_h): (f32, f32),
+) -> io::Result<usize> {
+	let (x, y) = ((page_w - draw_w) * 0.5, (page_h - draw_h) * 0.5);
+	let image_id = write_image(pdf, image)?;
+	let contents = format!("q {:.3} 0 0 {:.3} {:.3} {:.3} cm /Im0 Do Q", draw_w, draw_h, x, y);
+	let contents_id = pdf.reserve();
+	pdf.write_object(
+		contents_id,
+		&format!("<< /Length {} >>", contents.len()),
+		Some(contents.as_bytes()),
+	)?;
+	let page_id = pdf.reserve();
+	pdf.write_object(
+		page_id,
+		&format!(
+			"<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {:.3} {:.3}] /Contents {} 0 R \
+			 /Resources << /XObject << /Im0 {} 0 R >> >> >>",
+			pages_id, page_w, page_h, contents_id, image_id
+		),
+		None,
+	)?;
+	Ok(page_id)
+}
+
+fn finish_document(
+	mut pdf: PdfFile,
+	catalog_id: usize,
+	pages_id: usize,
+	page_ids: &[usize],
+) -> io::Result<()> {
+	let kids: Vec<String> = page_ids.iter().map(|id| format!("{} 0 R", id)).collect();
+	pdf.write_object(
+		pages_id,
+		&format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), page_ids.len()),
+		None,
+	)?;
+	pdf.write_object(catalog_id, &format!("<< /Type /Catalog /Pages {} 0 R >>", pages_id), None)?;
+	pdf.finish(catalog_id)
+}
+
+/// Writes the image XObject and returns its object number
+fn write_image(pdf: &mut PdfFile, image: EmbeddedImage) -> io::Result<usize> {
+	let image_id = pdf.reserve();
+	match image {
+		EmbeddedImage::Jpeg { data, width, height, components } => {
+			let color_space = if components == 1 { "/DeviceGray" } else { "/DeviceRGB" };
+			let dictionary = format!(
+				"<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace {} \
+				 /BitsPerComponent 8 /Filter /DCTDecode /Length {} >>",
+				width,
+				height,
+				color_space,
+				data.len()
+			);
+			pdf.write_object(image_id, &dictionary, Some(&data))?;
+		}
+		EmbeddedImage::Pixels(image) => {
+			let (width, height) = image.dimensions();
+			let has_alpha = image.pixels().any(|p| p[3] < 255);
+			let mut smask = String::new();
+			if has_alpha {
+				let alpha: Vec<u8> = image.pixels().map(|p| p[3]).collect();
+				let alpha = deflate(&alpha)?;
+				let smask_id = pdf.reserve();
+				let dictionary = format!(
+					"<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace \
+					 /DeviceGray /BitsPerComponent 8 /Filter /FlateDecode /Length {} >>",
+					width,
+					height,
+					alpha.len()
+				);
+				pdf.write_object(smask_id, &dictionary, Some(&alpha))?;
+				smask = format!(" /SMask {} 0 R", smask_id);
+			}
+			let rgb: Vec<u8> = image.pixels().flat_map(|p| [p[0], p[1], p[2]]).collect();
+			let rgb = deflate(&rgb)?;
+			let dictionary = format!(
+				"<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB \
+				 /BitsPerComponent 8 /Filter /FlateDecode{} /Length {} >>",
+				width,
+				height,
+				smask,
+				rgb.len()
+			);
+			pdf.write_object(image_id, &dictionary, Some(&rgb))?;
+		}
+	}
+	Ok(image_id)
+}
+
+fn deflate(data: &[u8]) -> io::Result<Vec<u8>> {
+	let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+	encoder.write_all(data)?;
+	encoder.finish()
+}
+
+/// Reads the JPEG as it is if a PDF reader can display it the same way, otherwise decodes the
+/// image. Only the first frame of animations is used.
+fn read_image(path: &Path) -> Result<EmbeddedImage, String> {
+	let is_jpeg = matches!(detect_format(path), Ok(ImgFormat::Image(ImageFormat::Jpeg)));
+	// The PDF would show the pixels without the rotation of the EXIF orientation tag
+	if is_jpeg && matches!(detect_orientation(path), Ok(Orientation::Deg0) | Err(_)) {
+		let data = fs::read(path).map_err(|e| e.to_string())?;
+		if let Some((width, height, components)) = jpeg_frame_info(&data) {
+			if components == 1 || components == 3 {
+				return Ok(EmbeddedImage::Jpeg { data, width, height, components });
+			}
+		}
+	}
+	let mut first_frame = None;
+	complex_load_image(path, false, 0, |frame| {
+		if let LoadResult::Frame { image, orientation, .. } = frame {
+			first_frame = Some(orientation.apply(image));
+		}
+		Ok(())
+	})
+	.map_err(|e| e.to_string())?;
+	first_frame.map(EmbeddedImage::Pixels).ok_or_else(|| "The image has no frames".into())
+}
+
+/// Reads the width, the height and the number of color components from the start of frame
+/// segment of a JPEG with 8 bits per sample
+pub fn jpeg_frame_info(data: &[u8]) -> Option<(u32, u32, u8)> {
+	if !data.starts_with(&[0xFF, 0xD8]) {
+		return None;
+	}
+	let mut pos = 2;
+	while pos + 4 <= data.len() {
+		if data[pos] != 0xFF {
+			return None;
+		}
+		let marker = data[pos + 1];
+		if marker == 0xFF {
+			// Fill byte
+			pos += 1;
+			continue;
+		}
+		let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+		let is_start_of_frame =
+			matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC);
+		if is_start_of_frame {
+			let segment = data.get(pos + 4..pos + 10)?;
+			let precision = segment[0];
+			let height = u16::from_be_bytes([segment[1], segment[2]]) as u32;
+			let width = u16::from_be_bytes([segment[3], segment[4]]) as u32;
+			let components = segment[5];
+			return if precision == 8 && width > 0 && height > 0 {
+				Some((width, height, components))
+			} else {
+				None
+			};
+		}
+		if marker == 0xDA {
+			// The scan starts before any frame header was found
+			return None;
+		}
+		pos += 2 + length;
+	}
+	None
+}
+
+/// The file next to the images with the name of their folder, or with a number appended if
+/// that already exists
+pub fn pdf_path(folder: &Path) -> PathBuf {
+	let name = folder
+		.file_name()
+		.map(|n| n.to_string_lossy().into_owned())
+		.unwrap_or_else(|| "images".into());
+	let mut target = folder.join(format!("{}.pdf", name));
+	let mut number = 2;
+	while target.exists() {
+		target = folder.join(format!("{}-{}.pdf", name, number));
+		number += 1;
+	}
+	target
+}
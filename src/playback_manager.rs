@@ -1,11 +1,12 @@
-use std::io::Write;
+use std::borrow::Cow;
 use std::marker::PhantomData;
 use std::mem;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
 use std::time::{Duration, Instant};
 
-use gelatin::NextUpdate;
 use rand::seq::SliceRandom;
 use rand::thread_rng;
 
@@ -14,14 +15,23 @@ use log::{debug, trace};
 use gelatin::window::Window;
 use gelatin::Display;
 
+use gelatin::image::RgbaImage;
+
+use crate::configuration::ScaleQuality;
 use crate::image_cache::{
-	self, AnimationFrameTexture, ImageCache, PathResolutionError, PathedTextureResult,
-	TextureResult,
+	self,
+	archive::{self, ComicBook},
+	image_loader::{self, Orientation, TextureColorSpace},
+	load_log::LoadLog,
+	AnimationFrameTexture, CacheStats, ImageCache, LoadBreakdown, PathResolutionError,
+	PathedTextureResult, TextureResult,
 };
 
-use image_cache::directory;
+use image_cache::directory::{self, DirListing};
 
 const NANOS_PER_SEC: u64 = 1_000_000_000;
+/// How often the files are added to the list while the folder is read in the background
+const DIR_SCAN_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum LoadRequest {
@@ -146,6 +156,18 @@ impl Playback for AnimPlayback {
 pub struct PlaybackManager {
 	//playback_state: PlaybackState,
 	image_cache: ImageCache,
+	cache_capacity: isize,
+	thread_count: u32,
+	preload_all: bool,
+	scale_quality: ScaleQuality,
+	zoom_hint: Option<f32>,
+	prefetch_window: (usize, usize),
+	max_texture_size: Option<u32>,
+	/// The comic book that the shown pages are from
+	book: Option<ComicBook>,
+	/// The archive whose pages are being extracted and the receiver of the extracted book.
+	/// The cover is shown from the archive in the meantime.
+	book_extraction: Option<(PathBuf, Receiver<image_loader::Result<ComicBook>>)>,
 
 	// image_texture: Option<Rc<glium::texture::SrgbTexture2d>>,
 	// filename: Option<OsString>,
@@ -153,34 +175,154 @@ pub struct PlaybackManager {
 	image_player: ImgSequencePlayer<AnimPlayback>,
 }
 
-impl PlaybackManager {
-	pub fn new() -> Self {
-		let cache_capacity = match sys_info::mem_info() {
-			Ok(value) => {
-				// value originally reported in KiB
-				((value.total / 8) * 1024) as isize
-			}
-			_ => {
-				eprintln!("Could not get system memory size, using default value");
-				// bytes
-				500_000_000
-			}
-		};
+/// The number of bytes used for caching images if it's not set in the configuration
+pub fn default_cache_capacity() -> isize {
+	match sys_info::mem_info() {
+		Ok(value) => {
+			// value originally reported in KiB
+			((value.total / 8) * 1024) as isize
+		}
+		_ => {
+			eprintln!("Could not get system memory size, using default value");
+			// bytes
+			500_000_000
+		}
+	}
+}
 
-		let thread_count = match sys_info::cpu_num() {
-			Ok(value) => value.clamp(2, 4),
-			_ => 4,
-		};
+/// The number of image loader threads if it's not set in the configuration
+pub fn default_thread_count() -> u32 {
+	match sys_info::cpu_num() {
+		Ok(value) => value.clamp(2, 4),
+		_ => 4,
+	}
+}
+
+/// The time each image is shown for during a presentation, if it's not set in the configuration
+pub const DEFAULT_PRESENTATION_INTERVAL: Duration = Duration::from_secs(6);
+/// Shorter intervals are raised to this, so that the images still get a chance to be drawn
+pub const MIN_PRESENTATION_INTERVAL: Duration = Duration::from_millis(100);
 
+impl PlaybackManager {
+	pub fn new(cache_capacity: isize, thread_count: u32) -> Self {
 		PlaybackManager {
 			//playback_state: PlaybackState::Paused,
 			// filename: None,
 			image_cache: ImageCache::new(cache_capacity, thread_count),
+			cache_capacity,
+			thread_count,
+			preload_all: false,
+			scale_quality: ScaleQuality::default(),
+			zoom_hint: None,
+			prefetch_window: image_cache::DEFAULT_PREFETCH_WINDOW,
+			max_texture_size: None,
+			book: None,
+			book_extraction: None,
 			folder_player: ImgSequencePlayer::new(),
 			image_player: ImgSequencePlayer::new(),
 		}
 	}
 
+	/// Replaces the image cache if the capacity or the number of loader threads changed.
+	/// The currently shown image is loaded again into the new cache.
+	pub fn set_cache_settings(&mut self, cache_capacity: isize, thread_count: u32) {
+		if self.cache_capacity == cache_capacity && self.thread_count == thread_count {
+			return;
+		}
+		self.cache_capacity = cache_capacity;
+		self.thread_count = thread_count;
+		self.image_cache = ImageCache::new(cache_capacity, thread_count);
+		self.image_cache.set_preload_all(self.preload_all);
+		self.image_cache.set_scale_quality(self.scale_quality);
+		self.image_cache.set_zoom_hint(self.zoom_hint);
+		self.image_cache.set_prefetch_window(self.prefetch_window.0, self.prefetch_window.1);
+		self.image_cache.set_max_texture_size(self.max_texture_size);
+		if let LoadedImgPath::Loaded(path) = self.shown_file_path().clone() {
+			self.request_load(LoadRequest::FilePath(path));
+		}
+	}
+
+	/// Stops showing the current file and drops every cached texture, so that the viewer is
+	/// in the same state as when it was started without a file
+	pub fn close_file(&mut self) {
+		self.image_cache = ImageCache::new(self.cache_capacity, self.thread_count);
+		self.image_cache.set_preload_all(self.preload_all);
+		self.image_cache.set_scale_quality(self.scale_quality);
+		self.image_cache.set_zoom_hint(self.zoom_hint);
+		self.image_cache.set_prefetch_window(self.prefetch_window.0, self.prefetch_window.1);
+		self.image_cache.set_max_texture_size(self.max_texture_size);
+		self.book = None;
+		self.book_extraction = None;
+		let presentation_interval = self.presentation_interval();
+		self.folder_player = ImgSequencePlayer::new();
+		self.folder_player.presentation_interval = presentation_interval;
+		self.image_player = ImgSequencePlayer::new();
+	}
+
+	/// `ImageCache::set_scale_quality`
+	pub fn set_scale_quality(&mut self, quality: ScaleQuality) {
+		self.scale_quality = quality;
+		self.image_cache.set_scale_quality(quality);
+	}
+
+	/// `ImageCache::set_zoom_hint`
+	pub fn set_zoom_hint(&mut self, zoom: Option<f32>) {
+		self.zoom_hint = zoom;
+		self.image_cache.set_zoom_hint(zoom);
+	}
+
+	/// `ImageCache::set_prefetch_window`
+	pub fn set_prefetch_window(&mut self, ahead: usize, behind: usize) {
+		self.prefetch_window = (ahead, behind);
+		self.image_cache.set_prefetch_window(ahead, behind);
+	}
+
+	/// `ImageCache::set_max_texture_size`
+	pub fn set_max_texture_size(&mut self, max_size: Option<u32>) {
+		self.max_texture_size = max_size;
+		self.image_cache.set_max_texture_size(max_size);
+	}
+
+	/// Loads every image of the directory into the cache in the background, see
+	/// `ImageCache::set_preload_all`
+	pub fn set_preload_all(&mut self, enabled: bool) {
+		self.preload_all = enabled;
+		self.image_cache.set_preload_all(enabled);
+	}
+
+	/// Returns the number of preloaded images and the number of images in the directory
+	pub fn preload_progress(&mut self) -> Option<(usize, usize)> {
+		self.image_cache.preload_progress()
+	}
+
+	pub fn load_log(&self) -> &LoadLog {
+		self.image_cache.load_log()
+	}
+
+	pub fn load_breakdown(&self) -> Option<LoadBreakdown> {
+		self.image_cache.load_breakdown()
+	}
+
+	pub fn detected_format(&self) -> Option<Cow<'static, str>> {
+		self.image_cache.detected_format()
+	}
+
+	pub fn queue_depth(&self) -> usize {
+		self.image_cache.queue_depth()
+	}
+
+	pub fn cache_stats(&self) -> CacheStats {
+		self.image_cache.stats()
+	}
+
+	pub fn set_presentation_interval(&mut self, interval: Duration) {
+		self.folder_player.presentation_interval = interval;
+	}
+
+	pub fn presentation_interval(&self) -> Duration {
+		self.folder_player.presentation_interval
+	}
+
 	pub fn playback_state(&self) -> PlaybackState {
 		self.folder_player.playback_state()
 	}
@@ -222,6 +364,28 @@ impl PlaybackManager {
 		self.image_cache.current_dir_len()
 	}
 
+	/// Returns None when the folder hasn't finished filtering
+	pub fn image_paths(&mut self) -> Option<Vec<PathBuf>> {
+		self.image_cache.image_paths()
+	}
+
+	/// `ImageCache::current_dir`
+	pub fn current_dir(&self) -> Option<&Path> {
+		self.image_cache.current_dir()
+	}
+
+	/// Replaces the list of files with a listing that was read in the background, and shows
+	/// the current file again or the next one if it was removed. Returns false if another
+	/// folder is shown by now.
+	pub fn apply_listing(&mut self, listing: DirListing) -> bool {
+		if !self.image_cache.apply_listing(listing) {
+			return false;
+		}
+		let path = self.image_cache.current_file_path().unwrap_or_default();
+		self.request_load(LoadRequest::FilePath(path));
+		true
+	}
+
 	pub fn update_directory(&mut self) -> directory::Result<()> {
 		debug!("In `update_directory`");
 		if let LoadRequest::None = self.folder_player.load_request {
@@ -241,20 +405,121 @@ impl PlaybackManager {
 	}
 
 	pub fn request_load(&mut self, request: LoadRequest) {
+		match &request {
+			LoadRequest::FilePath(path) => self.update_book(path),
+			// Going somewhere else while the book is extracted doesn't open it afterwards
+			_ => self.book_extraction = None,
+		}
 		self.folder_player.request_load(request);
 		self.image_player.request_load(LoadRequest::Jump(0));
 	}
 
+	/// Starts extracting the comic book on another thread if `path` points to one. The
+	/// previous book is closed when a path outside of it is opened.
+	fn update_book(&mut self, path: &Path) {
+		if archive::is_comic_book(path) {
+			let extracting = self.book_extraction.as_ref().map(|(archive, _)| archive.as_path());
+			if extracting != Some(path) {
+				let (sender, receiver) = mpsc::channel();
+				let archive_path = path.to_owned();
+				thread::spawn(move || {
+					// The book removes its folder when it's dropped because nobody waits
+					let _ = sender.send(ComicBook::extract(&archive_path, &std::env::temp_dir()));
+				});
+				self.book_extraction = Some((path.to_owned(), receiver));
+			}
+		} else {
+			self.book_extraction = None;
+		}
+		if self.book.as_ref().is_some_and(|book| !book.contains(path)) {
+			self.book = None;
+		}
+	}
+
+	/// Shows the first page once the comic book is extracted. Returns true while extracting.
+	fn update_book_extraction(&mut self) -> bool {
+		let result = match &self.book_extraction {
+			Some((_, receiver)) => match receiver.try_recv() {
+				Ok(result) => result,
+				Err(TryRecvError::Empty) => return true,
+				Err(TryRecvError::Disconnected) => {
+					self.book_extraction = None;
+					return false;
+				}
+			},
+			None => return false,
+		};
+		let (archive_path, _) = self.book_extraction.take().unwrap();
+		match result {
+			Ok(book) => {
+				if let Some(first_page) = book.first_page() {
+					self.book = Some(book);
+					self.folder_player.request_load(LoadRequest::FilePath(first_page));
+					self.image_player.request_load(LoadRequest::Jump(0));
+				}
+			}
+			// Only the cover is shown then
+			Err(e) => eprintln!("Could not open the comic book {:?}: {}", archive_path, e),
+		}
+		false
+	}
+
+	/// Returns the path of the comic book archive, the index of the shown page and the number
+	/// of pages, if a comic book is open
+	pub fn book_page(&mut self) -> Option<(PathBuf, usize, usize)> {
+		let archive_path = self.book.as_ref()?.archive_path.clone();
+		Some((archive_path, self.current_file_index()?, self.current_dir_len()?))
+	}
+
 	pub fn image_texture(&self) -> Option<AnimationFrameTexture> {
 		self.image_player.image_texture()
 	}
 
+	/// Displays an image that isn't loaded from a file, like one pasted from the clipboard.
+	/// `path` is only used to name the image, there doesn't have to be a file at it.
+	///
+	/// Navigating to the next or previous image continues within the folder that was shown
+	/// before.
+	pub fn show_image(
+		&mut self,
+		display: &Display,
+		image: RgbaImage,
+		path: PathBuf,
+	) -> TextureResult<()> {
+		let image = image_cache::fit_texture_limit(display, image, self.max_texture_size);
+		let texture = AnimationFrameTexture::from_image(
+			display,
+			image,
+			0,
+			Orientation::Deg0,
+			TextureColorSpace::Srgb,
+		)?;
+		self.folder_player.pause_playback();
+		self.folder_player.image_texture = Some(texture.clone());
+		self.image_player.image_texture = Some(texture);
+		self.folder_player.file_path = LoadedImgPath::Loaded(path.clone());
+		self.image_player.file_path = LoadedImgPath::Loaded(path);
+		Ok(())
+	}
+
+	/// Since when the next image to show is being loaded, or `None` if it isn't
+	pub fn loading_since(&self) -> Option<Instant> {
+		self.folder_player.waiting_since
+	}
+
 	/// The path to the image file which is currently rendered onto the screen.
 	pub fn shown_file_path(&self) -> &LoadedImgPath {
 		&self.folder_player.file_path
 	}
 
+	/// Why the file couldn't be loaded, if loading it failed since this was last called
+	pub fn take_load_error(&mut self) -> Option<String> {
+		self.folder_player.load_error.take()
+	}
+
 	pub fn update_image(&mut self, window: &Window) -> gelatin::NextUpdate {
+		let extracting_book = self.update_book_extraction();
+		let scanning_dir = self.image_cache.update_dir_scan();
 		let display = window.display_mut();
 		let prev_file = self.folder_player.image_texture();
 		let next_update = self.folder_player.update_image(&display, &mut self.image_cache);
@@ -275,11 +540,25 @@ impl PlaybackManager {
 			self.image_player.update_image(&display, &mut self.image_cache);
 
 		// trace!("Image player next update: {:?}", img_player_next_update);
-		next_update.aggregate(img_player_next_update)
+		let mut next_update = next_update.aggregate(img_player_next_update);
+		if let Some((preloaded, total)) = self.image_cache.preload_progress() {
+			if preloaded < total {
+				// Images that fail to load are reported when they are shown
+				let _ = self.image_cache.process_prefetched(&display);
+				self.image_cache.preload_next();
+				let next_check = Instant::now() + Duration::from_millis(20);
+				next_update = next_update.aggregate(gelatin::NextUpdate::WaitUntil(next_check));
+			}
+		}
+		if scanning_dir || extracting_book {
+			let next_check = Instant::now() + DIR_SCAN_POLL_INTERVAL;
+			next_update = next_update.aggregate(gelatin::NextUpdate::WaitUntil(next_check));
+		}
+		next_update
 	}
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum LoadedImgPath {
 	NotYetLoaded,
 	ErrLoading(PathBuf),
@@ -298,11 +577,17 @@ struct ImgSequencePlayer<P: Playback> {
 
 	last_frame_change_time: Instant,
 	frametime_drift_offset: i64, // in nanosecs
+	presentation_interval: Duration,
 	//frame_count_since_playback_start: u64,
 	load_request: LoadRequest,
 
 	image_texture: Option<AnimationFrameTexture>,
 	file_path: LoadedImgPath,
+	/// When the requested image was first found to be not loaded yet, or `None` if nothing
+	/// is being waited on
+	waiting_since: Option<Instant>,
+	/// Why the last image that was tried couldn't be loaded, until it's taken to be shown
+	load_error: Option<String>,
 
 	_playback: PhantomData<P>,
 }
@@ -314,10 +599,13 @@ impl<P: Playback> ImgSequencePlayer<P> {
 			present_remaining: Vec::new(),
 			last_frame_change_time: Instant::now(),
 			frametime_drift_offset: 0,
+			presentation_interval: DEFAULT_PRESENTATION_INTERVAL,
 			//frame_count_since_playback_start: 0,
 			load_request: LoadRequest::None,
 			image_texture: None,
 			file_path: LoadedImgPath::NotYetLoaded,
+			waiting_since: None,
+			load_error: None,
 
 			_playback: PhantomData,
 		}
@@ -388,14 +676,11 @@ impl<P: Playback> ImgSequencePlayer<P> {
 		// function early. And at the same time I want to use it's value as it is at this line.
 		let mut load_request = LoadRequest::None;
 		mem::swap(&mut self.load_request, &mut load_request);
-		let frame_delta_time_nanos;
-		match self.playback_state {
+		let frame_delta_time_nanos = match self.playback_state {
 			PlaybackState::Present | PlaybackState::RandomPresent => {
-				frame_delta_time_nanos = (NANOS_PER_SEC * 6) as i64;
-			}
-			_ => {
-				frame_delta_time_nanos = P::delay_nanos(self) as i64;
+				self.presentation_interval.as_nanos() as i64
 			}
+			_ => P::delay_nanos(self) as i64,
 		};
 		if self.playback_state == PlaybackState::Paused {
 			if let Err(e) = image_cache.process_prefetched(display) {
@@ -429,12 +714,8 @@ impl<P: Playback> ImgSequencePlayer<P> {
 			// This assumes that the following frames have the same delay but that's okay considering that
 			// if frame step is greater than 1 it almost certainly means that we couldn't load the
 			// next frame quiclky enough so there's not much else to do here.
-			let frame_step;
-			if frame_delta_time_nanos > 0 {
-				frame_step = elapsed_nanos / frame_delta_time_nanos;
-			} else {
-				frame_step = 0;
-			}
+			let frame_step =
+				if frame_delta_time_nanos > 0 { elapsed_nanos / frame_delta_time_nanos } else { 0 };
 			if frame_step > 0 {
 				load_request = match self.playback_state {
 					PlaybackState::Forward | PlaybackState::Present => {
@@ -504,6 +785,12 @@ impl<P: Playback> ImgSequencePlayer<P> {
 			LoadRequest::None => None,
 		};
 		if let Some(loaded_image) = load_result {
+			let waiting = matches!(
+				loaded_image,
+				Ok((_, Err(image_cache::TextureError::WaitingOnLoader)))
+					| Err(PathResolutionError::WaitingOnDirFilter)
+			);
+			self.waiting_since = if waiting { self.waiting_since.or(Some(now)) } else { None };
 			match loaded_image {
 				Ok((path, result)) => match result {
 					Ok(frame) => {
@@ -511,6 +798,11 @@ impl<P: Playback> ImgSequencePlayer<P> {
 						self.file_path = LoadedImgPath::Loaded(path);
 					}
 					Err(image_cache::TextureError::WaitingOnLoader) => {
+						// The embedded thumbnail is shown until the image itself is uploaded
+						if let Some(placeholder) = image_cache.placeholder(display) {
+							self.image_texture = Some(placeholder);
+							self.file_path = LoadedImgPath::Loaded(path);
+						}
 						// Set the load request to jump in place so that
 						// next time we attempt to load this again.
 						self.load_request = LoadRequest::Jump(0);
@@ -519,14 +811,9 @@ impl<P: Playback> ImgSequencePlayer<P> {
 					Err(err) => {
 						self.image_texture = None;
 						self.file_path = LoadedImgPath::ErrLoading(path);
-						let stderr = &mut ::std::io::stderr();
-						let stderr_errmsg = "Error writing to stderr";
-						writeln!(stderr, "Error occurred while loading image: {}", err)
-							.expect(stderr_errmsg);
-						// if let Some(backtrace) = err.backtrace() {
-						// 	writeln!(stderr, "backtrace: {:?}", backtrace).expect(stderr_errmsg);
-						// }
-						writeln!(stderr).expect(stderr_errmsg);
+						// Goes to the log file if there is one, see `log_file`
+						eprintln!("Error occurred while loading image: {}\n", err);
+						self.load_error = Some(err.to_string());
 					}
 				},
 				Err(PathResolutionError::WaitingOnDirFilter) => {
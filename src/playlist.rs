@@ -0,0 +1,103 @@
+//! Reads and writes lists of images as M3U playlists, so that a set of images from different
+//! folders can be browsed and shared without moving the files.
+//!
+//! A session is a JSON file with the list of images, the position in the list and the view of
+//! the current image, see `save_session`. It's browsed like a playlist, and opening it also
+//! returns to the image that was shown and to its zoom and pan.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// The header of the extended M3U format, which is written into the exported playlists
+const M3U_HEADER: &str = "#EXTM3U";
+
+/// Returns true if the file at `path` is a playlist or a session, judged by its extension
+pub fn is_playlist(path: &Path) -> bool {
+	match path.extension() {
+		Some(ext) => {
+			ext.eq_ignore_ascii_case("m3u") || ext.eq_ignore_ascii_case("m3u8") || is_session(path)
+		}
+		None => false,
+	}
+}
+
+pub fn is_session(path: &Path) -> bool {
+	path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+/// The paths in the playlist, one per line. The lines starting with `#` are comments and the
+/// relative paths are relative to `base`, the folder of the playlist.
+pub fn parse_playlist(text: &str, base: &Path) -> Vec<PathBuf> {
+	text.lines()
+		.map(|line| line.trim_start_matches('\u{feff}').trim())
+		.filter(|line| !line.is_empty() && !line.starts_with('#'))
+		.map(|line| base.join(line))
+		.collect()
+}
+
+pub fn read_playlist(path: &Path) -> io::Result<Vec<PathBuf>> {
+	if is_session(path) {
+		return read_session(path).map(|session| session.files);
+	}
+	let text = fs::read_to_string(path)?;
+	Ok(parse_playlist(&text, path.parent().unwrap_or(Path::new(""))))
+}
+
+/// The playlist of `paths`. The paths inside `base` are written relative to it, so that the
+/// playlist can be moved together with the images.
+pub fn format_playlist(paths: &[PathBuf], base: &Path) -> String {
+	let mut text = String::from(M3U_HEADER);
+	text.push('\n');
+	for path in paths {
+		let path = path.strip_prefix(base).unwrap_or(path);
+		text.push_str(&path.to_string_lossy());
+		text.push('\n');
+	}
+	text
+}
+
+pub fn write_playlist(path: &Path, paths: &[PathBuf]) -> io::Result<()> {
+	fs::write(path, format_playlist(paths, path.parent().unwrap_or(Path::new(""))))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+	/// The position of the image that was shown within `files`
+	pub current_index: usize,
+	pub files: Vec<PathBuf>,
+	/// The size of an image pixel in screen pixels, like `--zoom` divided by 100
+	pub zoom: f32,
+	/// The point of the image at the center of the view, from 0 to 1 along its width and height
+	pub pan: [f32; 2],
+	/// The order that the folder was sorted in, see `--sort`
+	pub sort: String,
+}
+
+impl Session {
+	pub fn current_file(&self) -> Option<&Path> {
+		self.files.get(self.current_index).map(PathBuf::as_path)
+	}
+}
+
+/// Reads the session at `path`. The relative paths of the images are relative to the folder
+/// of the session.
+pub fn read_session(path: &Path) -> io::Result<Session> {
+	let text = fs::read_to_string(path)?;
+	let mut session: Session = serde_json::from_str(&text).map_err(io::Error::other)?;
+	let base = path.parent().unwrap_or(Path::new(""));
+	session.files = session.files.iter().map(|file| base.join(file)).collect();
+	Ok(session)
+}
+
+/// Writes the session to `path`. The images inside the folder of the session are written
+/// relative to it, like in the playlists.
+pub fn write_session(path: &Path, session: &Session) -> io::Result<()> {
+	let base = path.parent().unwrap_or(Path::new(""));
+	let files = session.files.iter().map(|file| file.strip_prefix(base).unwrap_or(file).to_owned());
+	let session = Session { files: files.collect(), ..session.clone() };
+	let text = serde_json::to_string_pretty(&session).map_err(io::Error::other)?;
+	fs::write(path, text)
+}
@@ -0,0 +1,35 @@
+//! Sends the PDFs of `print_image` to the printer with the tools of the operating system.
+
+use std::path::Path;
+use std::process::Command;
+
+/// Adds the PDF at `path` to the queue of the default printer
+pub fn print_pdf(path: &Path) -> Result<(), String> {
+	let output = platform_print_command(path)
+		.output()
+		.map_err(|e| format!("Could not start printing: {}", e))?;
+	if output.status.success() {
+		Ok(())
+	} else {
+		let stderr = String::from_utf8_lossy(&output.stderr);
+		Err(format!("Printing failed: {}", stderr.trim()))
+	}
+}
+
+#[cfg(target_os = "windows")]
+fn platform_print_command(path: &Path) -> Command {
+	let mut command = Command::new("powershell");
+	command
+		.args(["-NoProfile", "-NonInteractive", "-Command"])
+		.arg("Start-Process -FilePath $env:EMULSION_PRINT -Verb Print")
+		.env("EMULSION_PRINT", path);
+	command
+}
+
+/// CUPS comes with both macOS and the Linux desktops
+#[cfg(not(target_os = "windows"))]
+fn platform_print_command(path: &Path) -> Command {
+	let mut command = Command::new("lp");
+	command.arg("--").arg(path);
+	command
+}
@@ -0,0 +1,122 @@
+//! Stores the ratings of images in XMP sidecar files.
+//!
+//! The sidecar of `photo.jpg` is `photo.jpg.xmp` in the same folder. The rating is the
+//! standard `xmp:Rating` property. Existing sidecars are edited in place, so that the
+//! metadata written by other programs is kept.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Sender};
+use std::thread;
+
+const RATING_ATTRIBUTE: &str = "xmp:Rating=\"";
+const XMP_NAMESPACE: &str = "xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\"";
+const DESCRIPTION_START: &str = "<rdf:Description";
+
+pub fn sidecar_path(image_path: &Path) -> PathBuf {
+	let mut name = image_path.as_os_str().to_owned();
+	name.push(".xmp");
+	PathBuf::from(name)
+}
+
+/// Returns the rating from the sidecar of the image, or `None` if the image isn't rated
+pub fn read_rating(image_path: &Path) -> Option<u8> {
+	let xmp = fs::read_to_string(sidecar_path(image_path)).ok()?;
+	parse_rating(&xmp)
+}
+
+fn parse_rating(xmp: &str) -> Option<u8> {
+	let start = if let Some(index) = xmp.find(RATING_ATTRIBUTE) {
+		index + RATING_ATTRIBUTE.len()
+	} else {
+		xmp.find("<xmp:Rating>")? + "<xmp:Rating>".len()
+	};
+	let digits: String = xmp[start..].chars().take_while(|ch| ch.is_ascii_digit()).collect();
+	// Zero means that the image isn't rated, negative values mark rejected images
+	digits.parse().ok().filter(|&rating| rating > 0)
+}
+
+/// Returns the sidecar with the rating set to `rating`, or removed if it's `None`
+fn set_rating(xmp: Option<String>, rating: Option<u8>) -> String {
+	let mut xmp = match xmp {
+		Some(xmp) if xmp.contains(DESCRIPTION_START) => xmp,
+		_ => return new_sidecar(rating),
+	};
+	// Remove the previous rating in either of its forms
+	if let Some(start) = xmp.find(RATING_ATTRIBUTE) {
+		if let Some(len) = xmp[start + RATING_ATTRIBUTE.len()..].find('"') {
+			let end = start + RATING_ATTRIBUTE.len() + len + 1;
+			let start = xmp[..start].trim_end().len();
+			xmp.replace_range(start..end, "");
+		}
+	} else if let Some(start) = xmp.find("<xmp:Rating>") {
+		if let Some(len) = xmp[start..].find("</xmp:Rating>") {
+			xmp.replace_range(start..start + len + "</xmp:Rating>".len(), "");
+		}
+	}
+	if let Some(rating) = rating {
+		let description = xmp.find(DESCRIPTION_START).unwrap() + DESCRIPTION_START.len();
+		let mut attributes = format!(" {}{}\"", RATING_ATTRIBUTE, rating);
+		if !xmp.contains(XMP_NAMESPACE) {
+			attributes = format!(" {}{}", XMP_NAMESPACE, attributes);
+		}
+		xmp.insert_str(description, &attributes);
+	}
+	xmp
+}
+
+fn new_sidecar(rating: Option<u8>) -> String {
+	let rating = rating.map(|r| format!("\n      {}{}\"", RATING_ATTRIBUTE, r)).unwrap_or_default();
+	format!(
+		"<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n  \
+		<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n    \
+		<rdf:Description rdf:about=\"\"\n      {}{}/>\n  \
+		</rdf:RDF>\n\
+		</x:xmpmeta>\n",
+		XMP_NAMESPACE, rating
+	)
+}
+
+pub fn write_rating(image_path: &Path, rating: Option<u8>) -> io::Result<()> {
+	let path = sidecar_path(image_path);
+	let existing = match fs::read_to_string(&path) {
+		Ok(xmp) => Some(xmp),
+		Err(e) if e.kind() == io::ErrorKind::NotFound => {
+			if rating.is_none() {
+				// There's nothing to remove
+				return Ok(());
+			}
+			None
+		}
+		Err(e) => return Err(e),
+	};
+	fs::write(&path, set_rating(existing, rating))
+}
+
+/// Writes the sidecars on a background thread so that the disk access doesn't block the UI.
+/// The writes happen in the order of the requests.
+pub struct RatingWriter {
+	sender: Sender<(PathBuf, Option<u8>)>,
+}
+
+impl RatingWriter {
+	pub fn new() -> RatingWriter {
+		let (sender, receiver) = channel::<(PathBuf, Option<u8>)>();
+		thread::Builder::new()
+			.name("rating writer".into())
+			.spawn(move || {
+				for (path, rating) in receiver {
+					if let Err(e) = write_rating(&path, rating) {
+						eprintln!("Could not write the rating of {:?}: {}", path, e);
+					}
+				}
+			})
+			.unwrap();
+		RatingWriter { sender }
+	}
+
+	pub fn request_write(&self, image_path: PathBuf, rating: Option<u8>) {
+		let _ = self.sender.send((image_path, rating));
+	}
+}
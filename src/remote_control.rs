@@ -0,0 +1,326 @@
+//! A WebSocket server that lets scripts control the viewer, see `--remote-port`.
+//!
+//! Every text message is a JSON command like `{ "action": "goto", "index": 5 }` or
+//! `{ "action": "show_overlay", "text": "Done", "duration_ms": 2000 }`. Messages
+//! that aren't valid commands are answered with `{ "error": "..." }`.
+//!
+//! Browsers send an `Origin` header with every WebSocket request, so requests that have one
+//! are refused, also when it's `null` like for sandboxed frames and `data:` pages. Otherwise
+//! any web page that's open could control the viewer.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::Deserialize;
+
+/// How often the received commands are checked
+pub const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Appended to the key of the client to compute the accept header of the handshake
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+/// Messages longer than this close the connection
+const MAX_MESSAGE_LEN: usize = 64 * 1024;
+/// Handshakes whose request line and headers are longer than this together are refused
+const MAX_HANDSHAKE_LEN: u64 = 16 * 1024;
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case", deny_unknown_fields)]
+pub enum RemoteCommand {
+	Next,
+	Prev,
+	/// Shows the image at `index` in the folder, starting from 0
+	Goto {
+		index: usize,
+	},
+	/// Multiplies the zoom by `factor`, keeping the center of the window in place
+	Zoom {
+		factor: f32,
+	},
+	/// Shows the text over the picture for `duration_ms` milliseconds
+	ShowOverlay {
+		text: String,
+		duration_ms: u32,
+	},
+	Quit,
+}
+
+/// Accepts the connections on a background thread and passes the commands that arrive to
+/// the main thread
+pub struct RemoteControl {
+	receiver: Receiver<RemoteCommand>,
+}
+
+impl RemoteControl {
+	/// Listens on `port` of the loopback interface, so that only the programs running on this
+	/// computer can connect
+	pub fn start(port: u16) -> io::Result<RemoteControl> {
+		let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+		let (sender, receiver) = channel();
+		thread::Builder::new()
+			.name("remote control".into())
+			.spawn(move || accept_connections(listener, sender))?;
+		Ok(RemoteControl { receiver })
+	}
+
+	/// Returns the commands that arrived since the last call, in order
+	pub fn try_recv_all(&self) -> Vec<RemoteCommand> {
+		let mut commands = Vec::new();
+		loop {
+			match self.receiver.try_recv() {
+				Ok(command) => commands.push(command),
+				Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => return commands,
+			}
+		}
+	}
+}
+
+fn accept_connections(listener: TcpListener, sender: Sender<RemoteCommand>) {
+	for stream in listener.incoming() {
+		let stream = match stream {
+			Ok(stream) => stream,
+			Err(e) => {
+				eprintln!("Could not accept a remote control connection: {}", e);
+				continue;
+			}
+		};
+		let sender = sender.clone();
+		let spawned =
+			thread::Builder::new().name("remote control client".into()).spawn(move || {
+				if let Err(e) = serve_client(stream, &sender) {
+					eprintln!("The remote control connection failed: {}", e);
+				}
+			});
+		if let Err(e) = spawned {
+			eprintln!("Could not start the remote control connection: {}", e);
+		}
+	}
+}
+
+fn serve_client(stream: TcpStream, sender: &Sender<RemoteCommand>) -> io::Result<()> {
+	let mut reader = BufReader::new(stream.try_clone()?);
+	let mut writer = stream;
+	handshake(&mut reader, &mut writer)?;
+
+	let mut message = Vec::new();
+	loop {
+		let frame = read_frame(&mut reader)?;
+		if !frame.masked {
+			// RFC 6455 requires the clients to mask every frame
+			write_frame(&mut writer, OPCODE_CLOSE, &1002u16.to_be_bytes())?;
+			return Err(io::Error::new(io::ErrorKind::InvalidData, "unmasked client frame"));
+		}
+		match frame.opcode {
+			OPCODE_TEXT | OPCODE_BINARY | OPCODE_CONTINUATION => {
+				if message.len() + frame.payload.len() > MAX_MESSAGE_LEN {
+					write_frame(&mut writer, OPCODE_CLOSE, &1009u16.to_be_bytes())?;
+					return Ok(());
+				}
+				message.extend_from_slice(&frame.payload);
+				if !frame.fin {
+					continue;
+				}
+				let text = String::from_utf8_lossy(&message);
+				match serde_json::from_str::<RemoteCommand>(&text) {
+					Ok(command) => {
+						if sender.send(command).is_err() {
+							// The viewer is closing
+							return Ok(());
+						}
+					}
+					Err(e) => {
+						let reply = serde_json::json!({ "error": e.to_string() }).to_string();
+						write_frame(&mut writer, OPCODE_TEXT, reply.as_bytes())?;
+					}
+				}
+				message.clear();
+			}
+			OPCODE_PING => write_frame(&mut writer, OPCODE_PONG, &frame.payload)?,
+			OPCODE_PONG => {}
+			OPCODE_CLOSE => {
+				write_frame(&mut writer, OPCODE_CLOSE, &frame.payload)?;
+				return Ok(());
+			}
+			opcode => {
+				return Err(io::Error::new(
+					io::ErrorKind::InvalidData,
+					format!("unknown opcode {}", opcode),
+				))
+			}
+		}
+	}
+}
+
+/// Reads the HTTP upgrade request and answers it. Requests from web pages are refused.
+fn handshake(reader: &mut impl BufRead, writer: &mut impl Write) -> io::Result<()> {
+	let mut key = None;
+	let mut from_web_page = false;
+	let mut line = String::new();
+	let mut remaining = MAX_HANDSHAKE_LEN;
+	loop {
+		line.clear();
+		let read = reader.by_ref().take(remaining).read_line(&mut line)?;
+		if read == 0 && remaining > 0 {
+			return Err(io::ErrorKind::UnexpectedEof.into());
+		}
+		remaining -= read as u64;
+		if !line.ends_with('\n') {
+			writer.write_all(b"HTTP/1.1 431 Request Header Fields Too Large\r\n")?;
+			writer.write_all(b"Content-Length: 0\r\n\r\n")?;
+			return Err(io::Error::new(io::ErrorKind::InvalidData, "the handshake is too long"));
+		}
+		let line = line.trim_end();
+		if line.is_empty() {
+			break;
+		}
+		if let Some((name, value)) = line.split_once(':') {
+			if name.trim().eq_ignore_ascii_case("sec-websocket-key") {
+				key = Some(value.trim().to_owned());
+			}
+			// Even `null`, which sandboxed frames and `data:` pages send
+			if name.trim().eq_ignore_ascii_case("origin") {
+				from_web_page = true;
+			}
+		}
+	}
+	if from_web_page {
+		writer.write_all(b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\n\r\n")?;
+		return Err(io::Error::new(io::ErrorKind::PermissionDenied, "request from a web page"));
+	}
+	let key = match key {
+		Some(key) => key,
+		None => {
+			writer.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n")?;
+			return Err(io::Error::new(io::ErrorKind::InvalidData, "not a WebSocket request"));
+		}
+	};
+	let accept = BASE64.encode(sha1(format!("{}{}", key, WEBSOCKET_GUID).as_bytes()));
+	write!(
+		writer,
+		"HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+		Sec-WebSocket-Accept: {}\r\n\r\n",
+		accept
+	)?;
+	writer.flush()
+}
+
+struct Frame {
+	fin: bool,
+	masked: bool,
+	opcode: u8,
+	payload: Vec<u8>,
+}
+
+fn read_frame(reader: &mut impl Read) -> io::Result<Frame> {
+	let mut head = [0; 2];
+	reader.read_exact(&mut head)?;
+	let fin = head[0] & 0x80 != 0;
+	let opcode = head[0] & 0x0F;
+	let masked = head[1] & 0x80 != 0;
+	let len = match head[1] & 0x7F {
+		126 => {
+			let mut len = [0; 2];
+			reader.read_exact(&mut len)?;
+			u16::from_be_bytes(len) as u64
+		}
+		127 => {
+			let mut len = [0; 8];
+			reader.read_exact(&mut len)?;
+			u64::from_be_bytes(len)
+		}
+		len => len as u64,
+	};
+	if len > MAX_MESSAGE_LEN as u64 {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "the message is too long"));
+	}
+	let mut mask = [0; 4];
+	if masked {
+		reader.read_exact(&mut mask)?;
+	}
+	let mut payload = vec![0; len as usize];
+	reader.read_exact(&mut payload)?;
+	for (i, byte) in payload.iter_mut().enumerate() {
+		*byte ^= mask[i % 4];
+	}
+	Ok(Frame { fin, masked, opcode, payload })
+}
+
+/// Writes an unmasked frame, the way servers have to send them
+fn write_frame(writer: &mut impl Write, opcode: u8, payload: &[u8]) -> io::Result<()> {
+	let mut head = vec![0x80 | opcode];
+	match payload.len() {
+		len @ 0..=125 => head.push(len as u8),
+		len @ 126..=0xFFFF => {
+			head.push(126);
+			head.extend_from_slice(&(len as u16).to_be_bytes());
+		}
+		len => {
+			head.push(127);
+			head.extend_from_slice(&(len as u64).to_be_bytes());
+		}
+	}
+	writer.write_all(&head)?;
+	writer.write_all(payload)?;
+	writer.flush()
+}
+
+/// SHA-1 is only used for the accept header of the handshake, which the protocol defines
+/// with it
+fn sha1(data: &[u8]) -> [u8; 20] {
+	let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+	let mut message = data.to_vec();
+	message.push(0x80);
+	while message.len() % 64 != 56 {
+		message.push(0);
+	}
+	message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+	for block in message.chunks_exact(64) {
+		let mut words = [0u32; 80];
+		for (i, word) in block.chunks_exact(4).enumerate() {
+			words[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+		}
+		for i in 16..80 {
+			words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1);
+		}
+		let [mut a, mut b, mut c, mut d, mut e] = state;
+		for (i, word) in words.iter().enumerate() {
+			let (f, k) = match i {
+				0..=19 => ((b & c) | (!b & d), 0x5A827999),
+				20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+				40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+				_ => (b ^ c ^ d, 0xCA62C1D6),
+			};
+			let temp = a
+				.rotate_left(5)
+				.wrapping_add(f)
+				.wrapping_add(e)
+				.wrapping_add(k)
+				.wrapping_add(*word);
+			e = d;
+			d = c;
+			c = b.rotate_left(30);
+			b = a;
+			a = temp;
+		}
+		for (value, new) in state.iter_mut().zip([a, b, c, d, e]) {
+			*value = value.wrapping_add(new);
+		}
+	}
+
+	let mut digest = [0; 20];
+	for (bytes, value) in digest.chunks_exact_mut(4).zip(state) {
+		bytes.copy_from_slice(&value.to_be_bytes());
+	}
+	digest
+}
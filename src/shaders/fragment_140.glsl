@@ -1,19 +1,67 @@
-#version 140
-uniform sampler2D tex;
-uniform float bright_shade;
-uniform float lod_level;
-in vec2 v_tex_coords;
-out vec4 f_color;
-void main() {
-    vec4 color = textureLod(tex, v_tex_coords, lod_level);
-    const float grid_size = 12.0;
-    vec4 grid_color;
-    if ((mod(gl_FragCoord.x, grid_size * 2.0) < grid_size)
-        ^^ (mod(gl_FragCoord.y, grid_size * 2.0) < grid_size)
-    ) {
-        grid_color = vec4(bright_shade);
-    } else {
-        grid_color = vec4(bright_shade * 0.55);
-    }
-    f_color = mix(grid_color, color, color.a);
-}
+#version 140
+uniform sampler2D tex;
+uniform float bright_shade;
+uniform float lod_level;
+uniform float opacity;
+uniform float gamma;
+uniform bool smooth_edges;
+// 0 is nearest, 1 is bilinear and 2 is bicubic, like `InterpolationMode`
+uniform int interpolation_mode;
+in vec2 v_tex_coords;
+in vec2 v_image_coords;
+out vec4 f_color;
+// The Catmull-Rom weights of the four texels around a sample that's `t` past the second one
+vec4 cubic_weights(float t) {
+    float t2 = t * t;
+    float t3 = t2 * t;
+    return vec4(
+        -0.5 * t3 + t2 - 0.5 * t,
+        1.5 * t3 - 2.5 * t2 + 1.0,
+        -1.5 * t3 + 2.0 * t2 + 0.5 * t,
+        0.5 * t3 - 0.5 * t2
+    );
+}
+// Interpolates the 4x4 texels around the coordinates of the full size texture
+vec4 sample_bicubic(vec2 coords) {
+    vec2 size = vec2(textureSize(tex, 0));
+    vec2 pos = coords * size - 0.5;
+    vec2 base = floor(pos);
+    vec4 wx = cubic_weights(pos.x - base.x);
+    vec4 wy = cubic_weights(pos.y - base.y);
+    vec4 sum = vec4(0.0);
+    for (int y = 0; y < 4; y++) {
+        vec4 row = vec4(0.0);
+        for (int x = 0; x < 4; x++) {
+            vec2 texel = (base + vec2(float(x) - 0.5, float(y) - 0.5)) / size;
+            row += textureLod(tex, texel, 0.0) * wx[x];
+        }
+        sum += row * wy[y];
+    }
+    // The negative lobes can overshoot at sharp edges
+    return clamp(sum, 0.0, 1.0);
+}
+void main() {
+    vec4 color;
+    if (interpolation_mode == 2 && lod_level == 0.0) {
+        color = sample_bicubic(v_tex_coords);
+    } else {
+        color = textureLod(tex, v_tex_coords, lod_level);
+    }
+    const float grid_size = 12.0;
+    vec4 grid_color;
+    if ((mod(gl_FragCoord.x, grid_size * 2.0) < grid_size)
+        ^^ (mod(gl_FragCoord.y, grid_size * 2.0) < grid_size)
+    ) {
+        grid_color = vec4(bright_shade);
+    } else {
+        grid_color = vec4(bright_shade * 0.55);
+    }
+    vec3 rgb = mix(grid_color, color, color.a).rgb;
+    float coverage = 1.0;
+    if (smooth_edges) {
+        // The distance to the nearest edge of the image in screen pixels, negative outside
+        vec2 edge_dist = min(v_image_coords, 1.0 - v_image_coords) / fwidth(v_image_coords);
+        coverage = clamp(min(edge_dist.x, edge_dist.y) + 0.5, 0.0, 1.0);
+    }
+    f_color = vec4(pow(rgb, vec3(gamma)), opacity * coverage);
+}
@@ -1,9 +1,18 @@
-#version 140
-uniform mat4 matrix;
-in vec2 position;
-in vec2 tex_coords;
-out vec2 v_tex_coords;
-void main() {
-    gl_Position = matrix * vec4(position, 0.0, 1.0);
-    v_tex_coords = tex_coords;
-}
+#version 140
+uniform mat4 matrix;
+// How far the quad is extended beyond the tile on each side, in texture coordinates
+uniform vec2 expand_min;
+uniform vec2 expand_max;
+// Where the tile is within the whole image
+uniform vec2 tile_offset;
+uniform vec2 tile_scale;
+in vec2 position;
+in vec2 tex_coords;
+out vec2 v_tex_coords;
+out vec2 v_image_coords;
+void main() {
+    vec2 pos = position * (vec2(1.0) + expand_min + expand_max) - expand_min;
+    gl_Position = matrix * vec4(pos, 0.0, 1.0);
+    v_tex_coords = pos;
+    v_image_coords = tile_offset + pos * tile_scale;
+}
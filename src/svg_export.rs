@@ -0,0 +1,25 @@
+//! Wraps an exported view into an SVG file, with the pixels embedded as a PNG data URL.
+
+use std::io::Cursor;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use gelatin::image::{DynamicImage, ImageFormat, ImageResult, RgbaImage};
+
+/// Returns the SVG document that displays `view` at `width` by `height` user units. The view
+/// keeps all of its pixels when it's larger than that, for example on high DPI displays.
+pub fn view_svg(view: &RgbaImage, width: f32, height: f32) -> ImageResult<String> {
+	let mut png = Vec::new();
+	DynamicImage::ImageRgba8(view.clone())
+		.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+	Ok(format!(
+		"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+		<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+		viewBox=\"0 0 {w} {h}\">\n\
+		<image width=\"{w}\" height=\"{h}\" preserveAspectRatio=\"none\" \
+		href=\"data:image/png;base64,{data}\"/>\n\
+		</svg>\n",
+		w = width,
+		h = height,
+		data = BASE64.encode(&png),
+	))
+}
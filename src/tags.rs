@@ -0,0 +1,92 @@
+//! Stores the tags of images in a file in the configuration folder.
+//!
+//! Every line of the file holds the comma separated tags of one image, followed by a tab and
+//! the absolute path of the image. The paths come last so that they may contain tabs.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub struct TagStore {
+	file_path: PathBuf,
+	tags: BTreeMap<PathBuf, Vec<String>>,
+}
+
+impl TagStore {
+	/// Reads the tags from the file at `file_path`. A missing file means that no image is
+	/// tagged yet.
+	pub fn load(file_path: PathBuf) -> io::Result<TagStore> {
+		let contents = match fs::read_to_string(&file_path) {
+			Ok(contents) => contents,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+			Err(e) => return Err(e),
+		};
+		let tags = contents
+			.lines()
+			.filter_map(|line| line.split_once('\t'))
+			.map(|(tags, path)| (PathBuf::from(path), parse_tags(tags)))
+			.filter(|(_, tags)| !tags.is_empty())
+			.collect();
+		Ok(TagStore { file_path, tags })
+	}
+
+	pub fn tags(&self, image_path: &Path) -> &[String] {
+		match key(image_path).ok().and_then(|key| self.tags.get(&key)) {
+			Some(tags) => tags,
+			None => &[],
+		}
+	}
+
+	/// Replaces the tags of the image and saves every tag to the file
+	pub fn set_tags(&mut self, image_path: &Path, tags: Vec<String>) -> io::Result<()> {
+		let key = key(image_path)?;
+		if key.to_string_lossy().contains('\n') {
+			return Err(io::Error::other("the path contains a line break"));
+		}
+		if tags.is_empty() {
+			self.tags.remove(&key);
+		} else {
+			self.tags.insert(key, tags);
+		}
+		self.save()
+	}
+
+	/// The images that have the tag
+	pub fn images_with_tag(&self, tag: &str) -> HashSet<PathBuf> {
+		let tag = tag.trim();
+		self.tags
+			.iter()
+			.filter(|(_, tags)| tags.iter().any(|t| t == tag))
+			.map(|(path, _)| path.clone())
+			.collect()
+	}
+
+	fn save(&self) -> io::Result<()> {
+		let mut contents = String::new();
+		for (path, tags) in &self.tags {
+			contents.push_str(&tags.join(","));
+			contents.push('\t');
+			contents.push_str(&path.to_string_lossy());
+			contents.push('\n');
+		}
+		fs::write(&self.file_path, contents)
+	}
+}
+
+/// Splits a comma separated list of tags, leaving out the empty ones and the duplicates
+pub fn parse_tags(text: &str) -> Vec<String> {
+	let mut tags: Vec<String> = Vec::new();
+	for tag in text.split(',').map(|tag| tag.replace('\t', " ").trim().to_owned()) {
+		if !tag.is_empty() && !tags.contains(&tag) {
+			tags.push(tag);
+		}
+	}
+	tags
+}
+
+/// The images are identified by their absolute path, so that the tags are found no matter
+/// how the image was opened
+pub fn key(image_path: &Path) -> io::Result<PathBuf> {
+	image_path.canonicalize()
+}
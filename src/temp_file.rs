@@ -0,0 +1,24 @@
+//! Files in the temporary folder that are handed to the tools of the operating system.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Creates an empty file named `{prefix}-{random}.{extension}` in the temporary folder that
+/// only the current user can access, and returns its path. A file that exists already is
+/// never reused, because on a shared temporary folder another user could have put it there.
+pub fn create(prefix: &str, extension: &str) -> io::Result<PathBuf> {
+	loop {
+		let name = format!("{}-{:016x}.{}", prefix, rand::random::<u64>(), extension);
+		let path = std::env::temp_dir().join(name);
+		let mut options = fs::OpenOptions::new();
+		options.write(true).create_new(true);
+		#[cfg(unix)]
+		std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+		match options.open(&path) {
+			Ok(_) => return Ok(path),
+			Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+			Err(e) => return Err(e),
+		}
+	}
+}
@@ -0,0 +1,57 @@
+//! Opens a terminal in a folder, with the command from the configuration or the usual
+//! terminal of the operating system.
+
+use std::path::Path;
+use std::process::Command;
+
+/// The placeholder in the terminal command that's replaced by the quoted path of the folder
+const FOLDER_PLACEHOLDER: &str = "${folder}";
+
+#[cfg(target_os = "windows")]
+const DEFAULT_TERMINAL_COMMAND: &str = "wt -d ${folder}";
+#[cfg(target_os = "macos")]
+const DEFAULT_TERMINAL_COMMAND: &str = "open -a Terminal ${folder}";
+/// The working directory is already the folder, so xterm starts its shell there
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const DEFAULT_TERMINAL_COMMAND: &str = "xterm";
+
+/// Runs `command`, or the default terminal of the platform if it's `None`, with the shell of
+/// the system and `folder` as the working directory. The terminal isn't waited for.
+pub fn open_terminal(folder: &Path, command: Option<&str>) -> Result<(), String> {
+	let command = command.unwrap_or(DEFAULT_TERMINAL_COMMAND);
+	let command = command.replace(FOLDER_PLACEHOLDER, &quote(&folder.to_string_lossy()));
+	let mut shell = shell_command(&command);
+	shell.current_dir(folder);
+	match shell.spawn() {
+		Ok(_) => Ok(()),
+		Err(e) => Err(format!("Could not open the terminal with {:?}: {}", command, e)),
+	}
+}
+
+#[cfg(target_os = "windows")]
+pub fn shell_command(command: &str) -> Command {
+	use std::os::windows::process::CommandExt;
+
+	let mut shell = Command::new("cmd");
+	// Passed as it is, because cmd has its own rules for the quotes
+	shell.arg("/C").raw_arg(command);
+	shell
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn shell_command(command: &str) -> Command {
+	let mut shell = Command::new("sh");
+	shell.args(["-c", command]);
+	shell
+}
+
+/// Quotes the path so that the shell reads it as a single word
+#[cfg(target_os = "windows")]
+pub fn quote(path: &str) -> String {
+	format!("\"{}\"", path)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn quote(path: &str) -> String {
+	format!("'{}'", path.replace('\'', "'\\''"))
+}
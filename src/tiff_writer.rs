@@ -0,0 +1,87 @@
+//! Writes the images of a folder into a single TIFF with one image per page.
+//!
+//! Every image is decoded and written as LZW compressed RGBA pixels, turned the way its EXIF
+//! orientation says. Only the first frame of animations is used.
+
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use gelatin::image::RgbaImage;
+use tiff::encoder::{colortype, compression::Lzw, TiffEncoder};
+
+use crate::image_cache::image_loader::{complex_load_image, LoadResult};
+
+/// How many images made it into the TIFF
+#[derive(Debug, Copy, Clone)]
+pub struct TiffSummary {
+	pub pages: usize,
+	/// The images that could not be read
+	pub skipped: usize,
+}
+
+/// Writes a TIFF to `target` with one page for each of the images, in the given order.
+/// `progress` is called after each image with the number of images that were processed.
+pub fn write_tiff(
+	images: &[PathBuf],
+	target: &Path,
+	mut progress: impl FnMut(usize),
+) -> Result<TiffSummary, String> {
+	let result = write_pages(images, target, &mut progress);
+	if !matches!(result, Ok(TiffSummary { pages, .. }) if pages > 0) {
+		let _ = fs::remove_file(target);
+	}
+	match result {
+		Ok(summary) if summary.pages == 0 => Err("None of the images could be read".into()),
+		Ok(summary) => Ok(summary),
+		Err(e) => Err(format!("Could not write {}: {}", target.display(), e)),
+	}
+}
+
+fn write_pages(
+	images: &[PathBuf],
+	target: &Path,
+	progress: &mut impl FnMut(usize),
+) -> Result<TiffSummary, tiff::TiffError> {
+	let mut encoder = TiffEncoder::new(BufWriter::new(File::create(target)?))?;
+	let mut pages = 0;
+	let mut skipped = 0;
+	for (index, path) in images.iter().enumerate() {
+		match read_image(path) {
+			Ok(image) => {
+				let (width, height) = image.dimensions();
+				encoder.write_image_with_compression::<colortype::RGBA8, _>(
+					width,
+					height,
+					Lzw,
+					image.as_raw(),
+				)?;
+				pages += 1;
+			}
+			Err(e) => {
+				eprintln!("Could not add {:?} to the TIFF: {}", path, e);
+				skipped += 1;
+			}
+		}
+		progress(index + 1);
+	}
+	Ok(TiffSummary { pages, skipped })
+}
+
+fn read_image(path: &Path) -> Result<RgbaImage, String> {
+	let mut first_frame = None;
+	complex_load_image(path, false, 0, |frame| {
+		if let LoadResult::Frame { image, orientation, .. } = frame {
+			first_frame = Some(orientation.apply(image));
+		}
+		Ok(())
+	})
+	.map_err(|e| e.to_string())?;
+	first_frame.ok_or_else(|| "The image has no frames".into())
+}
+
+/// The file that's suggested for the TIFF of a folder, named after the folder
+pub fn tiff_file_name(folder: &Path) -> String {
+	let name = folder.file_name().map(|n| n.to_string_lossy().into_owned());
+	format!("{}.tif", name.unwrap_or_else(|| "images".into()))
+}
@@ -0,0 +1,28 @@
+//! The timecodes of the numbered images of a sequence, see `toggle_timecode`.
+
+use std::path::Path;
+
+/// The frame rate of the sequences when `sequence_fps` isn't set
+pub const DEFAULT_SEQUENCE_FPS: f32 = 24.0;
+
+/// The last number in the file name without its extension, so `shot2_frame_0042.png` is the
+/// frame 42. Version numbers like the `v3` of `frame_0042_v3.exr` are skipped.
+pub fn frame_number(path: &Path) -> Option<u64> {
+	let mut stem = path.file_stem()?.to_str()?;
+	loop {
+		let end = stem.rfind(|c: char| c.is_ascii_digit())? + 1;
+		let start = stem[..end].rfind(|c: char| !c.is_ascii_digit()).map_or(0, |i| i + 1);
+		if !stem[..start].ends_with(['v', 'V']) {
+			return stem[start..end].parse().ok();
+		}
+		stem = &stem[..start];
+	}
+}
+
+/// Writes the frame as `HH:MM:SS:FF`, where FF is the frame within its second. Fractional
+/// frame rates are rounded, like the non-drop-frame timecodes of 23.976 fps video.
+pub fn timecode(frame: u64, fps: f32) -> String {
+	let fps = (fps.round() as u64).max(1);
+	let seconds = frame / fps;
+	format!("{:02}:{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60, frame % fps)
+}
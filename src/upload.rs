@@ -0,0 +1,116 @@
+//! Uploads the shown image to an image hosting service, see `upload_image`. The service is set
+//! up in the `[upload]` section of the config file, like
+//!
+//! ```toml
+//! [upload]
+//! url = "https://api.imgur.com/3/image"
+//! token = "Client-ID 0123456789abcdef"
+//! form_field = "image"
+//! url_path = "data.link"
+//! ```
+//!
+//! The file is sent as the body of a POST request, or as the given field of a
+//! `multipart/form-data` form. The address of the uploaded image is read from the JSON reply.
+//!
+//! Uploading needs the `networking` feature.
+
+use std::path::Path;
+
+#[cfg(feature = "networking")]
+use serde_json::Value as JsonValue;
+
+/// Where and how the images are uploaded to
+#[cfg_attr(not(feature = "networking"), allow(dead_code))]
+pub struct UploadTarget {
+	/// The address that the image is posted to
+	pub url: String,
+	/// Sent as the `Authorization` header, like `Bearer abc` or `Client-ID abc`
+	pub token: Option<String>,
+	/// The name of the form field that the file is sent in. The file is the whole body if it's
+	/// `None`.
+	pub form_field: Option<String>,
+	/// Where the address of the uploaded image is in the reply, like `data.link`
+	pub url_path: String,
+}
+
+/// Uploads the file at `path` and returns the address that the service gave it
+#[cfg(feature = "networking")]
+pub fn upload(target: &UploadTarget, path: &Path) -> Result<String, String> {
+	let bytes = std::fs::read(path).map_err(|e| format!("Could not read {:?}: {}", path, e))?;
+	let mut request = ureq::post(&target.url).set("User-Agent", "emulsion");
+	if let Some(token) = &target.token {
+		request = request.set("Authorization", token);
+	}
+	let content_type = content_type(path);
+	let response = match &target.form_field {
+		Some(field) => {
+			let boundary = format!("emulsion-{:016x}", rand::random::<u64>());
+			let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+			let body = multipart_body(&boundary, field, &file_name, content_type, &bytes);
+			let form_type = format!("multipart/form-data; boundary={}", boundary);
+			request.set("Content-Type", &form_type).send_bytes(&body)
+		}
+		None => request.set("Content-Type", content_type).send_bytes(&bytes),
+	};
+	let response = response.map_err(|e| format!("The upload failed: {}", e))?;
+	let reply: JsonValue =
+		response.into_json().map_err(|e| format!("The reply to the upload isn't JSON: {}", e))?;
+	find_url(&reply, &target.url_path)
+		.ok_or_else(|| format!("The reply to the upload has no address at `{}`", target.url_path))
+}
+
+#[cfg(not(feature = "networking"))]
+pub fn upload(_target: &UploadTarget, _path: &Path) -> Result<String, String> {
+	Err("Uploading needs Emulsion to be built with the `networking` feature".into())
+}
+
+/// The string at `url_path` in the reply. The path is made of the keys of the objects and the
+/// indices of the arrays, separated by dots.
+#[cfg(feature = "networking")]
+pub fn find_url(reply: &JsonValue, url_path: &str) -> Option<String> {
+	let mut value = reply;
+	for key in url_path.split('.').filter(|key| !key.is_empty()) {
+		value = match value {
+			JsonValue::Array(items) => items.get(key.parse::<usize>().ok()?)?,
+			value => value.get(key)?,
+		};
+	}
+	value.as_str().map(str::to_owned)
+}
+
+/// A `multipart/form-data` body with the file as its only field
+#[cfg(feature = "networking")]
+pub fn multipart_body(
+	boundary: &str,
+	field: &str,
+	file_name: &str,
+	content_type: &str,
+	bytes: &[u8],
+) -> Vec<u8> {
+	let file_name = file_name.replace(['"', '\r', '\n'], "_");
+	let mut body = format!(
+		"--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\
+		 Content-Type: {}\r\n\r\n",
+		boundary, field, file_name, content_type
+	)
+	.into_bytes();
+	body.extend_from_slice(bytes);
+	body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+	body
+}
+
+#[cfg(feature = "networking")]
+fn content_type(path: &Path) -> &'static str {
+	let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+	match extension.as_deref() {
+		Some("jpg") | Some("jpeg") => "image/jpeg",
+		Some("png") => "image/png",
+		Some("gif") => "image/gif",
+		Some("webp") => "image/webp",
+		Some("bmp") => "image/bmp",
+		Some("tif") | Some("tiff") => "image/tiff",
+		Some("avif") => "image/avif",
+		Some("svg") => "image/svg+xml",
+		_ => "application/octet-stream",
+	}
+}
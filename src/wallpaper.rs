@@ -0,0 +1,153 @@
+//! Sets the desktop wallpaper with the tools of the operating system.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::image_cache::image_loader::{
+	complex_load_image, detect_orientation, ImageLoaderError, LoadResult, Orientation,
+};
+use crate::PROJECT_DIRS;
+
+/// The formats that the desktops can show without converting the image first
+const WALLPAPER_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "bmp"];
+
+/// The start of the names of the converted wallpapers
+const CONVERTED_PREFIX: &str = "wallpaper-";
+
+/// Sets the image at `path` as the wallpaper of the desktop. Images that the desktop can't
+/// show the way that they are displayed in the viewer are converted to a PNG in the data
+/// folder first, where it stays until another image is converted.
+pub fn set_wallpaper(path: &Path) -> Result<(), String> {
+	let path = wallpaper_source(path)?;
+	let path = path.canonicalize().map_err(|e| format!("Could not find {:?}: {}", path, e))?;
+	set_platform_wallpaper(&path)?;
+	remove_old_wallpapers(&path);
+	Ok(())
+}
+
+/// The folder of the converted wallpapers. The desktop reads the file whenever it draws the
+/// wallpaper, so it can't be in the temp folder, which is emptied on restart.
+fn wallpaper_dir() -> Result<PathBuf, String> {
+	let dir = match *PROJECT_DIRS {
+		Some(ref project_dirs) => project_dirs.data_local_dir().join("wallpapers"),
+		None => return Err("Could not find the data folder for the wallpaper".into()),
+	};
+	fs::create_dir_all(&dir).map_err(|e| format!("Could not create {:?}: {}", dir, e))?;
+	Ok(dir)
+}
+
+/// Deletes the wallpapers that were converted before `current`
+fn remove_old_wallpapers(current: &Path) {
+	let dir = match current.parent() {
+		Some(dir) => dir,
+		None => return,
+	};
+	let entries = match fs::read_dir(dir) {
+		Ok(entries) => entries,
+		Err(_) => return,
+	};
+	for entry in entries.flatten() {
+		let path = entry.path();
+		let is_converted =
+			entry.file_name().to_str().is_some_and(|name| name.starts_with(CONVERTED_PREFIX));
+		if is_converted && path != current {
+			let _ = fs::remove_file(path);
+		}
+	}
+}
+
+fn wallpaper_source(path: &Path) -> Result<PathBuf, String> {
+	let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+	let supported = extension.is_some_and(|e| WALLPAPER_EXTENSIONS.contains(&e.as_str()));
+	let rotated = !matches!(detect_orientation(path), Ok(Orientation::Deg0) | Err(_));
+	if supported && !rotated {
+		return Ok(path.to_owned());
+	}
+	// A new name every time, because the desktops don't read a file again that they showed
+	// already
+	let millis = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+	let target = wallpaper_dir()?.join(format!("{}{}.png", CONVERTED_PREFIX, millis));
+	let result = complex_load_image(path, false, 0, |frame| {
+		// Only the first frame of an animation is shown as the wallpaper
+		if let LoadResult::Frame { frame_index: 0, image, orientation, .. } = frame {
+			orientation.apply(image).save(&target)?;
+		}
+		Ok::<_, ImageLoaderError>(())
+	});
+	match result {
+		Ok(()) => Ok(target),
+		Err(e) => {
+			let _ = fs::remove_file(&target);
+			Err(format!("Could not convert the image for the wallpaper: {}", e))
+		}
+	}
+}
+
+fn run(command: &mut Command) -> Result<(), String> {
+	let output = command.output().map_err(|e| format!("Could not run {:?}: {}", command, e))?;
+	if output.status.success() {
+		Ok(())
+	} else {
+		Err(format!("{:?} failed: {}", command, String::from_utf8_lossy(&output.stderr).trim()))
+	}
+}
+
+#[cfg(target_os = "windows")]
+fn set_platform_wallpaper(path: &Path) -> Result<(), String> {
+	// SPI_SETDESKWALLPAPER with SPIF_UPDATEINIFILE | SPIF_SENDCHANGE
+	const SCRIPT: &str = "Add-Type -TypeDefinition 'using System.Runtime.InteropServices; \
+		public class Wallpaper { [DllImport(\"user32.dll\", CharSet = CharSet.Unicode)] \
+		public static extern bool SystemParametersInfo(int action, int param, string path, \
+		int flags); }'; \
+		if (-not [Wallpaper]::SystemParametersInfo(20, 0, $env:EMULSION_WALLPAPER, 3)) { exit 1 }";
+	run(Command::new("powershell")
+		.args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+		.env("EMULSION_WALLPAPER", path))
+}
+
+#[cfg(target_os = "macos")]
+fn set_platform_wallpaper(path: &Path) -> Result<(), String> {
+	let path = path.to_string_lossy().replace('\\', "\\\\").replace('"', "\\\"");
+	let script = format!(
+		"tell application \"System Events\" to tell every desktop to set picture to \"{}\"",
+		path
+	);
+	run(Command::new("osascript").args(["-e", &script]))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn set_platform_wallpaper(path: &Path) -> Result<(), String> {
+	let uri = file_uri(path);
+	run(Command::new("gsettings").args([
+		"set",
+		"org.gnome.desktop.background",
+		"picture-uri",
+		&uri,
+	]))?;
+	// GNOME 42 and later use a separate setting for the dark style, older versions don't have it
+	let _ = run(Command::new("gsettings").args([
+		"set",
+		"org.gnome.desktop.background",
+		"picture-uri-dark",
+		&uri,
+	]));
+	Ok(())
+}
+
+/// Converts an absolute path to a `file://` URI, escaping the bytes that can't appear in it
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn file_uri(path: &Path) -> String {
+	use std::os::unix::ffi::OsStrExt;
+
+	let mut uri = String::from("file://");
+	for &byte in path.as_os_str().as_bytes() {
+		if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+			uri.push(byte as char);
+		} else {
+			uri.push_str(&format!("%{:02X}", byte));
+		}
+	}
+	uri
+}
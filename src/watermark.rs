@@ -0,0 +1,85 @@
+//! Draws the watermark of `export_watermarked` over an image.
+
+use std::path::Path;
+
+use gelatin::image::{self, imageops, DynamicImage, ImageFormat, RgbaImage};
+
+use crate::configuration::WatermarkCorner;
+
+/// How the watermark is put on the image
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WatermarkLayout {
+	pub corner: WatermarkCorner,
+	/// From 0 to 1, multiplies the alpha of the watermark
+	pub alpha: f32,
+	pub margin: WatermarkMargin,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WatermarkMargin {
+	Pixels(u32),
+	/// Of the width of the image horizontally and of its height vertically
+	Percent(f32),
+}
+
+/// Where the top left corner of the watermark goes on the image. The watermark may reach
+/// past the edges if it's larger than the image.
+pub fn watermark_position(
+	(img_w, img_h): (u32, u32),
+	(mark_w, mark_h): (u32, u32),
+	layout: &WatermarkLayout,
+) -> (i64, i64) {
+	let (margin_x, margin_y) = match layout.margin {
+		WatermarkMargin::Pixels(pixels) => (pixels as i64, pixels as i64),
+		WatermarkMargin::Percent(percent) => {
+			let fraction = percent.max(0.0) / 100.0;
+			((img_w as f32 * fraction).round() as i64, (img_h as f32 * fraction).round() as i64)
+		}
+	};
+	let (img_w, img_h, mark_w, mark_h) = (img_w as i64, img_h as i64, mark_w as i64, mark_h as i64);
+	let left = margin_x;
+	let right = img_w - mark_w - margin_x;
+	let top = margin_y;
+	let bottom = img_h - mark_h - margin_y;
+	match layout.corner {
+		WatermarkCorner::TopLeft => (left, top),
+		WatermarkCorner::TopRight => (right, top),
+		WatermarkCorner::BottomLeft => (left, bottom),
+		WatermarkCorner::BottomRight => (right, bottom),
+		WatermarkCorner::Center => ((img_w - mark_w) / 2, (img_h - mark_h) / 2),
+	}
+}
+
+/// Blends the watermark over the image
+pub fn apply_watermark(image: &mut RgbaImage, watermark: &RgbaImage, layout: &WatermarkLayout) {
+	let alpha = layout.alpha.clamp(0.0, 1.0);
+	let mut faded = watermark.clone();
+	for pixel in faded.pixels_mut() {
+		pixel.0[3] = (pixel.0[3] as f32 * alpha).round() as u8;
+	}
+	let (x, y) = watermark_position(image.dimensions(), watermark.dimensions(), layout);
+	imageops::overlay(image, &faded, x, y);
+}
+
+/// Saves the image in the format of the file ending of `target`. The alpha channel is dropped
+/// for the formats that can't store it.
+pub fn save_image(image: RgbaImage, target: &Path) -> Result<(), String> {
+	let format = ImageFormat::from_path(target)
+		.map_err(|_| format!("Unknown image format for {}", target.display()))?;
+	let image = match format {
+		ImageFormat::Jpeg | ImageFormat::Pnm | ImageFormat::Hdr => {
+			DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(image).to_rgb8())
+		}
+		_ => DynamicImage::ImageRgba8(image),
+	};
+	image
+		.save_with_format(target, format)
+		.map_err(|e| format!("Could not save {}: {}", target.display(), e))
+}
+
+/// Decodes the watermark image
+pub fn load_watermark(path: &Path) -> Result<RgbaImage, String> {
+	image::open(path)
+		.map(|image| image.into_rgba8())
+		.map_err(|e| format!("Could not read the watermark {}: {}", path.display(), e))
+}
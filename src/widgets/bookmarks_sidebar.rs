@@ -0,0 +1,117 @@
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
+
+use gelatin::{
+	image::imageops,
+	misc::{Length, LogicalVector},
+	picture::Picture,
+	tree_view::{TreeView, TreeViewRow},
+	Widget,
+};
+
+use super::directory_sidebar::SIDEBAR_WIDTH;
+use super::text::{render_text, OVERSAMPLING, TEXT_COLOR};
+use crate::bookmarks::Bookmarks;
+
+const ROW_HEIGHT: f32 = 22.0;
+const TEXT_HEIGHT: f32 = 13.0;
+/// The space kept free left and right of the names
+const PADDING: f32 = 6.0;
+const BG_COLOR: [f32; 4] = [0.08, 0.08, 0.08, 1.0];
+
+type NavigateCallback = Rc<dyn Fn(&Path)>;
+
+/// The bookmarked folders on the left of the picture, in the order they were added. Clicking
+/// one opens it.
+pub struct BookmarksSidebar {
+	pub widget: Rc<TreeView>,
+	bookmarks: RefCell<Bookmarks>,
+	on_navigate: RefCell<Option<NavigateCallback>>,
+}
+
+impl BookmarksSidebar {
+	pub fn new(bookmarks: Bookmarks) -> Rc<Self> {
+		Rc::new_cyclic(|this: &Weak<BookmarksSidebar>| {
+			let widget = Rc::new(TreeView::new());
+			widget.set_width(Length::Fixed(SIDEBAR_WIDTH));
+			widget.set_height(Length::Stretch { min: 0.0, max: f32::INFINITY });
+			widget.set_row_height(ROW_HEIGHT);
+			widget.set_indent(PADDING);
+			widget.set_bg_color(BG_COLOR);
+			widget.set_visible(false);
+			let this = this.clone();
+			widget.set_on_click(move |index| {
+				if let Some(sidebar) = this.upgrade() {
+					sidebar.click(index);
+				}
+			});
+			BookmarksSidebar {
+				widget,
+				bookmarks: RefCell::new(bookmarks),
+				on_navigate: RefCell::new(None),
+			}
+		})
+	}
+
+	/// The callback gets the folder of the bookmark that was clicked
+	pub fn set_on_navigate<F: Fn(&Path) + 'static>(&self, callback: F) {
+		*self.on_navigate.borrow_mut() = Some(Rc::new(callback));
+	}
+
+	pub fn is_open(&self) -> bool {
+		self.widget.visible()
+	}
+
+	pub fn toggle(&self) {
+		if !self.is_open() {
+			self.update_rows();
+		}
+		self.widget.set_visible(!self.is_open());
+	}
+
+	/// Bookmarks the folder and saves the bookmarks, see `Bookmarks::add`
+	pub fn add(&self, name: String, path: PathBuf) -> Result<(), String> {
+		let result = self.bookmarks.borrow_mut().add(name, path);
+		self.update_rows();
+		result
+	}
+
+	fn click(&self, index: usize) {
+		let path = match self.bookmarks.borrow().entries().get(index) {
+			Some(bookmark) => bookmark.path.clone(),
+			None => return,
+		};
+		let on_navigate = self.on_navigate.borrow().clone();
+		if let Some(callback) = on_navigate {
+			callback(&path);
+		}
+	}
+
+	fn update_rows(&self) {
+		let rows = self
+			.bookmarks
+			.borrow()
+			.entries()
+			.iter()
+			.map(|bookmark| {
+				let (mut image, mut size) = render_text(&bookmark.name, TEXT_HEIGHT, TEXT_COLOR);
+				// The names that don't fit are cut off at the edge of the sidebar
+				let max_width = SIDEBAR_WIDTH - 2.0 * PADDING;
+				if size.vec.x > max_width {
+					let width = (max_width * OVERSAMPLING) as u32;
+					image = imageops::crop_imm(&image, 0, 0, width, image.height()).to_image();
+					size = LogicalVector::new(image.width() as f32 / OVERSAMPLING, size.vec.y);
+				}
+				TreeViewRow {
+					depth: 0,
+					expandable: false,
+					expanded: false,
+					label: Rc::new(Picture::from_image(image)),
+					label_size: size,
+				}
+			})
+			.collect();
+		self.widget.set_rows(rows);
+	}
+}
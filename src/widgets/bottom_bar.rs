@@ -1,13 +1,17 @@
 use super::picture_widget::ScalingMode;
+use super::text::set_label_text_colored;
 use crate::{ConfigWindowSection, Configuration, Theme};
 
 use gelatin::{
 	button::Button,
+	label::Label,
 	line_layout_container::HorizontalLayoutContainer,
 	misc::{Alignment, Length},
 	picture::Picture,
 	slider::Slider,
+	Widget,
 };
+use std::cell::{Cell, RefCell};
 use std::f32;
 use std::rc::Rc;
 
@@ -30,6 +34,10 @@ const ACTIVE_BG_COLOR: [f32; 4] = [0.3, 0.3, 0.3, 0.5];
 const SMALL_BUTTON_GAP: f32 = 4.0;
 const BIG_BUTTON_GAP: f32 = 32.0;
 const BUTTON_SIZE: f32 = 24.0;
+const TEXT_HEIGHT: f32 = 16.0;
+
+const DARK_TEXT_COLOR: [u8; 3] = [20, 20, 20];
+const LIGHT_TEXT_COLOR: [u8; 3] = [230, 230, 230];
 
 pub struct BottomBar {
 	pub widget: Rc<HorizontalLayoutContainer>,
@@ -39,10 +47,28 @@ pub struct BottomBar {
 	pub slider: Rc<Slider>,
 	pub theme_button: Rc<Button>,
 	pub help_button: Rc<Button>,
+	/// Replaces the other widgets while the current file is being renamed
+	rename_label: Rc<Label>,
+	/// Shows the rating of the current image
+	rating_label: Rc<Label>,
+	rating: Cell<Option<u8>>,
+	/// Shows the gamma correction while it isn't 1
+	gamma_label: Rc<Label>,
+	gamma: Cell<f32>,
+	/// Shows the detected format of the current image when its file ending names another one
+	format_label: Rc<Label>,
+	format: RefCell<Option<String>>,
+	/// Shows the tags of the current image
+	tags_label: Rc<Label>,
+	tags: RefCell<Vec<String>>,
+	/// Shows which images are left out of the navigation
+	filter_label: Rc<Label>,
+	filter_text: RefCell<Option<String>>,
+	text_color: Cell<[u8; 3]>,
 
 	/// This is false if the configuration requires this to be invisible
 	// and true otherwise.
-	pub should_show: bool,
+	should_show: Cell<bool>,
 
 	question: Rc<Picture>,
 	question_light: Rc<Picture>,
@@ -85,6 +111,35 @@ impl BottomBar {
 		let slider = make_slider();
 		let theme_button = make_icon_button(Alignment::End);
 		let help_button = make_icon_button(Alignment::End);
+		let rename_label = Rc::new(Label::new());
+		rename_label.set_margin_top(SMALL_BUTTON_GAP * 2.0);
+		rename_label.set_margin_left(SMALL_BUTTON_GAP * 2.0);
+		rename_label.set_horizontal_align(Alignment::Start);
+		rename_label.set_visible(false);
+		let rating_label = Rc::new(Label::new());
+		rating_label.set_margin_top(SMALL_BUTTON_GAP * 2.0);
+		rating_label.set_horizontal_align(Alignment::End);
+		rating_label.set_visible(false);
+		let gamma_label = Rc::new(Label::new());
+		gamma_label.set_margin_top(SMALL_BUTTON_GAP * 2.0);
+		gamma_label.set_margin_right(SMALL_BUTTON_GAP * 2.0);
+		gamma_label.set_horizontal_align(Alignment::End);
+		gamma_label.set_visible(false);
+		let format_label = Rc::new(Label::new());
+		format_label.set_margin_top(SMALL_BUTTON_GAP * 2.0);
+		format_label.set_margin_right(SMALL_BUTTON_GAP * 2.0);
+		format_label.set_horizontal_align(Alignment::End);
+		format_label.set_visible(false);
+		let tags_label = Rc::new(Label::new());
+		tags_label.set_margin_top(SMALL_BUTTON_GAP * 2.0);
+		tags_label.set_margin_right(SMALL_BUTTON_GAP * 2.0);
+		tags_label.set_horizontal_align(Alignment::End);
+		tags_label.set_visible(false);
+		let filter_label = Rc::new(Label::new());
+		filter_label.set_margin_top(SMALL_BUTTON_GAP * 2.0);
+		filter_label.set_margin_left(SMALL_BUTTON_GAP * 2.0);
+		filter_label.set_horizontal_align(Alignment::Start);
+		filter_label.set_visible(false);
 
 		orig_scale_button.set_margin_left(SMALL_BUTTON_GAP);
 		fit_stretch_button.set_margin_right(SMALL_BUTTON_GAP);
@@ -95,9 +150,15 @@ impl BottomBar {
 		widget.add_child(orig_scale_button.clone());
 		widget.add_child(fit_best_button.clone());
 		widget.add_child(fit_stretch_button.clone());
+		widget.add_child(filter_label.clone());
 		widget.add_child(slider.clone());
+		widget.add_child(gamma_label.clone());
+		widget.add_child(format_label.clone());
+		widget.add_child(tags_label.clone());
+		widget.add_child(rating_label.clone());
 		widget.add_child(theme_button.clone());
 		widget.add_child(help_button.clone());
+		widget.add_child(rename_label.clone());
 
 		let should_show;
 		if let Some(ConfigWindowSection { show_bottom_bar: Some(false), .. }) = config.window {
@@ -115,7 +176,19 @@ impl BottomBar {
 			slider,
 			theme_button,
 			help_button,
-			should_show,
+			rename_label,
+			rating_label,
+			rating: Cell::new(None),
+			gamma_label,
+			gamma: Cell::new(1.0),
+			format_label,
+			format: RefCell::new(None),
+			tags_label,
+			tags: RefCell::new(Vec::new()),
+			filter_label,
+			filter_text: RefCell::new(None),
+			text_color: Cell::new(LIGHT_TEXT_COLOR),
+			should_show: Cell::new(should_show),
 
 			question,
 			question_light,
@@ -141,6 +214,7 @@ impl BottomBar {
 				self.theme_button.set_icon(Some(self.moon_img.clone()));
 				self.widget.set_bg_color([1.0, 1.0, 1.0, 1.0]);
 				self.slider.set_shadow_color([0.0, 0.0, 0.0]);
+				self.text_color.set(DARK_TEXT_COLOR);
 
 				if update_available {
 					self.help_button.set_icon(Some(self.question_noti.clone()));
@@ -155,6 +229,7 @@ impl BottomBar {
 				self.theme_button.set_icon(Some(self.light_img.clone()));
 				self.widget.set_bg_color([0.08, 0.08, 0.08, 1.0]);
 				self.slider.set_shadow_color([0.0, 0.0, 0.0]);
+				self.text_color.set(LIGHT_TEXT_COLOR);
 
 				if update_available {
 					self.help_button.set_icon(Some(self.question_light_noti.clone()));
@@ -163,12 +238,104 @@ impl BottomBar {
 				}
 			}
 		}
+		self.set_rating(self.rating.get());
+		self.set_gamma(self.gamma.get());
+		let format = self.format.take();
+		self.set_format(format.as_deref());
+		let tags = self.tags.borrow().clone();
+		self.set_tags(&tags);
+		let filter_text = self.filter_text.borrow().clone();
+		self.set_filter_text(filter_text);
+	}
+
+	/// Describes the filter that leaves images out of the navigation, or hides the
+	/// description if `text` is `None`
+	pub fn set_filter_text(&self, text: Option<String>) {
+		if let Some(text) = &text {
+			set_label_text_colored(&self.filter_label, text, TEXT_HEIGHT, self.text_color.get());
+		}
+		self.filter_label.set_visible(text.is_some() && !self.rename_label.visible());
+		*self.filter_text.borrow_mut() = text;
+	}
+
+	/// Shows a star for every point of the rating, or nothing if the image isn't rated
+	pub fn set_rating(&self, rating: Option<u8>) {
+		self.rating.set(rating);
+		if let Some(rating) = rating {
+			let stars = "★".repeat(rating as usize);
+			set_label_text_colored(&self.rating_label, &stars, TEXT_HEIGHT, self.text_color.get());
+		}
+		self.rating_label.set_visible(rating.is_some() && !self.rename_label.visible());
+	}
+
+	/// Shows the gamma correction of the picture, or nothing if it's 1
+	pub fn set_gamma(&self, gamma: f32) {
+		self.gamma.set(gamma);
+		let shown = (gamma - 1.0).abs() > f32::EPSILON;
+		if shown {
+			let text = format!("γ {:.2}", gamma);
+			set_label_text_colored(&self.gamma_label, &text, TEXT_HEIGHT, self.text_color.get());
+		}
+		self.gamma_label.set_visible(shown && !self.rename_label.visible());
+	}
+
+	/// Shows the name of the format, or nothing if `format` is `None`
+	pub fn set_format(&self, format: Option<&str>) {
+		if self.format.borrow().as_deref() == format {
+			return;
+		}
+		if let Some(format) = format {
+			set_label_text_colored(&self.format_label, format, TEXT_HEIGHT, self.text_color.get());
+		}
+		self.format_label.set_visible(format.is_some() && !self.rename_label.visible());
+		*self.format.borrow_mut() = format.map(str::to_owned);
+	}
+
+	/// Shows the tags separated by commas, or nothing if there are no tags
+	pub fn set_tags(&self, tags: &[String]) {
+		if !tags.is_empty() {
+			let text = tags.join(", ");
+			set_label_text_colored(&self.tags_label, &text, TEXT_HEIGHT, self.text_color.get());
+		}
+		self.tags_label.set_visible(!tags.is_empty() && !self.rename_label.visible());
+		*self.tags.borrow_mut() = tags.to_vec();
 	}
 
 	/// Sets this visible iff both the `visible` parameter is `true` and
 	/// the `should_show` property of this object is `true`
 	pub fn set_visible_if_should_show(&self, visible: bool) {
-		self.widget.set_visible(visible && self.should_show);
+		self.widget.set_visible(visible && self.should_show.get());
+	}
+
+	pub fn should_show(&self) -> bool {
+		self.should_show.get()
+	}
+
+	/// Takes effect with the next call to `set_visible_if_should_show`
+	pub fn set_should_show(&self, should_show: bool) {
+		self.should_show.set(should_show);
+	}
+
+	/// Shows the text in place of the buttons and the slider, or brings them back if `text`
+	/// is `None`. The bar is visible while there's text, even if it's otherwise hidden.
+	pub fn set_rename_text(&self, text: Option<&str>) {
+		let editing = text.is_some();
+		self.orig_scale_button.set_visible(!editing);
+		self.fit_best_button.set_visible(!editing);
+		self.fit_stretch_button.set_visible(!editing);
+		self.slider.set_visible(!editing);
+		self.theme_button.set_visible(!editing);
+		self.help_button.set_visible(!editing);
+		self.rating_label.set_visible(!editing && self.rating.get().is_some());
+		self.gamma_label.set_visible(!editing && (self.gamma.get() - 1.0).abs() > f32::EPSILON);
+		self.format_label.set_visible(!editing && self.format.borrow().is_some());
+		self.tags_label.set_visible(!editing && !self.tags.borrow().is_empty());
+		self.filter_label.set_visible(!editing && self.filter_text.borrow().is_some());
+		self.rename_label.set_visible(editing);
+		if let Some(text) = text {
+			set_label_text_colored(&self.rename_label, text, TEXT_HEIGHT, self.text_color.get());
+			self.widget.set_visible(true);
+		}
 	}
 
 	pub fn set_help_visible(&self, visible: bool) {
@@ -197,6 +364,11 @@ impl BottomBar {
 				self.fit_best_button.set_bg_color(NO_BG_COLOR);
 				self.fit_stretch_button.set_bg_color(ACTIVE_BG_COLOR);
 			}
+			ScalingMode::FitWidth | ScalingMode::FitHeight => {
+				self.orig_scale_button.set_bg_color(NO_BG_COLOR);
+				self.fit_best_button.set_bg_color(NO_BG_COLOR);
+				self.fit_stretch_button.set_bg_color(NO_BG_COLOR);
+			}
 		}
 	}
 }
@@ -216,7 +388,7 @@ fn make_slider() -> Rc<Slider> {
 	slider.set_margin_left(BIG_BUTTON_GAP);
 	slider.set_margin_right(BIG_BUTTON_GAP);
 	slider.set_height(Length::Fixed(BUTTON_SIZE));
-	slider.set_width(Length::Stretch { min: 0.0, max: std::f32::INFINITY });
+	slider.set_width(Length::Stretch { min: 0.0, max: f32::INFINITY });
 	slider.set_horizontal_align(Alignment::Center);
 	slider.set_steps(6, 1);
 	slider
@@ -0,0 +1,43 @@
+use std::rc::{Rc, Weak};
+
+use gelatin::{label::Label, misc::*, Widget};
+
+use super::text::set_label_lines_boxed_monospace;
+
+const TEXT_HEIGHT: f32 = 14.0;
+const PADDING: f32 = 12.0;
+const TEXT_COLOR: [u8; 3] = [230, 230, 230];
+const BG_COLOR: [u8; 4] = [20, 20, 20, 220];
+
+/// The tables of `channel_stats` and `decoder_info`. It's modal: any key closes it.
+pub struct ChannelStatsOverlay {
+	pub widget: Weak<Label>,
+}
+
+impl ChannelStatsOverlay {
+	pub fn new(widget: &Rc<Label>) -> ChannelStatsOverlay {
+		widget.set_icon(None);
+		widget.set_ignore_layout(true);
+		widget.set_horizontal_align(Alignment::Center);
+		widget.set_vertical_align(Alignment::Center);
+		widget.set_visible(false);
+
+		ChannelStatsOverlay { widget: Rc::downgrade(widget) }
+	}
+
+	pub fn is_open(&self) -> bool {
+		self.widget.upgrade().is_some_and(|widget| widget.visible())
+	}
+
+	pub fn show(&self, lines: &[String]) {
+		let widget = self.widget.upgrade().unwrap();
+		set_label_lines_boxed_monospace(&widget, lines, TEXT_HEIGHT, TEXT_COLOR, BG_COLOR, PADDING);
+		widget.set_visible(true);
+	}
+
+	pub fn close(&self) {
+		if let Some(widget) = self.widget.upgrade() {
+			widget.set_visible(false);
+		}
+	}
+}
@@ -0,0 +1,251 @@
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
+
+use gelatin::{
+	image::{imageops, Rgba, RgbaImage},
+	label::Label,
+	misc::*,
+	picture::Picture,
+};
+
+use super::text::{render_text, OVERSAMPLING, TEXT_COLOR};
+use crate::image_cache::image_loader::{complex_load_image, LoadResult};
+
+const TEXT_HEIGHT: f32 = 14.0;
+const PADDING: f32 = 6.0;
+const BG_COLOR: [u8; 4] = [30, 30, 30, 235];
+/// The diameter of the color preview circle in logical pixels
+const SWATCH_SIZE: f32 = 22.0;
+/// The distance between the cursor and the top left corner of the tooltip
+const CURSOR_OFFSET: f32 = 16.0;
+/// The sampled color stays on the tooltip until the cursor moves farther than this from the
+/// point that was clicked
+const PICK_RADIUS: f32 = 8.0;
+
+struct SampledImage {
+	path: PathBuf,
+	/// The first frame of the image, with its orientation applied
+	image: RgbaImage,
+}
+
+/// The eyedropper. Shows the color under the cursor in a circle next to the cursor, and the
+/// values of the clicked color in a tooltip.
+pub struct ColorSampler {
+	pub widget: Weak<Label>,
+	sampled: Option<SampledImage>,
+	/// The color of the pixel that was clicked last
+	picked: Option<Rgba<u8>>,
+	/// Where the values of the picked color are shown, relative to the picture area
+	pinned_at: Option<LogicalVector>,
+}
+
+impl ColorSampler {
+	pub fn new(widget: &Rc<Label>) -> ColorSampler {
+		widget.set_icon(None);
+		widget.set_ignore_layout(true);
+		widget.set_horizontal_align(Alignment::Start);
+		widget.set_vertical_align(Alignment::Start);
+		widget.set_visible(false);
+
+		ColorSampler { widget: Rc::downgrade(widget), sampled: None, picked: None, pinned_at: None }
+	}
+
+	pub fn is_active(&self) -> bool {
+		self.sampled.is_some()
+	}
+
+	/// Decodes the image at `path` so that its colors can be sampled. The colors are read from
+	/// the decoded pixels and not from the screen, so they don't depend on the zoom or the
+	/// antialiasing.
+	pub fn activate(&mut self, path: &Path) -> Result<(), String> {
+		if self.sampled.as_ref().is_some_and(|sampled| sampled.path == path) {
+			return Ok(());
+		}
+		let mut first_frame = None;
+		let result = complex_load_image(path, false, 0, |frame| {
+			if let LoadResult::Frame { image, orientation, .. } = frame {
+				first_frame = Some(orientation.apply(image));
+			}
+			Ok(())
+		});
+		if let Err(e) = result {
+			return Err(format!("Could not sample the colors of the image: {}", e));
+		}
+		let image = first_frame.ok_or("The image has no frames to sample")?;
+		self.sampled = Some(SampledImage { path: path.to_owned(), image });
+		self.pinned_at = None;
+		Ok(())
+	}
+
+	pub fn deactivate(&mut self) {
+		self.sampled = None;
+		self.hide();
+	}
+
+	pub fn hide(&mut self) {
+		self.pinned_at = None;
+		self.widget.upgrade().unwrap().set_visible(false);
+	}
+
+	/// The hex code of the color that was clicked last
+	pub fn picked_hex(&self) -> Option<String> {
+		self.picked.map(hex)
+	}
+
+	/// Shows the color under the cursor in the preview circle. `image_pos` is the position
+	/// of the cursor on the image, from 0 to 1 along both axes, and `cursor_pos` is relative
+	/// to the picture area whose size is `area_size`.
+	pub fn hover(
+		&mut self,
+		image_pos: (f32, f32),
+		cursor_pos: LogicalVector,
+		area_size: LogicalVector,
+	) {
+		if let Some(pos) = self.pinned_at {
+			let distance = cursor_pos - pos;
+			if distance.vec.x.hypot(distance.vec.y) <= PICK_RADIUS {
+				return;
+			}
+			self.pinned_at = None;
+		}
+		match self.color_at(image_pos) {
+			Some(color) => self.show(color, &[], cursor_pos, area_size),
+			None => self.widget.upgrade().unwrap().set_visible(false),
+		}
+	}
+
+	/// Samples the color at the clicked position and shows its values in the tooltip
+	pub fn pick(
+		&mut self,
+		image_pos: (f32, f32),
+		cursor_pos: LogicalVector,
+		area_size: LogicalVector,
+	) {
+		let color = match self.color_at(image_pos) {
+			Some(color) => color,
+			None => {
+				self.hide();
+				return;
+			}
+		};
+		self.picked = Some(color);
+		self.pinned_at = Some(cursor_pos);
+		let [r, g, b, _] = color.0;
+		let (h, s, l) = rgb_to_hsl(r, g, b);
+		let lines = [
+			hex(color),
+			format!("rgb({}, {}, {})", r, g, b),
+			format!("hsl({}, {}%, {}%)", h, s, l),
+		];
+		self.show(color, &lines, cursor_pos, area_size);
+	}
+
+	fn color_at(&self, (x, y): (f32, f32)) -> Option<Rgba<u8>> {
+		let image = &self.sampled.as_ref()?.image;
+		if !(0.0..1.0).contains(&x) || !(0.0..1.0).contains(&y) {
+			return None;
+		}
+		let px = ((x * image.width() as f32) as u32).min(image.width() - 1);
+		let py = ((y * image.height() as f32) as u32).min(image.height() - 1);
+		Some(*image.get_pixel(px, py))
+	}
+
+	/// Draws the preview circle filled with `color` and the lines of text next to it, and
+	/// places the tooltip next to the cursor
+	fn show(
+		&self,
+		color: Rgba<u8>,
+		lines: &[String],
+		cursor_pos: LogicalVector,
+		area_size: LogicalVector,
+	) {
+		let rendered: Vec<_> =
+			lines.iter().map(|line| render_text(line, TEXT_HEIGHT, TEXT_COLOR)).collect();
+		let swatch = (SWATCH_SIZE * OVERSAMPLING) as u32;
+		let pad = (PADDING * OVERSAMPLING) as u32;
+		let text_width = rendered.iter().map(|(image, _)| image.width()).max().unwrap_or(0);
+		let text_height: u32 = rendered.iter().map(|(image, _)| image.height()).sum();
+		let gap = if rendered.is_empty() { 0 } else { pad };
+		let width = swatch + gap + text_width + 2 * pad;
+		let height = swatch.max(text_height) + 2 * pad;
+		let mut image = RgbaImage::from_pixel(width, height, Rgba(BG_COLOR));
+		draw_circle(&mut image, pad, (height - swatch) / 2, swatch, color);
+		let mut y = (height - text_height) / 2;
+		for (line_image, _) in &rendered {
+			imageops::overlay(&mut image, line_image, (pad + swatch + gap) as i64, y as i64);
+			y += line_image.height();
+		}
+
+		let logical_w = width as f32 / OVERSAMPLING;
+		let logical_h = height as f32 / OVERSAMPLING;
+		// The tooltip goes to the other side of the cursor when it wouldn't fit
+		let mut x = cursor_pos.vec.x + CURSOR_OFFSET;
+		if x + logical_w > area_size.vec.x {
+			x = cursor_pos.vec.x - CURSOR_OFFSET - logical_w;
+		}
+		let mut y = cursor_pos.vec.y + CURSOR_OFFSET;
+		if y + logical_h > area_size.vec.y {
+			y = cursor_pos.vec.y - CURSOR_OFFSET - logical_h;
+		}
+
+		let widget = self.widget.upgrade().unwrap();
+		widget.set_icon(Some(Rc::new(Picture::from_image(image))));
+		widget.set_width(Length::Fixed(logical_w));
+		widget.set_height(Length::Fixed(logical_h));
+		widget.set_margin_left(x.max(0.0));
+		widget.set_margin_top(y.max(0.0));
+		widget.set_visible(true);
+	}
+}
+
+/// Fills a circle with a light outline, so that dark colors are visible on the background
+fn draw_circle(image: &mut RgbaImage, left: u32, top: u32, diameter: u32, color: Rgba<u8>) {
+	let radius = diameter as f32 * 0.5;
+	let outline = 2.0 * OVERSAMPLING;
+	let Rgba([r, g, b, a]) = color;
+	for y in 0..diameter {
+		for x in 0..diameter {
+			let dx = x as f32 + 0.5 - radius;
+			let dy = y as f32 + 0.5 - radius;
+			let distance = dx.hypot(dy);
+			let pixel = if distance > radius {
+				continue;
+			} else if distance > radius - outline {
+				Rgba([TEXT_COLOR[0], TEXT_COLOR[1], TEXT_COLOR[2], 255])
+			} else {
+				// Blend the translucent colors over the background
+				let a = a as u32;
+				let blend = |c: u8, bg: u8| ((c as u32 * a + bg as u32 * (255 - a)) / 255) as u8;
+				Rgba([blend(r, BG_COLOR[0]), blend(g, BG_COLOR[1]), blend(b, BG_COLOR[2]), 255])
+			};
+			image.put_pixel(left + x, top + y, pixel);
+		}
+	}
+}
+
+pub fn hex(color: Rgba<u8>) -> String {
+	let [r, g, b, _] = color.0;
+	format!("#{:02X}{:02X}{:02X}", r, g, b)
+}
+
+/// Returns the hue in degrees, and the saturation and lightness in percents
+pub fn rgb_to_hsl(r: u8, g: u8, b: u8) -> (u32, u32, u32) {
+	let (r, g, b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+	let max = r.max(g).max(b);
+	let min = r.min(g).min(b);
+	let lightness = (max + min) * 0.5;
+	let delta = max - min;
+	if delta == 0.0 {
+		return (0, 0, (lightness * 100.0).round() as u32);
+	}
+	let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+	let hue = if max == r {
+		((g - b) / delta).rem_euclid(6.0)
+	} else if max == g {
+		(b - r) / delta + 2.0
+	} else {
+		(r - g) / delta + 4.0
+	};
+	let hue = (hue * 60.0).round() as u32 % 360;
+	(hue, (saturation * 100.0).round() as u32, (lightness * 100.0).round() as u32)
+}
@@ -0,0 +1,36 @@
+//! The state of the inline text input for the command that `run_command` runs on the current
+//! image.
+
+use std::path::{Path, PathBuf};
+
+use super::line_editor::LineEditor;
+
+pub struct CommandEditor {
+	pub image_path: PathBuf,
+	pub line: LineEditor,
+}
+
+impl CommandEditor {
+	/// Starts with the command that was run last, for the image at `path`
+	pub fn new(path: &Path, last_command: Option<&str>) -> CommandEditor {
+		CommandEditor {
+			image_path: path.to_owned(),
+			line: LineEditor::new(last_command.unwrap_or_default().to_owned()),
+		}
+	}
+
+	/// The text that's displayed in the bottom bar while editing, with a bar at the caret
+	pub fn display_text(&self) -> String {
+		let (before, after) = self.line.split_at_caret();
+		format!("Run command ($input, $output): {}|{}", before, after)
+	}
+
+	/// The command to run, or an error message if it's empty
+	pub fn command(&self) -> Result<String, String> {
+		let command = self.line.text().trim();
+		if command.is_empty() {
+			return Err("The command can't be empty".into());
+		}
+		Ok(command.to_owned())
+	}
+}
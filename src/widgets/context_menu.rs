@@ -0,0 +1,148 @@
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+
+use gelatin::{
+	misc::{Alignment, Length, LogicalRect, LogicalVector},
+	picture::Picture,
+	tree_view::{TreeView, TreeViewRow},
+	Widget,
+};
+
+use super::text::{render_text, TEXT_COLOR};
+
+const ROW_HEIGHT: f32 = 24.0;
+const TEXT_HEIGHT: f32 = 13.0;
+/// The space kept free left and right of the names
+const PADDING: f32 = 10.0;
+const BG_COLOR: [f32; 4] = [0.1, 0.1, 0.1, 0.96];
+
+/// The actions that can be picked from the menu. Each one does the same as its shortcut.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MenuItem {
+	OpenExternally,
+	CopyPath,
+	CopyImage,
+	RotateCw,
+	RotateCcw,
+	FlipHorizontal,
+	FlipVertical,
+	Delete,
+	Properties,
+}
+
+const ITEMS: &[(MenuItem, &str)] = &[
+	(MenuItem::OpenExternally, "Open in external editor"),
+	(MenuItem::CopyPath, "Copy path"),
+	(MenuItem::CopyImage, "Copy image"),
+	(MenuItem::RotateCw, "Rotate CW"),
+	(MenuItem::RotateCcw, "Rotate CCW"),
+	(MenuItem::FlipHorizontal, "Flip Horizontal"),
+	(MenuItem::FlipVertical, "Flip Vertical"),
+	(MenuItem::Delete, "Delete"),
+	(MenuItem::Properties, "Properties"),
+];
+
+type SelectCallback = Rc<dyn Fn(MenuItem)>;
+
+/// The menu that's opened by right clicking the picture. It floats over the picture at the
+/// cursor and is closed when an item is picked.
+pub struct ContextMenu {
+	pub widget: Rc<TreeView>,
+	/// The area of the menu within the window while it's open
+	rect: Cell<LogicalRect>,
+	width: f32,
+	on_select: RefCell<Option<SelectCallback>>,
+}
+
+impl ContextMenu {
+	pub fn new() -> Rc<Self> {
+		Rc::new_cyclic(|this: &Weak<ContextMenu>| {
+			let widget = Rc::new(TreeView::new());
+			widget.set_ignore_layout(true);
+			widget.set_horizontal_align(Alignment::Start);
+			widget.set_vertical_align(Alignment::Start);
+			widget.set_row_height(ROW_HEIGHT);
+			widget.set_indent(PADDING);
+			widget.set_bg_color(BG_COLOR);
+			widget.set_visible(false);
+			let mut width: f32 = 0.0;
+			let rows = ITEMS
+				.iter()
+				.map(|(_, name)| {
+					let (image, size) = render_text(name, TEXT_HEIGHT, TEXT_COLOR);
+					width = width.max(size.vec.x + 2.0 * PADDING);
+					TreeViewRow {
+						depth: 0,
+						expandable: false,
+						expanded: false,
+						label: Rc::new(Picture::from_image(image)),
+						label_size: size,
+					}
+				})
+				.collect();
+			widget.set_rows(rows);
+			let height = ITEMS.len() as f32 * ROW_HEIGHT;
+			widget.set_width(Length::Fixed(width));
+			widget.set_height(Length::Fixed(height));
+			let this = this.clone();
+			widget.set_on_click(move |index| {
+				if let Some(menu) = this.upgrade() {
+					menu.click(index);
+				}
+			});
+			ContextMenu {
+				widget,
+				rect: Cell::new(LogicalRect {
+					pos: LogicalVector::new(0.0, 0.0),
+					size: LogicalVector::new(width, height),
+				}),
+				width,
+				on_select: RefCell::new(None),
+			}
+		})
+	}
+
+	/// The callback gets the item that was picked, after the menu was closed
+	pub fn set_on_select<F: Fn(MenuItem) + 'static>(&self, callback: F) {
+		*self.on_select.borrow_mut() = Some(Rc::new(callback));
+	}
+
+	pub fn is_open(&self) -> bool {
+		self.widget.visible()
+	}
+
+	/// Opens the menu with its top left corner at the cursor. It's moved up or left as far as
+	/// necessary to stay within `bounds`, which is the area of the picture.
+	pub fn open_at(&self, cursor: LogicalVector, bounds: LogicalRect) {
+		let size = LogicalVector::new(self.width, ITEMS.len() as f32 * ROW_HEIGHT);
+		let max = bounds.size - size;
+		let offset = cursor - bounds.pos;
+		let x = offset.vec.x.min(max.vec.x).max(0.0);
+		let y = offset.vec.y.min(max.vec.y).max(0.0);
+		self.widget.set_margin_left(x);
+		self.widget.set_margin_top(y);
+		self.rect.set(LogicalRect { pos: bounds.pos + LogicalVector::new(x, y), size });
+		self.widget.set_visible(true);
+	}
+
+	pub fn close(&self) {
+		self.widget.set_visible(false);
+	}
+
+	/// Whether the position within the window is on the open menu
+	pub fn contains(&self, pos: LogicalVector) -> bool {
+		self.is_open() && self.rect.get().contains(pos)
+	}
+
+	fn click(&self, index: usize) {
+		let item = match ITEMS.get(index) {
+			Some((item, _)) => *item,
+			None => return,
+		};
+		self.close();
+		let on_select = self.on_select.borrow().clone();
+		if let Some(callback) = on_select {
+			callback(item);
+		}
+	}
+}
@@ -0,0 +1,91 @@
+use std::rc::{Rc, Weak};
+use std::time::{Duration, Instant};
+
+use gelatin::{label::Label, misc::*, Widget};
+
+use super::text::set_label_lines_boxed_monospace;
+use crate::image_cache::CacheStats;
+
+const TEXT_HEIGHT: f32 = 13.0;
+const PADDING: f32 = 8.0;
+const TEXT_COLOR: [u8; 3] = [150, 255, 150];
+const BG_COLOR: [u8; 4] = [20, 20, 20, 220];
+/// The text is redrawn at most this often. Changing it makes the window draw another frame,
+/// which would change the frame time again and keep the window drawing.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Shows how long the last frame took to draw and what the image cache and the loader are doing
+pub struct DebugOverlay {
+	pub widget: Weak<Label>,
+	/// The lines that are displayed and when they were displayed
+	shown: Option<(Vec<String>, Instant)>,
+}
+
+impl DebugOverlay {
+	pub fn new(widget: &Rc<Label>) -> DebugOverlay {
+		widget.set_icon(None);
+		widget.set_ignore_layout(true);
+		widget.set_margin_all(8.0);
+		widget.set_horizontal_align(Alignment::Start);
+		widget.set_vertical_align(Alignment::End);
+		widget.set_visible(false);
+
+		DebugOverlay { widget: Rc::downgrade(widget), shown: None }
+	}
+
+	pub fn is_open(&self) -> bool {
+		self.widget.upgrade().is_some_and(|widget| widget.visible())
+	}
+
+	pub fn toggle(&mut self) {
+		let widget = self.widget.upgrade().unwrap();
+		widget.set_visible(!widget.visible());
+		self.shown = None;
+	}
+
+	/// Displays the stats if they changed, unless the text was displayed less than
+	/// `REFRESH_INTERVAL` ago. Returns when it should be called again to display the stats
+	/// that were held back.
+	pub fn update(&mut self, frame_time: Duration, stats: &CacheStats) -> Option<Instant> {
+		if !self.is_open() {
+			return None;
+		}
+		let lines = stat_lines(frame_time, stats);
+		let now = Instant::now();
+		if let Some((shown, shown_at)) = &self.shown {
+			if *shown == lines {
+				return None;
+			}
+			if now < *shown_at + REFRESH_INTERVAL {
+				return Some(*shown_at + REFRESH_INTERVAL);
+			}
+		}
+		let widget = self.widget.upgrade().unwrap();
+		set_label_lines_boxed_monospace(
+			&widget,
+			&lines,
+			TEXT_HEIGHT,
+			TEXT_COLOR,
+			BG_COLOR,
+			PADDING,
+		);
+		self.shown = Some((lines, now));
+		None
+	}
+}
+
+fn stat_lines(frame_time: Duration, stats: &CacheStats) -> Vec<String> {
+	let frame = match stats.frame {
+		Some((index, count, true)) => format!("{} / {}", index + 1, count),
+		Some((index, count, false)) => format!("{} / {}+", index + 1, count),
+		None => "-".to_owned(),
+	};
+	vec![
+		format!("frame time      {:>8.1} ms", frame_time.as_secs_f64() * 1000.0),
+		format!("textures        {:>8}", stats.textures),
+		format!("texture memory  {:>8.1} MB", stats.texture_bytes as f64 / (1024.0 * 1024.0)),
+		format!("loader queue    {:>8}", stats.queue_depth),
+		format!("pending results {:>8}", stats.pending_results),
+		format!("frame           {:>8}", frame),
+	]
+}
@@ -0,0 +1,150 @@
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
+
+use gelatin::{
+	image::imageops,
+	misc::{Length, LogicalVector},
+	picture::Picture,
+	tree_view::{TreeView, TreeViewRow},
+	Widget,
+};
+
+use super::text::{render_text, OVERSAMPLING, TEXT_COLOR};
+use crate::directory_tree::DirectoryTree;
+use crate::image_cache::image_loader::is_file_supported;
+
+pub const SIDEBAR_WIDTH: f32 = 200.0;
+const ROW_HEIGHT: f32 = 22.0;
+const INDENT: f32 = 14.0;
+const TEXT_HEIGHT: f32 = 13.0;
+/// The space kept free right of the names
+const PADDING: f32 = 6.0;
+const BG_COLOR: [f32; 4] = [0.08, 0.08, 0.08, 1.0];
+/// The folders without images are dimmed
+const EMPTY_COLOR: [u8; 3] = [140, 140, 140];
+
+type NavigateCallback = Rc<dyn Fn(&Path)>;
+
+/// The folder tree on the left of the picture. Clicking a folder with subfolders expands or
+/// collapses it, and clicking one without subfolders opens it.
+///
+/// The tree is read when the sidebar is first opened. The expanded folders and the scroll
+/// position are kept while the sidebar is closed.
+pub struct DirectorySidebar {
+	pub widget: Rc<TreeView>,
+	root: PathBuf,
+	tree: RefCell<Option<DirectoryTree>>,
+	on_navigate: RefCell<Option<NavigateCallback>>,
+}
+
+impl DirectorySidebar {
+	pub fn new(root: PathBuf) -> Rc<Self> {
+		Rc::new_cyclic(|this: &Weak<DirectorySidebar>| {
+			let widget = Rc::new(TreeView::new());
+			widget.set_width(Length::Fixed(SIDEBAR_WIDTH));
+			widget.set_height(Length::Stretch { min: 0.0, max: f32::INFINITY });
+			widget.set_row_height(ROW_HEIGHT);
+			widget.set_indent(INDENT);
+			widget.set_bg_color(BG_COLOR);
+			widget.set_visible(false);
+			let this = this.clone();
+			widget.set_on_click(move |index| {
+				if let Some(sidebar) = this.upgrade() {
+					sidebar.click(index);
+				}
+			});
+			DirectorySidebar {
+				widget,
+				root,
+				tree: RefCell::new(None),
+				on_navigate: RefCell::new(None),
+			}
+		})
+	}
+
+	/// The callback gets the folder that was clicked to be opened
+	pub fn set_on_navigate<F: Fn(&Path) + 'static>(&self, callback: F) {
+		*self.on_navigate.borrow_mut() = Some(Rc::new(callback));
+	}
+
+	pub fn is_open(&self) -> bool {
+		self.widget.visible()
+	}
+
+	/// Shows the sidebar with the folders read again, or hides it
+	pub fn toggle(&self) {
+		if self.is_open() {
+			self.widget.set_visible(false);
+			return;
+		}
+		{
+			let mut tree = self.tree.borrow_mut();
+			match tree.as_mut() {
+				Some(tree) => tree.refresh(),
+				None => *tree = Some(DirectoryTree::new(self.root.clone(), is_file_supported)),
+			}
+		}
+		self.update_rows();
+		self.widget.set_visible(true);
+	}
+
+	fn click(&self, index: usize) {
+		let navigate_to = {
+			let mut tree = self.tree.borrow_mut();
+			let tree = match tree.as_mut() {
+				Some(tree) => tree,
+				None => return,
+			};
+			match tree.nodes().get(index) {
+				Some(node) if node.has_subfolders => {
+					tree.toggle(index);
+					None
+				}
+				Some(node) => Some(node.path.clone()),
+				None => None,
+			}
+		};
+		match navigate_to {
+			Some(path) => {
+				let on_navigate = self.on_navigate.borrow().clone();
+				if let Some(callback) = on_navigate {
+					callback(&path);
+				}
+			}
+			None => self.update_rows(),
+		}
+	}
+
+	fn update_rows(&self) {
+		let tree = self.tree.borrow();
+		let nodes = match tree.as_ref() {
+			Some(tree) => tree.nodes(),
+			None => return,
+		};
+		let rows = nodes
+			.iter()
+			.map(|node| {
+				let text = format!("{} ({})", node.name, node.image_count);
+				let color = if node.image_count == 0 { EMPTY_COLOR } else { TEXT_COLOR };
+				let (mut image, mut size) = render_text(&text, TEXT_HEIGHT, color);
+				// The names that don't fit are cut off at the edge of the sidebar
+				let max_width = SIDEBAR_WIDTH - (node.depth + 1) as f32 * INDENT - PADDING;
+				if size.vec.x > max_width {
+					let width = (max_width.max(0.0) * OVERSAMPLING) as u32;
+					image =
+						imageops::crop_imm(&image, 0, 0, width.max(1), image.height()).to_image();
+					size = LogicalVector::new(image.width() as f32 / OVERSAMPLING, size.vec.y);
+				}
+				TreeViewRow {
+					depth: node.depth,
+					expandable: node.has_subfolders,
+					expanded: node.expanded,
+					label: Rc::new(Picture::from_image(image)),
+					label_size: size,
+				}
+			})
+			.collect();
+		self.widget.set_rows(rows);
+	}
+}
@@ -0,0 +1,39 @@
+use std::rc::{Rc, Weak};
+
+use gelatin::{label::Label, misc::*, Widget};
+
+use super::text::set_label_text_boxed;
+
+const TEXT: &str = "Drop an image here or press Ctrl+V to paste";
+const TEXT_HEIGHT: f32 = 16.0;
+const PADDING: f32 = 10.0;
+const TEXT_COLOR: [u8; 3] = [230, 230, 230];
+const BG_COLOR: [u8; 4] = [20, 20, 20, 200];
+
+/// Fills the empty window while no file is open, that is when the viewer was started without
+/// a file or the file was closed, until an image is dropped or pasted
+pub struct EmptyHint {
+	pub widget: Weak<Label>,
+}
+
+impl EmptyHint {
+	pub fn new(widget: &Rc<Label>) -> EmptyHint {
+		widget.set_ignore_layout(true);
+		widget.set_horizontal_align(Alignment::Center);
+		widget.set_vertical_align(Alignment::Center);
+		set_label_text_boxed(widget, TEXT, TEXT_HEIGHT, TEXT_COLOR, BG_COLOR, PADDING);
+		widget.set_visible(false);
+
+		EmptyHint { widget: Rc::downgrade(widget) }
+	}
+
+	pub fn visible(&self) -> bool {
+		self.widget.upgrade().is_some_and(|widget| widget.visible())
+	}
+
+	pub fn set_visible(&self, visible: bool) {
+		if let Some(widget) = self.widget.upgrade() {
+			widget.set_visible(visible);
+		}
+	}
+}
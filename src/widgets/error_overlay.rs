@@ -0,0 +1,65 @@
+use std::rc::{Rc, Weak};
+
+use std::time::{Duration, Instant};
+
+use gelatin::{label::Label, misc::*, NextUpdate, Widget};
+
+use super::text::set_label_text_boxed;
+
+const DISPLAY_TIME: Duration = Duration::from_secs(4);
+const TEXT_HEIGHT: f32 = 16.0;
+const PADDING: f32 = 10.0;
+const TEXT_COLOR: [u8; 3] = [255, 255, 255];
+const BG_COLOR: [u8; 4] = [170, 30, 30, 230];
+const STATUS_BG_COLOR: [u8; 4] = [40, 40, 40, 230];
+
+/// Shows an error message over the bottom of the picture for a few seconds
+#[derive(Clone)]
+pub struct ErrorOverlay {
+	pub widget: Weak<Label>,
+	shown_time: Instant,
+}
+
+impl ErrorOverlay {
+	pub fn new(widget: &Rc<Label>) -> ErrorOverlay {
+		widget.set_icon(None);
+		widget.set_ignore_layout(true);
+		widget.set_margin_all(8.0);
+		widget.set_horizontal_align(Alignment::Center);
+		widget.set_vertical_align(Alignment::End);
+		widget.set_visible(false);
+
+		ErrorOverlay { widget: Rc::downgrade(widget), shown_time: Instant::now() }
+	}
+
+	pub fn show(&mut self, message: &str) {
+		self.show_with_background(message, BG_COLOR);
+	}
+
+	/// Shows a message that isn't an error, like the confirmation of an action
+	pub fn show_status(&mut self, message: &str) {
+		self.show_with_background(message, STATUS_BG_COLOR);
+	}
+
+	fn show_with_background(&mut self, message: &str, bg_color: [u8; 4]) {
+		let widget = self.widget.upgrade().unwrap();
+		set_label_text_boxed(&widget, message, TEXT_HEIGHT, TEXT_COLOR, bg_color, PADDING);
+		widget.set_visible(true);
+		self.shown_time = Instant::now();
+	}
+
+	pub fn update(&mut self) -> NextUpdate {
+		let widget = self.widget.upgrade().unwrap();
+		if widget.visible() {
+			let hide_time = self.shown_time + DISPLAY_TIME;
+			if Instant::now() > hide_time {
+				widget.set_visible(false);
+				NextUpdate::Latest
+			} else {
+				NextUpdate::WaitUntil(hide_time)
+			}
+		} else {
+			NextUpdate::Latest
+		}
+	}
+}
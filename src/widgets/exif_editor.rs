@@ -0,0 +1,107 @@
+use std::path::{Path, PathBuf};
+use std::rc::{Rc, Weak};
+
+use gelatin::{label::Label, misc::*};
+
+use super::{line_editor::LineEditor, text::set_label_lines_boxed};
+use crate::exif_writer::{self, EditableTag};
+
+const TEXT_HEIGHT: f32 = 15.0;
+const PADDING: f32 = 12.0;
+const TEXT_COLOR: [u8; 3] = [230, 230, 230];
+const BG_COLOR: [u8; 4] = [30, 30, 30, 235];
+
+struct EditedImage {
+	path: PathBuf,
+	/// The values of the tags in the order of `EditableTag::ALL`
+	lines: Vec<LineEditor>,
+	focused: usize,
+}
+
+/// A panel over the picture with a text input for each of the editable EXIF tags
+pub struct ExifEditor {
+	pub widget: Weak<Label>,
+	edited: Option<EditedImage>,
+}
+
+impl ExifEditor {
+	pub fn new(widget: &Rc<Label>) -> ExifEditor {
+		widget.set_icon(None);
+		widget.set_ignore_layout(true);
+		widget.set_horizontal_align(Alignment::Center);
+		widget.set_vertical_align(Alignment::Center);
+		widget.set_visible(false);
+
+		ExifEditor { widget: Rc::downgrade(widget), edited: None }
+	}
+
+	pub fn is_open(&self) -> bool {
+		self.edited.is_some()
+	}
+
+	/// Opens the panel with the current tags of the JPEG at `path`
+	pub fn open(&mut self, path: &Path) -> Result<(), String> {
+		if !exif_writer::is_editable(path) {
+			return Err("Only the EXIF data of JPEG files can be edited".into());
+		}
+		let values = exif_writer::read_editable(path)?;
+		let lines = values.into_iter().map(LineEditor::new).collect();
+		self.edited = Some(EditedImage { path: path.to_owned(), lines, focused: 0 });
+		self.widget.upgrade().unwrap().set_visible(true);
+		self.redraw();
+		Ok(())
+	}
+
+	pub fn close(&mut self) {
+		self.edited = None;
+		self.widget.upgrade().unwrap().set_visible(false);
+	}
+
+	/// The input of the tag that the key presses edit
+	pub fn focused_line(&mut self) -> Option<&mut LineEditor> {
+		self.edited.as_mut().map(|edited| &mut edited.lines[edited.focused])
+	}
+
+	/// Moves the focus to the next tag if `forward` is true and to the previous one otherwise
+	pub fn move_focus(&mut self, forward: bool) {
+		if let Some(edited) = &mut self.edited {
+			let count = edited.lines.len();
+			edited.focused = if forward { edited.focused + 1 } else { edited.focused + count - 1 };
+			edited.focused %= count;
+		}
+	}
+
+	/// Writes the edited tags to the file. Returns the path of the file on success.
+	pub fn save(&mut self) -> Result<PathBuf, String> {
+		let edited = match &self.edited {
+			Some(edited) => edited,
+			None => return Err("The EXIF editor is not open".into()),
+		};
+		let values: Vec<String> = edited.lines.iter().map(|line| line.text().to_owned()).collect();
+		exif_writer::write_editable(&edited.path, &values)?;
+		let path = edited.path.clone();
+		self.close();
+		Ok(path)
+	}
+
+	pub fn redraw(&self) {
+		let edited = match &self.edited {
+			Some(edited) => edited,
+			None => return,
+		};
+		let name = edited.path.file_name().unwrap_or_default().to_string_lossy();
+		let mut lines = vec![format!("EXIF of {}", name), String::new()];
+		for (i, (tag, line)) in EditableTag::ALL.iter().zip(&edited.lines).enumerate() {
+			let (before, after) = line.split_at_caret();
+			lines.push(if i == edited.focused {
+				format!("> {}: {}|{}", tag.label(), before, after)
+			} else {
+				format!("   {}: {}", tag.label(), line.text())
+			});
+		}
+		lines.push(String::new());
+		lines.push("Tab: next field    Enter: save    Escape: cancel".into());
+		let widget = self.widget.upgrade().unwrap();
+		set_label_lines_boxed(&widget, &lines, TEXT_HEIGHT, TEXT_COLOR, BG_COLOR, PADDING);
+	}
+}
@@ -0,0 +1,245 @@
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+
+use gelatin::{
+	button::Button,
+	label::Label,
+	line_layout_container::HorizontalLayoutContainer,
+	misc::{Alignment, Length, LogicalVector},
+	slider::Slider,
+	winit::{
+		event::KeyEvent,
+		keyboard::{Key, ModifiersState, NamedKey},
+	},
+	Widget,
+};
+
+use super::line_editor::LineEditor;
+use super::text::{set_button_text, set_label_text};
+
+const MIN_PANEL_GAMMA: f32 = 0.1;
+const MAX_PANEL_GAMMA: f32 = 4.0;
+const PANEL_GAMMA_STEP: f32 = 0.05;
+
+const PANEL_WIDTH: f32 = 560.0;
+const ROW_HEIGHT: f32 = 40.0;
+const TEXT_HEIGHT: f32 = 16.0;
+const NAME_WIDTH: f32 = 70.0;
+const VALUE_WIDTH: f32 = 60.0;
+const GAP: f32 = 8.0;
+const SLIDER_HEIGHT: f32 = 22.0;
+/// Keeps the panel above the bottom bar
+const BOTTOM_MARGIN: f32 = 40.0;
+
+const PANEL_BG_COLOR: [f32; 4] = [0.12, 0.12, 0.12, 0.9];
+const BUTTON_BG_COLOR: [f32; 4] = [0.3, 0.3, 0.3, 0.5];
+
+type ChangeCallback = Rc<dyn Fn(f32)>;
+
+/// The panel of `gamma_panel` at the bottom of the picture. The gamma is applied while the
+/// slider is moved, and it can be typed in as a number while the panel is open.
+pub struct GammaPanel {
+	pub widget: Rc<HorizontalLayoutContainer>,
+	on_change: RefCell<Option<ChangeCallback>>,
+	gamma: Cell<f32>,
+	/// The number while it's being typed
+	entry: RefCell<Option<LineEditor>>,
+
+	slider: Rc<Slider>,
+	value_label: Rc<Label>,
+}
+
+impl GammaPanel {
+	pub fn new() -> Rc<Self> {
+		Rc::new_cyclic(|this: &Weak<GammaPanel>| {
+			let widget = Rc::new(HorizontalLayoutContainer::new());
+			widget.set_ignore_layout(true);
+			widget.set_width(Length::Fixed(PANEL_WIDTH));
+			widget.set_height(Length::Fixed(ROW_HEIGHT));
+			widget.set_margin_bottom(BOTTOM_MARGIN);
+			widget.set_horizontal_align(Alignment::Center);
+			widget.set_vertical_align(Alignment::End);
+			widget.set_bg_color(PANEL_BG_COLOR);
+			widget.set_visible(false);
+
+			let name_label = make_label(Alignment::Start);
+			set_label_text(&name_label, "Gamma", TEXT_HEIGHT);
+			widget.add_child(make_area(name_label, NAME_WIDTH));
+
+			let slider = Rc::new(Slider::new());
+			slider.set_margin_left(GAP);
+			slider.set_margin_right(GAP);
+			slider.set_height(Length::Fixed(SLIDER_HEIGHT));
+			slider.set_width(Length::Stretch { min: 0.0, max: f32::INFINITY });
+			slider.set_vertical_align(Alignment::Center);
+			slider.set_steps(slider_steps(), slider_value(1.0));
+			slider.set_shadow_color([0.0, 0.0, 0.0]);
+			{
+				let panel = this.clone();
+				let weak_slider = Rc::downgrade(&slider);
+				slider.set_on_value_change(move || {
+					if let (Some(panel), Some(slider)) = (panel.upgrade(), weak_slider.upgrade()) {
+						let gamma = MIN_PANEL_GAMMA + slider.value() as f32 * PANEL_GAMMA_STEP;
+						panel.change_gamma(gamma);
+					}
+				});
+			}
+			widget.add_child(slider.clone());
+
+			let value_label = make_label(Alignment::End);
+			widget.add_child(make_area(value_label.clone(), VALUE_WIDTH));
+
+			let reset_button = make_button();
+			set_button_text(&reset_button, "Reset", TEXT_HEIGHT, GAP);
+			on_click(&reset_button, this, |panel| panel.change_gamma(1.0));
+			widget.add_child(reset_button);
+
+			let close_button = make_button();
+			close_button.set_margin_right(GAP);
+			set_button_text(&close_button, "Close", TEXT_HEIGHT, GAP);
+			on_click(&close_button, this, |panel| panel.close());
+			widget.add_child(close_button);
+
+			GammaPanel {
+				widget,
+				on_change: RefCell::new(None),
+				gamma: Cell::new(1.0),
+				entry: RefCell::new(None),
+				slider,
+				value_label,
+			}
+		})
+	}
+
+	/// The callback is called with the gamma that was set on the panel
+	pub fn set_on_change<F: Fn(f32) + 'static>(&self, callback: F) {
+		*self.on_change.borrow_mut() = Some(Rc::new(callback));
+	}
+
+	pub fn is_open(&self) -> bool {
+		self.widget.visible()
+	}
+
+	pub fn open(&self, gamma: f32) {
+		self.show_gamma(gamma);
+		self.widget.set_visible(true);
+	}
+
+	pub fn close(&self) {
+		*self.entry.borrow_mut() = None;
+		self.widget.set_visible(false);
+	}
+
+	/// Moves the controls to the gamma that's applied to the picture
+	pub fn show_gamma(&self, gamma: f32) {
+		self.gamma.set(gamma);
+		self.slider.set_value(slider_value(gamma));
+		self.refresh();
+	}
+
+	/// Types the number on the panel. Returns false for the keys that are left to the other
+	/// actions.
+	pub fn handle_key(&self, input: &KeyEvent, modifiers: ModifiersState) -> bool {
+		let typing = self.entry.borrow().is_some();
+		match &input.logical_key {
+			Key::Named(NamedKey::Enter) if typing => {
+				let text = self.entry.take().map(|entry| entry.text().to_owned());
+				match text.and_then(|text| text.parse::<f32>().ok()) {
+					Some(gamma) if gamma.is_finite() => self.change_gamma(gamma),
+					_ => self.refresh(),
+				}
+			}
+			Key::Named(NamedKey::Escape) if typing => {
+				*self.entry.borrow_mut() = None;
+				self.refresh();
+			}
+			Key::Named(NamedKey::Backspace) if typing => {
+				if let Some(entry) = self.entry.borrow_mut().as_mut() {
+					entry.backspace();
+				}
+				self.refresh();
+			}
+			_ => {
+				if modifiers.control_key() || modifiers.alt_key() || modifiers.super_key() {
+					return false;
+				}
+				let text = match input.text.as_deref() {
+					Some(text) if !text.is_empty() && text.chars().all(is_number_char) => text,
+					_ => return false,
+				};
+				let mut entry = self.entry.borrow_mut();
+				entry.get_or_insert_with(|| LineEditor::new(String::new())).insert(text);
+				drop(entry);
+				self.refresh();
+			}
+		}
+		true
+	}
+
+	fn change_gamma(&self, gamma: f32) {
+		let gamma = (gamma / PANEL_GAMMA_STEP).round() * PANEL_GAMMA_STEP;
+		self.show_gamma(gamma.clamp(MIN_PANEL_GAMMA, MAX_PANEL_GAMMA));
+		let on_change = self.on_change.borrow().clone();
+		if let Some(callback) = on_change {
+			callback(self.gamma.get());
+		}
+	}
+
+	fn refresh(&self) {
+		let text = match self.entry.borrow().as_ref() {
+			Some(entry) => {
+				let (before, after) = entry.split_at_caret();
+				format!("{}|{}", before, after)
+			}
+			None => format!("{:.2}", self.gamma.get()),
+		};
+		set_label_text(&self.value_label, &text, TEXT_HEIGHT);
+	}
+}
+
+fn is_number_char(ch: char) -> bool {
+	ch.is_ascii_digit() || ch == '.'
+}
+
+fn slider_steps() -> u32 {
+	((MAX_PANEL_GAMMA - MIN_PANEL_GAMMA) / PANEL_GAMMA_STEP).round() as u32 + 1
+}
+
+fn slider_value(gamma: f32) -> u32 {
+	let value = ((gamma - MIN_PANEL_GAMMA) / PANEL_GAMMA_STEP).round().max(0.0) as u32;
+	value.min(slider_steps() - 1)
+}
+
+fn on_click<F: Fn(&GammaPanel) + 'static>(button: &Button, panel: &Weak<GammaPanel>, f: F) {
+	let panel = panel.clone();
+	button.set_on_click(move || {
+		if let Some(panel) = panel.upgrade() {
+			f(&panel);
+		}
+	});
+}
+
+fn make_area(label: Rc<Label>, width: f32) -> Rc<HorizontalLayoutContainer> {
+	let area = Rc::new(HorizontalLayoutContainer::new());
+	area.set_margin_all(0.0);
+	area.set_margin_left(GAP);
+	area.set_fixed_size(LogicalVector::new(width, ROW_HEIGHT));
+	area.add_child(label);
+	area
+}
+
+fn make_label(alignment: Alignment) -> Rc<Label> {
+	let label = Rc::new(Label::new());
+	label.set_margin_all(0.0);
+	label.set_horizontal_align(alignment);
+	label.set_vertical_align(Alignment::Center);
+	label
+}
+
+fn make_button() -> Rc<Button> {
+	let button = Rc::new(Button::new());
+	button.set_margin_left(GAP * 0.5);
+	button.set_vertical_align(Alignment::Center);
+	button.set_bg_color(BUTTON_BG_COLOR);
+	button
+}
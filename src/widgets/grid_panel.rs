@@ -0,0 +1,317 @@
+use std::cell::{Cell, RefCell};
+use std::path::PathBuf;
+use std::rc::{Rc, Weak};
+
+use gelatin::{
+	button::Button,
+	label::Label,
+	line_layout_container::{HorizontalLayoutContainer, VerticalLayoutContainer},
+	misc::{Alignment, Length, LogicalVector},
+	slider::Slider,
+	Widget,
+};
+
+use super::text::{set_button_text, set_label_text};
+use crate::configuration::{parse_hex_color, ConfigGridSection, Configuration, PanelSetting};
+
+const PANEL_WIDTH: f32 = 420.0;
+const ROW_HEIGHT: f32 = 32.0;
+const TEXT_HEIGHT: f32 = 16.0;
+const NAME_WIDTH: f32 = 110.0;
+const VALUE_WIDTH: f32 = 70.0;
+const GAP: f32 = 8.0;
+const SWATCH_SIZE: f32 = 22.0;
+
+const PANEL_BG_COLOR: [f32; 4] = [0.12, 0.12, 0.12, 1.0];
+const BUTTON_BG_COLOR: [f32; 4] = [0.3, 0.3, 0.3, 0.5];
+
+const LINE_COLORS: [&str; 6] = ["#ffffff", "#000000", "#ff3b30", "#ffcc00", "#34c759", "#00c7ff"];
+const MAX_LINES: u32 = 12;
+/// The opacity is set in steps of 5 percent
+const OPACITY_STEPS: u32 = 20;
+const MAX_THICKNESS: u32 = 8;
+
+/// The panel of `grid_settings`, which sets up the grid over the image. Like the settings
+/// panel, every change is applied right away and saved to the config file when the panel is
+/// closed.
+pub struct GridPanel {
+	pub widget: Rc<VerticalLayoutContainer>,
+	configuration: Rc<RefCell<Configuration>>,
+	config_path: PathBuf,
+	on_change: RefCell<Option<Rc<dyn Fn()>>>,
+	/// Whether the grid was changed since the panel was opened
+	changed: Cell<bool>,
+
+	visible_button: Rc<Button>,
+	columns_slider: Rc<Slider>,
+	columns_value: Rc<Label>,
+	rows_slider: Rc<Slider>,
+	rows_value: Rc<Label>,
+	opacity_slider: Rc<Slider>,
+	opacity_value: Rc<Label>,
+	thickness_slider: Rc<Slider>,
+	thickness_value: Rc<Label>,
+}
+
+impl GridPanel {
+	pub fn new(configuration: Rc<RefCell<Configuration>>, config_path: PathBuf) -> Rc<Self> {
+		Rc::new_cyclic(|this: &Weak<GridPanel>| {
+			let widget = Rc::new(VerticalLayoutContainer::new());
+			widget.set_ignore_layout(true);
+			widget.set_width(Length::Fixed(PANEL_WIDTH));
+			widget.set_height(Length::Fixed(ROW_HEIGHT * 8.0 + GAP * 2.0));
+			widget.set_horizontal_align(Alignment::Center);
+			widget.set_vertical_align(Alignment::Center);
+			widget.set_bg_color(PANEL_BG_COLOR);
+			widget.set_visible(false);
+
+			let title = make_label(Alignment::Center);
+			set_label_text(&title, "Grid", TEXT_HEIGHT * 1.25);
+			widget.add_child(make_row(vec![title]));
+
+			let visible_button = make_button();
+			on_click(&visible_button, this, |panel| {
+				panel.update_grid(|grid| grid.visible = Some(!grid.visible.unwrap_or(false)))
+			});
+			widget.add_child(make_setting_row("Show", vec![visible_button.clone()], None));
+
+			let columns_slider = make_slider(MAX_LINES - 1);
+			let columns_value = make_label(Alignment::End);
+			on_value_change(&columns_slider, this, |panel, value| {
+				panel.update_grid(|grid| grid.columns = Some(value as u8 + 1));
+			});
+			widget.add_child(make_setting_row(
+				"Columns",
+				vec![columns_slider.clone()],
+				Some(columns_value.clone()),
+			));
+
+			let rows_slider = make_slider(MAX_LINES - 1);
+			let rows_value = make_label(Alignment::End);
+			on_value_change(&rows_slider, this, |panel, value| {
+				panel.update_grid(|grid| grid.rows = Some(value as u8 + 1));
+			});
+			widget.add_child(make_setting_row(
+				"Rows",
+				vec![rows_slider.clone()],
+				Some(rows_value.clone()),
+			));
+
+			let mut swatches: Vec<Rc<dyn Widget>> = Vec::new();
+			for color in LINE_COLORS.iter() {
+				let swatch = make_button();
+				let [r, g, b] = parse_hex_color(color).unwrap();
+				swatch.set_bg_color([r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0]);
+				swatch.set_fixed_size(LogicalVector::new(SWATCH_SIZE, SWATCH_SIZE));
+				on_click(&swatch, this, move |panel| {
+					panel.update_grid(|grid| grid.color = Some(color.to_string()))
+				});
+				swatches.push(swatch);
+			}
+			widget.add_child(make_setting_row("Color", swatches, None));
+
+			let opacity_slider = make_slider(OPACITY_STEPS);
+			let opacity_value = make_label(Alignment::End);
+			on_value_change(&opacity_slider, this, |panel, value| {
+				panel.update_grid(|grid| grid.opacity = Some(value as f32 / OPACITY_STEPS as f32));
+			});
+			widget.add_child(make_setting_row(
+				"Opacity",
+				vec![opacity_slider.clone()],
+				Some(opacity_value.clone()),
+			));
+
+			let thickness_slider = make_slider(MAX_THICKNESS - 1);
+			let thickness_value = make_label(Alignment::End);
+			on_value_change(&thickness_slider, this, |panel, value| {
+				panel.update_grid(|grid| grid.thickness = Some((value + 1) as f32));
+			});
+			widget.add_child(make_setting_row(
+				"Thickness",
+				vec![thickness_slider.clone()],
+				Some(thickness_value.clone()),
+			));
+
+			let close_button = make_button();
+			close_button.set_horizontal_align(Alignment::Center);
+			set_button_text(&close_button, "Close", TEXT_HEIGHT, GAP);
+			on_click(&close_button, this, |panel| panel.close());
+			widget.add_child(make_row(vec![close_button]));
+
+			GridPanel {
+				widget,
+				configuration,
+				config_path,
+				on_change: RefCell::new(None),
+				changed: Cell::new(false),
+				visible_button,
+				columns_slider,
+				columns_value,
+				rows_slider,
+				rows_value,
+				opacity_slider,
+				opacity_value,
+				thickness_slider,
+				thickness_value,
+			}
+		})
+	}
+
+	/// The callback is called after the grid was changed in the configuration
+	pub fn set_on_change<F: Fn() + 'static>(&self, callback: F) {
+		*self.on_change.borrow_mut() = Some(Rc::new(callback));
+	}
+
+	pub fn is_open(&self) -> bool {
+		self.widget.visible()
+	}
+
+	/// Opens the panel and shows the grid, so that the changes can be seen
+	pub fn open(&self) {
+		if self.configuration.borrow().grid.as_ref().and_then(|g| g.visible) != Some(true) {
+			self.update_grid(|grid| grid.visible = Some(true));
+		}
+		self.refresh();
+		self.widget.set_visible(true);
+	}
+
+	/// Hides the panel and saves the grid to the config file
+	pub fn close(&self) {
+		if !self.is_open() {
+			return;
+		}
+		self.widget.set_visible(false);
+		if !self.changed.replace(false) {
+			return;
+		}
+		let config = self.configuration.borrow();
+		if let Err(e) = config.save_panel_settings(&self.config_path, &[PanelSetting::Grid]) {
+			eprintln!("Could not save the grid settings: {}", e);
+		}
+	}
+
+	fn update_grid<F: FnOnce(&mut ConfigGridSection)>(&self, update: F) {
+		update(self.configuration.borrow_mut().grid.get_or_insert_with(Default::default));
+		self.changed.set(true);
+		self.refresh();
+		let on_change = self.on_change.borrow().clone();
+		if let Some(callback) = on_change {
+			callback();
+		}
+	}
+
+	/// Updates the controls to show the values from the configuration
+	fn refresh(&self) {
+		let config = self.configuration.borrow();
+		let default = ConfigGridSection::default();
+		let grid = config.grid.as_ref().unwrap_or(&default);
+
+		let visible = if grid.visible == Some(true) { "On" } else { "Off" };
+		set_button_text(&self.visible_button, visible, TEXT_HEIGHT, GAP * 0.5);
+
+		let columns = (grid.columns() as u32).min(MAX_LINES);
+		self.columns_slider.set_value(columns - 1);
+		set_label_text(&self.columns_value, &columns.to_string(), TEXT_HEIGHT);
+		let rows = (grid.rows() as u32).min(MAX_LINES);
+		self.rows_slider.set_value(rows - 1);
+		set_label_text(&self.rows_value, &rows.to_string(), TEXT_HEIGHT);
+
+		let opacity = grid.line_color()[3];
+		self.opacity_slider.set_value((opacity * OPACITY_STEPS as f32).round() as u32);
+		let opacity_text = format!("{}%", (opacity * 100.0).round());
+		set_label_text(&self.opacity_value, &opacity_text, TEXT_HEIGHT);
+
+		let thickness = (grid.thickness().round() as u32).clamp(1, MAX_THICKNESS);
+		self.thickness_slider.set_value(thickness - 1);
+		set_label_text(&self.thickness_value, &format!("{} px", thickness), TEXT_HEIGHT);
+	}
+}
+
+fn on_click<F: Fn(&GridPanel) + 'static>(button: &Button, panel: &Weak<GridPanel>, f: F) {
+	let panel = panel.clone();
+	button.set_on_click(move || {
+		if let Some(panel) = panel.upgrade() {
+			f(&panel);
+		}
+	});
+}
+
+fn on_value_change<F: Fn(&GridPanel, u32) + 'static>(
+	slider: &Rc<Slider>,
+	panel: &Weak<GridPanel>,
+	f: F,
+) {
+	let panel = panel.clone();
+	let weak_slider = Rc::downgrade(slider);
+	slider.set_on_value_change(move || {
+		if let (Some(panel), Some(slider)) = (panel.upgrade(), weak_slider.upgrade()) {
+			f(&panel, slider.value());
+		}
+	});
+}
+
+fn make_row(children: Vec<Rc<dyn Widget>>) -> Rc<HorizontalLayoutContainer> {
+	let row = Rc::new(HorizontalLayoutContainer::new());
+	row.set_margin_left(GAP);
+	row.set_margin_right(GAP);
+	row.set_height(Length::Fixed(ROW_HEIGHT));
+	row.set_width(Length::Stretch { min: 0.0, max: f32::INFINITY });
+	for child in children {
+		row.add_child(child);
+	}
+	row
+}
+
+fn make_setting_row(
+	name: &str,
+	controls: Vec<Rc<dyn Widget>>,
+	value: Option<Rc<Label>>,
+) -> Rc<HorizontalLayoutContainer> {
+	let name_label = make_label(Alignment::Start);
+	set_label_text(&name_label, name, TEXT_HEIGHT);
+	let name_area = Rc::new(HorizontalLayoutContainer::new());
+	name_area.set_margin_all(0.0);
+	name_area.set_fixed_size(LogicalVector::new(NAME_WIDTH, ROW_HEIGHT));
+	name_area.add_child(name_label);
+
+	let row = make_row(vec![name_area]);
+	for control in controls {
+		row.add_child(control);
+	}
+	if let Some(value) = value {
+		let value_area = Rc::new(HorizontalLayoutContainer::new());
+		value_area.set_margin_all(0.0);
+		value_area.set_fixed_size(LogicalVector::new(VALUE_WIDTH, ROW_HEIGHT));
+		value_area.add_child(value);
+		row.add_child(value_area);
+	}
+	row
+}
+
+fn make_label(alignment: Alignment) -> Rc<Label> {
+	let label = Rc::new(Label::new());
+	label.set_margin_all(0.0);
+	label.set_horizontal_align(alignment);
+	label.set_vertical_align(Alignment::Center);
+	label
+}
+
+fn make_button() -> Rc<Button> {
+	let button = Rc::new(Button::new());
+	button.set_margin_left(GAP * 0.5);
+	button.set_vertical_align(Alignment::Center);
+	button.set_bg_color(BUTTON_BG_COLOR);
+	button
+}
+
+fn make_slider(steps: u32) -> Rc<Slider> {
+	let slider = Rc::new(Slider::new());
+	slider.set_margin_left(GAP);
+	slider.set_margin_right(GAP);
+	slider.set_height(Length::Fixed(SWATCH_SIZE));
+	slider.set_width(Length::Stretch { min: 0.0, max: f32::INFINITY });
+	slider.set_vertical_align(Alignment::Center);
+	slider.set_steps(steps, 0);
+	slider.set_shadow_color([0.0, 0.0, 0.0]);
+	slider
+}
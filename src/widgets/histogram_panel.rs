@@ -0,0 +1,170 @@
+use std::rc::{Rc, Weak};
+
+use gelatin::{
+	image::{imageops, Rgba, RgbaImage},
+	label::Label,
+	misc::*,
+	picture::Picture,
+	Widget,
+};
+
+use super::text::{render_text, OVERSAMPLING, TEXT_COLOR};
+use crate::histogram::{Histogram, BIN_COUNT, HISTOGRAM_NAMES};
+
+const CHART_WIDTH: f32 = 200.0;
+const CHART_HEIGHT: f32 = 100.0;
+const TEXT_HEIGHT: f32 = 12.0;
+const PADDING: f32 = 6.0;
+/// The distance from the right edge of the picture area
+const RIGHT_MARGIN: f32 = 10.0;
+/// Keeps the panel above the bottom bar
+const BOTTOM_MARGIN: f32 = 40.0;
+
+const BG_COLOR: [u8; 4] = [20, 20, 20, 220];
+/// The fill colors of the R, G, B and luminance histograms
+const FILL_COLORS: [[u8; 4]; 4] =
+	[[230, 60, 60, 90], [60, 200, 60, 90], [70, 110, 240, 90], [220, 220, 220, 70]];
+const MARKER_COLOR: [u8; 4] = [255, 255, 255, 160];
+
+/// The panel of `histogram` in the lower right corner of the picture. The counts are drawn on
+/// a logarithmic scale, and the exact counts of the bin under the cursor are written above
+/// the chart.
+pub struct HistogramPanel {
+	pub widget: Weak<Label>,
+	histogram: Option<Histogram>,
+	/// The bin under the cursor
+	hovered: Option<usize>,
+}
+
+impl HistogramPanel {
+	pub fn new(widget: &Rc<Label>) -> HistogramPanel {
+		widget.set_icon(None);
+		widget.set_ignore_layout(true);
+		widget.set_horizontal_align(Alignment::End);
+		widget.set_vertical_align(Alignment::End);
+		widget.set_margin_right(RIGHT_MARGIN);
+		widget.set_margin_bottom(BOTTOM_MARGIN);
+		widget.set_visible(false);
+
+		HistogramPanel { widget: Rc::downgrade(widget), histogram: None, hovered: None }
+	}
+
+	pub fn is_open(&self) -> bool {
+		self.widget.upgrade().is_some_and(|widget| widget.visible())
+	}
+
+	pub fn open(&mut self) {
+		self.redraw();
+		self.widget.upgrade().unwrap().set_visible(true);
+	}
+
+	pub fn close(&mut self) {
+		self.hovered = None;
+		if let Some(widget) = self.widget.upgrade() {
+			widget.set_visible(false);
+		}
+	}
+
+	/// Replaces the histogram, `None` leaves the chart empty while the next one is computed
+	pub fn set_histogram(&mut self, histogram: Option<Histogram>) {
+		self.histogram = histogram;
+		if self.is_open() {
+			self.redraw();
+		}
+	}
+
+	/// Shows the counts of the bin under the cursor. `cursor_pos` is relative to the picture
+	/// area whose size is `area_size`. Returns true if the panel has to be drawn again.
+	pub fn hover(&mut self, cursor_pos: LogicalVector, area_size: LogicalVector) -> bool {
+		if !self.is_open() {
+			return false;
+		}
+		let (panel_w, _) = panel_size();
+		let left = area_size.vec.x - RIGHT_MARGIN - panel_w + PADDING;
+		let bottom = area_size.vec.y - BOTTOM_MARGIN - PADDING;
+		let x = cursor_pos.vec.x - left;
+		let y = cursor_pos.vec.y - (bottom - CHART_HEIGHT);
+		let hovered = if (0.0..CHART_WIDTH).contains(&x) && (0.0..CHART_HEIGHT).contains(&y) {
+			Some(((x / CHART_WIDTH * BIN_COUNT as f32) as usize).min(BIN_COUNT - 1))
+		} else {
+			None
+		};
+		if hovered == self.hovered {
+			return false;
+		}
+		self.hovered = hovered;
+		self.redraw();
+		true
+	}
+
+	fn redraw(&self) {
+		let (panel_w, panel_h) = panel_size();
+		let (width, height) = ((panel_w * OVERSAMPLING) as u32, (panel_h * OVERSAMPLING) as u32);
+		let pad = (PADDING * OVERSAMPLING) as u32;
+		let (chart_w, chart_h) =
+			((CHART_WIDTH * OVERSAMPLING) as u32, (CHART_HEIGHT * OVERSAMPLING) as u32);
+		let chart_top = height - pad - chart_h;
+		let mut image = RgbaImage::from_pixel(width, height, Rgba(BG_COLOR));
+
+		if let Some(histogram) = &self.histogram {
+			let max = (histogram.max_count() as f32).ln_1p().max(f32::EPSILON);
+			for (bins, color) in histogram.bins.iter().zip(FILL_COLORS) {
+				for x in 0..chart_w {
+					let bin = (x as usize * BIN_COUNT / chart_w as usize).min(BIN_COUNT - 1);
+					let fill = (bins[bin] as f32).ln_1p() / max;
+					let bar_h = (fill * chart_h as f32).round() as u32;
+					for y in (chart_h - bar_h)..chart_h {
+						blend(image.get_pixel_mut(pad + x, chart_top + y), color);
+					}
+				}
+			}
+		}
+		if let Some(bin) = self.hovered {
+			let x = (bin as u32 * chart_w + chart_w / 2) / BIN_COUNT as u32;
+			for y in 0..chart_h {
+				blend(image.get_pixel_mut(pad + x, chart_top + y), MARKER_COLOR);
+			}
+		}
+
+		let lines = self.caption_lines();
+		let mut y = pad;
+		for line in &lines {
+			let (line_image, _) = render_text(line, TEXT_HEIGHT, TEXT_COLOR);
+			imageops::overlay(&mut image, &line_image, pad as i64, y as i64);
+			y += line_image.height();
+		}
+
+		let widget = self.widget.upgrade().unwrap();
+		widget.set_icon(Some(Rc::new(Picture::from_image(image))));
+		widget.set_width(Length::Fixed(panel_w));
+		widget.set_height(Length::Fixed(panel_h));
+	}
+
+	/// The name of the panel, or the counts of the hovered bin on two lines
+	fn caption_lines(&self) -> [String; 2] {
+		let (histogram, bin) = match (&self.histogram, self.hovered) {
+			(Some(histogram), Some(bin)) => (histogram, bin),
+			(None, _) => return ["Histogram".into(), "Computing...".into()],
+			_ => return ["Histogram".into(), String::new()],
+		};
+		let count =
+			|index: usize| format!("{} {}", HISTOGRAM_NAMES[index], histogram.bins[index][bin]);
+		[
+			format!("Value {}: {}", bin, count(3)),
+			format!("{}  {}  {}", count(0), count(1), count(2)),
+		]
+	}
+}
+
+/// The logical width and height of the panel, with room for two lines of text above the chart
+fn panel_size() -> (f32, f32) {
+	(CHART_WIDTH + 2.0 * PADDING, CHART_HEIGHT + 3.0 * PADDING + 2.0 * TEXT_HEIGHT * 1.4)
+}
+
+/// Draws the translucent color over the pixel
+fn blend(pixel: &mut Rgba<u8>, color: [u8; 4]) {
+	let a = color[3] as u32;
+	for (channel, value) in pixel.0.iter_mut().zip(&color[..3]) {
+		*channel = ((*value as u32 * a + *channel as u32 * (255 - a)) / 255) as u8;
+	}
+}
@@ -0,0 +1,120 @@
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+
+use gelatin::{
+	misc::{Alignment, Length, LogicalRect, LogicalVector},
+	picture::Picture,
+	tree_view::{TreeView, TreeViewRow},
+	Widget,
+};
+
+use super::text::{render_text, TEXT_COLOR};
+
+const ROW_HEIGHT: f32 = 24.0;
+const TEXT_HEIGHT: f32 = 13.0;
+/// The space kept free left and right of the names
+const PADDING: f32 = 10.0;
+/// The distance from the top left corner of the picture area
+const MARGIN: f32 = 10.0;
+const BG_COLOR: [f32; 4] = [0.1, 0.1, 0.1, 0.96];
+
+type SelectCallback = Rc<dyn Fn(String)>;
+
+/// The dropdown of `load_layout` that lists the saved layouts by name. It's closed when a
+/// layout is picked.
+pub struct LayoutMenu {
+	pub widget: Rc<TreeView>,
+	/// The area of the menu within the window while it's open
+	rect: Cell<LogicalRect>,
+	names: RefCell<Vec<String>>,
+	on_select: RefCell<Option<SelectCallback>>,
+}
+
+impl LayoutMenu {
+	pub fn new() -> Rc<Self> {
+		Rc::new_cyclic(|this: &Weak<LayoutMenu>| {
+			let widget = Rc::new(TreeView::new());
+			widget.set_ignore_layout(true);
+			widget.set_horizontal_align(Alignment::Start);
+			widget.set_vertical_align(Alignment::Start);
+			widget.set_margin_left(MARGIN);
+			widget.set_margin_top(MARGIN);
+			widget.set_row_height(ROW_HEIGHT);
+			widget.set_indent(PADDING);
+			widget.set_bg_color(BG_COLOR);
+			widget.set_visible(false);
+			let this = this.clone();
+			widget.set_on_click(move |index| {
+				if let Some(menu) = this.upgrade() {
+					menu.click(index);
+				}
+			});
+			LayoutMenu {
+				widget,
+				rect: Cell::new(LogicalRect {
+					pos: LogicalVector::new(0.0, 0.0),
+					size: LogicalVector::new(0.0, 0.0),
+				}),
+				names: RefCell::new(Vec::new()),
+				on_select: RefCell::new(None),
+			}
+		})
+	}
+
+	/// The callback gets the name of the layout that was picked, after the menu was closed
+	pub fn set_on_select<F: Fn(String) + 'static>(&self, callback: F) {
+		*self.on_select.borrow_mut() = Some(Rc::new(callback));
+	}
+
+	pub fn is_open(&self) -> bool {
+		self.widget.visible()
+	}
+
+	/// Lists the names in the top left corner of `bounds`, which is the area of the picture
+	pub fn open(&self, names: Vec<String>, bounds: LogicalRect) {
+		let mut width: f32 = 0.0;
+		let rows = names
+			.iter()
+			.map(|name| {
+				let (image, size) = render_text(name, TEXT_HEIGHT, TEXT_COLOR);
+				width = width.max(size.vec.x + 2.0 * PADDING);
+				TreeViewRow {
+					depth: 0,
+					expandable: false,
+					expanded: false,
+					label: Rc::new(Picture::from_image(image)),
+					label_size: size,
+				}
+			})
+			.collect();
+		self.widget.set_rows(rows);
+		let size = LogicalVector::new(width, names.len() as f32 * ROW_HEIGHT);
+		self.widget.set_width(Length::Fixed(size.vec.x));
+		self.widget.set_height(Length::Fixed(size.vec.y));
+		let pos = bounds.pos + LogicalVector::new(MARGIN, MARGIN);
+		self.rect.set(LogicalRect { pos, size });
+		*self.names.borrow_mut() = names;
+		self.widget.set_visible(true);
+	}
+
+	pub fn close(&self) {
+		self.widget.set_visible(false);
+	}
+
+	/// Whether the position within the window is on the open menu
+	pub fn contains(&self, pos: LogicalVector) -> bool {
+		self.is_open() && self.rect.get().contains(pos)
+	}
+
+	fn click(&self, index: usize) {
+		let name = match self.names.borrow().get(index) {
+			Some(name) => name.clone(),
+			None => return,
+		};
+		self.close();
+		let on_select = self.on_select.borrow().clone();
+		if let Some(callback) = on_select {
+			callback(name);
+		}
+	}
+}
@@ -0,0 +1,67 @@
+//! A single line of editable text with a caret, for the text inputs of the bottom bar.
+
+pub struct LineEditor {
+	text: String,
+	/// The position of the caret as a byte index into `text`
+	caret: usize,
+}
+
+impl LineEditor {
+	/// Starts editing `text` with the caret at its end
+	pub fn new(text: String) -> LineEditor {
+		let caret = text.len();
+		LineEditor { text, caret }
+	}
+
+	pub fn text(&self) -> &str {
+		&self.text
+	}
+
+	/// The text before and after the caret
+	pub fn split_at_caret(&self) -> (&str, &str) {
+		self.text.split_at(self.caret)
+	}
+
+	pub fn insert(&mut self, input: &str) {
+		// Control characters like the tab or the return are sent as text too
+		for ch in input.chars().filter(|ch| !ch.is_control()) {
+			self.text.insert(self.caret, ch);
+			self.caret += ch.len_utf8();
+		}
+	}
+
+	/// Removes the character before the caret
+	pub fn backspace(&mut self) {
+		if let Some(ch) = self.text[..self.caret].chars().next_back() {
+			self.caret -= ch.len_utf8();
+			self.text.remove(self.caret);
+		}
+	}
+
+	/// Removes the character after the caret
+	pub fn delete(&mut self) {
+		if self.caret < self.text.len() {
+			self.text.remove(self.caret);
+		}
+	}
+
+	pub fn move_left(&mut self) {
+		if let Some(ch) = self.text[..self.caret].chars().next_back() {
+			self.caret -= ch.len_utf8();
+		}
+	}
+
+	pub fn move_right(&mut self) {
+		if let Some(ch) = self.text[self.caret..].chars().next() {
+			self.caret += ch.len_utf8();
+		}
+	}
+
+	pub fn move_home(&mut self) {
+		self.caret = 0;
+	}
+
+	pub fn move_end(&mut self) {
+		self.caret = self.text.len();
+	}
+}
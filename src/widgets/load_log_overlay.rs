@@ -0,0 +1,78 @@
+use std::rc::{Rc, Weak};
+
+use gelatin::{label::Label, misc::*, Widget};
+
+use super::text::set_label_lines_boxed;
+use crate::image_cache::load_log::LoadLog;
+
+const TEXT_HEIGHT: f32 = 13.0;
+const PADDING: f32 = 8.0;
+const TEXT_COLOR: [u8; 3] = [230, 230, 230];
+const BG_COLOR: [u8; 4] = [20, 20, 20, 220];
+/// The number of entries that fit on the overlay, the others are reached by scrolling
+const VISIBLE_ENTRIES: usize = 10;
+
+/// Lists the recent loads with their timing, the most recent one first
+pub struct LoadLogOverlay {
+	pub widget: Weak<Label>,
+	/// The number of entries that are scrolled past
+	scroll: usize,
+	/// The revision of the log, the scroll position and the queue depth that are displayed
+	shown: Option<(u64, usize, usize)>,
+}
+
+impl LoadLogOverlay {
+	pub fn new(widget: &Rc<Label>) -> LoadLogOverlay {
+		widget.set_icon(None);
+		widget.set_ignore_layout(true);
+		widget.set_margin_all(8.0);
+		// The preload indicator is in the top left corner
+		widget.set_horizontal_align(Alignment::End);
+		widget.set_vertical_align(Alignment::Start);
+		widget.set_visible(false);
+
+		LoadLogOverlay { widget: Rc::downgrade(widget), scroll: 0, shown: None }
+	}
+
+	pub fn is_open(&self) -> bool {
+		self.widget.upgrade().is_some_and(|widget| widget.visible())
+	}
+
+	pub fn toggle(&mut self) {
+		let widget = self.widget.upgrade().unwrap();
+		widget.set_visible(!widget.visible());
+		self.scroll = 0;
+		self.shown = None;
+	}
+
+	/// Scrolls towards the older entries if `lines` is positive
+	pub fn scroll(&mut self, lines: isize) {
+		self.scroll = self.scroll.saturating_add_signed(lines);
+	}
+
+	/// Displays the entries of the log if they changed since they were last displayed.
+	/// `queue_depth` is the number of requests waiting for a loader thread.
+	pub fn update(&mut self, log: &LoadLog, queue_depth: usize) {
+		if !self.is_open() {
+			return;
+		}
+		let max_scroll = log.entries().len().saturating_sub(VISIBLE_ENTRIES);
+		self.scroll = self.scroll.min(max_scroll);
+		let shown = (log.revision(), self.scroll, queue_depth);
+		if self.shown == Some(shown) {
+			return;
+		}
+		self.shown = Some(shown);
+		let mut lines = vec![format!(
+			"Load log: [seconds since start] path - format - size - decode - upload ({} of {}, {} queued)",
+			log.entries().len().min(VISIBLE_ENTRIES),
+			log.entries().len(),
+			queue_depth
+		)];
+		lines.extend(
+			log.entries().rev().skip(self.scroll).take(VISIBLE_ENTRIES).map(|e| e.description()),
+		);
+		let widget = self.widget.upgrade().unwrap();
+		set_label_lines_boxed(&widget, &lines, TEXT_HEIGHT, TEXT_COLOR, BG_COLOR, PADDING);
+	}
+}
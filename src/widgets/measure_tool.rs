@@ -0,0 +1,91 @@
+use std::path::{Path, PathBuf};
+
+use gelatin::cgmath::Vector2;
+
+/// A line between two clicked points, in the pixels of the oriented image
+#[derive(Debug, Clone, Copy)]
+pub struct Measurement {
+	pub from: Vector2<f32>,
+	pub to: Vector2<f32>,
+}
+
+impl Measurement {
+	pub fn distance(&self) -> f32 {
+		let delta = self.to - self.from;
+		delta.x.hypot(delta.y)
+	}
+
+	/// The angle of the line in degrees, counter-clockwise from pointing to the right, like on
+	/// a protractor. The y axis of the image points down, so it's flipped.
+	pub fn angle(&self) -> f32 {
+		let (dx, dy) = (self.to.x - self.from.x, self.from.y - self.to.y);
+		dy.atan2(dx).to_degrees()
+	}
+
+	pub fn describe(&self) -> String {
+		format!("{:.1} px, {:.1}°", self.distance(), self.angle())
+	}
+}
+
+/// The ruler. Every second click ends a measurement, and the measurements stay on the image
+/// until they are cleared or another image is opened.
+#[derive(Default)]
+pub struct MeasureTool {
+	/// The image that the points are on, or `None` if the tool is off
+	path: Option<PathBuf>,
+	/// The first point of the measurement that's being made
+	start: Option<Vector2<f32>>,
+	measurements: Vec<Measurement>,
+}
+
+impl MeasureTool {
+	pub fn is_active(&self) -> bool {
+		self.path.is_some()
+	}
+
+	pub fn activate(&mut self, path: &Path) {
+		self.path = Some(path.to_owned());
+	}
+
+	pub fn deactivate(&mut self) {
+		self.path = None;
+		self.clear();
+	}
+
+	pub fn clear(&mut self) {
+		self.start = None;
+		self.measurements.clear();
+	}
+
+	/// Forgets the points if they were made on another image than the one at `path`
+	pub fn follow_image(&mut self, path: &Path) {
+		if self.path.as_deref().is_some_and(|measured| measured != path) {
+			self.path = Some(path.to_owned());
+			self.clear();
+		}
+	}
+
+	/// Places a point at `pos`, in image pixels. Returns the measurement if the point ended
+	/// one.
+	pub fn click(&mut self, pos: Vector2<f32>) -> Option<Measurement> {
+		match self.start.take() {
+			Some(from) => {
+				let measurement = Measurement { from, to: pos };
+				self.measurements.push(measurement);
+				Some(measurement)
+			}
+			None => {
+				self.start = Some(pos);
+				None
+			}
+		}
+	}
+
+	pub fn start(&self) -> Option<Vector2<f32>> {
+		self.start
+	}
+
+	pub fn measurements(&self) -> &[Measurement] {
+		&self.measurements
+	}
+}
@@ -1,4 +1,31 @@
+pub mod bookmarks_sidebar;
 pub mod bottom_bar;
+pub mod channel_stats_overlay;
+pub mod color_sampler;
+pub mod command_editor;
+pub mod context_menu;
 pub mod copy_notification;
+pub mod debug_overlay;
+pub mod directory_sidebar;
+pub mod empty_hint;
+pub mod error_overlay;
+pub mod exif_editor;
+pub mod gamma_panel;
+pub mod grid_panel;
 pub mod help_screen;
+pub mod histogram_panel;
+pub mod layout_menu;
+pub mod line_editor;
+pub mod load_log_overlay;
+pub mod measure_tool;
+pub mod move_editor;
 pub mod picture_widget;
+pub mod preload_indicator;
+pub mod print_panel;
+pub mod rename_editor;
+pub mod settings_panel;
+pub mod shortcuts_overlay;
+pub mod tag_editor;
+pub mod text;
+pub mod text_overlay;
+pub mod timecode_overlay;
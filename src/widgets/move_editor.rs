@@ -0,0 +1,83 @@
+//! The state of the inline text input for the folder that the current image is moved into.
+
+use std::path::{Path, PathBuf};
+
+use super::line_editor::LineEditor;
+
+/// Edits the name of a folder next to the image, or picks one of the folders that images were
+/// moved into before with the arrow keys
+pub struct MoveEditor {
+	pub image_path: PathBuf,
+	pub line: LineEditor,
+	/// The folders that images were moved into, the most recent first
+	recent: Vec<PathBuf>,
+	/// The index into `recent` of the folder that was picked last
+	selected: Option<usize>,
+}
+
+impl MoveEditor {
+	/// Starts with an empty name for moving the image at `path`
+	pub fn new(path: &Path, recent: &[PathBuf]) -> MoveEditor {
+		MoveEditor {
+			image_path: path.to_owned(),
+			line: LineEditor::new(String::new()),
+			recent: recent.to_vec(),
+			selected: None,
+		}
+	}
+
+	/// The text that's displayed in the bottom bar while editing, with a bar at the caret and
+	/// the recent folders after it, the picked one in brackets
+	pub fn display_text(&self) -> String {
+		let (before, after) = self.line.split_at_caret();
+		let mut text = format!("Move to folder: {}|{}", before, after);
+		if !self.recent.is_empty() {
+			let names: Vec<String> = self
+				.recent
+				.iter()
+				.enumerate()
+				.map(|(i, dir)| match self.selected {
+					Some(selected) if selected == i => format!("[{}]", self.name_of(dir)),
+					_ => self.name_of(dir),
+				})
+				.collect();
+			text.push_str(&format!("    Recent (Up/Down): {}", names.join(", ")));
+		}
+		text
+	}
+
+	/// Replaces the name with the next recent folder, or the previous one if `down` is false
+	pub fn select_recent(&mut self, down: bool) {
+		if self.recent.is_empty() {
+			return;
+		}
+		let count = self.recent.len();
+		let selected = match (self.selected, down) {
+			(None, true) => 0,
+			(None, false) => count - 1,
+			(Some(i), true) => (i + 1) % count,
+			(Some(i), false) => (i + count - 1) % count,
+		};
+		self.selected = Some(selected);
+		self.line = LineEditor::new(self.name_of(&self.recent[selected]));
+	}
+
+	/// The folder that the image should be moved into, or an error message if the edited name
+	/// can't be used. A relative name is a folder next to the image.
+	pub fn target_dir(&self) -> Result<PathBuf, String> {
+		let name = self.line.text().trim();
+		if name.is_empty() {
+			return Err("The folder name can't be empty".into());
+		}
+		Ok(self.image_dir().join(name))
+	}
+
+	fn image_dir(&self) -> &Path {
+		self.image_path.parent().unwrap_or(Path::new(""))
+	}
+
+	/// The folder relative to the folder of the image, or its full path if it's elsewhere
+	fn name_of(&self, dir: &Path) -> String {
+		dir.strip_prefix(self.image_dir()).unwrap_or(dir).to_string_lossy().into_owned()
+	}
+}
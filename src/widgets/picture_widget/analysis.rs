@@ -0,0 +1,276 @@
+//! Computes the channel statistics, the decoder info and the histogram of the shown image on
+//! other threads.
+
+use std::{
+	fs,
+	io::Read,
+	path::{Path, PathBuf},
+	sync::mpsc,
+	time::Duration,
+};
+
+use gelatin::image;
+
+use crate::{
+	channel_stats::{channel_stats, table_lines},
+	histogram::{histogram, Histogram},
+	image_cache::image_loader::{complex_load_image, detect_format, LoadResult, TextureColorSpace},
+	playback_manager::LoadedImgPath,
+};
+
+use super::PictureWidgetData;
+
+/// The computations that are running for the tables and the histogram panel
+#[derive(Default)]
+pub(super) struct AnalysisJobs {
+	/// Receives the table of `channel_stats` once it's computed
+	channel_stats_job: Option<mpsc::Receiver<Result<Vec<String>, String>>>,
+	/// Receives the lines of `decoder_info` once the file was decoded again
+	decoder_info_job: Option<mpsc::Receiver<Result<Vec<String>, String>>>,
+	/// The file whose histogram is shown or being computed
+	histogram_path: Option<PathBuf>,
+	histogram_job: Option<mpsc::Receiver<Result<Histogram, String>>>,
+}
+
+impl PictureWidgetData {
+	/// Shows the results of the computations. Returns true while one of them runs.
+	pub(super) fn update_analysis(&mut self) -> bool {
+		let computing_stats = self.update_channel_stats();
+		let reading_decoder_info = self.update_decoder_info();
+		let computing_histogram = self.update_histogram();
+		computing_stats || reading_decoder_info || computing_histogram
+	}
+
+	/// Computes the statistics of the color channels of the shown file on another thread, the
+	/// table is shown by `update_channel_stats`
+	pub(super) fn start_channel_stats(&mut self) {
+		if self.analysis.channel_stats_job.is_some() {
+			return;
+		}
+		let path = match self.shown_file() {
+			Some(path) => path,
+			None => return,
+		};
+		let (sender, receiver) = mpsc::channel();
+		std::thread::spawn(move || {
+			let _ = sender.send(channel_stats_table(&path));
+		});
+		self.analysis.channel_stats_job = Some(receiver);
+		self.error_overlay.show_status("Computing the channel statistics");
+	}
+
+	/// Shows the table of the channel statistics once it's done. Returns true while computing.
+	fn update_channel_stats(&mut self) -> bool {
+		let result = match &self.analysis.channel_stats_job {
+			Some(receiver) => match receiver.try_recv() {
+				Ok(result) => result,
+				Err(mpsc::TryRecvError::Empty) => return true,
+				Err(mpsc::TryRecvError::Disconnected) => {
+					Err("The channel statistics stopped unexpectedly".into())
+				}
+			},
+			None => return false,
+		};
+		self.analysis.channel_stats_job = None;
+		match (result, &self.channel_stats_overlay) {
+			(Ok(lines), Some(overlay)) => {
+				overlay.show(&lines);
+				self.render_validity.invalidate();
+			}
+			(Ok(_), None) => {}
+			(Err(e), _) => self.error_overlay.show(&e),
+		}
+		false
+	}
+
+	pub(super) fn toggle_histogram(&mut self) {
+		let panel = match &mut self.histogram_panel {
+			Some(panel) => panel,
+			None => return,
+		};
+		if panel.is_open() {
+			panel.close();
+		} else {
+			panel.open();
+		}
+		self.render_validity.invalidate();
+	}
+
+	/// Computes the histogram of the shown file on another thread whenever another file is
+	/// shown while the histogram panel is open. Returns true while computing.
+	fn update_histogram(&mut self) -> bool {
+		let panel = match &mut self.histogram_panel {
+			Some(panel) if panel.is_open() => panel,
+			_ => return false,
+		};
+		if let Some(receiver) = &self.analysis.histogram_job {
+			let result = match receiver.try_recv() {
+				Ok(result) => result,
+				Err(mpsc::TryRecvError::Empty) => return true,
+				Err(mpsc::TryRecvError::Disconnected) => {
+					Err("The histogram stopped unexpectedly".into())
+				}
+			};
+			self.analysis.histogram_job = None;
+			match result {
+				Ok(histogram) => panel.set_histogram(Some(histogram)),
+				Err(e) => self.error_overlay.show(&e),
+			}
+			self.render_validity.invalidate();
+		}
+		let path = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) if path.is_file() => path.clone(),
+			_ => return false,
+		};
+		if self.analysis.histogram_path.as_ref() == Some(&path) {
+			return false;
+		}
+		panel.set_histogram(None);
+		self.analysis.histogram_path = Some(path.clone());
+		let (sender, receiver) = mpsc::channel();
+		std::thread::spawn(move || {
+			let _ = sender.send(image_histogram(&path));
+		});
+		self.analysis.histogram_job = Some(receiver);
+		self.render_validity.invalidate();
+		true
+	}
+
+	/// Decodes the shown file again on another thread to show how its format was detected and
+	/// what decoded it. The time between `LoadResult::Start` and `LoadResult::Done` is the
+	/// decoding time of the load that's shown.
+	pub(super) fn start_decoder_info(&mut self) {
+		if self.analysis.decoder_info_job.is_some() {
+			return;
+		}
+		let path = match self.shown_file() {
+			Some(path) if path.is_file() => path,
+			_ => {
+				self.error_overlay.show("Open an image file to see how it's decoded");
+				return;
+			}
+		};
+		let decode_time = self.playback_manager.load_breakdown().map(|b| b.worker.decode);
+		let (sender, receiver) = mpsc::channel();
+		std::thread::spawn(move || {
+			let _ = sender.send(decoder_info_lines(&path, decode_time));
+		});
+		self.analysis.decoder_info_job = Some(receiver);
+	}
+
+	/// Shows the lines of `decoder_info` once they're ready. Returns true while decoding.
+	fn update_decoder_info(&mut self) -> bool {
+		let result = match &self.analysis.decoder_info_job {
+			Some(receiver) => match receiver.try_recv() {
+				Ok(result) => result,
+				Err(mpsc::TryRecvError::Empty) => return true,
+				Err(mpsc::TryRecvError::Disconnected) => {
+					Err("Decoding the image stopped unexpectedly".into())
+				}
+			},
+			None => return false,
+		};
+		self.analysis.decoder_info_job = None;
+		match (result, &self.channel_stats_overlay) {
+			(Ok(lines), Some(overlay)) => {
+				overlay.show(&lines);
+				self.render_validity.invalidate();
+			}
+			(Ok(_), None) => {}
+			(Err(e), _) => self.error_overlay.show(&e),
+		}
+		false
+	}
+}
+
+/// The table of `channel_stats` for the first frame of the image at `path`
+fn channel_stats_table(path: &Path) -> Result<Vec<String>, String> {
+	let mut first_frame = None;
+	complex_load_image(path, false, 0, |frame| {
+		if let LoadResult::Frame { image, color_space, .. } = frame {
+			first_frame.get_or_insert((image, color_space));
+		}
+		Ok(())
+	})
+	.map_err(|e| format!("Could not read the image: {}", e))?;
+	let (image, color_space) = first_frame.ok_or("The image has no frames")?;
+	let stats = channel_stats(&image).ok_or("The image has no pixels")?;
+	let encoding = match color_space {
+		TextureColorSpace::Srgb => "sRGB encoded",
+		TextureColorSpace::Linear => "linear light",
+	};
+	let name = path.file_name().unwrap_or_default().to_string_lossy();
+	let (width, height) = image.dimensions();
+	let mut lines =
+		vec![format!("{} - {}×{}, {} values", name, width, height, encoding), String::new()];
+	lines.extend(table_lines(&stats));
+	lines.push(String::new());
+	lines.push("Press any key to close".into());
+	Ok(lines)
+}
+
+/// The lines of `decoder_info` for the file at `path`. Every frame is decoded to count them.
+fn decoder_info_lines(path: &Path, decode_time: Option<Duration>) -> Result<Vec<String>, String> {
+	let format = detect_format(path).map_err(|e| format!("Could not detect the format: {}", e))?;
+	let mut header = Vec::new();
+	fs::File::open(path)
+		.and_then(|file| file.take(512).read_to_end(&mut header))
+		.map_err(|e| format!("Could not read the image: {}", e))?;
+	let guessed = match image::guess_format(&header) {
+		Ok(guessed) => format!("{:?}", guessed),
+		Err(_) => "not recognized".into(),
+	};
+	let mut frame_count = 0;
+	let mut first_frame = None;
+	complex_load_image(path, true, 0, |frame| {
+		if let LoadResult::Frame { image, .. } = frame {
+			frame_count += 1;
+			first_frame.get_or_insert(image.dimensions());
+		}
+		Ok(())
+	})
+	.map_err(|e| format!("Could not decode the image: {}", e))?;
+	let (width, height) = first_frame.ok_or("The image has no frames")?;
+
+	let extension = match path.extension() {
+		Some(extension) => format!(".{}", extension.to_string_lossy()),
+		None => "none".into(),
+	};
+	let frames = match frame_count {
+		1 => "1".to_owned(),
+		count => format!("{} (animation)", count),
+	};
+	let bytes = width as u64 * height as u64 * 4;
+	let decode_time = match decode_time {
+		Some(time) => format!("{:.1} ms", time.as_secs_f64() * 1000.0),
+		None => "still loading".into(),
+	};
+	let name = path.file_name().unwrap_or_default().to_string_lossy();
+	Ok(vec![
+		name.into_owned(),
+		String::new(),
+		format!("{:<18}{}", "Extension", extension),
+		format!("{:<18}{} ({:?})", "Detected format", format.name(), format),
+		format!("{:<18}{}", "Header", guessed),
+		format!("{:<18}{}", "Decoder", format.decoder()),
+		format!("{:<18}{}", "Frames", frames),
+		format!("{:<18}{}×{}", "RgbaImage", width, height),
+		format!("{:<18}{} bytes ({:.1} MiB)", "Pixel data", bytes, bytes as f64 / 1048576.0),
+		format!("{:<18}{}", "Start to Done", decode_time),
+		String::new(),
+		"Press any key to close".into(),
+	])
+}
+
+/// The histogram of the first frame of the image at `path`
+fn image_histogram(path: &Path) -> Result<Histogram, String> {
+	let mut first_frame = None;
+	complex_load_image(path, false, 0, |frame| {
+		if let LoadResult::Frame { image, .. } = frame {
+			first_frame.get_or_insert(image);
+		}
+		Ok(())
+	})
+	.map_err(|e| format!("Could not read the image: {}", e))?;
+	Ok(histogram(&first_frame.ok_or("The image has no frames")?))
+}
@@ -0,0 +1,73 @@
+//! Bookmarks the folder of the current image under the name typed into the bottom bar.
+
+use gelatin::winit::{
+	event::KeyEvent,
+	keyboard::{Key, ModifiersState, NamedKey},
+};
+
+use super::{super::line_editor::LineEditor, edit_line, PictureWidgetData};
+
+impl PictureWidgetData {
+	/// Turns the bottom bar into a text input for the name of a bookmark of the current folder,
+	/// which starts out as the name of the folder
+	pub(super) fn start_bookmark(&mut self) {
+		let dir = match self.playback_manager.current_dir() {
+			Some(dir) => dir.to_owned(),
+			None => {
+				self.error_overlay.show_status("Open an image to bookmark its folder");
+				return;
+			}
+		};
+		let name = dir.file_name().unwrap_or(dir.as_os_str()).to_string_lossy().into_owned();
+		let editor = LineEditor::new(name);
+		self.bottom_bar.set_rename_text(Some(&bookmark_display_text(&editor)));
+		self.bookmark_editor = Some((dir, editor));
+	}
+
+	pub(super) fn stop_bookmark_editing(&mut self) {
+		self.bookmark_editor = None;
+		self.bottom_bar.set_rename_text(None);
+		let fullscreen = self.window.upgrade().map(|w| w.fullscreen()).unwrap_or(false);
+		self.bottom_bar.set_visible_if_should_show(!fullscreen);
+	}
+
+	pub(super) fn confirm_bookmark(&mut self) {
+		let (dir, name) = match &self.bookmark_editor {
+			Some((dir, editor)) => (dir.clone(), editor.text().trim().to_owned()),
+			None => return,
+		};
+		if name.is_empty() {
+			return;
+		}
+		self.stop_bookmark_editing();
+		let sidebar = match &self.bookmarks_sidebar {
+			Some(sidebar) => sidebar,
+			None => return,
+		};
+		match sidebar.add(name.clone(), dir) {
+			Ok(()) => self.error_overlay.show_status(&format!("Bookmarked {:?}", name)),
+			Err(e) => self.error_overlay.show(&format!("Could not save the bookmark: {}", e)),
+		}
+	}
+
+	/// Edits the name according to a key press while the bookmark input is open
+	pub(super) fn handle_bookmark_key(&mut self, input: &KeyEvent, modifiers: ModifiersState) {
+		let editor = match &mut self.bookmark_editor {
+			Some((_, editor)) => editor,
+			None => return,
+		};
+		match &input.logical_key {
+			Key::Named(NamedKey::Enter) => self.confirm_bookmark(),
+			Key::Named(NamedKey::Escape) => self.stop_bookmark_editing(),
+			_ => {
+				edit_line(editor, input, modifiers);
+				self.bottom_bar.set_rename_text(Some(&bookmark_display_text(editor)));
+			}
+		}
+	}
+}
+
+fn bookmark_display_text(editor: &LineEditor) -> String {
+	let (before, after) = editor.split_at_caret();
+	format!("Bookmark name: {}|{}", before, after)
+}
@@ -0,0 +1,216 @@
+//! Runs the commands typed into the bottom bar on the current file, uploads it and opens a
+//! terminal in its folder.
+
+use std::{
+	path::{Path, PathBuf},
+	sync::mpsc,
+};
+
+use gelatin::winit::{
+	event::KeyEvent,
+	keyboard::{Key, ModifiersState, NamedKey},
+};
+
+use crate::{
+	clipboard_handler::set_clipboard_text,
+	external_command,
+	playback_manager::{LoadRequest, LoadedImgPath, PlaybackState},
+	terminal::open_terminal,
+	upload::{self, UploadTarget},
+};
+
+use super::{
+	super::{command_editor::CommandEditor, text_overlay::OverlayText},
+	edit_line, PictureWidgetData,
+};
+
+/// The command that's typed or running and the upload
+#[derive(Default)]
+pub(super) struct CommandJobs {
+	/// The command of `run_command` while it's being typed in the bottom bar
+	pub(super) command_editor: Option<CommandEditor>,
+	/// Receives the file to show once the command exited
+	command_job: Option<mpsc::Receiver<Result<PathBuf, String>>>,
+	upload_job: Option<mpsc::Receiver<Result<String, String>>>,
+}
+
+impl PictureWidgetData {
+	/// Shows the results of the command and the upload. Returns true while one of them runs.
+	pub(super) fn update_commands(&mut self) -> bool {
+		let running_command = self.update_command();
+		let uploading = self.update_upload();
+		running_command || uploading
+	}
+
+	/// Opens a terminal in the folder of the shown image, or in the current folder if no image
+	/// is shown
+	pub(super) fn open_terminal(&mut self) {
+		let folder = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) => path.parent().map(Path::to_owned),
+			_ => self.playback_manager.current_dir().map(Path::to_owned),
+		};
+		let folder = match folder {
+			Some(folder) if folder.is_dir() => folder,
+			_ => return,
+		};
+		let command = self
+			.configuration
+			.borrow()
+			.file_operations
+			.as_ref()
+			.and_then(|f| f.terminal_command.clone());
+		if let Err(e) = open_terminal(&folder, command.as_deref()) {
+			self.error_overlay.show(&e);
+		}
+	}
+
+	/// Turns the bottom bar into a text input for the command that's run on the current file.
+	/// It starts with the command that was run last.
+	pub(super) fn start_command_editing(&mut self) {
+		let path = match self.shown_file() {
+			Some(path) => path,
+			None => return,
+		};
+		let last_command = self.cache.lock().unwrap().image.last_command.clone();
+		let editor = CommandEditor::new(&path, last_command.as_deref());
+		self.bottom_bar.set_rename_text(Some(&editor.display_text()));
+		self.commands.command_editor = Some(editor);
+		if matches!(
+			self.playback_manager.playback_state(),
+			PlaybackState::Present | PlaybackState::RandomPresent
+		) {
+			self.playback_manager.pause_playback();
+		}
+	}
+
+	pub(super) fn stop_command_editing(&mut self) {
+		self.commands.command_editor = None;
+		self.bottom_bar.set_rename_text(None);
+		let fullscreen = self.window.upgrade().map(|w| w.fullscreen()).unwrap_or(false);
+		self.bottom_bar.set_visible_if_should_show(!fullscreen);
+	}
+
+	/// Runs the typed command on another thread, the result is shown by `update_command`
+	pub(super) fn confirm_command(&mut self) {
+		let editor = match &self.commands.command_editor {
+			Some(editor) => editor,
+			None => return,
+		};
+		let command = match editor.command() {
+			Ok(command) => command,
+			Err(e) => {
+				self.error_overlay.show(&e);
+				return;
+			}
+		};
+		let input = editor.image_path.clone();
+		self.cache.lock().unwrap().image.last_command = Some(command.clone());
+		self.stop_command_editing();
+		let (sender, receiver) = mpsc::channel();
+		std::thread::spawn(move || {
+			let _ = sender.send(external_command::run(&command, &input));
+		});
+		self.commands.command_job = Some(receiver);
+		self.error_overlay.show_status("Running the command");
+	}
+
+	/// Edits the command according to a key press while the command editor is open
+	pub(super) fn handle_command_key(&mut self, input: &KeyEvent, modifiers: ModifiersState) {
+		let editor = match &mut self.commands.command_editor {
+			Some(editor) => editor,
+			None => return,
+		};
+		match &input.logical_key {
+			Key::Named(NamedKey::Enter) => {
+				self.confirm_command();
+				return;
+			}
+			Key::Named(NamedKey::Escape) => {
+				self.stop_command_editing();
+				return;
+			}
+			_ => edit_line(&mut editor.line, input, modifiers),
+		}
+		self.bottom_bar.set_rename_text(Some(&editor.display_text()));
+	}
+
+	/// Shows the result of the command once it exited. Returns true while it's running.
+	fn update_command(&mut self) -> bool {
+		let result = match &self.commands.command_job {
+			Some(receiver) => match receiver.try_recv() {
+				Ok(result) => result,
+				Err(mpsc::TryRecvError::Empty) => return true,
+				Err(mpsc::TryRecvError::Disconnected) => {
+					Err("The command stopped unexpectedly".into())
+				}
+			},
+			None => return false,
+		};
+		self.commands.command_job = None;
+		match result {
+			Ok(path) => {
+				// The image may have been changed in place, which the folder has to notice
+				if let Err(e) = self.playback_manager.update_directory() {
+					eprintln!("Error while updating directory {:?}", e);
+				}
+				self.error_overlay.show_status("The command finished");
+				self.playback_manager.request_load(LoadRequest::FilePath(path));
+				self.render_validity.invalidate();
+			}
+			Err(e) => self.error_overlay.show(&e),
+		}
+		false
+	}
+
+	/// Uploads the shown file on another thread, the address is copied by `update_upload`
+	pub(super) fn start_upload(&mut self) {
+		if self.commands.upload_job.is_some() {
+			return;
+		}
+		let path = match self.shown_file() {
+			Some(path) => path,
+			None => return,
+		};
+		let settings = self.configuration.borrow().upload.clone().unwrap_or_default();
+		let url = match settings.url {
+			Some(url) => url,
+			None => {
+				self.error_overlay.show("Set the `url` of the `[upload]` section to upload images");
+				return;
+			}
+		};
+		let target = UploadTarget {
+			url,
+			token: settings.token,
+			form_field: settings.form_field,
+			url_path: settings.url_path.unwrap_or_default(),
+		};
+		let (sender, receiver) = mpsc::channel();
+		std::thread::spawn(move || {
+			let _ = sender.send(upload::upload(&target, &path));
+		});
+		self.commands.upload_job = Some(receiver);
+		self.error_overlay.show_status("Uploading the image");
+	}
+
+	/// Copies the address of the uploaded image once it's done. Returns true while uploading.
+	fn update_upload(&mut self) -> bool {
+		let result = match &self.commands.upload_job {
+			Some(receiver) => match receiver.try_recv() {
+				Ok(result) => result,
+				Err(mpsc::TryRecvError::Empty) => return true,
+				Err(mpsc::TryRecvError::Disconnected) => {
+					Err("The upload stopped unexpectedly".into())
+				}
+			},
+			None => return false,
+		};
+		self.commands.upload_job = None;
+		let copied = result.and_then(|url| set_clipboard_text(&url).map(|()| url));
+		match copied {
+			Ok(url) => self.text_overlay.show(OverlayText::confirmation(format!("Copied {}", url))),
+			Err(e) => self.error_overlay.show(&e),
+		}
+		false
+	}
+}
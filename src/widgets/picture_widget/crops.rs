@@ -0,0 +1,254 @@
+//! Stores the region of an image that's dragged over and zooms to it whenever the image is
+//! opened.
+
+use std::path::PathBuf;
+
+use gelatin::{
+	cgmath::Vector2,
+	glium::Frame,
+	winit::{event::ElementState, window::CursorIcon},
+	DrawContext,
+};
+
+use crate::{
+	crops::{Crop, CropStore},
+	playback_manager::LoadedImgPath,
+};
+
+use super::{super::text_overlay::OverlayText, PictureWidgetData, ZoomPanState};
+
+/// The region that's being dragged over with `set_crop`, in the pixels of the oriented image
+pub(super) struct CropSelection {
+	path: PathBuf,
+	/// Where the drag started, or `None` before the button is pressed
+	start: Option<Vector2<f32>>,
+	end: Vector2<f32>,
+}
+
+impl CropSelection {
+	fn crop(&self) -> Option<Crop> {
+		let start = self.start?;
+		let (left, top) = (start.x.min(self.end.x).round(), start.y.min(self.end.y).round());
+		let (right, bottom) = (start.x.max(self.end.x).round(), start.y.max(self.end.y).round());
+		if right - left < 1.0 || bottom - top < 1.0 {
+			return None;
+		}
+		let (x, y) = (left as u32, top as u32);
+		Some(Crop { x, y, w: right as u32 - x, h: bottom as u32 - y })
+	}
+}
+
+/// The stored regions and the one that's being dragged over
+#[derive(Default)]
+pub(super) struct Crops {
+	/// Only exists if the stored regions could be read
+	pub(super) crop_store: Option<CropStore>,
+	/// The image that the view was last zoomed to the stored region of, if it has one
+	cropped_path: Option<PathBuf>,
+	pub(super) crop_selection: Option<CropSelection>,
+}
+
+impl PictureWidgetData {
+	/// Lets the region of the current image be dragged over, which is shown whenever the image
+	/// is opened from then on
+	pub(super) fn start_crop_selection(&mut self) {
+		let path = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) if path.is_file() => path.clone(),
+			_ => {
+				self.error_overlay.show("Open an image file to set the region to show");
+				return;
+			}
+		};
+		if self.crops.crop_store.is_none() {
+			self.error_overlay.show("The stored regions could not be read");
+			return;
+		}
+		self.stop_color_sampler();
+		self.stop_measure_tool();
+		self.crops.crop_selection =
+			Some(CropSelection { path, start: None, end: Vector2::new(0.0, 0.0) });
+		if let Some(window) = self.window.upgrade() {
+			window.window_mut().set_cursor_icon(CursorIcon::Crosshair);
+		}
+		self.error_overlay.show_status("Drag over the region to show whenever the image is opened");
+	}
+
+	pub(super) fn stop_crop_selection(&mut self) {
+		if self.crops.crop_selection.take().is_none() {
+			return;
+		}
+		if let Some(window) = self.window.upgrade() {
+			window.window_mut().set_cursor_icon(CursorIcon::Default);
+		}
+		self.render_validity.invalidate();
+	}
+
+	/// The pixel of the oriented image under the cursor. Positions beside the image are moved
+	/// onto its edge.
+	pub(super) fn cursor_pixel(&self) -> Option<Vector2<f32>> {
+		let dpi_scale = self.window.upgrade()?.window_mut().scale_factor() as f32;
+		let texture = self.get_texture()?;
+		let (x, y) = self.cursor_on_image(dpi_scale)?;
+		let (img_w, img_h) = texture.oriented_dimensions();
+		Some(Vector2::new(x.clamp(0.0, 1.0) * img_w as f32, y.clamp(0.0, 1.0) * img_h as f32))
+	}
+
+	/// Starts, moves or ends the drag over the region
+	pub(super) fn drag_crop_selection(&mut self, state: Option<ElementState>) {
+		let pos = match self.cursor_pixel() {
+			Some(pos) => pos,
+			None => return,
+		};
+		let selection = match &mut self.crops.crop_selection {
+			Some(selection) => selection,
+			None => return,
+		};
+		match state {
+			Some(ElementState::Pressed) => selection.start = Some(pos),
+			None if selection.start.is_none() => return,
+			_ => (),
+		}
+		selection.end = pos;
+		self.render_validity.invalidate();
+		if state == Some(ElementState::Released) {
+			self.finish_crop_selection();
+		}
+	}
+
+	pub(super) fn finish_crop_selection(&mut self) {
+		let selection = match self.crops.crop_selection.take() {
+			Some(selection) => selection,
+			None => return,
+		};
+		if let Some(window) = self.window.upgrade() {
+			window.window_mut().set_cursor_icon(CursorIcon::Default);
+		}
+		let crop = match selection.crop() {
+			Some(crop) => crop,
+			None => {
+				self.error_overlay.show("The region is empty, drag over the part to show");
+				return;
+			}
+		};
+		let store = match &mut self.crops.crop_store {
+			Some(store) => store,
+			None => return,
+		};
+		if let Err(e) = store.set(&selection.path, crop) {
+			self.error_overlay.show(&format!("Could not store the region: {}", e));
+			return;
+		}
+		self.zoom_to_crop(crop);
+		self.crops.cropped_path = Some(selection.path);
+		let message = format!("Showing {}×{} px whenever the image is opened", crop.w, crop.h);
+		self.text_overlay.show(OverlayText::confirmation(message));
+	}
+
+	/// Forgets the stored region of the current image and fits the image into the widget
+	pub(super) fn clear_crop(&mut self) {
+		let path = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) => path.clone(),
+			_ => return,
+		};
+		self.stop_crop_selection();
+		let removed = match &mut self.crops.crop_store {
+			Some(store) => store.remove(&path),
+			None => return,
+		};
+		match removed {
+			Ok(true) => {
+				let stretch = self.cache.lock().unwrap().image.fit_stretches;
+				self.set_img_size_to_fit(stretch);
+				self.text_overlay.show(OverlayText::confirmation("Cleared the region"));
+			}
+			Ok(false) => self.error_overlay.show("The image has no stored region"),
+			Err(e) => self.error_overlay.show(&format!("Could not clear the region: {}", e)),
+		}
+	}
+
+	/// Zooms and pans so that the region fills the widget
+	pub(super) fn zoom_to_crop(&mut self, crop: Crop) {
+		let dpi_scale = match self.window.upgrade() {
+			Some(window) => window.window_mut().scale_factor() as f32,
+			None => return,
+		};
+		let texture = match self.get_texture() {
+			Some(texture) => texture,
+			None => return,
+		};
+		let (img_w, img_h) = texture.oriented_dimensions();
+		let view = self.drawn_bounds.size.vec * dpi_scale;
+		let zoom = (view.x / crop.w as f32).min(view.y / crop.h as f32);
+		let center_x = crop.x as f32 + crop.w as f32 * 0.5;
+		let center_y = crop.y as f32 + crop.h as f32 * 0.5;
+		let texel_to_logical = zoom / dpi_scale;
+		self.apply_zoom_pan_state(ZoomPanState {
+			zoom,
+			pan_x: (img_w as f32 * 0.5 - center_x) * texel_to_logical,
+			pan_y: (img_h as f32 * 0.5 - center_y) * texel_to_logical,
+		});
+	}
+
+	/// Starts or ends the drag over the region. Returns false if no region is being dragged
+	/// over, or if the button is pressed beside the image, because the drag may end beside the
+	/// image but it has to start on it.
+	pub(super) fn click_crop_selection(&mut self, state: ElementState) -> bool {
+		let selecting = match &self.crops.crop_selection {
+			Some(selection) => selection.start.is_some() || self.hover,
+			None => false,
+		};
+		if selecting {
+			self.drag_crop_selection(Some(state));
+		}
+		selecting
+	}
+
+	/// Zooms to the stored region of the image once it's shown, if it has one. The drag over
+	/// the region ends when another image is shown.
+	pub(super) fn update_crop(&mut self) {
+		let path = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) => path.clone(),
+			_ => return,
+		};
+		if self.crops.crop_selection.as_ref().is_some_and(|selection| selection.path != path) {
+			self.stop_crop_selection();
+		}
+		if self.crops.cropped_path.as_ref() == Some(&path) || self.get_texture().is_none() {
+			return;
+		}
+		if let Some(crop) = self.crops.crop_store.as_ref().and_then(|store| store.get(&path)) {
+			self.zoom_to_crop(crop);
+		}
+		self.crops.cropped_path = Some(path);
+	}
+}
+
+/// Draws the outline of the region that's being dragged over
+pub(super) fn draw_crop_selection(
+	data: &PictureWidgetData,
+	target: &mut Frame,
+	context: &DrawContext,
+) {
+	const LINE_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+	const OUTLINE_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+	const LINE_WIDTH: f32 = 1.5;
+	let (start, end) = match &data.crops.crop_selection {
+		Some(CropSelection { start: Some(start), end, .. }) => (*start, *end),
+		_ => return,
+	};
+	let corners = [start, Vector2::new(end.x, start.y), end, Vector2::new(start.x, end.y)];
+	let corners: Option<Vec<_>> = corners
+		.iter()
+		.map(|corner| data.image_to_logical(*corner, context.dpi_scale_factor))
+		.collect();
+	let corners = match corners {
+		Some(corners) => corners,
+		None => return,
+	};
+	for (width, color) in [(LINE_WIDTH + 2.0, OUTLINE_COLOR), (LINE_WIDTH, LINE_COLOR)] {
+		for i in 0..corners.len() {
+			let next = corners[(i + 1) % corners.len()];
+			context.draw_line(target, corners[i], next, width, color);
+		}
+	}
+}
@@ -0,0 +1,275 @@
+//! Renames the current file, moves it into another folder and edits its EXIF data from the
+//! inputs in the bottom bar.
+
+use std::{
+	fs,
+	path::{Path, PathBuf},
+};
+
+use gelatin::winit::{
+	event::KeyEvent,
+	keyboard::{Key, ModifiersState, NamedKey},
+};
+
+use crate::{
+	exif_writer,
+	file_ops::{FileOperation, OpKind},
+	playback_manager::{LoadRequest, LoadedImgPath, PlaybackState},
+};
+
+use super::{
+	super::{move_editor::MoveEditor, rename_editor::RenameEditor, text_overlay::OverlayText},
+	edit_line, PictureWidgetData, CLIPBOARD_IMAGE_PATH,
+};
+
+/// The number of folders that are offered when moving an image into a folder
+const RECENT_MOVE_DIRS: usize = 8;
+
+/// The inputs of the bottom bar that change the current file
+#[derive(Default)]
+pub(super) struct FileEditors {
+	/// The name of the current file while it's being edited in the bottom bar
+	pub(super) rename_editor: Option<RenameEditor>,
+	/// The folder that the current file is moved into while it's being typed in the bottom bar
+	pub(super) move_editor: Option<MoveEditor>,
+	/// The folders that images were moved into, the most recent first
+	recent_move_dirs: Vec<PathBuf>,
+}
+
+impl PictureWidgetData {
+	/// Turns the bottom bar into a text input for the name of the current file
+	pub(super) fn start_rename(&mut self) {
+		let path = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) if path != Path::new(CLIPBOARD_IMAGE_PATH) => path,
+			_ => return,
+		};
+		self.editors.rename_editor = RenameEditor::new(path);
+		if self.editors.rename_editor.is_some() {
+			if matches!(
+				self.playback_manager.playback_state(),
+				PlaybackState::Present | PlaybackState::RandomPresent
+			) {
+				self.playback_manager.pause_playback();
+			}
+			self.update_rename_text();
+		}
+	}
+
+	pub(super) fn update_rename_text(&self) {
+		if let Some(editor) = &self.editors.rename_editor {
+			self.bottom_bar.set_rename_text(Some(&editor.display_text()));
+		}
+	}
+
+	pub(super) fn stop_rename(&mut self) {
+		self.editors.rename_editor = None;
+		self.bottom_bar.set_rename_text(None);
+		let fullscreen = self.window.upgrade().map(|w| w.fullscreen()).unwrap_or(false);
+		self.bottom_bar.set_visible_if_should_show(!fullscreen);
+	}
+
+	/// Renames the file to the edited name. The editor stays open if the file can't be renamed,
+	/// and it never replaces an existing file.
+	pub(super) fn confirm_rename(&mut self) {
+		let editor = match &self.editors.rename_editor {
+			Some(editor) => editor,
+			None => return,
+		};
+		let original_path = editor.original_path.clone();
+		let new_path = match editor.new_path() {
+			Ok(new_path) => new_path,
+			Err(e) => {
+				self.error_overlay.show(&e);
+				return;
+			}
+		};
+		if new_path == original_path {
+			self.stop_rename();
+			return;
+		}
+		if new_path.exists() {
+			let name = new_path.file_name().unwrap_or_default().to_string_lossy();
+			self.error_overlay.show(&format!("\"{}\" already exists", name));
+			return;
+		}
+		if let Err(e) = fs::rename(&original_path, &new_path) {
+			self.error_overlay.show(&format!("Could not rename the file: {}", e));
+			return;
+		}
+		self.navigation_history.rename(&original_path, &new_path);
+		self.file_op_history.push(FileOperation::with_new_path(
+			OpKind::Rename,
+			original_path,
+			new_path.clone(),
+		));
+		self.stop_rename();
+		if let Err(e) = self.playback_manager.update_directory() {
+			eprintln!("Error while updating directory {:?}", e);
+		}
+		self.playback_manager.request_load(LoadRequest::FilePath(new_path));
+		self.render_validity.invalidate();
+	}
+
+	/// Edits the file name according to a key press while the rename editor is open
+	pub(super) fn handle_rename_key(&mut self, input: &KeyEvent, modifiers: ModifiersState) {
+		let editor = match &mut self.editors.rename_editor {
+			Some(editor) => editor,
+			None => return,
+		};
+		match &input.logical_key {
+			Key::Named(NamedKey::Enter) => {
+				self.confirm_rename();
+				return;
+			}
+			Key::Named(NamedKey::Escape) => {
+				self.stop_rename();
+				return;
+			}
+			_ => edit_line(&mut editor.line, input, modifiers),
+		}
+		self.update_rename_text();
+	}
+
+	/// Turns the bottom bar into a text input for the folder that the current file is moved
+	/// into
+	pub(super) fn start_move_editing(&mut self) {
+		let path = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) if path != Path::new(CLIPBOARD_IMAGE_PATH) => path,
+			_ => return,
+		};
+		let editor = MoveEditor::new(path, &self.editors.recent_move_dirs);
+		self.bottom_bar.set_rename_text(Some(&editor.display_text()));
+		self.editors.move_editor = Some(editor);
+		if matches!(
+			self.playback_manager.playback_state(),
+			PlaybackState::Present | PlaybackState::RandomPresent
+		) {
+			self.playback_manager.pause_playback();
+		}
+	}
+
+	pub(super) fn stop_move_editing(&mut self) {
+		self.editors.move_editor = None;
+		self.bottom_bar.set_rename_text(None);
+		let fullscreen = self.window.upgrade().map(|w| w.fullscreen()).unwrap_or(false);
+		self.bottom_bar.set_visible_if_should_show(!fullscreen);
+	}
+
+	/// Creates the folder if it doesn't exist yet and moves the file into it. The viewer stays
+	/// in the folder and shows the next image. The editor stays open if the file can't be
+	/// moved, and it never replaces an existing file.
+	pub(super) fn confirm_move(&mut self) {
+		let editor = match &self.editors.move_editor {
+			Some(editor) => editor,
+			None => return,
+		};
+		let original_path = editor.image_path.clone();
+		let dir = match editor.target_dir() {
+			Ok(dir) => dir,
+			Err(e) => {
+				self.error_overlay.show(&e);
+				return;
+			}
+		};
+		if !dir.is_dir() {
+			if let Err(e) = fs::create_dir(&dir) {
+				self.error_overlay.show(&format!("Could not create the folder: {}", e));
+				return;
+			}
+		}
+		let new_path = dir.join(original_path.file_name().unwrap_or_default());
+		if new_path.exists() {
+			let name = new_path.file_name().unwrap_or_default().to_string_lossy();
+			self.error_overlay.show(&format!("\"{}\" already exists in the folder", name));
+			return;
+		}
+		if let Err(e) = fs::rename(&original_path, &new_path) {
+			self.error_overlay.show(&format!("Could not move the file: {}", e));
+			return;
+		}
+		self.navigation_history.rename(&original_path, &new_path);
+		self.file_op_history.push(FileOperation::with_new_path(
+			OpKind::Move,
+			original_path,
+			new_path,
+		));
+		self.stop_move_editing();
+		self.editors.recent_move_dirs.retain(|recent| recent != &dir);
+		self.editors.recent_move_dirs.insert(0, dir.clone());
+		self.editors.recent_move_dirs.truncate(RECENT_MOVE_DIRS);
+		let name = dir.file_name().unwrap_or(dir.as_os_str()).to_string_lossy();
+		self.text_overlay.show(OverlayText::confirmation(format!("Moved to {}", name)));
+		if let Err(e) = self.playback_manager.update_directory() {
+			eprintln!("Error while updating directory {:?}", e);
+		}
+		self.render_validity.invalidate();
+	}
+
+	/// Edits the folder name according to a key press while the move editor is open. The up
+	/// and down arrows pick one of the recent folders.
+	pub(super) fn handle_move_key(&mut self, input: &KeyEvent, modifiers: ModifiersState) {
+		let editor = match &mut self.editors.move_editor {
+			Some(editor) => editor,
+			None => return,
+		};
+		match &input.logical_key {
+			Key::Named(NamedKey::Enter) => {
+				self.confirm_move();
+				return;
+			}
+			Key::Named(NamedKey::Escape) => {
+				self.stop_move_editing();
+				return;
+			}
+			Key::Named(NamedKey::ArrowDown) => editor.select_recent(true),
+			Key::Named(NamedKey::ArrowUp) => editor.select_recent(false),
+			_ => edit_line(&mut editor.line, input, modifiers),
+		}
+		self.bottom_bar.set_rename_text(Some(&editor.display_text()));
+	}
+
+	/// Opens the EXIF editor for the current image
+	pub(super) fn start_exif_editing(&mut self) {
+		let path = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) if path != Path::new(CLIPBOARD_IMAGE_PATH) => path,
+			_ => return,
+		};
+		if let Err(e) = self.exif_editor.open(path) {
+			self.error_overlay.show(&e);
+			return;
+		}
+		if matches!(
+			self.playback_manager.playback_state(),
+			PlaybackState::Present | PlaybackState::RandomPresent
+		) {
+			self.playback_manager.pause_playback();
+		}
+	}
+
+	/// Edits the focused tag according to a key press while the EXIF editor is open
+	pub(super) fn handle_exif_key(&mut self, input: &KeyEvent, modifiers: ModifiersState) {
+		match &input.logical_key {
+			Key::Named(NamedKey::Enter) => match self.exif_editor.save() {
+				Ok(path) => {
+					let backup = exif_writer::backup_path(&path);
+					let backup_name = backup.file_name().unwrap_or_default().to_string_lossy();
+					self.error_overlay.show_status(&format!(
+						"Saved the EXIF data. The original file is {}",
+						backup_name
+					));
+				}
+				Err(e) => self.error_overlay.show(&e),
+			},
+			Key::Named(NamedKey::Escape) => self.exif_editor.close(),
+			Key::Named(NamedKey::Tab) => self.exif_editor.move_focus(!modifiers.shift_key()),
+			Key::Named(NamedKey::ArrowDown) => self.exif_editor.move_focus(true),
+			Key::Named(NamedKey::ArrowUp) => self.exif_editor.move_focus(false),
+			_ => {
+				if let Some(line) = self.exif_editor.focused_line() {
+					edit_line(line, input, modifiers);
+				}
+			}
+		}
+		self.exif_editor.redraw();
+	}
+}
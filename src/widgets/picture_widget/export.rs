@@ -0,0 +1,401 @@
+//! Writes the PDFs, the TIFFs, the watermarked copies and the prints on other threads, and
+//! shows their progress.
+
+use std::{
+	path::{Path, PathBuf},
+	sync::{mpsc, Arc},
+};
+
+use gelatin::image::RgbaImage;
+
+use crate::{
+	file_dialog,
+	image_cache::image_loader::{complex_load_image, LoadResult},
+	pdf_writer::{self, PageLayout, PdfSummary, PrintLayout},
+	printing, temp_file,
+	tiff_writer::{self, TiffSummary},
+	watermark::{self, WatermarkLayout, WatermarkMargin},
+	widgets::error_overlay::ErrorOverlay,
+};
+
+use super::PictureWidgetData;
+
+/// The space around the images on the pages of a PDF if it's not set in the configuration
+const DEFAULT_PDF_MARGIN_MM: f32 = 10.0;
+const DEFAULT_PRINT_DPI: u32 = 300;
+/// The distance of the watermark from the edges of the image in pixels
+const DEFAULT_WATERMARK_MARGIN: u32 = 16;
+
+enum PdfProgress {
+	/// The number of images that were written and the number of all images
+	Pages(usize, usize),
+	Finished(Result<(PathBuf, PdfSummary), String>),
+}
+
+enum TiffProgress {
+	/// The number of images that were written and the number of all images
+	Pages(usize, usize),
+	/// `None` if no file was picked
+	Finished(Result<Option<(PathBuf, TiffSummary)>, String>),
+}
+
+/// The files that are being written. There's at most one of every kind at a time.
+#[derive(Default)]
+pub(super) struct ExportJobs {
+	/// Reports the progress of the PDF that's being written, if there's one
+	pdf_progress: Option<mpsc::Receiver<PdfProgress>>,
+	tiff_progress: Option<mpsc::Receiver<TiffProgress>>,
+	/// The decoded watermark of `export_watermarked`, loaded again when its path changes
+	watermark: Option<(PathBuf, Arc<RgbaImage>)>,
+	/// Receives the saved file once the watermarked copy is written, `None` if the user
+	/// didn't pick a file
+	watermark_job: Option<mpsc::Receiver<Result<Option<PathBuf>, String>>>,
+	/// Receives the result of `print_image` once the image was sent to the printer
+	print_job: Option<mpsc::Receiver<Result<(), String>>>,
+}
+
+impl ExportJobs {
+	/// Shows the progress and the results of the jobs. Returns true while one of them runs.
+	pub(super) fn update(&mut self, error_overlay: &mut ErrorOverlay) -> bool {
+		let writing_pdf = self.update_pdf_progress(error_overlay);
+		let writing_tiff = self.update_tiff_progress(error_overlay);
+		let watermarking = self.update_watermark_export(error_overlay);
+		let printing = self.update_print(error_overlay);
+		writing_pdf || writing_tiff || watermarking || printing
+	}
+
+	/// Shows the progress of the PDF. Returns true while it's being written.
+	fn update_pdf_progress(&mut self, error_overlay: &mut ErrorOverlay) -> bool {
+		let receiver = match &self.pdf_progress {
+			Some(receiver) => receiver,
+			None => return false,
+		};
+		let mut finished = None;
+		let mut pages = None;
+		loop {
+			match receiver.try_recv() {
+				Ok(PdfProgress::Pages(done, total)) => pages = Some((done, total)),
+				Ok(PdfProgress::Finished(result)) => finished = Some(result),
+				Err(mpsc::TryRecvError::Empty) => break,
+				Err(mpsc::TryRecvError::Disconnected) => {
+					if finished.is_none() {
+						finished = Some(Err("Writing the PDF stopped unexpectedly".into()));
+					}
+					break;
+				}
+			}
+		}
+		match finished {
+			Some(Ok((target, PdfSummary { pages, skipped }))) => {
+				let name = target.file_name().unwrap_or_default().to_string_lossy();
+				let mut message = format!("Saved {} pages to {}", pages, name);
+				if skipped > 0 {
+					message.push_str(&format!(", {} images could not be read", skipped));
+				}
+				error_overlay.show_status(&message);
+			}
+			Some(Err(e)) => error_overlay.show(&e),
+			None => {
+				if let Some((done, total)) = pages {
+					error_overlay.show_status(&format!("Writing the PDF: {} of {}", done, total));
+				}
+				return true;
+			}
+		}
+		self.pdf_progress = None;
+		false
+	}
+
+	/// Shows whether the image made it to the printer. Returns true while it's being sent.
+	fn update_print(&mut self, error_overlay: &mut ErrorOverlay) -> bool {
+		let result = match &self.print_job {
+			Some(receiver) => match receiver.try_recv() {
+				Ok(result) => result,
+				Err(mpsc::TryRecvError::Empty) => return true,
+				Err(mpsc::TryRecvError::Disconnected) => {
+					Err("Printing stopped unexpectedly".into())
+				}
+			},
+			None => return false,
+		};
+		self.print_job = None;
+		match result {
+			Ok(()) => error_overlay.show_status("Sent the image to the printer"),
+			Err(e) => error_overlay.show(&e),
+		}
+		false
+	}
+
+	/// Shows the progress of the TIFF. Returns true while the file is picked or written.
+	fn update_tiff_progress(&mut self, error_overlay: &mut ErrorOverlay) -> bool {
+		let receiver = match &self.tiff_progress {
+			Some(receiver) => receiver,
+			None => return false,
+		};
+		let mut finished = None;
+		let mut pages = None;
+		loop {
+			match receiver.try_recv() {
+				Ok(TiffProgress::Pages(done, total)) => pages = Some((done, total)),
+				Ok(TiffProgress::Finished(result)) => finished = Some(result),
+				Err(mpsc::TryRecvError::Empty) => break,
+				Err(mpsc::TryRecvError::Disconnected) => {
+					if finished.is_none() {
+						finished = Some(Err("Writing the TIFF stopped unexpectedly".into()));
+					}
+					break;
+				}
+			}
+		}
+		match finished {
+			Some(Ok(Some((target, TiffSummary { pages, skipped })))) => {
+				let name = target.file_name().unwrap_or_default().to_string_lossy();
+				let mut message = format!("Saved {} pages to {}", pages, name);
+				if skipped > 0 {
+					message.push_str(&format!(", {} images could not be read", skipped));
+				}
+				error_overlay.show_status(&message);
+			}
+			Some(Ok(None)) => (),
+			Some(Err(e)) => error_overlay.show(&e),
+			None => {
+				if let Some((done, total)) = pages {
+					error_overlay.show_status(&format!("Writing page {}/{}", done, total));
+				}
+				return true;
+			}
+		}
+		self.tiff_progress = None;
+		false
+	}
+
+	/// Shows where the watermarked image was saved. Returns true while the file is picked or
+	/// written.
+	fn update_watermark_export(&mut self, error_overlay: &mut ErrorOverlay) -> bool {
+		let result = match &self.watermark_job {
+			Some(receiver) => match receiver.try_recv() {
+				Ok(result) => result,
+				Err(mpsc::TryRecvError::Empty) => return true,
+				Err(mpsc::TryRecvError::Disconnected) => {
+					Err("Saving the watermarked image stopped unexpectedly".into())
+				}
+			},
+			None => return false,
+		};
+		self.watermark_job = None;
+		match result {
+			Ok(Some(target)) => {
+				let name = target.file_name().unwrap_or_default().to_string_lossy();
+				error_overlay.show_status(&format!("Saved {}", name));
+			}
+			Ok(None) => (),
+			Err(e) => error_overlay.show(&e),
+		}
+		false
+	}
+}
+
+impl PictureWidgetData {
+	/// Writes every image of the folder into a PDF next to the images, on a separate thread
+	pub(super) fn start_pdf_export(&mut self) {
+		if self.export.pdf_progress.is_some() {
+			self.error_overlay.show_status("A PDF is already being written");
+			return;
+		}
+		let images = match self.playback_manager.image_paths() {
+			Some(images) if !images.is_empty() => images,
+			Some(_) => return,
+			None => {
+				self.error_overlay.show_status("The folder is still being read");
+				return;
+			}
+		};
+		let folder = match images[0].parent() {
+			Some(folder) => folder.to_owned(),
+			None => return,
+		};
+		let layout = {
+			let config = self.configuration.borrow();
+			let pdf = config.pdf.as_ref();
+			PageLayout {
+				paper: pdf.and_then(|p| p.paper).unwrap_or_default(),
+				margin_mm: pdf.and_then(|p| p.margin_mm).unwrap_or(DEFAULT_PDF_MARGIN_MM),
+			}
+		};
+		let target = pdf_writer::pdf_path(&folder);
+		let (sender, receiver) = mpsc::channel();
+		std::thread::spawn(move || {
+			let total = images.len();
+			let result = pdf_writer::write_pdf(&images, &target, layout, |done| {
+				let _ = sender.send(PdfProgress::Pages(done, total));
+			});
+			let _ = sender.send(PdfProgress::Finished(result.map(|summary| (target, summary))));
+		});
+		self.export.pdf_progress = Some(receiver);
+		self.error_overlay.show_status("Writing the PDF");
+	}
+
+	/// The layout of the print panel when it's opened for the first time. The paper and the
+	/// margin are the ones of the PDFs.
+	pub(super) fn default_print_layout(&self) -> PrintLayout {
+		let config = self.configuration.borrow();
+		let pdf = config.pdf.as_ref();
+		PrintLayout {
+			paper: Some(pdf.and_then(|p| p.paper).unwrap_or_default()),
+			landscape: false,
+			dpi: DEFAULT_PRINT_DPI,
+			actual_size: false,
+			margin_mm: pdf.and_then(|p| p.margin_mm).unwrap_or(DEFAULT_PDF_MARGIN_MM),
+		}
+	}
+
+	/// Writes the shown image into a temporary PDF and sends it to the printer, on a separate
+	/// thread
+	pub(super) fn start_print(&mut self, layout: PrintLayout) {
+		if self.export.print_job.is_some() {
+			self.error_overlay.show_status("An image is already being printed");
+			return;
+		}
+		let path = match self.shown_file() {
+			Some(path) => path,
+			None => return,
+		};
+		let (sender, receiver) = mpsc::channel();
+		std::thread::spawn(move || {
+			let result = temp_file::create("emulsion-print", "pdf")
+				.map_err(|e| format!("Could not create the file to print: {}", e))
+				.and_then(|target| {
+					pdf_writer::write_print_pdf(&path, &target, layout)?;
+					printing::print_pdf(&target)
+				});
+			let _ = sender.send(result);
+		});
+		self.export.print_job = Some(receiver);
+		self.error_overlay.show_status("Printing");
+	}
+
+	/// Asks where to save the TIFF and writes the images of the folder into it, on a separate
+	/// thread
+	pub(super) fn start_tiff_export(&mut self) {
+		if self.export.tiff_progress.is_some() {
+			self.error_overlay.show_status("A TIFF is already being written");
+			return;
+		}
+		let images = match self.playback_manager.image_paths() {
+			Some(images) if !images.is_empty() => images,
+			Some(_) => return,
+			None => {
+				self.error_overlay.show_status("The folder is still being read");
+				return;
+			}
+		};
+		let folder = match images[0].parent() {
+			Some(folder) => folder.to_owned(),
+			None => return,
+		};
+		let (sender, receiver) = mpsc::channel();
+		std::thread::spawn(move || {
+			let file_name = tiff_writer::tiff_file_name(&folder);
+			let target = match file_dialog::pick_save_path(&folder, &file_name) {
+				Ok(Some(target)) => target,
+				result => {
+					let _ = sender.send(TiffProgress::Finished(result.map(|_| None)));
+					return;
+				}
+			};
+			let total = images.len();
+			let result = tiff_writer::write_tiff(&images, &target, |done| {
+				let _ = sender.send(TiffProgress::Pages(done, total));
+			});
+			let result = result.map(|summary| Some((target, summary)));
+			let _ = sender.send(TiffProgress::Finished(result));
+		});
+		self.export.tiff_progress = Some(receiver);
+	}
+
+	/// Asks where to save the copy of the shown image with the watermark and writes it, on a
+	/// separate thread
+	pub(super) fn start_watermark_export(&mut self) {
+		if self.export.watermark_job.is_some() {
+			self.error_overlay.show_status("A watermarked image is already being saved");
+			return;
+		}
+		let path = match self.shown_file() {
+			Some(path) if path.is_file() => path,
+			_ => {
+				self.error_overlay.show("Open an image file to add the watermark to it");
+				return;
+			}
+		};
+		let (watermark_path, layout) = {
+			let config = self.configuration.borrow();
+			let section = config.watermark.clone().unwrap_or_default();
+			let margin = match section.margin_percent {
+				Some(percent) => WatermarkMargin::Percent(percent),
+				None => {
+					WatermarkMargin::Pixels(section.margin_px.unwrap_or(DEFAULT_WATERMARK_MARGIN))
+				}
+			};
+			let layout = WatermarkLayout {
+				corner: section.corner.unwrap_or_default(),
+				alpha: section.alpha.unwrap_or(1.0),
+				margin,
+			};
+			(section.path, layout)
+		};
+		let watermark_path = match watermark_path {
+			Some(watermark_path) => watermark_path,
+			None => {
+				self.error_overlay.show("Set the path of the watermark in the [watermark] section");
+				return;
+			}
+		};
+		// The watermark is usually the same for every export, so it's only decoded once
+		let watermark = match &self.export.watermark {
+			Some((cached_path, watermark)) if *cached_path == watermark_path => watermark.clone(),
+			_ => match watermark::load_watermark(&watermark_path) {
+				Ok(watermark) => {
+					let watermark = Arc::new(watermark);
+					self.export.watermark = Some((watermark_path, watermark.clone()));
+					watermark
+				}
+				Err(e) => {
+					self.error_overlay.show(&e);
+					return;
+				}
+			},
+		};
+		let (sender, receiver) = mpsc::channel();
+		std::thread::spawn(move || {
+			let _ = sender.send(save_watermarked(&path, &watermark, &layout));
+		});
+		self.export.watermark_job = Some(receiver);
+	}
+}
+
+/// Asks where to save the copy of the image at `path`, and saves it with the watermark drawn
+/// over its first frame. Returns `None` if no file was picked.
+fn save_watermarked(
+	path: &Path,
+	watermark: &RgbaImage,
+	layout: &WatermarkLayout,
+) -> Result<Option<PathBuf>, String> {
+	let folder = path.parent().unwrap_or(Path::new("."));
+	let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+	let target = match file_dialog::pick_save_path(folder, &format!("{}-watermarked.png", stem))? {
+		Some(target) => target,
+		None => return Ok(None),
+	};
+	let mut first_frame = None;
+	complex_load_image(path, false, 0, |frame| {
+		if let LoadResult::Frame { image, orientation, .. } = frame {
+			first_frame.get_or_insert_with(|| orientation.apply(image));
+		}
+		Ok(())
+	})
+	.map_err(|e| format!("Could not read the image: {}", e))?;
+	let mut image = first_frame.ok_or("The image has no frames")?;
+	watermark::apply_watermark(&mut image, watermark, layout);
+	watermark::save_image(image, &target)?;
+	Ok(Some(target))
+}
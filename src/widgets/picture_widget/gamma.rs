@@ -0,0 +1,45 @@
+//! Adjusts the gamma correction that the shader applies to the image.
+
+use crate::configuration::Configuration;
+
+use super::PictureWidgetData;
+
+/// How much the gamma correction changes with each key press
+pub(super) const GAMMA_STEP: f32 = 0.05;
+const MIN_GAMMA: f32 = 0.05;
+const MAX_GAMMA: f32 = 5.0;
+
+impl PictureWidgetData {
+	/// Changes the exponent of the gamma correction and shows it in the bottom bar
+	pub(super) fn adjust_gamma(&mut self, delta: f32) {
+		// Rounding keeps the steps from drifting away from the multiples of the step
+		let gamma = ((self.gamma + delta) / GAMMA_STEP).round() * GAMMA_STEP;
+		self.set_gamma(gamma.clamp(MIN_GAMMA, MAX_GAMMA));
+	}
+
+	/// Only the uniform of the shader changes, the textures stay as they are
+	pub(super) fn set_gamma(&mut self, gamma: f32) {
+		self.gamma = gamma;
+		self.bottom_bar.set_gamma(gamma);
+		if let Some(panel) = &self.gamma_panel {
+			panel.show_gamma(gamma);
+		}
+		self.render_validity.invalidate();
+	}
+
+	pub(super) fn toggle_gamma_panel(&mut self) {
+		if let Some(panel) = &self.gamma_panel {
+			if panel.is_open() {
+				panel.close();
+			} else {
+				panel.open(self.gamma);
+			}
+			self.render_validity.invalidate();
+		}
+	}
+}
+
+pub(super) fn gamma_correction(configuration: &Configuration) -> f32 {
+	let gamma = configuration.image.as_ref().and_then(|i| i.gamma_correction).unwrap_or(1.0);
+	gamma.clamp(MIN_GAMMA, MAX_GAMMA)
+}
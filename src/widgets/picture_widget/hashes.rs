@@ -0,0 +1,119 @@
+//! Hashes the opened files on another thread and compares the hashes with the stored ones.
+
+use std::{
+	io,
+	path::{Path, PathBuf},
+	sync::mpsc,
+};
+
+use crate::{
+	integrity::{self, HashStore, Integrity},
+	playback_manager::LoadedImgPath,
+	widgets::text_overlay::{OverlayPosition, OverlayText},
+};
+
+use super::{PictureWidgetData, CLIPBOARD_IMAGE_PATH, SEARCH_RESULTS_PATH};
+
+/// How long the hash of a file is shown
+const HASH_OVERLAY_MS: u32 = 6000;
+
+/// The hashes of the opened files, see `toggle_integrity`
+#[derive(Default)]
+pub(super) struct FileHashes {
+	/// Whether the SHA-256 hash of every opened file is computed and shown
+	show_hashes: bool,
+	/// The file that's being hashed on another thread and the receiver of its hash
+	hash_job: Option<(PathBuf, mpsc::Receiver<io::Result<String>>)>,
+	/// The last file that was hashed, so that it's only hashed once while it's shown
+	hashed_path: Option<PathBuf>,
+	/// Only exists if the stored hashes could be read
+	pub(super) hash_store: Option<HashStore>,
+}
+
+impl PictureWidgetData {
+	/// Starts or stops hashing the opened files. The current file is hashed by the next
+	/// `update_integrity`.
+	pub(super) fn toggle_integrity(&mut self) {
+		self.hashes.show_hashes = !self.hashes.show_hashes;
+		if !self.hashes.show_hashes {
+			self.hashes.hash_job = None;
+			self.hashes.hashed_path = None;
+			self.text_overlay.show(OverlayText::confirmation("File hashes off"));
+		}
+	}
+
+	/// Hashes the shown file on another thread when it wasn't hashed yet, and shows the hash
+	/// once it's done. Returns true while a file is being hashed.
+	pub(super) fn update_integrity(&mut self) -> bool {
+		if !self.hashes.show_hashes {
+			return false;
+		}
+		if let LoadedImgPath::Loaded(path) = self.playback_manager.shown_file_path() {
+			let is_file =
+				path != Path::new(CLIPBOARD_IMAGE_PATH) && path != Path::new(SEARCH_RESULTS_PATH);
+			if is_file && self.hashes.hashed_path.as_ref() != Some(path) {
+				let path = path.clone();
+				let (sender, receiver) = mpsc::channel();
+				let hashed = path.clone();
+				std::thread::spawn(move || {
+					let _ = sender.send(integrity::hash_file(&hashed));
+				});
+				// A file that's still being hashed is replaced by the new one
+				self.hashes.hash_job = Some((path.clone(), receiver));
+				self.hashes.hashed_path = Some(path);
+			}
+		}
+		let result = match &self.hashes.hash_job {
+			Some((_, receiver)) => match receiver.try_recv() {
+				Ok(result) => result,
+				Err(mpsc::TryRecvError::Empty) => return true,
+				Err(mpsc::TryRecvError::Disconnected) => {
+					Err(io::Error::other("the hashing stopped unexpectedly"))
+				}
+			},
+			None => return false,
+		};
+		let (path, _) = self.hashes.hash_job.take().unwrap();
+		self.show_integrity(&path, result);
+		false
+	}
+
+	/// Shows the hash along with how it compares with the stored one. A hash is stored for
+	/// files that have none if the configuration says so.
+	pub(super) fn show_integrity(&mut self, path: &Path, hash: io::Result<String>) {
+		let hash = match hash {
+			Ok(hash) => hash,
+			Err(e) => {
+				self.error_overlay.show(&format!("Could not hash the file: {}", e));
+				return;
+			}
+		};
+		let store_hashes = {
+			let config = self.configuration.borrow();
+			config.image.as_ref().and_then(|s| s.store_file_hashes).unwrap_or(false)
+		};
+		let status = match &mut self.hashes.hash_store {
+			Some(store) => match store.check(path, &hash) {
+				Integrity::Ok => "File integrity: OK".to_owned(),
+				Integrity::Mismatch => {
+					self.error_overlay.show(&format!(
+						"WARNING: Hash does not match stored value (SHA-256 {})",
+						hash
+					));
+					return;
+				}
+				Integrity::Unknown if store_hashes => match store.store(path, hash.clone()) {
+					Ok(()) => "Hash stored".to_owned(),
+					Err(e) => format!("Could not store the hash: {}", e),
+				},
+				Integrity::Unknown => "No stored hash".to_owned(),
+			},
+			None => "The stored hashes could not be read".to_owned(),
+		};
+		self.text_overlay.show(OverlayText {
+			text: format!("SHA-256 {} · {}", hash, status),
+			duration_ms: HASH_OVERLAY_MS,
+			position: OverlayPosition::Top,
+		});
+	}
+}
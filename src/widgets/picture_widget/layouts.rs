@@ -0,0 +1,97 @@
+//! Saves the open panels as named layouts and opens them again.
+
+use gelatin::winit::{
+	event::KeyEvent,
+	keyboard::{Key, ModifiersState, NamedKey},
+};
+
+use crate::configuration::WindowLayout;
+
+use super::{super::line_editor::LineEditor, edit_line, PictureWidgetData};
+
+impl PictureWidgetData {
+	/// The panels that are shown now
+	pub(super) fn current_layout(&self) -> WindowLayout {
+		WindowLayout {
+			directory_sidebar: self.directory_sidebar.as_ref().is_some_and(|s| s.is_open()),
+			bookmarks_sidebar: self.bookmarks_sidebar.as_ref().is_some_and(|s| s.is_open()),
+			bottom_bar: self.bottom_bar.should_show(),
+			histogram: self.histogram_panel.as_ref().is_some_and(|p| p.is_open()),
+		}
+	}
+
+	/// Opens and closes the panels to match the layout. The presentation mode is left first,
+	/// because it hides the panels until then.
+	pub(super) fn apply_layout(&mut self, layout: WindowLayout) {
+		self.stop_presentation();
+		let current = self.current_layout();
+		if layout.directory_sidebar != current.directory_sidebar {
+			if let Some(sidebar) = &self.directory_sidebar {
+				sidebar.toggle();
+			}
+		}
+		if layout.bookmarks_sidebar != current.bookmarks_sidebar {
+			if let Some(sidebar) = &self.bookmarks_sidebar {
+				sidebar.toggle();
+			}
+		}
+		self.bottom_bar.set_should_show(layout.bottom_bar);
+		let fullscreen = self.window.upgrade().map(|w| w.fullscreen()).unwrap_or(false);
+		self.bottom_bar.set_visible_if_should_show(!fullscreen);
+		if layout.histogram != current.histogram {
+			self.toggle_histogram();
+		}
+		self.render_validity.invalidate();
+	}
+
+	/// Turns the bottom bar into a text input for the name that the current layout is saved
+	/// under
+	pub(super) fn start_layout_saving(&mut self) {
+		let editor = LineEditor::new(String::new());
+		self.bottom_bar.set_rename_text(Some(&layout_display_text(&editor)));
+		self.layout_editor = Some(editor);
+	}
+
+	pub(super) fn stop_layout_editing(&mut self) {
+		self.layout_editor = None;
+		self.bottom_bar.set_rename_text(None);
+		let fullscreen = self.window.upgrade().map(|w| w.fullscreen()).unwrap_or(false);
+		self.bottom_bar.set_visible_if_should_show(!fullscreen);
+	}
+
+	/// Saves the layout in the cache, replacing the one with the same name
+	pub(super) fn confirm_layout(&mut self) {
+		let name = match &self.layout_editor {
+			Some(editor) => editor.text().trim().to_owned(),
+			None => return,
+		};
+		if name.is_empty() {
+			return;
+		}
+		self.stop_layout_editing();
+		let layout = self.current_layout();
+		self.cache.lock().unwrap().layouts.insert(name.clone(), layout);
+		self.error_overlay.show_status(&format!("Saved the layout {:?}", name));
+	}
+
+	/// Edits the name according to a key press while the layout input is open
+	pub(super) fn handle_layout_key(&mut self, input: &KeyEvent, modifiers: ModifiersState) {
+		let editor = match &mut self.layout_editor {
+			Some(editor) => editor,
+			None => return,
+		};
+		match &input.logical_key {
+			Key::Named(NamedKey::Enter) => self.confirm_layout(),
+			Key::Named(NamedKey::Escape) => self.stop_layout_editing(),
+			_ => {
+				edit_line(editor, input, modifiers);
+				self.bottom_bar.set_rename_text(Some(&layout_display_text(editor)));
+			}
+		}
+	}
+}
+
+fn layout_display_text(editor: &LineEditor) -> String {
+	let (before, after) = editor.split_at_caret();
+	format!("Layout name: {}|{}", before, after)
+}
@@ -0,0 +1,78 @@
+//! Opens and closes the context menu and the menu of the saved layouts.
+
+use std::rc::Rc;
+
+use gelatin::{misc::LogicalVector, winit::event::ElementState};
+
+use crate::playback_manager::LoadedImgPath;
+
+use super::{
+	super::{context_menu::ContextMenu, layout_menu::LayoutMenu},
+	PictureWidgetData,
+};
+
+/// The menus are drawn by their own widgets, the picture only opens and closes them
+#[derive(Default)]
+pub(super) struct Menus {
+	pub(super) context_menu: Option<Rc<ContextMenu>>,
+	pub(super) layout_menu: Option<Rc<LayoutMenu>>,
+}
+
+impl PictureWidgetData {
+	/// Opens the context menu at the cursor if an image is shown
+	pub(super) fn open_context_menu(&mut self, cursor: LogicalVector) {
+		let shown = matches!(self.playback_manager.shown_file_path(), LoadedImgPath::Loaded(_));
+		if let (Some(menu), true) = (&self.menus.context_menu, shown) {
+			menu.open_at(cursor, self.drawn_bounds);
+		}
+	}
+
+	/// Lists the saved layouts in the top left corner of the picture
+	pub(super) fn open_layout_menu(&mut self) {
+		let menu = match &self.menus.layout_menu {
+			Some(menu) => menu,
+			None => return,
+		};
+		let names: Vec<String> = self.cache.lock().unwrap().layouts.keys().cloned().collect();
+		if names.is_empty() {
+			self.error_overlay.show_status("No layouts were saved yet");
+			return;
+		}
+		self.close_context_menu();
+		menu.open(names, self.drawn_bounds);
+		self.render_validity.invalidate();
+	}
+
+	/// Closes the context menu and the layout menu. Returns true if one of them was open.
+	pub(super) fn close_context_menu(&self) -> bool {
+		let mut was_open = false;
+		if let Some(menu) = self.menus.context_menu.as_ref().filter(|m| m.is_open()) {
+			menu.close();
+			was_open = true;
+		}
+		if let Some(menu) = self.menus.layout_menu.as_ref().filter(|m| m.is_open()) {
+			menu.close();
+			was_open = true;
+		}
+		was_open
+	}
+
+	/// Closes the open menu when the left button is pressed outside of it. Returns true if a
+	/// menu is open, because the clicks on the menu are handled by the menu itself.
+	pub(super) fn click_open_menu(&self, state: ElementState, cursor: LogicalVector) -> bool {
+		let pressed = state == ElementState::Pressed;
+		if let Some(menu) = self.menus.context_menu.as_ref().filter(|m| m.is_open()) {
+			if pressed && !menu.contains(cursor) {
+				menu.close();
+			}
+			return true;
+		}
+		if let Some(menu) = self.menus.layout_menu.as_ref().filter(|m| m.is_open()) {
+			if pressed && !menu.contains(cursor) {
+				menu.close();
+			}
+			return true;
+		}
+		false
+	}
+}
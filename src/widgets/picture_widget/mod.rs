@@ -0,0 +1,3389 @@
+use std::{
+	cell::RefCell,
+	fs,
+	path::{Path, PathBuf},
+	process::Command,
+	rc::{Rc, Weak},
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use gelatin::{
+	cgmath::{InnerSpace, Matrix4, Vector2, Vector3},
+	glium::{
+		texture::RawImage2d, uniform, uniforms::MagnifySamplerFilter, Blend, Frame, Program,
+		Surface,
+	},
+	image::{imageops, ImageFormat, RgbaImage},
+	shaders::ShaderDescriptor,
+	winit::{
+		dpi::PhysicalSize,
+		event::{ElementState, KeyEvent, MouseButton},
+		keyboard::{Key, NamedKey},
+		platform::modifier_supplement::KeyEventExtModifierSupplement,
+	},
+};
+
+use gelatin::{
+	add_common_widget_functions,
+	application::request_exit,
+	misc::{Alignment, Length, LogicalRect, LogicalVector, WidgetPlacement},
+	window::{RenderValidity, Window},
+	winit::{keyboard::ModifiersState, window::CursorIcon},
+	Display, DrawContext, Event, EventKind, NextUpdate, Widget, WidgetData, WidgetError,
+};
+
+use crate::{
+	clipboard_handler::{get_clipboard_image, set_clipboard_text, ClipboardHandler},
+	color_profile::color_profile_name,
+	configuration::{
+		Antialias, Cache, ConfigGridSection, Configuration, FitMode, InterpolationMode,
+		ScaleQuality,
+	},
+	crops::CropStore,
+	dir_watcher::{self, DirWatcher},
+	download::{self},
+	file_ops::{FileOpHistory, FileOperation, OpKind, DEFAULT_UNDO_STEPS},
+	geolocation,
+	gesture_recognizer::{Gesture, GestureRecognizer},
+	image_cache::{
+		image_loader::{detect_format, format_from_extension, Orientation},
+		AnimationFrameTexture, TileTexture, DEFAULT_PREFETCH_WINDOW,
+	},
+	input_handling::*,
+	integrity::HashStore,
+	navigation_history::NavigationHistory,
+	pdf_writer::PrintLayout,
+	playback_manager::*,
+	remote_control::{self, RemoteCommand, RemoteControl},
+	shaders, svg_export,
+	tags::TagStore,
+	timecode::DEFAULT_SEQUENCE_FPS,
+	utils::virtual_keycode_to_string,
+	wallpaper::set_wallpaper,
+};
+
+use super::{
+	bookmarks_sidebar::BookmarksSidebar,
+	bottom_bar::BottomBar,
+	channel_stats_overlay::ChannelStatsOverlay,
+	color_sampler::ColorSampler,
+	context_menu::{ContextMenu, MenuItem},
+	copy_notification::CopyNotifications,
+	debug_overlay::DebugOverlay,
+	directory_sidebar::DirectorySidebar,
+	empty_hint::EmptyHint,
+	error_overlay::ErrorOverlay,
+	exif_editor::ExifEditor,
+	gamma_panel::GammaPanel,
+	grid_panel::GridPanel,
+	help_screen::HelpScreen,
+	histogram_panel::HistogramPanel,
+	layout_menu::LayoutMenu,
+	line_editor::LineEditor,
+	load_log_overlay::LoadLogOverlay,
+	measure_tool::MeasureTool,
+	preload_indicator::PreloadIndicator,
+	print_panel::PrintPanel,
+	settings_panel::SettingsPanel,
+	shortcuts_overlay::ShortcutsOverlay,
+	text_overlay::{OverlayPosition, OverlayText, TextOverlay},
+	timecode_overlay::TimecodeOverlay,
+};
+
+mod analysis;
+use analysis::AnalysisJobs;
+mod bookmarks;
+mod commands;
+use commands::CommandJobs;
+mod crops;
+use crops::{draw_crop_selection, Crops};
+mod editors;
+use editors::FileEditors;
+mod export;
+use export::ExportJobs;
+mod gamma;
+use gamma::{gamma_correction, GAMMA_STEP};
+mod hashes;
+use hashes::FileHashes;
+mod layouts;
+mod menus;
+use menus::Menus;
+mod opening;
+use opening::OpenJobs;
+mod presentation;
+use presentation::LayoutBeforePresentation;
+mod rating;
+use rating::Rating;
+mod search;
+use search::FileSearch;
+mod session;
+use session::PendingSession;
+mod split_view;
+use split_view::SplitView;
+mod tagging;
+use tagging::Tags;
+mod tools;
+use tools::draw_measurements;
+mod transitions;
+use transitions::{CrossfadeTransition, KenBurnsAnimator};
+mod zoom_memory;
+use zoom_memory::ZoomMemory;
+
+const MIN_ZOOM_FACTOR: f32 = 0.0001;
+const MAX_ZOOM_FACTOR: f32 = 10000.0;
+const AA_TEXEL_SIZE_THRESHOLD: f32 = 4f32;
+/// The zoom above which the narrow images of `PIXEL_ART_MAX_WIDTH` are shown with sharp
+/// texels by the automatic antialiasing, unless the configuration sets another one
+const DEFAULT_PIXEL_ART_ZOOM: f32 = 2.0;
+/// The images narrower than this are likely to be pixel art or icons
+const PIXEL_ART_MAX_WIDTH: f32 = 256.0;
+/// How long the mouse has to stay still before the cursor is hidden in fullscreen
+const CURSOR_HIDE_DELAY: Duration = Duration::from_secs(3);
+/// The same for the presentation mode, where the cursor is hidden outside of fullscreen too
+const PRESENTATION_CURSOR_HIDE_DELAY: Duration = Duration::from_secs(2);
+/// Loads that take less than this don't show the loading indicator, so that it doesn't flicker
+const LOADING_INDICATOR_DELAY: Duration = Duration::from_millis(200);
+/// How long the properties of a file are shown
+const PROPERTIES_OVERLAY_MS: u32 = 6000;
+const LOAD_TIMINGS_OVERLAY_MS: u32 = 5000;
+/// Releasing the right mouse button opens the context menu, unless the mouse was moved
+/// further than this since it was pressed
+const CONTEXT_MENU_MAX_DRAG: f32 = 4.0;
+/// The name that an image pasted from the clipboard is displayed with
+const CLIPBOARD_IMAGE_PATH: &str = "<clipboard>";
+/// The name that the thumbnails of the search results are displayed with
+const SEARCH_RESULTS_PATH: &str = "<search results>";
+
+/// The zoom beyond which the artifacts of lossy compression become visible
+const LOSSY_HINT_ZOOM: f32 = 2.0;
+const LOSSY_JPEG_HINT: &str = "Lossy JPEG, the blocks are compression artifacts";
+const HDR_HINT: &str = "HDR image, the colors brighter than white are clipped";
+
+type ReloadCallback = Rc<dyn Fn() -> Result<Vec<&'static str>, String>>;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ScalingMode {
+	Fixed,
+	FitStretch,
+	FitMin,
+	/// The width of the image is scaled to the width of the widget
+	FitWidth,
+	/// The height of the image is scaled to the height of the widget
+	FitHeight,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum MovementDir {
+	None,
+	Positive,
+	Negative,
+}
+
+impl MovementDir {
+	fn moving(self) -> bool {
+		!matches!(self, MovementDir::None)
+	}
+}
+
+/// The zoom and pan of an image at the moment when the user navigated away from it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct ZoomPanState {
+	/// See `PictureWidgetData::img_texel_size`
+	zoom: f32,
+	/// The horizontal offset of the image center from the center of the widget, in logical pixels
+	pan_x: f32,
+	/// The vertical offset of the image center from the center of the widget, in logical pixels
+	pan_y: f32,
+}
+
+#[derive(Debug, Clone)]
+enum HoverState {
+	None,
+	ItemHovered { prev_path: LoadedImgPath },
+}
+
+fn orientation_to_matrix(orientation: Orientation) -> Matrix4<f32> {
+	#[rustfmt::skip]
+	let result = match orientation {
+		Orientation::Deg0 => Matrix4::from_scale(1.0),
+		Orientation::Deg0HorFlip => Matrix4::new(
+			-1.0, 0.0, 0.0, 0.0,
+			0.0, 1.0, 0.0, 0.0,
+			0.0, 0.0, 1.0, 0.0,
+			0.0, 0.0, 0.0, 1.0
+		),
+		Orientation::Deg180 => Matrix4::new(
+			-1.0, 0.0, 0.0, 0.0,
+			0.0, -1.0, 0.0, 0.0,
+			0.0, 0.0, 1.0, 0.0,
+			0.0, 0.0, 0.0, 1.0
+		),
+		Orientation::Deg180HorFlip => Matrix4::new(
+			1.0, 0.0, 0.0, 0.0,
+			0.0, -1.0, 0.0, 0.0,
+			0.0, 0.0, 1.0, 0.0,
+			0.0, 0.0, 0.0, 1.0
+		),
+		Orientation::Deg90 => Matrix4::new(
+			0.0, -1.0, 0.0, 0.0,
+			1.0, 0.0, 0.0, 0.0,
+			0.0, 0.0, 1.0, 0.0,
+			0.0, 0.0, 0.0, 1.0
+		),
+		Orientation::Deg90VerFlip => Matrix4::new(
+			0.0, -1.0, 0.0, 0.0,
+			-1.0, 0.0, 0.0, 0.0,
+			0.0, 0.0, 1.0, 0.0,
+			0.0, 0.0, 0.0, 1.0
+		),
+		Orientation::Deg270 => Matrix4::new(
+			0.0, 1.0, 0.0, 0.0,
+			-1.0, 0.0, 0.0, 0.0,
+			0.0, 0.0, 1.0, 0.0,
+			0.0, 0.0, 0.0, 1.0
+		),
+		Orientation::Deg270VerFlip => Matrix4::new(
+			0.0, 1.0, 0.0, 0.0,
+			1.0, 0.0, 0.0, 0.0,
+			0.0, 0.0, 1.0, 0.0,
+			0.0, 0.0, 0.0, 1.0
+		),
+	};
+	result
+}
+
+struct PictureWidgetData {
+	placement: WidgetPlacement,
+	drawn_bounds: LogicalRect,
+	prev_draw_size: LogicalVector,
+	visible: bool,
+	render_validity: RenderValidity,
+
+	click: bool,
+	hover: bool,
+
+	configuration: Rc<RefCell<Configuration>>,
+	cache: Arc<Mutex<Cache>>,
+	playback_manager: PlaybackManager,
+	// It's an option to allow manual destruction.
+	clipboard_handler: Option<ClipboardHandler>,
+	clipboard_request_was_pending: bool,
+	/// The PDFs, TIFFs, watermarked copies and prints that are being written
+	export: ExportJobs,
+	/// The SHA-256 hashes of the opened files
+	hashes: FileHashes,
+	/// The regions of the images that are shown when they are opened
+	crops: Crops,
+	/// Only set while the presentation mode shows nothing but the image, see
+	/// `presentation_mode`
+	presentation: Option<LayoutBeforePresentation>,
+	split_view: Option<SplitView>,
+	/// The picked, downloaded and reloaded files that are being read
+	opening: OpenJobs,
+	/// The image that the format hints were shown for, and the hints that were shown
+	format_hints_shown: Option<(PathBuf, Vec<&'static str>)>,
+	pending_session: Option<PendingSession>,
+	/// The external programs that run on the current file
+	commands: CommandJobs,
+	/// Computes what the channel statistics, the decoder info and the histogram show
+	analysis: AnalysisJobs,
+	/// The search for file names in the folder of the current image
+	search: FileSearch,
+	/// The folder that's being bookmarked and the name of the bookmark while it's typed
+	bookmark_editor: Option<(PathBuf, LineEditor)>,
+	/// The name of the layout while it's typed, see `save_layout`
+	layout_editor: Option<LineEditor>,
+	file_op_history: FileOpHistory,
+	/// The images that were navigated to, see `history_back`
+	navigation_history: NavigationHistory,
+
+	program: Program,
+	bright_shade: f32,
+	/// The width divided by the height of the frame that the image is shown in, see
+	/// `frame_rect`
+	aspect_ratio: Option<f32>,
+	/// The exponent the shader applies to the drawn colors
+	gamma: f32,
+	/// The gamma correction of the configuration when it was last applied. Changing it
+	/// replaces the gamma adjusted with the keys.
+	applied_gamma: f32,
+	/// Size of an image texel in physical display pixels
+	img_texel_size: f32,
+	scaling: ScalingMode,
+	img_pos: LogicalVector,
+	antialiasing: Antialias,
+	/// The turns and flips of the shown image on top of its own orientation. They are reset
+	/// when another file is shown.
+	view_orientation: Orientation,
+
+	hor_pan_input: MovementDir,
+	ver_pan_input: MovementDir,
+	zoom_input: MovementDir,
+	/// The velocity of horizontal panning
+	hor_pan_vel: f32,
+	/// The velocity of vertical panning
+	ver_pan_vel: f32,
+	/// The velocity of zooming
+	zoom_vel: f32,
+
+	last_click_time: Instant,
+	last_mouse_pos: LogicalVector,
+	last_mouse_move: Instant,
+	/// The cursor is hidden in fullscreen when the mouse isn't moved for a while
+	cursor_hidden: bool,
+	/// The spinning arc is drawn while an image takes long to load
+	loading_indicator_shown: bool,
+	panning_2d: bool,
+	gestures: GestureRecognizer,
+	/// The image is being dragged with the left mouse button
+	dragging: bool,
+	panning_vert: bool,
+	panning_hor: bool,
+	/// Where the right mouse button was pressed while it's held. The context menu is opened
+	/// when it's released without moving the mouse far.
+	right_press_pos: Option<LogicalVector>,
+	hover_state: HoverState,
+
+	zoom_memory: ZoomMemory,
+	/// The path of the image that the current zoom and pan belongs to
+	zoom_memory_path: Option<PathBuf>,
+	/// The point in image texels that is moved to the center of the widget when
+	/// the first image is displayed. A missing coordinate means the image center.
+	initial_pan: Option<(Option<f32>, Option<f32>)>,
+	transition: Option<CrossfadeTransition>,
+	ken_burns: Option<KenBurnsAnimator>,
+
+	first_draw: bool,
+	last_cam_move_time: Instant,
+	next_update: NextUpdate,
+	bottom_bar: Rc<BottomBar>,
+	left_to_pan_hint: Rc<HelpScreen>,
+	copy_notifications: CopyNotifications,
+	error_overlay: ErrorOverlay,
+	text_overlay: TextOverlay,
+	load_log_overlay: LoadLogOverlay,
+	exif_editor: ExifEditor,
+	color_sampler: ColorSampler,
+	measure_tool: MeasureTool,
+	/// Only exists if every image of the directory is preloaded
+	preload_indicator: Option<PreloadIndicator>,
+	/// Reports the new images of the watched folder
+	dir_watcher: Option<DirWatcher>,
+	/// Whether the new images of the watched folder are shown when they are found
+	auto_advance: bool,
+	remote_control: Option<RemoteControl>,
+	/// Shown while no file is open
+	empty_hint: Option<EmptyHint>,
+	shortcuts_overlay: Option<ShortcutsOverlay>,
+	channel_stats_overlay: Option<ChannelStatsOverlay>,
+	histogram_panel: Option<HistogramPanel>,
+	debug_overlay: Option<DebugOverlay>,
+	timecode_overlay: Option<TimecodeOverlay>,
+	/// How long the last call of `draw` took, without waiting for the GPU
+	last_draw_time: Duration,
+	directory_sidebar: Option<Rc<DirectorySidebar>>,
+	bookmarks_sidebar: Option<Rc<BookmarksSidebar>>,
+	/// The menus that the right click and `open_layout_menu` open
+	menus: Menus,
+	/// Reads the config file again, see `set_on_reload_config`
+	on_reload_config: Option<ReloadCallback>,
+	settings_panel: Rc<SettingsPanel>,
+	grid_panel: Option<Rc<GridPanel>>,
+	gamma_panel: Option<Rc<GammaPanel>>,
+	print_panel: Option<Rc<PrintPanel>>,
+	/// The name or the folder of the current file while it's being edited
+	editors: FileEditors,
+	/// The tags of the images and their editor
+	tags: Tags,
+	/// The rating of the current image in the bottom bar
+	rating: Rating,
+	/// The fit mode from the configuration that the scaling was last set from
+	applied_fit: Option<FitMode>,
+	window: Weak<Window>,
+}
+impl WidgetData for PictureWidgetData {
+	fn placement(&mut self) -> &mut WidgetPlacement {
+		&mut self.placement
+	}
+	fn drawn_bounds(&mut self) -> &mut LogicalRect {
+		&mut self.drawn_bounds
+	}
+	fn visible(&mut self) -> &mut bool {
+		&mut self.visible
+	}
+}
+impl PictureWidgetData {
+	fn fit_image_to_panel(&mut self, dpi_scale: f32, stretch: bool) {
+		let size = self.drawn_bounds.size.vec;
+		let fit_size = self.frame_rect().size.vec;
+		if let Some(texture) = self.get_texture() {
+			let panel_aspect = fit_size.x / fit_size.y;
+			let (img_phys_w, img_pyhs_h) = {
+				let (w, h) = texture.oriented_dimensions();
+				(w as f32, h as f32)
+			};
+			let img_aspect = img_phys_w / img_pyhs_h;
+
+			let texel_size_to_fit_width = fit_size.x / img_phys_w;
+			let img_texel_size = if img_aspect > panel_aspect {
+				// The image is relatively wider than the panel
+				texel_size_to_fit_width
+			} else {
+				texel_size_to_fit_width * (img_aspect / panel_aspect)
+			};
+			let widget_phys_size = fit_size * dpi_scale;
+			let fits_in_widget =
+				widget_phys_size.x >= img_phys_w && widget_phys_size.y >= img_pyhs_h;
+			self.img_pos = LogicalVector::new(size.x * 0.5, size.y * 0.5);
+			if fits_in_widget && !stretch {
+				self.img_texel_size = 1.0;
+			} else {
+				self.img_texel_size = img_texel_size * dpi_scale;
+			}
+			if stretch {
+				self.scaling = ScalingMode::FitStretch;
+			} else {
+				self.scaling = ScalingMode::FitMin;
+			}
+		}
+	}
+
+	/// Scales the image so that it spans the entire widget either horizontally or vertically
+	fn fit_image_to_panel_axis(&mut self, dpi_scale: f32, horizontal: bool) {
+		let size = self.drawn_bounds.size.vec;
+		let fit_size = self.frame_rect().size.vec;
+		if let Some(texture) = self.get_texture() {
+			let (img_phys_w, img_phys_h) = {
+				let (w, h) = texture.oriented_dimensions();
+				(w as f32, h as f32)
+			};
+			let img_texel_size =
+				if horizontal { fit_size.x / img_phys_w } else { fit_size.y / img_phys_h };
+			self.img_pos = LogicalVector::new(size.x * 0.5, size.y * 0.5);
+			self.img_texel_size = img_texel_size * dpi_scale;
+			self.scaling = if horizontal { ScalingMode::FitWidth } else { ScalingMode::FitHeight };
+		}
+	}
+
+	/// The part of the widget that the image is fitted into. That's the whole widget, unless an
+	/// aspect ratio was given with `--aspect-ratio`. Then it's the largest centered rectangle
+	/// with that ratio, and the rest is covered by black bars.
+	fn frame_rect(&self) -> LogicalRect {
+		let bounds = self.drawn_bounds;
+		let ratio = match self.aspect_ratio {
+			Some(ratio) if bounds.size.vec.y > 0.0 => ratio,
+			_ => return bounds,
+		};
+		let size = bounds.size.vec;
+		let frame_size = if size.x / size.y > ratio {
+			LogicalVector::new(size.y * ratio, size.y)
+		} else {
+			LogicalVector::new(size.x, size.x / ratio)
+		};
+		LogicalRect { pos: bounds.pos + (bounds.size - frame_size) * 0.5, size: frame_size }
+	}
+
+	fn zoom_image(&mut self, anchor: LogicalVector, mut delta: f32) {
+		delta = if delta > 0.0 { delta + 1.0 } else { 1.0 / (delta.abs() + 1.0) };
+		let mut image_texel_size = (self.img_texel_size * delta).max(0.0);
+		if (image_texel_size - 1.0).abs() < 0.01 {
+			image_texel_size = 1.0;
+		} else {
+			image_texel_size = image_texel_size.clamp(MIN_ZOOM_FACTOR, MAX_ZOOM_FACTOR)
+		}
+		self.img_pos = (image_texel_size / self.img_texel_size) * (self.img_pos - anchor) + anchor;
+		let prev_percent = (self.img_texel_size * 100.0).round();
+		self.img_texel_size = image_texel_size;
+		self.scaling = ScalingMode::Fixed;
+		self.update_scaling_buttons();
+		let percent = (image_texel_size * 100.0).round();
+		#[allow(clippy::float_cmp)]
+		if percent != prev_percent {
+			self.text_overlay.show(OverlayText {
+				text: format!("{}%", percent),
+				duration_ms: 600,
+				position: OverlayPosition::Center,
+			});
+		}
+		self.render_validity.invalidate();
+	}
+
+	/// Multiplies the zoom by `factor`, keeping the point at `anchor` in place
+	fn zoom_image_by(&mut self, anchor: LogicalVector, factor: f32) {
+		if factor.is_finite() && factor > 0.0 {
+			// `zoom_image` multiplies the zoom by `delta + 1` or divides it by `1 - delta` for
+			// negative deltas
+			let delta = if factor >= 1.0 { factor - 1.0 } else { 1.0 - 1.0 / factor };
+			self.zoom_image(anchor, delta);
+		}
+	}
+
+	fn pan_image(&mut self, delta: LogicalVector) {
+		self.scaling = ScalingMode::Fixed;
+		self.update_scaling_buttons();
+		self.img_pos += delta;
+		self.render_validity.invalidate();
+	}
+
+	fn update_image_transform(&mut self, dpi_scale: f32) {
+		match self.scaling {
+			ScalingMode::Fixed => {
+				let center_offset = (self.drawn_bounds.size - self.prev_draw_size) * 0.5f32;
+				self.img_pos += center_offset;
+				if let Some((x, y)) = self.initial_pan {
+					if let Some(state) = self.zoom_pan_state_centered_on(x, y, dpi_scale) {
+						self.apply_zoom_pan_state(state);
+						self.initial_pan = None;
+					}
+				}
+				self.apply_img_bounds(dpi_scale);
+			}
+			ScalingMode::FitStretch => {
+				self.fit_image_to_panel(dpi_scale, true);
+			}
+			ScalingMode::FitMin => {
+				self.fit_image_to_panel(dpi_scale, false);
+			}
+			ScalingMode::FitWidth => {
+				self.fit_image_to_panel_axis(dpi_scale, true);
+			}
+			ScalingMode::FitHeight => {
+				self.fit_image_to_panel_axis(dpi_scale, false);
+			}
+		}
+		self.prev_draw_size = self.drawn_bounds.size;
+		// While the images are fit into the widget, the cache works out the zoom of each one
+		let zoom_hint = (self.scaling == ScalingMode::Fixed).then_some(self.img_texel_size);
+		self.playback_manager.set_zoom_hint(zoom_hint);
+	}
+
+	fn apply_camera_movement(&mut self, dpi_scale: f32) {
+		fn animate_value(v: &mut f32, dir: f32, dt: f32, next_update: &mut NextUpdate) {
+			#[allow(clippy::float_cmp)]
+			if v.signum() != dir {
+				*v = 0.0;
+			}
+			*v += dir * dt * (2.0 / (v.abs() + 1.0));
+			*next_update = NextUpdate::Soonest;
+		}
+
+		let now = Instant::now();
+		let dt_sec = now.duration_since(self.last_cam_move_time).as_secs_f32();
+		self.last_cam_move_time = now;
+
+		match self.hor_pan_input {
+			MovementDir::None => self.hor_pan_vel = 0.0,
+			MovementDir::Positive => {
+				animate_value(&mut self.hor_pan_vel, 1.0, dt_sec, &mut self.next_update)
+			}
+			MovementDir::Negative => {
+				animate_value(&mut self.hor_pan_vel, -1.0, dt_sec, &mut self.next_update)
+			}
+		}
+		match self.ver_pan_input {
+			MovementDir::None => self.ver_pan_vel = 0.0,
+			MovementDir::Positive => {
+				animate_value(&mut self.ver_pan_vel, 1.0, dt_sec, &mut self.next_update)
+			}
+			MovementDir::Negative => {
+				animate_value(&mut self.ver_pan_vel, -1.0, dt_sec, &mut self.next_update)
+			}
+		}
+		match self.zoom_input {
+			MovementDir::None => self.zoom_vel = 0.0,
+			MovementDir::Positive => {
+				animate_value(&mut self.zoom_vel, 1.0, dt_sec, &mut self.next_update)
+			}
+			MovementDir::Negative => {
+				animate_value(&mut self.zoom_vel, -1.0, dt_sec, &mut self.next_update)
+			}
+		}
+
+		if self.zoom_input.moving() {
+			let bounds_size = self.drawn_bounds.size.vec;
+			let anchor = LogicalVector::new(bounds_size.x * 0.5, bounds_size.y * 0.5);
+			self.zoom_image(anchor, self.zoom_vel * dt_sec);
+		}
+		if self.hor_pan_input.moving() || self.ver_pan_input.moving() {
+			let panning_speed = 400.0 * dpi_scale;
+			let pos_delta = Vector2::new(self.hor_pan_vel, self.ver_pan_vel) * dt_sec;
+			self.scaling = ScalingMode::Fixed;
+			self.update_scaling_buttons();
+			self.img_pos.vec += panning_speed * pos_delta;
+		}
+	}
+
+	fn camera_movement_will_start(&mut self) {
+		// If there hasn't been any movement in a while, then reset the last update time
+		// to avoid large jumps at the beggining of a move when the delta would be large.
+		if !self.hor_pan_input.moving() && !self.ver_pan_input.moving() && !self.zoom_input.moving()
+		{
+			self.last_cam_move_time = Instant::now();
+		}
+	}
+
+	fn set_window_title_filename(
+		&self,
+		window: &Window,
+		playback_state: PlaybackState,
+		file_path: &LoadedImgPath,
+		book_page: Option<(PathBuf, usize, usize)>,
+	) {
+		let playback = match playback_state {
+			PlaybackState::Forward => " : Playing",
+			PlaybackState::Present => " : Presenting",
+			PlaybackState::RandomPresent => " : Presenting Shuffled",
+			PlaybackState::Paused => "",
+		};
+
+		let config = self.configuration.borrow();
+		let title_config = config.title.clone().unwrap_or_default();
+
+		let name = match file_path {
+			// Like the title of a window that has no file open
+			LoadedImgPath::NotYetLoaded => {
+				window.set_title("emulsion".into());
+				return;
+			}
+			LoadedImgPath::ErrLoading(path) => {
+				format!("[ FAILED TO OPEN ] {}", title_config.format_file_path(path)).into()
+			}
+			LoadedImgPath::Loaded(path) => match &book_page {
+				Some((archive_path, page, page_count)) => format!(
+					"{} - Page {}/{}",
+					title_config.format_file_path(archive_path),
+					page + 1,
+					page_count
+				)
+				.into(),
+				None => title_config.format_file_path(path),
+			},
+		};
+		let title = format!("{}{}{}", name, playback, title_config.format_program_name());
+		window.set_title(title);
+	}
+
+	/// The shown frame with the turns and flips of the view applied to its orientation
+	fn get_texture(&self) -> Option<AnimationFrameTexture> {
+		let mut texture = self.playback_manager.image_texture()?;
+		texture.orientation = texture.orientation.then(self.view_orientation);
+		Some(texture)
+	}
+
+	pub fn set_img_size_to_orig(&mut self) {
+		self.img_texel_size = 1.0;
+		self.scaling = ScalingMode::Fixed;
+		self.update_scaling_buttons();
+		self.render_validity.invalidate();
+	}
+
+	pub fn set_img_size_to_fit(&mut self, stretch: bool) {
+		{
+			let mut cache = self.cache.lock().unwrap();
+			cache.image.fit_stretches = stretch;
+		}
+		self.scaling = if stretch { ScalingMode::FitStretch } else { ScalingMode::FitMin };
+		self.update_scaling_buttons();
+		self.render_validity.invalidate();
+	}
+
+	pub fn toggle_antialias(&mut self) {
+		let aa = match self.antialiasing {
+			Antialias::Auto if self.img_texel_size < AA_TEXEL_SIZE_THRESHOLD => Antialias::Never,
+			Antialias::Auto | Antialias::Never => Antialias::Always,
+			Antialias::Always => Antialias::Never,
+		};
+		self.antialiasing = aa;
+		self.cache.lock().unwrap().image.antialiasing = aa;
+		self.render_validity.invalidate();
+	}
+
+	pub fn set_automatic_antialias(&mut self) {
+		self.antialiasing = Antialias::Auto;
+		self.cache.lock().unwrap().image.antialiasing = Antialias::Auto;
+		self.render_validity.invalidate();
+	}
+
+	/// Whether the image is wider or taller than the widget at the current zoom, which is when
+	/// dragging it makes sense
+	fn img_exceeds_bounds(&self, dpi_scale: f32) -> bool {
+		match self.get_texture() {
+			Some(texture) => {
+				let (w, h) = texture.oriented_dimensions();
+				let img_w = w as f32 * self.img_texel_size / dpi_scale;
+				let img_h = h as f32 * self.img_texel_size / dpi_scale;
+				let widget_size = self.drawn_bounds.size.vec;
+				img_w > widget_size.x + 0.5 || img_h > widget_size.y + 0.5
+			}
+			None => false,
+		}
+	}
+
+	fn set_dragging(&mut self, dragging: bool) {
+		if self.dragging == dragging {
+			return;
+		}
+		self.dragging = dragging;
+		if let Some(window) = self.window.upgrade() {
+			let icon = if dragging { CursorIcon::Grabbing } else { CursorIcon::Default };
+			window.window_mut().set_cursor_icon(icon);
+		}
+	}
+
+	/// Ensures that the image is within the widget, or at least touches an edge of the widget
+	fn apply_img_bounds(&mut self, dpi_scale: f32) {
+		if let Some(texture) = self.get_texture() {
+			let (img_phys_w, img_phys_h) = {
+				let (w, h) = texture.oriented_dimensions();
+				(w as f32 * self.img_texel_size, h as f32 * self.img_texel_size)
+			};
+			let img_w = img_phys_w / dpi_scale;
+			let img_h = img_phys_h / dpi_scale;
+
+			let widget_size = self.drawn_bounds.size.vec;
+			let img_pos = self.img_pos.vec;
+
+			if img_pos.x < -img_w / 2.0 {
+				self.img_pos.vec.x = -img_w / 2.0;
+			}
+			if img_pos.y < -img_h / 2.0 {
+				self.img_pos.vec.y = -img_h / 2.0;
+			}
+
+			if img_pos.x > widget_size.x + img_w / 2.0 {
+				self.img_pos.vec.x = (widget_size.x + img_w / 2.0).ceil();
+			}
+			if img_pos.y > widget_size.y + img_h / 2.0 {
+				self.img_pos.vec.y = (widget_size.y + img_h / 2.0).ceil();
+			}
+		}
+	}
+
+	fn update_scaling_buttons(&mut self) {
+		self.bottom_bar.update_scaling_buttons(self.scaling, self.img_texel_size);
+	}
+
+	/// The interpolation of the magnified texels for an image of `image_width` texels at the
+	/// zoom `texel_size`. The automatic antialiasing shows the images that are zoomed in far,
+	/// and the narrow ones from a lower zoom, with sharp texels.
+	fn interpolation(&self, texel_size: f32, image_width: f32) -> InterpolationMode {
+		let (configured, pixel_art_zoom) = {
+			let config = self.configuration.borrow();
+			let section = config.image.as_ref();
+			(
+				section.and_then(|s| s.interpolation_mode).unwrap_or_default(),
+				section.and_then(|s| s.pixel_art_zoom).unwrap_or(DEFAULT_PIXEL_ART_ZOOM),
+			)
+		};
+		match self.antialiasing {
+			Antialias::Never => InterpolationMode::Nearest,
+			Antialias::Always => configured,
+			Antialias::Auto if texel_size >= AA_TEXEL_SIZE_THRESHOLD => InterpolationMode::Nearest,
+			Antialias::Auto if texel_size > pixel_art_zoom && image_width < PIXEL_ART_MAX_WIDTH => {
+				InterpolationMode::Nearest
+			}
+			Antialias::Auto => configured,
+		}
+	}
+
+	/// The edges are only smoothed while one of them is inside the widget, so that a zoomed in
+	/// image is drawn exactly as before
+	fn smooth_edges_enabled(&self, dpi_scale: f32) -> bool {
+		let enabled = {
+			let config = self.configuration.borrow();
+			config.image.as_ref().and_then(|s| s.smooth_edges).unwrap_or(true)
+		};
+		let texture = match self.get_texture() {
+			Some(texture) if enabled => texture,
+			_ => return false,
+		};
+		let (w, h) = texture.oriented_dimensions();
+		let display_size = Vector2::new(w as f32, h as f32) * self.img_texel_size / dpi_scale;
+		let corner = self.img_pos.vec - display_size * 0.5;
+		let far_corner = corner + display_size;
+		let size = self.drawn_bounds.size.vec;
+		corner.x > 0.0 || corner.y > 0.0 || far_corner.x < size.x || far_corner.y < size.y
+	}
+
+	/// Switches the window in or out of fullscreen and remembers the new state in the cache
+	fn set_fullscreen(&mut self, fullscreen: bool) {
+		if let Some(window) = self.window.upgrade() {
+			window.set_fullscreen(fullscreen);
+			self.bottom_bar.set_visible_if_should_show(!fullscreen);
+			self.cache.lock().unwrap().window.fullscreen = fullscreen;
+		}
+	}
+
+	fn apply_zoom_pan_state(&mut self, state: ZoomPanState) {
+		let center = self.drawn_bounds.size * 0.5;
+		self.img_texel_size = state.zoom;
+		self.img_pos = center + LogicalVector::new(state.pan_x, state.pan_y);
+		self.scaling = ScalingMode::Fixed;
+		self.update_scaling_buttons();
+		self.render_validity.invalidate();
+	}
+
+	/// Returns the pan at the current zoom that puts the given image texel at the center of the
+	/// widget. The point is clamped so that the image covers as much of the widget as possible.
+	/// Returns `None` if there's no image yet.
+	fn zoom_pan_state_centered_on(
+		&self,
+		x: Option<f32>,
+		y: Option<f32>,
+		dpi_scale: f32,
+	) -> Option<ZoomPanState> {
+		let texture = self.get_texture()?;
+		let (img_w, img_h) = texture.oriented_dimensions();
+		let (img_w, img_h) = (img_w as f32, img_h as f32);
+		// The size of the widget measured in image texels
+		let view = self.drawn_bounds.size.vec * dpi_scale / self.img_texel_size;
+		let clamp = |v: Option<f32>, img_size: f32, view_size: f32| {
+			if img_size <= view_size {
+				img_size * 0.5
+			} else {
+				v.unwrap_or(img_size * 0.5).clamp(view_size * 0.5, img_size - view_size * 0.5)
+			}
+		};
+		let x = clamp(x, img_w, view.x);
+		let y = clamp(y, img_h, view.y);
+		let texel_to_logical = self.img_texel_size / dpi_scale;
+		Some(ZoomPanState {
+			zoom: self.img_texel_size,
+			pan_x: (img_w * 0.5 - x) * texel_to_logical,
+			pan_y: (img_h * 0.5 - y) * texel_to_logical,
+		})
+	}
+
+	fn toggle_fullscreen(&mut self) {
+		if let Some(window) = self.window.upgrade() {
+			self.set_fullscreen(!window.fullscreen());
+		}
+	}
+
+	/// Resizes the window so that this widget is exactly as large as the image at the current
+	/// zoom level. The window is not made larger than the monitor.
+	fn snap_window_to_image(&mut self) {
+		let (Some(window), Some(texture)) = (self.window.upgrade(), self.get_texture()) else {
+			return;
+		};
+		if window.fullscreen() {
+			return;
+		}
+		let winit_window = window.window_mut();
+		let dpi_scale = winit_window.scale_factor() as f32;
+		let (img_w, img_h) = texture.oriented_dimensions();
+		let img_size = Vector2::new(img_w as f32, img_h as f32) * self.img_texel_size;
+		// The rest of the window is taken up by the other widgets, like the bottom bar
+		let inner_size = winit_window.inner_size();
+		let widget_size = self.drawn_bounds.size.vec * dpi_scale;
+		let mut width = (img_size.x + inner_size.width as f32 - widget_size.x).round() as u32;
+		let mut height = (img_size.y + inner_size.height as f32 - widget_size.y).round() as u32;
+		if let Some(monitor) = winit_window.current_monitor() {
+			// winit doesn't report the area taken by the taskbar, so only the window
+			// decorations are subtracted from the monitor size.
+			let outer_size = winit_window.outer_size();
+			let decoration_w = outer_size.width.saturating_sub(inner_size.width);
+			let decoration_h = outer_size.height.saturating_sub(inner_size.height);
+			width = width.min(monitor.size().width.saturating_sub(decoration_w));
+			height = height.min(monitor.size().height.saturating_sub(decoration_h));
+		}
+		winit_window.set_maximized(false);
+		let _ = winit_window.request_inner_size(PhysicalSize::new(width, height));
+
+		// Keep the zoom and center the image, the fixed scaling keeps it centered while resizing
+		self.scaling = ScalingMode::Fixed;
+		self.img_pos = self.drawn_bounds.size * 0.5;
+		self.update_scaling_buttons();
+		self.render_validity.invalidate();
+	}
+
+	/// Displays the image from the clipboard without saving it to a file
+	fn paste_from_clipboard(&mut self) {
+		let image = match get_clipboard_image() {
+			Ok(image) => image,
+			Err(e) => {
+				eprintln!("{}", e);
+				return;
+			}
+		};
+		let window = match self.window.upgrade() {
+			Some(window) => window,
+			None => return,
+		};
+		let path = PathBuf::from(CLIPBOARD_IMAGE_PATH);
+		let result = self.playback_manager.show_image(&window.display_mut(), image, path);
+		match result {
+			Ok(()) => {
+				self.text_overlay.show(OverlayText::confirmation("Pasted from the clipboard"))
+			}
+			Err(e) => eprintln!("Could not display the clipboard image: {:?}", e),
+		}
+		self.render_validity.invalidate();
+	}
+
+	/// Saves the picture area as it was last drawn, including the zoom, the pan and the
+	/// overlays, to a PNG file next to the current image
+	fn export_view(&mut self) {
+		let (view, target) = match (self.capture_view(), self.export_target("png")) {
+			(Some((view, _)), Some(target)) => (view, target),
+			_ => return,
+		};
+		match view.save(&target) {
+			Ok(()) => self.error_overlay.show_status(&format!("Exported the view to {:?}", target)),
+			Err(e) => self.error_overlay.show(&format!("Could not export the view: {}", e)),
+		}
+	}
+
+	/// Saves the picture area like `export_view`, but as an SVG that embeds the pixels. The
+	/// SVG has the logical size of the area and keeps every physical pixel of it.
+	fn export_view_svg(&mut self) {
+		let ((view, size), target) = match (self.capture_view(), self.export_target("svg")) {
+			(Some(captured), Some(target)) => (captured, target),
+			_ => return,
+		};
+		let result = svg_export::view_svg(&view, size.vec.x, size.vec.y)
+			.map_err(|e| e.to_string())
+			.and_then(|svg| fs::write(&target, svg).map_err(|e| e.to_string()));
+		match result {
+			Ok(()) => self.error_overlay.show_status(&format!("Exported the view to {:?}", target)),
+			Err(e) => self.error_overlay.show(&format!("Could not export the view: {}", e)),
+		}
+	}
+
+	/// Reads the picture area back from the window. Returns the pixels and the logical size
+	/// of the area.
+	fn capture_view(&mut self) -> Option<(RgbaImage, LogicalVector)> {
+		let window = self.window.upgrade()?;
+		let dpi_scale = window.window_mut().scale_factor() as f32;
+		let front_buffer: Result<RawImage2d<u8>, _> = window.display_mut().read_front_buffer();
+		let raw = match front_buffer {
+			Ok(raw) => raw,
+			Err(e) => {
+				eprintln!("Could not read the rendered view: {:?}", e);
+				return None;
+			}
+		};
+		let mut frame = RgbaImage::from_raw(raw.width, raw.height, raw.data.into_owned())?;
+		// OpenGL stores the rows from the bottom to the top
+		imageops::flip_vertical_in_place(&mut frame);
+
+		let bounds = &self.drawn_bounds;
+		let x = ((bounds.pos.vec.x * dpi_scale).round().max(0.0) as u32).min(frame.width());
+		let y = ((bounds.pos.vec.y * dpi_scale).round().max(0.0) as u32).min(frame.height());
+		let w = ((bounds.size.vec.x * dpi_scale).round() as u32).min(frame.width() - x);
+		let h = ((bounds.size.vec.y * dpi_scale).round() as u32).min(frame.height() - y);
+		Some((imageops::crop_imm(&frame, x, y, w, h).to_image(), bounds.size))
+	}
+
+	/// The path that the view is exported to, next to the current image or in the working
+	/// directory for a pasted image
+	fn export_target(&self, extension: &str) -> Option<PathBuf> {
+		match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) if path != Path::new(CLIPBOARD_IMAGE_PATH) => {
+				Some(export_path(path, extension))
+			}
+			_ => std::env::current_dir()
+				.ok()
+				.map(|dir| export_path(&dir.join("clipboard"), extension)),
+		}
+	}
+
+	/// Reverts the latest file operation and shows the image that it brought back
+	fn undo_file_operation(&mut self) {
+		let operation = match self.file_op_history.undo() {
+			Some(Ok(operation)) => operation,
+			Some(Err(e)) => {
+				eprintln!("Error while undoing a file operation: {}", e);
+				return;
+			}
+			None => return,
+		};
+		self.show_undone(operation);
+	}
+
+	/// Restores the latest file that was moved to the trash and shows it
+	fn undo_delete(&mut self) {
+		match self.file_op_history.undo_delete() {
+			Some(Ok(operation)) => self.show_undone(operation),
+			Some(Err(e)) => self.error_overlay.show(&e),
+			None => self.error_overlay.show_status("No file was moved to the trash"),
+		}
+	}
+
+	/// Shows the file that an undone operation brought back
+	fn show_undone(&mut self, operation: FileOperation) {
+		// The restored file is only found after the directory is collected again
+		if let Err(e) = self.playback_manager.update_directory() {
+			eprintln!("Error while updating directory {:?}", e);
+		}
+		match operation.kind {
+			OpKind::Delete | OpKind::Move | OpKind::Rename => {
+				self.playback_manager.request_load(LoadRequest::FilePath(operation.original_path));
+			}
+			OpKind::Copy => {}
+		}
+		self.render_validity.invalidate();
+	}
+
+	/// Whether the file at `path` was modified after the shown image. Files whose time can't
+	/// be read count as newer.
+	fn is_newer_than_shown(&self, path: &Path) -> bool {
+		let shown = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(shown) => shown,
+			_ => return true,
+		};
+		let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+		match (modified(path), modified(shown)) {
+			(Some(new), Some(shown)) => new >= shown,
+			_ => true,
+		}
+	}
+
+	/// Goes back to the state of a viewer without a file. The changes to the files are saved
+	/// when they are made, so there's nothing to confirm.
+	fn close_file(&mut self) {
+		if matches!(self.playback_manager.shown_file_path(), LoadedImgPath::NotYetLoaded) {
+			return;
+		}
+		if self.editors.rename_editor.is_some() {
+			self.stop_rename();
+		}
+		if self.editors.move_editor.is_some() {
+			self.stop_move_editing();
+		}
+		if self.tags.tag_editor.is_some() {
+			self.stop_tag_editing();
+		}
+		if self.commands.command_editor.is_some() {
+			self.stop_command_editing();
+		}
+		self.playback_manager.close_file();
+		self.transition = None;
+		self.ken_burns = None;
+		self.bottom_bar.slider.set_steps(1, 0);
+		if let Some(hint) = &self.empty_hint {
+			hint.set_visible(true);
+		}
+		self.render_validity.invalidate();
+	}
+
+	/// Hides the cursor in fullscreen once the mouse wasn't moved for `CURSOR_HIDE_DELAY`. It
+	/// stays visible while a panel or an overlay that can be used with the mouse is open.
+	fn update_cursor_visibility(&mut self, window: &Window, now: Instant) {
+		let keep_visible = (!window.fullscreen() && self.presentation.is_none())
+			|| self.panel_open()
+			|| self.exif_editor.is_open()
+			|| self.load_log_overlay.is_open()
+			|| self.shortcuts_overlay.as_ref().is_some_and(ShortcutsOverlay::is_open)
+			|| self.color_sampler.is_active()
+			|| self.measure_tool.is_active()
+			|| self.crops.crop_selection.is_some();
+		let delay = match self.presentation {
+			Some(_) => PRESENTATION_CURSOR_HIDE_DELAY,
+			None => CURSOR_HIDE_DELAY,
+		};
+		let hide_at = self.last_mouse_move + delay;
+		let hide = !keep_visible && now >= hide_at;
+		if hide != self.cursor_hidden {
+			window.window_mut().set_cursor_visible(!hide);
+			self.cursor_hidden = hide;
+		}
+		if !keep_visible && !hide {
+			self.next_update = self.next_update.aggregate(NextUpdate::WaitUntil(hide_at));
+		}
+	}
+
+	/// The position of the cursor on the displayed image, from 0 to 1 along both axes of the
+	/// oriented image
+	fn cursor_on_image(&self, dpi_scale: f32) -> Option<(f32, f32)> {
+		let texture = self.get_texture()?;
+		let (img_w, img_h) = texture.oriented_dimensions();
+		let display_size =
+			Vector2::new(img_w as f32, img_h as f32) * self.img_texel_size / dpi_scale;
+		let corner = self.drawn_bounds.pos.vec + self.img_pos.vec - display_size * 0.5;
+		let offset = self.last_mouse_pos.vec - corner;
+		Some((offset.x / display_size.x, offset.y / display_size.y))
+	}
+
+	/// Shows the name of the color profile of the current image and copies it as text
+	fn copy_color_profile(&mut self) {
+		let path = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) if path.is_file() => path.clone(),
+			_ => {
+				self.error_overlay.show("Open an image file to see its color profile");
+				return;
+			}
+		};
+		let name = color_profile_name(&path);
+		self.text_overlay.show(OverlayText::confirmation(name.clone()));
+		if let Err(e) = set_clipboard_text(&name) {
+			self.error_overlay.show(&e);
+		}
+	}
+
+	/// Opens the location where the photo was taken on a map in the browser
+	fn show_on_map(&mut self) {
+		let path = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) if path.is_file() => path.clone(),
+			_ => {
+				self.error_overlay.show("Open a photo to see where it was taken");
+				return;
+			}
+		};
+		let (latitude, longitude) = match geolocation::read_coordinates(&path) {
+			Some(coordinates) => coordinates,
+			None => {
+				self.error_overlay.show("The image has no GPS coordinates");
+				return;
+			}
+		};
+		let url = geolocation::map_url(latitude, longitude);
+		match open::that(&url) {
+			Ok(()) => self.error_overlay.show_status(&format!("{:.5}, {:.5}", latitude, longitude)),
+			Err(e) => self.error_overlay.show(&format!("Could not open {}: {}", url, e)),
+		}
+	}
+
+	/// Whether the settings panel, the grid panel or the print panel is open. They're modal.
+	fn panel_open(&self) -> bool {
+		self.settings_panel.is_open()
+			|| self.grid_panel.as_ref().is_some_and(|p| p.is_open())
+			|| self.print_panel.as_ref().is_some_and(|p| p.is_open())
+	}
+
+	/// Where a point given in the pixels of the oriented image is on the widget
+	fn image_to_logical(&self, pos: Vector2<f32>, dpi_scale: f32) -> Option<LogicalVector> {
+		let texture = self.get_texture()?;
+		let (img_w, img_h) = texture.oriented_dimensions();
+		let img_size = Vector2::new(img_w as f32, img_h as f32);
+		let display_size = img_size * self.img_texel_size / dpi_scale;
+		let corner = self.drawn_bounds.pos.vec + self.img_pos.vec - display_size * 0.5;
+		let offset = Vector2::new(pos.x / img_size.x, pos.y / img_size.y);
+		let logical = corner + Vector2::new(offset.x * display_size.x, offset.y * display_size.y);
+		Some(LogicalVector::new(logical.x, logical.y))
+	}
+
+	fn run_remote_command(&mut self, command: RemoteCommand) {
+		match command {
+			RemoteCommand::Next => self.playback_manager.request_load(LoadRequest::LoadNext),
+			RemoteCommand::Prev => self.playback_manager.request_load(LoadRequest::LoadPrevious),
+			RemoteCommand::Goto { index } => {
+				self.playback_manager.request_load(LoadRequest::LoadAtIndex(index))
+			}
+			RemoteCommand::Zoom { factor } => {
+				let bounds_size = self.drawn_bounds.size.vec;
+				let anchor = LogicalVector::new(bounds_size.x * 0.5, bounds_size.y * 0.5);
+				self.zoom_image_by(anchor, factor);
+			}
+			RemoteCommand::ShowOverlay { text, duration_ms } => {
+				let position = OverlayPosition::Center;
+				self.text_overlay.show(OverlayText { text, duration_ms, position });
+			}
+			RemoteCommand::Quit => request_exit(),
+		}
+		self.render_validity.invalidate();
+	}
+
+	/// Points out what the format of the shown image means for what's seen of it, like the
+	/// compression artifacts of a JPEG that's zoomed in. Every hint is shown once per image.
+	fn update_format_hints(&mut self) {
+		let path = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) => path.clone(),
+			_ => return,
+		};
+		let hint = match self.get_texture().and_then(|texture| texture.source_format) {
+			Some(ImageFormat::Jpeg) if self.img_texel_size > LOSSY_HINT_ZOOM => LOSSY_JPEG_HINT,
+			Some(ImageFormat::Hdr | ImageFormat::OpenExr) => HDR_HINT,
+			_ => return,
+		};
+		let shown = match &mut self.format_hints_shown {
+			Some((shown_for, shown)) if *shown_for == path => shown,
+			hints => &mut hints.insert((path, Vec::new())).1,
+		};
+		if !shown.contains(&hint) {
+			shown.push(hint);
+			self.error_overlay.show_status(hint);
+		}
+	}
+
+	/// The shown file, if it's a file on the disk
+	fn shown_file(&self) -> Option<PathBuf> {
+		match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) if path.is_file() => Some(path.clone()),
+			_ => None,
+		}
+	}
+
+	/// Moves the shown file to the trash and shows the next one
+	fn delete_image(&mut self) {
+		if let LoadedImgPath::Loaded(path) = self.playback_manager.shown_file_path() {
+			let path = path.clone();
+			if let Err(e) = trash::delete(&path) {
+				eprintln!("Error while moving file '{:?}' to trash: {:?}", path, e);
+			} else {
+				self.file_op_history.push(FileOperation::delete(path));
+				self.text_overlay.show(OverlayText::confirmation("Moved to the trash"));
+			}
+			if let Err(e) = self.playback_manager.update_directory() {
+				eprintln!("Error while updating directory {:?}", e);
+			}
+			self.render_validity.invalidate();
+		}
+	}
+
+	fn copy_image(&mut self) {
+		if let LoadedImgPath::Loaded(path) = self.playback_manager.shown_file_path().clone() {
+			if let Some(clipboard_handler) = &mut self.clipboard_handler {
+				clipboard_handler.request_copy(path);
+				self.copy_notifications.set_started();
+				self.clipboard_request_was_pending = true;
+			}
+		}
+	}
+
+	fn copy_path(&mut self) {
+		let path = match self.shown_file() {
+			Some(path) => path,
+			None => return,
+		};
+		match set_clipboard_text(&path.to_string_lossy()) {
+			Ok(()) => self.text_overlay.show(OverlayText::confirmation("Copied the path")),
+			Err(e) => self.error_overlay.show(&e),
+		}
+	}
+
+	/// Copies the path, the size, the dimensions, the format and the modification time of the
+	/// shown file as JSON
+	fn copy_metadata(&mut self) {
+		let path = match self.shown_file() {
+			Some(path) => path,
+			None => return,
+		};
+		let metadata = match fs::metadata(&path) {
+			Ok(metadata) => metadata,
+			Err(e) => {
+				self.error_overlay.show(&format!("Could not read the file properties: {}", e));
+				return;
+			}
+		};
+		let texture = self.playback_manager.image_texture();
+		let dimensions = texture.as_ref().map(|texture| texture.oriented_dimensions());
+		let format = texture.and_then(|texture| texture.source_format).map(|f| format!("{:?}", f));
+		let modified = metadata.modified().ok().map(humantime::format_rfc3339_seconds);
+		let json = serde_json::json!({
+			"path": path.to_string_lossy(),
+			"size_bytes": metadata.len(),
+			"width": dimensions.map(|(w, _)| w),
+			"height": dimensions.map(|(_, h)| h),
+			"format": format,
+			"modified": modified.map(|m| m.to_string()),
+		});
+		match set_clipboard_text(&json.to_string()) {
+			Ok(()) => self.text_overlay.show(OverlayText::confirmation("Copied the metadata")),
+			Err(e) => self.error_overlay.show(&e),
+		}
+	}
+
+	/// Opens the shown file in the application that the system opens images with
+	fn open_externally(&mut self) {
+		let path = match self.shown_file() {
+			Some(path) => path,
+			None => return,
+		};
+		if let Err(e) = open::that_detached(&path) {
+			self.error_overlay.show(&format!("Could not open the file: {}", e));
+		}
+	}
+
+	/// Shows the name, the size, the format and the modification time of the shown file
+	fn show_properties(&mut self) {
+		let path = match self.shown_file() {
+			Some(path) => path,
+			None => return,
+		};
+		let metadata = match fs::metadata(&path) {
+			Ok(metadata) => metadata,
+			Err(e) => {
+				self.error_overlay.show(&format!("Could not read the file properties: {}", e));
+				return;
+			}
+		};
+		let name = path.file_name().unwrap_or_default().to_string_lossy();
+		let mut parts = vec![name.into_owned()];
+		let texture = self.playback_manager.image_texture();
+		if let Some(texture) = &texture {
+			let (w, h) = texture.oriented_dimensions();
+			parts.push(format!("{} × {}", w, h));
+		}
+		parts.push(format_file_size(metadata.len()));
+		let format = texture.and_then(|texture| texture.source_format).map(|f| format!("{:?}", f));
+		let extension = path.extension().map(|e| e.to_string_lossy().into_owned());
+		if let Some(format) = format.or(extension) {
+			parts.push(format.to_uppercase());
+		}
+		if let Ok(modified) = metadata.modified() {
+			parts.push(format!("Modified {}", humantime::format_rfc3339_seconds(modified)));
+		}
+		self.text_overlay.show(OverlayText {
+			text: parts.join(" · "),
+			duration_ms: PROPERTIES_OVERLAY_MS,
+			position: OverlayPosition::Top,
+		});
+	}
+
+	/// Shows the next image of the folder whose format differs from the shown one. The formats
+	/// are detected from the contents of the files, not from their endings.
+	fn show_next_other_format(&mut self) {
+		let paths = self.playback_manager.image_paths();
+		let (paths, index) = match (paths, self.playback_manager.current_file_index()) {
+			(Some(paths), Some(index)) if index < paths.len() => (paths, index),
+			_ => return,
+		};
+		let format_name = |path: &Path| detect_format(path).ok().map(|format| format.name());
+		let current = format_name(&paths[index]);
+		let next = (index + 1..paths.len())
+			.find(|&i| format_name(&paths[i]).is_some_and(|name| Some(&name) != current.as_ref()));
+		match next {
+			Some(next) => {
+				self.playback_manager.request_load(LoadRequest::LoadAtIndex(next));
+				self.render_validity.invalidate();
+			}
+			None => self.error_overlay.show_status("No different format found"),
+		}
+	}
+
+	/// Shows how long the steps of the last load of the shown image took
+	fn show_load_timings(&mut self) {
+		let breakdown = match self.playback_manager.load_breakdown() {
+			Some(breakdown) => breakdown,
+			None => {
+				self.error_overlay.show_status("The image hasn't finished loading");
+				return;
+			}
+		};
+		let ms = |duration: Duration| duration.as_millis();
+		let text = format!(
+			"File read: {}ms · Format detection: {}ms · Header read: {}ms · Decode: {}ms · \
+			 Texture upload: {}ms · Total: {}ms",
+			ms(breakdown.worker.file_read),
+			ms(breakdown.worker.format_detection),
+			ms(breakdown.worker.header_read),
+			ms(breakdown.worker.decode),
+			ms(breakdown.texture_upload),
+			ms(breakdown.total()),
+		);
+		self.text_overlay.show(OverlayText {
+			text,
+			duration_ms: LOAD_TIMINGS_OVERLAY_MS,
+			position: OverlayPosition::Top,
+		});
+	}
+
+	/// Turns or flips the view of the shown image by `turn`. The file isn't changed.
+	fn turn_view(&mut self, turn: Orientation) {
+		self.view_orientation = self.view_orientation.then(turn);
+		self.render_validity.invalidate();
+	}
+
+	/// Shows the format that the current image was detected as in the bottom bar, when its file
+	/// ending names another format, like `PNG` for `image.backup`
+	fn update_format(&mut self) {
+		let format = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) => {
+				let expected = format_from_extension(path).ok().map(|format| format.name());
+				self.playback_manager
+					.detected_format()
+					.filter(|format| expected.as_ref() != Some(format))
+			}
+			_ => None,
+		};
+		self.bottom_bar.set_format(format.as_deref());
+	}
+}
+
+fn scaling_for_fit_mode(fit_mode: Option<FitMode>, cache: &Mutex<Cache>) -> (ScalingMode, f32) {
+	match fit_mode {
+		Some(FitMode::Window) => (ScalingMode::FitStretch, 0.0),
+		Some(FitMode::Width) => (ScalingMode::FitWidth, 0.0),
+		Some(FitMode::Height) => (ScalingMode::FitHeight, 0.0),
+		Some(FitMode::Original) => (ScalingMode::Fixed, 1.0),
+		Some(FitMode::None) | None => {
+			let cache = cache.lock().unwrap();
+			if cache.image.fit_stretches {
+				(ScalingMode::FitStretch, 0.0)
+			} else {
+				(ScalingMode::FitMin, 0.0)
+			}
+		}
+	}
+}
+
+/// Returns the cache capacity in bytes, the number of loader threads and the presentation
+/// interval from the configuration
+fn playback_settings(configuration: &Configuration) -> (isize, u32, Duration) {
+	let image = configuration.image.as_ref();
+	let cache_capacity = image
+		.and_then(|i| i.cache_size_mb)
+		.map(|mb| mb as isize * 1024 * 1024)
+		.unwrap_or_else(default_cache_capacity);
+	let thread_count = image.and_then(|i| i.thread_count).unwrap_or_else(default_thread_count);
+	let presentation_interval = configuration
+		.slideshow
+		.as_ref()
+		.and_then(|s| s.interval_secs)
+		// The intervals that are too short, including zero and negative ones, get the shortest
+		.and_then(|secs| Duration::try_from_secs_f32(secs.max(0.0)).ok())
+		.map(|interval| interval.max(MIN_PRESENTATION_INTERVAL))
+		.unwrap_or(DEFAULT_PRESENTATION_INTERVAL);
+	(cache_capacity, thread_count.max(1), presentation_interval)
+}
+
+fn scale_quality(configuration: &Configuration) -> ScaleQuality {
+	configuration.image.as_ref().and_then(|i| i.scale_quality).unwrap_or_default()
+}
+
+fn prefetch_window(configuration: &Configuration) -> (usize, usize) {
+	let window = configuration.image.as_ref().and_then(|i| i.prefetch_window);
+	window.unwrap_or(DEFAULT_PREFETCH_WINDOW)
+}
+
+fn max_texture_size(configuration: &Configuration) -> Option<u32> {
+	configuration.image.as_ref().and_then(|i| i.max_texture_size)
+}
+
+pub struct PictureWidget {
+	data: RefCell<PictureWidgetData>,
+}
+impl PictureWidget {
+	#[allow(clippy::too_many_arguments)]
+	pub fn new(
+		display: &Display,
+		window: &Rc<Window>,
+		bottom_bar: Rc<BottomBar>,
+		left_to_pan_hint: Rc<HelpScreen>,
+		copy_notifications: CopyNotifications,
+		error_overlay: ErrorOverlay,
+		text_overlay: TextOverlay,
+		load_log_overlay: LoadLogOverlay,
+		exif_editor: ExifEditor,
+		color_sampler: ColorSampler,
+		settings_panel: Rc<SettingsPanel>,
+		configuration: Rc<RefCell<Configuration>>,
+		cache: Arc<Mutex<Cache>>,
+	) -> PictureWidget {
+		// let program = program!(display,
+		// 	140 => {
+		// 		vertex: shaders::VERTEX_140,
+		// 		fragment: shaders::FRAGMENT_140
+		// 	},
+		// 	110 => {
+		// 		vertex: shaders::VERTEX_110,
+		// 		fragment: shaders::FRAGMENT_110
+		// 	},
+		// )
+		// .unwrap();
+
+		let program = gelatin::shaders::shader_from_source(
+			display,
+			ShaderDescriptor {
+				vertex_shader: shaders::VERTEX_140,
+				fragment_shader: shaders::FRAGMENT_140,
+				outputs_srgb: false,
+				..Default::default()
+			},
+		)
+		.unwrap();
+
+		let fit_mode = configuration.borrow().image.as_ref().and_then(|s| s.fit);
+		let (scaling, img_texel_size) = scaling_for_fit_mode(fit_mode, &cache);
+
+		let antialiasing = configuration
+			.borrow()
+			.image
+			.as_ref()
+			.and_then(|s| s.antialiasing.clone())
+			.unwrap_or_else(|| "auto".into());
+
+		let antialiasing = match antialiasing.as_str() {
+			"auto" => Antialias::Auto,
+			"always" => Antialias::Always,
+			"never" => Antialias::Never,
+			"previous" => cache.lock().unwrap().image.antialiasing,
+			val => {
+				eprintln!("Illegal configuration value {:?} for antialiasing!", val);
+				eprintln!(r#"Allowed values are "auto", "always", "never" and "previous"."#);
+				Antialias::default()
+			}
+		};
+
+		let undo_steps = configuration
+			.borrow()
+			.file_operations
+			.as_ref()
+			.and_then(|f| f.undo_steps)
+			.unwrap_or(DEFAULT_UNDO_STEPS);
+
+		let (cache_capacity, thread_count, presentation_interval) =
+			playback_settings(&configuration.borrow());
+		let mut playback_manager = PlaybackManager::new(cache_capacity, thread_count);
+		playback_manager.set_presentation_interval(presentation_interval);
+		playback_manager.set_scale_quality(scale_quality(&configuration.borrow()));
+		let (ahead, behind) = prefetch_window(&configuration.borrow());
+		playback_manager.set_prefetch_window(ahead, behind);
+		playback_manager.set_max_texture_size(max_texture_size(&configuration.borrow()));
+		let gamma = gamma_correction(&configuration.borrow());
+		let auto_advance = configuration
+			.borrow()
+			.image
+			.as_ref()
+			.and_then(|i| i.auto_advance_on_new_load)
+			.unwrap_or(true);
+
+		let mut data = PictureWidgetData {
+			placement: Default::default(),
+			drawn_bounds: Default::default(),
+			visible: true,
+			prev_draw_size: Default::default(),
+			click: false,
+			hover: false,
+			configuration,
+			cache,
+			playback_manager,
+			clipboard_handler: Some(ClipboardHandler::new()),
+			clipboard_request_was_pending: false,
+			export: ExportJobs::default(),
+			hashes: FileHashes::default(),
+			crops: Crops::default(),
+			presentation: None,
+			split_view: None,
+			search: FileSearch::default(),
+			bookmark_editor: None,
+			layout_editor: None,
+			opening: OpenJobs::default(),
+			commands: CommandJobs::default(),
+			analysis: AnalysisJobs::default(),
+			pending_session: None,
+			format_hints_shown: None,
+			file_op_history: FileOpHistory::new(undo_steps),
+			navigation_history: NavigationHistory::new(),
+			render_validity: Default::default(),
+
+			program,
+			bright_shade: 0.95,
+			aspect_ratio: None,
+			gamma,
+			applied_gamma: gamma,
+			img_texel_size,
+			scaling,
+			img_pos: Default::default(),
+			antialiasing,
+			view_orientation: Orientation::Deg0,
+			hor_pan_input: MovementDir::None,
+			ver_pan_input: MovementDir::None,
+			zoom_input: MovementDir::None,
+			hor_pan_vel: 0.0,
+			ver_pan_vel: 0.0,
+			zoom_vel: 0.0,
+			last_click_time: Instant::now() - Duration::from_secs(10),
+			last_mouse_move: Instant::now(),
+			cursor_hidden: false,
+			loading_indicator_shown: false,
+			last_mouse_pos: Default::default(),
+			panning_2d: false,
+			gestures: GestureRecognizer::new(),
+			dragging: false,
+			panning_vert: false,
+			panning_hor: false,
+			right_press_pos: None,
+			hover_state: HoverState::None,
+			zoom_memory: ZoomMemory::new(cache_capacity),
+			zoom_memory_path: None,
+			initial_pan: None,
+			transition: None,
+			ken_burns: None,
+			last_cam_move_time: Instant::now(),
+			first_draw: true,
+			next_update: NextUpdate::Latest,
+			bottom_bar,
+			left_to_pan_hint,
+			copy_notifications,
+			error_overlay,
+			text_overlay,
+			load_log_overlay,
+			exif_editor,
+			color_sampler,
+			measure_tool: MeasureTool::default(),
+			preload_indicator: None,
+			dir_watcher: None,
+			auto_advance,
+			remote_control: None,
+			empty_hint: None,
+			shortcuts_overlay: None,
+			channel_stats_overlay: None,
+			histogram_panel: None,
+			debug_overlay: None,
+			timecode_overlay: None,
+			last_draw_time: Duration::ZERO,
+			directory_sidebar: None,
+			bookmarks_sidebar: None,
+			menus: Menus::default(),
+			on_reload_config: None,
+			settings_panel,
+			grid_panel: None,
+			gamma_panel: None,
+			print_panel: None,
+			editors: FileEditors::default(),
+			tags: Tags::default(),
+			rating: Rating::new(),
+			applied_fit: fit_mode,
+			window: Rc::downgrade(window),
+		};
+		data.update_scaling_buttons();
+		data.bottom_bar.set_gamma(gamma);
+		PictureWidget { data: RefCell::new(data) }
+	}
+
+	add_common_widget_functions!(data);
+
+	pub fn set_bright_shade(&self, shade: f32) {
+		let mut borrowed = self.data.borrow_mut();
+		borrowed.bright_shade = shade;
+		borrowed.render_validity.invalidate();
+	}
+
+	pub fn set_img_size_to_orig(&self) {
+		let mut borrowed = self.data.borrow_mut();
+		borrowed.set_img_size_to_orig();
+	}
+
+	pub fn set_img_size_to_fit(&self, stretch: bool) {
+		let mut borrowed = self.data.borrow_mut();
+		borrowed.set_img_size_to_fit(stretch);
+	}
+
+	/// Displays the image at a fixed zoom level, where 100 percent means that
+	/// one image texel covers one physical display pixel.
+	pub fn set_zoom_percent(&self, percent: u32) {
+		let mut borrowed = self.data.borrow_mut();
+		borrowed.set_img_size_to_orig();
+		borrowed.img_texel_size = percent as f32 / 100.0;
+	}
+
+	/// Starts in the presentation mode, see `--presentation`
+	pub fn start_presentation(&self) {
+		self.data.borrow_mut().start_presentation();
+	}
+
+	/// Shows the image in a frame of the given aspect ratio with black bars around it
+	pub fn set_aspect_ratio(&self, width: u32, height: u32) {
+		let mut borrowed = self.data.borrow_mut();
+		borrowed.aspect_ratio = Some(width as f32 / height as f32);
+		borrowed.render_validity.invalidate();
+	}
+
+	/// Pans the first displayed image so that the point `(x, y)` in image texels is at the
+	/// center of the widget. Only has an effect at a fixed zoom level.
+	pub fn set_initial_pan(&self, x: Option<f32>, y: Option<f32>) {
+		let mut borrowed = self.data.borrow_mut();
+		borrowed.initial_pan = Some((x, y));
+	}
+
+	/// Applies the settings from the configuration that can be changed in the settings panel
+	pub fn apply_settings(&self) {
+		let mut borrowed = self.data.borrow_mut();
+		let borrowed = &mut *borrowed;
+		let (cache_capacity, thread_count, presentation_interval) =
+			playback_settings(&borrowed.configuration.borrow());
+		borrowed.playback_manager.set_cache_settings(cache_capacity, thread_count);
+		borrowed.zoom_memory.set_cache_capacity(cache_capacity);
+		borrowed.playback_manager.set_presentation_interval(presentation_interval);
+		let quality = scale_quality(&borrowed.configuration.borrow());
+		borrowed.playback_manager.set_scale_quality(quality);
+		let (ahead, behind) = prefetch_window(&borrowed.configuration.borrow());
+		borrowed.playback_manager.set_prefetch_window(ahead, behind);
+		let max_size = max_texture_size(&borrowed.configuration.borrow());
+		borrowed.playback_manager.set_max_texture_size(max_size);
+		let gamma = gamma_correction(&borrowed.configuration.borrow());
+		#[allow(clippy::float_cmp)]
+		if gamma != borrowed.applied_gamma {
+			borrowed.applied_gamma = gamma;
+			borrowed.set_gamma(gamma);
+		}
+
+		let fit_mode = borrowed.configuration.borrow().image.as_ref().and_then(|s| s.fit);
+		if fit_mode != borrowed.applied_fit {
+			let (scaling, img_texel_size) = scaling_for_fit_mode(fit_mode, &borrowed.cache);
+			borrowed.applied_fit = fit_mode;
+			borrowed.scaling = scaling;
+			if scaling == ScalingMode::Fixed {
+				borrowed.img_texel_size = img_texel_size;
+				borrowed.img_pos = borrowed.drawn_bounds.size * 0.5;
+			}
+			borrowed.update_scaling_buttons();
+		}
+		borrowed.render_validity.invalidate();
+	}
+
+	/// Loads every image of the directory in the background and shows the progress on
+	/// the indicator
+	pub fn enable_preloading(&self, indicator: PreloadIndicator) {
+		let mut borrowed = self.data.borrow_mut();
+		borrowed.playback_manager.set_preload_all(true);
+		borrowed.preload_indicator = Some(indicator);
+	}
+
+	/// Lets the user tag the images with `Ctrl+T`, and shows the tags of the current image
+	pub fn set_crop_store(&self, crop_store: CropStore) {
+		self.data.borrow_mut().crops.crop_store = Some(crop_store);
+	}
+
+	pub fn set_tag_store(&self, tag_store: TagStore) {
+		self.data.borrow_mut().tags.tag_store = Some(tag_store);
+	}
+
+	pub fn set_hash_store(&self, hash_store: HashStore) {
+		self.data.borrow_mut().hashes.hash_store = Some(hash_store);
+	}
+
+	/// Adds the new images that the watcher finds to the folder, and shows them as soon as
+	/// they are reported if auto-advance is on
+	pub fn watch_directory(&self, watcher: DirWatcher) {
+		self.data.borrow_mut().dir_watcher = Some(watcher);
+	}
+
+	pub fn set_remote_control(&self, remote_control: RemoteControl) {
+		self.data.borrow_mut().remote_control = Some(remote_control);
+	}
+
+	pub fn set_empty_hint(&self, hint: EmptyHint) {
+		self.data.borrow_mut().empty_hint = Some(hint);
+	}
+
+	pub fn set_debug_overlay(&self, overlay: DebugOverlay) {
+		self.data.borrow_mut().debug_overlay = Some(overlay);
+	}
+
+	pub fn set_timecode_overlay(&self, overlay: TimecodeOverlay) {
+		self.data.borrow_mut().timecode_overlay = Some(overlay);
+	}
+
+	pub fn set_shortcuts_overlay(&self, overlay: ShortcutsOverlay) {
+		self.data.borrow_mut().shortcuts_overlay = Some(overlay);
+	}
+
+	pub fn set_channel_stats_overlay(&self, overlay: ChannelStatsOverlay) {
+		self.data.borrow_mut().channel_stats_overlay = Some(overlay);
+	}
+
+	pub fn set_histogram_panel(&self, panel: HistogramPanel) {
+		self.data.borrow_mut().histogram_panel = Some(panel);
+	}
+
+	pub fn set_directory_sidebar(&self, sidebar: Rc<DirectorySidebar>) {
+		self.data.borrow_mut().directory_sidebar = Some(sidebar);
+	}
+
+	pub fn set_bookmarks_sidebar(&self, sidebar: Rc<BookmarksSidebar>) {
+		self.data.borrow_mut().bookmarks_sidebar = Some(sidebar);
+	}
+
+	pub fn set_context_menu(&self, menu: Rc<ContextMenu>) {
+		self.data.borrow_mut().menus.context_menu = Some(menu);
+	}
+
+	pub fn set_layout_menu(&self, menu: Rc<LayoutMenu>) {
+		self.data.borrow_mut().menus.layout_menu = Some(menu);
+	}
+
+	/// Shows the panels of the layout that was saved under the name
+	pub fn load_layout(&self, name: &str) {
+		let mut borrowed = self.data.borrow_mut();
+		let layout = borrowed.cache.lock().unwrap().layouts.get(name).copied();
+		match layout {
+			Some(layout) => {
+				borrowed.apply_layout(layout);
+				borrowed.error_overlay.show_status(&format!("Loaded the layout {:?}", name));
+			}
+			None => borrowed.error_overlay.show(&format!("There's no layout named {:?}", name)),
+		}
+	}
+
+	/// The callback of `reload_config` replaces the configuration with the one in the config
+	/// file, and returns the settings that only take effect after a restart
+	pub fn set_on_reload_config<F>(&self, callback: F)
+	where
+		F: Fn() -> Result<Vec<&'static str>, String> + 'static,
+	{
+		self.data.borrow_mut().on_reload_config = Some(Rc::new(callback));
+	}
+
+	fn reload_config(&self) {
+		let reload = match self.data.borrow().on_reload_config.clone() {
+			Some(reload) => reload,
+			None => return,
+		};
+		// The callback changes the theme, which needs to borrow the widget
+		let result = reload();
+		if result.is_ok() {
+			self.apply_settings();
+		}
+		let mut borrowed = self.data.borrow_mut();
+		match result {
+			Ok(needing_restart) if needing_restart.is_empty() => {
+				borrowed.text_overlay.show(OverlayText::confirmation("Reloaded the config file"));
+			}
+			Ok(needing_restart) => {
+				let message = format!(
+					"Reloaded the config file. Restart Emulsion to apply {}",
+					needing_restart.join(", ")
+				);
+				borrowed.error_overlay.show(&message);
+			}
+			Err(e) => borrowed.error_overlay.show(&format!("Could not reload the config: {}", e)),
+		}
+		borrowed.render_validity.invalidate();
+	}
+
+	/// Lets the user set up the grid over the image with `grid_settings`
+	pub fn set_grid_panel(&self, panel: Rc<GridPanel>) {
+		self.data.borrow_mut().grid_panel = Some(panel);
+	}
+
+	pub fn set_gamma_panel(&self, panel: Rc<GammaPanel>) {
+		self.data.borrow_mut().gamma_panel = Some(panel);
+	}
+
+	/// Lets the user choose the paper and the DPI with `print_image`
+	pub fn set_print_panel(&self, panel: Rc<PrintPanel>) {
+		self.data.borrow_mut().print_panel = Some(panel);
+	}
+
+	/// Prints the shown image, like the Print button of the print panel does
+	pub fn print(&self, layout: PrintLayout) {
+		self.data.borrow_mut().start_print(layout);
+	}
+
+	/// Sets the exponent of the gamma correction, like the gamma panel does
+	pub fn set_gamma(&self, gamma: f32) {
+		self.data.borrow_mut().set_gamma(gamma);
+	}
+
+	/// Does the same as the shortcut of the item
+	pub fn run_menu_item(&self, item: MenuItem) {
+		let mut borrowed = self.data.borrow_mut();
+		match item {
+			MenuItem::OpenExternally => borrowed.open_externally(),
+			MenuItem::CopyPath => borrowed.copy_path(),
+			MenuItem::CopyImage => borrowed.copy_image(),
+			MenuItem::RotateCw => borrowed.turn_view(Orientation::Deg270),
+			MenuItem::RotateCcw => borrowed.turn_view(Orientation::Deg90),
+			MenuItem::FlipHorizontal => borrowed.turn_view(Orientation::Deg0HorFlip),
+			MenuItem::FlipVertical => borrowed.turn_view(Orientation::Deg180HorFlip),
+			MenuItem::Delete => borrowed.delete_image(),
+			MenuItem::Properties => borrowed.show_properties(),
+		}
+	}
+
+	pub fn jump_to_index(&self, index: u32) {
+		let mut borrowed = self.data.borrow_mut();
+		borrowed.playback_manager.request_load(LoadRequest::LoadAtIndex(index as usize));
+		borrowed.render_validity.invalidate();
+	}
+
+	pub fn jump_to_path<P: Into<PathBuf>>(&self, path: P) {
+		let mut borrowed = self.data.borrow_mut();
+		let path = path.into();
+		if download::is_url(&path) {
+			borrowed.start_download(path.to_string_lossy().into_owned());
+			return;
+		}
+		borrowed.open_path(path);
+	}
+
+	fn handle_key_input(&self, input_key: &str, modifiers: ModifiersState) {
+		let mut borrowed = self.data.borrow_mut();
+		macro_rules! triggered {
+			($action_name:ident) => {
+				action_triggered(&borrowed.configuration, $action_name, input_key, modifiers)
+			};
+		}
+		if borrowed.settings_panel.is_open() {
+			// The panel is modal, so every other action is ignored while it's open
+			if triggered!(SETTINGS_NAME) || triggered!(ESCAPE_NAME) {
+				borrowed.settings_panel.close();
+			}
+			return;
+		}
+		if let Some(overlay) = borrowed.channel_stats_overlay.as_ref().filter(|o| o.is_open()) {
+			// The table is modal, so any key closes it
+			overlay.close();
+			borrowed.render_validity.invalidate();
+			return;
+		}
+		if triggered!(SETTINGS_NAME) {
+			if matches!(
+				borrowed.playback_manager.playback_state(),
+				PlaybackState::Present | PlaybackState::RandomPresent
+			) {
+				borrowed.playback_manager.pause_playback();
+			}
+			borrowed.settings_panel.open();
+			borrowed.render_validity.invalidate();
+			return;
+		}
+		if let Some(grid_panel) = borrowed.grid_panel.clone() {
+			if grid_panel.is_open() {
+				if triggered!(GRID_SETTINGS_NAME) || triggered!(ESCAPE_NAME) {
+					grid_panel.close();
+					borrowed.render_validity.invalidate();
+				}
+				return;
+			}
+			if triggered!(GRID_SETTINGS_NAME) {
+				// Opening the panel may show the grid, which redraws the widget
+				drop(borrowed);
+				grid_panel.open();
+				self.data.borrow_mut().render_validity.invalidate();
+				return;
+			}
+		}
+		if let Some(print_panel) = borrowed.print_panel.clone() {
+			if print_panel.is_open() {
+				if triggered!(PRINT_IMAGE_NAME) || triggered!(ESCAPE_NAME) {
+					print_panel.close();
+					borrowed.render_validity.invalidate();
+				} else if input_key == "return" {
+					// Printing starts a job on the widget, so it must not be borrowed
+					drop(borrowed);
+					print_panel.print();
+					self.data.borrow_mut().render_validity.invalidate();
+				}
+				return;
+			}
+			if triggered!(PRINT_IMAGE_NAME) {
+				if borrowed.shown_file().is_some() {
+					print_panel.open(borrowed.default_print_layout());
+				} else {
+					borrowed.error_overlay.show_status("There's no image to print");
+				}
+				borrowed.render_validity.invalidate();
+				return;
+			}
+		}
+		if borrowed.presentation.is_some()
+			&& (triggered!(PRESENTATION_MODE_NAME) || triggered!(ESCAPE_NAME))
+		{
+			borrowed.stop_presentation();
+			return;
+		}
+		if triggered!(PRESENTATION_MODE_NAME) {
+			borrowed.start_presentation();
+			return;
+		}
+		if triggered!(HISTORY_BACK_NAME) || triggered!(HISTORY_FORWARD_NAME) {
+			let back = triggered!(HISTORY_BACK_NAME);
+			let history = &mut borrowed.navigation_history;
+			let path = if back { history.back() } else { history.forward() };
+			match path.map(Path::to_owned) {
+				Some(path) => borrowed.open_path(path),
+				None if back => {
+					borrowed.error_overlay.show_status("No earlier image in the history")
+				}
+				None => borrowed.error_overlay.show_status("No later image in the history"),
+			}
+			return;
+		}
+		if triggered!(NEXT_OTHER_FORMAT_NAME) {
+			borrowed.show_next_other_format();
+			return;
+		}
+		if triggered!(RELOAD_CONFIG_NAME) {
+			drop(borrowed);
+			self.reload_config();
+			return;
+		}
+		if triggered!(RATING_MODE_NAME) {
+			borrowed.rating.rating_mode = !borrowed.rating.rating_mode;
+			let message = if borrowed.rating.rating_mode {
+				"Rating mode: 1-9 rate the image, 0 removes the rating"
+			} else {
+				"Rating mode off"
+			};
+			borrowed.error_overlay.show_status(message);
+			borrowed.render_validity.invalidate();
+			return;
+		}
+		let has_command_modifier =
+			modifiers.control_key() || modifiers.alt_key() || modifiers.super_key();
+		if borrowed.rating.rating_mode && !has_command_modifier {
+			if let Some(digit) = single_digit(input_key) {
+				borrowed.set_rating(if digit == 0 { None } else { Some(digit) });
+				borrowed.render_validity.invalidate();
+				return;
+			}
+		}
+		if triggered!(TOGGLE_FULLSCREEN_NAME) {
+			borrowed.toggle_fullscreen();
+		}
+		if triggered!(COLOR_SAMPLER_NAME) {
+			borrowed.toggle_color_sampler();
+		}
+		if triggered!(COPY_COLOR_NAME) {
+			borrowed.copy_picked_color();
+		}
+		if triggered!(DECODER_INFO_NAME) {
+			borrowed.start_decoder_info();
+			return;
+		}
+		if triggered!(HISTOGRAM_NAME) {
+			borrowed.toggle_histogram();
+			return;
+		}
+		let gamma_panel_open = borrowed.gamma_panel.as_ref().is_some_and(|p| p.is_open());
+		if triggered!(GAMMA_PANEL_NAME) || (gamma_panel_open && triggered!(ESCAPE_NAME)) {
+			borrowed.toggle_gamma_panel();
+			return;
+		}
+		if triggered!(SPLIT_VIEW_NAME) || (borrowed.split_view.is_some() && triggered!(ESCAPE_NAME))
+		{
+			borrowed.toggle_split_view();
+			return;
+		}
+		if borrowed.color_sampler.is_active() && triggered!(ESCAPE_NAME) {
+			borrowed.stop_color_sampler();
+			return;
+		}
+		if triggered!(COPY_COLOR_PROFILE_NAME) {
+			borrowed.copy_color_profile();
+		}
+		if triggered!(SHOW_ON_MAP_NAME) {
+			borrowed.show_on_map();
+		}
+		if triggered!(MEASURE_NAME) {
+			borrowed.toggle_measure_tool();
+		}
+		if borrowed.measure_tool.is_active() && triggered!(ESCAPE_NAME) {
+			borrowed.stop_measure_tool();
+			return;
+		}
+		if triggered!(SET_CROP_NAME) {
+			borrowed.start_crop_selection();
+		}
+		if triggered!(CLEAR_CROP_NAME) {
+			borrowed.clear_crop();
+		}
+		if borrowed.crops.crop_selection.is_some() && triggered!(ESCAPE_NAME) {
+			borrowed.stop_crop_selection();
+			return;
+		}
+		if triggered!(ESCAPE_NAME) {
+			if let Some(window) = borrowed.window.upgrade() {
+				if window.fullscreen() {
+					borrowed.set_fullscreen(false);
+				} else {
+					request_exit();
+				}
+			}
+		}
+		if triggered!(PLAY_ANIM_NAME) {
+			match borrowed.playback_manager.playback_state() {
+				PlaybackState::Forward => borrowed.playback_manager.pause_playback(),
+				_ => borrowed.playback_manager.start_playback_forward(),
+			}
+		}
+		if triggered!(IMG_PREV_NAME) {
+			borrowed.playback_manager.request_load(LoadRequest::LoadPrevious);
+			borrowed.render_validity.invalidate();
+		}
+		if triggered!(IMG_NEXT_NAME) {
+			borrowed.playback_manager.request_load(LoadRequest::LoadNext);
+			borrowed.render_validity.invalidate();
+		}
+		if triggered!(IMG_FIT_NAME) {
+			borrowed.set_img_size_to_fit(true);
+		}
+		if triggered!(IMG_FIT_BEST_NAME) {
+			borrowed.set_img_size_to_fit(false);
+		}
+		if triggered!(IMG_ORIG_NAME) {
+			borrowed.set_img_size_to_orig();
+		}
+		if triggered!(TOGGLE_ANTIALIAS_NAME) {
+			borrowed.toggle_antialias();
+		}
+		if triggered!(SET_AUTOMATIC_ANTIALIAS_NAME) {
+			borrowed.set_automatic_antialias();
+		}
+		if triggered!(PLAY_PRESENT_NAME) {
+			match borrowed.playback_manager.playback_state() {
+				PlaybackState::Present => borrowed.playback_manager.pause_playback(),
+				_ => borrowed.playback_manager.start_presentation(),
+			}
+			borrowed.render_validity.invalidate();
+		}
+		if triggered!(PLAY_PRESENT_RND_NAME) {
+			match borrowed.playback_manager.playback_state() {
+				PlaybackState::RandomPresent => borrowed.playback_manager.pause_playback(),
+				_ => borrowed.playback_manager.start_random_presentation(),
+			}
+			borrowed.render_validity.invalidate();
+		}
+		if triggered!(IMG_DEL_NAME) {
+			borrowed.delete_image();
+		}
+		if triggered!(ROTATE_CW_NAME) {
+			borrowed.turn_view(Orientation::Deg270);
+		}
+		if triggered!(ROTATE_CCW_NAME) {
+			borrowed.turn_view(Orientation::Deg90);
+		}
+		if triggered!(FLIP_HORIZONTAL_NAME) {
+			borrowed.turn_view(Orientation::Deg0HorFlip);
+		}
+		if triggered!(FLIP_VERTICAL_NAME) {
+			borrowed.turn_view(Orientation::Deg180HorFlip);
+		}
+		if triggered!(COPY_METADATA_NAME) {
+			borrowed.copy_metadata();
+		}
+		if triggered!(COPY_PATH_NAME) {
+			borrowed.copy_path();
+		}
+		if triggered!(OPEN_EXTERNALLY_NAME) {
+			borrowed.open_externally();
+		}
+		if triggered!(PROPERTIES_NAME) {
+			borrowed.show_properties();
+		}
+		if triggered!(NEW_WINDOW_NAME) {
+			if let LoadedImgPath::Loaded(path) = borrowed.playback_manager.shown_file_path() {
+				open_in_new_process(path);
+			}
+		}
+		if triggered!(NEW_EMPTY_WINDOW_NAME) {
+			open_empty_window();
+		}
+		if triggered!(AUTO_ADVANCE_NAME) {
+			borrowed.auto_advance = !borrowed.auto_advance;
+			let text = if borrowed.auto_advance { "Live view on" } else { "Live view off" };
+			borrowed.text_overlay.show(OverlayText::confirmation(text));
+		}
+		if triggered!(CLOSE_FILE_NAME) {
+			borrowed.close_file();
+		}
+		if triggered!(SNAP_WINDOW_NAME) {
+			borrowed.snap_window_to_image();
+		}
+		if triggered!(UNDO_FILE_OP_NAME) {
+			borrowed.undo_file_operation();
+		}
+		if triggered!(SHOW_INTEGRITY_NAME) {
+			borrowed.toggle_integrity();
+		}
+		if triggered!(UNDO_DELETE_NAME) {
+			borrowed.undo_delete();
+		}
+		if triggered!(RENAME_NAME) {
+			borrowed.start_rename();
+			if borrowed.editors.rename_editor.is_some() {
+				return;
+			}
+		}
+		if triggered!(MOVE_TO_NEW_FOLDER_NAME) {
+			borrowed.start_move_editing();
+			if borrowed.editors.move_editor.is_some() {
+				return;
+			}
+		}
+		if triggered!(TOGGLE_SIDEBAR_NAME) {
+			if let Some(sidebar) = &borrowed.directory_sidebar {
+				sidebar.toggle();
+			}
+		}
+		if triggered!(TOGGLE_BOOKMARKS_NAME) {
+			if let Some(sidebar) = &borrowed.bookmarks_sidebar {
+				sidebar.toggle();
+			}
+		}
+		if triggered!(ADD_BOOKMARK_NAME) {
+			borrowed.start_bookmark();
+			if borrowed.bookmark_editor.is_some() {
+				return;
+			}
+		}
+		if triggered!(SAVE_LAYOUT_NAME) {
+			borrowed.start_layout_saving();
+			return;
+		}
+		if triggered!(LOAD_LAYOUT_NAME) {
+			borrowed.open_layout_menu();
+			return;
+		}
+		if triggered!(UPLOAD_IMAGE_NAME) {
+			borrowed.start_upload();
+		}
+		if triggered!(RUN_COMMAND_NAME) {
+			borrowed.start_command_editing();
+			if borrowed.commands.command_editor.is_some() {
+				return;
+			}
+		}
+		if triggered!(SEARCH_FILES_NAME) {
+			borrowed.start_search();
+			if borrowed.search.search_editor.is_some() {
+				return;
+			}
+		}
+		if triggered!(EDIT_TAGS_NAME) {
+			borrowed.start_tag_editing();
+			if borrowed.tags.tag_editor.is_some() {
+				return;
+			}
+		}
+		if triggered!(PASTE_NAME) {
+			borrowed.paste_from_clipboard();
+		}
+		if triggered!(EXPORT_VIEW_NAME) {
+			borrowed.export_view();
+		}
+		if triggered!(EXPORT_SVG_NAME) {
+			borrowed.export_view_svg();
+		}
+		if triggered!(EXPORT_PLAYLIST_NAME) {
+			borrowed.export_playlist();
+		}
+		if triggered!(SAVE_SESSION_NAME) {
+			borrowed.save_session();
+		}
+		if triggered!(OPEN_FILE_NAME) {
+			borrowed.start_open_file();
+		}
+		if triggered!(OPEN_TERMINAL_NAME) {
+			borrowed.open_terminal();
+		}
+		if triggered!(EDIT_EXIF_NAME) {
+			borrowed.start_exif_editing();
+			if borrowed.exif_editor.is_open() {
+				return;
+			}
+		}
+		if triggered!(MAKE_PDF_NAME) {
+			borrowed.start_pdf_export();
+		}
+		if triggered!(MAKE_TIFF_NAME) {
+			borrowed.start_tiff_export();
+		}
+		if triggered!(EXPORT_WATERMARKED_NAME) {
+			borrowed.start_watermark_export();
+		}
+		if triggered!(RELOAD_FOLDER_NAME) {
+			borrowed.start_folder_reload();
+		}
+		if triggered!(GAMMA_UP_NAME) {
+			borrowed.adjust_gamma(GAMMA_STEP);
+		}
+		if triggered!(GAMMA_DOWN_NAME) {
+			borrowed.adjust_gamma(-GAMMA_STEP);
+		}
+		if triggered!(TOGGLE_LOAD_LOG_NAME) {
+			borrowed.load_log_overlay.toggle();
+		}
+		if triggered!(SHOW_LOAD_TIMINGS_NAME) {
+			borrowed.show_load_timings();
+		}
+		if triggered!(CHANNEL_STATS_NAME) {
+			borrowed.start_channel_stats();
+		}
+		if triggered!(TOGGLE_DEBUG_NAME) {
+			if let Some(overlay) = &mut borrowed.debug_overlay {
+				overlay.toggle();
+			}
+		}
+		if triggered!(TOGGLE_TIMECODE_NAME) {
+			if let Some(overlay) = &mut borrowed.timecode_overlay {
+				overlay.toggle();
+			}
+			borrowed.render_validity.invalidate();
+		}
+		if triggered!(TOGGLE_SHORTCUTS_NAME) {
+			let borrowed = &mut *borrowed;
+			if let Some(overlay) = &mut borrowed.shortcuts_overlay {
+				overlay.toggle(&borrowed.configuration.borrow());
+			}
+		}
+		if triggered!(TOGGLE_STAR_NAME) {
+			borrowed.toggle_star();
+		}
+		if triggered!(SET_WALLPAPER_NAME) {
+			if let LoadedImgPath::Loaded(path) = borrowed.playback_manager.shown_file_path() {
+				match set_wallpaper(&path.clone()) {
+					Ok(()) => borrowed.error_overlay.show_status("Set as the desktop wallpaper"),
+					Err(e) => borrowed.error_overlay.show(&e),
+				}
+			}
+		}
+		if triggered!(IMG_COPY_NAME) {
+			borrowed.copy_image();
+		}
+		if let LoadedImgPath::Loaded(img_path) = borrowed.playback_manager.shown_file_path() {
+			if let Some(folder_path) = img_path.parent() {
+				let img_and_folder = (img_path.to_str(), folder_path.to_str());
+				if let (Some(img_path), Some(folder_path)) = img_and_folder {
+					execute_triggered_commands(
+						borrowed.configuration.clone(),
+						input_key,
+						modifiers,
+						img_path,
+						folder_path,
+					);
+				} else {
+					log::error!("Could not convert the image path to utf8. Path: '{:?}'", img_path);
+				}
+			} else {
+				log::error!("Could not get parent folder for the image path {:?}", img_path);
+			}
+		}
+	}
+}
+
+impl Widget for PictureWidget {
+	fn before_draw(&self, window: &Window) -> NextUpdate {
+		let mut data = self.data.borrow_mut();
+		let data = &mut *data;
+		if !data.visible {
+			return NextUpdate::Latest;
+		}
+		if data.first_draw {
+			// Don't block on the main thread and
+			// wait on the image to be loaded on the first draw,
+			// instead let the ui draw itself first and then we can wait.
+			data.first_draw = false;
+			data.next_update = NextUpdate::Soonest;
+			return data.next_update;
+		}
+		let now = Instant::now();
+		// The previous image fades out as it was turned
+		let prev_texture = data.get_texture();
+		let prev_path = data.playback_manager.shown_file_path().clone();
+		let (prev_texel_size, prev_pos) = (data.img_texel_size, data.img_pos);
+		data.next_update = data.playback_manager.update_image(window);
+		if let Some(error) = data.playback_manager.take_load_error() {
+			data.error_overlay.show(&format!("Could not open the image: {}", error));
+		}
+		if prev_path != *data.playback_manager.shown_file_path() {
+			data.view_orientation = Orientation::Deg0;
+		}
+		let new_texture = data.playback_manager.image_texture();
+		let transition_ms = data.transition_ms();
+		// Only crossfade when switching to another file, not between the frames of an animation
+		if transition_ms > 0 && prev_path != *data.playback_manager.shown_file_path() {
+			if let (Some(from), Some(_)) = (&prev_texture, &new_texture) {
+				data.transition = Some(CrossfadeTransition {
+					from: from.clone(),
+					from_texel_size: prev_texel_size,
+					from_pos: prev_pos,
+					start: now,
+					duration_ms: transition_ms,
+				});
+			}
+		}
+		if let Some(transition) = &data.transition {
+			if transition.alpha(now) >= 1.0 {
+				// Dropping the transition releases the texture of the previous image
+				data.transition = None;
+			} else {
+				data.next_update = NextUpdate::Soonest;
+			}
+			data.render_validity.invalidate();
+		}
+		let presenting = matches!(
+			data.playback_manager.playback_state(),
+			PlaybackState::Present | PlaybackState::RandomPresent
+		);
+		// The images of a presentation aren't navigated to by the user
+		if let LoadedImgPath::Loaded(path) = data.playback_manager.shown_file_path() {
+			if !presenting && prev_path != *data.playback_manager.shown_file_path() {
+				let path = path.clone();
+				data.navigation_history.push(path);
+			}
+		}
+		if presenting && data.ken_burns_enabled() {
+			if data.ken_burns.is_none() || prev_path != *data.playback_manager.shown_file_path() {
+				let duration = data.playback_manager.presentation_interval();
+				data.ken_burns = Some(KenBurnsAnimator::new(now, duration));
+			}
+			data.next_update = NextUpdate::Soonest;
+			data.render_validity.invalidate();
+		} else if data.ken_burns.take().is_some() {
+			data.render_validity.invalidate();
+		}
+		if data.zoom_memory_enabled() {
+			data.update_zoom_memory();
+		}
+		data.update_crop();
+		data.update_session();
+		let show_indicator_at =
+			data.playback_manager.loading_since().map(|since| since + LOADING_INDICATOR_DELAY);
+		let show_indicator = show_indicator_at.is_some_and(|show_at| now >= show_at);
+		if show_indicator || data.loading_indicator_shown {
+			// Redrawn on every frame to keep the arc spinning, and once more to remove it
+			data.render_validity.invalidate();
+		}
+		data.loading_indicator_shown = show_indicator;
+		match show_indicator_at {
+			Some(_) if show_indicator => data.next_update = NextUpdate::Soonest,
+			Some(show_at) => {
+				data.next_update = data.next_update.aggregate(NextUpdate::WaitUntil(show_at))
+			}
+			None => {}
+		}
+		let curr_file_index = data.playback_manager.current_file_index();
+		let curr_dir_len = data.playback_manager.current_dir_len();
+		if let (Some(curr_file_index), Some(curr_dir_len)) = (curr_file_index, curr_dir_len) {
+			// dbg!(curr_file_index);
+			// dbg!(curr_dir_len);
+			data.bottom_bar.slider.set_steps(curr_dir_len as u32, curr_file_index as u32);
+		}
+		//data.slider.set_step_bg(data.playback_manager.cached_from_dir());
+		data.update_rating();
+		data.update_format();
+		data.update_tags();
+		let playback_state = data.playback_manager.playback_state();
+		let book_page = data.playback_manager.book_page();
+		data.set_window_title_filename(
+			window,
+			playback_state,
+			data.playback_manager.shown_file_path(),
+			book_page,
+		);
+		let texture_changed = match (prev_texture, new_texture) {
+			(Some(prev_tex), Some(new_tex)) => !Rc::ptr_eq(&prev_tex.tex_grid, &new_tex.tex_grid),
+			(prev_tex, new_tex) => prev_tex.is_none() != new_tex.is_none(),
+		};
+		if texture_changed {
+			data.render_validity.invalidate();
+			// A newly loaded image or frame is shown right away, regardless of the frame rate
+			window.force_next_redraw();
+		}
+		if let Some(clipboard_handler) = &data.clipboard_handler {
+			let clipboard_result = clipboard_handler.try_get_result();
+			let request_pending = clipboard_result.is_none();
+			if data.clipboard_request_was_pending != request_pending {
+				match clipboard_result {
+					Some(succeeded) => data.copy_notifications.set_finished(succeeded),
+					None => data.copy_notifications.set_started(),
+				}
+				data.clipboard_request_was_pending = request_pending;
+			} else if request_pending {
+				let next_update = now + Duration::from_millis(100);
+				data.next_update = data.next_update.aggregate(NextUpdate::WaitUntil(next_update));
+			}
+		}
+		let exporting = data.export.update(&mut data.error_overlay);
+		let opening = data.update_opening();
+		let running_command = data.update_commands();
+		let analysing = data.update_analysis();
+		data.update_format_hints();
+		let searching = data.update_search();
+		let hashing = data.update_integrity();
+		if let LoadedImgPath::Loaded(path) = data.playback_manager.shown_file_path() {
+			let path = path.clone();
+			data.measure_tool.follow_image(&path);
+		}
+		if exporting || opening || running_command || analysing || searching || hashing {
+			let next_update = now + Duration::from_millis(100);
+			data.next_update = data.next_update.aggregate(NextUpdate::WaitUntil(next_update));
+		}
+		if data.zoom_input.moving() || data.hor_pan_input.moving() || data.ver_pan_input.moving() {
+			data.render_validity.invalidate();
+			data.next_update = NextUpdate::Soonest;
+		}
+		let next_copy_noti_update = data.copy_notifications.update();
+		data.next_update = data.next_update.aggregate(next_copy_noti_update);
+		let next_error_update = data.error_overlay.update();
+		data.next_update = data.next_update.aggregate(next_error_update);
+		let next_text_update = data.text_overlay.update();
+		data.next_update = data.next_update.aggregate(next_text_update);
+		if let Some(watcher) = &data.dir_watcher {
+			if let Some(path) = watcher.try_recv_latest() {
+				// The new file is only in the list of images after the folder is listed again
+				if let Err(e) = data.playback_manager.update_directory() {
+					eprintln!("Error while updating directory {:?}", e);
+				}
+				if data.auto_advance && data.is_newer_than_shown(&path) {
+					data.playback_manager.request_load(LoadRequest::FilePath(path));
+				}
+				data.render_validity.invalidate();
+			}
+			let next_poll = Instant::now() + dir_watcher::POLL_INTERVAL;
+			data.next_update = data.next_update.aggregate(NextUpdate::WaitUntil(next_poll));
+		}
+		let commands = data.remote_control.as_ref().map(|remote| remote.try_recv_all());
+		if let Some(commands) = commands {
+			for command in commands {
+				data.run_remote_command(command);
+			}
+			let next_poll = Instant::now() + remote_control::POLL_INTERVAL;
+			data.next_update = data.next_update.aggregate(NextUpdate::WaitUntil(next_poll));
+		}
+		if !matches!(data.playback_manager.shown_file_path(), LoadedImgPath::NotYetLoaded) {
+			if let Some(hint) = data.empty_hint.as_ref().filter(|hint| hint.visible()) {
+				hint.set_visible(false);
+			}
+		}
+		let preload_progress = data.playback_manager.preload_progress();
+		if let Some(indicator) = &mut data.preload_indicator {
+			indicator.update(preload_progress);
+		}
+		let data = &mut *data;
+		data.load_log_overlay
+			.update(data.playback_manager.load_log(), data.playback_manager.queue_depth());
+		if let Some(overlay) = &mut data.shortcuts_overlay {
+			overlay.update();
+		}
+		if let Some(overlay) = &mut data.timecode_overlay {
+			let path = match data.playback_manager.shown_file_path() {
+				LoadedImgPath::Loaded(path) => Some(path.as_path()),
+				_ => None,
+			};
+			let fps = data.configuration.borrow().image.as_ref().and_then(|i| i.sequence_fps);
+			let fps = fps.filter(|fps| *fps > 0.0).unwrap_or(DEFAULT_SEQUENCE_FPS);
+			if overlay.update(path, fps) {
+				data.render_validity.invalidate();
+			}
+		}
+		if let Some(overlay) = &mut data.debug_overlay {
+			let stats = data.playback_manager.cache_stats();
+			if let Some(refresh_at) = overlay.update(data.last_draw_time, &stats) {
+				data.next_update = data.next_update.aggregate(NextUpdate::WaitUntil(refresh_at));
+			}
+		}
+		data.update_cursor_visibility(window, Instant::now());
+		data.next_update
+	}
+
+	fn draw(&self, target: &mut Frame, context: &DrawContext) -> Result<NextUpdate, WidgetError> {
+		let draw_start = Instant::now();
+		let texture;
+		{
+			let mut data = self.data.borrow_mut();
+			if !data.visible {
+				return Ok(data.next_update);
+			}
+			data.update_image_transform(context.dpi_scale_factor);
+			data.apply_camera_movement(context.dpi_scale_factor);
+			texture = data.get_texture();
+			if data.presentation.is_some() {
+				const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+				context.clear_color(target, BLACK, Some(data.drawn_bounds));
+			}
+		}
+		if let Some(texture) = texture {
+			let data = self.data.borrow();
+			let mut alpha = 1.0;
+			if let Some(transition) = &data.transition {
+				let from = transition.from.clone();
+				let (texel_size, pos) = (transition.from_texel_size, transition.from_pos);
+				let style = TileStyle { opacity: 1.0, gamma: data.gamma, clip: None };
+				draw_tex_grid(&data, target, context, from, texel_size, pos, style);
+				alpha = transition.alpha(Instant::now());
+			}
+			let (mut texel_size, mut pos) = (data.img_texel_size, data.img_pos);
+			if let Some(ken_burns) = &data.ken_burns {
+				let (zoom, pan) = ken_burns.zoom_and_pan(Instant::now());
+				// Only move the image as far as the zoom allows, so that the edges of the image
+				// don't come further into the view than they were without the effect
+				let (w, h) = texture.oriented_dimensions();
+				let display_w = w as f32 * texel_size / context.dpi_scale_factor;
+				let display_h = h as f32 * texel_size / context.dpi_scale_factor;
+				let slack = (zoom - 1.0) * 0.5;
+				pos.vec -= Vector2::new(pan.x * display_w, pan.y * display_h) * slack;
+				texel_size *= zoom;
+			}
+			let mut style = TileStyle { opacity: alpha, gamma: data.gamma, clip: None };
+			if let Some(divider_x) = data.split_divider_x() {
+				let bounds = data.drawn_bounds;
+				let left_size = LogicalVector::new(divider_x - bounds.left(), bounds.size.vec.y);
+				let right_pos = LogicalVector::new(divider_x, bounds.top());
+				let right_size = LogicalVector::new(bounds.right() - divider_x, bounds.size.vec.y);
+				if let Some(original) = data.playback_manager.image_texture() {
+					let clip = Some(LogicalRect { pos: bounds.pos, size: left_size });
+					let original_style = TileStyle { opacity: alpha, gamma: 1.0, clip };
+					draw_tex_grid(
+						&data,
+						target,
+						context,
+						original,
+						texel_size,
+						pos,
+						original_style,
+					);
+				}
+				style.clip = Some(LogicalRect { pos: right_pos, size: right_size });
+			}
+			draw_tex_grid(&data, target, context, texture, texel_size, pos, style);
+			if let Some(divider_x) = data.split_divider_x() {
+				const DIVIDER_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.9];
+				let top = LogicalVector::new(divider_x, data.drawn_bounds.top());
+				let bottom = LogicalVector::new(divider_x, data.drawn_bounds.bottom());
+				context.draw_line(target, top, bottom, 2.0, DIVIDER_COLOR);
+			}
+		}
+		let borrowed = self.data.borrow();
+		if let Some(since) = borrowed.playback_manager.loading_since() {
+			if borrowed.loading_indicator_shown {
+				draw_loading_indicator(target, context, borrowed.drawn_bounds.center(), since);
+			}
+		}
+		let grid = borrowed.configuration.borrow().grid.clone();
+		if let Some(grid) = grid.filter(|grid| grid.visible == Some(true)) {
+			draw_grid(&borrowed, target, context, &grid);
+		}
+		if borrowed.crops.crop_selection.is_some() {
+			draw_crop_selection(&borrowed, target, context);
+		}
+		if borrowed.measure_tool.is_active() {
+			draw_measurements(&borrowed, target, context);
+		}
+		if borrowed.aspect_ratio.is_some() {
+			draw_bars(target, context, borrowed.drawn_bounds, borrowed.frame_rect());
+		}
+		let next_update = borrowed.next_update;
+		drop(borrowed);
+		self.data.borrow_mut().last_draw_time = draw_start.elapsed();
+		Ok(next_update)
+	}
+
+	fn layout(&self, available_space: LogicalRect) {
+		let mut borrowed = self.data.borrow_mut();
+		borrowed.default_layout(available_space);
+		borrowed.hover = borrowed.drawn_bounds.contains(borrowed.last_mouse_pos);
+	}
+
+	fn handle_event(&self, event: &Event) {
+		if !self.data.borrow().visible {
+			return;
+		}
+		if let EventKind::MouseMove = event.kind {
+			let mut borrowed = self.data.borrow_mut();
+			borrowed.last_mouse_move = Instant::now();
+			if borrowed.cursor_hidden {
+				if let Some(window) = borrowed.window.upgrade() {
+					window.window_mut().set_cursor_visible(true);
+				}
+				borrowed.cursor_hidden = false;
+			}
+		}
+		if self.data.borrow().panel_open() {
+			// Only key presses are handled to allow closing the panel. Releases are still
+			// processed so that no movement gets stuck.
+			if !matches!(event.kind, EventKind::KeyInput { .. }) {
+				return;
+			}
+		}
+		match event.kind {
+			EventKind::MouseMove => {
+				let mut borrowed = self.data.borrow_mut();
+				borrowed.hover = borrowed.drawn_bounds.contains(event.cursor_pos);
+				if borrowed.drag_split_divider(event.cursor_pos) {
+					borrowed.last_mouse_pos = event.cursor_pos;
+					return;
+				}
+				let panning_2d = borrowed.panning_2d || borrowed.dragging;
+				if panning_2d || borrowed.panning_hor || borrowed.panning_vert {
+					let mut delta = event.cursor_pos - borrowed.last_mouse_pos;
+					if !panning_2d {
+						if !borrowed.panning_hor {
+							// only vertical panning
+							delta.vec.x = 0.0;
+						}
+						if !borrowed.panning_vert {
+							// only horzontal panning
+							delta.vec.y = 0.0;
+						}
+					}
+					borrowed.pan_image(delta);
+				}
+				if let Some(press_pos) = borrowed.right_press_pos {
+					let dragged = (event.cursor_pos - press_pos).vec.magnitude();
+					if dragged > CONTEXT_MENU_MAX_DRAG {
+						// The right button doesn't pan, so the hint tells which one does
+						borrowed.left_to_pan_hint.set_visible(true);
+					}
+				}
+				borrowed.last_mouse_pos = event.cursor_pos;
+				borrowed.update_color_sampler(false);
+				let cursor_pos = borrowed.last_mouse_pos - borrowed.drawn_bounds.pos;
+				let area_size = borrowed.drawn_bounds.size;
+				if let Some(panel) = &mut borrowed.histogram_panel {
+					if panel.hover(cursor_pos, area_size) {
+						borrowed.render_validity.invalidate();
+					}
+				}
+				borrowed.drag_crop_selection(None);
+			}
+			EventKind::MouseButton { state, button, .. } => match button {
+				MouseButton::Left => {
+					let mut borrowed = self.data.borrow_mut();
+					if borrowed.click_open_menu(state, event.cursor_pos) {
+						return;
+					}
+					let on_divider = match state {
+						ElementState::Pressed => borrowed.grab_split_divider(event.cursor_pos),
+						ElementState::Released => borrowed.release_split_divider(),
+					};
+					if on_divider {
+						return;
+					}
+					if borrowed.color_sampler.is_active() && borrowed.hover {
+						// Clicks sample the color instead of panning while the sampler is on
+						if state == ElementState::Pressed {
+							borrowed.update_color_sampler(true);
+						}
+						return;
+					}
+					if borrowed.measure_tool.is_active() && borrowed.hover {
+						if state == ElementState::Pressed {
+							borrowed.click_measure_tool();
+						}
+						return;
+					}
+					if borrowed.click_crop_selection(state) {
+						return;
+					}
+					if state == ElementState::Pressed {
+						if borrowed.hover {
+							borrowed.click = true;
+							let dpi_scale = match borrowed.window.upgrade() {
+								Some(window) => window.window_mut().scale_factor() as f32,
+								None => 1.0,
+							};
+							// A picture that fits the widget has nowhere to go
+							let dragging = borrowed.img_exceeds_bounds(dpi_scale);
+							borrowed.set_dragging(dragging);
+						}
+					} else {
+						borrowed.set_dragging(false);
+						// A release that closed the context menu isn't the end of a click
+						let clicked = std::mem::take(&mut borrowed.click);
+						if borrowed.hover && clicked {
+							let now = Instant::now();
+							let duration_since_last_click =
+								now.duration_since(borrowed.last_click_time);
+							borrowed.last_click_time = now;
+							if duration_since_last_click < Duration::from_millis(250) {
+								borrowed.toggle_fullscreen();
+							}
+						}
+					}
+					borrowed.render_validity.invalidate();
+				}
+				MouseButton::Right => {
+					let mut borrowed = self.data.borrow_mut();
+					if state == ElementState::Pressed {
+						borrowed.close_context_menu();
+						if borrowed.hover {
+							borrowed.right_press_pos = Some(event.cursor_pos);
+						}
+					} else {
+						borrowed.left_to_pan_hint.set_visible(false);
+						let press_pos = borrowed.right_press_pos.take();
+						let dragged = press_pos.map(|pos| (event.cursor_pos - pos).vec.magnitude());
+						if dragged.is_some_and(|dragged| dragged <= CONTEXT_MENU_MAX_DRAG) {
+							borrowed.open_context_menu(event.cursor_pos);
+						}
+					}
+				}
+				_ => {}
+			},
+			EventKind::Touch { id, phase, pos } => {
+				let mut borrowed = self.data.borrow_mut();
+				match borrowed.gestures.touch(id, phase, pos) {
+					Some(Gesture::PanZoom { pan, zoom, anchor }) => {
+						borrowed.pan_image(pan);
+						borrowed.zoom_image_by(anchor, zoom);
+					}
+					Some(Gesture::SwipeLeft) => {
+						borrowed.playback_manager.request_load(LoadRequest::LoadNext);
+						borrowed.render_validity.invalidate();
+					}
+					Some(Gesture::SwipeRight) => {
+						borrowed.playback_manager.request_load(LoadRequest::LoadPrevious);
+						borrowed.render_validity.invalidate();
+					}
+					None => {}
+				}
+			}
+			EventKind::MouseScroll { delta } => {
+				let mut borrowed = self.data.borrow_mut();
+				if borrowed.load_log_overlay.is_open() {
+					// Scrolling up shows the more recent entries
+					borrowed.load_log_overlay.scroll(-delta.vec.y.signum() as isize);
+					return;
+				}
+				if let Some(overlay) = borrowed.shortcuts_overlay.as_mut().filter(|o| o.is_open()) {
+					overlay.scroll(-delta.vec.y.signum() as isize);
+					return;
+				}
+				if event.cursor_pos.vec.x < borrowed.drawn_bounds.left() {
+					// Scrolling over the directory sidebar scrolls the sidebar
+					return;
+				}
+				let delta = delta.vec.y * 0.375;
+				borrowed.zoom_image(event.cursor_pos, delta);
+			}
+			EventKind::KeyInput { ref input } => {
+				let key = input.key_without_modifiers();
+				let is_pressed = input.state == ElementState::Pressed;
+				if is_pressed && self.data.borrow().close_context_menu() {
+					// Any key closes the menu, but only escape does nothing else
+					if input.logical_key == Key::Named(NamedKey::Escape) {
+						return;
+					}
+				}
+				if is_pressed && self.data.borrow().editors.rename_editor.is_some() {
+					// Every key press goes to the editor. Releases are still processed below so
+					// that no movement gets stuck.
+					self.data.borrow_mut().handle_rename_key(input, event.modifiers);
+					return;
+				}
+				if is_pressed && self.data.borrow().editors.move_editor.is_some() {
+					self.data.borrow_mut().handle_move_key(input, event.modifiers);
+					return;
+				}
+				if is_pressed && self.data.borrow().tags.tag_editor.is_some() {
+					self.data.borrow_mut().handle_tag_key(input, event.modifiers);
+					return;
+				}
+				if is_pressed && self.data.borrow().search.search_editor.is_some() {
+					self.data.borrow_mut().handle_search_key(input, event.modifiers);
+					return;
+				}
+				if is_pressed && self.data.borrow().commands.command_editor.is_some() {
+					self.data.borrow_mut().handle_command_key(input, event.modifiers);
+					return;
+				}
+				if is_pressed && self.data.borrow().bookmark_editor.is_some() {
+					self.data.borrow_mut().handle_bookmark_key(input, event.modifiers);
+					return;
+				}
+				if is_pressed && self.data.borrow().layout_editor.is_some() {
+					self.data.borrow_mut().handle_layout_key(input, event.modifiers);
+					return;
+				}
+				if is_pressed && self.data.borrow_mut().handle_search_results_key(input) {
+					return;
+				}
+				if is_pressed && self.data.borrow().exif_editor.is_open() {
+					self.data.borrow_mut().handle_exif_key(input, event.modifiers);
+					return;
+				}
+				let gamma_panel = self.data.borrow().gamma_panel.clone();
+				if let Some(panel) = gamma_panel.filter(|panel| is_pressed && panel.is_open()) {
+					// The typed gamma is applied through the callback of the panel, which
+					// borrows the data again
+					if panel.handle_key(input, event.modifiers) {
+						return;
+					}
+				}
+				//println!("Got input for {:?}", key);
+				let input_key_str = virtual_keycode_to_string(&key).to_lowercase();
+				let input_key_str = char_to_input_key(&input_key_str);
+				if is_pressed {
+					let panel_was_open = self.data.borrow().panel_open();
+					self.handle_key_input(input_key_str.as_str(), event.modifiers);
+					if panel_was_open || self.data.borrow().panel_open() {
+						return;
+					}
+				}
+				// Panning is a special snowflake
+				let mut borrowed = self.data.borrow_mut();
+				if action_triggered(
+					&borrowed.configuration,
+					PAN_NAME,
+					input_key_str.as_str(),
+					event.modifiers,
+				) {
+					borrowed.panning_2d = is_pressed;
+				}
+				if action_triggered(
+					&borrowed.configuration,
+					PAN_VERT_NAME,
+					input_key_str.as_str(),
+					event.modifiers,
+				) {
+					borrowed.panning_vert = is_pressed;
+				}
+				if action_triggered(
+					&borrowed.configuration,
+					PAN_HOR_NAME,
+					input_key_str.as_str(),
+					event.modifiers,
+				) {
+					borrowed.panning_hor = is_pressed;
+				}
+
+				macro_rules! movement_trigger {
+					($input:expr, $vel:expr, $name:expr, $dir:expr) => {
+						if action_triggered(
+							&borrowed.configuration,
+							$name,
+							input_key_str.as_str(),
+							event.modifiers,
+						) {
+							if $input == $dir && !is_pressed {
+								$input = MovementDir::None;
+								$vel = 0.0;
+							}
+							if $input != $dir && is_pressed {
+								borrowed.camera_movement_will_start();
+								$input = $dir;
+							}
+						}
+					};
+				}
+
+				movement_trigger!(
+					borrowed.zoom_input,
+					borrowed.zoom_vel,
+					ZOOM_IN_NAME,
+					MovementDir::Positive
+				);
+				movement_trigger!(
+					borrowed.zoom_input,
+					borrowed.zoom_vel,
+					ZOOM_OUT_NAME,
+					MovementDir::Negative
+				);
+
+				movement_trigger!(
+					borrowed.hor_pan_input,
+					borrowed.hor_pan_vel,
+					PAN_LEFT_NAME,
+					MovementDir::Positive
+				);
+				movement_trigger!(
+					borrowed.hor_pan_input,
+					borrowed.hor_pan_vel,
+					PAN_RIGHT_NAME,
+					MovementDir::Negative
+				);
+
+				movement_trigger!(
+					borrowed.ver_pan_input,
+					borrowed.ver_pan_vel,
+					PAN_UP_NAME,
+					MovementDir::Positive
+				);
+				movement_trigger!(
+					borrowed.ver_pan_input,
+					borrowed.ver_pan_vel,
+					PAN_DOWN_NAME,
+					MovementDir::Negative
+				);
+			}
+			EventKind::DroppedFile(ref path) => {
+				let mut borrowed = self.data.borrow_mut();
+				borrowed.playback_manager.request_load(LoadRequest::FilePath(path.clone()));
+				borrowed.hover_state = HoverState::None;
+				borrowed.render_validity.invalidate();
+			}
+			EventKind::HoveredFile(ref path) => {
+				let mut borrowed = self.data.borrow_mut();
+				match borrowed.hover_state {
+					HoverState::None => {
+						let curr_path = borrowed.playback_manager.shown_file_path().clone();
+						borrowed.hover_state = HoverState::ItemHovered { prev_path: curr_path };
+					}
+					HoverState::ItemHovered { .. } => {}
+				}
+				borrowed.playback_manager.request_load(LoadRequest::FilePath(path.clone()));
+				borrowed.render_validity.invalidate();
+			}
+			EventKind::HoveredFileCancelled => {
+				let mut borrowed = self.data.borrow_mut();
+				match borrowed.hover_state.clone() {
+					HoverState::None => {
+						// Suprisingly this does happen sometimes, so let's just ignore this.
+					}
+					HoverState::ItemHovered { prev_path } => {
+						if let LoadedImgPath::Loaded(prev_path) = prev_path {
+							borrowed
+								.playback_manager
+								.request_load(LoadRequest::FilePath(prev_path));
+						}
+						borrowed.hover_state = HoverState::None;
+					}
+				}
+				borrowed.render_validity.invalidate();
+			}
+			EventKind::Focused(focused) => {
+				if focused {
+					let mut borrowed = self.data.borrow_mut();
+					if let Err(e) = borrowed.playback_manager.update_directory() {
+						eprintln!("{}", e);
+					}
+					borrowed.render_validity.invalidate();
+				}
+			}
+			EventKind::CloseRequested => {
+				let mut borrowed = self.data.borrow_mut();
+				// Just let it drop.
+				borrowed.clipboard_handler.take();
+			}
+		}
+	}
+
+	// No children for a button
+	fn children(&self, _children: &mut Vec<Rc<dyn Widget>>) {}
+
+	fn placement(&self) -> WidgetPlacement {
+		self.data.borrow().placement
+	}
+
+	fn visible(&self) -> bool {
+		self.data.borrow().visible
+	}
+
+	fn set_valid_ref(&self, render_validity: RenderValidity) {
+		self.data.borrow_mut().render_validity = render_validity;
+	}
+}
+
+impl Drop for PictureWidget {
+	fn drop(&mut self) {
+		// This doesn't work. Would be nice to fix at some point. I think I managed to create a
+		// circular reference with my `Rc`s
+		//println!("Called drop for the picture widget.");
+	}
+}
+
+/// Returns the value of the key if it's one of the number keys
+fn single_digit(input_key: &str) -> Option<u8> {
+	let mut chars = input_key.chars();
+	match (chars.next(), chars.next()) {
+		(Some(ch), None) => ch.to_digit(10).map(|digit| digit as u8),
+		_ => None,
+	}
+}
+
+/// Applies a key press to the text of an inline text input
+fn edit_line(line: &mut LineEditor, input: &KeyEvent, modifiers: ModifiersState) {
+	match &input.logical_key {
+		Key::Named(NamedKey::Backspace) => line.backspace(),
+		Key::Named(NamedKey::Delete) => line.delete(),
+		Key::Named(NamedKey::ArrowLeft) => line.move_left(),
+		Key::Named(NamedKey::ArrowRight) => line.move_right(),
+		Key::Named(NamedKey::Home) => line.move_home(),
+		Key::Named(NamedKey::End) => line.move_end(),
+		_ => {
+			// Shortcuts with these modifiers don't type anything
+			if !modifiers.control_key() && !modifiers.super_key() {
+				if let Some(text) = &input.text {
+					line.insert(text);
+				}
+			}
+		}
+	}
+}
+
+/// Returns a path for the exported view of the image at `path` in the same folder, which
+/// doesn't overwrite an existing file
+fn export_path(path: &Path, extension: &str) -> PathBuf {
+	let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+	let mut target = path.with_file_name(format!("{}-view.{}", stem, extension));
+	let mut number = 2;
+	while target.exists() {
+		target = path.with_file_name(format!("{}-view-{}.{}", stem, number, extension));
+		number += 1;
+	}
+	target
+}
+
+/// The size in bytes, or in the largest unit that it's at least one of
+fn format_file_size(bytes: u64) -> String {
+	const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+	if bytes < 1024 {
+		return format!("{} bytes", bytes);
+	}
+	let mut size = bytes as f64 / 1024.0;
+	let mut unit = 0;
+	while size >= 1024.0 && unit < UNITS.len() - 1 {
+		size /= 1024.0;
+		unit += 1;
+	}
+	format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Starts another instance of the viewer that displays the image at `path`
+fn open_in_new_process(path: &Path) {
+	let result = std::env::current_exe().and_then(|exe| Command::new(exe).arg(path).spawn());
+	if let Err(e) = result {
+		eprintln!("Could not open {:?} in a new window: {}", path, e);
+	}
+}
+
+/// Starts another instance of the viewer without a file, for dropping or pasting images into
+fn open_empty_window() {
+	let result = std::env::current_exe().and_then(|exe| Command::new(exe).spawn());
+	if let Err(e) = result {
+		eprintln!("Could not open a new window: {}", e);
+	}
+}
+
+/// Covers the parts of `bounds` that are outside of `frame` in black. `frame` is centered in
+/// `bounds` and spans it either horizontally or vertically.
+/// Draws a circle with a 60° gap that turns around once per second
+fn draw_loading_indicator(
+	target: &mut Frame,
+	context: &DrawContext,
+	center: LogicalVector,
+	since: Instant,
+) {
+	const RADIUS: f32 = 18.0;
+	const WIDTH: f32 = 4.0;
+	const COLOR: [f32; 4] = [0.9, 0.9, 0.9, 0.85];
+	const GAP: f32 = std::f32::consts::PI / 3.0;
+	let turn = since.elapsed().as_secs_f32().fract() * std::f32::consts::TAU;
+	let (start, sweep) = (-turn + GAP * 0.5, std::f32::consts::TAU - GAP);
+	context.draw_arc(target, center, RADIUS, WIDTH + 2.0, start, sweep, [0.0, 0.0, 0.0, 0.4]);
+	context.draw_arc(target, center, RADIUS, WIDTH, start, sweep, COLOR);
+}
+
+/// Divides the image into the columns and rows of the grid. The lines are cut off where the
+/// image goes past the edges of the widget.
+fn draw_grid(
+	data: &PictureWidgetData,
+	target: &mut Frame,
+	context: &DrawContext,
+	grid: &ConfigGridSection,
+) {
+	let (w, h) = match data.get_texture() {
+		Some(texture) => texture.oriented_dimensions(),
+		None => return,
+	};
+	let to_logical = |pos| data.image_to_logical(pos, context.dpi_scale_factor);
+	let (top_left, bottom_right) =
+		match (to_logical(Vector2::new(0.0, 0.0)), to_logical(Vector2::new(w as f32, h as f32))) {
+			(Some(top_left), Some(bottom_right)) => (top_left.vec, bottom_right.vec),
+			_ => return,
+		};
+	let bounds = data.drawn_bounds;
+	let left = top_left.x.max(bounds.left());
+	let right = bottom_right.x.min(bounds.right());
+	let top = top_left.y.max(bounds.top());
+	let bottom = bottom_right.y.min(bounds.bottom());
+	let (color, thickness) = (grid.line_color(), grid.thickness());
+	let columns = grid.columns();
+	for i in 1..columns {
+		let x = top_left.x + (bottom_right.x - top_left.x) * i as f32 / columns as f32;
+		if x > bounds.left() && x < bounds.right() && top < bottom {
+			let (from, to) = (LogicalVector::new(x, top), LogicalVector::new(x, bottom));
+			context.draw_line(target, from, to, thickness, color);
+		}
+	}
+	let rows = grid.rows();
+	for i in 1..rows {
+		let y = top_left.y + (bottom_right.y - top_left.y) * i as f32 / rows as f32;
+		if y > bounds.top() && y < bounds.bottom() && left < right {
+			let (from, to) = (LogicalVector::new(left, y), LogicalVector::new(right, y));
+			context.draw_line(target, from, to, thickness, color);
+		}
+	}
+}
+
+fn draw_bars(target: &mut Frame, context: &DrawContext, bounds: LogicalRect, frame: LogicalRect) {
+	const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+	if frame.size.vec.x < bounds.size.vec.x {
+		let bar_size = LogicalVector::new(frame.left() - bounds.left(), bounds.size.vec.y);
+		context.clear_color(target, BLACK, Some(LogicalRect { pos: bounds.pos, size: bar_size }));
+		let right_pos = LogicalVector::new(frame.right(), bounds.top());
+		context.clear_color(target, BLACK, Some(LogicalRect { pos: right_pos, size: bar_size }));
+	}
+	if frame.size.vec.y < bounds.size.vec.y {
+		let bar_size = LogicalVector::new(bounds.size.vec.x, frame.top() - bounds.top());
+		context.clear_color(target, BLACK, Some(LogicalRect { pos: bounds.pos, size: bar_size }));
+		let bottom_pos = LogicalVector::new(bounds.left(), frame.bottom());
+		context.clear_color(target, BLACK, Some(LogicalRect { pos: bottom_pos, size: bar_size }));
+	}
+}
+
+/// Draws the texture with its center at `img_pos` and blends it over the
+/// framebuffer with the given opacity
+/// How `draw_tex_grid` draws the tiles of an image
+#[derive(Copy, Clone)]
+struct TileStyle {
+	opacity: f32,
+	gamma: f32,
+	/// Only the part of the image within this area of the window is drawn
+	clip: Option<LogicalRect>,
+}
+
+fn draw_tex_grid(
+	data: &PictureWidgetData,
+	target: &mut Frame,
+	context: &DrawContext,
+	texture: AnimationFrameTexture,
+	img_texel_size: f32,
+	img_pos: LogicalVector,
+	style: TileStyle,
+) {
+	let size = data.drawn_bounds.size.vec;
+	let projection_transform = gelatin::cgmath::ortho(0.0, size.x, size.y, 0.0, -1.0, 1.0);
+
+	let viewport_rect = context.logical_rect_to_viewport(&data.drawn_bounds);
+	let image_draw_params = gelatin::glium::DrawParameters {
+		viewport: Some(viewport_rect),
+		scissor: style.clip.map(|clip| context.logical_rect_to_viewport(&clip)),
+		blend: Blend::alpha_blending(),
+		..Default::default()
+	};
+
+	// The downscaled copy is used while the screen doesn't have more pixels than it
+	let (tex_grid, img_phys_w, img_phys_h, img_texel_size) = match &texture.downscaled {
+		Some(small) if img_texel_size * texture.w as f32 <= small.w as f32 + 0.5 => {
+			let texel_size = img_texel_size * texture.w as f32 / small.w as f32;
+			(&small.tex_grid, small.w as f32, small.h as f32, texel_size)
+		}
+		_ => (&texture.tex_grid, texture.w as f32, texture.h as f32, img_texel_size),
+	};
+	let img_height_over_width = img_phys_h / img_phys_w;
+	let image_display_width = img_texel_size * img_phys_w / context.dpi_scale_factor;
+	let image_display_height = image_display_width * img_height_over_width;
+	// Model tranform
+	let img_pyhs_pos = img_pos.vec * context.dpi_scale_factor;
+	let img_phys_siz = {
+		let img_phys_w = image_display_width * context.dpi_scale_factor;
+		let img_phys_h = image_display_height * context.dpi_scale_factor;
+		LogicalVector::new(img_phys_w.ceil(), img_phys_h.ceil())
+	};
+	let img_logical_corner_x =
+		(img_pyhs_pos.x - img_phys_siz.vec.x * 0.5).ceil() / context.dpi_scale_factor;
+	let img_logical_corner_y =
+		(img_pyhs_pos.y - img_phys_siz.vec.y * 0.5).ceil() / context.dpi_scale_factor;
+
+	// This is the display width of the image in logical pixel units
+	let img_adjusted_w = img_phys_siz.vec.x / context.dpi_scale_factor;
+	// This is the display height of the image in logical pixel units
+	let img_adjusted_h = img_phys_siz.vec.y / context.dpi_scale_factor;
+	let img_scaling = Matrix4::from_nonuniform_scale(img_adjusted_w, img_adjusted_h, 1.0);
+	let orientation;
+	{
+		let to_center = Matrix4::from_translation(Vector3::new(
+			-0.5 * img_adjusted_w,
+			-0.5 * img_adjusted_h,
+			0.0,
+		));
+		let orient = orientation_to_matrix(texture.orientation);
+		let to_corner = Matrix4::from_translation(Vector3::new(
+			0.5 * img_adjusted_w,
+			0.5 * img_adjusted_h,
+			0.0,
+		));
+		orientation = to_corner * orient * to_center;
+	}
+	let img_translation =
+		Matrix4::from_translation(Vector3::new(img_logical_corner_x, img_logical_corner_y, 0.0));
+
+	// let img_logical_w = img_w / context.dpi_scale_factor;
+	// let img_logical_h = img_h / context.dpi_scale_factor;
+	let smooth_edges = data.smooth_edges_enabled(context.dpi_scale_factor);
+	let interpolation = data.interpolation(img_texel_size, texture.w as f32);
+	let cell_phy_step = tex_grid.tile_size;
+	let (last_col, last_row) = (tex_grid.cols.saturating_sub(1), tex_grid.rows.saturating_sub(1));
+	for cell_tex in tex_grid.tiles.iter() {
+		let (cell_phys_w, cell_phys_h) = cell_tex.tex().dimensions();
+
+		let cell_phy_offset_x = cell_phy_step * cell_tex.col;
+		let cell_phy_offset_y = cell_phy_step * cell_tex.row;
+		// let cell_logical_offset_x = cell_phy_offset_x as f32 / context.dpi_scale_factor;
+		// let cell_logical_offset_y = cell_phy_offset_y as f32 / context.dpi_scale_factor;
+
+		// The grid is constructed so that it is exactly of size (1, 1) and is located at (0, 0)
+		// This allows to leave most of the image transformation logic unchanged.
+		let cell_scaling = Matrix4::from_nonuniform_scale(
+			cell_phys_w as f32 / img_phys_w,
+			cell_phys_h as f32 / img_phys_h,
+			1.0,
+		);
+		let cell_translation = Matrix4::from_translation(Vector3::new(
+			cell_phy_offset_x as f32 / img_phys_w,
+			cell_phy_offset_y as f32 / img_phys_h,
+			0.0,
+		));
+
+		// The tiles on the edges of the image reach one screen pixel further out, where its
+		// edges are blended into the background
+		let (mut expand_min, mut expand_max) = ([0.0f32; 2], [0.0f32; 2]);
+		if smooth_edges {
+			let margin_x = 1.0 / (img_texel_size * cell_phys_w as f32);
+			let margin_y = 1.0 / (img_texel_size * cell_phys_h as f32);
+			let first = |index: u32, margin: f32| if index == 0 { margin } else { 0.0 };
+			let last = |index: u32, end: u32, margin: f32| if index == end { margin } else { 0.0 };
+			expand_min = [first(cell_tex.col, margin_x), first(cell_tex.row, margin_y)];
+			expand_max =
+				[last(cell_tex.col, last_col, margin_x), last(cell_tex.row, last_row, margin_y)];
+		}
+		let tile_offset =
+			[cell_phy_offset_x as f32 / img_phys_w, cell_phy_offset_y as f32 / img_phys_h];
+		let tile_scale = [cell_phys_w as f32 / img_phys_w, cell_phys_h as f32 / img_phys_h];
+
+		let transform =
+			img_translation * orientation * img_scaling * cell_translation * cell_scaling;
+		// Projection tranform
+		let transform = projection_transform * transform;
+
+		// The bicubic interpolation reads the texels one by one in the shader
+		let filter = match interpolation {
+			InterpolationMode::Nearest => MagnifySamplerFilter::Nearest,
+			InterpolationMode::Bilinear | InterpolationMode::Bicubic => {
+				MagnifySamplerFilter::Linear
+			}
+		};
+		let lod_level = ((1.0 / img_texel_size).log2().max(0.0) + 0.125).floor();
+
+		// The two kinds of textures have different sampler types
+		macro_rules! draw_tile {
+			($tex:expr) => {{
+				let sampler = $tex
+					.sampled()
+					.minify_filter(
+						gelatin::glium::uniforms::MinifySamplerFilter::LinearMipmapLinear,
+					)
+					.wrap_function(gelatin::glium::uniforms::SamplerWrapFunction::Clamp)
+					.magnify_filter(filter);
+
+				// building the uniforms
+				let uniforms = uniform! {
+					matrix: Into::<[[f32; 4]; 4]>::into(transform),
+					bright_shade: data.bright_shade,
+					tex: sampler,
+					lod_level: lod_level,
+					opacity: style.opacity,
+					gamma: style.gamma,
+					smooth_edges: smooth_edges,
+					interpolation_mode: interpolation as i32,
+					expand_min: expand_min,
+					expand_max: expand_max,
+					tile_offset: tile_offset,
+					tile_scale: tile_scale,
+				};
+				target
+					.draw(
+						context.unit_quad_vertices,
+						context.unit_quad_indices,
+						&data.program,
+						&uniforms,
+						&image_draw_params,
+					)
+					.unwrap();
+			}};
+		}
+		cell_tex.prepare_mipmaps(context.display, lod_level as u32);
+		match &*cell_tex.tex() {
+			TileTexture::Srgb(tex) => draw_tile!(tex),
+			TileTexture::Linear(tex) => draw_tile!(tex),
+		}
+	}
+}
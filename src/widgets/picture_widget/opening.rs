@@ -0,0 +1,185 @@
+//! Opens the files picked in the file chooser and the downloaded images, and reads the folder
+//! again, each on another thread.
+
+use std::{
+	path::{Path, PathBuf},
+	sync::mpsc,
+};
+
+use crate::{
+	download::{self, DownloadProgress},
+	file_dialog,
+	image_cache::directory::{self, DirListing},
+	playback_manager::LoadRequest,
+	playlist::is_playlist,
+};
+
+use super::{format_file_size, PictureWidgetData};
+
+/// The threads that the files and the folder are read on
+#[derive(Default)]
+pub(super) struct OpenJobs {
+	/// Receives the listing of the folder while it's being read again
+	folder_listing: Option<mpsc::Receiver<directory::Result<DirListing>>>,
+	/// Receives the file that was picked while the file chooser is open
+	picked_file: Option<mpsc::Receiver<Result<Option<PathBuf>, String>>>,
+	/// Receives the progress of the image that's being downloaded from the web
+	download: Option<mpsc::Receiver<DownloadProgress>>,
+}
+
+impl PictureWidgetData {
+	/// Opens the files and applies the listing once they were read. Returns true while one of
+	/// them is being read.
+	pub(super) fn update_opening(&mut self) -> bool {
+		let reading_folder = self.update_folder_reload();
+		let picking_file = self.update_open_file();
+		let downloading = self.update_download();
+		reading_folder || picking_file || downloading
+	}
+
+	/// Lists the files of the current folder again on a separate thread, to find the images
+	/// that were added or removed
+	pub(super) fn start_folder_reload(&mut self) {
+		if self.opening.folder_listing.is_some() {
+			return;
+		}
+		let dir = match self.playback_manager.current_dir() {
+			Some(dir) => dir.to_owned(),
+			None => return,
+		};
+		let (sender, receiver) = mpsc::channel();
+		std::thread::spawn(move || {
+			let _ = sender.send(DirListing::read(&dir));
+		});
+		self.opening.folder_listing = Some(receiver);
+	}
+
+	/// Shows the file chooser on a separate thread, so that the window is still drawn while
+	/// it's open
+	pub(super) fn start_open_file(&mut self) {
+		if self.opening.picked_file.is_some() {
+			return;
+		}
+		let start_dir = match self.playback_manager.current_dir() {
+			Some(dir) if is_playlist(dir) => dir.parent().map(Path::to_owned),
+			dir => dir.map(Path::to_owned),
+		};
+		let (sender, receiver) = mpsc::channel();
+		std::thread::spawn(move || {
+			let _ = sender.send(file_dialog::pick_image(start_dir.as_deref()));
+		});
+		self.opening.picked_file = Some(receiver);
+	}
+
+	/// Opens the picked file and its folder once the file chooser is closed. Returns true while
+	/// it's open.
+	fn update_open_file(&mut self) -> bool {
+		let result = match &self.opening.picked_file {
+			Some(receiver) => match receiver.try_recv() {
+				Ok(result) => result,
+				Err(mpsc::TryRecvError::Empty) => return true,
+				Err(mpsc::TryRecvError::Disconnected) => {
+					Err("The file chooser stopped unexpectedly".into())
+				}
+			},
+			None => return false,
+		};
+		self.opening.picked_file = None;
+		match result {
+			Ok(Some(path)) => self.open_path(path),
+			Ok(None) => (),
+			Err(e) => self.error_overlay.show(&e),
+		}
+		false
+	}
+
+	/// Downloads the image at the address on a separate thread and opens it when it's done. An
+	/// address that was already downloaded is opened right away.
+	pub(super) fn start_download(&mut self, url: String) {
+		if let Some(path) = download::downloaded_file(&url) {
+			self.playback_manager.request_load(LoadRequest::FilePath(path));
+			return;
+		}
+		let (sender, receiver) = mpsc::channel();
+		std::thread::spawn(move || {
+			let progress = |received, total| {
+				let _ = sender.send(DownloadProgress::Bytes(received, total));
+			};
+			let result = download::download(&url, &progress);
+			let _ = sender.send(DownloadProgress::Finished(result));
+		});
+		self.opening.download = Some(receiver);
+		self.error_overlay.show_status("Downloading the image");
+	}
+
+	/// Shows the progress of the download and opens the image once it's downloaded. Returns
+	/// true while it's being downloaded.
+	fn update_download(&mut self) -> bool {
+		let receiver = match &self.opening.download {
+			Some(receiver) => receiver,
+			None => return false,
+		};
+		let mut finished = None;
+		let mut bytes = None;
+		loop {
+			match receiver.try_recv() {
+				Ok(DownloadProgress::Bytes(received, total)) => bytes = Some((received, total)),
+				Ok(DownloadProgress::Finished(result)) => finished = Some(result),
+				Err(mpsc::TryRecvError::Empty) => break,
+				Err(mpsc::TryRecvError::Disconnected) => {
+					if finished.is_none() {
+						finished = Some(Err("The download stopped unexpectedly".into()));
+					}
+					break;
+				}
+			}
+		}
+		match finished {
+			Some(Ok(path)) => {
+				self.playback_manager.request_load(LoadRequest::FilePath(path));
+				self.render_validity.invalidate();
+			}
+			Some(Err(e)) => self.error_overlay.show(&e),
+			None => {
+				let message = match bytes {
+					Some((received, Some(total))) if total > 0 => {
+						format!("Downloading the image: {}%", (received * 100 / total).min(100))
+					}
+					Some((received, _)) => {
+						format!("Downloading the image: {}", format_file_size(received))
+					}
+					None => return true,
+				};
+				self.error_overlay.show_status(&message);
+				return true;
+			}
+		}
+		self.opening.download = None;
+		false
+	}
+
+	/// Applies the listing of the folder once it was read. Returns true while it's being read.
+	fn update_folder_reload(&mut self) -> bool {
+		let result = match &self.opening.folder_listing {
+			Some(receiver) => match receiver.try_recv() {
+				Ok(result) => result,
+				Err(mpsc::TryRecvError::Empty) => return true,
+				Err(mpsc::TryRecvError::Disconnected) => {
+					Err(directory::Error::Other("Reading the folder stopped unexpectedly".into()))
+				}
+			},
+			None => return false,
+		};
+		self.opening.folder_listing = None;
+		match result {
+			Ok(listing) => {
+				if self.playback_manager.apply_listing(listing) {
+					self.error_overlay.show_status("Reloaded the folder");
+					self.render_validity.invalidate();
+				}
+			}
+			Err(e) => self.error_overlay.show(&format!("Could not reload the folder: {}", e)),
+		}
+		false
+	}
+}
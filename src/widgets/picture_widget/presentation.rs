@@ -0,0 +1,91 @@
+//! The presentation mode, which shows nothing but the image in fullscreen.
+
+use std::time::Instant;
+
+use super::{PictureWidgetData, ScalingMode, ZoomPanState};
+
+/// The layout from before the presentation mode, which is restored when it's left
+pub(super) struct LayoutBeforePresentation {
+	fullscreen: bool,
+	scaling: ScalingMode,
+	zoom_pan: ZoomPanState,
+	directory_sidebar: bool,
+	bookmarks_sidebar: bool,
+}
+
+impl PictureWidgetData {
+	/// Shows nothing but the image, fit to the fullscreen window on a black background. The
+	/// bottom bar, the sidebars and the tools are hidden, and they come back when the mode is
+	/// left with `stop_presentation`.
+	pub(super) fn start_presentation(&mut self) {
+		let window = match self.window.upgrade() {
+			Some(window) => window,
+			None => return,
+		};
+		let center = self.drawn_bounds.size * 0.5;
+		let pan = self.img_pos - center;
+		let is_open = |open: Option<bool>| open.unwrap_or(false);
+		let layout = LayoutBeforePresentation {
+			fullscreen: window.fullscreen(),
+			scaling: self.scaling,
+			zoom_pan: ZoomPanState {
+				zoom: self.img_texel_size,
+				pan_x: pan.vec.x,
+				pan_y: pan.vec.y,
+			},
+			directory_sidebar: is_open(self.directory_sidebar.as_ref().map(|s| s.is_open())),
+			bookmarks_sidebar: is_open(self.bookmarks_sidebar.as_ref().map(|s| s.is_open())),
+		};
+		if layout.directory_sidebar {
+			self.directory_sidebar.as_ref().unwrap().toggle();
+		}
+		if layout.bookmarks_sidebar {
+			self.bookmarks_sidebar.as_ref().unwrap().toggle();
+		}
+		if let Some(menu) = &self.menus.context_menu {
+			menu.close();
+		}
+		self.stop_color_sampler();
+		self.stop_measure_tool();
+		self.stop_crop_selection();
+		window.set_fullscreen(true);
+		self.bottom_bar.set_visible_if_should_show(false);
+		self.scaling = ScalingMode::FitMin;
+		self.update_scaling_buttons();
+		self.presentation = Some(layout);
+		self.last_mouse_move = Instant::now();
+		self.render_validity.invalidate();
+	}
+
+	pub(super) fn stop_presentation(&mut self) {
+		let layout = match self.presentation.take() {
+			Some(layout) => layout,
+			None => return,
+		};
+		if let Some(window) = self.window.upgrade() {
+			window.set_fullscreen(layout.fullscreen);
+			if !layout.fullscreen && self.cursor_hidden {
+				window.window_mut().set_cursor_visible(true);
+				self.cursor_hidden = false;
+			}
+		}
+		self.bottom_bar.set_visible_if_should_show(!layout.fullscreen);
+		if layout.directory_sidebar {
+			if let Some(sidebar) = &self.directory_sidebar {
+				sidebar.toggle();
+			}
+		}
+		if layout.bookmarks_sidebar {
+			if let Some(sidebar) = &self.bookmarks_sidebar {
+				sidebar.toggle();
+			}
+		}
+		if layout.scaling == ScalingMode::Fixed {
+			self.apply_zoom_pan_state(layout.zoom_pan);
+		} else {
+			self.scaling = layout.scaling;
+			self.update_scaling_buttons();
+		}
+		self.render_validity.invalidate();
+	}
+}
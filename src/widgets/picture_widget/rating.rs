@@ -0,0 +1,67 @@
+//! Shows the rating of the current image in the bottom bar and sets it with the number keys.
+
+use std::path::{Path, PathBuf};
+
+use crate::{
+	playback_manager::LoadedImgPath,
+	ratings::{read_rating, RatingWriter},
+};
+
+use super::{super::text_overlay::OverlayText, PictureWidgetData, CLIPBOARD_IMAGE_PATH};
+
+/// The rating of the current image and the writes of the new ratings
+pub(super) struct Rating {
+	rating_writer: RatingWriter,
+	/// The image that the rating in the bottom bar belongs to, and its rating
+	shown_rating: Option<(PathBuf, Option<u8>)>,
+	/// The number keys set the rating of the image instead of triggering their actions
+	pub(super) rating_mode: bool,
+}
+
+impl Rating {
+	pub(super) fn new() -> Rating {
+		Rating { rating_writer: RatingWriter::new(), shown_rating: None, rating_mode: false }
+	}
+}
+
+impl PictureWidgetData {
+	/// Reads the rating of the current image when it changes and shows it in the bottom bar
+	pub(super) fn update_rating(&mut self) {
+		let path = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) if path != Path::new(CLIPBOARD_IMAGE_PATH) => path,
+			_ => {
+				if self.rating.shown_rating.take().is_some() {
+					self.bottom_bar.set_rating(None);
+				}
+				return;
+			}
+		};
+		if self.rating.shown_rating.as_ref().is_some_and(|(shown_path, _)| shown_path == path) {
+			return;
+		}
+		let rating = read_rating(path);
+		self.rating.shown_rating = Some((path.clone(), rating));
+		self.bottom_bar.set_rating(rating);
+	}
+
+	/// Sets the rating of the current image and writes it to its sidecar in the background
+	pub(super) fn set_rating(&mut self, rating: Option<u8>) {
+		self.update_rating();
+		if let Some((path, shown)) = &mut self.rating.shown_rating {
+			*shown = rating;
+			self.rating.rating_writer.request_write(path.clone(), rating);
+			self.bottom_bar.set_rating(rating);
+			let text = match rating {
+				Some(rating) => "★".repeat(rating as usize),
+				None => "Rating removed".to_owned(),
+			};
+			self.text_overlay.show(OverlayText::confirmation(text));
+		}
+	}
+
+	pub(super) fn toggle_star(&mut self) {
+		self.update_rating();
+		let starred = matches!(self.rating.shown_rating, Some((_, Some(_))));
+		self.set_rating(if starred { None } else { Some(1) });
+	}
+}
@@ -0,0 +1,212 @@
+//! Searches the folder of the current image for a file name pattern and shows the thumbnails
+//! of the matching files.
+
+use std::{
+	path::{Path, PathBuf},
+	sync::mpsc,
+};
+
+use gelatin::winit::{
+	event::KeyEvent,
+	keyboard::{Key, ModifiersState, NamedKey},
+};
+
+use crate::{
+	file_search::{self, ThumbnailGrid, DEFAULT_SEARCH_DEPTH},
+	image_cache::image_loader::is_file_supported,
+	playback_manager::{LoadRequest, LoadedImgPath},
+	playlist::is_playlist,
+};
+
+use super::{
+	super::{line_editor::LineEditor, text_overlay::OverlayText},
+	edit_line, PictureWidgetData, ScalingMode, SEARCH_RESULTS_PATH,
+};
+
+/// The thumbnails of the search results, while they are shown instead of an image
+struct SearchResultsView {
+	grid: ThumbnailGrid,
+	selected: usize,
+	/// The image that was shown before the search, for going back to it
+	previous: Option<PathBuf>,
+}
+
+/// The input of the pattern, the search and its results
+#[derive(Default)]
+pub(super) struct FileSearch {
+	/// The file name pattern while it's being typed into the bottom bar
+	pub(super) search_editor: Option<LineEditor>,
+	/// Receives the thumbnails of the matching files while the folders are being searched
+	search_progress: Option<mpsc::Receiver<ThumbnailGrid>>,
+	search_results: Option<SearchResultsView>,
+}
+
+impl PictureWidgetData {
+	/// Turns the bottom bar into a text input for the file name pattern to search for in the
+	/// folder of the current image
+	pub(super) fn start_search(&mut self) {
+		if self.search.search_progress.is_some() {
+			self.error_overlay.show_status("The folders are already being searched");
+			return;
+		}
+		if self.playback_manager.current_dir().is_none() {
+			self.error_overlay.show_status("Open an image to search its folder");
+			return;
+		}
+		let editor = LineEditor::new(String::new());
+		self.bottom_bar.set_rename_text(Some(&search_display_text(&editor)));
+		self.search.search_editor = Some(editor);
+	}
+
+	pub(super) fn stop_search_editing(&mut self) {
+		self.search.search_editor = None;
+		self.bottom_bar.set_rename_text(None);
+		let fullscreen = self.window.upgrade().map(|w| w.fullscreen()).unwrap_or(false);
+		self.bottom_bar.set_visible_if_should_show(!fullscreen);
+	}
+
+	/// Searches the folder of the current image and its subfolders for the typed pattern on a
+	/// separate thread
+	pub(super) fn confirm_search(&mut self) {
+		let pattern = match &self.search.search_editor {
+			Some(editor) => editor.text().trim().to_owned(),
+			None => return,
+		};
+		self.stop_search_editing();
+		let root = match self.playback_manager.current_dir() {
+			Some(dir) if is_playlist(dir) => dir.parent().unwrap_or(Path::new("")).to_owned(),
+			Some(dir) => dir.to_owned(),
+			None => return,
+		};
+		let max_depth = {
+			let config = self.configuration.borrow();
+			config.search.as_ref().and_then(|s| s.max_depth).unwrap_or(DEFAULT_SEARCH_DEPTH)
+		};
+		let (sender, receiver) = mpsc::channel();
+		std::thread::spawn(move || {
+			let paths = file_search::find_files(&root, &pattern, max_depth, is_file_supported);
+			let _ = sender.send(ThumbnailGrid::new(paths));
+		});
+		self.search.search_progress = Some(receiver);
+		self.error_overlay.show_status("Searching");
+	}
+
+	/// Edits the pattern according to a key press while the search input is open
+	pub(super) fn handle_search_key(&mut self, input: &KeyEvent, modifiers: ModifiersState) {
+		let editor = match &mut self.search.search_editor {
+			Some(editor) => editor,
+			None => return,
+		};
+		match &input.logical_key {
+			Key::Named(NamedKey::Enter) => self.confirm_search(),
+			Key::Named(NamedKey::Escape) => self.stop_search_editing(),
+			_ => {
+				edit_line(editor, input, modifiers);
+				self.bottom_bar.set_rename_text(Some(&search_display_text(editor)));
+			}
+		}
+	}
+
+	/// Shows the results of the search once it's finished. Returns true while it's running.
+	pub(super) fn update_search(&mut self) -> bool {
+		if self.search.search_results.is_some()
+			&& *self.playback_manager.shown_file_path()
+				!= LoadedImgPath::Loaded(PathBuf::from(SEARCH_RESULTS_PATH))
+		{
+			// Another image was opened
+			self.search.search_results = None;
+		}
+		let grid = match &self.search.search_progress {
+			Some(receiver) => match receiver.try_recv() {
+				Ok(grid) => Some(grid),
+				Err(mpsc::TryRecvError::Empty) => return true,
+				Err(mpsc::TryRecvError::Disconnected) => None,
+			},
+			None => return false,
+		};
+		self.search.search_progress = None;
+		match grid {
+			Some(grid) if grid.paths.is_empty() => {
+				self.error_overlay.show_status("No images match the pattern")
+			}
+			Some(grid) => {
+				let count = grid.paths.len();
+				let previous = match self.playback_manager.shown_file_path() {
+					LoadedImgPath::Loaded(path) => Some(path.clone()),
+					_ => None,
+				};
+				self.search.search_results =
+					Some(SearchResultsView { grid, selected: 0, previous });
+				self.scaling = ScalingMode::FitMin;
+				self.show_search_results();
+				let mut message = format!("Found {} images", count);
+				if count == file_search::MAX_RESULTS {
+					message.push_str(", the rest is left out");
+				}
+				self.error_overlay.show_status(&message);
+			}
+			None => self.error_overlay.show("The search stopped unexpectedly"),
+		}
+		false
+	}
+
+	/// Displays the thumbnails with the selected one framed
+	pub(super) fn show_search_results(&mut self) {
+		let (results, window) = match (&self.search.search_results, self.window.upgrade()) {
+			(Some(results), Some(window)) => (results, window),
+			_ => return,
+		};
+		let image = results.grid.render(results.selected);
+		let name = results.grid.paths[results.selected].file_name().unwrap_or_default();
+		let text = name.to_string_lossy().into_owned();
+		let path = PathBuf::from(SEARCH_RESULTS_PATH);
+		if let Err(e) = self.playback_manager.show_image(&window.display_mut(), image, path) {
+			eprintln!("Could not display the search results: {:?}", e);
+		}
+		self.text_overlay.show(OverlayText::confirmation(text));
+		self.render_validity.invalidate();
+	}
+
+	/// Moves the selection with the arrow keys, opens the selected image with `Enter` and goes
+	/// back to the previous image with `Escape`. Returns false for the other keys.
+	pub(super) fn handle_search_results_key(&mut self, input: &KeyEvent) -> bool {
+		let results = match &mut self.search.search_results {
+			Some(results) => results,
+			None => return false,
+		};
+		let (dx, dy) = match &input.logical_key {
+			Key::Named(NamedKey::ArrowLeft) => (-1, 0),
+			Key::Named(NamedKey::ArrowRight) => (1, 0),
+			Key::Named(NamedKey::ArrowUp) => (0, -1),
+			Key::Named(NamedKey::ArrowDown) => (0, 1),
+			Key::Named(NamedKey::Enter) => {
+				let path = results.grid.paths[results.selected].clone();
+				self.search.search_results = None;
+				self.playback_manager.request_load(LoadRequest::FilePath(path));
+				self.render_validity.invalidate();
+				return true;
+			}
+			Key::Named(NamedKey::Escape) => {
+				if let Some(previous) = self.search.search_results.take().and_then(|r| r.previous) {
+					self.playback_manager.request_load(LoadRequest::FilePath(previous));
+				}
+				self.render_validity.invalidate();
+				return true;
+			}
+			_ => return false,
+		};
+		let selected = results.grid.moved_selection(results.selected, dx, dy);
+		if selected != results.selected {
+			results.selected = selected;
+			self.show_search_results();
+		}
+		true
+	}
+}
+
+/// The text that's displayed in the bottom bar while the search pattern is typed, with a bar
+/// at the caret
+fn search_display_text(editor: &LineEditor) -> String {
+	let (before, after) = editor.split_at_caret();
+	format!("Search: {}|{}", before, after)
+}
@@ -0,0 +1,178 @@
+//! Opens and saves the sessions, which are playlists that remember the shown image and its
+//! view, and exports the plain playlists.
+
+use std::path::{Path, PathBuf};
+
+use gelatin::cgmath::Vector2;
+
+use crate::{
+	image_cache::directory,
+	playback_manager::{LoadRequest, LoadedImgPath},
+	playlist::{self, is_playlist, is_session, Session},
+};
+
+use super::{PictureWidgetData, ScalingMode};
+
+/// A session that's being opened. Its list is opened first, then the image that was shown,
+/// and then the view is restored.
+pub(super) struct PendingSession {
+	path: PathBuf,
+	session: Session,
+	/// Whether the image that was shown was requested yet
+	image_requested: bool,
+}
+
+impl PictureWidgetData {
+	/// Opens the file or folder. A session is opened as a playlist, and the image and the view
+	/// of the session are restored once it's shown.
+	pub(super) fn open_path(&mut self, path: PathBuf) {
+		self.pending_session = None;
+		if is_session(&path) {
+			match playlist::read_session(&path) {
+				Ok(session) => {
+					let path = path.clone();
+					self.pending_session =
+						Some(PendingSession { path, session, image_requested: false })
+				}
+				Err(e) => {
+					self.error_overlay.show(&format!("Could not read the session: {}", e));
+					return;
+				}
+			}
+		}
+		self.playback_manager.request_load(LoadRequest::FilePath(path));
+		self.render_validity.invalidate();
+	}
+
+	/// Saves the list of images, the current image and its zoom and pan as a session next to
+	/// the images
+	pub(super) fn save_session(&mut self) {
+		let folder = match self.playback_manager.current_dir() {
+			Some(dir) if is_playlist(dir) => dir.parent().unwrap_or(Path::new("")).to_owned(),
+			Some(dir) => dir.to_owned(),
+			None => {
+				self.error_overlay.show_status("There are no images to save");
+				return;
+			}
+		};
+		let (files, current_index) =
+			match (self.playback_manager.image_paths(), self.playback_manager.current_file_index())
+			{
+				(Some(files), Some(index)) => (files, index),
+				_ => {
+					self.error_overlay.show_status("The folder is still being read");
+					return;
+				}
+			};
+		let pan = match (self.window.upgrade(), self.get_texture()) {
+			(Some(window), Some(texture)) => {
+				let dpi_scale = window.window_mut().scale_factor() as f32;
+				let (img_w, img_h) = texture.oriented_dimensions();
+				let img_size = Vector2::new(img_w as f32, img_h as f32);
+				// The center of the widget, in texels from the center of the image
+				let offset = (self.drawn_bounds.size * 0.5 - self.img_pos).vec * dpi_scale
+					/ self.img_texel_size;
+				[0.5 + offset.x / img_size.x, 0.5 + offset.y / img_size.y]
+			}
+			_ => [0.5, 0.5],
+		};
+		let sort = directory::SORT_ORDER.get().copied().unwrap_or_default();
+		let session = Session {
+			current_index,
+			files,
+			zoom: self.img_texel_size,
+			pan,
+			sort: format!("{:?}", sort).to_lowercase(),
+		};
+		let mut target = folder.join("session.json");
+		let mut number = 2;
+		while target.exists() {
+			target = folder.join(format!("session-{}.json", number));
+			number += 1;
+		}
+		match playlist::write_session(&target, &session) {
+			Ok(()) => self.error_overlay.show_status(&format!("Saved the session to {:?}", target)),
+			Err(e) => self.error_overlay.show(&format!("Could not save the session: {}", e)),
+		}
+	}
+
+	/// Opens the image of the session once its list is shown, and then restores the view
+	pub(super) fn update_session(&mut self) {
+		let pending = match &mut self.pending_session {
+			Some(pending) => pending,
+			None => return,
+		};
+		if self.playback_manager.current_dir() != Some(pending.path.as_path()) {
+			return;
+		}
+		let current = match pending.session.current_file() {
+			Some(current) => current.to_owned(),
+			None => {
+				self.pending_session = None;
+				return;
+			}
+		};
+		if !pending.image_requested {
+			pending.image_requested = true;
+			self.playback_manager.request_load(LoadRequest::FilePath(current));
+			self.render_validity.invalidate();
+			return;
+		}
+		let texture = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) if *path == current => self.get_texture(),
+			LoadedImgPath::ErrLoading(path) if *path == current => None,
+			_ => return,
+		};
+		let session = match self.pending_session.take() {
+			Some(pending) => pending.session,
+			None => return,
+		};
+		let texture = match texture {
+			Some(texture) => texture,
+			None => return,
+		};
+		if session.zoom.is_finite() && session.zoom > 0.0 {
+			let (img_w, img_h) = texture.oriented_dimensions();
+			self.scaling = ScalingMode::Fixed;
+			self.update_scaling_buttons();
+			self.img_texel_size = session.zoom;
+			let [x, y] = session.pan;
+			self.initial_pan = Some((Some(x * img_w as f32), Some(y * img_h as f32)));
+			self.render_validity.invalidate();
+		}
+	}
+
+	/// Saves the images that are navigated through, in their order, as an M3U playlist in the
+	/// folder of the current image
+	pub(super) fn export_playlist(&mut self) {
+		let folder = match self.playback_manager.current_dir() {
+			Some(dir) if is_playlist(dir) => dir.parent().unwrap_or(Path::new("")).to_owned(),
+			Some(dir) => dir.to_owned(),
+			None => {
+				self.error_overlay.show_status("There are no images to export");
+				return;
+			}
+		};
+		let paths = match self.playback_manager.image_paths() {
+			Some(paths) => paths,
+			None => {
+				self.error_overlay.show_status("The folder is still being read");
+				return;
+			}
+		};
+		let mut target = folder.join("playlist.m3u");
+		let mut number = 2;
+		while target.exists() {
+			target = folder.join(format!("playlist-{}.m3u", number));
+			number += 1;
+		}
+		match playlist::write_playlist(&target, &paths) {
+			Ok(()) => self.error_overlay.show_status(&format!(
+				"Exported {} images to {:?}",
+				paths.len(),
+				target
+			)),
+			Err(e) => self.error_overlay.show(&format!("Could not export the playlist: {}", e)),
+		}
+	}
+}
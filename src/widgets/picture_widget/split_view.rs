@@ -0,0 +1,76 @@
+//! Shows the image without and with the changes of the view next to each other, divided where
+//! the divider is dragged to.
+
+use gelatin::misc::LogicalVector;
+
+use crate::image_cache::image_loader::Orientation;
+
+use super::PictureWidgetData;
+
+/// How far from the divider of the split view a press can be to grab it, in logical pixels
+const SPLIT_GRAB_DISTANCE: f32 = 8.0;
+
+/// The before and after view of `split_view`. The left side shows the image without the turns,
+/// the flips and the gamma correction of the view.
+pub(super) struct SplitView {
+	/// The position of the divider as a fraction of the width of the widget
+	divider: f32,
+	dragging: bool,
+}
+
+impl PictureWidgetData {
+	/// Shows the image without and with the changes of the view next to each other, or goes
+	/// back to the normal view
+	pub(super) fn toggle_split_view(&mut self) {
+		if self.split_view.take().is_none() {
+			if self.view_orientation == Orientation::Deg0 && self.gamma == 1.0 {
+				self.error_overlay.show_status("The view of the image isn't changed");
+				return;
+			}
+			self.split_view = Some(SplitView { divider: 0.5, dragging: false });
+		}
+		self.render_validity.invalidate();
+	}
+
+	/// The horizontal position of the divider of the split view within the window
+	pub(super) fn split_divider_x(&self) -> Option<f32> {
+		let split = self.split_view.as_ref()?;
+		Some(self.drawn_bounds.left() + self.drawn_bounds.size.vec.x * split.divider)
+	}
+
+	/// Starts dragging the divider if the press is close to it. Returns true if it was grabbed.
+	pub(super) fn grab_split_divider(&mut self, cursor: LogicalVector) -> bool {
+		let near = match self.split_divider_x() {
+			Some(x) => self.hover && (cursor.vec.x - x).abs() <= SPLIT_GRAB_DISTANCE,
+			None => false,
+		};
+		if let (true, Some(split)) = (near, &mut self.split_view) {
+			split.dragging = true;
+		}
+		near
+	}
+
+	/// Moves the divider to the cursor while it's dragged. Returns true if it's dragged.
+	pub(super) fn drag_split_divider(&mut self, cursor: LogicalVector) -> bool {
+		let bounds = self.drawn_bounds;
+		let split = match &mut self.split_view {
+			Some(split) if split.dragging => split,
+			_ => return false,
+		};
+		let offset = (cursor.vec.x - bounds.left()) / bounds.size.vec.x.max(1.0);
+		split.divider = offset.clamp(0.0, 1.0);
+		self.render_validity.invalidate();
+		true
+	}
+
+	/// Leaves the divider where it is. Returns true if it was being dragged.
+	pub(super) fn release_split_divider(&mut self) -> bool {
+		match &mut self.split_view {
+			Some(split) if split.dragging => {
+				split.dragging = false;
+				true
+			}
+			_ => false,
+		}
+	}
+}
@@ -0,0 +1,114 @@
+//! Shows the tags of the current image in the bottom bar and edits them there.
+
+use std::path::{Path, PathBuf};
+
+use gelatin::winit::{
+	event::KeyEvent,
+	keyboard::{Key, ModifiersState, NamedKey},
+};
+
+use crate::{
+	playback_manager::{LoadedImgPath, PlaybackState},
+	tags::TagStore,
+};
+
+use super::{super::tag_editor::TagEditor, edit_line, PictureWidgetData, CLIPBOARD_IMAGE_PATH};
+
+/// The tags of the images and the input that edits them
+#[derive(Default)]
+pub(super) struct Tags {
+	/// The tags of the current image while they are being edited in the bottom bar
+	pub(super) tag_editor: Option<TagEditor>,
+	/// Only exists if the tags file could be read
+	pub(super) tag_store: Option<TagStore>,
+	/// The image that the tags in the bottom bar belong to
+	shown_tags_path: Option<PathBuf>,
+}
+
+impl PictureWidgetData {
+	/// Shows the tags of the current image in the bottom bar if it's a different image than
+	/// the one the tags were last shown for
+	pub(super) fn update_tags(&mut self) {
+		let tag_store = match &self.tags.tag_store {
+			Some(tag_store) => tag_store,
+			None => return,
+		};
+		let path = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) if path != Path::new(CLIPBOARD_IMAGE_PATH) => path,
+			_ => {
+				if self.tags.shown_tags_path.take().is_some() {
+					self.bottom_bar.set_tags(&[]);
+				}
+				return;
+			}
+		};
+		if self.tags.shown_tags_path.as_ref() == Some(path) {
+			return;
+		}
+		self.bottom_bar.set_tags(tag_store.tags(path));
+		self.tags.shown_tags_path = Some(path.clone());
+	}
+
+	/// Turns the bottom bar into a text input for the tags of the current image
+	pub(super) fn start_tag_editing(&mut self) {
+		let tag_store = match &self.tags.tag_store {
+			Some(tag_store) => tag_store,
+			None => {
+				self.error_overlay.show("The tags file could not be read");
+				return;
+			}
+		};
+		let path = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) if path != Path::new(CLIPBOARD_IMAGE_PATH) => path,
+			_ => return,
+		};
+		let editor = TagEditor::new(path, tag_store.tags(path));
+		self.bottom_bar.set_rename_text(Some(&editor.display_text()));
+		self.tags.tag_editor = Some(editor);
+		if matches!(
+			self.playback_manager.playback_state(),
+			PlaybackState::Present | PlaybackState::RandomPresent
+		) {
+			self.playback_manager.pause_playback();
+		}
+	}
+
+	pub(super) fn stop_tag_editing(&mut self) {
+		self.tags.tag_editor = None;
+		self.bottom_bar.set_rename_text(None);
+		let fullscreen = self.window.upgrade().map(|w| w.fullscreen()).unwrap_or(false);
+		self.bottom_bar.set_visible_if_should_show(!fullscreen);
+	}
+
+	/// Saves the edited tags. The editor stays open if the tags can't be saved.
+	pub(super) fn confirm_tags(&mut self) {
+		let (editor, tag_store) = match (&self.tags.tag_editor, &mut self.tags.tag_store) {
+			(Some(editor), Some(tag_store)) => (editor, tag_store),
+			_ => return,
+		};
+		if let Err(e) = tag_store.set_tags(&editor.image_path, editor.tags()) {
+			self.error_overlay.show(&format!("Could not save the tags: {}", e));
+			return;
+		}
+		self.stop_tag_editing();
+		// Show the saved tags in the bottom bar
+		self.tags.shown_tags_path = None;
+		self.update_tags();
+	}
+
+	/// Edits the tags according to a key press while the tag editor is open
+	pub(super) fn handle_tag_key(&mut self, input: &KeyEvent, modifiers: ModifiersState) {
+		let editor = match &mut self.tags.tag_editor {
+			Some(editor) => editor,
+			None => return,
+		};
+		match &input.logical_key {
+			Key::Named(NamedKey::Enter) => self.confirm_tags(),
+			Key::Named(NamedKey::Escape) => self.stop_tag_editing(),
+			_ => {
+				edit_line(&mut editor.line, input, modifiers);
+				self.bottom_bar.set_rename_text(Some(&editor.display_text()));
+			}
+		}
+	}
+}
@@ -0,0 +1,181 @@
+//! Turns the color sampler and the ruler on and off, and feeds them the cursor position.
+
+use std::path::Path;
+
+use gelatin::{
+	cgmath::Vector2,
+	glium::Frame,
+	misc::{LogicalRect, LogicalVector},
+	winit::window::CursorIcon,
+	DrawContext,
+};
+
+use crate::{clipboard_handler::set_clipboard_text, playback_manager::LoadedImgPath};
+
+use super::{super::text_overlay::OverlayText, PictureWidgetData, CLIPBOARD_IMAGE_PATH};
+
+impl PictureWidgetData {
+	/// Turns the eyedropper on for the current image, or off if it's on
+	pub(super) fn toggle_color_sampler(&mut self) {
+		if self.color_sampler.is_active() {
+			self.stop_color_sampler();
+			return;
+		}
+		self.stop_measure_tool();
+		self.stop_crop_selection();
+		let path = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) if path != Path::new(CLIPBOARD_IMAGE_PATH) => path,
+			_ => return,
+		};
+		if let Err(e) = self.color_sampler.activate(&path.clone()) {
+			self.error_overlay.show(&e);
+			return;
+		}
+		self.error_overlay.show_status("Click a pixel to sample its color");
+		self.update_color_sampler(false);
+	}
+
+	pub(super) fn stop_color_sampler(&mut self) {
+		self.color_sampler.deactivate();
+		if let Some(window) = self.window.upgrade() {
+			window.window_mut().set_cursor_icon(CursorIcon::Default);
+		}
+	}
+
+	/// Shows the color under the cursor. If `pick` is true the values of the color are shown
+	/// as well, and it becomes the color that `copy_color` copies.
+	pub(super) fn update_color_sampler(&mut self, pick: bool) {
+		if !self.color_sampler.is_active() {
+			return;
+		}
+		let window = match self.window.upgrade() {
+			Some(window) => window,
+			None => return,
+		};
+		// Another image may have been opened since the sampler was turned on
+		let result = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) if path != Path::new(CLIPBOARD_IMAGE_PATH) => {
+				self.color_sampler.activate(&path.clone())
+			}
+			_ => Err("The pasted image can't be sampled".into()),
+		};
+		if let Err(e) = result {
+			self.error_overlay.show(&e);
+			self.stop_color_sampler();
+			return;
+		}
+		let dpi_scale = window.window_mut().scale_factor() as f32;
+		let image_pos = match self.cursor_on_image(dpi_scale) {
+			Some(image_pos) if self.hover => image_pos,
+			_ => {
+				self.color_sampler.hide();
+				window.window_mut().set_cursor_icon(CursorIcon::Default);
+				return;
+			}
+		};
+		window.window_mut().set_cursor_icon(CursorIcon::Crosshair);
+		let cursor_pos = self.last_mouse_pos - self.drawn_bounds.pos;
+		let area_size = self.drawn_bounds.size;
+		if pick {
+			self.color_sampler.pick(image_pos, cursor_pos, area_size);
+		} else {
+			self.color_sampler.hover(image_pos, cursor_pos, area_size);
+		}
+	}
+
+	/// Copies the hex code of the color that was picked last
+	pub(super) fn copy_picked_color(&mut self) {
+		let hex = match self.color_sampler.picked_hex() {
+			Some(hex) => hex,
+			None => {
+				self.error_overlay.show("Pick a color with the color sampler first");
+				return;
+			}
+		};
+		match set_clipboard_text(&hex) {
+			Ok(()) => self.error_overlay.show_status(&format!("Copied {}", hex)),
+			Err(e) => self.error_overlay.show(&e),
+		}
+	}
+
+	/// Turns the ruler on for the current image, or off if it's on
+	pub(super) fn toggle_measure_tool(&mut self) {
+		if self.measure_tool.is_active() {
+			self.stop_measure_tool();
+			return;
+		}
+		let path = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) => path.clone(),
+			_ => return,
+		};
+		self.stop_color_sampler();
+		self.stop_crop_selection();
+		self.measure_tool.activate(&path);
+		if let Some(window) = self.window.upgrade() {
+			window.window_mut().set_cursor_icon(CursorIcon::Crosshair);
+		}
+		self.error_overlay.show_status("Click two points to measure the distance between them");
+	}
+
+	pub(super) fn stop_measure_tool(&mut self) {
+		if !self.measure_tool.is_active() {
+			return;
+		}
+		self.measure_tool.deactivate();
+		if let Some(window) = self.window.upgrade() {
+			window.window_mut().set_cursor_icon(CursorIcon::Default);
+		}
+		self.render_validity.invalidate();
+	}
+
+	/// Places a point of a measurement under the cursor. The result is shown once both points
+	/// are placed.
+	pub(super) fn click_measure_tool(&mut self) {
+		let dpi_scale = match self.window.upgrade() {
+			Some(window) => window.window_mut().scale_factor() as f32,
+			None => return,
+		};
+		let (texture, (x, y)) = match (self.get_texture(), self.cursor_on_image(dpi_scale)) {
+			(Some(texture), Some(pos)) => (texture, pos),
+			_ => return,
+		};
+		let (img_w, img_h) = texture.oriented_dimensions();
+		let pos = Vector2::new(x * img_w as f32, y * img_h as f32);
+		if let Some(measurement) = self.measure_tool.click(pos) {
+			self.text_overlay.show(OverlayText::confirmation(measurement.describe()));
+		}
+		self.render_validity.invalidate();
+	}
+}
+
+/// Draws the lines of the ruler with a dark outline, so that they are visible on light and dark
+/// images alike
+pub(super) fn draw_measurements(
+	data: &PictureWidgetData,
+	target: &mut Frame,
+	context: &DrawContext,
+) {
+	const LINE_COLOR: [f32; 4] = [1.0, 0.85, 0.2, 1.0];
+	const OUTLINE_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+	const LINE_WIDTH: f32 = 1.5;
+	const DOT_SIZE: f32 = 6.0;
+	let to_logical = |pos| data.image_to_logical(pos, context.dpi_scale_factor);
+	let draw_dot = |target: &mut Frame, center: LogicalVector| {
+		let size = LogicalVector::new(DOT_SIZE, DOT_SIZE);
+		let outline = LogicalVector::new(DOT_SIZE + 2.0, DOT_SIZE + 2.0);
+		let rect = |size: LogicalVector| LogicalRect { pos: center - size * 0.5, size };
+		context.clear_color(target, OUTLINE_COLOR, Some(rect(outline)));
+		context.clear_color(target, LINE_COLOR, Some(rect(size)));
+	};
+	for measurement in data.measure_tool.measurements() {
+		if let (Some(from), Some(to)) = (to_logical(measurement.from), to_logical(measurement.to)) {
+			context.draw_line(target, from, to, LINE_WIDTH + 2.0, OUTLINE_COLOR);
+			context.draw_line(target, from, to, LINE_WIDTH, LINE_COLOR);
+			draw_dot(target, from);
+			draw_dot(target, to);
+		}
+	}
+	if let Some(start) = data.measure_tool.start().and_then(to_logical) {
+		draw_dot(target, start);
+	}
+}
@@ -0,0 +1,78 @@
+//! The crossfade between images and the Ken Burns effect of the presentations.
+
+use std::time::{Duration, Instant};
+
+use gelatin::{cgmath::Vector2, misc::LogicalVector};
+use rand::{thread_rng, Rng};
+
+use crate::image_cache::AnimationFrameTexture;
+
+use super::PictureWidgetData;
+
+/// The duration of the crossfade between images if it's not set in the configuration
+const DEFAULT_TRANSITION_MS: u32 = 150;
+/// The zoom at the end of the Ken Burns effect relative to the zoom at its start
+const KEN_BURNS_END_ZOOM: f32 = 1.1;
+
+/// Blends the previously displayed image into the current one after switching images.
+pub(super) struct CrossfadeTransition {
+	pub(super) from: AnimationFrameTexture,
+	/// The zoom of the previous image when it was last drawn
+	pub(super) from_texel_size: f32,
+	/// The position of the previous image when it was last drawn
+	pub(super) from_pos: LogicalVector,
+	pub(super) start: Instant,
+	pub(super) duration_ms: u32,
+}
+impl CrossfadeTransition {
+	/// The opacity of the current image, between 0 and 1
+	pub(super) fn alpha(&self, now: Instant) -> f32 {
+		let elapsed = now.saturating_duration_since(self.start).as_secs_f32() * 1000.0;
+		(elapsed / self.duration_ms as f32).min(1.0)
+	}
+}
+
+/// Slowly zooms into the image while panning from near a corner towards the center, for
+/// the duration that the image is shown for during a presentation.
+pub(super) struct KenBurnsAnimator {
+	start: Instant,
+	total_secs: f32,
+	/// The point of the image that the zoom is directed at, where (-1, -1) is the top left
+	/// corner and (1, 1) is the bottom right corner
+	start_pan: Vector2<f32>,
+	end_pan: Vector2<f32>,
+}
+impl KenBurnsAnimator {
+	/// Creates an animator with randomized start and end positions
+	pub(super) fn new(start: Instant, duration: Duration) -> Self {
+		let mut rng = thread_rng();
+		let mut corner_coord = || {
+			let sign = if rng.gen::<bool>() { 1.0 } else { -1.0 };
+			sign * rng.gen_range(0.5..=1.0)
+		};
+		let start_pan = Vector2::new(corner_coord(), corner_coord());
+		let end_pan = Vector2::new(rng.gen_range(-0.2..=0.2), rng.gen_range(-0.2..=0.2));
+		KenBurnsAnimator { start, total_secs: duration.as_secs_f32(), start_pan, end_pan }
+	}
+
+	/// Returns the zoom factor and the pan position at the given time
+	pub(super) fn zoom_and_pan(&self, now: Instant) -> (f32, Vector2<f32>) {
+		let elapsed_secs = now.saturating_duration_since(self.start).as_secs_f32();
+		let progress = (elapsed_secs / self.total_secs).clamp(0.0, 1.0);
+		let zoom = 1.0 + (KEN_BURNS_END_ZOOM - 1.0) * progress;
+		let pan = self.start_pan + (self.end_pan - self.start_pan) * progress;
+		(zoom, pan)
+	}
+}
+
+impl PictureWidgetData {
+	pub(super) fn ken_burns_enabled(&self) -> bool {
+		let config = self.configuration.borrow();
+		config.slideshow.as_ref().and_then(|s| s.ken_burns).unwrap_or(false)
+	}
+
+	pub(super) fn transition_ms(&self) -> u32 {
+		let config = self.configuration.borrow();
+		config.image.as_ref().and_then(|i| i.transition_ms).unwrap_or(DEFAULT_TRANSITION_MS)
+	}
+}
@@ -0,0 +1,102 @@
+//! Remembers the zoom and pan of the recently viewed images.
+
+use std::{
+	collections::{HashMap, VecDeque},
+	path::{Path, PathBuf},
+};
+
+use crate::{image_cache::get_image_size_estimate, playback_manager::LoadedImgPath};
+
+use super::{PictureWidgetData, ScalingMode, ZoomPanState};
+
+/// The zoom and pan is remembered for as many images of this size as fit into the image cache,
+/// so that it's kept about as long as the images themselves
+const ZOOM_MEMORY_IMAGE_SIZE: (u32, u32) = (1920, 1080);
+/// The number of images for which the zoom and pan is remembered even with a small cache
+const MIN_ZOOM_MEMORY_CAPACITY: usize = 16;
+
+/// Stores the zoom and pan for the most recently viewed images. The oldest entry is
+/// forgotten when the capacity is exceeded.
+pub(super) struct ZoomMemory {
+	states: HashMap<PathBuf, ZoomPanState>,
+	/// The paths from `states` with the least recently stored at the front
+	order: VecDeque<PathBuf>,
+	capacity: usize,
+}
+impl ZoomMemory {
+	/// Remembers about as many images as the image cache of `cache_capacity` bytes holds
+	pub(super) fn new(cache_capacity: isize) -> Self {
+		let mut memory = ZoomMemory { states: HashMap::new(), order: VecDeque::new(), capacity: 0 };
+		memory.set_cache_capacity(cache_capacity);
+		memory
+	}
+
+	pub(super) fn set_cache_capacity(&mut self, cache_capacity: isize) {
+		let (w, h) = ZOOM_MEMORY_IMAGE_SIZE;
+		let images = cache_capacity.max(0) / get_image_size_estimate(w, h);
+		self.capacity = (images as usize).max(MIN_ZOOM_MEMORY_CAPACITY);
+		self.evict();
+	}
+
+	pub(super) fn store(&mut self, path: PathBuf, state: ZoomPanState) {
+		self.order.retain(|p| *p != path);
+		self.order.push_back(path.clone());
+		self.states.insert(path, state);
+		self.evict();
+	}
+
+	fn evict(&mut self) {
+		while self.order.len() > self.capacity {
+			if let Some(oldest) = self.order.pop_front() {
+				self.states.remove(&oldest);
+			}
+		}
+	}
+
+	pub(super) fn forget(&mut self, path: &Path) {
+		if self.states.remove(path).is_some() {
+			self.order.retain(|p| p != path);
+		}
+	}
+
+	pub(super) fn get(&self, path: &Path) -> Option<ZoomPanState> {
+		self.states.get(path).copied()
+	}
+}
+
+impl PictureWidgetData {
+	pub(super) fn zoom_memory_enabled(&self) -> bool {
+		let config = self.configuration.borrow();
+		config.image.as_ref().and_then(|s| s.remember_zoom).unwrap_or(true)
+	}
+
+	/// Saves the zoom and pan of the previously shown image and restores
+	/// the state of the newly shown one if it was visited recently.
+	pub(super) fn update_zoom_memory(&mut self) {
+		let curr_path = match self.playback_manager.shown_file_path() {
+			LoadedImgPath::Loaded(path) => path.clone(),
+			_ => return,
+		};
+		if self.zoom_memory_path.as_ref() == Some(&curr_path) {
+			return;
+		}
+		if let Some(prev_path) = self.zoom_memory_path.take() {
+			if self.scaling == ScalingMode::Fixed {
+				let center = self.drawn_bounds.size * 0.5;
+				let offset = self.img_pos - center;
+				let state = ZoomPanState {
+					zoom: self.img_texel_size,
+					pan_x: offset.vec.x,
+					pan_y: offset.vec.y,
+				};
+				self.zoom_memory.store(prev_path, state);
+			} else {
+				self.zoom_memory.forget(&prev_path);
+			}
+		}
+		if let Some(state) = self.zoom_memory.get(&curr_path) {
+			self.apply_zoom_pan_state(state);
+		}
+		self.zoom_memory_path = Some(curr_path);
+	}
+}
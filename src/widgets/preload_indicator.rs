@@ -0,0 +1,47 @@
+use std::rc::{Rc, Weak};
+
+use gelatin::{label::Label, misc::*};
+
+use super::text::set_label_text_boxed;
+
+const TEXT_HEIGHT: f32 = 14.0;
+const PADDING: f32 = 6.0;
+const TEXT_COLOR: [u8; 3] = [230, 230, 230];
+const BG_COLOR: [u8; 4] = [20, 20, 20, 200];
+
+/// Shows how many images of the directory have been preloaded
+pub struct PreloadIndicator {
+	pub widget: Weak<Label>,
+	shown_progress: Option<(usize, usize)>,
+}
+
+impl PreloadIndicator {
+	pub fn new(widget: &Rc<Label>) -> PreloadIndicator {
+		widget.set_icon(None);
+		widget.set_ignore_layout(true);
+		widget.set_margin_all(8.0);
+		widget.set_horizontal_align(Alignment::Start);
+		widget.set_vertical_align(Alignment::Start);
+		widget.set_visible(false);
+
+		PreloadIndicator { widget: Rc::downgrade(widget), shown_progress: None }
+	}
+
+	/// Displays the number of preloaded images. The indicator is hidden once every image
+	/// is loaded.
+	pub fn update(&mut self, progress: Option<(usize, usize)>) {
+		if progress == self.shown_progress {
+			return;
+		}
+		self.shown_progress = progress;
+		let widget = self.widget.upgrade().unwrap();
+		match progress {
+			Some((preloaded, total)) if preloaded < total => {
+				let text = format!("Preloading: {}/{}", preloaded, total);
+				set_label_text_boxed(&widget, &text, TEXT_HEIGHT, TEXT_COLOR, BG_COLOR, PADDING);
+				widget.set_visible(true);
+			}
+			_ => widget.set_visible(false),
+		}
+	}
+}
@@ -0,0 +1,244 @@
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+
+use gelatin::{
+	button::Button,
+	label::Label,
+	line_layout_container::{HorizontalLayoutContainer, VerticalLayoutContainer},
+	misc::{Alignment, Length, LogicalVector},
+	Widget,
+};
+
+use super::text::{set_button_text, set_label_text};
+use crate::configuration::PaperSize;
+use crate::pdf_writer::PrintLayout;
+
+const PANEL_WIDTH: f32 = 460.0;
+const ROW_HEIGHT: f32 = 32.0;
+const TEXT_HEIGHT: f32 = 16.0;
+const NAME_WIDTH: f32 = 110.0;
+const GAP: f32 = 8.0;
+
+const PANEL_BG_COLOR: [f32; 4] = [0.12, 0.12, 0.12, 1.0];
+const BUTTON_BG_COLOR: [f32; 4] = [0.3, 0.3, 0.3, 0.5];
+const SELECTED_BG_COLOR: [f32; 4] = [0.2, 0.45, 0.8, 0.9];
+
+const PAPERS: [(Option<PaperSize>, &str); 4] = [
+	(Some(PaperSize::A4), "A4"),
+	(Some(PaperSize::A3), "A3"),
+	(Some(PaperSize::Letter), "Letter"),
+	(None, "Custom"),
+];
+const DPIS: [u32; 3] = [72, 150, 300];
+
+type PrintCallback = Rc<dyn Fn(PrintLayout)>;
+
+/// The panel of `print_image`, where the page is set up before the image is printed. The
+/// choices are kept until the viewer is closed.
+pub struct PrintPanel {
+	pub widget: Rc<VerticalLayoutContainer>,
+	/// `None` until the panel is opened for the first time
+	layout: Cell<Option<PrintLayout>>,
+	on_print: RefCell<Option<PrintCallback>>,
+
+	paper_buttons: Vec<Rc<Button>>,
+	portrait_button: Rc<Button>,
+	landscape_button: Rc<Button>,
+	dpi_buttons: Vec<Rc<Button>>,
+	fit_button: Rc<Button>,
+	actual_size_button: Rc<Button>,
+}
+
+impl PrintPanel {
+	pub fn new() -> Rc<Self> {
+		Rc::new_cyclic(|this: &Weak<PrintPanel>| {
+			let widget = Rc::new(VerticalLayoutContainer::new());
+			widget.set_ignore_layout(true);
+			widget.set_width(Length::Fixed(PANEL_WIDTH));
+			widget.set_height(Length::Fixed(ROW_HEIGHT * 6.0 + GAP * 2.0));
+			widget.set_horizontal_align(Alignment::Center);
+			widget.set_vertical_align(Alignment::Center);
+			widget.set_bg_color(PANEL_BG_COLOR);
+			widget.set_visible(false);
+
+			let title = make_label(Alignment::Center);
+			set_label_text(&title, "Print", TEXT_HEIGHT * 1.25);
+			widget.add_child(make_row(vec![title]));
+
+			let mut paper_buttons = Vec::new();
+			for (paper, name) in PAPERS {
+				let button = make_choice_button(name);
+				on_click(&button, this, move |panel| panel.update(|l| l.paper = paper));
+				paper_buttons.push(button);
+			}
+			widget.add_child(make_setting_row("Paper", &paper_buttons));
+
+			let portrait_button = make_choice_button("Portrait");
+			on_click(&portrait_button, this, |panel| panel.update(|l| l.landscape = false));
+			let landscape_button = make_choice_button("Landscape");
+			on_click(&landscape_button, this, |panel| panel.update(|l| l.landscape = true));
+			widget.add_child(make_setting_row(
+				"Orientation",
+				&[portrait_button.clone(), landscape_button.clone()],
+			));
+
+			let mut dpi_buttons = Vec::new();
+			for dpi in DPIS {
+				let button = make_choice_button(&dpi.to_string());
+				on_click(&button, this, move |panel| panel.update(|l| l.dpi = dpi));
+				dpi_buttons.push(button);
+			}
+			widget.add_child(make_setting_row("DPI", &dpi_buttons));
+
+			let fit_button = make_choice_button("Fit to page");
+			on_click(&fit_button, this, |panel| panel.update(|l| l.actual_size = false));
+			let actual_size_button = make_choice_button("Actual size");
+			on_click(&actual_size_button, this, |panel| panel.update(|l| l.actual_size = true));
+			widget.add_child(make_setting_row(
+				"Size",
+				&[fit_button.clone(), actual_size_button.clone()],
+			));
+
+			let print_button = make_button();
+			set_button_text(&print_button, "Print", TEXT_HEIGHT, GAP);
+			on_click(&print_button, this, |panel| panel.print());
+			let cancel_button = make_button();
+			set_button_text(&cancel_button, "Cancel", TEXT_HEIGHT, GAP);
+			on_click(&cancel_button, this, |panel| panel.close());
+			let buttons = make_row(vec![print_button, cancel_button]);
+			widget.add_child(buttons);
+
+			PrintPanel {
+				widget,
+				layout: Cell::new(None),
+				on_print: RefCell::new(None),
+				paper_buttons,
+				portrait_button,
+				landscape_button,
+				dpi_buttons,
+				fit_button,
+				actual_size_button,
+			}
+		})
+	}
+
+	/// The callback is called with the chosen layout when the Print button is pressed
+	pub fn set_on_print<F: Fn(PrintLayout) + 'static>(&self, callback: F) {
+		*self.on_print.borrow_mut() = Some(Rc::new(callback));
+	}
+
+	pub fn is_open(&self) -> bool {
+		self.widget.visible()
+	}
+
+	/// The layout is `default` the first time, and the last choices after that
+	pub fn open(&self, default: PrintLayout) {
+		if self.layout.get().is_none() {
+			self.layout.set(Some(default));
+		}
+		self.refresh();
+		self.widget.set_visible(true);
+	}
+
+	pub fn close(&self) {
+		self.widget.set_visible(false);
+	}
+
+	/// Closes the panel and prints with the chosen layout
+	pub fn print(&self) {
+		self.close();
+		let on_print = self.on_print.borrow().clone();
+		if let (Some(callback), Some(layout)) = (on_print, self.layout.get()) {
+			callback(layout);
+		}
+	}
+
+	fn update<F: FnOnce(&mut PrintLayout)>(&self, update: F) {
+		if let Some(mut layout) = self.layout.get() {
+			update(&mut layout);
+			self.layout.set(Some(layout));
+			self.refresh();
+		}
+	}
+
+	/// Highlights the buttons of the chosen options
+	fn refresh(&self) {
+		let layout = match self.layout.get() {
+			Some(layout) => layout,
+			None => return,
+		};
+		for ((paper, _), button) in PAPERS.iter().zip(&self.paper_buttons) {
+			select(button, *paper == layout.paper);
+		}
+		// The custom page has the shape of the image
+		select(&self.portrait_button, layout.paper.is_some() && !layout.landscape);
+		select(&self.landscape_button, layout.paper.is_some() && layout.landscape);
+		for (dpi, button) in DPIS.iter().zip(&self.dpi_buttons) {
+			select(button, *dpi == layout.dpi);
+		}
+		select(&self.fit_button, !layout.actual_size);
+		select(&self.actual_size_button, layout.actual_size);
+	}
+}
+
+fn select(button: &Button, selected: bool) {
+	button.set_bg_color(if selected { SELECTED_BG_COLOR } else { BUTTON_BG_COLOR });
+}
+
+fn on_click<F: Fn(&PrintPanel) + 'static>(button: &Button, panel: &Weak<PrintPanel>, f: F) {
+	let panel = panel.clone();
+	button.set_on_click(move || {
+		if let Some(panel) = panel.upgrade() {
+			f(&panel);
+		}
+	});
+}
+
+fn make_row(children: Vec<Rc<dyn Widget>>) -> Rc<HorizontalLayoutContainer> {
+	let row = Rc::new(HorizontalLayoutContainer::new());
+	row.set_margin_left(GAP);
+	row.set_margin_right(GAP);
+	row.set_height(Length::Fixed(ROW_HEIGHT));
+	row.set_width(Length::Stretch { min: 0.0, max: f32::INFINITY });
+	for child in children {
+		row.add_child(child);
+	}
+	row
+}
+
+fn make_setting_row(name: &str, buttons: &[Rc<Button>]) -> Rc<HorizontalLayoutContainer> {
+	let name_label = make_label(Alignment::Start);
+	set_label_text(&name_label, name, TEXT_HEIGHT);
+	let name_area = Rc::new(HorizontalLayoutContainer::new());
+	name_area.set_margin_all(0.0);
+	name_area.set_fixed_size(LogicalVector::new(NAME_WIDTH, ROW_HEIGHT));
+	name_area.add_child(name_label);
+
+	let row = make_row(vec![name_area]);
+	for button in buttons {
+		row.add_child(button.clone());
+	}
+	row
+}
+
+fn make_label(alignment: Alignment) -> Rc<Label> {
+	let label = Rc::new(Label::new());
+	label.set_margin_all(0.0);
+	label.set_horizontal_align(alignment);
+	label.set_vertical_align(Alignment::Center);
+	label
+}
+
+fn make_button() -> Rc<Button> {
+	let button = Rc::new(Button::new());
+	button.set_margin_left(GAP * 0.5);
+	button.set_vertical_align(Alignment::Center);
+	button.set_bg_color(BUTTON_BG_COLOR);
+	button
+}
+
+fn make_choice_button(text: &str) -> Rc<Button> {
+	let button = make_button();
+	set_button_text(&button, text, TEXT_HEIGHT, GAP * 0.5);
+	button
+}
@@ -0,0 +1,77 @@
+//! The state of the inline text input for renaming the current file.
+
+use std::path::{Path, PathBuf};
+
+use super::line_editor::LineEditor;
+
+/// Edits the name of a file without its extension. The extension is kept as it was.
+pub struct RenameEditor {
+	pub original_path: PathBuf,
+	/// The name without the extension, as edited so far
+	pub line: LineEditor,
+	/// The extension including the leading dot, or an empty string
+	extension: String,
+}
+
+impl RenameEditor {
+	/// Starts editing the name of the file at `path`. Returns `None` if the path has no file name.
+	pub fn new(path: &Path) -> Option<RenameEditor> {
+		let text = path.file_stem()?.to_string_lossy().into_owned();
+		let extension = match path.extension() {
+			Some(extension) => format!(".{}", extension.to_string_lossy()),
+			None => String::new(),
+		};
+		Some(RenameEditor {
+			original_path: path.to_owned(),
+			line: LineEditor::new(text),
+			extension,
+		})
+	}
+
+	/// The text that's displayed in the bottom bar while editing, with a bar at the caret
+	pub fn display_text(&self) -> String {
+		let (before, after) = self.line.split_at_caret();
+		format!("Rename: {}|{}{}", before, after, self.extension)
+	}
+
+	/// Returns the path that the file should be renamed to, or an error message if the
+	/// edited name can't be used.
+	pub fn new_path(&self) -> Result<PathBuf, String> {
+		let name = self.line.text().trim();
+		if name.is_empty() {
+			return Err("The file name can't be empty".into());
+		}
+		if name.contains(std::path::is_separator) {
+			return Err("The file name can't contain a path separator".into());
+		}
+		if let Some(c) = name.chars().find(|&c| is_forbidden_char(c)) {
+			return Err(format!("The file name can't contain {:?}", c));
+		}
+		if cfg!(windows) && is_reserved_windows_name(name) {
+			return Err(format!("\"{}\" is reserved by Windows", name));
+		}
+		Ok(self.original_path.with_file_name(format!("{}{}", name, self.extension)))
+	}
+}
+
+/// Characters that the file system of the current OS doesn't allow in file names
+fn is_forbidden_char(c: char) -> bool {
+	if cfg!(windows) {
+		matches!(c, '<' | '>' | ':' | '"' | '|' | '?' | '*') || c.is_ascii_control()
+	} else {
+		c == '\0'
+	}
+}
+
+/// Windows treats these names as devices, even when they have an extension
+fn is_reserved_windows_name(name: &str) -> bool {
+	const RESERVED: [&str; 4] = ["CON", "PRN", "AUX", "NUL"];
+	let upper = name.to_ascii_uppercase();
+	if RESERVED.contains(&upper.as_str()) {
+		return true;
+	}
+	match upper.strip_prefix("COM").or_else(|| upper.strip_prefix("LPT")) {
+		Some(digit) => matches!(digit.as_bytes(), [b'1'..=b'9']),
+		None => false,
+	}
+}
@@ -0,0 +1,349 @@
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::{Rc, Weak};
+
+use gelatin::{
+	button::Button,
+	label::Label,
+	line_layout_container::{HorizontalLayoutContainer, VerticalLayoutContainer},
+	misc::{Alignment, Length, LogicalVector},
+	slider::Slider,
+	Widget,
+};
+
+use super::text::{set_button_text, set_label_text};
+use crate::configuration::{parse_hex_color, Configuration, FitMode, PanelSetting};
+use crate::playback_manager::{
+	default_cache_capacity, default_thread_count, DEFAULT_PRESENTATION_INTERVAL,
+};
+
+const PANEL_WIDTH: f32 = 480.0;
+const ROW_HEIGHT: f32 = 32.0;
+const TEXT_HEIGHT: f32 = 16.0;
+const NAME_WIDTH: f32 = 150.0;
+const VALUE_WIDTH: f32 = 90.0;
+const GAP: f32 = 8.0;
+const SWATCH_SIZE: f32 = 22.0;
+
+const PANEL_BG_COLOR: [f32; 4] = [0.12, 0.12, 0.12, 1.0];
+const BUTTON_BG_COLOR: [f32; 4] = [0.3, 0.3, 0.3, 0.5];
+
+const BACKGROUND_COLORS: [&str; 6] =
+	["#000000", "#1a1a2e", "#303030", "#808080", "#d9d9d9", "#ffffff"];
+const FIT_MODES: [FitMode; 5] =
+	[FitMode::None, FitMode::Window, FitMode::Width, FitMode::Height, FitMode::Original];
+const MAX_THREADS: u32 = 16;
+const CACHE_STEP_MB: u32 = 256;
+const CACHE_STEPS: u32 = 32;
+const MAX_INTERVAL_SECS: u32 = 60;
+const BYTES_PER_MB: isize = 1024 * 1024;
+
+fn fit_mode_name(mode: Option<FitMode>) -> &'static str {
+	match mode {
+		Some(FitMode::Window) => "Window",
+		Some(FitMode::Width) => "Width",
+		Some(FitMode::Height) => "Height",
+		Some(FitMode::Original) => "1:1",
+		Some(FitMode::None) | None => "Previous",
+	}
+}
+
+/// A modal overlay for editing the most common settings of the configuration.
+///
+/// Every change is written into the configuration immediately and the `on_change` callback is
+/// called to apply it. The changed settings are saved to the config file when the panel is closed.
+pub struct SettingsPanel {
+	pub widget: Rc<VerticalLayoutContainer>,
+	configuration: Rc<RefCell<Configuration>>,
+	config_path: PathBuf,
+	on_change: RefCell<Option<Rc<dyn Fn()>>>,
+	/// The settings that were changed since the panel was opened, in order
+	changed: RefCell<Vec<PanelSetting>>,
+
+	thread_slider: Rc<Slider>,
+	thread_value: Rc<Label>,
+	cache_slider: Rc<Slider>,
+	cache_value: Rc<Label>,
+	fit_button: Rc<Button>,
+	interval_slider: Rc<Slider>,
+	interval_value: Rc<Label>,
+}
+
+impl SettingsPanel {
+	pub fn new(configuration: Rc<RefCell<Configuration>>, config_path: PathBuf) -> Rc<Self> {
+		Rc::new_cyclic(|this: &Weak<SettingsPanel>| {
+			let widget = Rc::new(VerticalLayoutContainer::new());
+			widget.set_ignore_layout(true);
+			widget.set_width(Length::Fixed(PANEL_WIDTH));
+			widget.set_height(Length::Fixed(ROW_HEIGHT * 7.0 + GAP * 2.0));
+			widget.set_horizontal_align(Alignment::Center);
+			widget.set_vertical_align(Alignment::Center);
+			widget.set_bg_color(PANEL_BG_COLOR);
+			widget.set_visible(false);
+
+			let title = make_label(Alignment::Center);
+			set_label_text(&title, "Settings", TEXT_HEIGHT * 1.25);
+			widget.add_child(make_row(vec![title]));
+
+			let mut swatches: Vec<Rc<dyn Widget>> = Vec::new();
+			let theme_button = make_button();
+			set_button_text(&theme_button, "Theme", TEXT_HEIGHT, GAP * 0.5);
+			on_click(&theme_button, this, |panel| panel.set_background_color(None));
+			swatches.push(theme_button);
+			for color in BACKGROUND_COLORS.iter() {
+				let swatch = make_button();
+				let [r, g, b] = parse_hex_color(color).unwrap();
+				swatch.set_bg_color([r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0]);
+				swatch.set_fixed_size(LogicalVector::new(SWATCH_SIZE, SWATCH_SIZE));
+				on_click(&swatch, this, move |panel| {
+					panel.set_background_color(Some(color.to_string()))
+				});
+				swatches.push(swatch);
+			}
+			widget.add_child(make_setting_row("Background", swatches, None));
+
+			let thread_slider = make_slider(MAX_THREADS);
+			let thread_value = make_label(Alignment::End);
+			on_value_change(&thread_slider, this, |panel, value| {
+				panel.update_config(PanelSetting::ThreadCount, |c| {
+					c.image.get_or_insert_with(Default::default).thread_count = Some(value + 1)
+				});
+			});
+			widget.add_child(make_setting_row(
+				"Loader threads",
+				vec![thread_slider.clone()],
+				Some(thread_value.clone()),
+			));
+
+			let cache_slider = make_slider(CACHE_STEPS);
+			let cache_value = make_label(Alignment::End);
+			on_value_change(&cache_slider, this, |panel, value| {
+				panel.update_config(PanelSetting::CacheSize, |c| {
+					let image = c.image.get_or_insert_with(Default::default);
+					image.cache_size_mb = Some((value + 1) * CACHE_STEP_MB)
+				});
+			});
+			widget.add_child(make_setting_row(
+				"Cache size",
+				vec![cache_slider.clone()],
+				Some(cache_value.clone()),
+			));
+
+			let fit_button = make_button();
+			on_click(&fit_button, this, |panel| panel.cycle_fit_mode());
+			widget.add_child(make_setting_row("Fit mode", vec![fit_button.clone()], None));
+
+			let interval_slider = make_slider(MAX_INTERVAL_SECS);
+			let interval_value = make_label(Alignment::End);
+			on_value_change(&interval_slider, this, |panel, value| {
+				panel.update_config(PanelSetting::SlideshowInterval, |c| {
+					let slideshow = c.slideshow.get_or_insert_with(Default::default);
+					slideshow.interval_secs = Some((value + 1) as f32)
+				});
+			});
+			widget.add_child(make_setting_row(
+				"Slideshow interval",
+				vec![interval_slider.clone()],
+				Some(interval_value.clone()),
+			));
+
+			let close_button = make_button();
+			close_button.set_horizontal_align(Alignment::Center);
+			set_button_text(&close_button, "Close", TEXT_HEIGHT, GAP);
+			on_click(&close_button, this, |panel| panel.close());
+			widget.add_child(make_row(vec![close_button]));
+
+			SettingsPanel {
+				widget,
+				configuration,
+				config_path,
+				on_change: RefCell::new(None),
+				changed: RefCell::new(Vec::new()),
+				thread_slider,
+				thread_value,
+				cache_slider,
+				cache_value,
+				fit_button,
+				interval_slider,
+				interval_value,
+			}
+		})
+	}
+
+	/// The callback is called after a setting was changed in the configuration
+	pub fn set_on_change<F: Fn() + 'static>(&self, callback: F) {
+		*self.on_change.borrow_mut() = Some(Rc::new(callback));
+	}
+
+	pub fn is_open(&self) -> bool {
+		self.widget.visible()
+	}
+
+	pub fn open(&self) {
+		self.refresh();
+		self.widget.set_visible(true);
+	}
+
+	/// Hides the panel and saves the settings that were changed to the config file
+	pub fn close(&self) {
+		if !self.is_open() {
+			return;
+		}
+		self.widget.set_visible(false);
+		let changed = self.changed.take();
+		if changed.is_empty() {
+			return;
+		}
+		let config = self.configuration.borrow();
+		if let Err(e) = config.save_panel_settings(&self.config_path, &changed) {
+			eprintln!("Could not save the settings: {}", e);
+		}
+	}
+
+	fn update_config<F: FnOnce(&mut Configuration)>(&self, setting: PanelSetting, update: F) {
+		update(&mut self.configuration.borrow_mut());
+		if !self.changed.borrow().contains(&setting) {
+			self.changed.borrow_mut().push(setting);
+		}
+		self.refresh();
+		let on_change = self.on_change.borrow().clone();
+		if let Some(callback) = on_change {
+			callback();
+		}
+	}
+
+	fn set_background_color(&self, color: Option<String>) {
+		self.update_config(PanelSetting::BackgroundColor, |c| {
+			c.window.get_or_insert_with(Default::default).background_color = color
+		});
+	}
+
+	fn cycle_fit_mode(&self) {
+		let curr = self.configuration.borrow().image.as_ref().and_then(|i| i.fit);
+		let curr_index = FIT_MODES.iter().position(|m| Some(*m) == curr).unwrap_or(0);
+		let next = FIT_MODES[(curr_index + 1) % FIT_MODES.len()];
+		self.update_config(PanelSetting::Fit, |c| {
+			c.image.get_or_insert_with(Default::default).fit = Some(next)
+		});
+	}
+
+	/// Updates the controls to show the values from the configuration
+	fn refresh(&self) {
+		let config = self.configuration.borrow();
+		let image = config.image.as_ref();
+
+		let threads = image.and_then(|i| i.thread_count).unwrap_or_else(default_thread_count);
+		let threads = threads.clamp(1, MAX_THREADS);
+		self.thread_slider.set_value(threads - 1);
+		set_label_text(&self.thread_value, &threads.to_string(), TEXT_HEIGHT);
+
+		let cache_mb = image
+			.and_then(|i| i.cache_size_mb)
+			.unwrap_or_else(|| (default_cache_capacity() / BYTES_PER_MB) as u32);
+		let cache_step = (cache_mb / CACHE_STEP_MB).clamp(1, CACHE_STEPS);
+		self.cache_slider.set_value(cache_step - 1);
+		set_label_text(&self.cache_value, &format!("{} MB", cache_mb), TEXT_HEIGHT);
+
+		let fit = image.and_then(|i| i.fit);
+		set_button_text(&self.fit_button, fit_mode_name(fit), TEXT_HEIGHT, GAP * 0.5);
+
+		let interval = config
+			.slideshow
+			.as_ref()
+			.and_then(|s| s.interval_secs)
+			.unwrap_or(DEFAULT_PRESENTATION_INTERVAL.as_secs_f32());
+		let interval_step = (interval.round() as u32).clamp(1, MAX_INTERVAL_SECS);
+		self.interval_slider.set_value(interval_step - 1);
+		set_label_text(&self.interval_value, &format!("{} s", interval), TEXT_HEIGHT);
+	}
+}
+
+fn on_click<F: Fn(&SettingsPanel) + 'static>(button: &Button, panel: &Weak<SettingsPanel>, f: F) {
+	let panel = panel.clone();
+	button.set_on_click(move || {
+		if let Some(panel) = panel.upgrade() {
+			f(&panel);
+		}
+	});
+}
+
+fn on_value_change<F: Fn(&SettingsPanel, u32) + 'static>(
+	slider: &Rc<Slider>,
+	panel: &Weak<SettingsPanel>,
+	f: F,
+) {
+	let panel = panel.clone();
+	let weak_slider = Rc::downgrade(slider);
+	slider.set_on_value_change(move || {
+		if let (Some(panel), Some(slider)) = (panel.upgrade(), weak_slider.upgrade()) {
+			f(&panel, slider.value());
+		}
+	});
+}
+
+fn make_row(children: Vec<Rc<dyn Widget>>) -> Rc<HorizontalLayoutContainer> {
+	let row = Rc::new(HorizontalLayoutContainer::new());
+	row.set_margin_left(GAP);
+	row.set_margin_right(GAP);
+	row.set_height(Length::Fixed(ROW_HEIGHT));
+	row.set_width(Length::Stretch { min: 0.0, max: f32::INFINITY });
+	for child in children {
+		row.add_child(child);
+	}
+	row
+}
+
+fn make_setting_row(
+	name: &str,
+	controls: Vec<Rc<dyn Widget>>,
+	value: Option<Rc<Label>>,
+) -> Rc<HorizontalLayoutContainer> {
+	let name_label = make_label(Alignment::Start);
+	set_label_text(&name_label, name, TEXT_HEIGHT);
+	// Keep the controls of all rows aligned
+	let name_area = Rc::new(HorizontalLayoutContainer::new());
+	name_area.set_margin_all(0.0);
+	name_area.set_fixed_size(LogicalVector::new(NAME_WIDTH, ROW_HEIGHT));
+	name_area.add_child(name_label);
+
+	let row = make_row(vec![name_area]);
+	for control in controls {
+		row.add_child(control);
+	}
+	if let Some(value) = value {
+		let value_area = Rc::new(HorizontalLayoutContainer::new());
+		value_area.set_margin_all(0.0);
+		value_area.set_fixed_size(LogicalVector::new(VALUE_WIDTH, ROW_HEIGHT));
+		value_area.add_child(value);
+		row.add_child(value_area);
+	}
+	row
+}
+
+fn make_label(alignment: Alignment) -> Rc<Label> {
+	let label = Rc::new(Label::new());
+	label.set_margin_all(0.0);
+	label.set_horizontal_align(alignment);
+	label.set_vertical_align(Alignment::Center);
+	label
+}
+
+fn make_button() -> Rc<Button> {
+	let button = Rc::new(Button::new());
+	button.set_margin_left(GAP * 0.5);
+	button.set_vertical_align(Alignment::Center);
+	button.set_bg_color(BUTTON_BG_COLOR);
+	button
+}
+
+fn make_slider(steps: u32) -> Rc<Slider> {
+	let slider = Rc::new(Slider::new());
+	slider.set_margin_left(GAP);
+	slider.set_margin_right(GAP);
+	slider.set_height(Length::Fixed(SWATCH_SIZE));
+	slider.set_width(Length::Stretch { min: 0.0, max: f32::INFINITY });
+	slider.set_vertical_align(Alignment::Center);
+	slider.set_steps(steps, 0);
+	slider.set_shadow_color([0.0, 0.0, 0.0]);
+	slider
+}
@@ -0,0 +1,114 @@
+use std::rc::{Rc, Weak};
+
+use gelatin::{label::Label, misc::*, Widget};
+
+use super::text::set_label_columns_boxed;
+use crate::configuration::Configuration;
+use crate::input_handling::{action_keys, DEFAULT_BINDINGS, SHORTCUTS};
+
+const TEXT_HEIGHT: f32 = 14.0;
+const PADDING: f32 = 12.0;
+const KEY_COLOR: [u8; 3] = [150, 200, 255];
+const TEXT_COLOR: [u8; 3] = [230, 230, 230];
+const BG_COLOR: [u8; 4] = [20, 20, 20, 220];
+/// The number of shortcuts that fit on the overlay, the others are reached by scrolling
+const VISIBLE_ROWS: usize = 24;
+
+/// Lists the keys of every action next to what the action does. The keys come from the
+/// configuration, so the list shows the bindings that are in effect.
+pub struct ShortcutsOverlay {
+	pub widget: Weak<Label>,
+	rows: Vec<(String, String)>,
+	/// The number of rows that are scrolled past
+	scroll: usize,
+	/// The scroll position that is displayed
+	shown: Option<usize>,
+}
+
+impl ShortcutsOverlay {
+	pub fn new(widget: &Rc<Label>) -> ShortcutsOverlay {
+		widget.set_icon(None);
+		widget.set_ignore_layout(true);
+		widget.set_horizontal_align(Alignment::Center);
+		widget.set_vertical_align(Alignment::Center);
+		widget.set_visible(false);
+
+		ShortcutsOverlay { widget: Rc::downgrade(widget), rows: Vec::new(), scroll: 0, shown: None }
+	}
+
+	pub fn is_open(&self) -> bool {
+		self.widget.upgrade().is_some_and(|widget| widget.visible())
+	}
+
+	/// Shows the overlay scrolled to the top, with the bindings read again, or hides it
+	pub fn toggle(&mut self, config: &Configuration) {
+		let widget = self.widget.upgrade().unwrap();
+		widget.set_visible(!widget.visible());
+		self.rows = shortcut_rows(config);
+		self.scroll = 0;
+		self.shown = None;
+	}
+
+	/// Scrolls down if `rows` is positive
+	pub fn scroll(&mut self, rows: isize) {
+		let max_scroll = self.rows.len().saturating_sub(VISIBLE_ROWS);
+		self.scroll = self.scroll.saturating_add_signed(rows).min(max_scroll);
+	}
+
+	pub fn update(&mut self) {
+		if !self.is_open() || self.shown == Some(self.scroll) {
+			return;
+		}
+		self.shown = Some(self.scroll);
+		let mut rows: Vec<(String, String)> =
+			self.rows.iter().skip(self.scroll).take(VISIBLE_ROWS).cloned().collect();
+		if self.rows.len() > VISIBLE_ROWS {
+			let last = (self.scroll + VISIBLE_ROWS).min(self.rows.len());
+			let position = format!("{}-{} of {}", self.scroll + 1, last, self.rows.len());
+			rows.push((String::new(), format!("{}, scroll for more", position)));
+		}
+		let widget = self.widget.upgrade().unwrap();
+		set_label_columns_boxed(
+			&widget,
+			&rows,
+			TEXT_HEIGHT,
+			KEY_COLOR,
+			TEXT_COLOR,
+			BG_COLOR,
+			PADDING,
+		);
+	}
+}
+
+/// The keys and the description of every action that has keys, in the order of `SHORTCUTS`.
+/// The bound actions without a description are listed by their name at the end, so that
+/// nothing that can be triggered is missing.
+fn shortcut_rows(config: &Configuration) -> Vec<(String, String)> {
+	let mut actions: Vec<(&str, &str)> = SHORTCUTS.to_vec();
+	let mut undescribed: Vec<&str> = DEFAULT_BINDINGS
+		.keys()
+		.copied()
+		.chain(config.bindings.iter().flat_map(|b| b.keys().map(String::as_str)))
+		.filter(|name| SHORTCUTS.iter().all(|(described, _)| described != name))
+		.collect();
+	undescribed.sort_unstable();
+	undescribed.dedup();
+	actions.extend(undescribed.into_iter().map(|name| (name, name)));
+	actions
+		.into_iter()
+		.filter_map(|(name, description)| {
+			let keys = action_keys(config, name);
+			if keys.is_empty() {
+				return None;
+			}
+			let keys: Vec<String> = keys.iter().map(|key| display_key(key)).collect();
+			Some((keys.join(", "), description.to_owned()))
+		})
+		.collect()
+}
+
+/// Writes `CmdCtrl` the way it's labeled on the keyboard
+fn display_key(key: &str) -> String {
+	let command_key = if cfg!(target_os = "macos") { "Cmd" } else { "Ctrl" };
+	key.replace("CmdCtrl", command_key)
+}
@@ -0,0 +1,29 @@
+//! The state of the inline text input for the tags of the current image.
+
+use std::path::{Path, PathBuf};
+
+use super::line_editor::LineEditor;
+use crate::tags::parse_tags;
+
+/// Edits the tags of an image as a comma separated list
+pub struct TagEditor {
+	pub image_path: PathBuf,
+	pub line: LineEditor,
+}
+
+impl TagEditor {
+	/// Starts editing the tags of the image at `path`, which currently has the `tags`
+	pub fn new(path: &Path, tags: &[String]) -> TagEditor {
+		TagEditor { image_path: path.to_owned(), line: LineEditor::new(tags.join(", ")) }
+	}
+
+	/// The text that's displayed in the bottom bar while editing, with a bar at the caret
+	pub fn display_text(&self) -> String {
+		let (before, after) = self.line.split_at_caret();
+		format!("Tags: {}|{}", before, after)
+	}
+
+	pub fn tags(&self) -> Vec<String> {
+		parse_tags(self.line.text())
+	}
+}
@@ -0,0 +1,262 @@
+//! Rasterizes text into pictures, because gelatin can only draw images.
+
+use std::rc::Rc;
+
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use gelatin::{
+	button::Button,
+	image::{imageops, Rgba, RgbaImage},
+	label::Label,
+	misc::{Length, LogicalVector},
+	picture::Picture,
+};
+use lazy_static::lazy_static;
+
+static FONT_DATA: &[u8] = include_bytes!("../../resource/DejaVuSans.ttf");
+
+/// The text is rendered at this multiple of its logical size so that it stays sharp on
+/// high DPI displays.
+pub const OVERSAMPLING: f32 = 2.0;
+
+pub const TEXT_COLOR: [u8; 3] = [230, 230, 230];
+/// The space between the columns of `set_label_columns_boxed`
+const COLUMN_GAP: f32 = 16.0;
+
+lazy_static! {
+	static ref FONT: FontRef<'static> = FontRef::try_from_slice(FONT_DATA).unwrap();
+}
+
+/// Renders a single line of text onto a transparent image.
+///
+/// `height` is the line height in logical pixels. Returns the image along with its logical size.
+pub fn render_text(text: &str, height: f32, color: [u8; 3]) -> (RgbaImage, LogicalVector) {
+	rasterize(text, height, color, false)
+}
+
+/// Same as `render_text`, but every character is centered in a cell as wide as `M`, so that
+/// the columns of the lines line up
+pub fn render_text_monospace(
+	text: &str,
+	height: f32,
+	color: [u8; 3],
+) -> (RgbaImage, LogicalVector) {
+	rasterize(text, height, color, true)
+}
+
+fn rasterize(
+	text: &str,
+	height: f32,
+	color: [u8; 3],
+	monospace: bool,
+) -> (RgbaImage, LogicalVector) {
+	let font = FONT.as_scaled(PxScale::from(height * OVERSAMPLING));
+	let cell_width = font.h_advance(font.glyph_id('M'));
+
+	let mut glyphs = Vec::new();
+	let mut caret = 0.0;
+	let mut prev_id = None;
+	for ch in text.chars() {
+		let id = font.glyph_id(ch);
+		if monospace {
+			let x = caret + (cell_width - font.h_advance(id)) * 0.5;
+			glyphs.push(id.with_scale_and_position(font.scale(), point(x, font.ascent())));
+			caret += cell_width;
+			continue;
+		}
+		if let Some(prev_id) = prev_id {
+			caret += font.kern(prev_id, id);
+		}
+		glyphs.push(id.with_scale_and_position(font.scale(), point(caret, font.ascent())));
+		caret += font.h_advance(id);
+		prev_id = Some(id);
+	}
+
+	let width = (caret.ceil() as u32).max(1);
+	let img_height = (font.height().ceil() as u32).max(1);
+	let mut image = RgbaImage::new(width, img_height);
+	for glyph in glyphs {
+		if let Some(outlined) = font.outline_glyph(glyph) {
+			let bounds = outlined.px_bounds();
+			outlined.draw(|x, y, coverage| {
+				let x = x as i32 + bounds.min.x as i32;
+				let y = y as i32 + bounds.min.y as i32;
+				if x >= 0 && y >= 0 && (x as u32) < width && (y as u32) < img_height {
+					let alpha = (coverage.min(1.0) * 255.0) as u8;
+					let pixel = image.get_pixel_mut(x as u32, y as u32);
+					if alpha > pixel[3] {
+						*pixel = Rgba([color[0], color[1], color[2], alpha]);
+					}
+				}
+			});
+		}
+	}
+	let logical_size =
+		LogicalVector::new(width as f32 / OVERSAMPLING, img_height as f32 / OVERSAMPLING);
+	(image, logical_size)
+}
+
+/// Displays the text on the label and resizes the label to fit the text
+pub fn set_label_text(label: &Label, text: &str, height: f32) {
+	set_label_text_colored(label, text, height, TEXT_COLOR);
+}
+
+/// Same as `set_label_text` but with the given text color
+pub fn set_label_text_colored(label: &Label, text: &str, height: f32, color: [u8; 3]) {
+	let (image, size) = render_text(text, height, color);
+	label.set_icon(Some(Rc::new(Picture::from_image(image))));
+	label.set_width(Length::Fixed(size.vec.x));
+	label.set_height(Length::Fixed(size.vec.y));
+}
+
+/// Displays the text on the button with some padding around it and resizes the button to fit
+pub fn set_button_text(button: &Button, text: &str, height: f32, padding: f32) {
+	let (text_image, size) = render_text(text, height, TEXT_COLOR);
+	let pad = (padding * OVERSAMPLING) as u32;
+	let mut image = RgbaImage::new(text_image.width() + 2 * pad, text_image.height());
+	imageops::overlay(&mut image, &text_image, pad as i64, 0);
+	button.set_icon(Some(Rc::new(Picture::from_image(image))));
+	button.set_width(Length::Fixed(size.vec.x + 2.0 * padding));
+	button.set_height(Length::Fixed(size.vec.y));
+}
+
+/// Displays the text on a filled box with some padding around the text, and resizes the label
+/// to fit the box
+pub fn set_label_text_boxed(
+	label: &Label,
+	text: &str,
+	height: f32,
+	color: [u8; 3],
+	bg_color: [u8; 4],
+	padding: f32,
+) {
+	let (text_image, size) = render_text(text, height, color);
+	let pad = (padding * OVERSAMPLING) as u32;
+	let mut image = RgbaImage::from_pixel(
+		text_image.width() + 2 * pad,
+		text_image.height() + 2 * pad,
+		Rgba(bg_color),
+	);
+	imageops::overlay(&mut image, &text_image, pad as i64, pad as i64);
+	label.set_icon(Some(Rc::new(Picture::from_image(image))));
+	label.set_width(Length::Fixed(size.vec.x + 2.0 * padding));
+	label.set_height(Length::Fixed(size.vec.y + 2.0 * padding));
+}
+
+/// Renders the text on a filled box with rounded corners. Returns the image along with its
+/// logical size.
+pub fn render_text_rounded_box(
+	text: &str,
+	height: f32,
+	color: [u8; 3],
+	bg_color: [u8; 4],
+	padding: f32,
+	radius: f32,
+) -> (RgbaImage, LogicalVector) {
+	let (text_image, size) = render_text(text, height, color);
+	let pad = (padding * OVERSAMPLING) as u32;
+	let (w, h) = (text_image.width() + 2 * pad, text_image.height() + 2 * pad);
+	let radius = (radius * OVERSAMPLING).min(w.min(h) as f32 / 2.0);
+	let mut image = RgbaImage::from_fn(w, h, |x, y| {
+		// The distance from the center of the pixel to the closest point that isn't cut off
+		// by a corner, which gives the corners a one pixel wide antialiased edge
+		let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+		let dx = (radius - px).max(px - (w as f32 - radius)).max(0.0);
+		let dy = (radius - py).max(py - (h as f32 - radius)).max(0.0);
+		let coverage = (radius + 0.5 - (dx * dx + dy * dy).sqrt()).clamp(0.0, 1.0);
+		let mut pixel = bg_color;
+		pixel[3] = (bg_color[3] as f32 * coverage).round() as u8;
+		Rgba(pixel)
+	});
+	imageops::overlay(&mut image, &text_image, pad as i64, pad as i64);
+	let logical_size = LogicalVector::new(size.vec.x + 2.0 * padding, size.vec.y + 2.0 * padding);
+	(image, logical_size)
+}
+
+/// Same as `set_label_text_boxed` but with the lines of text below each other
+pub fn set_label_lines_boxed(
+	label: &Label,
+	lines: &[String],
+	height: f32,
+	color: [u8; 3],
+	bg_color: [u8; 4],
+	padding: f32,
+) {
+	let rendered: Vec<_> = lines.iter().map(|line| render_text(line, height, color)).collect();
+	set_rendered_lines_boxed(label, &rendered, bg_color, padding);
+}
+
+/// Same as `set_label_lines_boxed` but with the text rendered by `render_text_monospace`
+pub fn set_label_lines_boxed_monospace(
+	label: &Label,
+	lines: &[String],
+	height: f32,
+	color: [u8; 3],
+	bg_color: [u8; 4],
+	padding: f32,
+) {
+	let rendered: Vec<_> =
+		lines.iter().map(|line| render_text_monospace(line, height, color)).collect();
+	set_rendered_lines_boxed(label, &rendered, bg_color, padding);
+}
+
+fn set_rendered_lines_boxed(
+	label: &Label,
+	rendered: &[(RgbaImage, LogicalVector)],
+	bg_color: [u8; 4],
+	padding: f32,
+) {
+	let width = rendered.iter().map(|(image, _)| image.width()).max().unwrap_or(1);
+	let logical_width = rendered.iter().map(|(_, size)| size.vec.x).fold(0.0, f32::max);
+	let text_height: u32 = rendered.iter().map(|(image, _)| image.height()).sum();
+	let logical_height: f32 = rendered.iter().map(|(_, size)| size.vec.y).sum();
+	let pad = (padding * OVERSAMPLING) as u32;
+	let mut image = RgbaImage::from_pixel(width + 2 * pad, text_height + 2 * pad, Rgba(bg_color));
+	let mut y = pad;
+	for (line_image, _) in rendered {
+		imageops::overlay(&mut image, line_image, pad as i64, y as i64);
+		y += line_image.height();
+	}
+	label.set_icon(Some(Rc::new(Picture::from_image(image))));
+	label.set_width(Length::Fixed(logical_width + 2.0 * padding));
+	label.set_height(Length::Fixed(logical_height + 2.0 * padding));
+}
+
+/// Same as `set_label_lines_boxed` but with two columns of text, the first one in `key_color`.
+/// The second column starts `COLUMN_GAP` right of the widest entry of the first one.
+pub fn set_label_columns_boxed(
+	label: &Label,
+	rows: &[(String, String)],
+	height: f32,
+	key_color: [u8; 3],
+	color: [u8; 3],
+	bg_color: [u8; 4],
+	padding: f32,
+) {
+	let gap = COLUMN_GAP;
+	let rendered: Vec<_> = rows
+		.iter()
+		.map(|(key, text)| (render_text(key, height, key_color), render_text(text, height, color)))
+		.collect();
+	let key_width = rendered.iter().map(|((image, _), _)| image.width()).max().unwrap_or(0);
+	let text_width = rendered.iter().map(|(_, (image, _))| image.width()).max().unwrap_or(1);
+	let logical_key_width = rendered.iter().map(|((_, size), _)| size.vec.x).fold(0.0, f32::max);
+	let logical_text_width = rendered.iter().map(|(_, (_, size))| size.vec.x).fold(0.0, f32::max);
+	let row_heights: Vec<u32> =
+		rendered.iter().map(|((key, _), (text, _))| key.height().max(text.height())).collect();
+	let logical_height: f32 =
+		rendered.iter().map(|((_, key), (_, text))| key.vec.y.max(text.vec.y)).sum();
+	let pad = (padding * OVERSAMPLING) as u32;
+	let gap_px = (gap * OVERSAMPLING) as u32;
+	let width = key_width + gap_px + text_width + 2 * pad;
+	let height_px = row_heights.iter().sum::<u32>() + 2 * pad;
+	let mut image = RgbaImage::from_pixel(width, height_px, Rgba(bg_color));
+	let mut y = pad;
+	for (((key_image, _), (text_image, _)), row_height) in rendered.iter().zip(row_heights) {
+		imageops::overlay(&mut image, key_image, pad as i64, y as i64);
+		imageops::overlay(&mut image, text_image, (pad + key_width + gap_px) as i64, y as i64);
+		y += row_height;
+	}
+	label.set_icon(Some(Rc::new(Picture::from_image(image))));
+	label.set_width(Length::Fixed(logical_key_width + gap + logical_text_width + 2.0 * padding));
+	label.set_height(Length::Fixed(logical_height + 2.0 * padding));
+}
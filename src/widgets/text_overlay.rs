@@ -0,0 +1,114 @@
+use std::rc::{Rc, Weak};
+use std::time::{Duration, Instant};
+
+use gelatin::{image::RgbaImage, label::Label, misc::*, picture::Picture, NextUpdate};
+
+use super::text::render_text_rounded_box;
+
+const TEXT_HEIGHT: f32 = 16.0;
+const PADDING: f32 = 10.0;
+const CORNER_RADIUS: f32 = 8.0;
+const TEXT_COLOR: [u8; 3] = [255, 255, 255];
+const BG_COLOR: [u8; 4] = [20, 20, 20, 200];
+/// How long the message takes to fade out at the end of its duration
+const FADE_TIME: Duration = Duration::from_millis(300);
+/// The duration of the confirmations of the actions
+pub const CONFIRMATION_MS: u32 = 1200;
+
+/// Where the text is shown over the picture. The bottom is left to the error messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlayPosition {
+	Top,
+	Center,
+}
+
+/// A message that's shown over the picture for a while
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverlayText {
+	pub text: String,
+	/// How long the message is fully visible, the fade out comes after this
+	pub duration_ms: u32,
+	pub position: OverlayPosition,
+}
+
+impl OverlayText {
+	/// A short confirmation of an action at the top of the picture
+	pub fn confirmation(text: impl Into<String>) -> OverlayText {
+		OverlayText {
+			text: text.into(),
+			duration_ms: CONFIRMATION_MS,
+			position: OverlayPosition::Top,
+		}
+	}
+}
+
+struct Shown {
+	image: RgbaImage,
+	fade_start: Instant,
+}
+
+/// Shows one `OverlayText` at a time in a semi-transparent rounded box. A new message replaces
+/// the one that's being shown.
+pub struct TextOverlay {
+	pub widget: Weak<Label>,
+	shown: Option<Shown>,
+}
+
+impl TextOverlay {
+	pub fn new(widget: &Rc<Label>) -> TextOverlay {
+		widget.set_icon(None);
+		widget.set_ignore_layout(true);
+		widget.set_margin_all(16.0);
+		widget.set_horizontal_align(Alignment::Center);
+		widget.set_visible(false);
+
+		TextOverlay { widget: Rc::downgrade(widget), shown: None }
+	}
+
+	pub fn show(&mut self, overlay: OverlayText) {
+		let widget = self.widget.upgrade().unwrap();
+		let (image, size) = render_text_rounded_box(
+			&overlay.text,
+			TEXT_HEIGHT,
+			TEXT_COLOR,
+			BG_COLOR,
+			PADDING,
+			CORNER_RADIUS,
+		);
+		widget.set_vertical_align(match overlay.position {
+			OverlayPosition::Top => Alignment::Start,
+			OverlayPosition::Center => Alignment::Center,
+		});
+		widget.set_width(Length::Fixed(size.vec.x));
+		widget.set_height(Length::Fixed(size.vec.y));
+		widget.set_icon(Some(Rc::new(Picture::from_image(image.clone()))));
+		widget.set_visible(true);
+		let fade_start = Instant::now() + Duration::from_millis(overlay.duration_ms as u64);
+		self.shown = Some(Shown { image, fade_start });
+	}
+
+	pub fn update(&mut self) -> NextUpdate {
+		let shown = match &self.shown {
+			Some(shown) => shown,
+			None => return NextUpdate::Latest,
+		};
+		let widget = self.widget.upgrade().unwrap();
+		let now = Instant::now();
+		if now < shown.fade_start {
+			return NextUpdate::WaitUntil(shown.fade_start);
+		}
+		let faded = now.duration_since(shown.fade_start).as_secs_f32() / FADE_TIME.as_secs_f32();
+		if faded >= 1.0 {
+			widget.set_visible(false);
+			self.shown = None;
+			return NextUpdate::Latest;
+		}
+		// The label can't be drawn transparent, so the alpha of the picture is scaled instead
+		let mut image = shown.image.clone();
+		for pixel in image.pixels_mut() {
+			pixel[3] = (pixel[3] as f32 * (1.0 - faded)).round() as u8;
+		}
+		widget.set_icon(Some(Rc::new(Picture::from_image(image))));
+		NextUpdate::Soonest
+	}
+}
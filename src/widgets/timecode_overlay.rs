@@ -0,0 +1,70 @@
+use std::path::Path;
+use std::rc::{Rc, Weak};
+
+use gelatin::{label::Label, misc::*};
+
+use super::text::set_label_lines_boxed_monospace;
+use crate::timecode::{frame_number, timecode};
+
+const TEXT_HEIGHT: f32 = 16.0;
+const PADDING: f32 = 8.0;
+const TEXT_COLOR: [u8; 3] = [240, 240, 240];
+const BG_COLOR: [u8; 4] = [20, 20, 20, 220];
+
+/// Shows the timecode of the frame number in the name of the shown file in the top left
+/// corner. It stays open while files without a number are shown, but hides until the next
+/// numbered one.
+pub struct TimecodeOverlay {
+	pub widget: Weak<Label>,
+	open: bool,
+	/// The timecode that's displayed
+	shown: Option<String>,
+}
+
+impl TimecodeOverlay {
+	pub fn new(widget: &Rc<Label>) -> TimecodeOverlay {
+		widget.set_icon(None);
+		widget.set_ignore_layout(true);
+		widget.set_margin_all(8.0);
+		// The preload indicator shares the corner, but only while `--preload-all` loads
+		widget.set_horizontal_align(Alignment::Start);
+		widget.set_vertical_align(Alignment::Start);
+		widget.set_visible(false);
+
+		TimecodeOverlay { widget: Rc::downgrade(widget), open: false, shown: None }
+	}
+
+	pub fn toggle(&mut self) {
+		self.open = !self.open;
+		self.shown = None;
+		if let Some(widget) = self.widget.upgrade() {
+			widget.set_visible(false);
+		}
+	}
+
+	/// Displays the timecode of the file at `path`. Returns true if the overlay changed.
+	pub fn update(&mut self, path: Option<&Path>, fps: f32) -> bool {
+		if !self.open {
+			return false;
+		}
+		let text = path.and_then(frame_number).map(|frame| timecode(frame, fps));
+		if text == self.shown {
+			return false;
+		}
+		let widget = self.widget.upgrade().unwrap();
+		if let Some(text) = &text {
+			let lines = [text.clone()];
+			set_label_lines_boxed_monospace(
+				&widget,
+				&lines,
+				TEXT_HEIGHT,
+				TEXT_COLOR,
+				BG_COLOR,
+				PADDING,
+			);
+		}
+		widget.set_visible(text.is_some());
+		self.shown = text;
+		true
+	}
+}
@@ -43,11 +43,9 @@ fn aggregate_control_flow<E>(event_loop: &EventLoopWindowTarget<E>, new: Control
 			return true;
 		}
 		ControlFlow::WaitUntil(new_time) => match original {
-			ControlFlow::WaitUntil(orig_time) => {
-				if new_time < orig_time {
-					set_control_flow(event_loop, new);
-					return true;
-				}
+			ControlFlow::WaitUntil(orig_time) if new_time < orig_time => {
+				set_control_flow(event_loop, new);
+				return true;
 			}
 			ControlFlow::Wait => {
 				set_control_flow(event_loop, new);
@@ -192,7 +190,15 @@ where
 						for window in windows.values() {
 							window.main_events_cleared();
 							if window.redraw_needed() {
-								window.request_redraw();
+								match window.next_redraw_time() {
+									None => window.request_redraw(),
+									Some(time) => {
+										aggregate_control_flow(
+											event_loop,
+											ControlFlow::WaitUntil(time),
+										);
+									}
+								}
 							}
 						}
 						// event_loop.set_control_flow(ControlFlow::Wait);
@@ -0,0 +1,48 @@
+use std::time::{Duration, Instant};
+
+/// Keeps the redraws of a window from happening more often than the target frame rate.
+pub struct FrameRateLimiter {
+	/// `None` means that there's no limit
+	min_frame_time: Option<Duration>,
+	last_draw: Option<Instant>,
+	forced: bool,
+}
+
+impl FrameRateLimiter {
+	pub const DEFAULT_FPS: u32 = 60;
+
+	pub fn new(target_fps: u32) -> Self {
+		let mut limiter = FrameRateLimiter { min_frame_time: None, last_draw: None, forced: false };
+		limiter.set_target_fps(target_fps);
+		limiter
+	}
+
+	/// Zero disables the limit
+	pub fn set_target_fps(&mut self, target_fps: u32) {
+		self.min_frame_time =
+			if target_fps == 0 { None } else { Some(Duration::from_secs(1) / target_fps) };
+	}
+
+	/// Lets the next redraw happen right away, regardless of the time of the previous one
+	pub fn force_next_draw(&mut self) {
+		self.forced = true;
+	}
+
+	pub fn record_draw(&mut self) {
+		self.last_draw = Some(Instant::now());
+		self.forced = false;
+	}
+
+	/// Returns `None` if a redraw may happen now, otherwise the earliest time it may happen at
+	pub fn next_draw_time(&self) -> Option<Instant> {
+		if self.forced {
+			return None;
+		}
+		let next = self.last_draw? + self.min_frame_time?;
+		if Instant::now() < next {
+			Some(next)
+		} else {
+			None
+		}
+	}
+}
@@ -10,12 +10,12 @@ use std::{
 };
 
 use winit::{
-	event::{ElementState, KeyEvent, MouseButton},
+	event::{ElementState, KeyEvent, MouseButton, TouchPhase},
 	event_loop::ControlFlow,
 	keyboard::ModifiersState,
 };
 
-use cgmath::{Matrix4, Vector3};
+use cgmath::{Matrix4, Rad, Vector3};
 use glium::{
 	glutin::surface::WindowSurface, implement_vertex, uniform, Blend, BlendingFunction, Frame,
 	IndexBuffer, LinearBlendingFactor, Program, Rect, Surface, VertexBuffer,
@@ -25,12 +25,14 @@ use misc::*;
 
 pub mod application;
 pub mod button;
+pub mod frame_rate_limiter;
 pub mod label;
 pub mod line_layout_container;
 pub mod misc;
 pub mod picture;
 pub mod shaders;
 pub mod slider;
+pub mod tree_view;
 pub mod window;
 
 pub type Display = glium::Display<WindowSurface>;
@@ -332,14 +334,28 @@ pub struct Event {
 }
 pub enum EventKind {
 	MouseMove,
-	MouseButton { state: ElementState, button: MouseButton },
-	MouseScroll { delta: LogicalVector },
-	KeyInput { input: KeyEvent },
+	MouseButton {
+		state: ElementState,
+		button: MouseButton,
+	},
+	MouseScroll {
+		delta: LogicalVector,
+	},
+	KeyInput {
+		input: KeyEvent,
+	},
 	DroppedFile(PathBuf),
 	HoveredFile(PathBuf),
 	HoveredFileCancelled,
 	Focused(bool),
 	CloseRequested,
+	/// A finger on a touchscreen. `id` tells the fingers apart while they touch the screen and
+	/// `pos` is in virtual pixels, like `cursor_pos`.
+	Touch {
+		id: u64,
+		phase: TouchPhase,
+		pos: LogicalVector,
+	},
 }
 
 #[derive(Copy, Clone)]
@@ -388,6 +404,54 @@ impl<'a> DrawContext<'a> {
 			let scale = Matrix4::from_scale(2.0);
 			transform = Matrix4::from_translation(Vector3::new(-1.0, -1.0, 0.0)) * scale;
 		}
+		self.fill_unit_quad(target, transform, color);
+	}
+
+	/// Draws a straight line from `from` to `to` that is `width` thick, in logical pixels
+	pub fn draw_line(
+		&self,
+		target: &mut Frame,
+		from: LogicalVector,
+		to: LogicalVector,
+		width: f32,
+		color: [f32; 4],
+	) {
+		let delta = to.vec - from.vec;
+		let length = delta.x.hypot(delta.y);
+		// The unit quad is stretched along the x axis, centered on it, and rotated onto the line
+		let scale = Matrix4::from_nonuniform_scale(length, width, 1.0);
+		let centered = Matrix4::from_translation(Vector3::new(0.0, -0.5 * width, 0.0)) * scale;
+		let rotated = Matrix4::from_angle_z(Rad(delta.y.atan2(delta.x))) * centered;
+		let transform = Matrix4::from_translation(from.vec.extend(0.0)) * rotated;
+		self.fill_unit_quad(target, self.projection_transform * transform, color);
+	}
+
+	/// Draws the part of a circle outline from `start` going counter-clockwise by `sweep` (both
+	/// in radians, with the y axis pointing down) as a chain of short lines
+	#[allow(clippy::too_many_arguments)]
+	pub fn draw_arc(
+		&self,
+		target: &mut Frame,
+		center: LogicalVector,
+		radius: f32,
+		width: f32,
+		start: f32,
+		sweep: f32,
+		color: [f32; 4],
+	) {
+		// Short enough to look round on large arcs
+		let segments = ((sweep.abs() * radius / 4.0).ceil() as usize).max(1);
+		let point =
+			|angle: f32| center + LogicalVector::new(angle.cos() * radius, -angle.sin() * radius);
+		let mut prev = point(start);
+		for i in 1..=segments {
+			let next = point(start + sweep * i as f32 / segments as f32);
+			self.draw_line(target, prev, next, width, color);
+			prev = next;
+		}
+	}
+
+	fn fill_unit_quad(&self, target: &mut Frame, transform: Matrix4<f32>, color: [f32; 4]) {
 		let image_draw_params = glium::DrawParameters {
 			blend: Blend {
 				color: BlendingFunction::Addition {
@@ -8,7 +8,7 @@ use std::ops::{Add, AddAssign, Div, Mul, Sub};
 /// This struct is distinct from `PhysicalVector` which represents
 /// physical pixel coordinates and dimensions to avoid
 /// confusion when dealing with scaled dpi scenarios.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct LogicalVector {
 	pub vec: Vector2<f32>,
 }
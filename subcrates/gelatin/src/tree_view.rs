@@ -0,0 +1,335 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use cgmath::{Matrix4, Vector3};
+use glium::{uniform, Frame, Surface};
+use winit::event::{ElementState, MouseButton};
+
+use crate::add_common_widget_functions;
+use crate::misc::{Alignment, Length, LogicalRect, LogicalVector, WidgetPlacement};
+use crate::picture::Picture;
+use crate::window::RenderValidity;
+use crate::NextUpdate;
+use crate::{DrawContext, Event, EventKind, Widget, WidgetData, WidgetError};
+
+const DEFAULT_ROW_HEIGHT: f32 = 24.0;
+const DEFAULT_INDENT: f32 = 16.0;
+/// The number of rows that one step of the mouse wheel scrolls
+const SCROLL_ROWS: usize = 3;
+const MARKER_SIZE: f32 = 4.0;
+const MARKER_WIDTH: f32 = 1.5;
+const MARKER_COLOR: [f32; 4] = [0.8, 0.8, 0.8, 1.0];
+const HOVER_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 0.08];
+
+/// One line of a `TreeView`. The text is drawn by the user of the widget into `label`.
+pub struct TreeViewRow {
+	/// The number of ancestors of the node, which sets how far the row is indented
+	pub depth: usize,
+	/// Whether the node has children, which gets it a marker for expanding it
+	pub expandable: bool,
+	pub expanded: bool,
+	pub label: Rc<Picture>,
+	/// The logical size that the label is drawn at
+	pub label_size: LogicalVector,
+}
+
+struct TreeViewData {
+	placement: WidgetPlacement,
+	drawn_bounds: LogicalRect,
+	visible: bool,
+
+	rows: Vec<TreeViewRow>,
+	row_height: f32,
+	indent: f32,
+	bg_color: [f32; 4],
+	/// The index of the topmost row that is shown
+	first_row: usize,
+	hover_row: Option<usize>,
+	pressed_row: Option<usize>,
+	on_click: Option<Rc<dyn Fn(usize)>>,
+
+	render_validity: RenderValidity,
+}
+impl WidgetData for TreeViewData {
+	fn placement(&mut self) -> &mut WidgetPlacement {
+		&mut self.placement
+	}
+	fn drawn_bounds(&mut self) -> &mut LogicalRect {
+		&mut self.drawn_bounds
+	}
+	fn visible(&mut self) -> &mut bool {
+		&mut self.visible
+	}
+}
+
+impl TreeViewData {
+	/// The number of rows that fit into the widget completely
+	fn visible_row_count(&self) -> usize {
+		(self.drawn_bounds.size.vec.y / self.row_height).floor().max(0.0) as usize
+	}
+
+	fn max_first_row(&self) -> usize {
+		self.rows.len().saturating_sub(self.visible_row_count())
+	}
+
+	fn row_at(&self, pos: LogicalVector) -> Option<usize> {
+		if !self.drawn_bounds.contains(pos) {
+			return None;
+		}
+		let offset = ((pos.vec.y - self.drawn_bounds.top()) / self.row_height) as usize;
+		let index = self.first_row + offset;
+		(offset < self.visible_row_count() && index < self.rows.len()).then_some(index)
+	}
+}
+
+/// A scrollable list of rows that are indented by their depth in a tree, with a marker
+/// showing whether each node with children is expanded. Which nodes are shown is up to the
+/// user of the widget, who gets the index of the row that was clicked.
+pub struct TreeView {
+	data: RefCell<TreeViewData>,
+}
+
+impl TreeView {
+	pub fn new() -> TreeView {
+		TreeView {
+			data: RefCell::new(TreeViewData {
+				placement: Default::default(),
+				drawn_bounds: Default::default(),
+				visible: true,
+				rows: Vec::new(),
+				row_height: DEFAULT_ROW_HEIGHT,
+				indent: DEFAULT_INDENT,
+				bg_color: [0.0, 0.0, 0.0, 0.0],
+				first_row: 0,
+				hover_row: None,
+				pressed_row: None,
+				on_click: None,
+				render_validity: Default::default(),
+			}),
+		}
+	}
+
+	add_common_widget_functions!(data);
+
+	/// Replaces the rows. The scroll position is kept as far as the new rows allow.
+	pub fn set_rows(&self, rows: Vec<TreeViewRow>) {
+		let mut borrowed = self.data.borrow_mut();
+		borrowed.rows = rows;
+		borrowed.first_row = borrowed.first_row.min(borrowed.rows.len().saturating_sub(1));
+		borrowed.hover_row = None;
+		borrowed.pressed_row = None;
+		borrowed.render_validity.invalidate();
+	}
+
+	pub fn set_row_height(&self, row_height: f32) {
+		let mut borrowed = self.data.borrow_mut();
+		borrowed.row_height = row_height;
+		borrowed.render_validity.invalidate();
+	}
+
+	/// The space left of the marker for each level of depth
+	pub fn set_indent(&self, indent: f32) {
+		let mut borrowed = self.data.borrow_mut();
+		borrowed.indent = indent;
+		borrowed.render_validity.invalidate();
+	}
+
+	pub fn set_bg_color(&self, color: [f32; 4]) {
+		let mut borrowed = self.data.borrow_mut();
+		borrowed.bg_color = color;
+		borrowed.render_validity.invalidate();
+	}
+
+	/// The callback gets the index of the clicked row
+	pub fn set_on_click<F: Fn(usize) + 'static>(&self, callback: F) {
+		self.data.borrow_mut().on_click = Some(Rc::new(callback));
+	}
+
+	/// The index of the topmost row that is shown
+	pub fn first_row(&self) -> usize {
+		self.data.borrow().first_row
+	}
+}
+
+impl Default for TreeView {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Widget for TreeView {
+	fn draw(&self, target: &mut Frame, context: &DrawContext) -> Result<NextUpdate, WidgetError> {
+		let borrowed = self.data.borrow();
+		if !borrowed.visible {
+			return Ok(NextUpdate::Latest);
+		}
+		let bounds = borrowed.drawn_bounds.align_to_pixels(context.dpi_scale_factor);
+		if borrowed.bg_color[3] > 0.0 {
+			context.clear_color(target, borrowed.bg_color, Some(bounds));
+		}
+		let shown = borrowed.rows.iter().enumerate().skip(borrowed.first_row);
+		for (i, row) in shown.take(borrowed.visible_row_count()) {
+			let top = bounds.top() + (i - borrowed.first_row) as f32 * borrowed.row_height;
+			if borrowed.hover_row == Some(i) {
+				let rect = LogicalRect {
+					pos: LogicalVector::new(bounds.left(), top),
+					size: LogicalVector::new(bounds.size.vec.x, borrowed.row_height),
+				};
+				context.clear_color(target, HOVER_COLOR, Some(rect));
+			}
+			let marker_x = bounds.left() + (row.depth as f32 + 0.5) * borrowed.indent;
+			let middle = LogicalVector::new(marker_x, top + borrowed.row_height * 0.5);
+			if row.expandable {
+				draw_marker(target, context, middle, row.expanded);
+			}
+			let label_pos = LogicalVector::new(
+				marker_x + borrowed.indent * 0.5,
+				top + (borrowed.row_height - row.label_size.vec.y) * 0.5,
+			);
+			let label_pos = LogicalRect { pos: label_pos, size: row.label_size }
+				.align_to_pixels(context.dpi_scale_factor);
+			draw_picture(target, context, &row.label, label_pos)?;
+		}
+		Ok(NextUpdate::Latest)
+	}
+
+	fn layout(&self, available_space: LogicalRect) {
+		let mut borrowed = self.data.borrow_mut();
+		borrowed.default_layout(available_space);
+		borrowed.first_row = borrowed.first_row.min(borrowed.max_first_row());
+	}
+
+	fn handle_event(&self, event: &Event) {
+		let on_click = {
+			let mut borrowed = self.data.borrow_mut();
+			if !borrowed.visible {
+				return;
+			}
+			match event.kind {
+				EventKind::MouseMove => {
+					let hover_row = borrowed.row_at(event.cursor_pos);
+					if hover_row != borrowed.hover_row {
+						borrowed.hover_row = hover_row;
+						borrowed.render_validity.invalidate();
+					}
+					None
+				}
+				EventKind::MouseButton { state, button: MouseButton::Left } => match state {
+					ElementState::Pressed => {
+						borrowed.pressed_row = borrowed.row_at(event.cursor_pos);
+						None
+					}
+					ElementState::Released => {
+						let pressed_row = borrowed.pressed_row.take();
+						let clicked = pressed_row
+							.filter(|&row| borrowed.row_at(event.cursor_pos) == Some(row));
+						clicked.zip(borrowed.on_click.clone())
+					}
+				},
+				EventKind::MouseScroll { delta } => {
+					if borrowed.drawn_bounds.contains(event.cursor_pos) && delta.vec.y != 0.0 {
+						// Scrolling up shows the rows above
+						borrowed.first_row = if delta.vec.y > 0.0 {
+							borrowed.first_row.saturating_sub(SCROLL_ROWS)
+						} else {
+							(borrowed.first_row + SCROLL_ROWS).min(borrowed.max_first_row())
+						};
+						borrowed.hover_row = borrowed.row_at(event.cursor_pos);
+						borrowed.render_validity.invalidate();
+					}
+					None
+				}
+				_ => None,
+			}
+		};
+		// Called without the borrow, because the callback is likely to set new rows
+		if let Some((row, callback)) = on_click {
+			callback(row);
+		}
+	}
+
+	fn children(&self, _children: &mut Vec<Rc<dyn Widget>>) {}
+
+	fn placement(&self) -> WidgetPlacement {
+		self.data.borrow().placement
+	}
+
+	fn visible(&self) -> bool {
+		self.data.borrow().visible
+	}
+
+	fn set_valid_ref(&self, render_validity: RenderValidity) {
+		self.data.borrow_mut().render_validity = render_validity;
+	}
+}
+
+/// A chevron pointing down for the expanded nodes and to the right for the collapsed ones
+fn draw_marker(target: &mut Frame, context: &DrawContext, center: LogicalVector, expanded: bool) {
+	let s = MARKER_SIZE;
+	let (first, tip, last) = if expanded {
+		(
+			LogicalVector::new(-s, -s * 0.5),
+			LogicalVector::new(0.0, s * 0.5),
+			LogicalVector::new(s, -s * 0.5),
+		)
+	} else {
+		(
+			LogicalVector::new(-s * 0.5, -s),
+			LogicalVector::new(s * 0.5, 0.0),
+			LogicalVector::new(-s * 0.5, s),
+		)
+	};
+	context.draw_line(target, center + first, center + tip, MARKER_WIDTH, MARKER_COLOR);
+	context.draw_line(target, center + tip, center + last, MARKER_WIDTH, MARKER_COLOR);
+}
+
+fn draw_picture(
+	target: &mut Frame,
+	context: &DrawContext,
+	picture: &Picture,
+	rect: LogicalRect,
+) -> Result<(), WidgetError> {
+	use glium::{Blend, BlendingFunction, LinearBlendingFactor};
+
+	let size = rect.size.vec;
+	let transform = Matrix4::from_nonuniform_scale(size.x, size.y, 1.0);
+	let transform = Matrix4::from_translation(rect.pos.vec.extend(0.0)) * transform;
+	let transform = context.projection_transform * transform;
+	let draw_params = glium::DrawParameters {
+		viewport: Some(*context.viewport),
+		blend: Blend {
+			color: BlendingFunction::Addition {
+				source: LinearBlendingFactor::SourceAlpha,
+				destination: LinearBlendingFactor::OneMinusSourceAlpha,
+			},
+			..Default::default()
+		},
+		..Default::default()
+	};
+	let texture = picture.texture(context.display)?;
+	let sampler = texture
+		.sampled()
+		.wrap_function(glium::uniforms::SamplerWrapFunction::Clamp)
+		.minify_filter(glium::uniforms::MinifySamplerFilter::Linear)
+		.magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear);
+	let uniforms = uniform! {
+		matrix: Into::<[[f32; 4]; 4]>::into(transform),
+		tex: sampler,
+		color: [1.0f32, 1.0, 1.0, 1.0],
+		bg_color: [0.0f32, 0.0, 0.0, 0.0],
+		texture_size: [size.x, size.y],
+		brighten: 0.0f32,
+		shadow_color: Into::<[f32; 3]>::into(Vector3::<f32>::new(0.0, 0.0, 0.0)),
+		shadow_offset: 1.0f32,
+	};
+	target
+		.draw(
+			context.unit_quad_vertices,
+			context.unit_quad_indices,
+			context.textured_program,
+			&uniforms,
+			&draw_params,
+		)
+		.unwrap();
+	Ok(())
+}
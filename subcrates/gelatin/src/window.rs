@@ -42,6 +42,7 @@ use derive_builder::Builder;
 use crate::shaders;
 use crate::{
 	application::Application,
+	frame_rate_limiter::FrameRateLimiter,
 	shaders::{shader_from_source, ShaderDescriptor},
 };
 use crate::{
@@ -147,11 +148,21 @@ pub struct WindowDescriptor {
 
 pub type EventHandler = dyn FnMut(&Window, &WindowEvent);
 
+/// The position and the inner size of a window in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowState {
+	pub x: i32,
+	pub y: i32,
+	pub width: u32,
+	pub height: u32,
+}
+
 struct WindowData {
 	display: glium::Display<WindowSurface>,
 	window: winit::window::Window,
 
-	size_before_fullscreen: PhysicalSize<u32>,
+	/// Restored when leaving fullscreen. `None` if the position couldn't be queried.
+	state_before_fullscreen: Option<WindowState>,
 	fullscreen: bool,
 	last_mouse_move_update_time: std::time::Instant,
 	unprocessed_move_event: Option<Event>,
@@ -160,6 +171,7 @@ struct WindowData {
 	new_title: Option<String>,
 
 	render_validity: RenderValidity,
+	frame_rate_limiter: FrameRateLimiter,
 	cursor_pos: LogicalVector,
 	modifiers: ModifiersState,
 	root_widget: Rc<dyn Widget>,
@@ -234,7 +246,7 @@ impl Window {
 		#[cfg(not(any(target_os = "macos", windows)))]
 		let window_builder = if let Some(app_id) = desc.app_id {
 			let is_wayland = std::env::var("XDG_SESSION_TYPE")
-				.map_or(false, |var| var.to_lowercase().contains("wayland"));
+				.is_ok_and(|var| var.to_lowercase().contains("wayland"));
 			if is_wayland {
 				WindowBuilderExtWayland::with_name(window_builder, &app_id, app_id.to_lowercase())
 			} else {
@@ -306,7 +318,7 @@ impl Window {
 			data: RefCell::new(WindowData {
 				display,
 				window,
-				size_before_fullscreen: desc.size,
+				state_before_fullscreen: None,
 				fullscreen: false,
 				last_mouse_move_update_time: std::time::Instant::now(),
 				unprocessed_move_event: None,
@@ -315,6 +327,7 @@ impl Window {
 				cursor_pos: Default::default(),
 				modifiers: ModifiersState::empty(),
 				render_validity: RenderValidity { validity: Rc::new(Cell::new(false)) },
+				frame_rate_limiter: FrameRateLimiter::new(FrameRateLimiter::DEFAULT_FPS),
 				root_widget: Rc::new(crate::line_layout_container::VerticalLayoutContainer::new()),
 				bg_color: [0.85, 0.85, 0.85, 1.0],
 
@@ -544,6 +557,18 @@ impl Window {
 						kind: EventKind::Focused(focused),
 					});
 				}
+				WindowEvent::Touch(touch) => {
+					let scaling = borrowed.window.scale_factor() as f32;
+					event = Some(Event {
+						cursor_pos: borrowed.cursor_pos,
+						modifiers: borrowed.modifiers,
+						kind: EventKind::Touch {
+							id: touch.id,
+							phase: touch.phase,
+							pos: LogicalVector::from_physical(touch.location, scaling),
+						},
+					});
+				}
 				WindowEvent::ModifiersChanged(modifiers) => {
 					borrowed.modifiers = modifiers.state();
 					event = None;
@@ -599,6 +624,23 @@ impl Window {
 		!self.data.borrow().render_validity.get()
 	}
 
+	/// Limits the redraws to this many per second. Zero disables the limit.
+	pub fn set_target_fps(&self, target_fps: u32) {
+		self.data.borrow_mut().frame_rate_limiter.set_target_fps(target_fps);
+	}
+
+	/// Lets the next redraw happen without waiting for the frame rate limit, for example
+	/// because new content arrived that should be shown as soon as possible
+	pub fn force_next_redraw(&self) {
+		self.data.borrow_mut().frame_rate_limiter.force_next_draw();
+	}
+
+	/// Returns `None` if the window may be redrawn now, otherwise the earliest time it may be
+	/// redrawn at
+	pub fn next_redraw_time(&self) -> Option<std::time::Instant> {
+		self.data.borrow().frame_rate_limiter.next_draw_time()
+	}
+
 	/// WARNING The window may not be changed during the drawing phase.
 	/// This means that trying to borrow the window *mutably* in a widget's
 	/// draw function will fail.
@@ -610,6 +652,7 @@ impl Window {
 				borrowed.window.set_title(&new_title);
 			}
 			borrowed.last_event_invalidated = false;
+			borrowed.frame_rate_limiter.record_draw();
 		}
 		// this way self.data is not borrowed while before draw is running.
 		let dpi_scaling = self.data.borrow().window.scale_factor();
@@ -678,20 +721,35 @@ impl Window {
 		self.data.borrow().fullscreen
 	}
 
+	/// Switches to borderless fullscreen on the current monitor, or back to the position and
+	/// size that the window had before
 	pub fn set_fullscreen(&self, fullscreen: bool) {
 		let mut borrowed = self.data.borrow_mut();
+		if borrowed.fullscreen == fullscreen {
+			return;
+		}
 		borrowed.fullscreen = fullscreen;
-		let monitor = if fullscreen {
-			let curr_mon;
-			borrowed.size_before_fullscreen = {
-				curr_mon = borrowed.window.current_monitor();
-				borrowed.window.inner_size()
-			};
-			Some(Fullscreen::Borderless(curr_mon))
+		if fullscreen {
+			let size = borrowed.window.inner_size();
+			borrowed.state_before_fullscreen = borrowed.window.outer_position().ok().map(|pos| {
+				WindowState { x: pos.x, y: pos.y, width: size.width, height: size.height }
+			});
+			let curr_mon = borrowed.window.current_monitor();
+			borrowed.window.set_fullscreen(Some(Fullscreen::Borderless(curr_mon)));
 		} else {
-			None
-		};
-		borrowed.window.set_fullscreen(monitor);
+			borrowed.window.set_fullscreen(None);
+			if let Some(state) = borrowed.state_before_fullscreen.take() {
+				let _ = borrowed
+					.window
+					.request_inner_size(PhysicalSize::new(state.width, state.height));
+				borrowed.window.set_outer_position(PhysicalPosition::new(state.x, state.y));
+			}
+		}
+	}
+
+	/// The position and size that leaving fullscreen restores, if the window is fullscreen
+	pub fn state_before_fullscreen(&self) -> Option<WindowState> {
+		self.data.borrow().state_before_fullscreen
 	}
 
 	pub fn set_maximized(&self, maximized: bool) {
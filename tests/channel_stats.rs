@@ -0,0 +1,31 @@
+//! The per channel statistics of `channel_stats`.
+
+#[allow(dead_code)]
+#[path = "../src/channel_stats.rs"]
+mod channel_stats;
+
+use channel_stats::{channel_stats, table_lines, ChannelStats};
+use gelatin::image::{Rgba, RgbaImage};
+
+#[test]
+fn every_channel_gets_its_own_statistics() {
+	// Half of the rows are black and half are white, the alpha is the same everywhere
+	let image = RgbaImage::from_fn(3, 4, |_, y| {
+		let value = if y < 2 { 0 } else { 255 };
+		Rgba([value, value / 5, 100, 255])
+	});
+	let [r, g, b, a] = channel_stats(&image).unwrap();
+	assert_eq!(r, ChannelStats { min: 0, max: 255, mean: 127.5, std_dev: 127.5 });
+	assert_eq!((g.min, g.max, g.mean, g.std_dev), (0, 51, 25.5, 25.5));
+	assert_eq!(b, ChannelStats { min: 100, max: 100, mean: 100.0, std_dev: 0.0 });
+	assert_eq!(a, ChannelStats { min: 255, max: 255, mean: 255.0, std_dev: 0.0 });
+
+	let lines = table_lines(&[r, g, b, a]);
+	assert_eq!(lines.len(), 5);
+	assert!(lines[1].starts_with('R') && lines[1].contains("1.000 (255.0)"));
+}
+
+#[test]
+fn empty_images_have_no_statistics() {
+	assert_eq!(channel_stats(&RgbaImage::new(0, 5)), None);
+}
@@ -0,0 +1,61 @@
+//! Reads the descriptions of hand made ICC profiles of both versions.
+
+#[allow(dead_code)]
+#[path = "../src/color_profile.rs"]
+mod color_profile;
+
+use color_profile::icc_description;
+
+/// A header of zeros followed by a tag table with only the `desc` tag
+fn profile_with_desc(tag: &[u8]) -> Vec<u8> {
+	let mut profile = vec![0; 128];
+	profile.extend_from_slice(&1u32.to_be_bytes());
+	let offset = 128 + 4 + 12;
+	profile.extend_from_slice(b"desc");
+	profile.extend_from_slice(&(offset as u32).to_be_bytes());
+	profile.extend_from_slice(&(tag.len() as u32).to_be_bytes());
+	profile.extend_from_slice(tag);
+	profile
+}
+
+#[test]
+fn reads_version_2_descriptions() {
+	let text = b"Adobe RGB (1998)\0";
+	let mut tag = b"desc\0\0\0\0".to_vec();
+	tag.extend_from_slice(&(text.len() as u32).to_be_bytes());
+	tag.extend_from_slice(text);
+	// The Unicode and ScriptCode descriptions that follow are ignored
+	tag.extend_from_slice(&[0; 12]);
+	let profile = profile_with_desc(&tag);
+	assert_eq!(icc_description(&profile).as_deref(), Some("Adobe RGB (1998)"));
+}
+
+#[test]
+fn reads_the_first_language_of_version_4_descriptions() {
+	let texts = ["Display P3", "Anzeige P3"];
+	let mut tag = b"mluc\0\0\0\0".to_vec();
+	tag.extend_from_slice(&(texts.len() as u32).to_be_bytes());
+	tag.extend_from_slice(&12u32.to_be_bytes());
+	let mut strings = Vec::new();
+	let records_end = 16 + 12 * texts.len();
+	for (text, language) in texts.iter().zip([b"enUS", b"deDE"]) {
+		let utf16: Vec<u8> = text.encode_utf16().flat_map(|unit| unit.to_be_bytes()).collect();
+		tag.extend_from_slice(language);
+		tag.extend_from_slice(&(utf16.len() as u32).to_be_bytes());
+		tag.extend_from_slice(&((records_end + strings.len()) as u32).to_be_bytes());
+		strings.extend_from_slice(&utf16);
+	}
+	tag.extend_from_slice(&strings);
+	let profile = profile_with_desc(&tag);
+	assert_eq!(icc_description(&profile).as_deref(), Some("Display P3"));
+}
+
+#[test]
+fn broken_profiles_have_no_description() {
+	assert_eq!(icc_description(&[]), None);
+	assert_eq!(icc_description(&[0; 200]), None);
+	let mut truncated = profile_with_desc(b"desc\0\0\0\0\0\0\0\x40abc");
+	assert_eq!(icc_description(&truncated), None);
+	truncated.truncate(140);
+	assert_eq!(icc_description(&truncated), None);
+}
@@ -0,0 +1,25 @@
+//! The helpers that the integration tests share.
+
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A directory in the system temp folder that's removed when the test ends
+pub struct TestDir(pub PathBuf);
+impl TestDir {
+	pub fn new(name: &str) -> TestDir {
+		let path = std::env::temp_dir().join(format!("emulsion-{}-{}", name, std::process::id()));
+		fs::create_dir_all(&path).unwrap();
+		TestDir(path)
+	}
+}
+impl Drop for TestDir {
+	fn drop(&mut self) {
+		let _ = fs::remove_dir_all(&self.0);
+	}
+}
+
+pub fn is_png(path: &Path) -> bool {
+	path.extension().is_some_and(|ext| ext == "png")
+}
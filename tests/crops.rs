@@ -0,0 +1,32 @@
+//! Stores the regions of interest of images and reads them back.
+
+use std::fs;
+
+#[allow(dead_code)]
+#[path = "../src/crops.rs"]
+mod crops;
+
+use crops::{Crop, CropStore};
+
+#[test]
+fn stored_regions_are_read_back_and_removed() {
+	let dir = std::env::temp_dir().join(format!("emulsion-crops-{}", std::process::id()));
+	fs::create_dir_all(&dir).unwrap();
+	let image = dir.join("chart.png");
+	fs::write(&image, b"png").unwrap();
+	let store_path = dir.join("crops.toml");
+
+	let mut store = CropStore::load(store_path.clone()).unwrap();
+	assert_eq!(store.get(&image), None);
+	let crop = Crop { x: 100, y: 50, w: 800, h: 400 };
+	store.set(&image, crop).unwrap();
+
+	let mut store = CropStore::load(store_path.clone()).unwrap();
+	assert_eq!(store.get(&image), Some(crop));
+	let contents = fs::read_to_string(&store_path).unwrap();
+	assert!(contents.contains("[[crops]]") && contents.contains("w = 800"), "{}", contents);
+	assert!(store.remove(&image).unwrap());
+	assert!(!store.remove(&image).unwrap());
+	assert_eq!(CropStore::load(store_path).unwrap().get(&image), None);
+	let _ = fs::remove_dir_all(&dir);
+}
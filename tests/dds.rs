@@ -0,0 +1,157 @@
+//! Decodes hand made DDS files with the compressed and the uncompressed formats.
+
+use gelatin::image::Rgba;
+
+// The decoder refers to the loader through `super`, see `loader_integration.rs`
+#[allow(dead_code)]
+#[path = "../src/image_cache/archive.rs"]
+mod archive;
+#[allow(dead_code)]
+#[path = "../src/image_cache/color_management.rs"]
+mod color_management;
+#[allow(dead_code)]
+#[path = "../src/image_cache/dds.rs"]
+mod dds;
+#[allow(dead_code)]
+#[path = "../src/image_cache/farbfeld.rs"]
+mod farbfeld;
+#[allow(dead_code)]
+#[path = "../src/image_cache/icns.rs"]
+mod icns;
+#[allow(dead_code)]
+#[path = "../src/image_cache/image_loader.rs"]
+mod image_loader;
+#[cfg(feature = "jpeg2000")]
+#[allow(dead_code)]
+#[path = "../src/image_cache/jpeg2000.rs"]
+mod jpeg2000;
+#[allow(dead_code)]
+#[path = "../src/image_cache/perceptual_hash.rs"]
+mod perceptual_hash;
+#[allow(dead_code)]
+#[path = "../src/image_cache/postscript.rs"]
+mod postscript;
+#[allow(dead_code)]
+#[path = "../src/image_cache/raw_thumbnail.rs"]
+mod raw_thumbnail;
+#[allow(dead_code)]
+#[path = "../src/image_cache/scratch_file.rs"]
+mod scratch_file;
+#[allow(dead_code)]
+#[path = "../src/image_cache/xbm.rs"]
+mod xbm;
+#[allow(dead_code)]
+#[path = "../src/image_cache/xpm.rs"]
+mod xpm;
+
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+
+/// Builds the magic bytes and the 124 byte header. `masks` are the bit count and the red,
+/// green, blue and alpha masks.
+fn header(
+	width: u32,
+	height: u32,
+	mip_count: u32,
+	flags: u32,
+	four_cc: &[u8; 4],
+	masks: [u32; 5],
+) -> Vec<u8> {
+	let mut data = b"DDS ".to_vec();
+	let mut fields = [0u32; 31];
+	fields[0] = 124;
+	fields[1] = 0x1007;
+	fields[2] = height;
+	fields[3] = width;
+	fields[6] = mip_count;
+	fields[18] = 32;
+	fields[19] = flags;
+	fields[20] = u32::from_le_bytes(*four_cc);
+	fields[21..26].copy_from_slice(&masks);
+	for field in fields {
+		data.extend_from_slice(&field.to_le_bytes());
+	}
+	data
+}
+
+/// A BC1 block with pure red and pure blue as its colors. The rows of the block use the
+/// palette indices 0, 1, 2 and 3 in that order.
+fn red_blue_block() -> [u8; 8] {
+	let red: u16 = 0xf800;
+	let blue: u16 = 0x001f;
+	let [r0, r1] = red.to_le_bytes();
+	let [b0, b1] = blue.to_le_bytes();
+	[r0, r1, b0, b1, 0x00, 0x55, 0xaa, 0xff]
+}
+
+#[test]
+fn bc1_uses_the_first_mip_level() {
+	let mut data = header(4, 4, 3, DDPF_FOURCC, b"DXT1", [0; 5]);
+	data.extend_from_slice(&red_blue_block());
+	// The smaller mip levels are all black
+	data.extend_from_slice(&[0; 16]);
+	let image = dds::decode_dds(&data).unwrap();
+	assert_eq!(image.dimensions(), (4, 4));
+	assert_eq!(*image.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+	assert_eq!(*image.get_pixel(3, 1), Rgba([0, 0, 255, 255]));
+	assert_eq!(*image.get_pixel(1, 2), Rgba([170, 0, 85, 255]));
+	assert_eq!(*image.get_pixel(2, 3), Rgba([85, 0, 170, 255]));
+}
+
+#[test]
+fn bc1_crops_the_blocks_at_the_edges() {
+	let mut data = header(5, 3, 1, DDPF_FOURCC, b"DXT1", [0; 5]);
+	for _ in 0..2 {
+		data.extend_from_slice(&red_blue_block());
+	}
+	let image = dds::decode_dds(&data).unwrap();
+	assert_eq!(image.dimensions(), (5, 3));
+	assert_eq!(*image.get_pixel(4, 0), Rgba([255, 0, 0, 255]));
+	assert_eq!(*image.get_pixel(4, 1), Rgba([0, 0, 255, 255]));
+}
+
+#[test]
+fn bc3_interpolates_the_alpha() {
+	let mut data = header(4, 4, 1, DDPF_FOURCC, b"DXT5", [0; 5]);
+	// Alpha 255 and 0 with every pixel using the second interpolated value (index 2)
+	let alpha_indices: u64 = (0..16).fold(0, |bits, i| bits | (2 << (3 * i)));
+	data.extend_from_slice(&[255, 0]);
+	data.extend_from_slice(&alpha_indices.to_le_bytes()[..6]);
+	data.extend_from_slice(&red_blue_block());
+	let image = dds::decode_dds(&data).unwrap();
+	assert_eq!(*image.get_pixel(0, 0), Rgba([255, 0, 0, 218]));
+	assert_eq!(*image.get_pixel(3, 3), Rgba([85, 0, 170, 218]));
+}
+
+#[test]
+fn uncompressed_pixels_use_the_masks() {
+	let masks = [32, 0xff_0000, 0xff00, 0xff, 0xff00_0000];
+	let mut data = header(2, 1, 1, DDPF_RGB | DDPF_ALPHAPIXELS, &[0; 4], masks);
+	// The pixels are stored as BGRA
+	data.extend_from_slice(&[10, 20, 30, 40, 50, 60, 70, 80]);
+	let image = dds::decode_dds(&data).unwrap();
+	assert_eq!(*image.get_pixel(0, 0), Rgba([30, 20, 10, 40]));
+	assert_eq!(*image.get_pixel(1, 0), Rgba([70, 60, 50, 80]));
+
+	// 5-6-5 pixels without alpha are opaque
+	let masks = [16, 0xf800, 0x07e0, 0x001f, 0];
+	let mut data = header(1, 1, 1, DDPF_RGB, &[0; 4], masks);
+	data.extend_from_slice(&0x07e0u16.to_le_bytes());
+	let image = dds::decode_dds(&data).unwrap();
+	assert_eq!(*image.get_pixel(0, 0), Rgba([0, 255, 0, 255]));
+}
+
+#[test]
+fn truncated_and_unsupported_files_fail() {
+	let mut data = header(8, 8, 1, DDPF_FOURCC, b"DXT1", [0; 5]);
+	data.extend_from_slice(&red_blue_block());
+	let error = dds::decode_dds(&data).unwrap_err();
+	assert!(error.description.contains("too short"), "{}", error.description);
+
+	let data = header(4, 4, 1, DDPF_FOURCC, b"ETC2", [0; 5]);
+	let error = dds::decode_dds(&data).unwrap_err();
+	assert!(error.description.contains("ETC2"), "{}", error.description);
+
+	assert!(dds::decode_dds(b"DDS ").is_err());
+}
@@ -0,0 +1,65 @@
+//! Property tests for converting the frame delays of animations to nanoseconds.
+
+use proptest::prelude::*;
+
+// The loader is compiled into the test directly, see `loader_integration.rs`
+#[allow(dead_code)]
+#[path = "../src/image_cache/archive.rs"]
+mod archive;
+#[allow(dead_code)]
+#[path = "../src/image_cache/color_management.rs"]
+mod color_management;
+#[allow(dead_code)]
+#[path = "../src/image_cache/dds.rs"]
+mod dds;
+#[allow(dead_code)]
+#[path = "../src/image_cache/farbfeld.rs"]
+mod farbfeld;
+#[allow(dead_code)]
+#[path = "../src/image_cache/icns.rs"]
+mod icns;
+#[allow(dead_code)]
+#[path = "../src/image_cache/image_loader.rs"]
+mod image_loader;
+#[cfg(feature = "jpeg2000")]
+#[allow(dead_code)]
+#[path = "../src/image_cache/jpeg2000.rs"]
+mod jpeg2000;
+#[allow(dead_code)]
+#[path = "../src/image_cache/perceptual_hash.rs"]
+mod perceptual_hash;
+#[allow(dead_code)]
+#[path = "../src/image_cache/postscript.rs"]
+mod postscript;
+#[allow(dead_code)]
+#[path = "../src/image_cache/raw_thumbnail.rs"]
+mod raw_thumbnail;
+#[allow(dead_code)]
+#[path = "../src/image_cache/scratch_file.rs"]
+mod scratch_file;
+#[allow(dead_code)]
+#[path = "../src/image_cache/xbm.rs"]
+mod xbm;
+#[allow(dead_code)]
+#[path = "../src/image_cache/xpm.rs"]
+mod xpm;
+
+use image_loader::delay_nano;
+
+const NANOS_PER_MS: f64 = 1_000_000.0;
+const DEFAULT_DELAY_NANO: u64 = 100_000_000;
+
+proptest! {
+	#[test]
+	fn delay_matches_float_reference(numerator_ms in 1..=u32::MAX, denom_ms in 1..=u32::MAX) {
+		let reference = numerator_ms as f64 / denom_ms as f64 * NANOS_PER_MS;
+		let delay = delay_nano(numerator_ms, denom_ms);
+		prop_assert!((delay as f64 - reference).abs() < NANOS_PER_MS);
+	}
+
+	#[test]
+	fn zero_delay_uses_default(numerator_ms: u32, denom_ms: u32) {
+		prop_assert_eq!(delay_nano(0, denom_ms), DEFAULT_DELAY_NANO);
+		prop_assert_eq!(delay_nano(numerator_ms, 0), DEFAULT_DELAY_NANO);
+	}
+}
@@ -0,0 +1,66 @@
+//! Expands and collapses the nodes of hand made folder trees.
+
+use std::fs;
+
+mod common;
+
+use common::{is_png, TestDir};
+
+#[allow(dead_code)]
+#[path = "../src/directory_tree.rs"]
+mod directory_tree;
+
+use directory_tree::DirectoryTree;
+
+/// `root/{b/{inner/}, A/, .hidden/}` with two images in `b` and one in `inner`
+fn make_tree(name: &str) -> TestDir {
+	let dir = TestDir::new(name);
+	fs::create_dir_all(dir.0.join("b").join("inner")).unwrap();
+	fs::create_dir_all(dir.0.join("A")).unwrap();
+	fs::create_dir_all(dir.0.join(".hidden")).unwrap();
+	fs::write(dir.0.join("b").join("1.png"), b"").unwrap();
+	fs::write(dir.0.join("b").join("2.png"), b"").unwrap();
+	fs::write(dir.0.join("b").join("notes.txt"), b"").unwrap();
+	fs::write(dir.0.join("b").join("inner").join("3.png"), b"").unwrap();
+	dir
+}
+
+fn names(tree: &DirectoryTree) -> Vec<(String, usize)> {
+	tree.nodes().iter().map(|node| (node.name.clone(), node.depth)).collect()
+}
+
+#[test]
+fn root_shows_its_subfolders() {
+	let dir = make_tree("directory-tree-root");
+	let tree = DirectoryTree::new(dir.0.clone(), is_png);
+	let root_name = dir.0.file_name().unwrap().to_string_lossy().into_owned();
+	// Sorted without regard to case, hidden folders left out
+	assert_eq!(names(&tree), vec![(root_name, 0), ("A".into(), 1), ("b".into(), 1)]);
+	let b = &tree.nodes()[2];
+	assert_eq!(b.image_count, 2);
+	assert!(b.has_subfolders && !b.expanded);
+	assert!(!tree.nodes()[1].has_subfolders);
+}
+
+#[test]
+fn expanded_folders_are_remembered() {
+	let dir = make_tree("directory-tree-expand");
+	let mut tree = DirectoryTree::new(dir.0.clone(), is_png);
+	tree.toggle(2);
+	assert_eq!(names(&tree)[3], ("inner".into(), 2));
+	assert_eq!(tree.nodes()[3].image_count, 1);
+
+	// Collapsing the root hides everything, expanding it again brings back `inner`
+	tree.toggle(0);
+	assert_eq!(tree.nodes().len(), 1);
+	tree.toggle(0);
+	assert_eq!(tree.nodes().len(), 4);
+
+	// Folders without subfolders can't be expanded
+	tree.toggle(1);
+	assert_eq!(tree.nodes().len(), 4);
+
+	fs::create_dir_all(dir.0.join("b").join("new")).unwrap();
+	tree.refresh();
+	assert_eq!(names(&tree)[4], ("new".into(), 2));
+}
@@ -0,0 +1,74 @@
+//! Writes the editable EXIF tags to generated JPEG files and reads them back.
+
+use std::fs;
+use std::path::PathBuf;
+
+use gelatin::image::{self, ImageFormat, Rgb, RgbImage};
+
+mod common;
+
+use common::TestDir;
+
+#[allow(dead_code)]
+#[path = "../src/exif_writer.rs"]
+mod exif_writer;
+
+use exif_writer::{backup_path, read_editable, write_editable, EditableTag};
+
+fn write_jpeg(dir: &TestDir) -> PathBuf {
+	let path = dir.0.join("photo.jpg");
+	let image = RgbImage::from_fn(24, 16, |x, y| Rgb([(x * 10) as u8, (y * 15) as u8, 128]));
+	image.save_with_format(&path, ImageFormat::Jpeg).unwrap();
+	path
+}
+
+fn values(values: [&str; 5]) -> Vec<String> {
+	assert_eq!(values.len(), EditableTag::ALL.len());
+	values.iter().map(|v| v.to_string()).collect()
+}
+
+#[test]
+fn written_tags_are_read_back() {
+	let dir = TestDir::new("exif-writer-roundtrip");
+	let path = write_jpeg(&dir);
+	let original = fs::read(&path).unwrap();
+	assert_eq!(read_editable(&path).unwrap(), values(["", "", "", "", ""]));
+
+	let edited = values([
+		"A street at night",
+		"Jane Doe",
+		"(c) 2024 Jane Doe",
+		"street; night; Ünïcödé",
+		"40.712800, -74.006000",
+	]);
+	write_editable(&path, &edited).unwrap();
+	assert_eq!(read_editable(&path).unwrap(), edited);
+	assert_eq!(fs::read(backup_path(&path)).unwrap(), original);
+	// The pixels are still there
+	assert_eq!(image::open(&path).unwrap().to_rgb8().dimensions(), (24, 16));
+
+	// Changing one tag keeps the others
+	let mut changed = edited.clone();
+	changed[1] = "John Doe".into();
+	changed[4] = "-33.8688, 151.2093".into();
+	write_editable(&path, &changed).unwrap();
+	let read = read_editable(&path).unwrap();
+	assert_eq!(read[..4], changed[..4]);
+	assert_eq!(read[4], "-33.868800, 151.209300");
+	// The backup is still the very first version of the file
+	assert_eq!(fs::read(backup_path(&path)).unwrap(), original);
+
+	write_editable(&path, &values(["", "", "", "", ""])).unwrap();
+	assert_eq!(read_editable(&path).unwrap(), values(["", "", "", "", ""]));
+	assert!(image::open(&path).is_ok());
+}
+
+#[test]
+fn invalid_gps_is_rejected() {
+	let dir = TestDir::new("exif-writer-gps");
+	let path = write_jpeg(&dir);
+	for gps in ["40.7", "north, west", "91, 0", "0, 181"] {
+		assert!(write_editable(&path, &values(["", "", "", "", gps])).is_err(), "{}", gps);
+	}
+	assert!(!backup_path(&path).exists());
+}
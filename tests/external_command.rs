@@ -0,0 +1,63 @@
+//! Runs shell commands on a file the way `run_command` does.
+
+#![cfg(unix)]
+
+use std::fs;
+use std::path::Path;
+
+mod common;
+use common::TestDir;
+
+#[allow(dead_code)]
+#[path = "../src/external_command.rs"]
+mod external_command;
+#[allow(dead_code)]
+#[path = "../src/temp_file.rs"]
+mod temp_file;
+#[allow(dead_code)]
+#[path = "../src/terminal.rs"]
+mod terminal;
+
+#[test]
+fn the_placeholders_are_replaced_by_quoted_paths() {
+	let command = external_command::expand(
+		"convert $input -negate $output && ls $file",
+		Path::new("/photos/it's.png"),
+		Path::new("/tmp/out.png"),
+	);
+	assert_eq!(
+		command,
+		"convert '/photos/it'\\''s.png' -negate '/tmp/out.png' && ls '/photos/it'\\''s.png'"
+	);
+}
+
+#[test]
+fn placeholders_in_the_paths_are_not_replaced() {
+	let command = external_command::expand(
+		"cp $file $output",
+		Path::new("/photos/a$file$(id).png"),
+		Path::new("/tmp/$input.png"),
+	);
+	assert_eq!(command, "cp '/photos/a$file$(id).png' '/tmp/$input.png'");
+}
+
+#[test]
+fn the_output_or_the_changed_input_is_returned() {
+	let dir = TestDir::new("command");
+	let input = dir.0.join("image.png");
+	fs::write(&input, b"png").unwrap();
+
+	let output = external_command::run("cp $input $output", &input).unwrap();
+	assert_ne!(output, input);
+	assert_eq!(output.extension().unwrap(), "png");
+	assert_eq!(fs::read(&output).unwrap(), b"png");
+	fs::remove_file(&output).unwrap();
+
+	let changed = external_command::run("printf changed > $file", &input).unwrap();
+	assert_eq!(changed, input);
+	assert_eq!(fs::read(&input).unwrap(), b"changed");
+
+	let error = external_command::run("echo 'no such filter' >&2; exit 3", &input).unwrap_err();
+	assert!(error.contains("no such filter"), "{}", error);
+	assert!(external_command::run("true $output", &input).is_err());
+}
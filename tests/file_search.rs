@@ -0,0 +1,54 @@
+//! Matches file names against search patterns and walks hand made folder trees.
+
+use std::fs;
+use std::path::PathBuf;
+
+mod common;
+
+use common::{is_png, TestDir};
+
+#[allow(dead_code)]
+#[path = "../src/file_search.rs"]
+mod file_search;
+
+use file_search::{find_files, glob_matches, ThumbnailGrid};
+
+#[test]
+fn patterns_match_names() {
+	assert!(glob_matches("*.png", "Holiday.PNG"));
+	assert!(glob_matches("img_??.jpg", "img_07.jpg"));
+	assert!(!glob_matches("img_??.jpg", "img_107.jpg"));
+	assert!(glob_matches("a*b*c", "axxbyybzc"));
+	assert!(!glob_matches("a*b*c", "axxbyy"));
+	// Without wildcards the pattern may be anywhere in the name
+	assert!(glob_matches("cat", "my_cat_2.png"));
+	assert!(glob_matches("", "anything.png"));
+}
+
+#[test]
+fn search_stops_at_the_depth() {
+	let dir = TestDir::new("file-search-depth");
+	let deep = dir.0.join("one").join("two");
+	fs::create_dir_all(&deep).unwrap();
+	for folder in [&dir.0, &dir.0.join("one"), &deep] {
+		fs::write(folder.join("cat.png"), b"").unwrap();
+		fs::write(folder.join("cat.txt"), b"").unwrap();
+	}
+
+	let found = find_files(&dir.0, "cat", 1, is_png);
+	assert_eq!(found, vec![dir.0.join("cat.png"), dir.0.join("one").join("cat.png")]);
+	assert_eq!(find_files(&dir.0, "*.png", 2, is_png).len(), 3);
+	assert!(find_files(&dir.0, "dog", 2, is_png).is_empty());
+}
+
+#[test]
+fn selection_stays_within_the_grid() {
+	// The files don't exist, so their cells stay empty
+	let paths = (0..5).map(|i| PathBuf::from(format!("missing-{}.png", i))).collect();
+	let grid = ThumbnailGrid::new(paths);
+	assert_eq!(grid.columns, 3);
+	assert_eq!(grid.moved_selection(0, 0, 1), 3);
+	assert_eq!(grid.moved_selection(3, 0, 1), 4);
+	assert_eq!(grid.moved_selection(1, -1, -1), 0);
+	assert_eq!(grid.render(4).width(), 3 * 168 + 8);
+}
@@ -0,0 +1,114 @@
+//! Property tests for putting the frames of an animation back into order.
+
+use std::time::SystemTime;
+
+use proptest::prelude::*;
+
+use gelatin::image::RgbaImage;
+
+// The loader is compiled into the test directly, see `loader_integration.rs`
+#[allow(dead_code)]
+#[path = "../src/image_cache/archive.rs"]
+mod archive;
+#[allow(dead_code)]
+#[path = "../src/image_cache/color_management.rs"]
+mod color_management;
+#[allow(dead_code)]
+#[path = "../src/image_cache/dds.rs"]
+mod dds;
+#[allow(dead_code)]
+#[path = "../src/image_cache/farbfeld.rs"]
+mod farbfeld;
+#[allow(dead_code)]
+#[path = "../src/image_cache/frame_reassembler.rs"]
+mod frame_reassembler;
+#[allow(dead_code)]
+#[path = "../src/image_cache/icns.rs"]
+mod icns;
+#[allow(dead_code)]
+#[path = "../src/image_cache/image_loader.rs"]
+mod image_loader;
+#[cfg(feature = "jpeg2000")]
+#[allow(dead_code)]
+#[path = "../src/image_cache/jpeg2000.rs"]
+mod jpeg2000;
+#[allow(dead_code)]
+#[path = "../src/image_cache/perceptual_hash.rs"]
+mod perceptual_hash;
+#[allow(dead_code)]
+#[path = "../src/image_cache/postscript.rs"]
+mod postscript;
+#[allow(dead_code)]
+#[path = "../src/image_cache/raw_thumbnail.rs"]
+mod raw_thumbnail;
+#[allow(dead_code)]
+#[path = "../src/image_cache/scratch_file.rs"]
+mod scratch_file;
+#[allow(dead_code)]
+#[path = "../src/image_cache/xbm.rs"]
+mod xbm;
+#[allow(dead_code)]
+#[path = "../src/image_cache/xpm.rs"]
+mod xpm;
+
+use frame_reassembler::FrameReassembler;
+use image_loader::{LoadResult, Orientation, TextureColorSpace};
+
+const REQ_ID: u64 = 7;
+
+fn frame(frame_index: u32) -> LoadResult {
+	LoadResult::Frame {
+		req_id: REQ_ID,
+		batch_id: None,
+		created_at: SystemTime::now(),
+		frame_index,
+		image: RgbaImage::new(1, 1),
+		delay_nano: 0,
+		orientation: Orientation::Deg0,
+		source_format: None,
+		color_space: TextureColorSpace::Srgb,
+	}
+}
+
+fn frame_indices(results: &[LoadResult]) -> Vec<u32> {
+	results
+		.iter()
+		.filter_map(|result| match result {
+			LoadResult::Frame { frame_index, .. } => Some(*frame_index),
+			_ => None,
+		})
+		.collect()
+}
+
+fn shuffled_indices() -> impl Strategy<Value = Vec<u32>> {
+	(1..32u32).prop_flat_map(|count| Just((0..count).collect::<Vec<_>>()).prop_shuffle())
+}
+
+proptest! {
+	#[test]
+	fn shuffled_frames_are_emitted_in_order(indices in shuffled_indices()) {
+		let mut reassembler = FrameReassembler::new();
+		let mut output = Vec::new();
+		for &index in &indices {
+			reassembler.push(frame(index), &mut output);
+		}
+		// Nothing is held back once every frame has arrived
+		let expected: Vec<u32> = (0..indices.len() as u32).collect();
+		prop_assert_eq!(frame_indices(&output), expected);
+	}
+
+	#[test]
+	fn frames_after_a_gap_are_released_at_the_end(indices in shuffled_indices(), missing in 0..32u32) {
+		let mut reassembler = FrameReassembler::new();
+		let mut output = Vec::new();
+		for &index in indices.iter().filter(|&&index| index != missing) {
+			reassembler.push(frame(index), &mut output);
+		}
+		reassembler.push(LoadResult::Done { req_id: REQ_ID, batch_id: None, created_at: SystemTime::now(), timings: Default::default() }, &mut output);
+		let expected: Vec<u32> =
+			(0..indices.len() as u32).filter(|&index| index != missing).collect();
+		prop_assert_eq!(frame_indices(&output), expected);
+		let ends_with_done = matches!(output.last(), Some(LoadResult::Done { .. }));
+		prop_assert!(ends_with_done);
+	}
+}
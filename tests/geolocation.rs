@@ -0,0 +1,24 @@
+//! Converts GPS coordinates to decimal degrees and map links.
+
+#[allow(dead_code)]
+#[path = "../src/geolocation.rs"]
+mod geolocation;
+
+use geolocation::{dms_to_decimal, map_url};
+
+#[test]
+fn south_and_west_are_negative() {
+	let north = dms_to_decimal(48, 51, 29.6, 'N');
+	assert!((north - 48.858222).abs() < 1e-6);
+	assert!((dms_to_decimal(48, 51, 29.6, 'S') + north).abs() < 1e-12);
+	assert!((dms_to_decimal(2, 17, 40.2, 'W') + 2.294500).abs() < 1e-6);
+	assert_eq!(dms_to_decimal(0, 30, 0.0, 'e'), 0.5);
+}
+
+#[test]
+fn map_links_point_at_the_location() {
+	assert_eq!(
+		map_url(48.858222, -2.2945),
+		"https://www.openstreetmap.org/?mlat=48.858222&mlon=-2.294500&zoom=14"
+	);
+}
@@ -0,0 +1,68 @@
+//! Feeds touch sequences to the gesture recognizer.
+
+use gelatin::{misc::LogicalVector, winit::event::TouchPhase};
+
+#[allow(dead_code)]
+#[path = "../src/gesture_recognizer.rs"]
+mod gesture_recognizer;
+
+use gesture_recognizer::{Gesture, GestureRecognizer};
+
+fn pos(x: f32, y: f32) -> LogicalVector {
+	LogicalVector::new(x, y)
+}
+
+#[test]
+fn two_fingers_pan_and_zoom_around_their_midpoint() {
+	let mut gestures = GestureRecognizer::new();
+	assert_eq!(gestures.touch(1, TouchPhase::Started, pos(100.0, 100.0)), None);
+	assert_eq!(gestures.touch(2, TouchPhase::Started, pos(200.0, 100.0)), None);
+	// Moving the second finger away doubles the distance
+	let gesture = gestures.touch(2, TouchPhase::Moved, pos(300.0, 100.0));
+	assert_eq!(
+		gesture,
+		Some(Gesture::PanZoom { pan: pos(50.0, 0.0), zoom: 2.0, anchor: pos(200.0, 100.0) })
+	);
+	// Every move of a single finger moves the midpoint by half as much
+	let gesture = gestures.touch(1, TouchPhase::Moved, pos(100.0, 150.0));
+	match gesture {
+		Some(Gesture::PanZoom { pan, zoom, anchor }) => {
+			assert_eq!(pan, pos(0.0, 25.0));
+			assert_eq!(anchor, pos(200.0, 125.0));
+			assert!((zoom - (200f32.powi(2) + 50f32.powi(2)).sqrt() / 200.0).abs() < 1e-5);
+		}
+		_ => panic!("{:?}", gesture),
+	}
+	// Lifting a finger ends the pinch
+	gestures.touch(2, TouchPhase::Ended, pos(300.0, 100.0));
+	assert_eq!(gestures.touch(1, TouchPhase::Moved, pos(110.0, 150.0)), None);
+}
+
+#[test]
+fn a_horizontal_swipe_navigates() {
+	let mut gestures = GestureRecognizer::new();
+	gestures.touch(1, TouchPhase::Started, pos(300.0, 200.0));
+	assert_eq!(gestures.touch(1, TouchPhase::Moved, pos(200.0, 210.0)), None);
+	assert_eq!(gestures.touch(1, TouchPhase::Ended, pos(200.0, 210.0)), Some(Gesture::SwipeLeft));
+
+	gestures.touch(1, TouchPhase::Started, pos(100.0, 200.0));
+	assert_eq!(gestures.touch(1, TouchPhase::Ended, pos(160.0, 190.0)), Some(Gesture::SwipeRight));
+}
+
+#[test]
+fn short_vertical_and_multi_touch_moves_are_not_swipes() {
+	let mut gestures = GestureRecognizer::new();
+	gestures.touch(1, TouchPhase::Started, pos(100.0, 100.0));
+	assert_eq!(gestures.touch(1, TouchPhase::Ended, pos(140.0, 100.0)), None);
+	gestures.touch(1, TouchPhase::Started, pos(100.0, 100.0));
+	assert_eq!(gestures.touch(1, TouchPhase::Ended, pos(200.0, 130.0)), None);
+
+	// The last finger of a pinch doesn't swipe after the other one is lifted
+	gestures.touch(1, TouchPhase::Started, pos(100.0, 100.0));
+	gestures.touch(2, TouchPhase::Started, pos(200.0, 100.0));
+	gestures.touch(2, TouchPhase::Ended, pos(200.0, 100.0));
+	assert_eq!(gestures.touch(1, TouchPhase::Ended, pos(300.0, 100.0)), None);
+	// A new touch can swipe again
+	gestures.touch(3, TouchPhase::Started, pos(300.0, 100.0));
+	assert_eq!(gestures.touch(3, TouchPhase::Ended, pos(100.0, 100.0)), Some(Gesture::SwipeLeft));
+}
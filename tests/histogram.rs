@@ -0,0 +1,34 @@
+//! The histograms of `histogram`.
+
+#[allow(dead_code)]
+#[path = "../src/histogram.rs"]
+mod histogram;
+
+use gelatin::image::{Rgba, RgbaImage};
+use histogram::{histogram, luminance};
+
+#[test]
+fn every_pixel_is_counted_once_per_histogram() {
+	let image = RgbaImage::from_fn(4, 3, |x, _| match x {
+		0 => Rgba([255, 0, 0, 255]),
+		1 => Rgba([255, 255, 255, 0]),
+		_ => Rgba([10, 20, 30, 255]),
+	});
+	let histogram = histogram(&image);
+	for bins in &histogram.bins {
+		assert_eq!(bins.iter().sum::<u32>(), 12);
+	}
+	assert_eq!(histogram.bins[0][255], 6);
+	assert_eq!(histogram.bins[1][0], 3);
+	assert_eq!(histogram.bins[2][30], 6);
+	assert_eq!(histogram.bins[3][255], 3);
+	assert_eq!(histogram.bins[3][luminance(255, 0, 0) as usize], 3);
+	assert_eq!(histogram.max_count(), 6);
+}
+
+#[test]
+fn gray_keeps_its_luminance() {
+	for value in [0, 1, 128, 254, 255] {
+		assert_eq!(luminance(value, value, value), value);
+	}
+}
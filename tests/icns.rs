@@ -0,0 +1,102 @@
+//! Decodes hand made ICNS files with PNG icons of different sizes.
+
+use std::io::Cursor;
+
+use gelatin::image::{ImageFormat, Rgba, RgbaImage};
+
+// The decoder refers to the loader through `super`, see `loader_integration.rs`
+#[allow(dead_code)]
+#[path = "../src/image_cache/archive.rs"]
+mod archive;
+#[allow(dead_code)]
+#[path = "../src/image_cache/color_management.rs"]
+mod color_management;
+#[allow(dead_code)]
+#[path = "../src/image_cache/dds.rs"]
+mod dds;
+#[allow(dead_code)]
+#[path = "../src/image_cache/farbfeld.rs"]
+mod farbfeld;
+#[allow(dead_code)]
+#[path = "../src/image_cache/icns.rs"]
+mod icns;
+#[allow(dead_code)]
+#[path = "../src/image_cache/image_loader.rs"]
+mod image_loader;
+#[cfg(feature = "jpeg2000")]
+#[allow(dead_code)]
+#[path = "../src/image_cache/jpeg2000.rs"]
+mod jpeg2000;
+#[allow(dead_code)]
+#[path = "../src/image_cache/perceptual_hash.rs"]
+mod perceptual_hash;
+#[allow(dead_code)]
+#[path = "../src/image_cache/postscript.rs"]
+mod postscript;
+#[allow(dead_code)]
+#[path = "../src/image_cache/raw_thumbnail.rs"]
+mod raw_thumbnail;
+#[allow(dead_code)]
+#[path = "../src/image_cache/scratch_file.rs"]
+mod scratch_file;
+#[allow(dead_code)]
+#[path = "../src/image_cache/xbm.rs"]
+mod xbm;
+#[allow(dead_code)]
+#[path = "../src/image_cache/xpm.rs"]
+mod xpm;
+
+/// A PNG file of a single colored image
+fn png(width: u32, height: u32, color: Rgba<u8>) -> Vec<u8> {
+	let mut data = Vec::new();
+	RgbaImage::from_pixel(width, height, color)
+		.write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+		.unwrap();
+	data
+}
+
+fn icns(entries: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+	let mut body = Vec::new();
+	for (icon_type, payload) in entries {
+		body.extend_from_slice(*icon_type);
+		body.extend_from_slice(&(payload.len() as u32 + 8).to_be_bytes());
+		body.extend_from_slice(payload);
+	}
+	let mut data = b"icns".to_vec();
+	data.extend_from_slice(&(body.len() as u32 + 8).to_be_bytes());
+	data.extend_from_slice(&body);
+	data
+}
+
+#[test]
+fn the_largest_png_icon_is_decoded() {
+	let red = Rgba([255, 0, 0, 255]);
+	let blue = Rgba([0, 0, 255, 255]);
+	// The raw pixels of the old types are skipped
+	let data = icns(&[
+		(b"ic07", &png(4, 4, red)),
+		(b"is32", &[0; 12]),
+		(b"ic09", &png(8, 8, blue)),
+		(b"ic08", &png(6, 6, red)),
+	]);
+	assert!(icns::is_icns(&data));
+	assert_eq!(icns::read_dimensions(&data), Some((512, 512)));
+	let image = icns::decode_icns(&data).unwrap();
+	assert_eq!(image.dimensions(), (8, 8));
+	assert_eq!(*image.get_pixel(0, 0), blue);
+}
+
+#[test]
+fn a_broken_icon_falls_back_to_a_smaller_one() {
+	let green = Rgba([0, 255, 0, 255]);
+	let data = icns(&[(b"ic10", b"\x89PNG\r\n\x1a\nbroken"), (b"ic07", &png(2, 2, green))]);
+	let image = icns::decode_icns(&data).unwrap();
+	assert_eq!(*image.get_pixel(1, 1), green);
+}
+
+#[test]
+fn an_entry_past_the_end_is_an_error() {
+	let mut data = icns(&[(b"ic07", &png(2, 2, Rgba([0, 0, 0, 255])))]);
+	data.truncate(data.len() - 1);
+	assert!(icns::decode_icns(&data).is_err());
+}
@@ -0,0 +1,61 @@
+//! Hashes data with SHA-256 and compares files with their stored hashes.
+
+use std::fs;
+
+#[allow(dead_code)]
+#[path = "../src/integrity.rs"]
+mod integrity;
+
+use integrity::{hash_file, to_hex, HashStore, Integrity, Sha256};
+
+fn sha256(data: &[u8]) -> String {
+	let mut hasher = Sha256::new();
+	hasher.update(data);
+	to_hex(&hasher.finish())
+}
+
+#[test]
+fn hashes_match_the_test_vectors() {
+	assert_eq!(sha256(b""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+	assert_eq!(sha256(b"abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+	// Long enough that the length goes into a block of its own
+	assert_eq!(
+		sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+		"248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+	);
+	let million = vec![b'a'; 1_000_000];
+	assert_eq!(
+		sha256(&million),
+		"cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+	);
+}
+
+#[test]
+fn pieces_hash_like_the_whole() {
+	let data: Vec<u8> = (0..1000u32).map(|i| (i * 7) as u8).collect();
+	let mut hasher = Sha256::new();
+	for piece in data.chunks(37) {
+		hasher.update(piece);
+	}
+	assert_eq!(to_hex(&hasher.finish()), sha256(&data));
+}
+
+#[test]
+fn stored_hashes_are_read_back_and_compared() {
+	let dir = std::env::temp_dir().join(format!("emulsion-integrity-{}", std::process::id()));
+	fs::create_dir_all(&dir).unwrap();
+	let image = dir.join("image.png");
+	fs::write(&image, b"abc").unwrap();
+	let store_path = dir.join("hashes.db");
+
+	let mut store = HashStore::load(store_path.clone()).unwrap();
+	let hash = hash_file(&image).unwrap();
+	assert_eq!(store.check(&image, &hash), Integrity::Unknown);
+	store.store(&image, hash.clone()).unwrap();
+
+	let store = HashStore::load(store_path).unwrap();
+	assert_eq!(store.check(&image, &hash), Integrity::Ok);
+	fs::write(&image, b"abd").unwrap();
+	assert_eq!(store.check(&image, &hash_file(&image).unwrap()), Integrity::Mismatch);
+	let _ = fs::remove_dir_all(&dir);
+}
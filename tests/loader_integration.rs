@@ -0,0 +1,660 @@
+//! Runs a real `ImageLoader` on generated image files and checks the results it sends back.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use gelatin::image::{codecs::gif::GifEncoder, Delay, Frame, ImageFormat, Rgba, RgbaImage};
+
+mod common;
+
+use common::TestDir;
+
+// Emulsion is only a binary, so the loader is compiled into the test directly. It refers to the
+// decoders of the other formats through `super`, which is the root of this test.
+#[allow(dead_code)]
+#[path = "../src/image_cache/archive.rs"]
+mod archive;
+#[allow(dead_code)]
+#[path = "../src/image_cache/color_management.rs"]
+mod color_management;
+#[allow(dead_code)]
+#[path = "../src/image_cache/dds.rs"]
+mod dds;
+#[allow(dead_code)]
+#[path = "../src/image_cache/farbfeld.rs"]
+mod farbfeld;
+#[allow(dead_code)]
+#[path = "../src/image_cache/icns.rs"]
+mod icns;
+#[allow(dead_code)]
+#[path = "../src/image_cache/image_loader.rs"]
+mod image_loader;
+#[cfg(feature = "jpeg2000")]
+#[allow(dead_code)]
+#[path = "../src/image_cache/jpeg2000.rs"]
+mod jpeg2000;
+#[allow(dead_code)]
+#[path = "../src/image_cache/perceptual_hash.rs"]
+mod perceptual_hash;
+#[allow(dead_code)]
+#[path = "../src/image_cache/postscript.rs"]
+mod postscript;
+#[allow(dead_code)]
+#[path = "../src/image_cache/raw_thumbnail.rs"]
+mod raw_thumbnail;
+#[allow(dead_code)]
+#[path = "../src/image_cache/scratch_file.rs"]
+mod scratch_file;
+#[allow(dead_code)]
+#[path = "../src/image_cache/xbm.rs"]
+mod xbm;
+#[allow(dead_code)]
+#[path = "../src/image_cache/xpm.rs"]
+mod xpm;
+
+use image_loader::{
+	FrameCompositor, ImageLoader, LoadRequest, LoadRequestPool, LoadResult, Orientation,
+	NON_EXISTENT_REQUEST_ID, PRIORITY_REQUEST_ID,
+};
+
+const GIF_FRAME_COUNT: usize = 3;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum ResultKind {
+	Start,
+	Frame,
+	Done,
+	Failed,
+	Aborted,
+}
+
+fn kind(result: &LoadResult) -> ResultKind {
+	match result {
+		LoadResult::Start { .. } => ResultKind::Start,
+		LoadResult::Frame { .. } => ResultKind::Frame,
+		LoadResult::Done { .. } => ResultKind::Done,
+		LoadResult::Failed { .. } => ResultKind::Failed,
+		LoadResult::Aborted { .. } => ResultKind::Aborted,
+	}
+}
+
+fn test_image(shade: u8) -> RgbaImage {
+	RgbaImage::from_fn(16, 8, |x, y| Rgba([shade, (x * 16) as u8, (y * 32) as u8, 255]))
+}
+
+fn write_still(dir: &Path, name: &str, format: ImageFormat) -> PathBuf {
+	let path = dir.join(name);
+	let image = gelatin::image::DynamicImage::ImageRgba8(test_image(0));
+	// JPEG can't store the alpha channel
+	let image = if format == ImageFormat::Jpeg { image.to_rgb8().into() } else { image };
+	image.save_with_format(&path, format).unwrap();
+	path
+}
+
+fn write_gif(dir: &Path) -> PathBuf {
+	let path = dir.join("animation.gif");
+	let mut encoder = GifEncoder::new(fs::File::create(&path).unwrap());
+	let frames = (0..GIF_FRAME_COUNT).map(|i| {
+		Frame::from_parts(test_image(i as u8 * 80), 0, 0, Delay::from_numer_denom_ms(50, 1))
+	});
+	encoder.encode_frames(frames).unwrap();
+	path
+}
+
+#[test]
+fn loader_sends_results_for_every_request() {
+	let dir = TestDir::new("loader-integration");
+	let corrupted = dir.0.join("corrupted.png");
+	fs::write(&corrupted, b"This is not a PNG file").unwrap();
+	let misnamed = dir.0.join("image.backup");
+	fs::copy(write_still(&dir.0, "misnamed.jpg", ImageFormat::Jpeg), &misnamed).unwrap();
+	let paths = [
+		write_still(&dir.0, "still.jpg", ImageFormat::Jpeg),
+		write_still(&dir.0, "still.png", ImageFormat::Png),
+		write_gif(&dir.0),
+		corrupted,
+		misnamed,
+	];
+
+	// Without a priority request, the workers take the requests in any order
+	PRIORITY_REQUEST_ID.store(NON_EXISTENT_REQUEST_ID, Ordering::SeqCst);
+	let mut loader = ImageLoader::new(2, 16);
+	let submitted_at = SystemTime::now();
+	for (req_id, path) in paths.iter().enumerate() {
+		loader.send_load_request(LoadRequest {
+			req_id: req_id as u64,
+			path: path.clone(),
+			batch_id: None,
+		});
+	}
+	// The results of different requests may be interleaved because of the multiple threads
+	let mut results: HashMap<u64, Vec<LoadResult>> = HashMap::new();
+	for result in &mut loader {
+		results.entry(result.req_id()).or_default().push(result);
+	}
+
+	let kinds = |req_id: u64| results[&req_id].iter().map(kind).collect::<Vec<_>>();
+	use ResultKind::*;
+	assert_eq!(kinds(0), [Start, Frame, Done]);
+	assert_eq!(kinds(1), [Start, Frame, Done]);
+	let mut expected_gif = vec![Start];
+	expected_gif.extend([Frame; GIF_FRAME_COUNT]);
+	expected_gif.push(Done);
+	assert_eq!(kinds(2), expected_gif);
+	assert_eq!(kinds(3), [Start, Failed]);
+	assert_eq!(kinds(4), [Start, Frame, Done]);
+
+	for result in &results[&2] {
+		if let LoadResult::Frame { delay_nano, image, .. } = result {
+			assert_ne!(*delay_nano, 0);
+			assert_eq!(image.dimensions(), (16, 8));
+		}
+	}
+	// Every frame knows the format of its file
+	for (req_id, format) in [(0, ImageFormat::Jpeg), (1, ImageFormat::Png), (2, ImageFormat::Gif)] {
+		for result in &results[&req_id] {
+			if let LoadResult::Frame { source_format, .. } = result {
+				assert_eq!(*source_format, Some(format));
+			}
+		}
+	}
+	if let LoadResult::Start { dimensions, .. } = &results[&1][0] {
+		assert_eq!(*dimensions, Some((16, 8)));
+	}
+	// The failure carries the reason to show
+	if let LoadResult::Failed { error, .. } = &results[&3][1] {
+		assert!(!error.is_empty());
+	}
+	// The format is detected from the contents, not from the file ending
+	if let LoadResult::Start { detected_format, .. } = &results[&4][0] {
+		assert_eq!(detected_format.as_deref(), Some("JPG"));
+	}
+
+	// The results of a request are made one after the other, after the request was sent
+	let received_at = SystemTime::now();
+	for request_results in results.values() {
+		let times: Vec<SystemTime> = request_results.iter().map(|r| r.created_at()).collect();
+		assert!(times.windows(2).all(|pair| pair[0] <= pair[1]));
+		assert!(times[0] >= submitted_at && *times.last().unwrap() <= received_at);
+		assert!(request_results.iter().all(|r| !r.is_stale(Duration::from_secs(60))));
+	}
+}
+
+#[test]
+fn cancelled_requests_end_with_aborted() {
+	let dir = TestDir::new("loader-cancel");
+	let path = write_still(&dir.0, "still.png", ImageFormat::Png);
+
+	PRIORITY_REQUEST_ID.store(NON_EXISTENT_REQUEST_ID, Ordering::SeqCst);
+	let mut loader = ImageLoader::new(1, 16);
+	// Cancelling first makes sure that the worker sees it before it starts loading
+	loader.cancel_request(0);
+	loader.send_load_request(LoadRequest { req_id: 0, path: path.clone(), batch_id: None });
+	loader.send_load_request(LoadRequest { req_id: 1, path, batch_id: None });
+	let mut results: HashMap<u64, Vec<ResultKind>> = HashMap::new();
+	for result in &mut loader {
+		results.entry(result.req_id()).or_default().push(kind(&result));
+	}
+	use ResultKind::*;
+	assert_eq!(results[&0], [Aborted]);
+	assert_eq!(results[&1], [Start, Frame, Done]);
+	assert_eq!(loader.queue_depth(), 0);
+}
+
+#[test]
+fn request_ids_beyond_u32_are_kept_apart() {
+	let dir = TestDir::new("loader-wide-ids");
+	let still = write_still(&dir.0, "still.png", ImageFormat::Png);
+	let gif = write_gif(&dir.0);
+
+	PRIORITY_REQUEST_ID.store(NON_EXISTENT_REQUEST_ID, Ordering::SeqCst);
+	let mut loader = ImageLoader::new(2, 16);
+	// These would be the same id if it was truncated to 32 bits
+	let wide_id = (1 << 32) + 1;
+	loader.send_load_request(LoadRequest { req_id: 1, path: still, batch_id: None });
+	loader.send_load_request(LoadRequest { req_id: wide_id, path: gif, batch_id: None });
+	let mut results: HashMap<u64, Vec<ResultKind>> = HashMap::new();
+	for result in &mut loader {
+		results.entry(result.req_id()).or_default().push(kind(&result));
+	}
+	use ResultKind::*;
+	assert_eq!(results[&1], [Start, Frame, Done]);
+	assert_eq!(results[&wide_id].len(), GIF_FRAME_COUNT + 2);
+}
+
+#[test]
+fn batch_results_are_drained_apart_from_the_others() {
+	let dir = TestDir::new("loader-batch");
+	let still = write_still(&dir.0, "still.png", ImageFormat::Png);
+	let gif = write_gif(&dir.0);
+
+	PRIORITY_REQUEST_ID.store(NON_EXISTENT_REQUEST_ID, Ordering::SeqCst);
+	let mut loader = ImageLoader::new(2, 16);
+	loader.send_load_request(LoadRequest { req_id: 0, path: still.clone(), batch_id: None });
+	let batch_id = loader.send_batch_request(vec![still, gif]);
+	let mut batch = Vec::new();
+	let start = Instant::now();
+	while batch.iter().filter(|r| kind(r) == ResultKind::Done).count() < 2 {
+		assert!(start.elapsed() < Duration::from_secs(5));
+		batch.extend(loader.drain_batch_results(batch_id));
+		thread::sleep(Duration::from_millis(1));
+	}
+	assert_eq!(batch.len(), 3 + GIF_FRAME_COUNT + 2);
+	assert!(batch.iter().all(|r| r.batch_id() == Some(batch_id) && r.req_id() != 0));
+
+	// The single request is still received afterwards
+	let others: Vec<LoadResult> = (&mut loader).collect();
+	assert!(others.iter().all(|r| r.req_id() == 0 && r.batch_id().is_none()));
+	use ResultKind::*;
+	assert_eq!(others.iter().map(kind).collect::<Vec<_>>(), [Start, Frame, Done]);
+}
+
+#[test]
+fn queue_depth_leaves_out_the_request_being_loaded() {
+	let dir = TestDir::new("loader-queue");
+	let gif = write_gif(&dir.0);
+
+	PRIORITY_REQUEST_ID.store(NON_EXISTENT_REQUEST_ID, Ordering::SeqCst);
+	// The only worker blocks on sending the frames of the GIF until they are received
+	let mut loader = ImageLoader::new(1, 1);
+	for req_id in 0..3 {
+		loader.send_load_request(LoadRequest { req_id, path: gif.clone(), batch_id: None });
+	}
+	let start = Instant::now();
+	while loader.queue_depth() != 2 && start.elapsed() < Duration::from_secs(5) {
+		thread::sleep(Duration::from_millis(1));
+	}
+	assert_eq!(loader.queue_depth(), 2);
+	for _ in &mut loader {}
+	assert_eq!(loader.queue_depth(), 0);
+}
+
+// The palette indices of the disposal GIFs
+const RED: u8 = 0;
+const GREEN: u8 = 1;
+const BLUE: u8 = 2;
+const WHITE: u8 = 3;
+
+/// Writes a 4 by 4 GIF where each frame fills the rectangle `(left, top, width, height)`
+/// with a single color and uses the given disposal method
+fn write_disposal_gif(
+	dir: &Path,
+	name: &str,
+	frames: &[(u16, u16, u16, u16, u8, gif::DisposalMethod)],
+) -> PathBuf {
+	let palette = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+	let path = dir.join(name);
+	let file = fs::File::create(&path).unwrap();
+	let mut encoder = gif::Encoder::new(file, 4, 4, &palette).unwrap();
+	for &(left, top, width, height, color, dispose) in frames {
+		let buffer = vec![color; width as usize * height as usize];
+		let frame = gif::Frame {
+			left,
+			top,
+			width,
+			height,
+			dispose,
+			delay: 5,
+			buffer: buffer.into(),
+			..Default::default()
+		};
+		encoder.write_frame(&frame).unwrap();
+	}
+	path
+}
+
+fn load_gif_frames(path: &Path) -> Vec<RgbaImage> {
+	image_loader::load_gif(fs::File::open(path).unwrap(), 0)
+		.unwrap()
+		.filter_map(|result| match result.unwrap() {
+			LoadResult::Frame { image, .. } => Some(image),
+			_ => None,
+		})
+		.collect()
+}
+
+#[test]
+fn gif_frames_apply_the_disposal_of_the_previous_frame() {
+	use gif::DisposalMethod::*;
+	let dir = TestDir::new("gif-disposal");
+	// A red first frame, where the later frames only cover a part of the canvas
+	let path = write_disposal_gif(
+		&dir.0,
+		"disposal.gif",
+		&[
+			(0, 0, 4, 4, RED, Keep),
+			(0, 0, 2, 2, GREEN, Background),
+			(3, 3, 1, 1, BLUE, Previous),
+			(3, 0, 1, 1, WHITE, Keep),
+		],
+	);
+	let frames = load_gif_frames(&path);
+	assert_eq!(frames.len(), 4);
+
+	let red = Rgba([255, 0, 0, 255]);
+	let green = Rgba([0, 255, 0, 255]);
+	let blue = Rgba([0, 0, 255, 255]);
+	let white = Rgba([255, 255, 255, 255]);
+	let clear = Rgba([0, 0, 0, 0]);
+	// Every frame is the whole canvas, with the pixels around the frame area kept
+	assert!(frames[0].pixels().all(|p| *p == red));
+	assert_eq!(*frames[1].get_pixel(1, 1), green);
+	assert_eq!(*frames[1].get_pixel(2, 2), red);
+	// "Background" clears the area of the green frame, showing the background through it
+	assert_eq!(*frames[2].get_pixel(1, 1), clear);
+	assert_eq!(*frames[2].get_pixel(3, 3), blue);
+	assert_eq!(*frames[2].get_pixel(2, 2), red);
+	// "Previous" restores the canvas from before the blue frame
+	assert_eq!(*frames[3].get_pixel(3, 3), red);
+	assert_eq!(*frames[3].get_pixel(3, 0), white);
+	assert_eq!(*frames[3].get_pixel(1, 1), clear);
+}
+
+#[test]
+fn consecutive_restore_to_previous_frames_restore_the_last_kept_canvas() {
+	use gif::DisposalMethod::*;
+	let dir = TestDir::new("gif-restore-previous");
+	let path = write_disposal_gif(
+		&dir.0,
+		"previous.gif",
+		&[
+			(0, 0, 4, 4, RED, Keep),
+			(0, 0, 2, 2, GREEN, Previous),
+			(2, 2, 2, 2, BLUE, Previous),
+			(0, 0, 1, 1, WHITE, Keep),
+		],
+	);
+	let frames = load_gif_frames(&path);
+	assert_eq!(frames.len(), 4);
+
+	let red = Rgba([255, 0, 0, 255]);
+	// The green frame is gone when the blue one is drawn, and both are when the white one is
+	assert_eq!(*frames[1].get_pixel(0, 0), Rgba([0, 255, 0, 255]));
+	assert_eq!(*frames[2].get_pixel(0, 0), red);
+	assert_eq!(*frames[2].get_pixel(3, 3), Rgba([0, 0, 255, 255]));
+	assert_eq!(*frames[3].get_pixel(0, 0), Rgba([255, 255, 255, 255]));
+	assert_eq!(*frames[3].get_pixel(1, 1), red);
+	assert_eq!(*frames[3].get_pixel(3, 3), red);
+}
+
+#[test]
+fn frames_with_an_offset_are_placed_onto_the_previous_frame() {
+	let red = Rgba([255, 0, 0, 255]);
+	let green = Rgba([0, 255, 0, 255]);
+	let delay = Delay::from_numer_denom_ms(50, 1);
+	let mut compositor = FrameCompositor::new((4, 4));
+
+	let full = RgbaImage::from_pixel(4, 4, red);
+	assert_eq!(compositor.composite(Frame::from_parts(full.clone(), 0, 0, delay)), full);
+	let sub_frame = RgbaImage::from_pixel(2, 1, green);
+	let composited = compositor.composite(Frame::from_parts(sub_frame, 1, 2, delay));
+	assert_eq!(composited.dimensions(), (4, 4));
+	assert_eq!(*composited.get_pixel(1, 2), green);
+	assert_eq!(*composited.get_pixel(2, 2), green);
+	assert_eq!(*composited.get_pixel(0, 2), red);
+	assert_eq!(*composited.get_pixel(1, 3), red);
+	// A transparent pixel of a frame shows the previous frame through it
+	let transparent = RgbaImage::new(1, 1);
+	let composited = compositor.composite(Frame::from_parts(transparent, 1, 2, delay));
+	assert_eq!(*composited.get_pixel(1, 2), green);
+}
+
+#[test]
+fn released_requests_are_reused_empty_with_their_path_buffer() {
+	let pool = LoadRequestPool::new();
+	let mut request = pool.acquire();
+	request.req_id = 7;
+	request.path.push("some/folder/image.png");
+	request.batch_id = Some(3);
+	let capacity = request.path.capacity();
+	let buffer = request.path.as_os_str().as_encoded_bytes().as_ptr();
+	// The pool is a stack, so the released request is the next one acquired
+	drop(request);
+	let request = pool.acquire();
+	assert_eq!(request.req_id, 0);
+	assert_eq!(request.path, PathBuf::new());
+	assert_eq!(request.batch_id, None);
+	assert_eq!(request.path.capacity(), capacity);
+	assert_eq!(request.path.as_os_str().as_encoded_bytes().as_ptr(), buffer);
+}
+
+#[test]
+fn the_callback_gets_the_results_instead_of_the_channel() {
+	let dir = TestDir::new("loader-callback");
+	let path = write_still(&dir.0, "still.png", ImageFormat::Png);
+
+	PRIORITY_REQUEST_ID.store(NON_EXISTENT_REQUEST_ID, Ordering::SeqCst);
+	let mut loader = ImageLoader::new(2, 16);
+	let (tx, rx) = std::sync::mpsc::channel();
+	let worker_threads = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+	let threads = worker_threads.clone();
+	loader.set_callback(std::sync::Arc::new(move |result: LoadResult| {
+		threads.lock().unwrap().push(thread::current().id());
+		tx.send(kind(&result)).unwrap();
+	}));
+	loader.send_load_request(LoadRequest { req_id: 0, path, batch_id: None });
+
+	use ResultKind::*;
+	let kinds: Vec<ResultKind> =
+		(0..3).map(|_| rx.recv_timeout(Duration::from_secs(10)).unwrap()).collect();
+	assert_eq!(kinds, [Start, Frame, Done]);
+	assert!(worker_threads.lock().unwrap().iter().all(|id| *id != thread::current().id()));
+	assert!(loader.try_recv_prefetched().is_err());
+	assert!(loader.next().is_none());
+}
+
+#[test]
+fn combined_orientations_turn_and_flip_like_one_after_the_other() {
+	use Orientation::*;
+	let all =
+		[Deg0, Deg0HorFlip, Deg180, Deg180HorFlip, Deg90VerFlip, Deg270, Deg270VerFlip, Deg90];
+	for a in all {
+		assert_eq!(a.then(Deg0), a);
+		assert_eq!(Deg0.then(a), a);
+		for b in all {
+			for c in all {
+				assert_eq!(a.then(b).then(c), a.then(b.then(c)), "{:?} {:?} {:?}", a, b, c);
+			}
+		}
+	}
+	// Deg270 is a clockwise turn and Deg90 a counter-clockwise one
+	let image = RgbaImage::from_fn(3, 2, |x, y| Rgba([x as u8, y as u8, 0, 255]));
+	assert_eq!(Deg270.then(Deg270).apply(image.clone()), Deg180.apply(image.clone()));
+	assert_eq!(Deg90.then(Deg180).apply(image.clone()), Deg270.apply(image));
+	assert_eq!(Deg270.then(Deg270).then(Deg270).then(Deg270), Deg0);
+	assert_eq!(Deg270.then(Deg90), Deg0);
+	// Flipping twice leaves the image as it was, flipping both ways turns it around
+	assert_eq!(Deg0HorFlip.then(Deg0HorFlip), Deg0);
+	assert_eq!(Deg0HorFlip.then(Deg180HorFlip), Deg180);
+}
+
+#[test]
+fn edid_with_the_srgb_colors_leaves_the_colors_as_they_are() {
+	// The primaries and the white point of sRGB, in the order of the EDID
+	let coordinates = [0.640, 0.330, 0.300, 0.600, 0.150, 0.060, 0.3127, 0.3290];
+	let mut edid = vec![0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00];
+	edid.resize(128, 0);
+	// A gamma of 2.2
+	edid[23] = 120;
+	for (index, coordinate) in coordinates.iter().enumerate() {
+		let value = (coordinate * 1024.0f32).round() as u16;
+		edid[25 + index / 4] |= ((value & 0b11) as u8) << (6 - 2 * (index % 4));
+		edid[27 + index] = (value >> 2) as u8;
+	}
+	let display = color_management::edid_profile(&edid).unwrap();
+	let transform = moxcms::ColorProfile::new_srgb()
+		.create_transform_8bit(
+			moxcms::Layout::Rgba,
+			&display,
+			moxcms::Layout::Rgba,
+			moxcms::TransformOptions::default(),
+		)
+		.unwrap();
+	let colors = [[0, 0, 0, 255], [255, 255, 255, 255], [255, 0, 0, 255], [40, 200, 90, 128]];
+	let mut image = RgbaImage::from_fn(4, 1, |x, _| Rgba(colors[x as usize]));
+	color_management::apply(transform.as_ref(), &mut image);
+	for (pixel, expected) in image.pixels().zip(colors) {
+		for (value, expected) in pixel.0.iter().zip(expected) {
+			assert!(value.abs_diff(expected) <= 12, "{:?} {:?}", pixel, expected);
+		}
+	}
+
+	edid[23] = 0xFF;
+	assert!(color_management::edid_profile(&edid).is_none());
+	assert!(color_management::edid_profile(b"not an edid").is_none());
+}
+
+/// A little endian TIFF with an orientation in IFD0 and the JPEG thumbnail in IFD1, as it's
+/// stored in the APP1 segment of a JPEG
+fn exif_with_thumbnail(thumbnail: &[u8]) -> Vec<u8> {
+	let entry = |tiff: &mut Vec<u8>, tag: u16, kind: u16, value: u32| {
+		tiff.extend_from_slice(&tag.to_le_bytes());
+		tiff.extend_from_slice(&kind.to_le_bytes());
+		tiff.extend_from_slice(&1u32.to_le_bytes());
+		tiff.extend_from_slice(&value.to_le_bytes());
+	};
+	const SHORT: u16 = 3;
+	const LONG: u16 = 4;
+	let (ifd1_start, thumbnail_start) = (26u32, 56u32);
+	let mut tiff = b"II\x2a\x00\x08\x00\x00\x00".to_vec();
+	tiff.extend_from_slice(&1u16.to_le_bytes());
+	entry(&mut tiff, 0x0112, SHORT, 1);
+	tiff.extend_from_slice(&ifd1_start.to_le_bytes());
+	tiff.extend_from_slice(&2u16.to_le_bytes());
+	entry(&mut tiff, 0x0201, LONG, thumbnail_start);
+	entry(&mut tiff, 0x0202, LONG, thumbnail.len() as u32);
+	tiff.extend_from_slice(&0u32.to_le_bytes());
+	assert_eq!(tiff.len(), thumbnail_start as usize);
+	tiff.extend_from_slice(thumbnail);
+	tiff
+}
+
+fn embedded_thumbnail(path: &Path) -> Option<Vec<u8>> {
+	let (format, file) = image_loader::detect_format_of_file(path).unwrap();
+	image_loader::embedded_thumbnail(&format, &mut file.unwrap())
+}
+
+#[test]
+fn the_thumbnail_embedded_in_a_jpeg_is_found() {
+	let dir = TestDir::new("embedded-thumbnail");
+	let mut thumbnail = Vec::new();
+	let small = gelatin::image::DynamicImage::ImageRgba8(test_image(200)).to_rgb8();
+	small.write_to(&mut std::io::Cursor::new(&mut thumbnail), ImageFormat::Jpeg).unwrap();
+
+	let plain = write_still(&dir.0, "plain.jpg", ImageFormat::Jpeg);
+	assert_eq!(embedded_thumbnail(&plain), None);
+	let jpeg = fs::read(&plain).unwrap();
+	let exif = exif_with_thumbnail(&thumbnail);
+	let mut with_exif = jpeg[..2].to_vec();
+	with_exif.extend_from_slice(&[0xFF, 0xE1]);
+	with_exif.extend_from_slice(&(2 + 6 + exif.len() as u16).to_be_bytes());
+	with_exif.extend_from_slice(b"Exif\0\0");
+	with_exif.extend_from_slice(&exif);
+	with_exif.extend_from_slice(&jpeg[2..]);
+	let path = dir.0.join("with-thumbnail.jpg");
+	fs::write(&path, with_exif).unwrap();
+	assert_eq!(embedded_thumbnail(&path), Some(thumbnail));
+
+	let png = write_still(&dir.0, "still.png", ImageFormat::Png);
+	assert_eq!(embedded_thumbnail(&png), None);
+}
+
+#[test]
+fn raw_files_are_shown_by_their_largest_preview() {
+	let dir = TestDir::new("raw-preview");
+	let encode = |image: RgbaImage| {
+		let mut jpeg = Vec::new();
+		let rgb = gelatin::image::DynamicImage::ImageRgba8(image).to_rgb8();
+		rgb.write_to(&mut std::io::Cursor::new(&mut jpeg), ImageFormat::Jpeg).unwrap();
+		jpeg
+	};
+	let small = encode(RgbaImage::from_pixel(4, 4, Rgba([10, 20, 30, 255])));
+	let large = test_image(120);
+	// The large preview isn't referenced by the IFDs, like the ones in the maker notes
+	let mut raw = exif_with_thumbnail(&small);
+	raw.extend_from_slice(&encode(large.clone()));
+	let path = dir.0.join("photo.CR2");
+	fs::write(&path, &raw).unwrap();
+
+	assert!(matches!(image_loader::detect_format(&path), Ok(image_loader::ImgFormat::Raw)));
+	let preview = raw_thumbnail::decode_preview(raw).unwrap();
+	assert_eq!(preview.dimensions(), large.dimensions());
+	assert!(raw_thumbnail::decode_preview(b"II\x2a\x00\x08\x00\x00\x00\x00\x00".to_vec()).is_err());
+}
+
+/// A ZIP archive with the entries stored without compression. The CRCs are left at zero,
+/// because the loader doesn't check them.
+fn stored_zip(entries: &[(&str, &[u8])]) -> Vec<u8> {
+	let mut zip = Vec::new();
+	let mut central = Vec::new();
+	for (name, contents) in entries {
+		let offset = zip.len() as u32;
+		let size = (contents.len() as u32).to_le_bytes();
+		let name_len = (name.len() as u16).to_le_bytes();
+		zip.extend_from_slice(b"PK\x03\x04\x14\x00\x00\x00\x00\x00\x00\x00\x00\x00\0\0\0\0");
+		zip.extend_from_slice(&size);
+		zip.extend_from_slice(&size);
+		zip.extend_from_slice(&name_len);
+		zip.extend_from_slice(&[0, 0]);
+		zip.extend_from_slice(name.as_bytes());
+		zip.extend_from_slice(contents);
+
+		central.extend_from_slice(b"PK\x01\x02\x14\x00\x14\x00\x00\x00\x00\x00\x00\x00\x00\x00");
+		central.extend_from_slice(&[0; 4]);
+		central.extend_from_slice(&size);
+		central.extend_from_slice(&size);
+		central.extend_from_slice(&name_len);
+		central.extend_from_slice(&[0; 12]);
+		central.extend_from_slice(&offset.to_le_bytes());
+		central.extend_from_slice(name.as_bytes());
+	}
+	let central_offset = zip.len() as u32;
+	let count = (entries.len() as u16).to_le_bytes();
+	zip.extend_from_slice(&central);
+	zip.extend_from_slice(b"PK\x05\x06\0\0\0\0");
+	zip.extend_from_slice(&count);
+	zip.extend_from_slice(&count);
+	zip.extend_from_slice(&(central.len() as u32).to_le_bytes());
+	zip.extend_from_slice(&central_offset.to_le_bytes());
+	zip.extend_from_slice(&[0, 0]);
+	zip
+}
+
+#[test]
+fn openraster_files_are_shown_by_their_merged_image() {
+	let dir = TestDir::new("openraster");
+	let merged = fs::read(write_still(&dir.0, "merged.png", ImageFormat::Png)).unwrap();
+	let layer = fs::read(write_still(&dir.0, "layer.png", ImageFormat::Png)).unwrap();
+	let ora = stored_zip(&[
+		("mimetype", b"image/openraster"),
+		("stack.xml", b"<image w=\"16\" h=\"8\"></image>"),
+		("data/layer0.png", &layer),
+		("mergedimage.png", &merged),
+	]);
+	// The format is recognized from the contents as well
+	for name in ["drawing.ora", "drawing.zip"] {
+		let path = dir.0.join(name);
+		fs::write(&path, &ora).unwrap();
+		assert!(matches!(
+			image_loader::detect_format(&path),
+			Ok(image_loader::ImgFormat::OpenRaster)
+		));
+		let mut frames = Vec::new();
+		image_loader::complex_load_image(&path, false, 0, |result| {
+			if let LoadResult::Frame { image, .. } = result {
+				frames.push(image);
+			}
+			Ok(())
+		})
+		.unwrap();
+		assert_eq!(frames, vec![test_image(0)]);
+	}
+
+	let path = dir.0.join("flat.ora");
+	fs::write(&path, stored_zip(&[("mimetype", b"image/openraster")])).unwrap();
+	assert!(image_loader::complex_load_image(&path, false, 0, |_| Ok(())).is_err());
+}
@@ -0,0 +1,55 @@
+//! Writes lines into a small log file until it's rotated.
+
+use std::fs;
+
+mod common;
+
+use common::TestDir;
+
+#[allow(dead_code)]
+#[path = "../src/log_file.rs"]
+mod log_file;
+
+use log_file::{rotated_path, Logger};
+
+#[test]
+fn lines_have_a_timestamp_and_a_label() {
+	let dir = TestDir::new("log-file-lines");
+	let path = dir.0.join("emulsion.log");
+	let mut logger = Logger::open(&path, 1024 * 1024).unwrap();
+	logger.write_line("WARN", "first").unwrap();
+	logger.write_line("STDERR", "second").unwrap();
+
+	let contents = fs::read_to_string(&path).unwrap();
+	let lines: Vec<&str> = contents.lines().collect();
+	assert_eq!(lines.len(), 2);
+	// Like 2024-05-01T12:34:56.789Z
+	let (timestamp, rest) = lines[0].split_once(' ').unwrap();
+	assert_eq!(timestamp.len(), 24);
+	assert!(timestamp.ends_with('Z') && timestamp.as_bytes()[10] == b'T');
+	assert_eq!(rest, "WARN   first");
+	assert!(lines[1].ends_with("STDERR second"));
+}
+
+#[test]
+fn full_file_is_rotated() {
+	let dir = TestDir::new("log-file-rotation");
+	let path = dir.0.join("emulsion.log");
+	let mut logger = Logger::open(&path, 200).unwrap();
+	for i in 0..6 {
+		logger.write_line("INFO", &format!("line {}", i)).unwrap();
+	}
+
+	let rotated = fs::read_to_string(rotated_path(&path)).unwrap();
+	let current = fs::read_to_string(&path).unwrap();
+	assert!(fs::metadata(&path).unwrap().len() <= 200);
+	assert!(rotated.contains("line 0") && !rotated.contains("line 5"));
+	assert!(current.contains("line 5") && !current.contains("line 0"));
+	assert_eq!(rotated_path(&path), dir.0.join("emulsion.log.1"));
+
+	// Reopening continues the file instead of starting over
+	drop(logger);
+	let mut logger = Logger::open(&path, 200).unwrap();
+	logger.write_line("INFO", "reopened").unwrap();
+	assert!(fs::read_to_string(&path).unwrap().starts_with(&current));
+}
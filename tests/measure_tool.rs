@@ -0,0 +1,48 @@
+//! Places points with the ruler and checks the measured distances and angles.
+
+use std::path::Path;
+
+use gelatin::cgmath::Vector2;
+
+#[allow(dead_code)]
+#[path = "../src/widgets/measure_tool.rs"]
+mod measure_tool;
+
+use measure_tool::MeasureTool;
+
+#[test]
+fn every_second_click_ends_a_measurement() {
+	let mut tool = MeasureTool::default();
+	tool.activate(Path::new("a.png"));
+	assert!(tool.click(Vector2::new(10.0, 20.0)).is_none());
+	assert_eq!(tool.start(), Some(Vector2::new(10.0, 20.0)));
+
+	let measurement = tool.click(Vector2::new(13.0, 16.0)).unwrap();
+	assert_eq!(measurement.distance(), 5.0);
+	// Up and to the right, since the y axis of the image points down
+	assert!((measurement.angle() - 53.13).abs() < 0.01);
+	assert_eq!(measurement.describe(), "5.0 px, 53.1°");
+	assert!(tool.start().is_none());
+
+	tool.click(Vector2::new(0.0, 0.0));
+	let left = tool.click(Vector2::new(-4.0, 0.0)).unwrap();
+	assert_eq!(left.angle(), 180.0);
+	assert_eq!(tool.measurements().len(), 2);
+}
+
+#[test]
+fn opening_another_image_clears_the_points() {
+	let mut tool = MeasureTool::default();
+	tool.activate(Path::new("a.png"));
+	tool.click(Vector2::new(0.0, 0.0));
+	tool.click(Vector2::new(1.0, 1.0));
+	tool.follow_image(Path::new("a.png"));
+	assert_eq!(tool.measurements().len(), 1);
+	tool.follow_image(Path::new("b.png"));
+	assert!(tool.is_active());
+	assert!(tool.measurements().is_empty());
+
+	tool.deactivate();
+	tool.follow_image(Path::new("c.png"));
+	assert!(!tool.is_active());
+}
@@ -0,0 +1,46 @@
+//! Goes back and forth through the images that were navigated to.
+
+use std::path::{Path, PathBuf};
+
+#[allow(dead_code)]
+#[path = "../src/navigation_history.rs"]
+mod navigation_history;
+
+use navigation_history::{NavigationHistory, MAX_ENTRIES};
+
+#[test]
+fn going_back_and_forward_follows_the_viewed_images() {
+	let mut history = NavigationHistory::new();
+	assert_eq!(history.back(), None);
+	for name in ["a.png", "b.png", "c.png"] {
+		history.push(PathBuf::from(name));
+	}
+	assert_eq!(history.back(), Some(Path::new("b.png")));
+	// Showing the image that was gone back to doesn't change the history
+	history.push(PathBuf::from("b.png"));
+	assert_eq!(history.back(), Some(Path::new("a.png")));
+	assert_eq!(history.back(), None);
+	assert_eq!(history.forward(), Some(Path::new("b.png")));
+	assert_eq!(history.forward(), Some(Path::new("c.png")));
+	assert_eq!(history.forward(), None);
+
+	// Navigating to another image drops the ones ahead
+	history.back();
+	history.push(PathBuf::from("d.png"));
+	assert_eq!(history.forward(), None);
+	assert_eq!(history.back(), Some(Path::new("b.png")));
+}
+
+#[test]
+fn only_the_latest_images_are_kept() {
+	let mut history = NavigationHistory::new();
+	for i in 0..MAX_ENTRIES + 10 {
+		history.push(PathBuf::from(format!("{}.png", i)));
+	}
+	let mut steps = 0;
+	while history.back().is_some() {
+		steps += 1;
+	}
+	assert_eq!(steps, MAX_ENTRIES - 1);
+	assert_eq!(history.current(), Some(Path::new("10.png")));
+}
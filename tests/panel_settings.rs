@@ -0,0 +1,30 @@
+//! The settings panels only write the settings that were changed in them.
+
+#[allow(dead_code)]
+#[path = "../src/configuration.rs"]
+mod configuration;
+
+use configuration::{
+	ConfigImageSection, ConfigWindowSection, Configuration, FitMode, PanelSetting,
+};
+
+#[test]
+fn only_the_changed_settings_are_written() {
+	let path = std::env::temp_dir().join(format!("emulsion-panel-{}.toml", std::process::id()));
+	std::fs::write(&path, "# My settings\n[window]\nbackground_color = \"#112233\"\n").unwrap();
+	// The background color stands for an override from the command line
+	let config = Configuration {
+		window: Some(ConfigWindowSection {
+			background_color: Some("#ffffff".into()),
+			..Default::default()
+		}),
+		image: Some(ConfigImageSection { fit: Some(FitMode::Width), ..Default::default() }),
+		..Default::default()
+	};
+	config.save_panel_settings(&path, &[PanelSetting::Fit]).unwrap();
+	let written = std::fs::read_to_string(&path).unwrap();
+	let _ = std::fs::remove_file(&path);
+	assert!(written.contains("# My settings"), "{}", written);
+	assert!(written.contains("background_color = \"#112233\""), "{}", written);
+	assert!(written.contains("fit = \"width\""), "{}", written);
+}
@@ -0,0 +1,50 @@
+//! Compares the perceptual hashes of generated images that look alike and that don't.
+
+use gelatin::image::{imageops, Rgba, RgbaImage};
+
+#[allow(dead_code)]
+#[path = "../src/image_cache/perceptual_hash.rs"]
+mod perceptual_hash;
+
+use perceptual_hash::{PerceptualHash, DUPLICATE_DISTANCE};
+
+fn gradient(width: u32, height: u32) -> RgbaImage {
+	RgbaImage::from_fn(width, height, |x, y| {
+		let value = (x * 255 / width + y * 128 / height).min(255) as u8;
+		Rgba([value, value, value, 255])
+	})
+}
+
+fn checkerboard(size: u32) -> RgbaImage {
+	RgbaImage::from_fn(size, size, |x, y| {
+		let value = if (x / 16 + y / 16) % 2 == 0 { 240 } else { 15 };
+		Rgba([value, value / 2, value, 255])
+	})
+}
+
+#[test]
+fn distance_counts_the_different_bits() {
+	assert_eq!(PerceptualHash::distance(0, 0), 0);
+	assert_eq!(PerceptualHash::distance(0b1011, 0b0001), 2);
+	assert_eq!(PerceptualHash::distance(0, u64::MAX), 64);
+}
+
+#[test]
+fn resized_copies_are_near_duplicates() {
+	let original = gradient(300, 200);
+	let resized = imageops::resize(&original, 120, 80, imageops::FilterType::Triangle);
+	let mut brightened = original.clone();
+	for pixel in brightened.pixels_mut() {
+		pixel.0[0] = pixel.0[0].saturating_add(10);
+	}
+
+	let hash = PerceptualHash::from_image(&original).0;
+	let resized_distance = PerceptualHash::distance(hash, PerceptualHash::from_image(&resized).0);
+	let brightened_distance =
+		PerceptualHash::distance(hash, PerceptualHash::from_image(&brightened).0);
+	assert!(resized_distance <= DUPLICATE_DISTANCE, "distance was {}", resized_distance);
+	assert!(brightened_distance <= DUPLICATE_DISTANCE, "distance was {}", brightened_distance);
+
+	let other = PerceptualHash::from_image(&checkerboard(256)).0;
+	assert!(PerceptualHash::distance(hash, other) > DUPLICATE_DISTANCE);
+}
@@ -0,0 +1,66 @@
+//! Parses and formats M3U playlists.
+
+use std::path::{Path, PathBuf};
+
+#[allow(dead_code)]
+#[path = "../src/playlist.rs"]
+mod playlist;
+
+use playlist::{
+	format_playlist, is_playlist, parse_playlist, read_playlist, read_session, write_session,
+	Session,
+};
+
+#[test]
+fn comments_are_skipped_and_relative_paths_resolved() {
+	let text = "\u{feff}#EXTM3U\n# Holiday\nbeach.jpg\n\n  sub/dunes.png  \r\n/photos/cat.png\n";
+	let base = Path::new("/sets");
+	let paths = parse_playlist(text, base);
+	assert_eq!(
+		paths,
+		vec![
+			PathBuf::from("/sets/beach.jpg"),
+			PathBuf::from("/sets/sub/dunes.png"),
+			PathBuf::from("/photos/cat.png"),
+		]
+	);
+}
+
+#[test]
+fn formatted_playlist_reads_back() {
+	let base = Path::new("/sets");
+	let paths = vec![PathBuf::from("/sets/a.png"), PathBuf::from("/photos/b.png")];
+	let text = format_playlist(&paths, base);
+	assert_eq!(text, "#EXTM3U\na.png\n/photos/b.png\n");
+	assert_eq!(parse_playlist(&text, base), paths);
+}
+
+#[test]
+fn playlists_are_recognized_by_extension() {
+	assert!(is_playlist(Path::new("set.m3u")));
+	assert!(is_playlist(Path::new("set.M3U8")));
+	assert!(!is_playlist(Path::new("set.png")));
+	assert!(!is_playlist(Path::new("m3u")));
+	assert!(is_playlist(Path::new("review.json")));
+}
+
+#[test]
+fn saved_session_reads_back_with_the_paths_relative_to_it() {
+	let dir = std::env::temp_dir().join(format!("emulsion-session-{}", std::process::id()));
+	std::fs::create_dir_all(&dir).unwrap();
+	let path = dir.join("session.json");
+	let session = Session {
+		current_index: 1,
+		files: vec![dir.join("a.png"), PathBuf::from("/photos/b.png")],
+		zoom: 1.5,
+		pan: [0.1, 0.2],
+		sort: "name".into(),
+	};
+	write_session(&path, &session).unwrap();
+	let text = std::fs::read_to_string(&path).unwrap();
+	assert!(text.contains("\"a.png\"") && text.contains("\"current_index\": 1"), "{}", text);
+	assert_eq!(read_session(&path).unwrap(), session);
+	assert_eq!(read_playlist(&path).unwrap(), session.files);
+	assert_eq!(session.current_file(), Some(Path::new("/photos/b.png")));
+	let _ = std::fs::remove_dir_all(&dir);
+}
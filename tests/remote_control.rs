@@ -0,0 +1,165 @@
+//! Talks to the remote control server like a WebSocket client would.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[allow(dead_code)]
+#[path = "../src/remote_control.rs"]
+mod remote_control;
+
+use remote_control::{RemoteCommand, RemoteControl};
+
+fn start_server() -> (RemoteControl, u16) {
+	// The listener only finds a free port, the server binds to it after it's released
+	let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+	(RemoteControl::start(port).unwrap(), port)
+}
+
+/// Connects with the sample key from RFC 6455 and returns the stream and the accept header
+fn connect(port: u16) -> (TcpStream, String) {
+	let (stream, status, accept) = connect_with_headers(port, "");
+	assert_eq!(status, "HTTP/1.1 101 Switching Protocols");
+	(stream, accept)
+}
+
+/// Sends the upgrade request with the extra header lines, returns the stream, the status
+/// line of the response and its accept header
+fn connect_with_headers(port: u16, headers: &str) -> (TcpStream, String, String) {
+	let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+	stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+	write!(
+		stream,
+		"GET / HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+		Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n{}\r\n",
+		headers
+	)
+	.unwrap();
+	let mut reader = BufReader::new(stream.try_clone().unwrap());
+	let mut status = String::new();
+	reader.read_line(&mut status).unwrap();
+	let mut accept = String::new();
+	let mut line = String::new();
+	loop {
+		line.clear();
+		reader.read_line(&mut line).unwrap();
+		if line.trim_end().is_empty() {
+			break;
+		}
+		if let Some(value) = line.strip_prefix("Sec-WebSocket-Accept:") {
+			accept = value.trim().to_owned();
+		}
+	}
+	(stream, status.trim_end().to_owned(), accept)
+}
+
+/// Sends a masked text frame, the way clients have to send them
+fn send_text(stream: &mut TcpStream, text: &str) {
+	let mask = [0x12, 0x34, 0x56, 0x78];
+	let mut frame = vec![0x81, 0x80 | text.len() as u8];
+	frame.extend_from_slice(&mask);
+	frame.extend(text.bytes().enumerate().map(|(i, byte)| byte ^ mask[i % 4]));
+	stream.write_all(&frame).unwrap();
+}
+
+fn receive_text(stream: &mut TcpStream) -> String {
+	let mut head = [0; 2];
+	stream.read_exact(&mut head).unwrap();
+	assert_eq!(head[0], 0x81);
+	let mut payload = vec![0; (head[1] & 0x7F) as usize];
+	stream.read_exact(&mut payload).unwrap();
+	String::from_utf8(payload).unwrap()
+}
+
+fn wait_for_commands(remote: &RemoteControl, count: usize) -> Vec<RemoteCommand> {
+	let start = Instant::now();
+	let mut commands = Vec::new();
+	while commands.len() < count && start.elapsed() < Duration::from_secs(5) {
+		commands.extend(remote.try_recv_all());
+		thread::sleep(Duration::from_millis(10));
+	}
+	commands
+}
+
+#[test]
+fn handshake_uses_the_accept_key_of_the_rfc() {
+	let (_remote, port) = start_server();
+	let (_stream, accept) = connect(port);
+	assert_eq!(accept, "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+}
+
+#[test]
+fn commands_arrive_in_order() {
+	let (remote, port) = start_server();
+	let (mut stream, _) = connect(port);
+	send_text(&mut stream, r#"{ "action": "next" }"#);
+	send_text(&mut stream, r#"{ "action": "goto", "index": 5 }"#);
+	send_text(&mut stream, r#"{ "action": "zoom", "factor": 2.0 }"#);
+	send_text(&mut stream, r#"{ "action": "show_overlay", "text": "Hi", "duration_ms": 2000 }"#);
+	send_text(&mut stream, r#"{ "action": "quit" }"#);
+	let commands = wait_for_commands(&remote, 5);
+	assert_eq!(
+		commands,
+		[
+			RemoteCommand::Next,
+			RemoteCommand::Goto { index: 5 },
+			RemoteCommand::Zoom { factor: 2.0 },
+			RemoteCommand::ShowOverlay { text: "Hi".into(), duration_ms: 2000 },
+			RemoteCommand::Quit,
+		]
+	);
+}
+
+#[test]
+fn invalid_commands_are_answered_with_an_error() {
+	let (remote, port) = start_server();
+	let (mut stream, _) = connect(port);
+	send_text(&mut stream, r#"{ "action": "fly" }"#);
+	let reply = receive_text(&mut stream);
+	assert!(reply.starts_with(r#"{"error":"#), "{}", reply);
+	send_text(&mut stream, r#"{ "action": "prev" }"#);
+	assert_eq!(wait_for_commands(&remote, 1), [RemoteCommand::Prev]);
+}
+
+#[test]
+fn requests_from_web_pages_are_refused() {
+	let (_remote, port) = start_server();
+	let (_, status, _) = connect_with_headers(port, "Origin: https://example.com\r\n");
+	assert_eq!(status, "HTTP/1.1 403 Forbidden");
+}
+
+#[test]
+fn requests_from_sandboxed_pages_are_refused() {
+	let (_remote, port) = start_server();
+	let (_, status, _) = connect_with_headers(port, "Origin: null\r\n");
+	assert_eq!(status, "HTTP/1.1 403 Forbidden");
+}
+
+#[test]
+fn handshakes_without_an_end_are_refused() {
+	let (_remote, port) = start_server();
+	let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+	stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+	// Exactly as long as the server reads, so that it doesn't leave any of it unread
+	let mut request = b"GET / HTTP/1.1\r\nX-Padding: ".to_vec();
+	request.resize(16 * 1024, b'a');
+	stream.write_all(&request).unwrap();
+	let mut status = String::new();
+	BufReader::new(stream).read_line(&mut status).unwrap();
+	assert_eq!(status.trim_end(), "HTTP/1.1 431 Request Header Fields Too Large");
+}
+
+#[test]
+fn unmasked_frames_close_the_connection() {
+	let (remote, port) = start_server();
+	let (mut stream, _) = connect(port);
+	let text = r#"{ "action": "next" }"#;
+	let mut frame = vec![0x81, text.len() as u8];
+	frame.extend_from_slice(text.as_bytes());
+	stream.write_all(&frame).unwrap();
+	let mut close = [0; 4];
+	stream.read_exact(&mut close).unwrap();
+	assert_eq!(close, [0x88, 2, 0x03, 0xEA]);
+	assert!(remote.try_recv_all().is_empty());
+}
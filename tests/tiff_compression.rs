@@ -0,0 +1,145 @@
+//! Checks that TIFF files are decoded the same way with every common compression.
+
+use std::fs;
+use std::path::Path;
+
+use gelatin::image::{Rgba, RgbaImage};
+use tiff::encoder::{
+	colortype,
+	compression::{self, Compression},
+	TiffEncoder,
+};
+
+mod common;
+
+use common::TestDir;
+
+// The loader is compiled into the test directly, see `loader_integration.rs`
+#[allow(dead_code)]
+#[path = "../src/image_cache/archive.rs"]
+mod archive;
+#[allow(dead_code)]
+#[path = "../src/image_cache/color_management.rs"]
+mod color_management;
+#[allow(dead_code)]
+#[path = "../src/image_cache/dds.rs"]
+mod dds;
+#[allow(dead_code)]
+#[path = "../src/image_cache/farbfeld.rs"]
+mod farbfeld;
+#[allow(dead_code)]
+#[path = "../src/image_cache/icns.rs"]
+mod icns;
+#[allow(dead_code)]
+#[path = "../src/image_cache/image_loader.rs"]
+mod image_loader;
+#[cfg(feature = "jpeg2000")]
+#[allow(dead_code)]
+#[path = "../src/image_cache/jpeg2000.rs"]
+mod jpeg2000;
+#[allow(dead_code)]
+#[path = "../src/image_cache/perceptual_hash.rs"]
+mod perceptual_hash;
+#[allow(dead_code)]
+#[path = "../src/image_cache/postscript.rs"]
+mod postscript;
+#[allow(dead_code)]
+#[path = "../src/image_cache/raw_thumbnail.rs"]
+mod raw_thumbnail;
+#[allow(dead_code)]
+#[path = "../src/image_cache/scratch_file.rs"]
+mod scratch_file;
+#[allow(dead_code)]
+#[path = "../src/image_cache/xbm.rs"]
+mod xbm;
+#[allow(dead_code)]
+#[path = "../src/image_cache/xpm.rs"]
+mod xpm;
+
+use image_loader::{complex_load_image, LoadResult};
+
+/// Has long runs of the same color, which PackBits and LZW compress, and some noise
+fn test_image() -> RgbaImage {
+	RgbaImage::from_fn(37, 19, |x, y| {
+		if y < 8 {
+			Rgba([200, 30, 30, 255])
+		} else {
+			Rgba([(x * 7) as u8, (y * 13) as u8, ((x * y) % 251) as u8, (x * 5 + 60) as u8])
+		}
+	})
+}
+
+fn write_rgba<D: Compression>(path: &Path, image: &RgbaImage, compression: D) {
+	let mut encoder = TiffEncoder::new(fs::File::create(path).unwrap()).unwrap();
+	encoder
+		.write_image_with_compression::<colortype::RGBA8, _>(
+			image.width(),
+			image.height(),
+			compression,
+			image.as_raw(),
+		)
+		.unwrap();
+}
+
+fn write_rgb<D: Compression>(path: &Path, image: &RgbaImage, compression: D) {
+	let rgb: Vec<u8> = image.pixels().flat_map(|p| [p[0], p[1], p[2]]).collect();
+	let mut encoder = TiffEncoder::new(fs::File::create(path).unwrap()).unwrap();
+	encoder
+		.write_image_with_compression::<colortype::RGB8, _>(
+			image.width(),
+			image.height(),
+			compression,
+			&rgb,
+		)
+		.unwrap();
+}
+
+fn load(path: &Path) -> RgbaImage {
+	let mut frames = Vec::new();
+	complex_load_image(path, true, 0, |result| {
+		if let LoadResult::Frame { image, .. } = result {
+			frames.push(image);
+		}
+		Ok(())
+	})
+	.unwrap();
+	assert_eq!(frames.len(), 1, "{:?} should have a single frame", path);
+	frames.pop().unwrap()
+}
+
+fn check_compression<D: Compression + Clone>(name: &str, compression: D) {
+	let dir = TestDir::new(&format!("tiff-{}", name));
+	let expected = test_image();
+
+	let rgba_path = dir.0.join(format!("{}-rgba.tiff", name));
+	write_rgba(&rgba_path, &expected, compression.clone());
+	assert_eq!(load(&rgba_path), expected, "{} compressed RGBA", name);
+
+	let rgb_path = dir.0.join(format!("{}-rgb.tif", name));
+	write_rgb(&rgb_path, &expected, compression);
+	let opaque = RgbaImage::from_fn(expected.width(), expected.height(), |x, y| {
+		let p = expected.get_pixel(x, y);
+		Rgba([p[0], p[1], p[2], 255])
+	});
+	assert_eq!(load(&rgb_path), opaque, "{} compressed RGB", name);
+}
+
+#[test]
+fn uncompressed_tiff() {
+	check_compression("none", compression::Uncompressed);
+}
+
+#[test]
+fn lzw_tiff() {
+	check_compression("lzw", compression::Lzw);
+}
+
+#[test]
+fn deflate_tiff() {
+	check_compression("deflate", compression::Deflate::default());
+}
+
+#[test]
+fn packbits_tiff() {
+	check_compression("packbits", compression::Packbits);
+}
@@ -0,0 +1,30 @@
+//! The frame numbers and timecodes of `toggle_timecode`.
+
+#[allow(dead_code)]
+#[path = "../src/timecode.rs"]
+mod timecode;
+
+use std::path::Path;
+
+use timecode::{frame_number, timecode};
+
+#[test]
+fn the_frame_number_is_the_last_number_of_the_file_name() {
+	assert_eq!(frame_number(Path::new("frames/frame_0042.png")), Some(42));
+	assert_eq!(frame_number(Path::new("shot2_frame_0007_v3.exr")), Some(7));
+	assert_eq!(frame_number(Path::new("shot2_frame_0007_V12.exr")), Some(7));
+	assert_eq!(frame_number(Path::new("still_v2.png")), None);
+	assert_eq!(frame_number(Path::new("1234.jpg")), Some(1234));
+	assert_eq!(frame_number(Path::new("take2/still.png")), None);
+	assert_eq!(frame_number(Path::new("scan.2024.tif")), Some(2024));
+}
+
+#[test]
+fn timecodes_count_the_frames_within_each_second() {
+	assert_eq!(timecode(0, 24.0), "00:00:00:00");
+	assert_eq!(timecode(23, 24.0), "00:00:00:23");
+	assert_eq!(timecode(24, 24.0), "00:00:01:00");
+	assert_eq!(timecode(9999, 24.0), "00:06:56:15");
+	assert_eq!(timecode(90_000, 25.0), "01:00:00:00");
+	assert_eq!(timecode(48, 23.976), "00:00:02:00");
+}
@@ -0,0 +1,35 @@
+//! Reads the address of an uploaded image from the reply, and builds the form that the image is
+//! sent in.
+
+#![cfg(feature = "networking")]
+
+#[allow(dead_code)]
+#[path = "../src/upload.rs"]
+mod upload;
+
+use serde_json::json;
+
+#[test]
+fn the_address_is_found_by_its_path() {
+	let reply = json!({
+		"data": { "link": "https://i.example.com/abc.png", "sizes": [{ "url": "https://s/1" }] },
+		"success": true,
+	});
+	assert_eq!(
+		upload::find_url(&reply, "data.link").as_deref(),
+		Some("https://i.example.com/abc.png")
+	);
+	assert_eq!(upload::find_url(&reply, "data.sizes.0.url").as_deref(), Some("https://s/1"));
+	assert_eq!(upload::find_url(&reply, "data.missing"), None);
+	assert_eq!(upload::find_url(&reply, "success"), None);
+	assert_eq!(upload::find_url(&json!("https://plain"), "").as_deref(), Some("https://plain"));
+}
+
+#[test]
+fn the_form_has_the_file_as_its_only_field() {
+	let body = upload::multipart_body("xyz", "image", "a\"b.png", "image/png", b"PNG");
+	let expected =
+		"--xyz\r\nContent-Disposition: form-data; name=\"image\"; filename=\"a_b.png\"\r\n\
+		Content-Type: image/png\r\n\r\nPNG\r\n--xyz--\r\n";
+	assert_eq!(String::from_utf8(body).unwrap(), expected);
+}
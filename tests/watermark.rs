@@ -0,0 +1,42 @@
+//! The placement and the blending of the watermark of `export_watermarked`.
+
+#[allow(dead_code)]
+#[path = "../src/configuration.rs"]
+mod configuration;
+#[allow(dead_code)]
+#[path = "../src/watermark.rs"]
+mod watermark;
+
+use configuration::WatermarkCorner;
+use gelatin::image::{Rgba, RgbaImage};
+use watermark::{apply_watermark, watermark_position, WatermarkLayout, WatermarkMargin};
+
+fn layout(corner: WatermarkCorner, margin: WatermarkMargin) -> WatermarkLayout {
+	WatermarkLayout { corner, alpha: 1.0, margin }
+}
+
+#[test]
+fn the_watermark_keeps_the_margin_from_its_corner() {
+	let pixels = WatermarkMargin::Pixels(10);
+	let place = |corner| watermark_position((200, 100), (50, 20), &layout(corner, pixels));
+	assert_eq!(place(WatermarkCorner::TopLeft), (10, 10));
+	assert_eq!(place(WatermarkCorner::TopRight), (140, 10));
+	assert_eq!(place(WatermarkCorner::BottomLeft), (10, 70));
+	assert_eq!(place(WatermarkCorner::BottomRight), (140, 70));
+	assert_eq!(place(WatermarkCorner::Center), (75, 40));
+
+	let percent = layout(WatermarkCorner::BottomRight, WatermarkMargin::Percent(5.0));
+	assert_eq!(watermark_position((200, 100), (50, 20), &percent), (140, 75));
+}
+
+#[test]
+fn the_alpha_fades_the_watermark() {
+	let mut image = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 255]));
+	let watermark = RgbaImage::from_pixel(2, 2, Rgba([255, 255, 255, 255]));
+	let mut half = layout(WatermarkCorner::TopLeft, WatermarkMargin::Pixels(0));
+	half.alpha = 0.5;
+	apply_watermark(&mut image, &watermark, &half);
+	let covered = image.get_pixel(1, 1).0;
+	assert!((120..=135).contains(&covered[0]), "{:?}", covered);
+	assert_eq!(image.get_pixel(3, 3).0, [0, 0, 0, 255]);
+}
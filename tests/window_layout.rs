@@ -0,0 +1,37 @@
+//! The layouts of `save_layout` are kept in the cache file.
+
+#[allow(dead_code)]
+#[path = "../src/configuration.rs"]
+mod configuration;
+
+use configuration::{Cache, WindowLayout};
+
+#[test]
+fn saved_layouts_are_read_back_from_the_cache() {
+	let path = std::env::temp_dir().join(format!("emulsion-layouts-{}.toml", std::process::id()));
+	let mut cache = Cache::default();
+	let editing = WindowLayout {
+		directory_sidebar: true,
+		bookmarks_sidebar: false,
+		bottom_bar: true,
+		histogram: true,
+	};
+	cache.layouts.insert("editing".into(), editing);
+	cache.layouts.insert("presentation".into(), WindowLayout::default());
+	cache.save(&path).unwrap();
+	let loaded = Cache::load(&path);
+	let _ = std::fs::remove_file(&path);
+	assert_eq!(loaded.unwrap(), cache);
+}
+
+#[test]
+fn caches_without_layouts_still_load() {
+	let path =
+		std::env::temp_dir().join(format!("emulsion-no-layouts-{}.toml", std::process::id()));
+	Cache::default().save(&path).unwrap();
+	let contents = std::fs::read_to_string(&path).unwrap();
+	let loaded = Cache::load(&path);
+	let _ = std::fs::remove_file(&path);
+	assert!(!contents.contains("layouts"));
+	assert!(loaded.unwrap().layouts.is_empty());
+}
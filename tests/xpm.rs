@@ -0,0 +1,80 @@
+//! Decodes hand made XPM files, including ones whose header doesn't match their pixels.
+
+use gelatin::image::Rgba;
+
+// The decoder refers to the loader through `super`, see `loader_integration.rs`
+#[allow(dead_code)]
+#[path = "../src/image_cache/archive.rs"]
+mod archive;
+#[allow(dead_code)]
+#[path = "../src/image_cache/color_management.rs"]
+mod color_management;
+#[allow(dead_code)]
+#[path = "../src/image_cache/dds.rs"]
+mod dds;
+#[allow(dead_code)]
+#[path = "../src/image_cache/farbfeld.rs"]
+mod farbfeld;
+#[allow(dead_code)]
+#[path = "../src/image_cache/icns.rs"]
+mod icns;
+#[allow(dead_code)]
+#[path = "../src/image_cache/image_loader.rs"]
+mod image_loader;
+#[cfg(feature = "jpeg2000")]
+#[allow(dead_code)]
+#[path = "../src/image_cache/jpeg2000.rs"]
+mod jpeg2000;
+#[allow(dead_code)]
+#[path = "../src/image_cache/perceptual_hash.rs"]
+mod perceptual_hash;
+#[allow(dead_code)]
+#[path = "../src/image_cache/postscript.rs"]
+mod postscript;
+#[allow(dead_code)]
+#[path = "../src/image_cache/raw_thumbnail.rs"]
+mod raw_thumbnail;
+#[allow(dead_code)]
+#[path = "../src/image_cache/scratch_file.rs"]
+mod scratch_file;
+#[allow(dead_code)]
+#[path = "../src/image_cache/xbm.rs"]
+mod xbm;
+#[allow(dead_code)]
+#[path = "../src/image_cache/xpm.rs"]
+mod xpm;
+
+/// An XPM file with the given header values, two colors and the pixel lines
+fn xpm_file(header: &str, pixels: &[&str]) -> Vec<u8> {
+	let mut text = format!("/* XPM */\nstatic char *image[] = {{\n\"{}\",\n", header);
+	text.push_str("\". c #FF0000\",\n\"# c #0000FF\",\n");
+	for line in pixels {
+		text.push_str(&format!("\"{}\",\n", line));
+	}
+	text.push_str("};\n");
+	text.into_bytes()
+}
+
+#[test]
+fn pixels_are_decoded_by_their_characters() {
+	let image = xpm::decode_xpm(&xpm_file("3 2 2 1", &[".#.", "#.#"])).unwrap();
+	assert_eq!(image.dimensions(), (3, 2));
+	assert_eq!(*image.get_pixel(0, 0), Rgba([255, 0, 0, 255]));
+	assert_eq!(*image.get_pixel(1, 0), Rgba([0, 0, 255, 255]));
+	assert_eq!(*image.get_pixel(2, 1), Rgba([0, 0, 255, 255]));
+}
+
+#[test]
+fn widths_beyond_the_pixel_lines_are_rejected() {
+	assert!(xpm::decode_xpm(&xpm_file("4 2 2 1", &[".#.", "#.#"])).is_err());
+	assert!(xpm::decode_xpm(&xpm_file("4000000000 1 2 1", &[".#."])).is_err());
+}
+
+#[test]
+fn colors_with_other_characters_than_hex_digits_are_rejected() {
+	let file =
+		"/* XPM */\nstatic char *image[] = {\n\"1 1 1 1\",\n\". c #a\u{e9}aaa\",\n\".\",\n};\n";
+	assert!(xpm::decode_xpm(file.as_bytes()).is_err());
+	let file = file.replace("#a\u{e9}aaa", "#+1+1+1");
+	assert!(xpm::decode_xpm(file.as_bytes()).is_err());
+}